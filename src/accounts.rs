@@ -0,0 +1,98 @@
+// 複数のアカウント - 鍵ペアを ~/.nostr-cli-app/accounts/<名前>/keys.json に分けて保存し、使うアカウントを切り替える
+// 名前のない以前からの鍵（~/.nostr-cli-app/keys.json）は "default" アカウントとして扱う
+use std::fs;
+use std::path::PathBuf;
+use std::sync::RwLock;
+use crate::commands::app_dir;
+use crate::config::AppConfig;
+
+// 以前からの鍵ファイルを使うアカウントの名前
+pub const DEFAULT_ACCOUNT: &str = "default";
+
+// --account や TUI での切り替えで、config の active_account より優先して使うアカウント
+static ACCOUNT_OVERRIDE: RwLock<Option<String>> = RwLock::new(None);
+
+// このプロセスの中だけで使うアカウントを設定する（None なら config の設定に戻す）
+pub fn set_account_override(name: Option<String>) {
+    if let Ok(mut current) = ACCOUNT_OVERRIDE.write() {
+        *current = name;
+    }
+}
+
+// 使用中のアカウントの名前（--account、config の active_account、default の順）
+pub fn active_account() -> Result<String, Box<dyn std::error::Error>> {
+    if let Some(name) = ACCOUNT_OVERRIDE.read().ok().and_then(|current| current.clone()) {
+        return Ok(name);
+    }
+    Ok(AppConfig::load()?.active_account.unwrap_or_else(|| DEFAULT_ACCOUNT.to_string()))
+}
+
+// アカウント名を確かめる（ディレクトリ名に使うので英数字・-・_ だけ）
+pub fn validate_account_name(name: &str) -> Result<(), Box<dyn std::error::Error>> {
+    if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+        return Err(format!("アカウント名には英数字・-・_ だけを使ってください: {}", name).into());
+    }
+    Ok(())
+}
+
+// アカウントの鍵を置くディレクトリ
+pub fn account_dir(name: &str) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    validate_account_name(name)?;
+    if name == DEFAULT_ACCOUNT {
+        return app_dir();
+    }
+    Ok(app_dir()?.join("accounts").join(name))
+}
+
+// 使用中のアカウントの鍵ファイルのパス
+pub fn keys_path() -> Result<PathBuf, Box<dyn std::error::Error>> {
+    Ok(account_dir(&active_account()?)?.join("keys.json"))
+}
+
+// 鍵が保存されているアカウントの一覧（default が先頭、ほかは名前順）
+pub fn list_accounts() -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let mut names = Vec::new();
+    if let Ok(entries) = fs::read_dir(app_dir()?.join("accounts")) {
+        for entry in entries.flatten() {
+            let name = entry.file_name().to_string_lossy().to_string();
+            if name != DEFAULT_ACCOUNT && entry.path().join("keys.json").exists() {
+                names.push(name);
+            }
+        }
+    }
+    names.sort();
+    if app_dir()?.join("keys.json").exists() {
+        names.insert(0, DEFAULT_ACCOUNT.to_string());
+    }
+    Ok(names)
+}
+
+// 既定で使うアカウントを切り替える（鍵が保存されているアカウントだけ）
+pub fn switch_account(name: &str) -> Result<(), Box<dyn std::error::Error>> {
+    if !account_dir(name)?.join("keys.json").exists() {
+        return Err(format!("アカウント {} の鍵が見つかりません（generate-keys または import-key で作成してください）", name).into());
+    }
+    let mut config = AppConfig::load()?;
+    config.active_account = (name != DEFAULT_ACCOUNT).then(|| name.to_string());
+    config.save()?;
+    set_account_override(None);
+    Ok(())
+}
+
+// アカウントの鍵を削除する（使用中なら default に戻す）
+pub fn remove_account(name: &str) -> Result<(), Box<dyn std::error::Error>> {
+    if name == DEFAULT_ACCOUNT {
+        return Err("default アカウントは削除できません".into());
+    }
+    let dir = account_dir(name)?;
+    if !dir.exists() {
+        return Err(format!("アカウント {} は存在しません", name).into());
+    }
+    fs::remove_dir_all(&dir)?;
+    let mut config = AppConfig::load()?;
+    if config.active_account.as_deref() == Some(name) {
+        config.active_account = None;
+        config.save()?;
+    }
+    Ok(())
+}
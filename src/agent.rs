@@ -0,0 +1,157 @@
+// 鍵のエージェント - 復号した秘密鍵をメモリに置いておく常駐プロセス（ssh-agent のようなもの）
+// ~/.nostr-cli-app/agent/agent.sock（本人だけが入れるディレクトリの Unix ドメインソケット）でコマンドと TUI に鍵を渡し、
+// 期限が来るか agent stop で鍵を消して終了する
+use nostr_sdk::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::fs::{DirBuilderExt, MetadataExt, PermissionsExt};
+use std::os::unix::net::UnixStream;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt};
+use tokio::net::UnixListener;
+use crate::commands::app_dir;
+
+// 問い合わせの応答を待つ時間（動いていないエージェントを長く待たない）
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(2);
+
+// エージェントへの問い合わせ（1行の JSON）
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(tag = "op", rename_all = "snake_case")]
+enum AgentRequest {
+    Get { account: String }, // 鍵を受け取る（持っているアカウントの鍵だけ）
+    Status,                  // 持っているアカウントと期限
+    Stop,                    // 鍵を消して終了する
+}
+
+// エージェントの応答（1行の JSON）
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct AgentResponse {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    secret_key: Option<String>,
+    #[serde(default)]
+    pub account: String,
+    #[serde(default)]
+    pub expires_at: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+// エージェントのソケットを置くディレクトリ（ソケットを作った瞬間にほかのユーザーが接続できないよう、本人だけが入れる 0700 にする）
+fn socket_dir() -> Result<PathBuf, Box<dyn std::error::Error>> {
+    Ok(app_dir()?.join("agent"))
+}
+
+// エージェントのソケットのパス
+fn socket_path() -> Result<PathBuf, Box<dyn std::error::Error>> {
+    Ok(socket_dir()?.join("agent.sock"))
+}
+
+// ソケットのディレクトリを 0700 で用意し、その持ち主（= このプロセスのユーザー）の uid を返す
+fn prepare_socket_dir() -> Result<u32, Box<dyn std::error::Error>> {
+    let dir = socket_dir()?;
+    std::fs::DirBuilder::new().recursive(true).mode(0o700).create(&dir)?;
+    // 以前からあるディレクトリも 0700 にする（持ち主でなければ変えられずにエラーになる）
+    std::fs::set_permissions(&dir, std::fs::Permissions::from_mode(0o700))?;
+    let metadata = std::fs::metadata(&dir)?;
+    if metadata.mode() & 0o077 != 0 {
+        return Err(format!("{:?} をほかのユーザーが読み書きできるため、エージェントを起動しません", dir).into());
+    }
+    Ok(metadata.uid())
+}
+
+// エージェントに問い合わせる（動いていなければエラー）
+fn request(req: &AgentRequest) -> Result<AgentResponse, Box<dyn std::error::Error>> {
+    let mut stream = UnixStream::connect(socket_path()?)?;
+    stream.set_read_timeout(Some(REQUEST_TIMEOUT))?;
+    stream.set_write_timeout(Some(REQUEST_TIMEOUT))?;
+    let mut line = serde_json::to_string(req)?;
+    line.push('\n');
+    stream.write_all(line.as_bytes())?;
+
+    let mut reply = String::new();
+    BufReader::new(stream).read_line(&mut reply)?;
+    let response: AgentResponse = serde_json::from_str(&reply)?;
+    match response.error {
+        Some(error) => Err(error.into()),
+        None => Ok(response),
+    }
+}
+
+// エージェントが持っているアカウントの鍵（動いていない、または別のアカウントの鍵なら None）
+pub fn agent_keys(account: &str) -> Option<Keys> {
+    let response = request(&AgentRequest::Get { account: account.to_string() }).ok()?;
+    Some(Keys::new(SecretKey::from_str(&response.secret_key?).ok()?))
+}
+
+// 動いているエージェントの状態
+pub fn agent_status() -> Result<AgentResponse, Box<dyn std::error::Error>> {
+    request(&AgentRequest::Status)
+}
+
+// エージェントを止める
+pub fn stop_agent() -> Result<(), Box<dyn std::error::Error>> {
+    request(&AgentRequest::Stop).map(|_| ())
+}
+
+// 鍵を持って問い合わせに応える（期限が来るか stop を受けたら、ソケットを消して終了する）
+pub async fn serve(account: String, keys: Keys, lifetime: Duration) -> Result<(), Box<dyn std::error::Error>> {
+    let uid = prepare_socket_dir()?;
+    let path = socket_path()?;
+    // 前のエージェントが残したソケットは消す（動いているエージェントがあれば agent start が断っている）
+    if path.exists() {
+        std::fs::remove_file(&path)?;
+    }
+    let listener = UnixListener::bind(&path)?;
+    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600))?;
+
+    let secret_key = keys.secret_key()?.display_secret().to_string();
+    let expires_at = Timestamp::now().as_u64() + lifetime.as_secs();
+    let deadline = tokio::time::sleep(lifetime);
+    tokio::pin!(deadline);
+
+    loop {
+        tokio::select! {
+            _ = &mut deadline => break,
+            accepted = listener.accept() => {
+                let Ok((stream, _)) = accepted else {
+                    continue;
+                };
+                // 別のユーザーのプロセスからの接続には応えない
+                if !stream.peer_cred().is_ok_and(|cred| cred.uid() == uid) {
+                    continue;
+                }
+                let (reader, mut writer) = stream.into_split();
+                let mut line = String::new();
+                let mut reader = tokio::io::BufReader::new(reader);
+                if !matches!(tokio::time::timeout(REQUEST_TIMEOUT, reader.read_line(&mut line)).await, Ok(Ok(_))) {
+                    continue;
+                }
+
+                let status = AgentResponse { account: account.clone(), expires_at, ..Default::default() };
+                let (response, stop) = match serde_json::from_str::<AgentRequest>(&line) {
+                    Ok(AgentRequest::Get { account: requested }) if requested == account => {
+                        (AgentResponse { secret_key: Some(secret_key.clone()), ..status }, false)
+                    }
+                    Ok(AgentRequest::Get { .. }) => (
+                        AgentResponse { error: Some(format!("エージェントが持っているのはアカウント {} の鍵です", account)), ..status },
+                        false,
+                    ),
+                    Ok(AgentRequest::Status) => (status, false),
+                    Ok(AgentRequest::Stop) => (status, true),
+                    Err(e) => (AgentResponse { error: Some(format!("問い合わせを解釈できません: {}", e)), ..status }, false),
+                };
+                let mut reply = serde_json::to_string(&response)?;
+                reply.push('\n');
+                let _ = writer.write_all(reply.as_bytes()).await;
+                if stop {
+                    break;
+                }
+            }
+        }
+    }
+
+    let _ = std::fs::remove_file(&path);
+    Ok(())
+}
@@ -0,0 +1,319 @@
+// 投稿ごとの反応集計（返信・リポスト・リアクション・Zap）
+use nostr_sdk::prelude::*;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+
+pub const KIND_REPOST: u64 = 6;
+pub const KIND_GENERIC_REPOST: u64 = 16;
+pub const KIND_REACTION: u64 = 7;
+pub const KIND_ZAP_RECEIPT: u64 = 9735;
+
+// 時間帯ごとの集計
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct StatsBucket {
+    pub start: u64,
+    pub replies: u64,
+    pub reposts: u64,
+    pub reactions: u64,
+    pub zaps: u64,
+    pub zap_sats: u64,
+}
+
+impl StatsBucket {
+    // 反応の合計件数（スパークライン用）
+    pub fn total(&self) -> u64 {
+        self.replies + self.reposts + self.reactions + self.zaps
+    }
+}
+
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct NoteStats {
+    pub event_id: String,
+    pub replies: u64,
+    pub reposts: u64,
+    pub reactions: u64,
+    pub zaps: u64,
+    pub zap_sats: u64,
+    pub unique_pubkeys: usize,
+    pub bucket_secs: u64,
+    pub buckets: Vec<StatsBucket>,
+}
+
+// 対象の投稿への反応をまとめて取得するフィルター
+pub fn interaction_filter(event_id: EventId) -> Filter {
+    Filter::new()
+        .kinds(vec![
+            Kind::TextNote,
+            Kind::from(KIND_REPOST),
+            Kind::from(KIND_GENERIC_REPOST),
+            Kind::from(KIND_REACTION),
+            Kind::from(KIND_ZAP_RECEIPT),
+        ])
+        .event(event_id)
+}
+
+// 投稿からの経過時間に応じた集計単位（2日以内は1時間、それ以降は1日）
+pub fn auto_bucket_secs(posted_at: Timestamp, now: Timestamp) -> u64 {
+    if now.as_u64().saturating_sub(posted_at.as_u64()) <= 2 * 24 * 60 * 60 {
+        60 * 60
+    } else {
+        24 * 60 * 60
+    }
+}
+
+// 反応イベントを集計する
+pub fn aggregate(target: EventId, posted_at: Timestamp, events: &[Event], bucket_secs: u64) -> NoteStats {
+    let bucket_secs = bucket_secs.max(1);
+    let mut buckets: BTreeMap<u64, StatsBucket> = BTreeMap::new();
+    let mut pubkeys = HashSet::new();
+    let mut seen = HashSet::new();
+    let mut stats = NoteStats {
+        event_id: target.to_hex(),
+        bucket_secs,
+        ..Default::default()
+    };
+
+    for event in events {
+        if event.id == target || !seen.insert(event.id) {
+            continue;
+        }
+
+        let offset = event.created_at.as_u64().saturating_sub(posted_at.as_u64());
+        let start = posted_at.as_u64() + offset / bucket_secs * bucket_secs;
+        let bucket = buckets.entry(start).or_insert_with(|| StatsBucket { start, ..Default::default() });
+
+        match event.kind.as_u64() {
+            1 => {
+                stats.replies += 1;
+                bucket.replies += 1;
+                pubkeys.insert(event.pubkey);
+            }
+            KIND_REPOST | KIND_GENERIC_REPOST => {
+                stats.reposts += 1;
+                bucket.reposts += 1;
+                pubkeys.insert(event.pubkey);
+            }
+            KIND_REACTION => {
+                stats.reactions += 1;
+                bucket.reactions += 1;
+                pubkeys.insert(event.pubkey);
+            }
+            KIND_ZAP_RECEIPT => {
+                let sats = zap_amount_msat(event).unwrap_or(0) / 1000;
+                stats.zaps += 1;
+                stats.zap_sats += sats;
+                bucket.zaps += 1;
+                bucket.zap_sats += sats;
+                // Zapレシートの署名者はLNサーバーなので、Zapした本人を数える
+                if let Some(sender) = zap_sender(event) {
+                    pubkeys.insert(sender);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // 空の時間帯も埋めてスパークラインが途切れないようにする
+    if let (Some(first), Some(last)) = (buckets.keys().next().copied(), buckets.keys().last().copied()) {
+        let mut start = first;
+        while start < last {
+            buckets.entry(start).or_insert_with(|| StatsBucket { start, ..Default::default() });
+            start += bucket_secs;
+        }
+    }
+
+    stats.unique_pubkeys = pubkeys.len();
+    stats.buckets = buckets.into_values().collect();
+    stats
+}
+
+// フォロー中の1人が投稿にした反応
+#[derive(Debug, Clone)]
+pub struct FollowInteraction {
+    pub pubkey: XOnlyPublicKey,
+    pub reactions: Vec<String>, // リアクションの内容（"+" や絵文字）
+    pub reposted: bool,
+    pub replied: bool,
+}
+
+// 反応の対象（NIP-25 のリアクションは最後の e タグ、リポストは最初の e タグ、返信は直接の返信先）
+fn interaction_target(event: &Event) -> Option<EventId> {
+    let e_tags = crate::mutes::event_tag_ids(event);
+    match event.kind.as_u64() {
+        1 => reply_parent(event),
+        KIND_REACTION => e_tags.last().copied(),
+        KIND_REPOST | KIND_GENERIC_REPOST => e_tags.first().copied(),
+        _ => None,
+    }
+}
+
+// 投稿にリアクション・リポスト・返信をしたフォロー中の人（先に反応した順）
+// follows は自分のコンタクトリストの公開鍵 (hex)
+pub fn follow_interactions<'a>(
+    target: EventId,
+    events: impl IntoIterator<Item = &'a Event>,
+    follows: &BTreeSet<String>,
+) -> Vec<FollowInteraction> {
+    let mut seen = HashSet::new();
+    let mut related: Vec<&Event> = events
+        .into_iter()
+        .filter(|e| follows.contains(&e.pubkey.to_string()))
+        .filter(|e| interaction_target(e) == Some(target))
+        .filter(|e| seen.insert(e.id))
+        .collect();
+    related.sort_by_key(|e| e.created_at);
+
+    let mut interactions: Vec<FollowInteraction> = Vec::new();
+    for event in related {
+        let index = match interactions.iter().position(|i| i.pubkey == event.pubkey) {
+            Some(index) => index,
+            None => {
+                interactions.push(FollowInteraction {
+                    pubkey: event.pubkey,
+                    reactions: Vec::new(),
+                    reposted: false,
+                    replied: false,
+                });
+                interactions.len() - 1
+            }
+        };
+        let interaction = &mut interactions[index];
+        match event.kind.as_u64() {
+            1 => interaction.replied = true,
+            KIND_REACTION => {
+                // "+" と空は「いいね」として1つにまとめる
+                let content = if event.content.is_empty() { "+" } else { event.content.as_str() };
+                if !interaction.reactions.iter().any(|r| r == content) {
+                    interaction.reactions.push(content.to_string());
+                }
+            }
+            _ => interaction.reposted = true,
+        }
+    }
+    interactions
+}
+
+// フィードの並べ替えに使う投稿ごとの件数
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FeedCounts {
+    pub replies: u64,
+    pub zaps: u64,
+    pub zap_sats: u64,
+}
+
+// 表示中の投稿への返信とZapをまとめて取得するフィルター
+pub fn feed_counts_filter(ids: Vec<EventId>) -> Filter {
+    Filter::new()
+        .kinds(vec![Kind::TextNote, Kind::from(KIND_ZAP_RECEIPT)])
+        .events(ids)
+}
+
+// 返信の直接の返信先（NIP-10 の reply マーカー、なければ root、マーカーがなければ最後の e タグ）
+fn reply_parent(event: &Event) -> Option<EventId> {
+    let e_tags: Vec<Vec<String>> = event
+        .tags
+        .iter()
+        .map(|tag| tag.as_vec())
+        .filter(|values| values.first().map(|k| k.as_str()) == Some("e"))
+        .collect();
+    let marked = |marker: &str| e_tags.iter().find(|values| values.get(3).map(|m| m.as_str()) == Some(marker));
+    marked("reply")
+        .or_else(|| marked("root"))
+        .or_else(|| e_tags.last())
+        .and_then(|values| values.get(1))
+        .and_then(|id| EventId::from_hex(id).ok())
+}
+
+// 返信とZapレシートを投稿ごとに数える（同じイベントが複数のリレーから届いても1回と数える）
+pub fn count_feed_interactions(events: &[Event]) -> HashMap<EventId, FeedCounts> {
+    let mut counts: HashMap<EventId, FeedCounts> = HashMap::new();
+    let mut seen = HashSet::new();
+    for event in events {
+        if !seen.insert(event.id) {
+            continue;
+        }
+        match event.kind.as_u64() {
+            1 => {
+                if let Some(parent) = reply_parent(event) {
+                    counts.entry(parent).or_default().replies += 1;
+                }
+            }
+            KIND_ZAP_RECEIPT => {
+                if let Some(target) = tag_value(event, "e").and_then(|id| EventId::from_hex(id).ok()) {
+                    let entry = counts.entry(target).or_default();
+                    entry.zaps += 1;
+                    entry.zap_sats += zap_amount_msat(event).unwrap_or(0) / 1000;
+                }
+            }
+            _ => {}
+        }
+    }
+    counts
+}
+
+// 表示中の投稿の返信数とZap額を取得する
+pub async fn fetch_feed_counts(client: &Client, events: &[Event]) -> Result<HashMap<EventId, FeedCounts>, Box<dyn std::error::Error>> {
+    if events.is_empty() {
+        return Ok(HashMap::new());
+    }
+    let ids = events.iter().map(|e| e.id).collect();
+    let interactions = client
+        .get_events_of(vec![feed_counts_filter(ids)], Some(std::time::Duration::from_secs(10)))
+        .await?;
+    Ok(count_feed_interactions(&interactions))
+}
+
+// タグの値を取り出す
+fn tag_value(event: &Event, key: &str) -> Option<String> {
+    event
+        .tags
+        .iter()
+        .map(|tag| tag.as_vec())
+        .find(|values| values.first().map(|k| k.as_str()) == Some(key))
+        .and_then(|values| values.get(1).cloned())
+}
+
+// Zapレシートの金額（ミリサトシ）を求める
+// bolt11 の金額を優先し、なければ Zap リクエストの amount タグを使う
+pub fn zap_amount_msat(receipt: &Event) -> Option<u64> {
+    if let Some(amount) = tag_value(receipt, "bolt11").and_then(|invoice| bolt11_amount_msat(&invoice)) {
+        return Some(amount);
+    }
+    let request = Event::from_json(tag_value(receipt, "description")?).ok()?;
+    tag_value(&request, "amount")?.parse().ok()
+}
+
+// Zapした本人（Zapリクエストの署名者）
+pub fn zap_sender(receipt: &Event) -> Option<XOnlyPublicKey> {
+    let request = Event::from_json(tag_value(receipt, "description")?).ok()?;
+    Some(request.pubkey)
+}
+
+// BOLT11インボイスのHRPから金額（ミリサトシ）を読み取る
+pub fn bolt11_amount_msat(invoice: &str) -> Option<u64> {
+    let invoice = invoice.trim().to_lowercase();
+    let invoice = invoice.strip_prefix("lightning:").unwrap_or(&invoice);
+    let hrp = &invoice[..invoice.rfind('1')?];
+    let rest = hrp.strip_prefix("ln")?;
+
+    // 通貨部分（bc, tb, bcrt など）を飛ばして金額を取り出す
+    let amount_part = rest.trim_start_matches(|c: char| c.is_ascii_alphabetic());
+    if amount_part.is_empty() {
+        return None; // 金額指定なし
+    }
+    let (digits, multiplier) = match amount_part.chars().last()? {
+        c if c.is_ascii_digit() => (amount_part, None),
+        c => (&amount_part[..amount_part.len() - 1], Some(c)),
+    };
+    let value: u64 = digits.parse().ok()?;
+
+    // 1 BTC = 100,000,000,000 ミリサトシ
+    match multiplier {
+        None => value.checked_mul(100_000_000_000),
+        Some('m') => value.checked_mul(100_000_000),
+        Some('u') => value.checked_mul(100_000),
+        Some('n') => value.checked_mul(100),
+        Some('p') => Some(value / 10),
+        Some(_) => None,
+    }
+}
@@ -0,0 +1,142 @@
+// 読み込み中のアニメーション - 初代Mac風の腕時計カーソルとズームする矩形、起動画面の Happy Mac
+// コマ数ではなく経過時間からコマを決めるので、描画の頻度が変わっても速さは変わらない
+use ratatui::layout::Rect;
+use std::time::{Duration, Instant};
+
+// 腕時計カーソルの針（1周 WATCH_FRAME_MS × 12）
+const WATCH_FRAMES: [&str; 12] = ["🕛", "🕐", "🕑", "🕒", "🕓", "🕔", "🕕", "🕖", "🕗", "🕘", "🕙", "🕚"];
+const WATCH_FRAME_MS: u128 = 100;
+
+// 矩形が中心から広がりきるまでの時間と、後に続く矩形の数・間隔
+const ZOOM_PERIOD_MS: u128 = 900;
+const ZOOM_TRAILS: usize = 3;
+const ZOOM_TRAIL_GAP: f64 = 0.2;
+
+// 進行中の処理
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Activity {
+    Connecting, // リレーへの接続
+    Fetching,   // イベントの取得
+    Publishing, // イベントの送信
+}
+
+impl Activity {
+    pub fn label(self) -> &'static str {
+        match self {
+            Activity::Connecting => "接続中",
+            Activity::Fetching => "取得中",
+            Activity::Publishing => "送信中",
+        }
+    }
+}
+
+// 経過時間に応じた腕時計カーソルのコマ
+pub fn watch_frame(elapsed: Duration) -> &'static str {
+    WATCH_FRAMES[(elapsed.as_millis() / WATCH_FRAME_MS) as usize % WATCH_FRAMES.len()]
+}
+
+// 経過時間に応じて、領域の中心から広がる矩形（大きいものから順）
+pub fn zoom_rects(area: Rect, elapsed: Duration) -> Vec<Rect> {
+    let progress = (elapsed.as_millis() % ZOOM_PERIOD_MS) as f64 / ZOOM_PERIOD_MS as f64;
+    (0..ZOOM_TRAILS)
+        .map(|i| progress - i as f64 * ZOOM_TRAIL_GAP)
+        .filter(|scale| *scale > 0.0)
+        .map(|scale| {
+            // 枠を描くには幅と高さが2以上必要
+            let width = ((area.width as f64 * scale).round() as u16).clamp(2, area.width.max(2));
+            let height = ((area.height as f64 * scale).round() as u16).clamp(2, area.height.max(2));
+            Rect::new(
+                area.x + area.width.saturating_sub(width) / 2,
+                area.y + area.height.saturating_sub(height) / 2,
+                width.min(area.width),
+                height.min(area.height),
+            )
+        })
+        .collect()
+}
+
+// 起動画面の Happy Mac（初代 Mac が起動するときに表示する顔）
+pub const HAPPY_MAC: [&str; 10] = [
+    "┌──────────┐",
+    "│┌────────┐│",
+    "││  ╻  ╻  ││",
+    "││    ╯   ││",
+    "││  ╰──╯  ││",
+    "│└────────┘│",
+    "│          │",
+    "│  ──  ━━  │",
+    "└┬────────┬┘",
+    " └────────┘ ",
+];
+
+// 起動画面を最低限表示しておく時間（すぐに終わっても一瞬で消えないようにする）
+const BOOT_MIN_DURATION: Duration = Duration::from_millis(1200);
+
+// 起動の段階（実際に初期化する順）
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BootStep {
+    LoadCache,     // 前回のイベントの読み込み
+    UnlockKeys,    // 鍵の読み込み
+    ConnectRelays, // リレーへの接続
+    Done,
+}
+
+impl BootStep {
+    pub fn label(self) -> &'static str {
+        match self {
+            BootStep::LoadCache => "前回のイベントを読み込んでいます...",
+            BootStep::UnlockKeys => "鍵を読み込んでいます...",
+            BootStep::ConnectRelays => "リレーに接続しています...",
+            BootStep::Done => "準備ができました",
+        }
+    }
+
+    fn index(self) -> usize {
+        match self {
+            BootStep::LoadCache => 0,
+            BootStep::UnlockKeys => 1,
+            BootStep::ConnectRelays => 2,
+            BootStep::Done => 3,
+        }
+    }
+}
+
+// 起動画面の状態
+#[derive(Debug, Clone)]
+pub struct BootScreen {
+    pub step: BootStep,
+    started: Instant,
+    step_started: Instant,
+}
+
+impl Default for BootScreen {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BootScreen {
+    pub fn new() -> Self {
+        let now = Instant::now();
+        Self { step: BootStep::LoadCache, started: now, step_started: now }
+    }
+
+    pub fn advance(&mut self, step: BootStep) {
+        self.step = step;
+        self.step_started = Instant::now();
+    }
+
+    // 進捗バーの割合（段階の途中は経過時間に応じて少しずつ進め、止まって見えないようにする）
+    pub fn progress(&self) -> f64 {
+        if self.step == BootStep::Done {
+            return 1.0;
+        }
+        let within = 1.0 - (-self.step_started.elapsed().as_secs_f64() / 2.0).exp();
+        (self.step.index() as f64 + within * 0.9) / BootStep::Done.index() as f64
+    }
+
+    // 初期化が終わり、最低限の時間も表示したか
+    pub fn finished(&self) -> bool {
+        self.step == BootStep::Done && self.started.elapsed() >= BOOT_MIN_DURATION
+    }
+}
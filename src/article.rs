@@ -0,0 +1,129 @@
+// 長文記事（NIP-23）の読み込み - 見出しからの目次と、記事ごとの読んだ位置の記録（cache/read_positions.json）
+use nostr_sdk::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use crate::commands::app_dir;
+
+// 記録しておく読んだ位置の上限（古いものから消す）
+const READ_POSITION_LIMIT: usize = 500;
+
+// Markdown の見出し1つ
+#[derive(Debug, Clone, PartialEq)]
+pub struct Heading {
+    pub level: usize,
+    pub title: String,
+    // 本文を改行で分けたときの行番号
+    pub line: usize,
+}
+
+// Markdown の ATX 見出し（# から ###### まで）を目次にする（コードブロックの中は除く）
+// 行は (行番号, 内容) で渡す。表示の都合で分けた行の続きは渡さないこと
+pub fn table_of_contents<'a>(lines: impl IntoIterator<Item = (usize, &'a str)>) -> Vec<Heading> {
+    let mut headings = Vec::new();
+    let mut fence: Option<&str> = None;
+    for (line, text) in lines {
+        let trimmed = text.trim_start();
+        if let Some(marker) = ["```", "~~~"].into_iter().find(|m| trimmed.starts_with(m)) {
+            fence = match fence {
+                Some(open) if open == marker => None,
+                Some(open) => Some(open),
+                None => Some(marker),
+            };
+            continue;
+        }
+        if fence.is_some() {
+            continue;
+        }
+
+        let level = trimmed.chars().take_while(|c| *c == '#').count();
+        let rest = &trimmed[level..];
+        if !(1..=6).contains(&level) || !(rest.is_empty() || rest.starts_with(' ')) {
+            continue;
+        }
+        // 閉じの # は見出しに含めない
+        let title = rest.trim().trim_end_matches('#').trim_end();
+        if !title.is_empty() {
+            headings.push(Heading { level, title: title.to_string(), line });
+        }
+    }
+    headings
+}
+
+// 記事を識別するアドレス（kind:公開鍵:d タグ）。記事を編集してもIDと違って変わらない
+pub fn article_address(event: &Event) -> String {
+    let identifier = event
+        .tags
+        .iter()
+        .map(|tag| tag.as_vec())
+        .find(|values| values.first().map(|k| k.as_str()) == Some("d"))
+        .and_then(|values| values.get(1).cloned())
+        .unwrap_or_default();
+    format!("{}:{}:{}", event.kind.as_u64(), event.pubkey, identifier)
+}
+
+// 記事のタイトル（title タグ）
+pub fn article_title(event: &Event) -> Option<String> {
+    event
+        .tags
+        .iter()
+        .map(|tag| tag.as_vec())
+        .find(|values| values.first().map(|k| k.as_str()) == Some("title"))
+        .and_then(|values| values.get(1).cloned())
+        .filter(|title| !title.trim().is_empty())
+}
+
+// 読んだ位置の1件
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+pub struct ReadPosition {
+    pub line: u16,
+    pub read_at: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct ReadPositions {
+    // 記事のアドレス → 読んだ位置
+    #[serde(default)]
+    pub positions: BTreeMap<String, ReadPosition>,
+}
+
+impl ReadPositions {
+    // cache/read_positions.json を読み込む（未作成なら空）
+    pub fn load() -> Result<Self, Box<dyn std::error::Error>> {
+        let path = app_dir()?.join("cache").join("read_positions.json");
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let contents = fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&contents)?)
+    }
+
+    // cache/read_positions.json に保存する
+    pub fn save(&self) -> Result<(), Box<dyn std::error::Error>> {
+        let dir = app_dir()?.join("cache");
+        fs::create_dir_all(&dir)?;
+        fs::write(dir.join("read_positions.json"), serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    pub fn get(&self, address: &str) -> Option<u16> {
+        self.positions.get(address).map(|p| p.line)
+    }
+
+    // 読んだ位置を記録する（先頭なら記録を消す）
+    pub fn set(&mut self, address: &str, line: u16) {
+        if line == 0 {
+            self.positions.remove(address);
+            return;
+        }
+        let read_at = Timestamp::now().as_u64();
+        self.positions.insert(address.to_string(), ReadPosition { line, read_at });
+        while self.positions.len() > READ_POSITION_LIMIT {
+            let oldest = self.positions.iter().min_by_key(|(_, p)| p.read_at).map(|(k, _)| k.clone());
+            match oldest {
+                Some(key) => self.positions.remove(&key),
+                None => break,
+            };
+        }
+    }
+}
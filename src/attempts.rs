@@ -0,0 +1,103 @@
+// パスワードの総当たり対策 - 続けて間違えた回数と最後に間違えた時刻を、鍵ファイルの隣の password_attempts.json に記録する
+// 間違えるたびに次に試せるまでの待ち時間を倍にし（1秒・2秒・4秒…最大5分）、
+// config の password_lockout_attempts を超えたら password_lockout_minutes の間は復号を試さずに断る
+use nostr_sdk::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::time::Duration;
+use crate::accounts::keys_path;
+use crate::config::AppConfig;
+use crate::storage::{read_json, write_json};
+
+// 待ち時間の上限（秒）
+const MAX_DELAY_SECS: u64 = 300;
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct PasswordAttempts {
+    #[serde(default)]
+    pub failures: u32, // 続けて間違えた回数（正しいパスワードで 0 に戻す）
+    #[serde(default)]
+    pub last_failure: u64, // 最後に間違えた時刻（UNIX 秒）
+}
+
+impl PasswordAttempts {
+    // 使用中のアカウントの鍵ファイルの隣
+    fn path() -> Result<PathBuf, Box<dyn std::error::Error>> {
+        Ok(keys_path()?.with_file_name("password_attempts.json"))
+    }
+
+    // 記録を読み込む（未作成なら 0 回）
+    pub fn load() -> Result<Self, Box<dyn std::error::Error>> {
+        let path = Self::path()?;
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        read_json(&path)
+    }
+
+    fn save(&self) -> Result<(), Box<dyn std::error::Error>> {
+        write_json(&Self::path()?, self)
+    }
+
+    // 間違えた回数に応じた待ち時間（1回目から 1秒・2秒・4秒…）
+    fn delay_secs(&self) -> u64 {
+        match self.failures {
+            0 => 0,
+            n => 1u64.checked_shl(n - 1).unwrap_or(MAX_DELAY_SECS).min(MAX_DELAY_SECS),
+        }
+    }
+
+    // 次に試せるまでの残り時間（待たなくてよければ None）
+    pub fn wait_remaining(&self, now: Timestamp) -> Option<Duration> {
+        let ready_at = self.last_failure + self.delay_secs();
+        (now.as_u64() < ready_at).then(|| Duration::from_secs(ready_at - now.as_u64()))
+    }
+
+    // ロックが解ける時刻（ロックしていなければ None）
+    pub fn locked_until(&self, config: &AppConfig, now: Timestamp) -> Option<u64> {
+        if config.password_lockout_attempts == 0 || self.failures < config.password_lockout_attempts {
+            return None;
+        }
+        let until = self.last_failure + config.password_lockout_minutes * 60;
+        (now.as_u64() < until).then_some(until)
+    }
+
+    // 間違えたことを記録する
+    pub fn record_failure(&mut self, now: Timestamp) -> Result<(), Box<dyn std::error::Error>> {
+        self.failures += 1;
+        self.last_failure = now.as_u64();
+        self.save()
+    }
+
+    // 正しいパスワードで数え直す
+    pub fn reset(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        if self.failures == 0 {
+            return Ok(());
+        }
+        *self = Self::default();
+        self.save()
+    }
+
+    // CLI のプロンプトの前や TUI のステータスに表示する1行（間違えていなければ None）
+    pub fn status_line(&self, config: &AppConfig, now: Timestamp) -> Option<String> {
+        if self.failures == 0 {
+            return None;
+        }
+        if let Some(until) = self.locked_until(config, now) {
+            return Some(format!(
+                "パスワードを{}回続けて間違えたため、あと{}分は鍵を読み込めません",
+                self.failures,
+                (until - now.as_u64()).div_ceil(60)
+            ));
+        }
+        let mut line = format!("パスワードを{}回続けて間違えています", self.failures);
+        if let Some(wait) = self.wait_remaining(now) {
+            line.push_str(&format!("（次に試せるまで{}秒）", wait.as_secs()));
+        }
+        if config.password_lockout_attempts > 0 {
+            let left = config.password_lockout_attempts.saturating_sub(self.failures);
+            line.push_str(&format!("。あと{}回間違えると{}分ロックします", left, config.password_lockout_minutes));
+        }
+        Some(line)
+    }
+}
@@ -0,0 +1,105 @@
+// 署名の記録 - このアプリが自分の鍵で署名したイベント（kind・ID・作成時刻）と、送って受理されたリレーを
+// 鍵ファイルの隣の signing_log.ndjson に1行ずつ追記する（書き換えずに追記だけする。log show で一覧する）
+use nostr_sdk::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use crate::accounts::keys_path;
+use crate::storage::FileLock;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum SigningAction {
+    Signed, // 署名した
+    Sent,   // リレーに送った（relays は受理したリレー）
+}
+
+// 1行分の記録
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SigningEntry {
+    pub at: u64, // 記録した時刻（UNIX 秒）
+    pub action: SigningAction,
+    pub id: String, // イベントID（hex）
+    pub kind: u64,
+    pub created_at: u64,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub relays: Vec<String>,
+}
+
+// 署名したイベントのID（送信を記録するかどうかを決める。ログのパスごとに一度だけ読み込む）
+static SIGNED_IDS: Mutex<Option<(PathBuf, HashSet<String>)>> = Mutex::new(None);
+
+// 使用中のアカウントの鍵ファイルの隣
+pub fn log_path() -> Result<PathBuf, Box<dyn std::error::Error>> {
+    Ok(keys_path()?.with_file_name("signing_log.ndjson"))
+}
+
+// 記録を古い順に1行ずつ読む（未作成なら空。壊れた行は読み飛ばす）
+pub fn read_entries() -> Result<impl Iterator<Item = SigningEntry>, Box<dyn std::error::Error>> {
+    let path = log_path()?;
+    let lines = match File::open(&path) {
+        Ok(file) => Some(BufReader::new(file).lines()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+        Err(e) => return Err(format!("{} を開けません: {}", path.display(), e).into()),
+    };
+    Ok(lines
+        .into_iter()
+        .flatten()
+        .map_while(Result::ok)
+        .filter_map(|line| serde_json::from_str::<SigningEntry>(&line).ok()))
+}
+
+// 1行追記する（ほかのプロセスと行が混ざらないようロックしてから書く）
+fn append(entry: &SigningEntry) -> Result<(), Box<dyn std::error::Error>> {
+    let path = log_path()?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let _lock = FileLock::acquire(&path)?;
+    let mut file = OpenOptions::new().create(true).append(true).mode(0o600).open(&path)?;
+    let mut line = serde_json::to_string(entry)?;
+    line.push('\n');
+    file.write_all(line.as_bytes())?;
+    Ok(())
+}
+
+fn entry(action: SigningAction, event: &Event, relays: Vec<String>) -> SigningEntry {
+    SigningEntry {
+        at: Timestamp::now().as_u64(),
+        action,
+        id: event.id.to_hex(),
+        kind: event.kind.as_u64(),
+        created_at: event.created_at.as_u64(),
+        relays,
+    }
+}
+
+// 署名したイベントのID の一覧に対して処理する（初回とアカウントを切り替えたときはログから読み込む）
+fn with_signed_ids<T>(f: impl FnOnce(&mut HashSet<String>) -> T) -> Result<T, Box<dyn std::error::Error>> {
+    let path = log_path()?;
+    let mut cached = SIGNED_IDS.lock().map_err(|_| "署名の記録を読み込めません")?;
+    if cached.as_ref().map_or(true, |(cached_path, _)| *cached_path != path) {
+        let ids = read_entries()?.filter(|e| e.action == SigningAction::Signed).map(|e| e.id).collect();
+        *cached = Some((path, ids));
+    }
+    Ok(f(&mut cached.as_mut().expect("署名の記録を読み込んでいません").1))
+}
+
+// 署名したことを記録する
+pub fn record_signed(event: &Event) -> Result<(), Box<dyn std::error::Error>> {
+    append(&entry(SigningAction::Signed, event, Vec::new()))?;
+    with_signed_ids(|ids| ids.insert(event.id.to_hex()))?;
+    Ok(())
+}
+
+// 送信して受理したリレーを記録する（このアプリで署名していないイベント、例えば event import で送る他人のイベントは記録しない）
+pub fn record_sent(event: &Event, relays: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    if relays.is_empty() || !with_signed_ids(|ids| ids.contains(&event.id.to_hex()))? {
+        return Ok(());
+    }
+    append(&entry(SigningAction::Sent, event, relays.to_vec()))
+}
@@ -0,0 +1,83 @@
+// 性能の計測 - リレーからの取得・キャッシュの検索・TUIの描画にかかる時間を集計し、比べやすい形で表示する
+use serde::Serialize;
+use std::time::{Duration, Instant};
+use unicode_width::UnicodeWidthStr;
+
+// 表の項目名の列の幅（表示幅）
+const NAME_WIDTH: usize = 30;
+
+// 計測した項目1つ分の集計
+#[derive(Debug, Clone, Serialize)]
+pub struct BenchResult {
+    pub name: String,
+    pub runs: usize,
+    pub min_ms: f64,
+    pub median_ms: f64,
+    pub p95_ms: f64,
+    pub max_ms: f64,
+    // 1回あたりに扱った件数から求めた1秒あたりの件数（件数のない項目は None）
+    pub per_sec: Option<f64>,
+}
+
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+// 並べた計測値の p（0.0〜1.0）の位置の値
+fn percentile(sorted: &[Duration], p: f64) -> Duration {
+    if sorted.is_empty() {
+        return Duration::ZERO;
+    }
+    let index = ((sorted.len() - 1) as f64 * p).round() as usize;
+    sorted[index.min(sorted.len() - 1)]
+}
+
+// 計測値を集計する（items は1回あたりに扱った件数）
+pub fn summarize(name: &str, samples: &[Duration], items: Option<usize>) -> BenchResult {
+    let mut sorted = samples.to_vec();
+    sorted.sort();
+    let total: Duration = sorted.iter().sum();
+    let per_sec = items
+        .filter(|_| !total.is_zero())
+        .map(|items| items as f64 * sorted.len() as f64 / total.as_secs_f64());
+    BenchResult {
+        name: name.to_string(),
+        runs: sorted.len(),
+        min_ms: millis(percentile(&sorted, 0.0)),
+        median_ms: millis(percentile(&sorted, 0.5)),
+        p95_ms: millis(percentile(&sorted, 0.95)),
+        max_ms: millis(percentile(&sorted, 1.0)),
+        per_sec,
+    }
+}
+
+// 処理を runs 回繰り返し、1回ごとの時間を返す
+pub fn time_runs<T>(runs: usize, mut run: impl FnMut() -> T) -> Vec<Duration> {
+    (0..runs)
+        .map(|_| {
+            let started = Instant::now();
+            std::hint::black_box(run());
+            started.elapsed()
+        })
+        .collect()
+}
+
+// 表示幅が width になるよう右を空白で埋める（全角文字は2桁）
+fn pad(text: &str, width: usize) -> String {
+    format!("{}{}", text, " ".repeat(width.saturating_sub(text.width())))
+}
+
+// 集計を表にして表示する
+pub fn print_report(results: &[BenchResult]) {
+    println!(
+        "{} {:>6} {:>10} {:>10} {:>10} {:>10} {:>12}",
+        pad("項目", NAME_WIDTH), "runs", "min(ms)", "p50(ms)", "p95(ms)", "max(ms)", "items/s"
+    );
+    for result in results {
+        let per_sec = result.per_sec.map(|v| format!("{:.0}", v)).unwrap_or_else(|| "-".to_string());
+        println!(
+            "{} {:>6} {:>10.3} {:>10.3} {:>10.3} {:>10.3} {:>12}",
+            pad(&result.name, NAME_WIDTH), result.runs, result.min_ms, result.median_ms, result.p95_ms, result.max_ms, per_sec
+        );
+    }
+}
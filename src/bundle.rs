@@ -0,0 +1,101 @@
+// バックアップの束 - ~/.nostr-cli-app の鍵・リレー・設定・連絡先・キャッシュを1つのファイルにまとめ、パスフレーズで暗号化する（age 形式）
+// 別の端末に移すためのもので、OS のキーチェーンに保存した秘密鍵は含めない（keys.json の暗号化した秘密鍵は含む）
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{Read, Write};
+use std::path::{Component, Path};
+use crate::storage::write_atomic;
+
+const BUNDLE_VERSION: u32 = 1;
+// 書き込み途中・ロック・壊れたファイルの退避など、移しても意味のないファイル
+const SKIPPED_EXTENSIONS: [&str; 4] = ["tmp", "lock", "bak", "corrupt"];
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct Bundle {
+    pub version: u32,
+    pub created_at: u64,
+    pub files: BTreeMap<String, String>, // ~/.nostr-cli-app からの相対パス（/ 区切り）と中身
+}
+
+impl Bundle {
+    // ディレクトリの中のファイルを集める（テキストでないファイルは飛ばしてパスを返す）
+    pub fn collect(dir: &Path, created_at: u64) -> Result<(Self, Vec<String>), Box<dyn std::error::Error>> {
+        let mut bundle = Self { version: BUNDLE_VERSION, created_at, files: BTreeMap::new() };
+        let mut skipped = Vec::new();
+        let mut pending = vec![dir.to_path_buf()];
+        while let Some(current) = pending.pop() {
+            for entry in fs::read_dir(&current)? {
+                let path = entry?.path();
+                if path.is_dir() {
+                    pending.push(path);
+                    continue;
+                }
+                let extension = path.extension().and_then(|e| e.to_str()).unwrap_or("");
+                // ソケット（鍵のエージェント）などの普通でないファイルも飛ばす
+                if !path.is_file() || SKIPPED_EXTENSIONS.contains(&extension) {
+                    continue;
+                }
+                let relative = path
+                    .strip_prefix(dir)?
+                    .components()
+                    .map(|c| c.as_os_str().to_string_lossy().into_owned())
+                    .collect::<Vec<_>>()
+                    .join("/");
+                match fs::read_to_string(&path) {
+                    Ok(contents) => {
+                        bundle.files.insert(relative, contents);
+                    }
+                    Err(_) => skipped.push(relative),
+                }
+            }
+        }
+        Ok((bundle, skipped))
+    }
+
+    // すでにあるファイル（戻すと上書きされるもの）
+    pub fn existing_files(&self, dir: &Path) -> Vec<&str> {
+        self.files.keys().filter(|name| dir.join(name.as_str()).exists()).map(|name| name.as_str()).collect()
+    }
+
+    // ファイルを戻す（1つずつ一時ファイルに書いてから置き換える）
+    pub fn restore(&self, dir: &Path) -> Result<usize, Box<dyn std::error::Error>> {
+        for (name, contents) in &self.files {
+            // ../ や絶対パスで ~/.nostr-cli-app の外に書かせない
+            let relative = Path::new(name.as_str());
+            if !relative.components().all(|c| matches!(c, Component::Normal(_))) {
+                return Err(format!("バックアップに不正なパスが含まれています: {}", name).into());
+            }
+            write_atomic(&dir.join(relative), contents.as_bytes())?;
+        }
+        Ok(self.files.len())
+    }
+
+    // パスフレーズで暗号化する
+    pub fn encrypt(&self, passphrase: String) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+        let encryptor = age::Encryptor::with_user_passphrase(age::secrecy::Secret::new(passphrase));
+        let mut encrypted = Vec::new();
+        let mut writer = encryptor.wrap_output(&mut encrypted)?;
+        writer.write_all(serde_json::to_string(self)?.as_bytes())?;
+        writer.finish()?;
+        Ok(encrypted)
+    }
+
+    // パスフレーズで復号する
+    pub fn decrypt(encrypted: &[u8], passphrase: String) -> Result<Self, Box<dyn std::error::Error>> {
+        let decryptor = match age::Decryptor::new(encrypted)? {
+            age::Decryptor::Passphrase(decryptor) => decryptor,
+            _ => return Err("パスフレーズで暗号化したバックアップではありません".into()),
+        };
+        let mut reader = decryptor
+            .decrypt(&age::secrecy::Secret::new(passphrase), None)
+            .map_err(|e| format!("復号できません（パスフレーズが違う可能性があります）: {}", e))?;
+        let mut plain = String::new();
+        reader.read_to_string(&mut plain)?;
+        let bundle: Self = serde_json::from_str(&plain)?;
+        if bundle.version > BUNDLE_VERSION {
+            return Err(format!("新しい形式のバックアップです（形式 {}）。アプリを更新してください", bundle.version).into());
+        }
+        Ok(bundle)
+    }
+}
@@ -0,0 +1,192 @@
+// リレーの性能・制限の記録（cache/capabilities.json）
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::RandomState;
+use std::collections::BTreeMap;
+use std::hash::BuildHasher;
+use crate::commands::app_dir;
+use crate::storage::{read_json, write_json};
+
+// 移動平均で新しい計測値に与える重み（大きいほど直近の結果を重視する）
+const SAMPLE_WEIGHT: f64 = 0.3;
+// 接続に失敗したリレーを再び使うまでの待ち時間（失敗が続くごとに倍にする）
+const BACKOFF_BASE_SECS: u64 = 30;
+const BACKOFF_MAX_SECS: u64 = 60 * 60;
+
+// 現在のUNIX時刻（秒）
+fn now_secs() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+// 失敗回数に応じた待ち時間（指数的に延ばし、上限の半分〜上限の間でばらつかせる）
+// 多数のリレーが同時に落ちたときに、復旧直後の再接続が一斉に集中しないようにする
+fn backoff_secs(failures: u32) -> u64 {
+    let ceiling = BACKOFF_BASE_SECS
+        .saturating_mul(1 << failures.saturating_sub(1).min(16))
+        .min(BACKOFF_MAX_SECS);
+    let jitter = RandomState::new().hash_one(now_secs()) % (ceiling / 2 + 1);
+    ceiling / 2 + jitter
+}
+
+// リレー1つ分の記録
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+#[serde(default)]
+pub struct RelayCapability {
+    // 実測した履歴の保持期間（この日数より古いイベントまで返ってきた）
+    pub retention_days: Option<u64>,
+    // 最も古く取得できたイベントの作成日時
+    pub oldest_event_at: Option<u64>,
+    // 試験用イベントを保存していたか
+    pub stored_marker: Option<bool>,
+    // 保持期間を調べた日時
+    pub retention_checked_at: Option<u64>,
+    // 接続・応答にかかった時間の移動平均（ミリ秒）
+    pub latency_ms: Option<f64>,
+    // 接続に成功した割合の移動平均（0.0〜1.0）
+    pub success_rate: Option<f64>,
+    // 計測した回数
+    pub samples: u64,
+    // 続けて接続に失敗した回数
+    pub consecutive_failures: u32,
+    // この日時までは接続を試みない（失敗が続いたリレーへの再接続を控える）
+    pub retry_after: Option<u64>,
+    // 問い合わせたフィルターに合わないイベントを返した件数（手元で捨てたもの）
+    pub filter_violations: u64,
+    // NIP-11 で公開している書き込みの制限と、それを取得した日時
+    pub limits: Option<RelayLimits>,
+    pub limits_checked_at: Option<u64>,
+}
+
+// リレーが NIP-11 の limitation で公開している、書き込みに関わる制限
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+#[serde(default)]
+pub struct RelayLimits {
+    // 本文の最大文字数
+    pub max_content_length: Option<u64>,
+    // タグの最大数
+    pub max_event_tags: Option<u64>,
+    // 求める PoW（NIP-13）の難易度（ビット数）
+    pub min_pow_difficulty: Option<u8>,
+    // 書き込みに認証（NIP-42）が必要か
+    pub auth_required: bool,
+    // 書き込みに支払いが必要か（と支払いの案内）
+    pub payment_required: bool,
+    pub payments_url: Option<String>,
+    // 書き込めるのは条件を満たしたユーザーだけか
+    pub restricted_writes: bool,
+    // 購読のフィルターで否定の条件（"!#e" など）を使えるか
+    pub negative_filters: bool,
+}
+
+impl RelayCapability {
+    // 計測結果を移動平均に反映する（失敗時は応答時間を更新しない）
+    pub fn record_sample(&mut self, ok: bool, latency_ms: Option<f64>) {
+        let success = if ok { 1.0 } else { 0.0 };
+        self.success_rate = Some(match self.success_rate {
+            Some(rate) => rate + SAMPLE_WEIGHT * (success - rate),
+            None => success,
+        });
+        if let (true, Some(latency)) = (ok, latency_ms) {
+            self.latency_ms = Some(match self.latency_ms {
+                Some(avg) => avg + SAMPLE_WEIGHT * (latency - avg),
+                None => latency,
+            });
+        }
+        self.samples += 1;
+
+        if ok {
+            self.consecutive_failures = 0;
+            self.retry_after = None;
+        } else {
+            self.consecutive_failures += 1;
+            self.retry_after = Some(now_secs() + backoff_secs(self.consecutive_failures));
+        }
+    }
+
+    // 再接続を控えている間なら、あと何秒待つかを返す
+    pub fn backoff_remaining(&self, now: u64) -> Option<u64> {
+        self.retry_after.filter(|until| *until > now).map(|until| until - now)
+    }
+
+    // 読み込みに使うリレーを選ぶための評価値（成功率が高く、速いほど大きい）
+    pub fn score(&self) -> Option<f64> {
+        let rate = self.success_rate?;
+        let latency = self.latency_ms.unwrap_or(f64::INFINITY);
+        Some(rate * 1000.0 / (latency + 100.0))
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct CapabilityCache {
+    #[serde(default)]
+    pub relays: BTreeMap<String, RelayCapability>,
+}
+
+impl CapabilityCache {
+    // cache/capabilities.json を読み込む（未作成なら空）
+    pub fn load() -> Result<Self, Box<dyn std::error::Error>> {
+        let path = app_dir()?.join("cache").join("capabilities.json");
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        read_json(&path)
+    }
+
+    // cache/capabilities.json に保存する
+    pub fn save(&self) -> Result<(), Box<dyn std::error::Error>> {
+        write_json(&app_dir()?.join("cache").join("capabilities.json"), self)
+    }
+
+    // リレーを評価値の高い順に並べる
+    // まだ計測していないリレーは評価できないので先頭に置き、一度は使って計測する
+    pub fn rank(&self, relays: &[String]) -> Vec<(String, Option<f64>)> {
+        let mut ranked: Vec<(String, Option<f64>)> = relays
+            .iter()
+            .map(|url| (url.clone(), self.relays.get(url).and_then(|cap| cap.score())))
+            .collect();
+        ranked.sort_by(|a, b| match (a.1, b.1) {
+            (None, None) => std::cmp::Ordering::Equal,
+            (None, Some(_)) => std::cmp::Ordering::Less,
+            (Some(_), None) => std::cmp::Ordering::Greater,
+            (Some(x), Some(y)) => y.total_cmp(&x),
+        });
+        ranked
+    }
+
+    // 接続するリレーを選ぶ（再接続を控えているリレーを除き、順番を保ったまま上限の数までに絞る）
+    // 戻り値は (接続するリレー, 控えているリレーと残りの秒数)
+    // すべてが控えている最中なら何もできなくなるため、待ち時間の短いものから使う
+    pub fn select_for_connect(&self, relays: &[String], limit: usize) -> (Vec<String>, Vec<(String, u64)>) {
+        let now = now_secs();
+        let mut ready = Vec::new();
+        let mut waiting = Vec::new();
+        for url in relays {
+            match self.relays.get(url).and_then(|cap| cap.backoff_remaining(now)) {
+                Some(secs) => waiting.push((url.clone(), secs)),
+                None => ready.push(url.clone()),
+            }
+        }
+        if ready.is_empty() {
+            waiting.sort_by_key(|(_, secs)| *secs);
+            ready = waiting.drain(..).map(|(url, _)| url).collect();
+        }
+        ready.truncate(limit.max(1));
+        (ready, waiting)
+    }
+
+    // リレーが購読のフィルターで否定の条件を使えるか（NIP-11 の記録がなければ false）
+    pub fn supports_negative_filters(&self, url: &str) -> bool {
+        self.relays
+            .iter()
+            .find(|(known, _)| known.trim_end_matches('/') == url.trim_end_matches('/'))
+            .and_then(|(_, cap)| cap.limits.as_ref())
+            .is_some_and(|limits| limits.negative_filters)
+    }
+
+    // リレーの記録を取得する（なければ作成する）
+    pub fn entry(&mut self, url: &str) -> &mut RelayCapability {
+        self.relays.entry(url.to_string()).or_default()
+    }
+}
@@ -0,0 +1,64 @@
+// 時計のずれ - 未来の日時で作られたイベントの検出と、リレーのサーバーとの時計のずれの測定
+// 時計がずれたまま投稿すると、リレーに拒否されたり、他の人のタイムラインの奥に埋もれたりする
+use nostr_sdk::prelude::*;
+use std::time::{Duration, Instant};
+
+// これより先の日時のイベントを「未来のイベント」とみなす（端末ごとの多少のずれは許す）
+pub const FUTURE_TOLERANCE_SECS: u64 = 5 * 60;
+// 自分の時計がリレーとこれ以上ずれていたら、投稿する前に警告する
+pub const SKEW_WARN_SECS: i64 = 2 * 60;
+// ずれを測るリレーの数
+const SKEW_SAMPLE_RELAYS: usize = 3;
+
+// 作成日時が今より大きく先のイベントか
+pub fn is_future(event: &Event, now: Timestamp) -> bool {
+    event.created_at.as_u64() > now.as_u64() + FUTURE_TOLERANCE_SECS
+}
+
+// 並べ替えに使う日時（未来の日時は今に丸め、一覧の先頭に居座らないようにする）
+pub fn sort_time(event: &Event, now: Timestamp) -> Timestamp {
+    event.created_at.min(now)
+}
+
+// リレーの HTTP 応答（NIP-11）の Date ヘッダーから、自分の時計とのずれ（秒、リレーが進んでいれば正）を求める
+async fn relay_clock_offset(http: &reqwest::Client, relay_url: &str) -> Result<i64, Box<dyn std::error::Error>> {
+    let url = relay_url.replacen("wss://", "https://", 1).replacen("ws://", "http://", 1);
+    let sent_at = Timestamp::now().as_u64() as i64;
+    let started = Instant::now();
+    let response = http.get(&url).header("Accept", "application/nostr+json").send().await?;
+    let date = response
+        .headers()
+        .get(reqwest::header::DATE)
+        .and_then(|value| value.to_str().ok())
+        .ok_or("Date ヘッダーがありません")?;
+    let server = chrono::DateTime::parse_from_rfc2822(date)?.timestamp();
+    // 往復にかかった時間の半分だけ、リレーが応答した時刻は送った時刻より後になる
+    let local = sent_at + (started.elapsed().as_secs_f64() / 2.0).round() as i64;
+    Ok(server - local)
+}
+
+// 複数のリレーで測ったずれの中央値（どのリレーでも測れなければ None）
+pub async fn measure_clock_skew(relay_urls: &[String]) -> Option<i64> {
+    let http = reqwest::Client::builder().timeout(Duration::from_secs(5)).build().ok()?;
+    let mut offsets = Vec::new();
+    for url in relay_urls.iter().take(SKEW_SAMPLE_RELAYS) {
+        if let Ok(offset) = relay_clock_offset(&http, url).await {
+            offsets.push(offset);
+        }
+    }
+    offsets.sort();
+    offsets.get(offsets.len() / 2).copied()
+}
+
+// ずれが大きければ警告の文を返す
+pub fn skew_warning(skew: i64) -> Option<String> {
+    if skew.abs() < SKEW_WARN_SECS {
+        return None;
+    }
+    let direction = if skew > 0 { "遅れて" } else { "進んで" };
+    Some(format!(
+        "この端末の時計がリレーより約{}秒{}います。このまま投稿するとリレーに拒否されたり、タイムラインに埋もれたりすることがあります",
+        skew.abs(),
+        direction
+    ))
+}
@@ -0,0 +1,154 @@
+// 複数列のレイアウト（TweetDeck 風） - 幅の広い端末で、列ごとに割り当てたフィード（ホーム・メンション・ハッシュタグ・検索・人）を横に並べる
+// 列はそれぞれ別にスクロールし、ライブ購読で届いたイベントは条件に合う列すべてに加える
+use nostr_sdk::prelude::*;
+use ratatui::widgets::ListState;
+use std::str::FromStr;
+use crate::mutes::mentions_me;
+
+// 1列に持っておくイベントの上限
+const COLUMN_EVENT_LIMIT: usize = 200;
+// 列を開いたときや更新したときに取得する件数
+pub const COLUMN_FETCH_LIMIT: usize = 50;
+// 1列の最小の幅（狭い端末では、選択している列の周りの並べられる分だけ表示する）
+pub const MIN_COLUMN_WIDTH: u16 = 32;
+
+// 列に割り当てるフィード（config の columns には spec の文字列で保存する）
+#[derive(Debug, Clone, PartialEq)]
+pub enum ColumnSource {
+    Home,                   // home: イベントリストと同じフィード
+    Mentions,               // mentions: 自分宛てのメンション
+    Hashtag(String),        // #タグ
+    Search(String),         // search:語（NIP-50 に対応したリレーで検索する）
+    Author(XOnlyPublicKey), // from:npub（その人の投稿）
+}
+
+impl ColumnSource {
+    pub fn parse(spec: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let spec = spec.trim();
+        if spec == "home" {
+            return Ok(Self::Home);
+        }
+        if spec == "mentions" {
+            return Ok(Self::Mentions);
+        }
+        if let Some(tag) = spec.strip_prefix('#').filter(|tag| !tag.is_empty()) {
+            return Ok(Self::Hashtag(tag.to_lowercase()));
+        }
+        if let Some(query) = spec.strip_prefix("search:").map(str::trim).filter(|query| !query.is_empty()) {
+            return Ok(Self::Search(query.to_string()));
+        }
+        if let Some(author) = spec.strip_prefix("from:") {
+            let author = author.trim().trim_start_matches("nostr:");
+            let pubkey = XOnlyPublicKey::from_bech32(author)
+                .ok()
+                .or_else(|| XOnlyPublicKey::from_str(author).ok())
+                .ok_or_else(|| format!("公開鍵を解釈できません: {}", author))?;
+            return Ok(Self::Author(pubkey));
+        }
+        Err(format!("列の指定は home / mentions / #タグ / search:語 / from:npub のいずれかです: {}", spec).into())
+    }
+
+    // config に保存する文字列
+    pub fn spec(&self) -> String {
+        match self {
+            Self::Home => "home".to_string(),
+            Self::Mentions => "mentions".to_string(),
+            Self::Hashtag(tag) => format!("#{}", tag),
+            Self::Search(query) => format!("search:{}", query),
+            Self::Author(pubkey) => format!("from:{}", pubkey.to_bech32().unwrap_or_else(|_| pubkey.to_string())),
+        }
+    }
+
+    // 列の見出し
+    pub fn title(&self) -> String {
+        match self {
+            Self::Home => "🏠 ホーム".to_string(),
+            Self::Mentions => "@ メンション".to_string(),
+            Self::Hashtag(tag) => format!("# {}", tag),
+            Self::Search(query) => format!("🔍 {}", query),
+            Self::Author(pubkey) => {
+                let npub = pubkey.to_bech32().unwrap_or_else(|_| pubkey.to_string());
+                format!("👤 {}…", &npub[..npub.len().min(14)])
+            }
+        }
+    }
+
+    // 列のイベントを取得するフィルター（メンションは自分の公開鍵がなければ取得できない）
+    pub fn filter(&self, me: Option<XOnlyPublicKey>, kinds: Vec<Kind>) -> Option<Filter> {
+        let filter = Filter::new().kinds(kinds);
+        Some(match self {
+            Self::Home => filter,
+            Self::Mentions => Filter::new().kind(Kind::TextNote).pubkey(me?),
+            Self::Hashtag(tag) => filter.hashtag(tag.clone()),
+            Self::Search(query) => filter.search(query.clone()),
+            Self::Author(pubkey) => filter.author(*pubkey),
+        })
+    }
+
+    // ライブ購読で届いたイベントがこの列に入るか
+    pub fn matches(&self, event: &Event, me: Option<XOnlyPublicKey>, kinds: &[Kind]) -> bool {
+        match self {
+            Self::Mentions => me.is_some_and(|me| event.pubkey != me && mentions_me(event, me)),
+            _ if !kinds.contains(&event.kind) => false,
+            Self::Home => true,
+            Self::Hashtag(tag) => event.tags.iter().any(|t| {
+                let values = t.as_vec();
+                values.first().map(|k| k.as_str()) == Some("t") && values.get(1).is_some_and(|v| v.eq_ignore_ascii_case(tag))
+            }),
+            Self::Search(query) => {
+                let content = event.content.to_lowercase();
+                query.to_lowercase().split_whitespace().all(|word| content.contains(word))
+            }
+            Self::Author(pubkey) => event.pubkey == *pubkey,
+        }
+    }
+}
+
+// 1列分の状態
+#[derive(Debug)]
+pub struct Column {
+    pub source: ColumnSource,
+    pub events: Vec<Event>, // 新しい順
+    pub state: ListState,
+}
+
+impl Column {
+    pub fn new(source: ColumnSource) -> Self {
+        Self { source, events: Vec::new(), state: ListState::default() }
+    }
+
+    // 取得したイベントで置き換える（新しい順に並べ、上限までにする）
+    pub fn set_events(&mut self, mut events: Vec<Event>) {
+        events.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+        events.dedup_by(|a, b| a.id == b.id);
+        events.truncate(COLUMN_EVENT_LIMIT);
+        self.events = events;
+        self.state.select((!self.events.is_empty()).then_some(0));
+    }
+
+    // 届いたイベントを加える（読んでいる位置がずれないよう、選択も1つ下げる）
+    pub fn insert(&mut self, event: Event) -> bool {
+        if self.events.iter().any(|e| e.id == event.id) {
+            return false;
+        }
+        let position = self.events.iter().position(|e| e.created_at <= event.created_at).unwrap_or(self.events.len());
+        self.events.insert(position, event);
+        self.events.truncate(COLUMN_EVENT_LIMIT);
+        match self.state.selected() {
+            Some(selected) if selected >= position && selected > 0 => self.state.select(Some((selected + 1).min(self.events.len() - 1))),
+            None => self.state.select(Some(0)),
+            _ => {}
+        }
+        true
+    }
+
+    pub fn select_previous(&mut self) {
+        let selected = self.state.selected().unwrap_or(0).saturating_sub(1);
+        self.state.select((!self.events.is_empty()).then_some(selected));
+    }
+
+    pub fn select_next(&mut self) {
+        let selected = (self.state.selected().unwrap_or(0) + 1).min(self.events.len().saturating_sub(1));
+        self.state.select((!self.events.is_empty()).then_some(selected));
+    }
+}
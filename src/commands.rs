@@ -1,13 +1,87 @@
 use clap::ArgMatches;
 use serde::{Deserialize, Serialize};
-use std::fs::{self, File};
+use std::fs;
 use std::io::{Cursor, Read, Write};
+use std::path::PathBuf;
+use std::os::unix::fs::OpenOptionsExt;
+use std::os::unix::process::CommandExt;
+use std::str::FromStr;
 use nostr_sdk::prelude::*;
 use ::hex;
-use rpassword;
 use dirs;
 use rodio::{Decoder, OutputStream, Sink};
 use reqwest;
+use chrono::{FixedOffset, NaiveDate, NaiveDateTime};
+use crate::bundle::Bundle;
+use crate::agent::{agent_keys, agent_status, serve as run_agent, stop_agent};
+use crate::accounts::{account_dir, active_account, keys_path, list_accounts, remove_account, switch_account, DEFAULT_ACCOUNT};
+use crate::analytics::{bolt11_amount_msat, fetch_feed_counts, KIND_GENERIC_REPOST, KIND_REPOST};
+use crate::attempts::PasswordAttempts;
+use crate::audit::{log_path as audit_log_path, read_entries, SigningAction, SigningEntry};
+use crate::capabilities::CapabilityCache;
+use crate::contacts::{contact_list_builder, display_pubkey, fetch_contact_list, parse_contacts, ContactEntry};
+use crate::compose::{parse_hashtag_list, reply_tags, thread_pubkeys, NoteDraft};
+use crate::clock::{is_future, measure_clock_skew, skew_warning};
+use crate::config::AppConfig;
+use crate::drafts::{sync_drafts, Draft, DraftStore};
+use crate::dm::{
+    build_message_events, dm_relays_builder, fetch_dm_relays, fetch_messages, group_conversations, purge_cached_conversation,
+    send_message_events, DmScheme,
+};
+use crate::explore::{histogram, kind_name, sample_events};
+use crate::feed_kinds::{compact_content, feed_kinds, EPHEMERAL_KINDS};
+use crate::feed_sort::{plugin_scores, sort_events, FeedSort};
+use crate::filter_check::{matches_any, query_checked, record_violations};
+use crate::keychain::{delete_account_secret_key, delete_secret_key, load_secret_key, store_secret_key};
+use crate::keystore::{
+    decrypt_encrypted_key, decrypt_secret_key, encrypt_secret_key, encrypt_secret_key_argon2id, key_security, Argon2Params, EncryptedKey,
+    KdfHeader, KeySecurity, DEFAULT_LOG_N, WRONG_PASSWORD,
+};
+use crate::import::{merge_follows, parse_follows, parse_relays, ImportFormat};
+use crate::geo::{geohash_tags, is_near, parse_location, parse_radius_km, precision_for_radius_km};
+use crate::lang::{fetch_preferred_variants, language_tags, normalize_lang, translation_tag, variant_ids};
+use crate::media::{
+    blossom_hash, fetch_verified_media, guess_mime, imeta_tag, is_valid_dim, parse_imeta, resolve_media_url,
+    route_media_url, with_alt_placeholders, MediaInfo, MediaRoute,
+};
+use crate::moderation::{compile_patterns, match_spam, report_builder, retract_builder, ModerationQueue, ModerationStatus};
+use crate::mutes::{mentions_me, thread_root, MuteList};
+use crate::ndjson::{NdjsonReader, NdjsonWriter};
+use crate::nip65::{fetch_relay_list, parse_relay_list, relay_list_builder};
+use crate::nip05::{address, fetch_well_known, parse_address, validate_domain, validate_name, well_known_json, well_known_url};
+use crate::bench::{print_report, summarize, time_runs, BenchResult};
+use crate::dev::seed_events;
+use crate::event_cache::{load_cached_events, load_cached_mentions, load_sent_events, save_cached_mentions, save_seeded_events, vacuum_caches};
+use crate::permalink::{copy_to_clipboard, find_event_relays, permalink as build_permalink, PermalinkFormat, MAX_RELAY_HINTS};
+use crate::profiles::{fetch_metadata_event, merge_metadata, ProfileCache, PROFILE_FIELDS};
+use crate::outbox::{archive_sent, publish, send_with_receipts, Outbox};
+use crate::preflight::{fetch_relay_limits, preflight_note, sign_checked, spawn_auto_auth, Preflight};
+use crate::retention::RetentionPolicy;
+use crate::transport::{configured_url, dial, validate as validate_relay_url};
+use crate::template::{builtin_vars, parse_assignment, render};
+use crate::session::SessionStats;
+use crate::shamir::{combine, parse_scheme, split, Share};
+use crate::signer::{external_signer, sign_event};
+use crate::storage::{read_json, shred_file, write_json, write_json_no_backup, FileLock, LOCK_HELD_NOTICE};
+use crate::streak::{notify_reminder, parse_reminder_time, reminder_due, PostingStreak};
+use crate::schema::{validate, validate_event, SchemaError};
+use crate::safety::{peer_handles, safety_number, KeyChange, KnownPeers};
+use crate::scrapbook::{Scrapbook, ScrapbookEntry};
+use crate::verify::{retain_verified, verify_event, VerifyError};
+use crate::zap::{encode_lnurl, fetch_invoice, fetch_pay_endpoint, lnurl_pay_url, zap_request_builder};
+
+// リレー未登録時に使用するデフォルトリレー
+pub const DEFAULT_RELAY: &str = "wss://yabu.me";
+
+// 日時ジャンプ時に指定時刻の前後へ広げる検索範囲（秒）
+pub const AROUND_WINDOW_SECS: u64 = 6 * 60 * 60;
+
+// アプリのデータディレクトリ（~/.nostr-cli-app）を返す関数
+pub fn app_dir() -> Result<PathBuf, Box<dyn std::error::Error>> {
+    Ok(dirs::home_dir()
+        .ok_or("ホームディレクトリが見つかりません")?
+        .join(".nostr-cli-app"))
+}
 
 // リレー設定の構造体
 #[derive(Serialize, Deserialize, Debug, Default)]
@@ -15,332 +89,4350 @@ pub struct RelayConfig {
     pub relays: Vec<String>,
 }
 
-// 新しい鍵ペアを生成する関数
+// 鍵を暗号化するパスワード（--password がなければ2回入力させて確かめる）
+fn new_key_password(matches: &ArgMatches) -> Result<String, Box<dyn std::error::Error>> {
+    if let Some(pass) = matches.get_one::<String>("password") {
+        return Ok(pass.clone());
+    }
+    let password = prompt_password("鍵を暗号化するためのパスワードを入力してください: ")?;
+    let confirm_password = prompt_password("確認のためもう一度パスワードを入力してください: ")?;
+
+    if password != confirm_password {
+        return Err("パスワードが一致しません".into());
+    }
+    Ok(password)
+}
+
+// 新しい鍵ペアを生成する関数（--mnemonic なら BIP-39 のニーモニックから NIP-06 で導出する）
 pub fn generate_keys(matches: &ArgMatches) -> Result<(), Box<dyn std::error::Error>> {
     // パスワードの入力を求める
-    let password = if let Some(pass) = matches.get_one::<String>("password") {
-        pass.clone()
-    } else {
-        println!("鍵を暗号化するためのパスワードを入力してください:");
-        let password = rpassword::read_password()?;
-        println!("確認のためもう一度パスワードを入力してください:");
-        let confirm_password = rpassword::read_password()?;
+    let password = new_key_password(matches)?;
 
-        if password != confirm_password {
-            return Err("パスワードが一致しません".into());
+    // キーを生成
+    let (keys, mnemonic) = if matches.get_flag("mnemonic") {
+        let words = matches.get_one::<usize>("words").copied().unwrap_or(12);
+        if words != 12 && words != 24 {
+            return Err("--words には 12 または 24 を指定してください".into());
         }
-        password
+        let mnemonic = Keys::generate_mnemonic(words)?.to_string();
+        (Keys::from_mnemonic(mnemonic.clone(), None)?, Some(mnemonic))
+    } else {
+        (Keys::generate(), None)
     };
-
-    // キーを生成
-    let keys = Keys::generate();
     let public_key = keys.public_key();
     let secret_key = keys.secret_key()?;
 
-    // 秘密鍵をHex形式で取得（displayメソッドを使用）
-    let secret_key_str = secret_key.display_secret().to_string();
+    // 秘密鍵をパスワードで暗号化して保存（NIP-49。key_kdf が argon2id なら Argon2id）
+    let keys_path = save_key_file(secret_key, &password, KeySecurity::Secure)?;
 
-    // 保存データの作成
-    let encrypted_data = format!("{{\"secret_key\":\"{}\",\"password\":\"{}\"}}", secret_key_str, password);
-
-    // 保存ディレクトリを作成
-    let config_dir = dirs::home_dir()
-        .ok_or("ホームディレクトリが見つかりません")?
-        .join(".nostr-cli-app");
-    fs::create_dir_all(&config_dir)?;
-
-    // 鍵を保存
-    let keys_path = config_dir.join("keys.json");
-    let mut file = File::create(&keys_path)?;
-    file.write_all(encrypted_data.as_bytes())?;
-
-    println!("鍵ペアを生成して保存しました");
+    eprintln!("鍵ペアを生成して保存しました（アカウント: {}）", active_account()?);
     println!("公開鍵: {}", public_key.to_bech32()?);
 
     if let Some(path) = keys_path.to_str() {
         println!("鍵の保存場所: {}", path);
     }
 
-    Ok(())
-}
-
-// 秘密鍵を表示する関数
-pub fn show_keys(_matches: &ArgMatches) -> Result<(), Box<dyn std::error::Error>> {
-    println!("鍵を復号化するためのパスワードを入力してください:");
-    let password = rpassword::read_password()?;
-
-    let keys = load_keys(&password)?;
-    let public_key = keys.public_key();
-    let secret_key = keys.secret_key()?;
-
-    println!("公開鍵 (hex): {}", public_key);
-    println!("公開鍵 (bech32): {}", public_key.to_bech32()?);
-    println!("秘密鍵 (hex): {}", secret_key.display_secret());
-    println!("秘密鍵 (bech32): {}", secret_key.to_bech32()?);
+    // ニーモニックはどこにも保存しないので、表示はこの1回だけ
+    if let Some(mnemonic) = mnemonic {
+        eprintln!("以下のニーモニック（シードフレーズ）を紙に書き写して保管してください。再表示はできません");
+        eprintln!("recover-keys でこの鍵ペアを復元できます（NIP-06 に対応した他のクライアントでも使えます）");
+        println!("{}", mnemonic);
+    }
 
     Ok(())
 }
 
-// 保存された鍵を読み込む関数
-pub fn load_keys(password: &str) -> Result<Keys, Box<dyn std::error::Error>> {
-    let keys_path = dirs::home_dir()
-        .ok_or("ホームディレクトリが見つかりません")?
-        .join(".nostr-cli-app")
-        .join("keys.json");
+// 取り込んだ鍵を暗号化して使用中のアカウントに保存する関数（既存の鍵を置き換える前に確認する）
+fn save_restored_keys(matches: &ArgMatches, keys: &Keys, done: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let public_key = keys.public_key().to_bech32()?;
 
-    if !keys_path.exists() {
-        return Err(format!("鍵ファイルが見つかりません: {:?}", keys_path).into());
+    // 既存の鍵を上書きする前に確認する
+    if keys_path()?.exists()
+        && !confirm(&format!(
+            "アカウント {} の保存済みの鍵を {} で置き換えますか？（元の鍵は復元できません）",
+            active_account()?,
+            public_key
+        ))?
+    {
+        return Ok(());
     }
 
-    let encrypted_data = std::fs::read_to_string(&keys_path)?;
+    let password = new_key_password(matches)?;
+    // 他のクライアントでどう扱われてきたかはわからない
+    let keys_path = save_key_file(keys.secret_key()?, &password, KeySecurity::Unknown)?;
 
-    // JSONからデータを解析
-    #[derive(Deserialize)]
-    struct KeyData {
-        secret_key: String,
-        password: String,
+    // キーチェーンを使っている場合はそちらも新しい鍵にする
+    if AppConfig::load()?.use_keyring {
+        store_secret_key(keys.secret_key()?)?;
     }
 
-    let key_data: KeyData = serde_json::from_str(&encrypted_data)?;
-
-    // パスワードの検証
-    if key_data.password != password {
-        return Err("パスワードが正しくありません".into());
+    eprintln!("{}", done);
+    println!("公開鍵: {}", public_key);
+    if let Some(path) = keys_path.to_str() {
+        println!("鍵の保存場所: {}", path);
     }
+    Ok(())
+}
 
-    // 16進数文字列から秘密鍵を生成
-    let bytes = hex::decode(&key_data.secret_key)?;
-    let secret_key = SecretKey::from_slice(&bytes)?;
-    let keys = Keys::new(secret_key);
-
-    Ok(keys)
+// ニーモニック（BIP-39）から NIP-06 の導出パス m/44'/1237'/0'/0/0 で鍵ペアを復元して保存する関数
+pub fn recover_keys(matches: &ArgMatches) -> Result<(), Box<dyn std::error::Error>> {
+    // 単語は引数、"-" なら標準入力、省略すれば画面に表示せずに入力させる
+    let words: Vec<String> = matches.get_many::<String>("mnemonic").map(|w| w.cloned().collect()).unwrap_or_default();
+    let input = match words.as_slice() {
+        [] => prompt_password("ニーモニック（単語を空白で区切って）を入力してください: ")?,
+        [dash] if dash == "-" => {
+            let mut input = String::new();
+            std::io::stdin().read_to_string(&mut input)?;
+            input
+        }
+        _ => words.join(" "),
+    };
+    let mnemonic = input.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase();
+    // BIP-39 のパスフレーズは、生成したクライアントで設定していたときだけ指定する
+    let passphrase = matches.get_one::<String>("passphrase").cloned();
+    let keys = Keys::from_mnemonic(mnemonic, passphrase).map_err(|e| format!("ニーモニックを解釈できません: {}", e))?;
+    save_restored_keys(matches, &keys, "ニーモニックから鍵ペアを復元して保存しました")
 }
 
-// テキストノートを送信する関数
-pub async fn send_note(matches: &ArgMatches) -> Result<(), Box<dyn std::error::Error>> {
-    // 入力内容を取得
-    let content = matches.get_one::<String>("content").ok_or("コンテンツが指定されていません")?;
+// 公開鍵（npub）だけを使用中のアカウントに保存する関数（閲覧専用。フィードの表示はできるが署名はできない）
+pub fn import_npub(matches: &ArgMatches) -> Result<(), Box<dyn std::error::Error>> {
+    let input = matches.get_one::<String>("npub").ok_or("公開鍵が指定されていません")?;
+    let public_key = resolve_pubkey(input.trim())?;
 
-    // パスワードの入力
-    println!("鍵を復号化するためのパスワードを入力してください:");
-    let password = rpassword::read_password()?;
+    // 秘密鍵を保存しているアカウントを閲覧専用にしてしまわないよう確認する
+    if read_key_file().map_or(false, |key_file| key_file.has_secret_key())
+        && !confirm(&format!(
+            "アカウント {} に保存済みの秘密鍵を削除し、公開鍵 {} だけの閲覧専用にしますか？（秘密鍵は復元できません）",
+            active_account()?,
+            public_key.to_bech32()?
+        ))?
+    {
+        return Ok(());
+    }
 
-    // 鍵をロード
-    let keys = load_keys(&password)?;
+    write_key_file(&KeyFile { public_key: Some(public_key.to_bech32()?), ..Default::default() })?;
 
-    // クライアントの初期化
-    let client = Client::new(&keys);
+    eprintln!("公開鍵を保存しました（アカウント: {}、閲覧専用）", active_account()?);
+    eprintln!("show-feed と TUI のイベントリストを使えます。投稿など署名が必要な操作には import-key で秘密鍵を取り込んでください");
+    println!("公開鍵: {}", public_key.to_bech32()?);
+    Ok(())
+}
 
-    // リレーの設定
-    let relay_config = load_relays()?;
-    if relay_config.relays.is_empty() {
-        client.add_relay("wss://yabu.me").await?;
+// 他のクライアントで使っている秘密鍵（nsec / hex）を取り込み、暗号化して保存する関数
+pub fn import_key(matches: &ArgMatches) -> Result<(), Box<dyn std::error::Error>> {
+    if matches.get_flag("shamir") {
+        let secret_key = combine(&read_shares(matches.get_one::<String>("key").map(|s| s.as_str()))?)?;
+        return save_restored_keys(matches, &Keys::new(secret_key), "断片から秘密鍵を戻して保存しました");
+    }
+    // 秘密鍵は引数、"-" なら標準入力、省略すれば画面に表示せずに入力させる
+    let input = match matches.get_one::<String>("key") {
+        Some(key) if key == "-" => {
+            let mut input = String::new();
+            std::io::stdin().read_to_string(&mut input)?;
+            input
+        }
+        Some(key) => key.clone(),
+        None => prompt_password("取り込む秘密鍵（nsec1... または hex）を入力してください: ")?,
+    };
+    let input = input.trim();
+    let secret_key = if input.starts_with("nsec1") {
+        SecretKey::from_bech32(input).map_err(|e| format!("nsec を解釈できません: {}", e))?
     } else {
-        for url in &relay_config.relays {
-            client.add_relay(url.clone()).await?;
+        SecretKey::from_str(input).map_err(|e| format!("nsec または 64桁の hex を指定してください: {}", e))?
+    };
+    save_restored_keys(matches, &Keys::new(secret_key), "秘密鍵を取り込んで保存しました")
+}
+
+// export-key --shamir で書き出した断片を集める関数（"-" なら標準入力の1行に1つ、省略すると必要な数だけ入力させる）
+fn read_shares(source: Option<&str>) -> Result<Vec<Share>, Box<dyn std::error::Error>> {
+    if source == Some("-") {
+        let mut input = String::new();
+        std::io::stdin().read_to_string(&mut input)?;
+        return input.lines().filter(|line| !line.trim().is_empty()).map(Share::parse).collect();
+    }
+    let mut shares: Vec<Share> = Vec::new();
+    loop {
+        let needed = shares.first().map_or(1, |s| s.threshold as usize);
+        if shares.len() >= needed {
+            return Ok(shares);
+        }
+        let line = prompt_password(&format!("断片を入力してください（{}/{}）: ", shares.len() + 1, if shares.is_empty() { "?".to_string() } else { needed.to_string() }))?;
+        match Share::parse(&line) {
+            Ok(share) if shares.iter().any(|s| s.index == share.index) => eprintln!("断片 {} は入力済みです", share.index),
+            Ok(share) => shares.push(share),
+            Err(e) => eprintln!("{}。もう一度入力してください", e),
         }
     }
+}
 
-    // リレーに接続
-    client.connect().await;
-
-    // イベントの作成と送信
-    let event = EventBuilder::new_text_note(content, Vec::<Tag>::new()).to_event(&keys)?;
-    client.send_event(event).await?;
+// 端末に QR コードを表示する関数（端末の背景色によらず読み取れるよう、明暗を反転した白地に黒の QR コードにする）
+fn print_qr(text: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let code = qrcode::QrCode::new(text.as_bytes())?;
+    let rendered = code
+        .render::<qrcode::render::unicode::Dense1x2>()
+        .dark_color(qrcode::render::unicode::Dense1x2::Light)
+        .light_color(qrcode::render::unicode::Dense1x2::Dark)
+        .quiet_zone(true)
+        .build();
+    println!("{}", rendered);
+    Ok(())
+}
 
-    println!("ノートを送信しました");
+// 秘密鍵を N-of-M の断片に分けて書き出す関数（--out-dir があれば1つずつ別のファイルに、なければ区切って表示する）
+fn export_shares(matches: &ArgMatches, secret_key: &SecretKey, scheme: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let (threshold, total) = parse_scheme(scheme)?;
+    let shares = split(secret_key, threshold, total);
+    eprintln!("秘密鍵を{}個の断片に分けました。{}個集めれば import-key --shamir で戻せます（{}個以下では何も分かりません）", total, threshold, threshold - 1);
 
-    // クライアントをシャットダウン
-    client.shutdown().await?;
+    if let Some(dir) = matches.get_one::<String>("out-dir") {
+        let dir = PathBuf::from(dir);
+        fs::create_dir_all(&dir)?;
+        for share in &shares {
+            let path = dir.join(format!("nostr-key-share-{}of{}.txt", share.index, share.total));
+            // 既存の断片は上書きせず、本人だけが読めるファイルとして作る
+            let mut file = fs::OpenOptions::new().write(true).create_new(true).mode(0o600).open(&path)?;
+            writeln!(file, "{}", share.encode())?;
+            eprintln!("断片 {}/{} を書き出しました: {:?}", share.index, share.total, path);
+        }
+        eprintln!("それぞれの断片は別々の場所（別の USB メモリ・紙など）に保管してください");
+        return Ok(());
+    }
 
+    if !confirm("断片を続けて表示します。1つずつ書き写すか印刷して、別々の場所に保管してください。続けますか？")? {
+        return Ok(());
+    }
+    for share in &shares {
+        println!("--- 断片 {}/{}（{}個で復元） ---", share.index, share.total, share.threshold);
+        println!("{}", share.encode());
+        if matches.get_flag("qr") {
+            print_qr(&share.encode())?;
+        }
+    }
     Ok(())
 }
 
-// イベントフィードを表示する関数
-pub async fn show_feed(_matches: &ArgMatches) -> Result<(), Box<dyn std::error::Error>> {
-    // パスワードの入力
-    println!("鍵を復号化するためのパスワードを入力してください:");
-    let password = rpassword::read_password()?;
-
-    // 鍵をロード
+// 秘密鍵を nsec（--ncryptsec なら暗号化したまま）で書き出す関数。--qr で端末に QR コードも表示する
+// キーチェーンに保存していても、必ずパスワードを入力させて本人か確かめる
+pub fn export_key(matches: &ArgMatches) -> Result<(), Box<dyn std::error::Error>> {
+    password_attempts_notice()?;
+    let password = prompt_password("鍵を復号化するためのパスワードを入力してください: ")?;
     let keys = load_keys(&password)?;
 
-    // クライアントの初期化
-    let client = Client::new(&keys);
+    if let Some(scheme) = matches.get_one::<String>("shamir") {
+        return export_shares(matches, keys.secret_key()?, scheme);
+    }
 
-    // リレーの設定
-    let relay_config = load_relays()?;
-    if relay_config.relays.is_empty() {
-        client.add_relay("wss://yabu.me").await?;
+    let exported = if matches.get_flag("ncryptsec") {
+        let key_file = read_key_file()?;
+        match (key_file.ncryptsec, key_file.encrypted_key) {
+            (Some(ncryptsec), _) => ncryptsec,
+            // Argon2id で保存している鍵は、ほかのクライアントでも読める NIP-49 の形式に暗号化し直して書き出す
+            (None, Some(encrypted)) => encrypt_secret_key(keys.secret_key()?, &password, DEFAULT_LOG_N, encrypted.security()?)?,
+            (None, None) => return Err("暗号化された秘密鍵が保存されていません".into()),
+        }
     } else {
-        for url in &relay_config.relays {
-            client.add_relay(url.clone()).await?;
+        if !confirm("秘密鍵をそのまま表示します。周りに見られていないことを確かめてください。続けますか？")? {
+            return Ok(());
         }
+        keys.secret_key()?.to_bech32()?
+    };
+
+    println!("{}", exported);
+    if matches.get_flag("qr") {
+        print_qr(&exported)?;
     }
+    Ok(())
+}
 
-    // リレーに接続
-    client.connect().await;
+// 秘密鍵を表示する関数
+pub fn show_keys(_matches: &ArgMatches) -> Result<(), Box<dyn std::error::Error>> {
+    let keys = unlock_keys()?;
+    let public_key = keys.public_key();
+    let secret_key = keys.secret_key()?;
 
-    // フィルターの設定
-    let filter = Filter::new()
-        .kind(Kind::TextNote)
-        .limit(20);
+    println!("公開鍵 (hex): {}", public_key);
+    println!("公開鍵 (bech32): {}", public_key.to_bech32()?);
+    println!("秘密鍵 (hex): {}", secret_key.display_secret());
+    println!("秘密鍵 (bech32): {}", secret_key.to_bech32()?);
+    // 保存している暗号化済みの秘密鍵（NIP-49 に対応したクライアントへそのまま移せる）
+    let key_file = read_key_file()?;
+    if let Some(ncryptsec) = key_file.ncryptsec {
+        println!("秘密鍵 (ncryptsec): {}", ncryptsec);
+        println!("鍵の安全性: {}", key_security(&ncryptsec)?.label());
+    } else if let Some(encrypted) = key_file.encrypted_key {
+        let params = encrypted.kdf.params;
+        println!(
+            "鍵の導出: {}（メモリ {} KiB、反復 {} 回、並列度 {}。export-key --ncryptsec で NIP-49 の形式に書き出せます）",
+            encrypted.kdf.algorithm, params.memory_kib, params.iterations, params.parallelism
+        );
+        println!("鍵の安全性: {}", encrypted.security()?.label());
+    }
 
-    // イベントの取得
-    client.subscribe(vec![filter.clone()]).await;
-    let _subscription_id = "feed";
+    Ok(())
+}
 
-    println!("イベントを取得中...");
+// 使用中のアカウントの鍵を消す関数（端末を手放すとき用）
+// アカウント名を入力して確かめてから、鍵ファイルとその .bak などをゼロで上書きして削除し、キーチェーンの項目とエージェントが持つ鍵も消す
+pub fn delete_keys(_matches: &ArgMatches) -> Result<(), Box<dyn std::error::Error>> {
+    let account = active_account()?;
+    let keys_path = keys_path()?;
+    let file_name = keys_path.file_name().and_then(|n| n.to_str()).unwrap_or("keys.json").to_string();
+    let candidates: Vec<PathBuf> = ["", ".bak", ".tmp", ".corrupt"]
+        .iter()
+        .map(|suffix| keys_path.with_file_name(format!("{}{}", file_name, suffix)))
+        .chain(std::iter::once(keys_path.with_file_name("password_attempts.json")))
+        .collect();
 
-    // 最大20件のイベントを表示
-    let mut events = Vec::new();
-    let start_time = std::time::Instant::now();
-    let timeout = std::time::Duration::from_secs(10);
+    let npub = read_key_file().ok().and_then(|key_file| key_file.public_key);
+    eprintln!("アカウント {} の鍵（{}）を消します。秘密鍵のバックアップがなければ、このアカウントには二度とログインできません", account, npub.as_deref().unwrap_or("公開鍵は不明"));
+    if prompt_line(&format!("確かめるため、アカウント名 {} を入力してください", account))? != account {
+        eprintln!("入力が一致しないため中止しました");
+        return Ok(());
+    }
 
-    while events.len() < 20 && start_time.elapsed() < timeout {
-        if let Ok(notification) = tokio::time::timeout(
-            std::time::Duration::from_secs(1),
-            client.notifications().recv(),
-        ).await {
-            if let Ok(RelayPoolNotification::Event { event, .. }) = notification {
-                events.push(event);
+    let mut removed = Vec::new();
+    {
+        // ほかのインスタンスが鍵ファイルを書き直している途中に消さない
+        let _lock = FileLock::acquire(&keys_path)?;
+        for path in &candidates {
+            if shred_file(path)? {
+                removed.push(format!("{:?}（上書きして削除）", path));
             }
         }
     }
+    let lock_path = keys_path.with_file_name(format!("{}.lock", file_name));
+    if fs::remove_file(&lock_path).is_ok() {
+        removed.push(format!("{:?}", lock_path));
+    }
+    match delete_secret_key() {
+        Ok(true) => removed.push("OS のキーチェーンの秘密鍵".to_string()),
+        Ok(false) => {}
+        Err(e) => eprintln!("{}", e),
+    }
+    // エージェントがこのアカウントの鍵を持っていれば止める（ほかのアカウントの鍵なら触らない）
+    if agent_status().is_ok_and(|status| status.account == account) && stop_agent().is_ok() {
+        removed.push("鍵のエージェント（メモリ上の秘密鍵）".to_string());
+    }
 
-    // サブスクリプションを解除
-    let _ = client.unsubscribe().await;
+    if removed.is_empty() {
+        println!("アカウント {} には消す鍵がありませんでした", account);
+        return Ok(());
+    }
+    println!("アカウント {} の鍵を消しました:", account);
+    for item in &removed {
+        println!("  - {}", item);
+    }
+    eprintln!("backup create や export-key で書き出したファイルは消していません。不要なら別に処分してください");
+    Ok(())
+}
 
-    // イベントを時系列順に並べ替え
-    events.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+// パスワードを入力させる関数（プロンプトは標準エラー出力に表示し、標準出力を汚さない）
+pub fn prompt_password(prompt: &str) -> Result<String, Box<dyn std::error::Error>> {
+    Ok(rpassword::prompt_password(prompt)?)
+}
 
-    // イベントの表示
-    println!("{}件のイベントを取得しました", events.len());
-    for event in &events {
-        let pubkey = event.pubkey.to_bech32()?;
-        println!("-----------------------------------");
-        println!("アカウント: {}", pubkey);
-        println!("時間: {}", event.created_at);
-        println!("内容: {}", event.content);
+// キーチェーンから鍵を読み込む関数（use_keyring が無効、または未登録なら None）
+pub fn keychain_keys() -> Result<Option<Keys>, Box<dyn std::error::Error>> {
+    if !AppConfig::load()?.use_keyring {
+        return Ok(None);
     }
+    Ok(load_secret_key()?.map(Keys::new))
+}
 
-    // クライアントをシャットダウン
-    client.shutdown().await?;
+// 鍵を読み込む関数（キーチェーンから読めなければパスワードを入力させる）
+pub fn unlock_keys() -> Result<Keys, Box<dyn std::error::Error>> {
+    // 署名を外部のプログラムや署名サーバーに任せるときは秘密鍵を読み込まない
+    if external_signer() {
+        return signer_keys();
+    }
+    // 閲覧専用のアカウントでは、パスワードを入力させる前に断る
+    if read_only_public_key()?.is_some() {
+        return Err(READ_ONLY_MESSAGE.into());
+    }
+    // 鍵のエージェントが動いていれば、そこから受け取る
+    if let Some(keys) = agent_keys(&active_account()?) {
+        return Ok(keys);
+    }
+    match keychain_keys() {
+        Ok(Some(keys)) => return Ok(keys),
+        Ok(None) => {}
+        Err(e) => eprintln!("{}。パスワードで読み込みます", e),
+    }
+    password_attempts_notice()?;
+    let password = prompt_password("鍵を復号化するためのパスワードを入力してください: ")?;
+    load_keys(&password)
+}
 
+// パスワードを続けて間違えていれば、入力させる前に知らせる関数（ロック中なら入力させずに断る）
+pub fn password_attempts_notice() -> Result<(), Box<dyn std::error::Error>> {
+    let config = AppConfig::load().unwrap_or_default();
+    let attempts = PasswordAttempts::load().unwrap_or_default();
+    let now = Timestamp::now();
+    let Some(line) = attempts.status_line(&config, now) else {
+        return Ok(());
+    };
+    if attempts.locked_until(&config, now).is_some() {
+        return Err(line.into());
+    }
+    eprintln!("{}", line);
     Ok(())
 }
 
-// リレーを追加する関数
-pub fn add_relay(matches: &ArgMatches) -> Result<(), Box<dyn std::error::Error>> {
-    let url = matches.get_one::<String>("url").ok_or("URLが指定されていません")?;
-
-    // 設定を読み込み
-    let mut config = load_relays()?;
+// 閲覧専用のアカウントで署名が必要な操作をしたときのエラー
+pub const READ_ONLY_MESSAGE: &str = "このアカウントは公開鍵だけを登録した閲覧専用のため、署名が必要な操作はできません（import-key で秘密鍵を取り込んでください）";
 
-    // リレーが既に存在するか確認
-    if config.relays.contains(url) {
-        println!("リレー {} は既に登録されています", url);
-        return Ok(());
+// 閲覧専用のアカウント（鍵ファイルに公開鍵しかない）なら、その公開鍵を返す関数
+pub fn read_only_public_key() -> Result<Option<XOnlyPublicKey>, Box<dyn std::error::Error>> {
+    let Ok(key_file) = read_key_file() else {
+        return Ok(None);
+    };
+    match &key_file.public_key {
+        Some(npub) if !key_file.has_secret_key() => Ok(Some(XOnlyPublicKey::from_bech32(npub)?)),
+        _ => Ok(None),
     }
+}
 
-    // リレーを追加
-    config.relays.push(url.clone());
+// 使用中のアカウントの鍵ファイルに記録した公開鍵を返す関数（復号しないのでパスワードは要らない）
+pub fn recorded_public_key() -> Option<XOnlyPublicKey> {
+    let npub = read_key_file().ok()?.public_key?;
+    XOnlyPublicKey::from_bech32(&npub).ok()
+}
 
-    // 設定を保存
-    save_relays(&config)?;
+// 署名を外部のプログラムに任せるときの鍵（鍵ファイルに記録した公開鍵だけを持つ）
+pub fn signer_keys() -> Result<Keys, Box<dyn std::error::Error>> {
+    let npub = read_key_file()
+        .ok()
+        .and_then(|key_file| key_file.public_key)
+        .ok_or("signer_command / bunker_uri を使うには、import-npub で署名する公開鍵を登録してください")?;
+    Ok(Keys::from_public_key(XOnlyPublicKey::from_bech32(&npub)?))
+}
 
-    println!("リレー {} を追加しました", url);
-    Ok(())
+// 閲覧だけに使う鍵を読み込む関数（閲覧専用のアカウントなら公開鍵だけの鍵にして、パスワードを入力させない）
+pub fn read_keys() -> Result<Keys, Box<dyn std::error::Error>> {
+    match read_only_public_key()? {
+        Some(public_key) => Ok(Keys::from_public_key(public_key)),
+        None => unlock_keys(),
+    }
 }
 
-// リレーを削除する関数
-pub fn remove_relay(matches: &ArgMatches) -> Result<(), Box<dyn std::error::Error>> {
-    let url = matches.get_one::<String>("url").ok_or("URLが指定されていません")?;
+// cron などから実行するときにパスワードを渡す環境変数
+pub const PASSWORD_ENV: &str = "NOSTR_CLI_APP_PASSWORD";
 
-    // 設定を読み込み
-    let mut config = load_relays()?;
+// 環境変数にパスワードがあればそれで鍵を読み込み、なければ入力を求める関数（自動実行向け）
+pub fn unlock_keys_from_env() -> Result<Keys, Box<dyn std::error::Error>> {
+    if external_signer() {
+        return signer_keys();
+    }
+    if read_only_public_key()?.is_some() {
+        return Err(READ_ONLY_MESSAGE.into());
+    }
+    match std::env::var(PASSWORD_ENV) {
+        Ok(password) => load_keys(&password),
+        Err(_) => unlock_keys(),
+    }
+}
 
-    // リレーが存在するか確認
-    let index = config.relays.iter().position(|r| r == url);
-    if let Some(idx) = index {
-        // リレーを削除
-        config.relays.remove(idx);
+// 鍵ファイル（keys.json）の内容
+#[derive(Serialize, Deserialize, Debug, Default)]
+struct KeyFile {
+    // パスワードで暗号化した秘密鍵（NIP-49）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    ncryptsec: Option<String>,
+    // 公開鍵（npub、復号せずにアカウントの一覧に表示する）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    public_key: Option<String>,
+    // Argon2id で暗号化した秘密鍵（key_kdf が argon2id のとき。導出のパラメータをヘッダーとして一緒に記録する）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    encrypted_key: Option<EncryptedKey>,
+    // 以前の形式（平文の秘密鍵とパスワード）。読み込んだときに ncryptsec へ移行する
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    secret_key: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    password: Option<String>,
+}
 
-        // 設定を保存
-        save_relays(&config)?;
-        println!("リレー {} を削除しました", url);
-    } else {
-        println!("リレー {} は登録されていません", url);
+impl KeyFile {
+    // 秘密鍵を（どの形式でも）保存しているか（なければ公開鍵だけの閲覧専用）
+    fn has_secret_key(&self) -> bool {
+        self.ncryptsec.is_some() || self.encrypted_key.is_some() || self.secret_key.is_some()
     }
+}
 
-    Ok(())
+// 使用中のアカウントの鍵ファイルを読み込む関数
+fn read_key_file() -> Result<KeyFile, Box<dyn std::error::Error>> {
+    read_key_file_at(&keys_path()?)
 }
 
-// リレーリストを表示する関数
-pub fn list_relays(_: &ArgMatches) -> Result<(), Box<dyn std::error::Error>> {
-    // 設定を読み込み
-    let config = load_relays()?;
+// 指定したパスの鍵ファイルを読み込む関数
+fn read_key_file_at(keys_path: &std::path::Path) -> Result<KeyFile, Box<dyn std::error::Error>> {
+    if !keys_path.exists() {
+        return Err(format!("鍵ファイルが見つかりません: {:?}", keys_path).into());
+    }
+    read_json(keys_path)
+}
 
-    if config.relays.is_empty() {
-        println!("登録されているリレーはありません");
-    } else {
-        println!("登録されているリレー一覧:");
-        for (i, url) in config.relays.iter().enumerate() {
-            println!("{}. {}", i + 1, url);
-        }
+// 使用中のアカウントの鍵ファイルを書き換える関数（保存先のパスを返す）
+// 以前の形式からの移行やパスワードの変更の前の内容が残らないよう、.bak は作らずに消す
+fn write_key_file(key_file: &KeyFile) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let keys_path = keys_path()?;
+    let _lock = FileLock::acquire(&keys_path)?;
+    write_json_no_backup(&keys_path, key_file)?;
+    Ok(keys_path)
+}
+
+// config で設定した Argon2id のパラメータ
+fn configured_argon2_params(config: &AppConfig) -> Argon2Params {
+    Argon2Params { memory_kib: config.argon2_memory_kib, iterations: config.argon2_iterations, parallelism: 1 }
+}
+
+// 秘密鍵を config の key_kdf の方式で暗号化し、公開鍵と一緒に使用中のアカウントの鍵ファイルに保存する関数（保存先のパスを返す）
+fn save_key_file(secret_key: &SecretKey, password: &str, security: KeySecurity) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    write_key_file(&encrypt_key_file(secret_key, password, security)?)
+}
+
+// 鍵を読み込んだついでに暗号化し直す関数（ほかのインスタンスが鍵ファイルのロックを持っていれば、待たずに次回へ回す）
+fn resave_key_file(secret_key: &SecretKey, password: &str, security: KeySecurity) -> Result<(), Box<dyn std::error::Error>> {
+    let keys_path = keys_path()?;
+    let Some(_lock) = FileLock::try_acquire(&keys_path)? else {
+        return Ok(());
+    };
+    write_json_no_backup(&keys_path, &encrypt_key_file(secret_key, password, security)?)
+}
+
+// 秘密鍵を config の key_kdf の方式で暗号化した鍵ファイルの中身を作る関数
+fn encrypt_key_file(secret_key: &SecretKey, password: &str, security: KeySecurity) -> Result<KeyFile, Box<dyn std::error::Error>> {
+    let config = AppConfig::load()?;
+    let public_key = Some(Keys::new(*secret_key).public_key().to_bech32()?);
+    let key_file = match config.key_kdf.as_str() {
+        "scrypt" => KeyFile {
+            ncryptsec: Some(encrypt_secret_key(secret_key, password, DEFAULT_LOG_N, security)?),
+            public_key,
+            ..Default::default()
+        },
+        "argon2id" => KeyFile {
+            encrypted_key: Some(encrypt_secret_key_argon2id(secret_key, password, configured_argon2_params(&config), security)?),
+            public_key,
+            ..Default::default()
+        },
+        other => return Err(format!("key_kdf には scrypt か argon2id を指定してください: {}", other).into()),
+    };
+    Ok(key_file)
+}
+
+// 保存している鍵の導出方式やパラメータが、今の設定と違うか（違えば暗号化し直す）
+fn kdf_outdated(kdf: Option<&KdfHeader>) -> Result<bool, Box<dyn std::error::Error>> {
+    let config = AppConfig::load()?;
+    Ok(match (config.key_kdf.as_str(), kdf) {
+        ("argon2id", Some(header)) => header.algorithm != "argon2id" || header.params != configured_argon2_params(&config),
+        ("argon2id", None) => true,
+        ("scrypt", Some(_)) => true,
+        _ => false,
+    })
+}
+
+// 保存された鍵を読み込む関数
+// パスワードを続けて間違えていれば、待ち時間が過ぎるまで待ってから試す（ロック中は試さずに断る）
+pub fn load_keys(password: &str) -> Result<Keys, Box<dyn std::error::Error>> {
+    let key_file = read_key_file()?;
+    if !key_file.has_secret_key() && key_file.public_key.is_some() {
+        return Err(READ_ONLY_MESSAGE.into());
     }
 
-    Ok(())
+    let config = AppConfig::load().unwrap_or_default();
+    let mut attempts = PasswordAttempts::load().unwrap_or_default();
+    if attempts.locked_until(&config, Timestamp::now()).is_some() {
+        return Err(attempts.status_line(&config, Timestamp::now()).unwrap_or_default().into());
+    }
+    if let Some(wait) = attempts.wait_remaining(Timestamp::now()) {
+        std::thread::sleep(wait);
+    }
+
+    match decrypt_key_file(&key_file, password) {
+        Ok(keys) => {
+            attempts.reset()?;
+            Ok(keys)
+        }
+        Err(e) if e.to_string() == WRONG_PASSWORD => {
+            attempts.record_failure(Timestamp::now())?;
+            match attempts.status_line(&config, Timestamp::now()) {
+                Some(line) => Err(format!("{}（{}）", WRONG_PASSWORD, line).into()),
+                None => Err(e),
+            }
+        }
+        Err(e) => Err(e),
+    }
 }
 
-// リレー設定を読み込む関数
-pub fn load_relays() -> Result<RelayConfig, Box<dyn std::error::Error>> {
-    let config_dir = dirs::home_dir()
-        .ok_or("ホームディレクトリが見つかりません")?
-        .join(".nostr-cli-app");
+// 鍵ファイルの秘密鍵を復号する関数
+fn decrypt_key_file(key_file: &KeyFile, password: &str) -> Result<Keys, Box<dyn std::error::Error>> {
+    // 鍵ファイルのヘッダーに記録したパラメータで復号する（あとから設定を変えても古いファイルを読める）
+    let stored = match (&key_file.encrypted_key, &key_file.ncryptsec) {
+        (Some(encrypted), _) => Some((decrypt_encrypted_key(encrypted, password)?, encrypted.security()?, Some(&encrypted.kdf))),
+        (None, Some(ncryptsec)) => Some((decrypt_secret_key(ncryptsec, password)?, key_security(ncryptsec)?, None)),
+        (None, None) => None,
+    };
+    if let Some((secret_key, security, kdf)) = stored {
+        // key_kdf や Argon2id のパラメータを変えていれば新しい設定で暗号化し直す
+        // 公開鍵を記録していない鍵ファイルにも書き足しておく（account list で表示するため）
+        if key_file.public_key.is_none() || kdf_outdated(kdf)? {
+            resave_key_file(&secret_key, password, security)?;
+        }
+        return Ok(Keys::new(secret_key));
+    }
 
-    let relays_path = config_dir.join("relays.json");
+    // 以前の形式: パスワードを確かめてから ncryptsec に暗号化し直し、平文を消す
+    let (Some(secret_hex), Some(stored_password)) = (&key_file.secret_key, &key_file.password) else {
+        return Err("鍵ファイルに秘密鍵が含まれていません".into());
+    };
+    if stored_password != password {
+        return Err(WRONG_PASSWORD.into());
+    }
+    let bytes = hex::decode(secret_hex)?;
+    let secret_key = SecretKey::from_slice(&bytes)?;
+    resave_key_file(&secret_key, password, KeySecurity::Insecure)?;
+    let keys = Keys::new(secret_key);
 
-    if !relays_path.exists() {
-        return Ok(RelayConfig::default());
+    Ok(keys)
+}
+
+// 登録済みのリレー一覧を返す関数（未登録ならデフォルトリレー）
+pub fn configured_relays() -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let relay_config = load_relays()?;
+    if relay_config.relays.is_empty() {
+        Ok(vec![DEFAULT_RELAY.to_string()])
+    } else {
+        Ok(relay_config.relays)
     }
+}
 
-    let mut file = File::open(relays_path)?;
-    let mut contents = String::new();
-    file.read_to_string(&mut contents)?;
+// 標準エラー出力に質問を表示し、y/N の回答を受け取る関数
+pub fn confirm(question: &str) -> Result<bool, Box<dyn std::error::Error>> {
+    eprint!("{} [y/N]: ", question);
+    std::io::stderr().flush()?;
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer)?;
+    Ok(matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"))
+}
 
-    let config: RelayConfig = serde_json::from_str(&contents)?;
-    Ok(config)
+// 標準エラー出力に質問を表示し、1行の回答を受け取る関数
+pub fn prompt_line(question: &str) -> Result<String, Box<dyn std::error::Error>> {
+    eprint!("{}: ", question);
+    std::io::stderr().flush()?;
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer)?;
+    Ok(answer.trim().to_string())
 }
 
-// リレー設定を保存する関数
-fn save_relays(config: &RelayConfig) -> Result<(), Box<dyn std::error::Error>> {
-    let config_dir = dirs::home_dir()
-        .ok_or("ホームディレクトリが見つかりません")?
-        .join(".nostr-cli-app");
+// 添付するメディアごとに代替テキストとサイズを尋ねる関数
+fn prompt_media_info(urls: &[String]) -> Result<Vec<MediaInfo>, Box<dyn std::error::Error>> {
+    let mut media = Vec::new();
+    for url in urls {
+        eprintln!("メディア: {}", url);
+        let alt = prompt_line("代替テキスト（画像を見られない人向けの説明、空欄で省略）")?;
+        let dim = loop {
+            let dim = prompt_line("サイズ（例: 1200x800、空欄で省略）")?;
+            if dim.is_empty() || is_valid_dim(&dim) {
+                break dim;
+            }
+            eprintln!("サイズは「幅x高さ」の形式で入力してください");
+        };
+        media.push(MediaInfo {
+            url: url.clone(),
+            mime: guess_mime(url),
+            alt: (!alt.is_empty()).then_some(alt),
+            dim: (!dim.is_empty()).then_some(dim),
+            // Blossom のURLならハッシュを x として付け、受け取った側が検証できるようにする
+            sha256: blossom_hash(url),
+        });
+    }
+    Ok(media)
+}
 
-    fs::create_dir_all(&config_dir)?;
+// 接続するリレーを選ぶ関数（接続に失敗が続いているリレーを除き、同時接続数の上限までに絞る）
+fn relays_to_connect(relays: &[String], cache: &CapabilityCache) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let limit = AppConfig::load()?.max_relay_connections.max(1);
+    let (selected, backing_off) = cache.select_for_connect(relays, limit);
+    for (url, secs) in &backing_off {
+        eprintln!("{} は接続の失敗が続いているため、あと{}秒は接続しません", url, secs);
+    }
+    if relays.len() - backing_off.len() > selected.len() {
+        eprintln!("同時に接続するリレーを{}件に制限しました（config set max_relay_connections <数>）", limit);
+    }
+    Ok(selected)
+}
 
-    let relays_path = config_dir.join("relays.json");
-    let contents = serde_json::to_string_pretty(config)?;
+// 登録済みのリレー（未登録ならデフォルトリレー）に接続したクライアントを作成する関数
+pub async fn connect_client(keys: &Keys) -> Result<Client, Box<dyn std::error::Error>> {
+    let mut cache = CapabilityCache::load()?;
+    let selected = relays_to_connect(&configured_relays()?, &cache)?;
 
-    let mut file = File::create(relays_path)?;
-    file.write_all(contents.as_bytes())?;
+    // リレーの設定（unix:// などは中継の URL にする）
+    let client = Client::new(keys);
+    let selected: Vec<String> = selected.iter().map(|url| dial(url)).collect::<Result<_, _>>()?;
+    for url in &selected {
+        client.add_relay(url.as_str()).await?;
+    }
+
+    // リレーに接続（AUTH を求められたら自動で認証する）
+    spawn_auto_auth(&client);
+    connect_measured(&client, &selected, &mut cache).await;
+    if let Err(e) = cache.save() {
+        eprintln!("リレーの計測結果を保存できませんでした: {}", e);
+    }
 
+    Ok(client)
+}
+
+// リレーの接続を待つ時間
+const RELAY_CONNECT_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+
+// 追加済みのリレーに接続し、接続済みになるまでの時間を記録する関数
+// 時間内に接続できなかったリレーは失敗として記録し、次回から間隔を空けて接続する
+pub async fn connect_measured(client: &Client, urls: &[String], cache: &mut CapabilityCache) {
+    let started = std::time::Instant::now();
+    client.connect().await;
+
+    let mut pending = urls.to_vec();
+    while !pending.is_empty() && started.elapsed() < RELAY_CONNECT_TIMEOUT {
+        for (url, relay) in client.relays().await {
+            let url = url.to_string();
+            let Some(index) = pending.iter().position(|p| p.trim_end_matches('/') == url.trim_end_matches('/')) else {
+                continue;
+            };
+            if relay.status().await == RelayStatus::Connected {
+                let latency = started.elapsed().as_secs_f64() * 1000.0;
+                cache.entry(&configured_url(&pending.remove(index))).record_sample(true, Some(latency));
+            }
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+    }
+    for url in pending {
+        cache.entry(&configured_url(&url)).record_sample(false, None);
+    }
+}
+
+// 読み込みだけのコマンド用に、評価値の高いリレー（既定で3つ）に接続したクライアントを作成する関数
+pub async fn connect_read_client(keys: &Keys) -> Result<Client, Box<dyn std::error::Error>> {
+    let count = AppConfig::load()?.read_relay_count.max(1);
+    let mut cache = CapabilityCache::load()?;
+    let ranked: Vec<String> = cache.rank(&configured_relays()?).into_iter().map(|(url, _)| url).collect();
+    let selected: Vec<String> = relays_to_connect(&ranked, &cache)?.into_iter().take(count).collect();
+    let selected: Vec<String> = selected.iter().map(|url| dial(url)).collect::<Result<_, _>>()?;
+
+    let client = Client::new(keys);
+    for url in &selected {
+        client.add_relay(url.as_str()).await?;
+    }
+
+    spawn_auto_auth(&client);
+    connect_measured(&client, &selected, &mut cache).await;
+    if let Err(e) = cache.save() {
+        eprintln!("リレーの計測結果を保存できませんでした: {}", e);
+    }
+
+    Ok(client)
+}
+
+// 指定した1つのリレーだけに接続したクライアントを作成する関数（登録済みのリレーとは関係なく閲覧する）
+pub async fn connect_single_relay(keys: &Keys, url: &str) -> Result<Client, Box<dyn std::error::Error>> {
+    check_relay_url(url)?;
+    let mut cache = CapabilityCache::load()?;
+
+    let client = Client::new(keys);
+    let dialed = dial(url)?;
+    client.add_relay(dialed.as_str()).await?;
+
+    spawn_auto_auth(&client);
+    connect_measured(&client, &[dialed], &mut cache).await;
+    if let Err(e) = cache.save() {
+        eprintln!("リレーの計測結果を保存できませんでした: {}", e);
+    }
+    if !any_relay_connected(&client).await {
+        return Err(format!("{} に接続できませんでした", url).into());
+    }
+
+    Ok(client)
+}
+
+// リレーと自分の時計のずれを測り、大きくずれていれば投稿を続けるか確かめる関数（測れなければそのまま続ける）
+pub async fn confirm_clock_skew() -> Result<bool, Box<dyn std::error::Error>> {
+    let Some(warning) = measure_clock_skew(&configured_relays()?).await.and_then(skew_warning) else {
+        return Ok(true);
+    };
+    eprintln!("{}", warning);
+    confirm("このまま送信しますか？")
+}
+
+// テキストノートを送信する関数
+pub async fn send_note(matches: &ArgMatches) -> Result<(), Box<dyn std::error::Error>> {
+    // 入力内容を取得
+    let content = matches.get_one::<String>("content").ok_or("コンテンツが指定されていません")?;
+
+    // 言語の指定（鍵の入力前に形式を検査する）
+    let lang = match matches.get_one::<String>("lang") {
+        Some(code) => Some(normalize_lang(code)?),
+        None => None,
+    };
+    let alternatives = match matches.get_many::<String>("alt") {
+        Some(values) => {
+            let values: Vec<&String> = values.collect();
+            values
+                .chunks(2)
+                .map(|pair| -> Result<(String, String), Box<dyn std::error::Error>> {
+                    Ok((normalize_lang(pair[0])?, pair[1].clone()))
+                })
+                .collect::<Result<Vec<(String, String)>, Box<dyn std::error::Error>>>()?
+        }
+        None => Vec::new(),
+    };
+
+    // 一時的なイベントの kind（鍵の入力前に範囲を検査する）
+    let ephemeral = matches.get_one::<u64>("ephemeral").copied();
+    if let Some(kind) = ephemeral {
+        if !EPHEMERAL_KINDS.contains(&kind) {
+            return Err(format!("--ephemeral には 20000〜29999 の kind を指定してください: {}", kind).into());
+        }
+    }
+
+    // 添付メディアの代替テキストとサイズ
+    let media_urls: Vec<String> = matches
+        .get_many::<String>("media")
+        .map(|urls| urls.cloned().collect())
+        .unwrap_or_default();
+    let media = prompt_media_info(&media_urls)?;
+
+    // 本文の末尾にメディアのURLを並べる（imeta 非対応のクライアントでも表示できるように）
+    let mut content = content.clone();
+    for info in &media {
+        if !content.contains(&info.url) {
+            content.push('\n');
+            content.push_str(&info.url);
+        }
+    }
+
+    // 件名・ハッシュタグ・メンション
+    let config = AppConfig::load()?;
+    let mut draft = NoteDraft {
+        content,
+        subject: matches.get_one::<String>("subject").cloned(),
+        hashtags: matches
+            .get_many::<String>("hashtag")
+            .map(|values| parse_hashtag_list(&values.cloned().collect::<Vec<_>>().join(" ")))
+            .unwrap_or_default(),
+        auto_hashtags: config.auto_hashtags,
+        mentions: Vec::new(),
+    };
+    for name in draft.apply_mentions(&config.aliases) {
+        eprintln!("別名が登録されていないため、そのまま送信します: @{}", name);
+    }
+
+    // 位置情報（--location だけなら設定の home_geohash を使う）
+    let geohash = match matches.get_one::<String>("location") {
+        Some(location) if location.is_empty() => Some(parse_location(
+            config
+                .home_geohash
+                .as_deref()
+                .ok_or("home_geohash が設定されていません（config set home_geohash <ジオハッシュ>）")?,
+        )?),
+        Some(location) => Some(parse_location(location)?),
+        None => None,
+    };
+
+    // 鍵をロード
+    let keys = unlock_keys()?;
+
+    // 時計が大きくずれていれば、送る前に確かめる
+    if !confirm_clock_skew().await? {
+        return Ok(());
+    }
+
+    // クライアントの初期化とリレーへの接続
+    let client = connect_client(&keys).await?;
+
+    let mut tags = Vec::new();
+    if let Some(lang) = &lang {
+        tags.extend(language_tags(lang)?);
+    }
+    for info in &media {
+        tags.push(imeta_tag(info)?);
+    }
+    tags.extend(draft.tags()?);
+    if let Some(geohash) = &geohash {
+        tags.extend(geohash_tags(geohash)?);
+    }
+
+    // リレーの制限と照らし合わせ、PoW を求めるリレーがあれば採掘してから署名する
+    // （別の言語の版へのタグも数に入れる。一時的なイベントは保存されないので確かめない）
+    let preflight = match ephemeral {
+        Some(_) => Preflight::default(),
+        None => {
+            let preflight = preflight_note(&client, &draft.content, tags.len() + alternatives.len()).await?;
+            if !report_preflight(&preflight)? {
+                client.shutdown().await?;
+                return Ok(());
+            }
+            preflight
+        }
+    };
+
+    // 別の言語の版も確かめてから署名し、元の投稿からそのIDを案内する
+    // （どれかを取りやめたときに一部の版だけが残らないよう、送るのはすべての確認が済んでから）
+    let mut alt_events = Vec::new();
+    for (alt_lang, alt_content) in &alternatives {
+        let alt_tags = language_tags(alt_lang)?;
+        let alt_preflight = preflight_note(&client, alt_content, alt_tags.len()).await?;
+        if !report_preflight(&alt_preflight)? {
+            client.shutdown().await?;
+            return Ok(());
+        }
+        let alt_event = sign_checked(EventBuilder::new_text_note(alt_content, alt_tags), &keys, &alt_preflight)?;
+        tags.push(translation_tag(alt_event.id, alt_lang)?);
+        alt_events.push((alt_lang, alt_event));
+    }
+    for (alt_lang, alt_event) in alt_events {
+        let receipt = publish(&client, alt_event).await?;
+        eprintln!("{} 版を送信しました（{}）", alt_lang, receipt.summary());
+    }
+
+    // 一時的なイベントはリレーに保存されないので、アウトボックスにも送信済みの記録にも残さない
+    if let Some(kind) = ephemeral {
+        let event = sign_event(EventBuilder::new(Kind::from(kind), &draft.content, tags), &keys)?;
+        let receipt = send_with_receipts(&client, &event).await;
+        client.shutdown().await?;
+        if receipt.accepted.is_empty() {
+            return Err(format!("どのリレーからも受理されませんでした（{}）", receipt.failure_reason()).into());
+        }
+        eprintln!("一時的なイベント（kind {}）を送信しました（{}）。購読中の相手にだけ届き、リレーには残りません", kind, receipt.summary());
+        return Ok(());
+    }
+
+    // イベントの作成と送信（どのリレーからも受理されなければエラー）
+    let event = sign_checked(EventBuilder::new_text_note(&draft.content, tags), &keys, &preflight)?;
+    let receipt = publish(&client, event).await?;
+    for (url, reason) in &receipt.rejected {
+        eprintln!("{} には送信できませんでした: {}", url, reason);
+    }
+
+    eprintln!("ノートを送信しました（{}）", receipt.summary());
+
+    // クライアントをシャットダウン
+    client.shutdown().await?;
+
+    Ok(())
+}
+
+// イベントフィードを表示する関数
+pub async fn show_feed(matches: &ArgMatches) -> Result<(), Box<dyn std::error::Error>> {
+    let limit = *matches.get_one::<usize>("limit").unwrap_or(&20);
+
+    // 日時ジャンプの指定があれば先に解析しておく（パスワード入力前に形式エラーを返すため）
+    let around = match matches.get_one::<String>("around") {
+        Some(input) => Some(parse_jst_datetime(input)?),
+        None => None,
+    };
+
+    // 並び順（スコア順はコマンドが設定されているかも先に確認する）
+    let sort = FeedSort::parse(matches.get_one::<String>("sort").map(|s| s.as_str()).unwrap_or("newest"))?;
+    let score_command = match sort {
+        FeedSort::Plugin => Some(AppConfig::load()?.score_command.ok_or("score_command が設定されていません")?),
+        _ => None,
+    };
+
+    // 近所の投稿に絞る場合は、半径に応じた桁数のジオハッシュの接頭辞で検索する
+    let near = match matches.get_one::<String>("near") {
+        Some(input) => {
+            let geohash = parse_location(input)?;
+            let radius = parse_radius_km(matches.get_one::<String>("radius").map(|s| s.as_str()).unwrap_or("20km"))?;
+            let precision = precision_for_radius_km(radius).min(geohash.len());
+            Some(geohash[..precision].to_string())
+        }
+        None => None,
+    };
+
+    // 厳格な検証モードでは、署名を手元で検証できたイベントだけを表示する
+    let config = AppConfig::load()?;
+    let strict = config.strict_verification;
+    let kinds = feed_kinds(&config.feed_kinds);
+    let mut invalid = 0;
+    let mut violations: std::collections::BTreeMap<String, usize> = Default::default();
+
+    // 鍵をロード（閲覧だけなので、閲覧専用のアカウントでも使える）
+    let keys = read_keys()?;
+
+    // クライアントの初期化とリレーへの接続（--relay があればそのリレーだけに接続する）
+    let client = match matches.get_one::<String>("relay") {
+        Some(url) => connect_single_relay(&keys, url).await?,
+        None => connect_read_client(&keys).await?,
+    };
+
+    // フィルターの設定
+    // 日時指定がある場合は、指定時刻より前と後ろの2つの範囲を別々に問い合わせる
+    let filters = match around {
+        Some(center) => {
+            let (since, until) = around_window(center);
+            let half = (limit / 2).max(1);
+            vec![
+                Filter::new().kinds(kinds.clone()).since(since).until(center).limit(half),
+                Filter::new().kinds(kinds).since(center).until(until).limit(half),
+            ]
+        }
+        None => vec![Filter::new().kinds(kinds).limit(limit)],
+    };
+    let filters = match &near {
+        Some(prefix) => filters
+            .into_iter()
+            .map(|filter| filter.custom_tag(Alphabet::G, vec![prefix.clone()]))
+            .collect(),
+        None => filters,
+    };
+
+    // イベントの取得
+    client.subscribe(filters.clone()).await;
+
+    eprintln!("イベントを取得中...");
+
+    // 最大limit件のイベントを表示
+    let mut events: Vec<Event> = Vec::new();
+    let start_time = std::time::Instant::now();
+    let timeout = std::time::Duration::from_secs(10);
+
+    while events.len() < limit && start_time.elapsed() < timeout {
+        if let Ok(notification) = tokio::time::timeout(
+            std::time::Duration::from_secs(1),
+            client.notifications().recv(),
+        ).await {
+            if let Ok(RelayPoolNotification::Event { relay_url, event, .. }) = notification {
+                if strict && verify_event(&event).is_err() {
+                    invalid += 1;
+                    continue;
+                }
+                // 問い合わせたフィルターに合わないイベントを返すリレーもあるので、手元で確かめて捨てる
+                if !matches_any(&filters, &event) {
+                    *violations.entry(relay_url.to_string()).or_default() += 1;
+                    continue;
+                }
+                // 複数リレーから同じイベントが届くことがあるので重複を除く
+                if !events.iter().any(|e| e.id == event.id) {
+                    events.push(event);
+                }
+            }
+        }
+    }
+
+    // サブスクリプションを解除
+    let _ = client.unsubscribe().await;
+    if invalid > 0 {
+        eprintln!("署名の検証に失敗した{}件のイベントを破棄しました", invalid);
+    }
+    for (url, count) in &violations {
+        eprintln!("{} が返した、条件に合わない{}件のイベントを破棄しました", url, count);
+    }
+    if let Err(e) = record_violations(&violations) {
+        eprintln!("リレーの記録を保存できませんでした: {}", e);
+    }
+
+    // 他の投稿の翻訳版として案内されているものは元の投稿にまとめる
+    let variants = variant_ids(&events);
+    events.retain(|e| !variants.contains(&e.id));
+
+    // g タグの検索を独自に解釈するリレーもあるため、範囲外の投稿は手元でも除く
+    if let Some(prefix) = &near {
+        events.retain(|e| is_near(e, prefix));
+    }
+
+    // イベントを指定の順に並べ替え（返信数・Zap額が必要なら取得する）
+    let counts = if sort.needs_counts() {
+        fetch_feed_counts(&client, &events).await?
+    } else {
+        Default::default()
+    };
+    let scores = match &score_command {
+        Some(command) => plugin_scores(command, &events)?,
+        None => Default::default(),
+    };
+    sort_events(&mut events, sort, &counts, &scores);
+
+    // イベントの表示
+    eprintln!("{}件のイベントを取得しました", events.len());
+    let future = events.iter().filter(|e| is_future(e, Timestamp::now())).count();
+    if future > 0 {
+        eprintln!("作成日時が未来の{}件のイベントがあります（投稿者の時計のずれ）。今の日時として並べています", future);
+    }
+
+    // JSON出力の場合はデータだけを標準出力に書き出す（jq などに渡せるように）
+    if matches.get_flag("json") {
+        for event in &events {
+            println!("{}", event.as_json());
+        }
+        client.shutdown().await?;
+        return Ok(());
+    }
+
+    // 優先言語の翻訳版があれば、そちらの本文を表示する
+    let preferred = config.preferred_language.clone();
+    let preferred_variants = match &preferred {
+        Some(lang) => fetch_preferred_variants(&client, &events, lang).await.unwrap_or_default(),
+        None => Default::default(),
+    };
+
+    // 投稿者のプロフィールを取得し、NIP-05 のアドレスが本人を指しているか確かめる（✓ を付ける）
+    let mut profiles = ProfileCache::load().unwrap_or_default();
+    if let Err(e) = profiles.refresh(&client, &events, None).await {
+        eprintln!("プロフィールを取得できませんでした: {}", e);
+    }
+    let authors: Vec<XOnlyPublicKey> = events.iter().map(|e| e.pubkey).collect();
+    if let Err(e) = profiles.verify_nip05s(&authors).await {
+        eprintln!("NIP-05 の確認結果を保存できませんでした: {}", e);
+    }
+
+    let mut center_marked = false;
+    for event in &events {
+        // 指定時刻をまたいだ位置に目印を表示
+        if let Some(center) = around {
+            if !center_marked && sort == FeedSort::Newest && event.created_at <= center {
+                println!("=========== {} ===========", format_jst(center));
+                center_marked = true;
+            }
+        }
+
+        let pubkey = event.pubkey.to_bech32()?;
+        println!("-----------------------------------");
+        match profiles.get(&event.pubkey).and_then(|p| p.verified_nip05()) {
+            Some(nip05) => println!("アカウント: {} ✓ {}", pubkey, nip05),
+            None => println!("アカウント: {}", pubkey),
+        }
+        println!("時間: {}", format_jst(event.created_at));
+        match (preferred_variants.get(&event.id), &preferred) {
+            (Some(variant), Some(lang)) => println!(
+                "内容 [{}]: {}",
+                lang,
+                with_alt_placeholders(&variant.content, &parse_imeta(variant))
+            ),
+            // リポストや長文記事などは1行にまとめて表示する
+            _ => match compact_content(event) {
+                Some(compact) => println!("内容: {}", compact),
+                None => println!("内容: {}", with_alt_placeholders(&event.content, &parse_imeta(event))),
+            },
+        }
+    }
+
+    // クライアントをシャットダウン
+    client.shutdown().await?;
+
+    Ok(())
+}
+
+// 過去のメンションを1回に取得する件数と、さかのぼる回数の上限
+const MENTION_PAGE_SIZE: usize = 200;
+const MENTION_MAX_PAGES: usize = 10;
+
+// 自分宛てのメンションを期間をさかのぼって取得し、保存済みの分とまとめてスレッドごとに表示する関数
+// ネットワークから離れていた間に届いたメンションを後から確かめるのに使う
+pub async fn mentions(matches: &ArgMatches) -> Result<(), Box<dyn std::error::Error>> {
+    let since = parse_duration(matches.get_one::<String>("since").map(|s| s.as_str()).unwrap_or("30d"))?;
+    let since = Timestamp::from(Timestamp::now().as_u64().saturating_sub(since));
+
+    // 閲覧だけなので、閲覧専用のアカウントでも使える
+    let keys = read_keys()?;
+    let me = keys.public_key();
+    let client = connect_read_client(&keys).await?;
+
+    // リレーが一度に返す件数には上限があるので、古い方へ少しずつさかのぼる
+    eprintln!("{} 以降のメンションを取得中...", format_jst(since));
+    let mut fetched: Vec<Event> = Vec::new();
+    let mut until = Timestamp::now();
+    for _ in 0..MENTION_MAX_PAGES {
+        let filter = Filter::new()
+            .kind(Kind::TextNote)
+            .pubkey(me)
+            .since(since)
+            .until(until)
+            .limit(MENTION_PAGE_SIZE);
+        let page = client.get_events_of(vec![filter], Some(std::time::Duration::from_secs(10))).await?;
+        let Some(oldest) = page.iter().map(|e| e.created_at).min() else {
+            break;
+        };
+        let full = page.len() >= MENTION_PAGE_SIZE;
+        fetched.extend(page);
+        if !full || oldest <= since {
+            break;
+        }
+        until = Timestamp::from(oldest.as_u64().saturating_sub(1));
+    }
+    client.shutdown().await?;
+
+    // 保存済みの分とまとめる（同じイベントは1件に）
+    let mut all = load_cached_mentions()?;
+    let fetched_count = fetched.len();
+    all.extend(fetched);
+    all.sort_by(|a, b| a.created_at.cmp(&b.created_at).then(a.id.cmp(&b.id)));
+    all.dedup_by(|a, b| a.id == b.id);
+    // 厳格な検証モードでは、保存済みの分も含めて署名を確かめ直す（件数が多ければスレッドに分けて検証する）
+    if AppConfig::load()?.strict_verification {
+        let invalid = retain_verified(&mut all);
+        if invalid > 0 {
+            eprintln!("署名の検証に失敗した{}件のメンションを破棄しました", invalid);
+        }
+    }
+    match save_cached_mentions(&all) {
+        Ok(true) => {}
+        Ok(false) => eprintln!("{}", LOCK_HELD_NOTICE),
+        Err(e) => eprintln!("メンションを保存できません: {}", e),
+    }
+
+    let mutes = MuteList::load()?;
+    let events: Vec<&Event> = all
+        .iter()
+        .filter(|e| e.created_at >= since && e.pubkey != me && mentions_me(e, me))
+        .filter(|e| !mutes.is_author_muted(e) && !mutes.is_thread_muted(e))
+        .collect();
+    eprintln!("{}件のメンションがあります（リレーから{}件取得）", events.len(), fetched_count);
+
+    if matches.get_flag("json") {
+        for event in &events {
+            println!("{}", event.as_json());
+        }
+        return Ok(());
+    }
+
+    // スレッドごとにまとめ、新しく動きのあったスレッドほど後ろ（端末の下の方）に表示する
+    let mut threads: Vec<(EventId, Vec<&Event>)> = Vec::new();
+    for event in events {
+        let root = thread_root(event);
+        match threads.iter_mut().find(|(id, _)| *id == root) {
+            Some((_, thread)) => thread.push(event),
+            None => threads.push((root, vec![event])),
+        }
+    }
+    threads.sort_by_key(|(_, thread)| thread.last().map(|e| e.created_at));
+
+    let profiles = ProfileCache::load().unwrap_or_default();
+    for (root, thread) in threads {
+        println!("===================================");
+        println!("スレッド: {}（{}件）", root.to_bech32()?, thread.len());
+        for event in thread {
+            let author = match profiles.get(&event.pubkey).and_then(|p| p.label()) {
+                Some(name) => name.to_string(),
+                None => event.pubkey.to_bech32()?,
+            };
+            println!("-----------------------------------");
+            println!("{} {}", format_jst(event.created_at), author);
+            println!("{}", with_alt_placeholders(&event.content, &parse_imeta(event)));
+        }
+    }
+    Ok(())
+}
+
+// "YYYY-MM-DD HH:MM" 形式（JST）の日時をタイムスタンプに変換する関数
+pub fn parse_jst_datetime(input: &str) -> Result<Timestamp, Box<dyn std::error::Error>> {
+    let input = input.trim();
+    let jst = FixedOffset::east_opt(9 * 3600).ok_or("タイムゾーンの初期化に失敗しました")?;
+
+    let formats = ["%Y-%m-%d %H:%M:%S", "%Y-%m-%d %H:%M", "%Y/%m/%d %H:%M:%S", "%Y/%m/%d %H:%M"];
+    let mut naive = formats
+        .iter()
+        .find_map(|fmt| NaiveDateTime::parse_from_str(input, fmt).ok());
+
+    // 日付のみの場合はその日の正午を中心にする
+    if naive.is_none() {
+        naive = ["%Y-%m-%d", "%Y/%m/%d"]
+            .iter()
+            .find_map(|fmt| NaiveDate::parse_from_str(input, fmt).ok())
+            .and_then(|date| date.and_hms_opt(12, 0, 0));
+    }
+
+    let naive = naive.ok_or_else(|| {
+        format!("日時の形式が正しくありません: {} (例: 2025-01-01 12:00)", input)
+    })?;
+
+    let datetime = naive
+        .and_local_timezone(jst)
+        .single()
+        .ok_or("日時をJSTに変換できませんでした")?;
+
+    let secs = datetime.timestamp();
+    if secs < 0 {
+        return Err("1970年より前の日時は指定できません".into());
+    }
+
+    Ok(Timestamp::from(secs as u64))
+}
+
+// 指定時刻を中心とした since/until の範囲を計算する関数
+pub fn around_window(center: Timestamp) -> (Timestamp, Timestamp) {
+    let center = center.as_u64();
+    (
+        Timestamp::from(center.saturating_sub(AROUND_WINDOW_SECS)),
+        Timestamp::from(center.saturating_add(AROUND_WINDOW_SECS)),
+    )
+}
+
+// "30d" "12h" "90m" のような期間指定を秒数に変換する関数（単位なしは秒）
+pub fn parse_duration(input: &str) -> Result<u64, Box<dyn std::error::Error>> {
+    let input = input.trim();
+    let (number, unit) = input.split_at(input.trim_end_matches(|c: char| c.is_ascii_alphabetic()).len());
+    let value: u64 = number
+        .parse()
+        .map_err(|_| format!("期間の形式が正しくありません: {} (例: 30d, 12h)", input))?;
+    let scale = match unit {
+        "" | "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        "w" => 7 * 24 * 60 * 60,
+        _ => return Err(format!("期間の単位が不明です: {} (s/m/h/d/w)", unit).into()),
+    };
+    Ok(value.saturating_mul(scale))
+}
+
+// タイムスタンプをJSTの文字列に整形する関数
+pub fn format_jst(timestamp: Timestamp) -> String {
+    let jst = FixedOffset::east_opt(9 * 3600).unwrap();
+    chrono::DateTime::<chrono::Utc>::from_timestamp(timestamp.as_u64() as i64, 0)
+        .unwrap_or_default()
+        .with_timezone(&jst)
+        .format("%Y-%m-%d %H:%M:%S (JST)")
+        .to_string()
+}
+
+// イベントIDの文字列（hex / note1 / nevent1）を解析する関数
+pub fn parse_event_id(input: &str) -> Result<EventId, Box<dyn std::error::Error>> {
+    let input = input.trim().trim_start_matches("nostr:");
+    if let Ok(id) = EventId::from_hex(input) {
+        return Ok(id);
+    }
+    if let Ok(id) = EventId::from_bech32(input) {
+        return Ok(id);
+    }
+    if let Ok(nevent) = Nip19Event::from_bech32(input) {
+        return Ok(nevent.event_id);
+    }
+    Err(format!("イベントIDを解釈できません: {}", input).into())
+}
+
+// IDを指定してリレーからイベントを1件取得する関数
+pub async fn fetch_event_by_id(client: &Client, id: EventId) -> Result<Event, Box<dyn std::error::Error>> {
+    let events = client
+        .get_events_of(vec![Filter::new().id(id)], Some(std::time::Duration::from_secs(10)))
+        .await?;
+    events
+        .into_iter()
+        .find(|e| e.id == id)
+        .ok_or_else(|| format!("イベントが見つかりません: {}", id).into())
+}
+
+// リアクション（NIP-25 の kind 7）を作成する（e タグに投稿、p タグに投稿者、k タグに投稿の kind）
+fn reaction_builder(target: &Event, reaction: &str) -> Result<EventBuilder, Box<dyn std::error::Error>> {
+    let tags = vec![
+        Tag::parse(vec!["e".to_string(), target.id.to_hex()])?,
+        Tag::parse(vec!["p".to_string(), target.pubkey.to_string()])?,
+        Tag::parse(vec!["k".to_string(), target.kind.as_u64().to_string()])?,
+    ];
+    Ok(EventBuilder::new(Kind::Reaction, reaction, tags))
+}
+
+// 投稿にリアクションを送る関数（絵文字を省略すると "+"、つまり「いいね」）
+pub async fn react(matches: &ArgMatches) -> Result<(), Box<dyn std::error::Error>> {
+    let event_id = parse_event_id(matches.get_one::<String>("event-id").ok_or("イベントIDが指定されていません")?)?;
+    let reaction = matches.get_one::<String>("emoji").map(|emoji| emoji.trim()).unwrap_or("+");
+    if reaction.is_empty() || reaction.chars().any(char::is_whitespace) {
+        return Err("リアクションには絵文字1つ、または + / - を指定してください".into());
+    }
+    if reaction.len() > 2 && reaction.starts_with(':') && reaction.ends_with(':') {
+        return Err("カスタム絵文字（:shortcode:）には対応していません".into());
+    }
+
+    let keys = unlock_keys()?;
+    let client = connect_client(&keys).await?;
+    // 投稿者と kind をタグに入れるため、先に投稿を取得する
+    let published = async {
+        let target = fetch_event_by_id(&client, event_id).await?;
+        let event = sign_event(reaction_builder(&target, reaction)?, &keys)?;
+        publish(&client, event).await
+    }
+    .await;
+    client.shutdown().await?;
+    eprintln!("{} に「{}」でリアクションしました（{}）", event_id.to_bech32()?, reaction, published?.summary());
+    Ok(())
+}
+
+// リポスト（NIP-18）を作成する（テキストノートは kind 6、それ以外は k タグ付きの kind 16。本文に元のイベントの JSON を埋め込む）
+fn repost_builder(target: &Event, relay: &str) -> Result<EventBuilder, Box<dyn std::error::Error>> {
+    let mut tags = vec![
+        Tag::parse(vec!["e".to_string(), target.id.to_hex(), relay.to_string()])?,
+        Tag::parse(vec!["p".to_string(), target.pubkey.to_string()])?,
+    ];
+    let kind = if target.kind == Kind::TextNote {
+        KIND_REPOST
+    } else {
+        tags.push(Tag::parse(vec!["k".to_string(), target.kind.as_u64().to_string()])?);
+        KIND_GENERIC_REPOST
+    };
+    Ok(EventBuilder::new(Kind::from(kind), target.as_json(), tags))
+}
+
+// 引用投稿（kind 1）を作成する（本文の末尾に nostr:nevent を置き、q タグで引用元を示す）
+fn quote_builder(target: &Event, relays: &[String], comment: &str) -> Result<EventBuilder, Box<dyn std::error::Error>> {
+    let nevent = build_permalink(target.id, relays, PermalinkFormat::Nevent)?;
+    let relay = relays.first().cloned().unwrap_or_default();
+    let tags = vec![
+        Tag::parse(vec!["q".to_string(), target.id.to_hex(), relay, target.pubkey.to_string()])?,
+        Tag::parse(vec!["p".to_string(), target.pubkey.to_string()])?,
+    ];
+    Ok(EventBuilder::new(Kind::TextNote, format!("{}\n\nnostr:{}", comment, nevent), tags))
+}
+
+// 投稿をリポストする関数（--quote を付けると、コメント付きの引用投稿にする）
+// リレーのヒントは nevent に含まれていればそれを、なければ投稿を持っている接続中のリレーを使う
+pub async fn repost(matches: &ArgMatches) -> Result<(), Box<dyn std::error::Error>> {
+    let input = matches.get_one::<String>("event-id").ok_or("イベントIDが指定されていません")?;
+    let event_id = parse_event_id(input)?;
+    let comment = matches.get_one::<String>("quote").map(|text| text.trim());
+    if comment.is_some_and(str::is_empty) {
+        return Err("引用のコメントが空です".into());
+    }
+    let hints: Vec<String> = Nip19Event::from_bech32(input.trim_start_matches("nostr:"))
+        .map(|nevent| nevent.relays)
+        .unwrap_or_default();
+
+    let keys = unlock_keys()?;
+    let client = connect_client(&keys).await?;
+    // 元のイベントを埋め込む・引用元を示すため、先に投稿を取得する
+    let published = async {
+        let target = fetch_event_by_id(&client, event_id).await?;
+        let relays = if hints.is_empty() { find_event_relays(&client, event_id).await } else { hints };
+        let relays: Vec<String> = relays.into_iter().take(MAX_RELAY_HINTS).collect();
+        let builder = match comment {
+            Some(comment) => quote_builder(&target, &relays, comment)?,
+            None => repost_builder(&target, relays.first().map(|r| r.as_str()).unwrap_or_default())?,
+        };
+        publish(&client, sign_event(builder, &keys)?).await
+    }
+    .await;
+    client.shutdown().await?;
+    let action = if comment.is_some() { "引用しました" } else { "リポストしました" };
+    eprintln!("{} を{}（{}）", event_id.to_bech32()?, action, published?.summary());
+    Ok(())
+}
+
+// Zap（NIP-57）を送る関数（npub なら相手に、note / nevent ならそのノートに。インボイスを表示し、zap_pay_command があれば支払う）
+pub async fn zap(matches: &ArgMatches) -> Result<(), Box<dyn std::error::Error>> {
+    let input = matches.get_one::<String>("target").ok_or("Zap する相手が指定されていません")?;
+    let amount = *matches.get_one::<u64>("amount").ok_or("金額が指定されていません")?;
+    let comment = matches.get_one::<String>("comment").map(|text| text.trim()).unwrap_or_default();
+    let amount_msats = amount.checked_mul(1000).filter(|msats| *msats > 0).ok_or("金額（sats）が正しくありません")?;
+    // note / nevent ならノートへの Zap、それ以外は公開鍵（npub / hex / 別名）への Zap
+    let bare = input.trim().trim_start_matches("nostr:");
+    let event_id = if bare.starts_with("note1") || bare.starts_with("nevent1") { Some(parse_event_id(bare)?) } else { None };
+    let pubkey = match event_id {
+        Some(_) => None,
+        None => Some(resolve_pubkey(input)?),
+    };
+
+    let config = AppConfig::load()?;
+    let keys = unlock_keys()?;
+    let client = connect_read_client(&keys).await?;
+    // ノートの投稿者と、支払い先の書かれたプロフィールを取得する
+    let fetched = async {
+        let target = match event_id {
+            Some(id) => Some(fetch_event_by_id(&client, id).await?),
+            None => None,
+        };
+        let recipient = target.as_ref().map(|event| event.pubkey).or(pubkey).ok_or("Zap する相手が分かりません")?;
+        let profile = fetch_metadata_event(&client, recipient)
+            .await?
+            .ok_or_else(|| format!("相手のプロフィールが見つかりません: {}", recipient.to_bech32().unwrap_or_default()))?;
+        Ok::<_, Box<dyn std::error::Error>>((target, recipient, Metadata::from_json(&profile.content)?))
+    }
+    .await;
+    client.shutdown().await?;
+    let (target, recipient, metadata) = fetched?;
+
+    let url = lnurl_pay_url(&metadata)?;
+    let lnurl = encode_lnurl(&url)?;
+    let endpoint = fetch_pay_endpoint(&url).await?;
+    if amount_msats < endpoint.min_sendable || amount_msats > endpoint.max_sendable {
+        return Err(format!(
+            "この相手には {}〜{} sats の範囲で送れます",
+            endpoint.min_sendable.div_ceil(1000),
+            endpoint.max_sendable / 1000
+        )
+        .into());
+    }
+    // Zap リクエストは署名してコールバックに渡すだけで、リレーには送らない（レシートを公開するリレーを relays タグで伝える）
+    let zap_request = if endpoint.allows_nostr {
+        let relays = configured_relays()?;
+        let builder = zap_request_builder(&recipient, target.as_ref(), amount_msats, &lnurl, &relays, comment)?;
+        Some(sign_event(builder, &keys)?)
+    } else {
+        eprintln!("相手のウォレットは Zap に対応していません。Zap レシートの出ない普通の支払いになります");
+        None
+    };
+    let invoice = fetch_invoice(&endpoint, amount_msats, zap_request.as_ref(), &lnurl).await?;
+    // コールバックが指定と違う金額のインボイスを返していないか確かめる（NIP-57）
+    let invoice_msats = bolt11_amount_msat(&invoice).ok_or("インボイスの金額を読み取れません")?;
+    if invoice_msats != amount_msats {
+        return Err(format!(
+            "インボイスの金額（{} sats）が指定した金額（{} sats）と違うため、支払いません",
+            invoice_msats as f64 / 1000.0,
+            amount
+        )
+        .into());
+    }
+
+    let name = metadata.lud16.as_deref().unwrap_or(&url);
+    eprintln!("{} に {} sats の Zap のインボイスを発行しました:", name, amount);
+    println!("{}", invoice);
+    print_qr(&invoice.to_uppercase())?;
+
+    let Some(command) = config.zap_pay_command.as_deref().filter(|c| !c.trim().is_empty()) else {
+        return Ok(());
+    };
+    if !confirm(&format!("インボイスの金額 {} sats を支払いますか？（{}）", invoice_msats / 1000, command))? {
+        return Ok(());
+    }
+    // インボイスは $1 で渡す
+    let status = std::process::Command::new("sh").arg("-c").arg(command).arg("zap").arg(&invoice).status()?;
+    if !status.success() {
+        return Err(format!("支払いのコマンドが失敗しました（{}）", status).into());
+    }
+    eprintln!("支払いました");
+    Ok(())
+}
+
+// 投稿に返信する関数（NIP-10 のマーカー付きの e タグでスレッドの root と返信先を示し、スレッドの参加者に p タグで通知する）
+pub async fn reply(matches: &ArgMatches) -> Result<(), Box<dyn std::error::Error>> {
+    let input = matches.get_one::<String>("event-id").ok_or("イベントIDが指定されていません")?;
+    let event_id = parse_event_id(input)?;
+    let content = matches.get_one::<String>("content").map(|text| text.trim()).unwrap_or_default();
+    if content.is_empty() {
+        return Err("本文が空です".into());
+    }
+    let hints: Vec<String> = Nip19Event::from_bech32(input.trim_start_matches("nostr:"))
+        .map(|nevent| nevent.relays)
+        .unwrap_or_default();
+
+    let config = AppConfig::load()?;
+    let mut draft = NoteDraft { content: content.to_string(), auto_hashtags: config.auto_hashtags, ..Default::default() };
+    for name in draft.apply_mentions(&config.aliases) {
+        eprintln!("別名が登録されていないため、そのまま送信します: @{}", name);
+    }
+
+    let keys = unlock_keys()?;
+    if !confirm_clock_skew().await? {
+        return Ok(());
+    }
+    let client = connect_client(&keys).await?;
+    // スレッドの root と参加者を知るため、先に返信先を取得する
+    let published = async {
+        let parent = fetch_event_by_id(&client, event_id).await?;
+        let relay = match hints.first() {
+            Some(relay) => relay.clone(),
+            None => find_event_relays(&client, event_id).await.into_iter().next().unwrap_or_default(),
+        };
+        for pubkey in thread_pubkeys(&parent) {
+            if pubkey != keys.public_key() && !draft.mentions.contains(&pubkey) {
+                draft.mentions.push(pubkey);
+            }
+        }
+        let mut tags = reply_tags(&parent, &relay)?;
+        tags.extend(draft.tags()?);
+
+        let preflight = preflight_note(&client, &draft.content, tags.len()).await?;
+        if !report_preflight(&preflight)? {
+            return Ok(None);
+        }
+        let event = sign_checked(EventBuilder::new_text_note(&draft.content, tags), &keys, &preflight)?;
+        publish(&client, event).await.map(Some)
+    }
+    .await;
+    client.shutdown().await?;
+    if let Some(receipt) = published? {
+        eprintln!("{} に返信しました（{}）", event_id.to_bech32()?, receipt.summary());
+    }
+    Ok(())
+}
+
+// ノートをスクラップブックに保存する関数
+pub async fn clip(matches: &ArgMatches) -> Result<(), Box<dyn std::error::Error>> {
+    let id_str = matches.get_one::<String>("event-id").ok_or("イベントIDが指定されていません")?;
+    let event_id = parse_event_id(id_str)?;
+    let note = matches.get_one::<String>("note").cloned().unwrap_or_default();
+    let tags: Vec<String> = matches
+        .get_many::<String>("tag")
+        .map(|values| values.map(|t| t.trim_start_matches('#').to_string()).collect())
+        .unwrap_or_default();
+
+    // 取得するだけなので一時的な鍵で接続する
+    let client = connect_read_client(&Keys::generate()).await?;
+    eprintln!("イベントを取得中...");
+    let event = fetch_event_by_id(&client, event_id).await;
+    client.shutdown().await?;
+    let event = event?;
+
+    let mut scrapbook = Scrapbook::load()?;
+    scrapbook.add(ScrapbookEntry {
+        event,
+        note,
+        tags,
+        clipped_at: Timestamp::now().as_u64(),
+    });
+    scrapbook.save()?;
+
+    eprintln!("スクラップブックに保存しました ({}件)", scrapbook.entries.len());
+    Ok(())
+}
+
+// スクラップブックの一覧表示・検索を行う関数
+pub fn show_scrapbook(matches: &ArgMatches) -> Result<(), Box<dyn std::error::Error>> {
+    let scrapbook = Scrapbook::load()?;
+    let query = matches
+        .get_many::<String>("query")
+        .map(|values| values.cloned().collect::<Vec<_>>().join(" "))
+        .unwrap_or_default();
+
+    let entries = scrapbook.search(&query);
+    eprintln!("{}件の項目", entries.len());
+
+    for entry in entries {
+        println!("-----------------------------------");
+        println!("ID: {}", entry.event.id.to_bech32()?);
+        println!("アカウント: {}", entry.event.pubkey.to_bech32()?);
+        println!("時間: {}", format_jst(entry.event.created_at));
+        if !entry.tags.is_empty() {
+            println!("タグ: {}", entry.tags.iter().map(|t| format!("#{}", t)).collect::<Vec<_>>().join(" "));
+        }
+        if !entry.note.is_empty() {
+            println!("メモ: {}", entry.note);
+        }
+        println!("内容: {}", entry.event.content);
+    }
+
+    Ok(())
+}
+
+// スレッドをミュートする関数（指定イベントが属するスレッドのルートをミュートする）
+pub async fn mute_thread(matches: &ArgMatches) -> Result<(), Box<dyn std::error::Error>> {
+    let id_str = matches.get_one::<String>("event-id").ok_or("イベントIDが指定されていません")?;
+    let event_id = parse_event_id(id_str)?;
+
+    // スレッドのルートを求めるためにイベントを取得する（取得できなければ指定IDをルートとみなす）
+    let client = connect_read_client(&Keys::generate()).await?;
+    let root = match fetch_event_by_id(&client, event_id).await {
+        Ok(event) => thread_root(&event),
+        Err(_) => {
+            eprintln!("イベントを取得できなかったため、指定IDをスレッドのルートとしてミュートします");
+            event_id
+        }
+    };
+    client.shutdown().await?;
+
+    let mut mutes = MuteList::load()?;
+    if mutes.mute_thread(root) {
+        mutes.save()?;
+        eprintln!("スレッド {} をミュートしました", root.to_bech32()?);
+    } else {
+        eprintln!("スレッド {} は既にミュートされています", root.to_bech32()?);
+    }
+    Ok(())
+}
+
+// スレッドのミュートを解除する関数
+pub fn unmute_thread(matches: &ArgMatches) -> Result<(), Box<dyn std::error::Error>> {
+    let id_str = matches.get_one::<String>("event-id").ok_or("イベントIDが指定されていません")?;
+    let root = parse_event_id(id_str)?;
+
+    let mut mutes = MuteList::load()?;
+    if mutes.unmute_thread(root) {
+        mutes.save()?;
+        eprintln!("スレッド {} のミュートを解除しました", root.to_bech32()?);
+    } else {
+        eprintln!("スレッド {} はミュートされていません", root.to_bech32()?);
+    }
+    Ok(())
+}
+
+// ミュート中のスレッドを一覧表示する関数
+pub fn list_muted_threads(_matches: &ArgMatches) -> Result<(), Box<dyn std::error::Error>> {
+    let mutes = MuteList::load()?;
+    if mutes.threads.is_empty() {
+        eprintln!("ミュート中のスレッドはありません");
+    }
+    for hex in &mutes.threads {
+        let id = EventId::from_hex(hex)?;
+        println!("{}", id.to_bech32()?);
+    }
+    Ok(())
+}
+
+// 自分の投稿への返信を検査し、スパムパターンに一致したものを処理する関数
+pub async fn moderation_scan(matches: &ArgMatches) -> Result<(), Box<dyn std::error::Error>> {
+    let config = AppConfig::load()?;
+    if config.spam_patterns.is_empty() {
+        return Err("spam_patterns が設定されていません (例: config set spam_patterns '[\"airdrop\"]')".into());
+    }
+    let patterns = compile_patterns(&config.spam_patterns)?;
+    let since = parse_duration(matches.get_one::<String>("since").map(|s| s.as_str()).unwrap_or("7d"))?;
+    let since = Timestamp::from(Timestamp::now().as_u64().saturating_sub(since));
+
+    let keys = unlock_keys()?;
+    let client = connect_client(&keys).await?;
+
+    // 期間内の自分の投稿とそれへの返信を取得
+    eprintln!("自分の投稿への返信を取得中...");
+    let my_notes = client
+        .get_events_of(
+            vec![Filter::new().kind(Kind::TextNote).author(keys.public_key()).since(since)],
+            Some(std::time::Duration::from_secs(10)),
+        )
+        .await?;
+    if my_notes.is_empty() {
+        eprintln!("期間内に自分の投稿がありません");
+        client.shutdown().await?;
+        return Ok(());
+    }
+    let replies = client
+        .get_events_of(
+            vec![Filter::new().kind(Kind::TextNote).events(my_notes.iter().map(|e| e.id).collect::<Vec<_>>())],
+            Some(std::time::Duration::from_secs(10)),
+        )
+        .await?;
+
+    let mut queue = ModerationQueue::load()?;
+    let mut mutes = MuteList::load()?;
+    let mut found = 0;
+
+    for reply in replies {
+        if reply.pubkey == keys.public_key() || queue.contains(reply.id) || mutes.is_author_muted(&reply) {
+            continue;
+        }
+        let Some(pattern) = match_spam(&reply.content, &patterns) else {
+            continue;
+        };
+        found += 1;
+        let pattern = pattern.as_str().to_string();
+        let author = reply.pubkey.to_bech32()?;
+
+        if config.auto_moderation {
+            // 自動モード: その場で通報してミュートする
+            let report = sign_event(report_builder(&reply)?, &keys)?;
+            let report_id = report.id;
+            publish(&client, report).await?;
+            mutes.mute_author(reply.pubkey);
+            let id = queue.push(reply, pattern, ModerationStatus::Applied);
+            if let Some(action) = queue.get_mut(id) {
+                action.report_id = Some(report_id.to_hex());
+            }
+            eprintln!("[{}] {} を通報・ミュートしました", id, author);
+        } else {
+            let id = queue.push(reply, pattern, ModerationStatus::Pending);
+            eprintln!("[{}] {} の返信を審査待ちに追加しました", id, author);
+        }
+    }
+
+    queue.save()?;
+    mutes.save()?;
+    client.shutdown().await?;
+
+    eprintln!("{}件の返信がスパムパターンに一致しました", found);
+    Ok(())
+}
+
+// 審査キューを表示する関数
+pub fn moderation_queue(_matches: &ArgMatches) -> Result<(), Box<dyn std::error::Error>> {
+    let queue = ModerationQueue::load()?;
+    if queue.actions.is_empty() {
+        eprintln!("審査キューは空です");
+    }
+    for action in &queue.actions {
+        let status = match action.status {
+            ModerationStatus::Pending => "審査待ち",
+            ModerationStatus::Applied => "通報・ミュート済み",
+            ModerationStatus::Undone => "取り消し済み",
+        };
+        println!("-----------------------------------");
+        println!("[{}] {}", action.id, status);
+        println!("アカウント: {}", action.reply.pubkey.to_bech32()?);
+        println!("パターン: {}", action.pattern);
+        println!("内容: {}", action.reply.content);
+    }
+    Ok(())
+}
+
+// 審査待ちの項目を承認し、通報・ミュートを実行する関数
+pub async fn moderation_approve(matches: &ArgMatches) -> Result<(), Box<dyn std::error::Error>> {
+    let id = *matches.get_one::<u64>("id").ok_or("番号が指定されていません")?;
+    let mut queue = ModerationQueue::load()?;
+    let action = queue.get_mut(id).ok_or_else(|| format!("番号 {} の項目がありません", id))?;
+    if action.status != ModerationStatus::Pending {
+        return Err(format!("番号 {} は審査待ちではありません", id).into());
+    }
+
+    let keys = unlock_keys()?;
+    let client = connect_client(&keys).await?;
+    let report = sign_event(report_builder(&action.reply)?, &keys)?;
+    action.report_id = Some(report.id.to_hex());
+    publish(&client, report).await?;
+    client.shutdown().await?;
+
+    let mut mutes = MuteList::load()?;
+    mutes.mute_author(action.reply.pubkey);
+    mutes.save()?;
+
+    action.status = ModerationStatus::Applied;
+    queue.save()?;
+
+    eprintln!("[{}] 通報・ミュートしました", id);
+    Ok(())
+}
+
+// 実行済みの通報・ミュートを取り消す関数（審査待ちの項目は却下扱いにする）
+pub async fn moderation_undo(matches: &ArgMatches) -> Result<(), Box<dyn std::error::Error>> {
+    let id = *matches.get_one::<u64>("id").ok_or("番号が指定されていません")?;
+    let mut queue = ModerationQueue::load()?;
+    let action = queue.get_mut(id).ok_or_else(|| format!("番号 {} の項目がありません", id))?;
+
+    match action.status {
+        ModerationStatus::Undone => {
+            return Err(format!("番号 {} は既に取り消されています", id).into());
+        }
+        ModerationStatus::Pending => {
+            eprintln!("[{}] 審査待ちの項目を却下しました", id);
+        }
+        ModerationStatus::Applied => {
+            // 通報イベントの削除要求を送り、ミュートを解除する
+            if let Some(report_id) = &action.report_id {
+                let keys = unlock_keys()?;
+                let client = connect_client(&keys).await?;
+                let deletion = sign_event(retract_builder(EventId::from_hex(report_id)?)?, &keys)?;
+                publish(&client, deletion).await?;
+                client.shutdown().await?;
+            }
+
+            let mut mutes = MuteList::load()?;
+            mutes.unmute_author(action.reply.pubkey);
+            mutes.save()?;
+            eprintln!("[{}] 通報を取り消し、ミュートを解除しました", id);
+        }
+    }
+
+    action.status = ModerationStatus::Undone;
+    queue.save()?;
+    Ok(())
+}
+
+// 各リレーに保存されているコンタクトリストを比較し、遅れているリレーに最新版を再送する関数
+pub async fn following_audit(matches: &ArgMatches) -> Result<(), Box<dyn std::error::Error>> {
+    let keys = unlock_keys()?;
+    let my_pubkey = keys.public_key();
+    let filter = Filter::new().kind(Kind::ContactList).author(my_pubkey).limit(1);
+
+    // リレーごとに最新のコンタクトリストを取得
+    let mut versions: Vec<(String, Client, Option<Event>)> = Vec::new();
+    for url in configured_relays()? {
+        eprintln!("{} から取得中...", url);
+        let client = match connect_single_relay(&keys, &url).await {
+            Ok(client) => client,
+            Err(e) => {
+                eprintln!("  接続エラー: {}", e);
+                continue;
+            }
+        };
+        let latest = client
+            .get_events_of(vec![filter.clone()], Some(std::time::Duration::from_secs(10)))
+            .await
+            .unwrap_or_default()
+            .into_iter()
+            .max_by_key(|e| e.created_at);
+        versions.push((url, client, latest));
+    }
+
+    // 最も新しいものを正とする
+    let canonical = versions
+        .iter()
+        .filter_map(|(_, _, event)| event.clone())
+        .max_by_key(|e| e.created_at)
+        .ok_or("どのリレーにもコンタクトリストがありません")?;
+
+    println!(
+        "基準: {} ({}件のフォロー, {})",
+        canonical.id.to_bech32()?,
+        crate::contacts::followed_set(&canonical).len(),
+        format_jst(canonical.created_at)
+    );
+
+    let mut lagging = Vec::new();
+    for (url, client, event) in &versions {
+        println!("-----------------------------------");
+        match event {
+            None => {
+                println!("{}: コンタクトリストがありません", url);
+                lagging.push((url.clone(), client));
+            }
+            Some(event) if event.id == canonical.id => {
+                println!("{}: 最新です", url);
+            }
+            Some(event) => {
+                let (missing, extra) = crate::contacts::diff_contacts(&canonical, event);
+                println!("{}: 古い版です ({})", url, format_jst(event.created_at));
+                for pubkey in &missing {
+                    println!("  - 不足: {}", crate::contacts::display_pubkey(pubkey));
+                }
+                for pubkey in &extra {
+                    println!("  + 余分: {}", crate::contacts::display_pubkey(pubkey));
+                }
+                lagging.push((url.clone(), client));
+            }
+        }
+    }
+
+    if !lagging.is_empty() {
+        let republish = matches.get_flag("yes")
+            || confirm(&format!("{}件のリレーに最新のコンタクトリストを再送しますか？", lagging.len()))?;
+        if republish {
+            // 署名済みのイベントをそのまま送るので再署名は不要
+            for (url, client) in &lagging {
+                match client.send_event(canonical.clone()).await {
+                    Ok(_) => eprintln!("{} に再送しました", url),
+                    Err(e) => eprintln!("{} への再送に失敗: {}", url, e),
+                }
+            }
+        }
+    }
+
+    for (_, client, _) in versions {
+        let _ = client.shutdown().await;
+    }
+
+    Ok(())
+}
+
+// 自分のコンタクトリストを取得し、書き換えて送信する関数（書き換えがなければ送らない）
+// 元のリストが見つからないまま送ると、ほかのクライアントでのフォローがすべて消えるので確かめる
+async fn update_contact_list(
+    edit: impl FnOnce(&mut Vec<ContactEntry>) -> Result<bool, Box<dyn std::error::Error>>,
+) -> Result<Option<Event>, Box<dyn std::error::Error>> {
+    let keys = unlock_keys()?;
+    let client = connect_client(&keys).await?;
+    let current = fetch_contact_list(&client, keys.public_key()).await?;
+    if current.is_none() && !confirm("コンタクトリストがリレーに見つかりません。新しく作成しますか？")? {
+        client.shutdown().await?;
+        return Ok(None);
+    }
+
+    let mut contacts = current.as_ref().map(parse_contacts).unwrap_or_default();
+    let changed = match edit(&mut contacts) {
+        Ok(changed) => changed,
+        Err(e) => {
+            client.shutdown().await?;
+            return Err(e);
+        }
+    };
+    if !changed {
+        client.shutdown().await?;
+        return Ok(None);
+    }
+
+    let event = sign_event(contact_list_builder(current.as_ref(), &contacts)?, &keys)?;
+    let result = publish(&client, event.clone()).await;
+    client.shutdown().await?;
+    let receipt = result?;
+    eprintln!("コンタクトリストを送信しました（{}人をフォロー中、{}）", contacts.len(), receipt.summary());
+
+    let mut profiles = ProfileCache::load()?;
+    profiles.set_following(&event);
+    profiles.save()?;
+    Ok(Some(event))
+}
+
+// フォローする関数（--relay と --petname でヒントを付ける。フォロー中なら、指定したヒントだけ書き換える）
+pub async fn follow(matches: &ArgMatches) -> Result<(), Box<dyn std::error::Error>> {
+    let target = resolve_pubkey(matches.get_one::<String>("npub").ok_or("フォローする相手が指定されていません")?)?;
+    let relay = matches.get_one::<String>("relay").cloned();
+    if let Some(url) = &relay {
+        check_relay_url(url)?;
+    }
+    let petname = matches.get_one::<String>("petname").map(|name| name.trim().to_string()).filter(|name| !name.is_empty());
+    let npub = target.to_bech32()?;
+
+    let sent = update_contact_list(|contacts| {
+        match contacts.iter_mut().find(|c| c.pubkey == target.to_string()) {
+            Some(_) if relay.is_none() && petname.is_none() => {
+                eprintln!("{} はすでにフォローしています", npub);
+                Ok(false)
+            }
+            Some(existing) => {
+                existing.relay = relay.or(existing.relay.take());
+                existing.petname = petname.or(existing.petname.take());
+                Ok(true)
+            }
+            None => {
+                contacts.push(ContactEntry { pubkey: target.to_string(), relay, petname });
+                Ok(true)
+            }
+        }
+    })
+    .await?;
+    if sent.is_some() {
+        println!("{} をフォローしました", npub);
+    }
+    Ok(())
+}
+
+// ほかのクライアントが書き出したフォローを取り込む関数（増えるフォローと埋まるヒントを表示し、確かめてから送る）
+pub async fn import_follows(matches: &ArgMatches) -> Result<(), Box<dyn std::error::Error>> {
+    let format = ImportFormat::parse(matches.get_one::<String>("from").ok_or("形式が指定されていません")?)?;
+    let path = matches.get_one::<String>("file").ok_or("ファイルが指定されていません")?;
+    let text = fs::read_to_string(path).map_err(|e| format!("{} を読み込めません: {}", path, e))?;
+    let (imported, skipped) = parse_follows(format, &text)?;
+    if skipped > 0 {
+        eprintln!("公開鍵を読み取れなかった {} 件を飛ばしました", skipped);
+    }
+    if imported.is_empty() {
+        return Err(format!("{} にフォローが見つかりません", path).into());
+    }
+    let count = imported.len();
+
+    let sent = update_contact_list(|contacts| {
+        let merge = merge_follows(contacts, imported);
+        if merge.is_empty() {
+            eprintln!("{} 人はすべてフォロー済みです", count);
+            return Ok(false);
+        }
+        merge.print_preview();
+        let question = format!("{} 人を新しくフォローし、{} 人のヒントを追加します。送信しますか？", merge.added.len(), merge.updated.len());
+        confirm(&question)
+    })
+    .await?;
+    if sent.is_some() {
+        println!("{} からフォローを取り込みました", path);
+    }
+    Ok(())
+}
+
+// フォローを外す関数（ほかのフォローのリレーのヒントとペットネームはそのまま残す）
+pub async fn unfollow(matches: &ArgMatches) -> Result<(), Box<dyn std::error::Error>> {
+    let target = resolve_pubkey(matches.get_one::<String>("npub").ok_or("フォローを外す相手が指定されていません")?)?;
+    let npub = target.to_bech32()?;
+
+    let sent = update_contact_list(|contacts| {
+        let before = contacts.len();
+        contacts.retain(|c| c.pubkey != target.to_string());
+        if contacts.len() == before {
+            return Err(format!("{} はフォローしていません", npub).into());
+        }
+        Ok(true)
+    })
+    .await?;
+    if sent.is_some() {
+        println!("{} のフォローを外しました", target.to_bech32()?);
+    }
+    Ok(())
+}
+
+// フォロー中の一覧を表示する関数（ペットネーム、保存済みのプロフィールの名前、リレーのヒントを添える）
+pub async fn following_list(matches: &ArgMatches) -> Result<(), Box<dyn std::error::Error>> {
+    let keys = read_keys()?;
+    let client = connect_read_client(&keys).await?;
+    let contact_list = fetch_contact_list(&client, keys.public_key()).await?;
+    client.shutdown().await?;
+    let Some(contact_list) = contact_list else {
+        println!("コンタクトリストが見つかりません（まだ誰もフォローしていません）");
+        return Ok(());
+    };
+
+    let contacts = parse_contacts(&contact_list);
+    let mut profiles = ProfileCache::load()?;
+    profiles.set_following(&contact_list);
+    if let Err(e) = profiles.save() {
+        eprintln!("フォロー中の一覧を保存できません: {}", e);
+    }
+
+    if matches.get_flag("json") {
+        for contact in &contacts {
+            println!("{}", serde_json::json!({ "pubkey": contact.pubkey, "relay": contact.relay, "petname": contact.petname }));
+        }
+        return Ok(());
+    }
+    for contact in &contacts {
+        let mut line = display_pubkey(&contact.pubkey);
+        let name = XOnlyPublicKey::from_str(&contact.pubkey)
+            .ok()
+            .and_then(|pubkey| profiles.get(&pubkey).and_then(|p| p.label()).map(str::to_string));
+        if let Some(label) = contact.petname.clone().or(name) {
+            line.push_str(&format!(" {}", label));
+        }
+        if let Some(relay) = &contact.relay {
+            line.push_str(&format!(" ({})", relay));
+        }
+        println!("{}", line);
+    }
+    eprintln!("{}人をフォロー中（{} 更新）", contacts.len(), format_jst(contact_list.created_at));
+    Ok(())
+}
+
+// 投稿への反応を時間帯ごとに集計して表示する関数
+pub async fn note_stats(matches: &ArgMatches) -> Result<(), Box<dyn std::error::Error>> {
+    let id_str = matches.get_one::<String>("event-id").ok_or("イベントIDが指定されていません")?;
+    let event_id = parse_event_id(id_str)?;
+
+    // 取得するだけなので一時的な鍵で接続する
+    let client = connect_read_client(&Keys::generate()).await?;
+    eprintln!("投稿と反応を取得中...");
+    let target = fetch_event_by_id(&client, event_id).await;
+    let interactions = client
+        .get_events_of(
+            vec![crate::analytics::interaction_filter(event_id)],
+            Some(std::time::Duration::from_secs(10)),
+        )
+        .await;
+    client.shutdown().await?;
+    let target = target?;
+    let interactions = interactions?;
+
+    let bucket_secs = match matches.get_one::<String>("bucket") {
+        Some(bucket) => parse_duration(bucket)?,
+        None => crate::analytics::auto_bucket_secs(target.created_at, Timestamp::now()),
+    };
+    let stats = crate::analytics::aggregate(event_id, target.created_at, &interactions, bucket_secs);
+
+    if matches.get_flag("json") {
+        println!("{}", serde_json::to_string_pretty(&stats)?);
+        return Ok(());
+    }
+
+    println!("{:<26} {:>6} {:>8} {:>12} {:>6} {:>10}", "時間帯", "返信", "リポスト", "リアクション", "Zap", "sats");
+    for bucket in &stats.buckets {
+        println!(
+            "{:<26} {:>6} {:>8} {:>12} {:>6} {:>10}",
+            format_jst(Timestamp::from(bucket.start)),
+            bucket.replies,
+            bucket.reposts,
+            bucket.reactions,
+            bucket.zaps,
+            bucket.zap_sats
+        );
+    }
+    println!(
+        "{:<26} {:>6} {:>8} {:>12} {:>6} {:>10}",
+        "合計", stats.replies, stats.reposts, stats.reactions, stats.zaps, stats.zap_sats
+    );
+    println!("反応したユニークな公開鍵: {}件", stats.unique_pubkeys);
+
+    Ok(())
+}
+
+// 設定をすべて表示する関数
+pub fn show_config(_matches: &ArgMatches) -> Result<(), Box<dyn std::error::Error>> {
+    let config = AppConfig::load()?;
+    println!("{}", serde_json::to_string_pretty(&config)?);
+    Ok(())
+}
+
+// 設定値を1つ表示する関数
+pub fn get_config(matches: &ArgMatches) -> Result<(), Box<dyn std::error::Error>> {
+    let key = matches.get_one::<String>("key").ok_or("キーが指定されていません")?;
+    let value = serde_json::to_value(AppConfig::load()?)?;
+    let item = value.get(key).ok_or_else(|| format!("不明な設定項目です: {}", key))?;
+    println!("{}", item);
+    Ok(())
+}
+
+// 設定値を変更する関数
+// 値はJSONとして解釈し、解釈できなければ文字列として扱う（例: config set feed_kinds = [1,6]）
+pub fn set_config(matches: &ArgMatches) -> Result<(), Box<dyn std::error::Error>> {
+    let key = matches.get_one::<String>("key").ok_or("キーが指定されていません")?;
+    let raw: Vec<&String> = matches
+        .get_many::<String>("value")
+        .ok_or("値が指定されていません")?
+        .collect();
+
+    // "key = value" の形式も受け付ける
+    let parts: Vec<&str> = raw.iter().map(|s| s.as_str()).skip_while(|s| *s == "=").collect();
+    let raw_value = parts.join(" ");
+    if raw_value.is_empty() {
+        return Err("値が指定されていません".into());
+    }
+
+    let mut value = serde_json::to_value(AppConfig::load()?)?;
+    let object = value.as_object_mut().ok_or("設定の形式が正しくありません")?;
+    if !object.contains_key(key) {
+        return Err(format!("不明な設定項目です: {}", key).into());
+    }
+
+    let parsed = serde_json::from_str::<serde_json::Value>(&raw_value)
+        .unwrap_or_else(|_| serde_json::Value::String(raw_value.clone()));
+    object.insert(key.clone(), parsed);
+
+    // 型が合っているかを構造体への変換で検証してから保存する
+    let config: AppConfig = serde_json::from_value(value)
+        .map_err(|e| format!("{} の値が正しくありません: {}", key, e))?;
+    config.save()?;
+
+    eprintln!("{} を更新しました", key);
+    Ok(())
+}
+
+// 保持期間の調査で遡る範囲（日数）
+const RETENTION_PROBE_DAYS: [u64; 7] = [1, 7, 30, 90, 365, 730, 1825];
+// 試験用イベントの識別子（NIP-78 のアプリデータとして送り、一般のフィードを汚さない）
+const RETENTION_MARKER_D_TAG: &str = "nostr-cli-app/retention-probe";
+
+// リレーの履歴保持期間を実測し、結果を capabilities.json に記録する関数
+pub async fn probe_retention(matches: &ArgMatches) -> Result<(), Box<dyn std::error::Error>> {
+    let url = matches.get_one::<String>("url").ok_or("URLが指定されていません")?;
+    let wait = *matches.get_one::<u64>("wait").unwrap_or(&10);
+    let timeout = Some(std::time::Duration::from_secs(10));
+
+    // 使い捨ての鍵で試験用イベントを送る（自分の鍵の履歴を汚さない）
+    let probe_keys = Keys::generate();
+    let client = connect_single_relay(&probe_keys, url).await?;
+
+    let expiration = Timestamp::from(Timestamp::now().as_u64() + 24 * 60 * 60);
+    let marker = EventBuilder::new(
+        Kind::from(30078),
+        "retention probe",
+        vec![
+            Tag::parse(vec!["d".to_string(), RETENTION_MARKER_D_TAG.to_string()])?,
+            Tag::parse(vec!["expiration".to_string(), expiration.as_u64().to_string()])?,
+        ],
+    )
+    .to_event(&probe_keys)?;
+    let marker_id = marker.id;
+
+    eprintln!("試験用イベントを送信中...");
+    if let Err(e) = client.send_event(marker).await {
+        eprintln!("試験用イベントの送信に失敗: {}", e);
+    }
+
+    eprintln!("{}秒待機しています...", wait);
+    tokio::time::sleep(std::time::Duration::from_secs(wait)).await;
+
+    let stored = !client
+        .get_events_of(vec![Filter::new().id(marker_id)], timeout)
+        .await
+        .unwrap_or_default()
+        .is_empty();
+    println!("試験用イベントの保存: {}", if stored { "あり" } else { "なし" });
+
+    // 新しい範囲から順に遡り、各期間にイベントが残っているかを調べる
+    let now = Timestamp::now().as_u64();
+    let mut retention_days = None;
+    let mut oldest_event_at = None;
+    let mut previous_days = 0;
+    for days in RETENTION_PROBE_DAYS {
+        let since = Timestamp::from(now.saturating_sub(days * 24 * 60 * 60));
+        let until = Timestamp::from(now.saturating_sub(previous_days * 24 * 60 * 60));
+        let events = client
+            .get_events_of(vec![Filter::new().kind(Kind::TextNote).since(since).until(until).limit(1)], timeout)
+            .await
+            .unwrap_or_default();
+
+        // 期間内の投稿が返ってこなければ、それより古い履歴は保持していないとみなす
+        let Some(oldest) = events.iter().map(|e| e.created_at.as_u64()).min() else {
+            println!("  {}〜{}日前: なし", previous_days, days);
+            break;
+        };
+        println!("  {}〜{}日前: あり ({})", previous_days, days, format_jst(Timestamp::from(oldest)));
+        oldest_event_at = Some(oldest);
+        retention_days = Some(now.saturating_sub(oldest) / (24 * 60 * 60));
+        previous_days = days;
+    }
+    client.shutdown().await?;
+
+    match retention_days {
+        Some(days) => println!("推定保持期間: 少なくとも{}日", days),
+        None => println!("推定保持期間: 不明（イベントを取得できませんでした）"),
+    }
+
+    // 結果を記録
+    let mut cache = CapabilityCache::load()?;
+    let entry = cache.entry(url);
+    entry.retention_days = retention_days;
+    entry.oldest_event_at = oldest_event_at;
+    entry.stored_marker = Some(stored);
+    entry.retention_checked_at = Some(now);
+    cache.save()?;
+
+    Ok(())
+}
+
+// テンプレートからイベントを作成・署名して標準出力に書き出す関数（--publish で送信も行う）
+pub async fn event_build(matches: &ArgMatches) -> Result<(), Box<dyn std::error::Error>> {
+    let kind = *matches.get_one::<u64>("kind").unwrap_or(&1);
+
+    // 本文のテンプレート（ファイル、"-" なら標準入力、または --content で直接指定）
+    let template = match (matches.get_one::<String>("content-template"), matches.get_one::<String>("content")) {
+        (Some(path), _) if path == "-" => {
+            let mut input = String::new();
+            std::io::stdin().read_to_string(&mut input)?;
+            input
+        }
+        (Some(path), _) => fs::read_to_string(path)
+            .map_err(|e| format!("テンプレートを読み込めません ({}): {}", path, e))?,
+        (None, Some(content)) => content.clone(),
+        (None, None) => String::new(),
+    };
+
+    // 変数（組み込みの now / date / datetime は --var で上書きできる）
+    let mut vars = builtin_vars();
+    for assignment in matches.get_many::<String>("var").into_iter().flatten() {
+        let (name, value) = parse_assignment(assignment)?;
+        vars.insert(name, value);
+    }
+    let content = render(&template, &vars)?;
+
+    // タグの値にも変数を使える
+    let mut values = Vec::new();
+    for assignment in matches.get_many::<String>("tag").into_iter().flatten() {
+        let (name, value) = parse_assignment(assignment)?;
+        values.push(vec![name, render(&value, &vars)?]);
+    }
+    // 署名する前に kind ごとの形式と照らし合わせる
+    report_schema_errors(&validate(kind, &content, &values))?;
+    let tags = values.into_iter().map(Tag::parse).collect::<Result<Vec<_>, _>>()?;
+
+    let keys = unlock_keys_from_env()?;
+    let event = sign_event(EventBuilder::new(Kind::from(kind), content, tags), &keys)?;
+    println!("{}", event.as_json());
+
+    if matches.get_flag("publish") {
+        let client = connect_client(&keys).await?;
+        let result = publish(&client, event).await;
+        client.shutdown().await?;
+        eprintln!("イベントを送信しました（{}）", result?.summary());
+    }
+
+    Ok(())
+}
+
+// イベントの形式の誤りを場所付きで表示する（誤りがあればエラーにする）
+fn report_schema_errors(errors: &[SchemaError]) -> Result<(), Box<dyn std::error::Error>> {
+    if errors.is_empty() {
+        return Ok(());
+    }
+    for error in errors {
+        eprintln!("  {}", error);
+    }
+    Err(format!("イベントの形式が正しくありません（{}か所）。署名していません", errors.len()).into())
+}
+
+// event export で1回に取得する件数
+const EXPORT_PAGE_SIZE: usize = 500;
+
+// リレーからイベントを古い方へさかのぼって取得し、1行に1件の JSON（NDJSON）で書き出す関数
+// ページごとに書き出すので、件数が多くても手元に持つのは1ページ分だけ（--author を省くと自分の投稿）
+pub async fn event_export(matches: &ArgMatches) -> Result<(), Box<dyn std::error::Error>> {
+    let output = matches.get_one::<String>("output").ok_or("出力先が指定されていません")?;
+    let keys = read_keys()?;
+    let author = match matches.get_one::<String>("author") {
+        Some(author) => resolve_pubkey(author)?,
+        None => keys.public_key(),
+    };
+    let mut filter = Filter::new().author(author);
+    let kinds: Vec<Kind> = matches.get_many::<u64>("kind").into_iter().flatten().map(|k| Kind::from(*k)).collect();
+    if !kinds.is_empty() {
+        filter = filter.kinds(kinds);
+    }
+    if let Some(since) = matches.get_one::<String>("since") {
+        filter = filter.since(Timestamp::from(Timestamp::now().as_u64().saturating_sub(parse_duration(since)?)));
+    }
+
+    let destination: Box<dyn Write> = if output == "-" {
+        Box::new(std::io::stdout().lock())
+    } else {
+        if std::path::Path::new(output).exists() && !confirm(&format!("{} は既にあります。上書きしますか？", output))? {
+            return Ok(());
+        }
+        Box::new(fs::File::create(output).map_err(|e| format!("{} を作成できません: {}", output, e))?)
+    };
+    let mut writer = NdjsonWriter::new(destination);
+
+    let client = connect_read_client(&keys).await?;
+    let mut until = Timestamp::now();
+    // until は範囲に含まれるので、前のページの最も古い時刻のイベントは次のページにも返ってくる（書き出さずに飛ばす）
+    let mut boundary: std::collections::HashSet<EventId> = std::collections::HashSet::new();
+    loop {
+        let page_filter = filter.clone().until(until).limit(EXPORT_PAGE_SIZE);
+        let (mut page, violations) = query_checked(&client, vec![page_filter], std::time::Duration::from_secs(10)).await?;
+        if let Err(e) = record_violations(&violations) {
+            eprintln!("リレーの記録を保存できませんでした: {}", e);
+        }
+        page.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+        let Some(oldest) = page.last().map(|e| e.created_at) else {
+            break;
+        };
+
+        let written = writer.written;
+        for event in page.iter().filter(|e| !boundary.contains(&e.id)) {
+            writer.write(event)?;
+        }
+        eprintln!("{}件を書き出しました（{} まで）", writer.written, format_jst(oldest));
+        if page.len() < EXPORT_PAGE_SIZE {
+            break;
+        }
+        if writer.written > written {
+            if oldest != until {
+                boundary.clear();
+            }
+            boundary.extend(page.iter().filter(|e| e.created_at == oldest).map(|e| e.id));
+            until = oldest;
+        } else {
+            // 同じ時刻のイベントが1ページに収まらない（残りはリレーから取得できないので、1秒前へ進む）
+            boundary.clear();
+            until = Timestamp::from(oldest.as_u64().saturating_sub(1));
+        }
+    }
+    client.shutdown().await?;
+
+    let count = writer.finish()?;
+    eprintln!("{}件のイベントを {} に書き出しました", count, output);
+    Ok(())
+}
+
+// NDJSON のアーカイブを1行ずつ読み、署名を確かめてからリレーに送る関数（--dry-run なら確かめるだけ）
+// 解釈できない行や署名の合わない行は行番号を付けて報告し、残りの行は続けて処理する
+pub async fn event_import(matches: &ArgMatches) -> Result<(), Box<dyn std::error::Error>> {
+    let input = matches.get_one::<String>("input").ok_or("読み込むファイルが指定されていません")?;
+    let source: Box<dyn std::io::BufRead> = if input == "-" {
+        Box::new(std::io::stdin().lock())
+    } else {
+        let file = fs::File::open(input).map_err(|e| format!("{} を開けません: {}", input, e))?;
+        Box::new(std::io::BufReader::new(file))
+    };
+
+    // 署名済みのイベントをそのまま送るので、自分の鍵は使わない
+    let client = match matches.get_flag("dry-run") {
+        true => None,
+        false => Some(connect_client(&Keys::generate()).await?),
+    };
+
+    let (mut accepted, mut malformed, mut invalid, mut failed) = (0, 0, 0, 0);
+    for (line, event) in NdjsonReader::new(source) {
+        let event = match event {
+            Ok(event) => event,
+            Err(e) => {
+                eprintln!("{}行目: 解釈できません: {}", line, e);
+                malformed += 1;
+                continue;
+            }
+        };
+        if let Err(e) = verify_event(&event) {
+            eprintln!("{}行目: {}（{}）", line, e.label(), event.id.to_hex());
+            invalid += 1;
+            continue;
+        }
+        // 署名済みのイベントは書き換えられないので、形式の誤りは知らせるだけで送る
+        let errors = validate_event(&event);
+        if !errors.is_empty() {
+            let details = errors.iter().map(|e| e.to_string()).collect::<Vec<_>>().join(" / ");
+            eprintln!("{}行目: 警告: 形式が正しくありません: {}（{}）", line, details, event.id.to_hex());
+        }
+        let Some(client) = &client else {
+            accepted += 1;
+            continue;
+        };
+        let receipt = send_with_receipts(client, &event).await;
+        if receipt.accepted.is_empty() {
+            eprintln!("{}行目: {} の送信に失敗: {}", line, event.id.to_hex(), receipt.failure_reason());
+            failed += 1;
+        } else {
+            accepted += 1;
+            if accepted % 1000 == 0 {
+                eprintln!("{}件を送信しました（{}行目まで）", accepted, line);
+            }
+        }
+    }
+    if let Some(client) = client {
+        client.shutdown().await?;
+        println!("{}件のイベントを送信しました", accepted);
+    } else {
+        println!("{}件のイベントを確かめました（送信はしていません）", accepted);
+    }
+
+    if malformed + invalid + failed > 0 {
+        return Err(format!(
+            "解釈できない行が{}行、署名の合わないイベントが{}件、送信できなかったイベントが{}件あります",
+            malformed, invalid, failed
+        )
+        .into());
+    }
+    Ok(())
+}
+
+// シェルのコマンドに埋め込めるように単一引用符で囲む関数
+fn shell_quote(text: &str) -> String {
+    format!("'{}'", text.replace('\'', "'\\''"))
+}
+
+// 接続中のリレーが1つでもあるかを返す関数
+async fn any_relay_connected(client: &Client) -> bool {
+    for relay in client.relays().await.values() {
+        if relay.status().await == RelayStatus::Connected {
+            return true;
+        }
+    }
+    false
+}
+
+// 受信したイベントごとにコマンドを実行する関数（--exec がなければJSONを1行ずつ出力する）
+// 切断されたら間隔を空けて再接続し、最後に受信した時刻から購読し直す
+pub async fn watch(matches: &ArgMatches) -> Result<(), Box<dyn std::error::Error>> {
+    let filter_json = matches.get_one::<String>("filter").ok_or("フィルターが指定されていません")?;
+    let filter: Filter = serde_json::from_str(filter_json)
+        .map_err(|e| format!("フィルターのJSONが正しくありません: {}", e))?;
+    let exec = matches.get_one::<String>("exec").cloned();
+    let max_concurrency = (*matches.get_one::<usize>("max-concurrency").unwrap_or(&4)).max(1);
+    let semaphore = std::sync::Arc::new(tokio::sync::Semaphore::new(max_concurrency));
+
+    // 再接続時に同じイベントで二重に実行しないよう、処理済みのIDを覚えておく
+    let mut seen: std::collections::HashSet<EventId> = std::collections::HashSet::new();
+    let mut last_seen: Option<Timestamp> = None;
+    let mut backoff_secs = 1;
+    let keys = Keys::generate();
+
+    loop {
+        let client = connect_client(&keys).await?;
+        let mut notifications = client.notifications();
+        let current = match last_seen {
+            Some(since) => filter.clone().since(since),
+            None => filter.clone(),
+        };
+        client.subscribe(vec![current]).await;
+        eprintln!("監視を開始しました（Ctrl-C で終了）");
+
+        let check_every = std::time::Duration::from_secs(30);
+        let mut check = tokio::time::interval_at(tokio::time::Instant::now() + check_every, check_every);
+        let reason = loop {
+            tokio::select! {
+                notification = notifications.recv() => match notification {
+                    Ok(RelayPoolNotification::Event { event, .. }) => {
+                        if !seen.insert(event.id) {
+                            continue;
+                        }
+                        backoff_secs = 1;
+                        last_seen = Some(last_seen.map_or(event.created_at, |t| t.max(event.created_at)));
+
+                        let json = event.as_json();
+                        let Some(command) = &exec else {
+                            println!("{}", json);
+                            continue;
+                        };
+
+                        // 同時実行数の上限に達していたら空くまで待つ
+                        let permit = semaphore.clone().acquire_owned().await?;
+                        let command_line = command.replace("{}", &shell_quote(&json));
+                        let event_id = event.id.to_hex();
+                        tokio::spawn(async move {
+                            let status = tokio::process::Command::new("sh")
+                                .arg("-c")
+                                .arg(&command_line)
+                                .env("NOSTR_EVENT", &json)
+                                .status()
+                                .await;
+                            match status {
+                                Ok(status) if status.success() => {}
+                                Ok(status) => eprintln!("{} のコマンドが失敗しました: {}", event_id, status),
+                                Err(e) => eprintln!("{} のコマンドを実行できません: {}", event_id, e),
+                            }
+                            drop(permit);
+                        });
+                    }
+                    Ok(RelayPoolNotification::Shutdown) => break "リレーとの接続が終了しました",
+                    Ok(_) => {}
+                    Err(tokio::sync::broadcast::error::RecvError::Lagged(skipped)) => {
+                        eprintln!("処理が追いつかず {}件の通知を読み飛ばしました", skipped);
+                    }
+                    Err(tokio::sync::broadcast::error::RecvError::Closed) => break "通知の受信が終了しました",
+                },
+                _ = check.tick() => {
+                    if !any_relay_connected(&client).await {
+                        break "すべてのリレーから切断されました";
+                    }
+                }
+                _ = tokio::signal::ctrl_c() => {
+                    let _ = client.shutdown().await;
+                    return Ok(());
+                }
+            }
+        };
+
+        eprintln!("{}。{}秒後に再接続します", reason, backoff_secs);
+        let _ = client.shutdown().await;
+        tokio::time::sleep(std::time::Duration::from_secs(backoff_secs)).await;
+        backoff_secs = (backoff_secs * 2).min(60);
+
+        // 処理済みIDが増え続けないよう、古いものは忘れる（再購読は last_seen 以降なので十分）
+        if seen.len() > 10_000 {
+            seen.clear();
+        }
+    }
+}
+
+// アウトボックス（送信に失敗したイベント）を一覧表示する関数
+pub fn outbox_list(_matches: &ArgMatches) -> Result<(), Box<dyn std::error::Error>> {
+    let outbox = Outbox::load()?;
+    if outbox.entries.is_empty() {
+        eprintln!("アウトボックスは空です");
+    }
+    for entry in &outbox.entries {
+        println!("-----------------------------------");
+        println!("ID: {}", entry.event.id.to_hex());
+        println!("種類: {}", entry.event.kind.as_u64());
+        println!("保存日時: {}", format_jst(Timestamp::from(entry.queued_at)));
+        println!("試行回数: {}", entry.attempts);
+        println!("最後のエラー: {}", entry.last_error);
+        println!("内容: {}", entry.event.content);
+    }
+    Ok(())
+}
+
+// 一度に存在を確かめるイベントの数
+const BACKUP_VERIFY_CHUNK: usize = 200;
+
+// 送信済みのイベントがすべてアーカイブリレーにあるか確かめる関数（--repair で足りないものを送る）
+pub async fn backup_verify(matches: &ArgMatches) -> Result<(), Box<dyn std::error::Error>> {
+    let url = AppConfig::load()?
+        .backup_relay
+        .ok_or("backup_relay が設定されていません（config set backup_relay '\"wss://...\"'）")?;
+    let sent = load_sent_events()?;
+    if sent.is_empty() {
+        println!("送信済みのイベントの記録がありません");
+        return Ok(());
+    }
+
+    // 認証が必要な個人用リレーもあるので自分の鍵で接続する
+    let keys = unlock_keys()?;
+    let client = connect_single_relay(&keys, &url).await?;
+
+    let mut found = std::collections::HashSet::new();
+    for chunk in sent.chunks(BACKUP_VERIFY_CHUNK) {
+        let filter = Filter::new().ids(chunk.iter().map(|e| e.id)).limit(chunk.len());
+        for event in client.get_events_of(vec![filter], Some(std::time::Duration::from_secs(10))).await? {
+            found.insert(event.id);
+        }
+    }
+    let missing: Vec<&Event> = sent.iter().filter(|e| !found.contains(&e.id)).collect();
+    println!("{}: 送信済みの{}件のうち{}件を保持しています", url, sent.len(), sent.len() - missing.len());
+    for event in &missing {
+        println!("  未保存: {} (kind {}, {})", event.id.to_bech32()?, event.kind.as_u64(), format_jst(event.created_at));
+    }
+
+    let mut failed = missing.len();
+    if matches.get_flag("repair") && !missing.is_empty() {
+        failed = 0;
+        for event in &missing {
+            let receipt = send_with_receipts(&client, event).await;
+            if receipt.accepted.is_empty() {
+                eprintln!("{} の送信に失敗: {}", event.id.to_hex(), receipt.failure_reason());
+                failed += 1;
+            }
+        }
+        println!("{}件をアーカイブリレーに送りました", missing.len() - failed);
+    }
+    client.shutdown().await?;
+
+    if failed > 0 {
+        return Err(format!("{}件のイベントがアーカイブリレーにありません", failed).into());
+    }
+    Ok(())
+}
+
+// アウトボックスのイベントを再送する関数（受理されたものはアウトボックスから除く）
+pub async fn outbox_flush(_matches: &ArgMatches) -> Result<(), Box<dyn std::error::Error>> {
+    let outbox = Outbox::load()?;
+    if outbox.entries.is_empty() {
+        eprintln!("アウトボックスは空です");
+        return Ok(());
+    }
+
+    // 署名済みのイベントをそのまま送るので鍵は不要
+    let client = connect_client(&Keys::generate()).await?;
+    let mut sent = Vec::new();
+    let mut failures = Vec::new();
+    for entry in &outbox.entries {
+        let receipt = send_with_receipts(&client, &entry.event).await;
+        if receipt.accepted.is_empty() {
+            let reason = receipt.failure_reason();
+            eprintln!("{} の再送に失敗: {}", entry.event.id.to_hex(), reason);
+            failures.push((entry.event.clone(), reason));
+        } else {
+            let mut receipt = receipt;
+            archive_sent(&client, &entry.event, &mut receipt).await;
+            eprintln!("{} を再送しました（{}）", entry.event.id.to_hex(), receipt.summary());
+            sent.push(entry.event.id);
+        }
+    }
+    client.shutdown().await?;
+
+    // 再送している間にほかのインスタンスが追加したイベントを消さないよう、読み込み直してから反映する
+    let failed = failures.len();
+    Outbox::update(|outbox| {
+        outbox.entries.retain(|entry| !sent.contains(&entry.event.id));
+        for (event, reason) in failures {
+            outbox.enqueue(event, reason);
+        }
+    })?;
+
+    if failed > 0 {
+        return Err(format!("{}件のイベントを再送できませんでした", failed).into());
+    }
+    Ok(())
+}
+
+// 登録済みのリレーの応答時間を計測し、読み込みに使う順位を表示する関数
+pub async fn rank_relays(_matches: &ArgMatches) -> Result<(), Box<dyn std::error::Error>> {
+    let relays = configured_relays()?;
+    let read_count = AppConfig::load()?.read_relay_count.max(1);
+    let mut cache = CapabilityCache::load()?;
+    let keys = Keys::generate();
+
+    eprintln!("{}件のリレーの応答時間を計測中...", relays.len());
+    for url in &relays {
+        // 接続して1件取得するまでの時間を計測する
+        let started = std::time::Instant::now();
+        let ok = match connect_single_relay(&keys, url).await {
+            Ok(client) => {
+                let events = client
+                    .get_events_of(vec![Filter::new().kind(Kind::TextNote).limit(1)], Some(RELAY_CONNECT_TIMEOUT))
+                    .await;
+                let _ = client.shutdown().await;
+                matches!(events, Ok(events) if !events.is_empty())
+            }
+            Err(_) => false,
+        };
+        let latency = started.elapsed().as_secs_f64() * 1000.0;
+        cache.entry(url).record_sample(ok, Some(latency));
+    }
+    cache.save()?;
+
+    println!("{:>4}  {:<40} {:>10} {:>8} {:>8} {:>6} {:>6}", "順位", "リレー", "応答時間", "成功率", "評価値", "計測数", "不一致");
+    for (i, (url, score)) in cache.rank(&relays).iter().enumerate() {
+        let cap = cache.relays.get(url).cloned().unwrap_or_default();
+        let latency = cap.latency_ms.map_or("-".to_string(), |ms| format!("{:.0}ms", ms));
+        let rate = cap.success_rate.map_or("-".to_string(), |r| format!("{:.0}%", r * 100.0));
+        let score = score.map_or("-".to_string(), |s| format!("{:.2}", s));
+        let mark = if i < read_count { "  ← 読み込みに使用" } else { "" };
+        println!("{:>4}  {:<40} {:>10} {:>8} {:>8} {:>6} {:>6}{}", i + 1, url, latency, rate, score, cap.samples, cap.filter_violations, mark);
+    }
+    if cache.relays.values().any(|cap| cap.filter_violations > 0) {
+        eprintln!("不一致: 問い合わせた条件（kind・作成者・期間・タグ）に合わないイベントを返し、手元で捨てた件数");
+    }
+    eprintln!("投稿は登録済みのすべてのリレーに送信します（読み込みに使う数: config set read_relay_count <数>）");
+
+    Ok(())
+}
+
+// リレーの NIP-11 の情報から、書き込みに認証や支払い、PoW が必要かを調べる関数（分かった注意点を返す）
+async fn relay_write_requirements(url: &str) -> Vec<String> {
+    fetch_relay_limits(url).await.map(|limits| limits.write_notes()).unwrap_or_default()
+}
+
+// 送信前の確認の結果を表示する関数（すべてのリレーが拒否する見込みなら、送るかどうか確かめる）
+fn report_preflight(preflight: &Preflight) -> Result<bool, Box<dyn std::error::Error>> {
+    for (url, reason) in &preflight.refused {
+        eprintln!("{} はこのノートを拒否します: {}", url, reason);
+    }
+    for (url, reason) in &preflight.warnings {
+        eprintln!("注意: {}: {}", url, reason);
+    }
+    if !preflight.auth.is_empty() {
+        eprintln!("{} には認証（NIP-42）してから送ります", preflight.auth.join(", "));
+    }
+    if preflight.pow > 0 {
+        eprintln!("{} が PoW（NIP-13）を求めているため、{}ビットを採掘してから署名します...", preflight.pow_relays.join(", "), preflight.pow);
+    }
+    if preflight.all_refused() {
+        return confirm("どのリレーも受理しない見込みです。それでも送信しますか？（アウトボックスに保存されます）");
+    }
+    Ok(true)
+}
+
+// 追加する前にリレーへ接続し、使い捨ての鍵で署名した一時イベント（保存されない kind 20000）を受理するか確かめる関数
+async fn test_relay_write(url: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let client = Client::new(&Keys::generate());
+    let dialed = dial(url)?;
+    client.add_relay(dialed.as_str()).await?;
+    let mut cache = CapabilityCache::load()?;
+    connect_measured(&client, &[dialed.clone()], &mut cache).await;
+    let _ = cache.save();
+    if client.relay(dialed.as_str()).await?.status().await != RelayStatus::Connected {
+        client.disconnect().await?;
+        return Err(format!("{} に接続できませんでした", url).into());
+    }
+
+    let written = send_write_test(&client).await;
+    client.disconnect().await?;
+    written
+}
+
+// 接続済みのリレーに使い捨ての鍵で署名した一時イベントを送り、受理されるか確かめる関数
+async fn send_write_test(client: &Client) -> Result<(), Box<dyn std::error::Error>> {
+    let event = EventBuilder::new(Kind::from(20000), "nostr-cli-app relay write test", []).to_event(&Keys::generate())?;
+    let receipt = send_with_receipts(client, &event).await;
+    if receipt.accepted.is_empty() {
+        return Err(format!("書き込みを受理しませんでした（{}）", receipt.failure_reason()).into());
+    }
+    Ok(())
+}
+
+// リレーを追加する関数（--force がなければ、接続と書き込みを確かめてから保存する）
+pub async fn add_relay(matches: &ArgMatches) -> Result<(), Box<dyn std::error::Error>> {
+    let url = matches.get_one::<String>("url").ok_or("URLが指定されていません")?;
+    // 形式の誤ったURLは以後リレーの読み込みで弾かれるので、--force でも保存しない
+    check_relay_url(url)?;
+
+    // 設定を読み込み
+    let mut config = load_relays()?;
+
+    // リレーが既に存在するか確認
+    if config.relays.contains(url) {
+        eprintln!("リレー {} は既に登録されています", url);
+        return Ok(());
+    }
+
+    if !matches.get_flag("force") {
+        for note in relay_write_requirements(url).await {
+            eprintln!("注意: {}", note);
+        }
+        test_relay_write(url)
+            .await
+            .map_err(|e| format!("リレー {} を追加しませんでした: {}（確かめずに追加するには --force）", url, e))?;
+        eprintln!("リレー {} への接続と書き込みを確認しました", url);
+    }
+
+    // リレーを追加
+    config.relays.push(url.clone());
+
+    // 設定を保存
+    save_relays(&config)?;
+
+    eprintln!("リレー {} を追加しました", url);
+    Ok(())
+}
+
+// ほかのクライアントが書き出したリレーを relays.json に取り込む関数（増えるリレーを表示し、確かめてから保存する）
+pub fn import_relays(matches: &ArgMatches) -> Result<(), Box<dyn std::error::Error>> {
+    let path = matches.get_one::<String>("from").ok_or("ファイルが指定されていません")?;
+    let text = fs::read_to_string(path).map_err(|e| format!("{} を読み込めません: {}", path, e))?;
+    let mut config = load_relays()?;
+    let mut added = Vec::new();
+    for url in parse_relays(&text) {
+        if let Err(e) = check_relay_url(&url) {
+            eprintln!("飛ばします: {}", e);
+            continue;
+        }
+        if !config.relays.contains(&url) && !added.contains(&url) {
+            added.push(url);
+        }
+    }
+    if added.is_empty() {
+        eprintln!("{} に新しいリレーは見つかりませんでした", path);
+        return Ok(());
+    }
+
+    for url in &added {
+        println!("+ {}", url);
+    }
+    if !confirm(&format!("{} 個のリレーを追加しますか？（接続と書き込みは確かめません）", added.len()))? {
+        return Ok(());
+    }
+    let count = added.len();
+    config.relays.extend(added);
+    save_relays(&config)?;
+    eprintln!("リレーを {} 個追加しました", count);
+    Ok(())
+}
+
+// relays.json のリレーを NIP-65 のリレーの一覧（kind 10002）として送る関数（前の一覧との違いを表示し、確かめてから送る）
+pub async fn relay_publish(_matches: &ArgMatches) -> Result<(), Box<dyn std::error::Error>> {
+    let relays = load_relays()?.relays;
+    if relays.is_empty() {
+        return Err("relays.json にリレーが登録されていません（relay add で追加してください）".into());
+    }
+    let keys = unlock_keys()?;
+    let client = connect_client(&keys).await?;
+    let published = async {
+        let previous = fetch_relay_list(&client, keys.public_key()).await?;
+        let before: Vec<String> = previous.as_ref().map(parse_relay_list).unwrap_or_default().into_iter().map(|e| e.url).collect();
+        let added: Vec<&String> = relays.iter().filter(|url| !before.contains(url)).collect();
+        let removed: Vec<&String> = before.iter().filter(|url| !relays.contains(url)).collect();
+        if previous.is_some() && added.is_empty() && removed.is_empty() {
+            eprintln!("リレーの一覧は送信済みのものと同じです");
+            return Ok(None);
+        }
+        for url in &added {
+            println!("+ {}", url);
+        }
+        for url in &removed {
+            println!("- {}", url);
+        }
+        if !confirm(&format!("{} 個のリレーを NIP-65 のリレーの一覧として送信しますか？", relays.len()))? {
+            return Ok(None);
+        }
+        let event = sign_event(relay_list_builder(&relays, previous.as_ref())?, &keys)?;
+        publish(&client, event).await.map(Some)
+    }
+    .await;
+    client.shutdown().await?;
+    if let Some(receipt) = published? {
+        eprintln!("リレーの一覧を送信しました（{}）", receipt.summary());
+    }
+    Ok(())
+}
+
+// ほかの人の NIP-65 のリレーの一覧を取得して表示し、確かめてから relays.json に取り込む関数
+pub async fn relay_fetch(matches: &ArgMatches) -> Result<(), Box<dyn std::error::Error>> {
+    let target = resolve_pubkey(matches.get_one::<String>("npub").ok_or("公開鍵が指定されていません")?)?;
+    let keys = read_keys()?;
+    let client = connect_read_client(&keys).await?;
+    let relay_list = fetch_relay_list(&client, target).await;
+    client.shutdown().await?;
+    let relay_list = relay_list?.ok_or_else(|| format!("{} のリレーの一覧（kind 10002）が見つかりません", target.to_bech32().unwrap_or_default()))?;
+
+    let mut config = load_relays()?;
+    let mut added = Vec::new();
+    println!("{} のリレー（{}）:", target.to_bech32()?, format_jst(relay_list.created_at));
+    for entry in parse_relay_list(&relay_list) {
+        let known = config.relays.contains(&entry.url);
+        println!("{} {}（{}）", if known { " " } else { "+" }, entry.url, entry.usage());
+        if !known && check_relay_url(&entry.url).is_ok() {
+            added.push(entry.url);
+        }
+    }
+    if added.is_empty() {
+        eprintln!("新しく追加できるリレーはありません");
+        return Ok(());
+    }
+    if !confirm(&format!("+ の {} 個のリレーを relays.json に追加しますか？", added.len()))? {
+        return Ok(());
+    }
+    let count = added.len();
+    config.relays.extend(added);
+    save_relays(&config)?;
+    eprintln!("リレーを {} 個追加しました", count);
+    Ok(())
+}
+
+// リレーを削除する関数
+pub fn remove_relay(matches: &ArgMatches) -> Result<(), Box<dyn std::error::Error>> {
+    let url = matches.get_one::<String>("url").ok_or("URLが指定されていません")?;
+
+    // 設定を読み込み
+    let mut config = load_relays()?;
+
+    // リレーが存在するか確認
+    let index = config.relays.iter().position(|r| r == url);
+    if let Some(idx) = index {
+        // リレーを削除
+        config.relays.remove(idx);
+
+        // 設定を保存
+        save_relays(&config)?;
+        eprintln!("リレー {} を削除しました", url);
+    } else {
+        eprintln!("リレー {} は登録されていません", url);
+    }
+
+    Ok(())
+}
+
+// リレーの AUTH（NIP-42）への自動の認証を切り替える関数（off にしたリレーは config の auth_opt_out に入れる）
+pub fn relay_auth(matches: &ArgMatches) -> Result<(), Box<dyn std::error::Error>> {
+    let url = matches.get_one::<String>("url").ok_or("URLが指定されていません")?;
+    let enable = matches.get_one::<String>("mode").map(String::as_str) == Some("on");
+    check_relay_url(url)?;
+
+    let mut config = AppConfig::load()?;
+    let opted_out = config.auth_opt_out.iter().any(|r| r.trim_end_matches('/') == url.trim_end_matches('/'));
+    if enable == !opted_out {
+        eprintln!("{} の認証はすでに{}です", url, if enable { "on" } else { "off" });
+        return Ok(());
+    }
+    if enable {
+        config.auth_opt_out.retain(|r| r.trim_end_matches('/') != url.trim_end_matches('/'));
+    } else {
+        config.auth_opt_out.push(url.clone());
+    }
+    config.save()?;
+    if enable {
+        eprintln!("{} が AUTH を求めたら自動で認証します", url);
+    } else {
+        eprintln!("{} には認証しません（認証が必要なリレーには書き込めない場合があります）", url);
+    }
+    Ok(())
+}
+
+// リレーリストを表示する関数
+pub fn list_relays(_: &ArgMatches) -> Result<(), Box<dyn std::error::Error>> {
+    // 設定を読み込み
+    let config = load_relays()?;
+
+    if config.relays.is_empty() {
+        eprintln!("登録されているリレーはありません");
+    } else {
+        eprintln!("登録されているリレー一覧:");
+        for (i, url) in config.relays.iter().enumerate() {
+            println!("{}. {}", i + 1, url);
+        }
+    }
+
+    Ok(())
+}
+
+// キャッシュの保存期間を表示し、--vacuum なら期限を過ぎたイベントを取り除く
+pub fn cache_policy(matches: &ArgMatches) -> Result<(), Box<dyn std::error::Error>> {
+    let vacuum = matches.get_flag("vacuum");
+    let config = AppConfig::load()?;
+    let policy = RetentionPolicy::compile(&config.cache_retention)?;
+
+    eprintln!("kind ごとの保存期間（先に書いた規則を優先。当てはまらない kind は件数の上限まで残す）:");
+    for rule in &config.cache_retention {
+        println!("  kind {}: {}", rule.kinds, rule.keep.describe());
+    }
+
+    let results = vacuum_caches(&policy, !vacuum)?;
+    eprintln!();
+    for result in &results {
+        let action = if vacuum { "取り除きました" } else { "取り除く見込み" };
+        println!("cache/{}: {}件を残し、{}件を{}", result.file, result.kept, result.removed, action);
+    }
+    if !vacuum && results.iter().any(|result| result.removed > 0) {
+        eprintln!("--vacuum で今すぐ取り除きます（TUI の起動中は1時間ごとに取り除きます）");
+    }
+    Ok(())
+}
+
+// リレー設定を読み込む関数
+pub fn load_relays() -> Result<RelayConfig, Box<dyn std::error::Error>> {
+    let config_dir = app_dir()?;
+
+    let relays_path = config_dir.join("relays.json");
+
+    if !relays_path.exists() {
+        return Ok(RelayConfig::default());
+    }
+
+    read_json(&relays_path)
+}
+
+// リレー設定を保存する関数
+pub fn save_relays(config: &RelayConfig) -> Result<(), Box<dyn std::error::Error>> {
+    write_json(&app_dir()?.join("relays.json"), config)
+}
+
+// health コマンドの終了コード（問題のあった分類ごとに異なる値を返す）
+pub const HEALTH_EXIT_KEYS: i32 = 10;
+pub const HEALTH_EXIT_CONFIG: i32 = 11;
+pub const HEALTH_EXIT_CACHE: i32 = 12;
+pub const HEALTH_EXIT_RELAY_READ: i32 = 13;
+pub const HEALTH_EXIT_RELAY_WRITE: i32 = 14;
+
+// ヘルスチェックを行い、問題があれば分類に応じた終了コードを返す関数（問題がなければ 0）
+pub async fn health(_matches: &ArgMatches) -> Result<i32, Box<dyn std::error::Error>> {
+    let mut failures: Vec<i32> = Vec::new();
+
+    // 鍵ファイル: 読み込めてJSONとして解釈できるか（パスワードは要求しない）
+    match check_keys_file() {
+        Ok(()) => println!("[OK] 鍵ファイル"),
+        Err(e) => {
+            println!("[NG] 鍵ファイル: {}", e);
+            failures.push(HEALTH_EXIT_KEYS);
+        }
+    }
+
+    // 設定: relays.json が読めて、各URLが ws:// または wss:// か
+    let relays = match check_relay_config() {
+        Ok(relays) => {
+            println!("[OK] リレー設定 ({}件)", relays.len());
+            relays
+        }
+        Err(e) => {
+            println!("[NG] リレー設定: {}", e);
+            failures.push(HEALTH_EXIT_CONFIG);
+            Vec::new()
+        }
+    };
+
+    // キャッシュ: cache ディレクトリ内のJSONが壊れていないか
+    match check_cache_dir() {
+        Ok(0) => println!("[OK] キャッシュ (未作成)"),
+        Ok(count) => println!("[OK] キャッシュ ({}ファイル)", count),
+        Err(e) => {
+            println!("[NG] キャッシュ: {}", e);
+            failures.push(HEALTH_EXIT_CACHE);
+        }
+    }
+
+    // リレー: 読み込み・書き込みそれぞれ最低1つに到達できるか
+    let relays = if relays.is_empty() {
+        vec![DEFAULT_RELAY.to_string()]
+    } else {
+        relays
+    };
+
+    let mut readable = 0;
+    let mut writable = 0;
+    let ok = |passed: bool| if passed { "OK" } else { "NG" };
+    for url in &relays {
+        let (connected, read_ok, write_ok) = check_relay(url).await;
+        println!("  {} 接続:{} 読み込み:{} 書き込み:{}", url, ok(connected), ok(read_ok), ok(write_ok));
+        if write_ok {
+            writable += 1;
+        }
+        if read_ok {
+            readable += 1;
+        }
+    }
+
+    if readable > 0 {
+        println!("[OK] 読み込み可能なリレー: {}件", readable);
+    } else {
+        println!("[NG] 読み込み可能なリレーがありません");
+        failures.push(HEALTH_EXIT_RELAY_READ);
+    }
+
+    if writable > 0 {
+        println!("[OK] 書き込みを受理したリレー: {}件", writable);
+    } else {
+        println!("[NG] 書き込みを受理したリレーがありません");
+        failures.push(HEALTH_EXIT_RELAY_WRITE);
+    }
+
+    // 最初に見つかった問題の分類で終了コードを決める
+    if let Some(code) = failures.first() {
+        eprintln!("ヘルスチェックで{}件の問題が見つかりました", failures.len());
+        return Ok(*code);
+    }
+
+    eprintln!("すべてのチェックに合格しました");
+    Ok(0)
+}
+
+// 鍵ファイルが読み込めるか確認する関数
+fn check_keys_file() -> Result<(), Box<dyn std::error::Error>> {
+    let keys_path = keys_path()?;
+    if !keys_path.exists() {
+        return Err(format!("鍵ファイルが見つかりません: {:?}", keys_path).into());
+    }
+    let contents = fs::read_to_string(&keys_path)?;
+    let value: serde_json::Value = serde_json::from_str(&contents)?;
+    if value.get("secret_key").is_some() {
+        return Err("秘密鍵が平文で保存されています（次に鍵を読み込むと ncryptsec 形式に移行します）".into());
+    }
+    if value.get("ncryptsec").is_none() && value.get("encrypted_key").is_none() && value.get("public_key").is_none() {
+        return Err("暗号化した秘密鍵（ncryptsec / encrypted_key）が含まれていません".into());
+    }
+    Ok(())
+}
+
+// リレー設定を検証し、登録済みのURL一覧を返す関数
+fn check_relay_config() -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let config = load_relays()?;
+    for url in &config.relays {
+        check_relay_url(url)?;
+    }
+    Ok(config.relays)
+}
+
+// リレーのURLが ws:// または wss:// で始まる正しい形式か確認する関数
+pub fn check_relay_url(url: &str) -> Result<(), Box<dyn std::error::Error>> {
+    validate_relay_url(url)
+}
+
+// キャッシュディレクトリ内のJSONファイルがすべて解釈できるか確認し、ファイル数を返す関数
+fn check_cache_dir() -> Result<usize, Box<dyn std::error::Error>> {
+    let cache_dir = app_dir()?.join("cache");
+    if !cache_dir.exists() {
+        return Ok(0);
+    }
+
+    let mut count = 0;
+    for entry in fs::read_dir(&cache_dir)? {
+        let path = entry?.path();
+        match path.extension().and_then(|e| e.to_str()) {
+            // 1行1件のイベントは全体を読み込まずに行ごとに検証する
+            Some("ndjson") => {
+                for (line, event) in NdjsonReader::open(&path)? {
+                    event.map_err(|e| format!("{:?} {}行目: {}", path, line, e))?;
+                }
+            }
+            Some("json") => {
+                serde_json::from_str::<serde_json::Value>(&fs::read_to_string(&path)?)
+                    .map_err(|e| format!("{:?}: {}", path, e))?;
+            }
+            _ => continue,
+        }
+        count += 1;
+    }
+
+    Ok(count)
+}
+
+// リレーへの接続・読み込み・書き込みを確認する関数（戻り値: (接続できたか, イベントを読めたか, 一時イベントを受理したか)）
+async fn check_relay(url: &str) -> (bool, bool, bool) {
+    // 確認だけなので一時的な鍵を使う
+    let client = Client::new(&Keys::generate());
+    let Ok(dialed) = dial(url) else {
+        return (false, false, false);
+    };
+    if client.add_relay(dialed).await.is_err() {
+        return (false, false, false);
+    }
+    client.connect().await;
+
+    let read_ok = match client
+        .get_events_of(vec![Filter::new().limit(1)], Some(std::time::Duration::from_secs(5)))
+        .await
+    {
+        Ok(events) => !events.is_empty(),
+        Err(_) => false,
+    };
+
+    let mut connected = false;
+    for relay in client.relays().await.values() {
+        if relay.status().await == RelayStatus::Connected {
+            connected = true;
+        }
+    }
+
+    // 書き込みは relay add と同じく、保存されない一時イベントで確かめる
+    let write_ok = connected && send_write_test(&client).await.is_ok();
+
+    let _ = client.shutdown().await;
+    (connected, read_ok, write_ok)
+}
+
+// メディアURLをどのように取得するか（プロキシ・許可/拒否リストの適用結果）を表示する関数
+pub fn media_route(matches: &ArgMatches) -> Result<(), Box<dyn std::error::Error>> {
+    let url = matches.get_one::<String>("url").ok_or("URLが指定されていません")?;
+    match route_media_url(url, &AppConfig::load()?) {
+        MediaRoute::Direct(url) => println!("直接取得: {}", url),
+        MediaRoute::Proxied(url) => println!("プロキシ経由: {}", url),
+        MediaRoute::Blocked => println!("取得しません（拒否リストに一致）"),
+    }
+    Ok(())
+}
+
+// メディアを取得して SHA-256 を照合する関数（一致したときだけ --output に保存する）
+pub async fn media_verify(matches: &ArgMatches) -> Result<(), Box<dyn std::error::Error>> {
+    let url = matches.get_one::<String>("url").ok_or("URLが指定されていません")?;
+    let expected = matches.get_one::<String>("sha256").map(|s| s.as_str());
+    if expected.is_none() && blossom_hash(url).is_none() {
+        return Err("SHA-256 を指定してください（Blossom のURLなら省略できます）".into());
+    }
+
+    eprintln!("{} を取得中...", url);
+    let bytes = fetch_verified_media(url, expected).await?;
+    println!("✓ ハッシュが一致しました ({} バイト)", bytes.len());
+
+    if let Some(path) = matches.get_one::<String>("output") {
+        fs::write(path, &bytes)?;
+        eprintln!("{} に保存しました", path);
+    }
+    Ok(())
+}
+
+// 1つのリレーから届いたイベントの検証結果
+#[derive(Default)]
+struct RelayVerifyTally {
+    received: usize,
+    id_mismatch: usize,
+    bad_signature: usize,
+}
+
+// フィードのイベントの ID と署名を手元で検証し、リレーごとの失敗数を表示する関数
+pub async fn verify_feed(matches: &ArgMatches) -> Result<(), Box<dyn std::error::Error>> {
+    let limit = *matches.get_one::<usize>("limit").unwrap_or(&200);
+
+    let keys = unlock_keys()?;
+    let client = match matches.get_one::<String>("relay") {
+        Some(url) => connect_single_relay(&keys, url).await?,
+        None => connect_client(&keys).await?,
+    };
+    let relay_count = client.relays().await.len();
+
+    // 同じイベントでもリレーごとに数えるため、重複を除く前のメッセージを受け取る
+    let mut notifications = client.notifications();
+    client.subscribe(vec![Filter::new().kind(Kind::TextNote).limit(limit)]).await;
+    eprintln!("イベントを検証中...");
+
+    let mut tally: std::collections::BTreeMap<String, RelayVerifyTally> = Default::default();
+    let mut finished = std::collections::HashSet::new();
+    let started = std::time::Instant::now();
+    while finished.len() < relay_count && started.elapsed() < std::time::Duration::from_secs(10) {
+        let Ok(Ok(notification)) = tokio::time::timeout(std::time::Duration::from_secs(1), notifications.recv()).await else {
+            continue;
+        };
+        let RelayPoolNotification::Message { relay_url, message } = notification else {
+            continue;
+        };
+        match message {
+            RelayMessage::Event { event, .. } => {
+                let entry = tally.entry(relay_url.to_string()).or_default();
+                entry.received += 1;
+                match verify_event(&event) {
+                    Ok(()) => {}
+                    Err(VerifyError::IdMismatch) => entry.id_mismatch += 1,
+                    Err(VerifyError::BadSignature) => entry.bad_signature += 1,
+                }
+            }
+            RelayMessage::EndOfStoredEvents(_) => {
+                finished.insert(relay_url.to_string());
+            }
+            _ => {}
+        }
+    }
+    let _ = client.unsubscribe().await;
+    client.shutdown().await?;
+
+    println!("{:<40} {:>6} {:>12} {:>12}", "リレー", "受信", VerifyError::IdMismatch.label(), VerifyError::BadSignature.label());
+    for (url, t) in &tally {
+        println!("{:<40} {:>6} {:>12} {:>12}", url, t.received, t.id_mismatch, t.bad_signature);
+    }
+    let failed: usize = tally.values().map(|t| t.id_mismatch + t.bad_signature).sum();
+    let received: usize = tally.values().map(|t| t.received).sum();
+    println!("合計: {}件中{}件が検証に失敗しました", received, failed);
+    Ok(())
+}
+
+// ヒストグラムの棒の最大の長さ
+const HISTOGRAM_WIDTH: usize = 30;
+
+// 最近のイベントを集め、kind ごとの件数をヒストグラムで表示する関数
+pub async fn explore_kinds(matches: &ArgMatches) -> Result<(), Box<dyn std::error::Error>> {
+    let limit = *matches.get_one::<usize>("limit").unwrap_or(&500);
+
+    let keys = unlock_keys()?;
+    let client = match matches.get_one::<String>("relay") {
+        Some(url) => connect_single_relay(&keys, url).await?,
+        None => connect_read_client(&keys).await?,
+    };
+
+    eprintln!("イベントを集めています...");
+    let events = sample_events(&client, limit).await?;
+    client.shutdown().await?;
+    let buckets = histogram(&events);
+    let total: usize = buckets.iter().map(|b| b.count).sum();
+    eprintln!("{}件のイベントを集めました", total);
+
+    if matches.get_flag("json") {
+        let json: Vec<serde_json::Value> = buckets
+            .iter()
+            .map(|b| {
+                serde_json::json!({
+                    "kind": b.kind,
+                    "name": kind_name(b.kind),
+                    "count": b.count,
+                    "examples": b.examples.iter().map(|e| e.id.to_hex()).collect::<Vec<_>>(),
+                })
+            })
+            .collect();
+        println!("{}", serde_json::to_string_pretty(&json)?);
+        return Ok(());
+    }
+
+    let max = buckets.first().map_or(1, |b| b.count);
+    for bucket in &buckets {
+        let bar = "█".repeat((bucket.count * HISTOGRAM_WIDTH).div_ceil(max));
+        let percent = bucket.count as f64 * 100.0 / total as f64;
+        println!(
+            "{:>6} {:<width$} {:>5} ({:>5.1}%) {}",
+            bucket.kind,
+            bar,
+            bucket.count,
+            percent,
+            kind_name(bucket.kind),
+            width = HISTOGRAM_WIDTH
+        );
+        // 例として最新のイベントを1件だけ表示する
+        if let Some(example) = bucket.examples.first() {
+            let preview: String = example.content.chars().take(60).collect::<String>().replace('\n', " ");
+            println!("       例: {} {}", example.id.to_bech32()?, preview);
+        }
+    }
+    Ok(())
+}
+
+// npub / nprofile / hex / 別名（config の aliases）から公開鍵を求める関数
+fn resolve_pubkey(input: &str) -> Result<XOnlyPublicKey, Box<dyn std::error::Error>> {
+    let config = AppConfig::load()?;
+    let target = config.aliases.get(input).map(|s| s.as_str()).unwrap_or(input).trim_start_matches("nostr:");
+    if target.starts_with("nprofile1") {
+        return Ok(Nip19Profile::from_bech32(target)?.public_key);
+    }
+    XOnlyPublicKey::from_bech32(target)
+        .ok()
+        .or_else(|| XOnlyPublicKey::from_str(target).ok())
+        .ok_or_else(|| format!("公開鍵または別名を指定してください: {}", input).into())
+}
+
+// 相手の別名や NIP-05 が前と別の鍵を指していないか調べ、変わっていれば目立つように警告する（戻り値は変わった呼び名）
+fn check_peer_key(known: &mut KnownPeers, handles: &[String], peer: &XOnlyPublicKey) -> Result<Vec<KeyChange>, Box<dyn std::error::Error>> {
+    let changes = known.observe(handles, peer);
+    known.save()?;
+    if !changes.is_empty() {
+        eprintln!("⚠⚠⚠ 相手の鍵が変わりました ⚠⚠⚠");
+        for change in &changes {
+            eprintln!("  {} は以前 {} を指していました", change.handle, change.previous.to_bech32()?);
+        }
+        eprintln!("  今の鍵: {}", peer.to_bech32()?);
+        eprintln!("  なりすましの可能性があります。相手と安全番号を見比べ、本人と確かめてから fingerprint --trust で記録し直してください");
+    }
+    Ok(changes)
+}
+
+// 相手の公開鍵を指している呼び名（config の別名と、取得済みプロフィールの NIP-05）
+fn known_handles(peer: &XOnlyPublicKey) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    Ok(peer_handles(peer, &AppConfig::load()?.aliases, &ProfileCache::load().unwrap_or_default()))
+}
+
+// DMの相手との安全番号を表示する関数（相手の画面の安全番号と同じなら、途中で鍵はすり替えられていない）
+// 別名や NIP-05 が前と別の鍵を指していれば警告し、--trust で今の鍵を記録し直す
+pub fn fingerprint(matches: &ArgMatches) -> Result<(), Box<dyn std::error::Error>> {
+    let peer = resolve_pubkey(matches.get_one::<String>("npub").ok_or("相手が指定されていません")?)?;
+    let keys = read_keys()?;
+    if peer == keys.public_key() {
+        return Err("自分以外の公開鍵を指定してください".into());
+    }
+
+    let handles = known_handles(&peer)?;
+    let profiles = ProfileCache::load().unwrap_or_default();
+    let npub = peer.to_bech32()?;
+    match profiles.get(&peer).and_then(|p| p.label()) {
+        Some(label) => println!("相手: {} ({})", label, npub),
+        None => println!("相手: {}", npub),
+    }
+    let number = safety_number(&keys.public_key(), &peer);
+    println!("安全番号:");
+    println!("  {}", number.emoji_line());
+    println!("  {}", number.digits_line());
+    eprintln!("相手の画面にも同じ安全番号が出ていれば、途中で鍵はすり替えられていません（会ったときや別の連絡手段で見比べてください）");
+
+    let mut known = KnownPeers::load()?;
+    let changes = check_peer_key(&mut known, &handles, &peer)?;
+    if matches.get_flag("trust") {
+        known.trust(&handles, &peer);
+        known.save()?;
+        if changes.is_empty() {
+            eprintln!("この鍵を信頼済みとして記録しました");
+        } else {
+            eprintln!("{}件の呼び名を今の鍵で記録し直しました", changes.len());
+        }
+    }
+    Ok(())
+}
+
+// DMを送る関数（既定は自分の鍵で NIP-04 の kind 4 に暗号化する。本文は読めなくても、送信者・受信者・時刻はリレーから見える）
+// --gift-wrap では NIP-17 のギフトラップ（NIP-44 で暗号化して封印し、使い捨ての鍵で包む）で相手の受信用リレーに送る
+pub async fn dm_send(matches: &ArgMatches) -> Result<(), Box<dyn std::error::Error>> {
+    let peer = resolve_pubkey(matches.get_one::<String>("npub").ok_or("相手が指定されていません")?)?;
+    let content = matches.get_one::<String>("text").map(|text| text.trim()).unwrap_or_default();
+    if content.is_empty() {
+        return Err("本文が空です".into());
+    }
+    let changes = check_peer_key(&mut KnownPeers::load()?, &known_handles(&peer)?, &peer)?;
+    if !changes.is_empty() && !confirm("鍵が変わった相手に送信しますか？")? {
+        return Ok(());
+    }
+
+    let keys = unlock_keys()?;
+    let client = connect_client(&keys).await?;
+    // 相手が NIP-17 の受信用リレーを公開していても、--gift-wrap を付けなければ NIP-04（kind 4）で送る
+    let scheme = if matches.get_flag("gift-wrap") { DmScheme::GiftWrap } else { DmScheme::Nip04 };
+    if scheme == DmScheme::Nip04 && !fetch_dm_relays(&client, peer).await.unwrap_or_default().is_empty() {
+        eprintln!("相手は DM 受信用リレー（kind 10050）を公開しています。--gift-wrap を付けると送信者と時刻も隠せます");
+    }
+
+    let sent = match build_message_events(&keys, peer, content, scheme) {
+        Ok(events) => send_message_events(&client, events).await,
+        Err(e) => Err(e),
+    };
+    client.shutdown().await?;
+    let summaries: Vec<String> = sent?.iter().map(|receipt| receipt.summary()).collect();
+    eprintln!("{} にDMを送信しました（{}、{}）", peer.to_bech32()?, scheme.label(), summaries.join(" / "));
+    Ok(())
+}
+
+// DM受信用リレー（kind 10050）を表示・公開する関数（URL を指定すると、その一覧で置き換えて公開する）
+// 公開しておくと、NIP-17 に対応したクライアントはギフトラップをそのリレーに送ってくる
+pub async fn dm_relays(matches: &ArgMatches) -> Result<(), Box<dyn std::error::Error>> {
+    let urls: Vec<String> = matches.get_many::<String>("url").map(|urls| urls.cloned().collect()).unwrap_or_default();
+    if urls.is_empty() {
+        let keys = read_keys()?;
+        let client = connect_read_client(&keys).await?;
+        let relays = fetch_dm_relays(&client, keys.public_key()).await;
+        client.shutdown().await?;
+        let relays = relays?;
+        if relays.is_empty() {
+            println!("DM受信用リレーはまだ公開していません（dm relays <URL>... で公開すると、ギフトラップを受け取れることを相手に知らせます）");
+        }
+        for url in relays {
+            println!("{}", url);
+        }
+        return Ok(());
+    }
+
+    for url in &urls {
+        check_relay_url(url)?;
+    }
+    let keys = unlock_keys()?;
+    let client = connect_client(&keys).await?;
+    let published = match sign_event(dm_relays_builder(&urls)?, &keys) {
+        Ok(event) => publish(&client, event).await,
+        Err(e) => Err(e),
+    };
+    client.shutdown().await?;
+    eprintln!("DM受信用リレー{}件を公開しました（{}）", urls.len(), published?.summary());
+    Ok(())
+}
+
+// DMを復号して表示する関数（相手を省略すると会話の一覧、指定するとその相手とのメッセージを古い順に）
+pub async fn dm_list(matches: &ArgMatches) -> Result<(), Box<dyn std::error::Error>> {
+    let peer = matches.get_one::<String>("npub").map(|target| resolve_pubkey(target)).transpose()?;
+    let limit = *matches.get_one::<usize>("limit").unwrap_or(&20);
+
+    let keys = unlock_keys()?;
+    if keys.secret_key().is_err() {
+        return Err("DMの復号には秘密鍵が必要です（閲覧専用のアカウントや signer_command では読めません）".into());
+    }
+    let client = connect_read_client(&keys).await?;
+    let (messages, saved) = fetch_messages(&client, &keys).await?;
+    client.shutdown().await?;
+    if !saved {
+        eprintln!("{}", LOCK_HELD_NOTICE);
+    }
+
+    let profiles = ProfileCache::load().unwrap_or_default();
+    let name = |pubkey: &XOnlyPublicKey| {
+        let npub = pubkey.to_bech32().unwrap_or_else(|_| pubkey.to_string());
+        match profiles.get(pubkey).and_then(|p| p.label()) {
+            Some(label) => format!("{} ({})", label, npub),
+            None => npub,
+        }
+    };
+
+    let Some(peer) = peer else {
+        let conversations = group_conversations(messages, &std::collections::BTreeSet::new());
+        if conversations.is_empty() {
+            println!("DMはまだありません");
+        }
+        for conversation in conversations.iter().take(limit) {
+            let Some(last) = conversation.messages.last() else {
+                continue;
+            };
+            let preview: String = last.content.lines().next().unwrap_or_default().chars().take(40).collect();
+            println!(
+                "{} {} {}件 {}{}",
+                format_jst(last.created_at),
+                name(&conversation.peer),
+                conversation.messages.len(),
+                if last.outgoing { "→ " } else { "← " },
+                preview
+            );
+        }
+        return Ok(());
+    };
+
+    let conversation: Vec<_> = messages.into_iter().filter(|m| m.peer == peer).collect();
+    if conversation.is_empty() {
+        println!("{} とのメッセージはありません", name(&peer));
+        return Ok(());
+    }
+    eprintln!("{} との{}件のメッセージのうち新しい{}件", name(&peer), conversation.len(), limit.min(conversation.len()));
+    eprintln!("安全番号: {}", safety_number(&keys.public_key(), &peer).emoji_line());
+    check_peer_key(&mut KnownPeers::load()?, &known_handles(&peer)?, &peer)?;
+    for message in conversation.iter().skip(conversation.len().saturating_sub(limit)) {
+        let mark = if message.scheme == DmScheme::GiftWrap { "🔒" } else { "⚠" };
+        let direction = if message.outgoing { "→ 自分" } else { "← 相手" };
+        println!("{} {} {}", format_jst(message.created_at), mark, direction);
+        for line in message.content.lines() {
+            println!("    {}", line);
+        }
+    }
+    Ok(())
+}
+
+// 指定した相手との会話を復号し、パスフレーズで暗号化したファイル（age 形式）に書き出す関数
+// 復号した本文は平文のままディスクに書かない
+pub async fn dm_export(matches: &ArgMatches) -> Result<(), Box<dyn std::error::Error>> {
+    let peer = resolve_pubkey(matches.get_one::<String>("npub").ok_or("相手が指定されていません")?)?;
+    let output = matches.get_one::<String>("output").ok_or("出力先が指定されていません")?;
+    if std::path::Path::new(output).exists() && !confirm(&format!("{} は既にあります。上書きしますか？", output))? {
+        return Ok(());
+    }
+
+    let keys = unlock_keys()?;
+    let client = connect_read_client(&keys).await?;
+    let (messages, saved) = fetch_messages(&client, &keys).await?;
+    client.shutdown().await?;
+    if !saved {
+        eprintln!("{}", LOCK_HELD_NOTICE);
+    }
+    let messages: Vec<_> = messages.into_iter().filter(|m| m.peer == peer).collect();
+    if messages.is_empty() {
+        return Err("この相手とのメッセージが見つかりません".into());
+    }
+
+    let export = serde_json::json!({
+        "peer": peer.to_bech32()?,
+        "exported_at": Timestamp::now().as_u64(),
+        "messages": messages.iter().map(|m| serde_json::json!({
+            "id": m.id.to_hex(),
+            "from_me": m.outgoing,
+            "created_at": m.created_at.as_u64(),
+            "encryption": match m.scheme {
+                DmScheme::GiftWrap => "nip44-giftwrap",
+                DmScheme::Nip04 => "nip04",
+            },
+            "content": m.content,
+        })).collect::<Vec<_>>(),
+    });
+
+    let passphrase = prompt_password("エクスポートを暗号化するパスフレーズ: ")?;
+    if passphrase.is_empty() {
+        return Err("パスフレーズが空です".into());
+    }
+    if prompt_password("もう一度入力してください: ")? != passphrase {
+        return Err("パスフレーズが一致しません".into());
+    }
+
+    let encryptor = age::Encryptor::with_user_passphrase(age::secrecy::Secret::new(passphrase));
+    let mut encrypted = Vec::new();
+    let mut writer = encryptor.wrap_output(&mut encrypted)?;
+    writer.write_all(serde_json::to_string_pretty(&export)?.as_bytes())?;
+    writer.finish()?;
+    fs::write(output, encrypted)?;
+
+    eprintln!("{}件のメッセージを {} に書き出しました（age -d で復号できます）", messages.len(), output);
+    Ok(())
+}
+
+// 鍵・リレー・設定・連絡先・キャッシュを1つのファイルにまとめ、パスフレーズで暗号化して書き出す関数（別の端末に移す用）
+pub fn backup_create(matches: &ArgMatches) -> Result<(), Box<dyn std::error::Error>> {
+    let output = matches.get_one::<String>("output").ok_or("出力先が指定されていません")?;
+    if std::path::Path::new(output).exists() && !confirm(&format!("{} は既にあります。上書きしますか？", output))? {
+        return Ok(());
+    }
+
+    let (bundle, skipped) = Bundle::collect(&app_dir()?, Timestamp::now().as_u64())?;
+    if bundle.files.is_empty() {
+        return Err("バックアップするファイルがありません".into());
+    }
+    for name in &skipped {
+        eprintln!("テキストでないため含めません: {}", name);
+    }
+
+    let passphrase = prompt_password("バックアップを暗号化するパスフレーズ: ")?;
+    if passphrase.is_empty() {
+        return Err("パスフレーズが空です".into());
+    }
+    if prompt_password("もう一度入力してください: ")? != passphrase {
+        return Err("パスフレーズが一致しません".into());
+    }
+    fs::write(output, bundle.encrypt(passphrase)?)?;
+
+    eprintln!("{}件のファイルを {} に書き出しました（backup restore で戻せます）", bundle.files.len(), output);
+    eprintln!("OS のキーチェーンに保存した秘密鍵は含みません。移した先では keys.json のパスワードで署名できます");
+    Ok(())
+}
+
+// backup create で書き出したファイルを復号し、~/.nostr-cli-app に戻す関数
+pub fn backup_restore(matches: &ArgMatches) -> Result<(), Box<dyn std::error::Error>> {
+    let input = matches.get_one::<String>("input").ok_or("バックアップのファイルが指定されていません")?;
+    let encrypted = fs::read(input).map_err(|e| format!("{} を読み込めません: {}", input, e))?;
+    let bundle = Bundle::decrypt(&encrypted, prompt_password("バックアップのパスフレーズ: ")?)?;
+
+    let dir = app_dir()?;
+    let existing = bundle.existing_files(&dir);
+    if !existing.is_empty() {
+        eprintln!("次のファイルは今の内容を上書きします:");
+        for name in &existing {
+            eprintln!("  {}", name);
+        }
+        if !confirm(&format!("{}件のファイルを上書きして戻しますか？", existing.len()))? {
+            return Ok(());
+        }
+    }
+    let restored = bundle.restore(&dir)?;
+    eprintln!(
+        "{}件のファイルを戻しました（{} に作成したバックアップ）",
+        restored,
+        format_jst(Timestamp::from(bundle.created_at))
+    );
+    Ok(())
+}
+
+// 指定した相手との会話を、手元に保存したDMから削除する関数（リレー上のイベントは削除しない）
+pub fn dm_purge(matches: &ArgMatches) -> Result<(), Box<dyn std::error::Error>> {
+    let input = matches.get_one::<String>("npub").ok_or("相手が指定されていません")?;
+    let peer = resolve_pubkey(input)?;
+    if !confirm(&format!("{} との会話を手元のキャッシュから削除しますか？", input))? {
+        return Ok(());
+    }
+
+    // ギフトラップは復号しないと相手がわからないため鍵を使う
+    let keys = unlock_keys()?;
+    let removed = purge_cached_conversation(&keys, &peer)?;
+    println!("{}件のイベントを削除しました", removed);
+    Ok(())
+}
+
+// 合成イベントでフィードのキャッシュと投稿者のプロフィールを置き換える関数（開発用）
+pub fn dev_seed_cache(matches: &ArgMatches) -> Result<(), Box<dyn std::error::Error>> {
+    let count = *matches.get_one::<usize>("events").unwrap_or(&1000);
+    let authors = *matches.get_one::<usize>("authors").unwrap_or(&20);
+
+    let cached = load_cached_events()?.len();
+    if cached > 0 && !confirm(&format!("保存済みの{}件のイベントを合成イベントで置き換えますか？", cached))? {
+        return Ok(());
+    }
+
+    let (events, seeded_profiles) = seed_events(count, authors)?;
+    save_seeded_events(&events)?;
+    let mut profiles = ProfileCache::load()?;
+    profiles.profiles.extend(seeded_profiles);
+    profiles.save()?;
+
+    // kind ごとの件数と本文の大きさを表示する
+    for bucket in histogram(&events) {
+        println!("{:>6} {:>6}件 {}", bucket.kind, bucket.count, kind_name(bucket.kind));
+    }
+    let largest = events.iter().map(|e| e.content.len()).max().unwrap_or(0);
+    eprintln!("{}人の投稿者による{}件のイベントを保存しました（最大の本文 {}KB）", authors.max(1), events.len(), largest / 1024);
+    Ok(())
+}
+
+// パスワードで復号した秘密鍵を OS のキーチェーンに保存し、以後パスワードの入力を省く関数
+pub fn keyring_store(_matches: &ArgMatches) -> Result<(), Box<dyn std::error::Error>> {
+    password_attempts_notice()?;
+    let password = prompt_password("鍵を復号化するためのパスワードを入力してください: ")?;
+    let keys = load_keys(&password)?;
+    store_secret_key(keys.secret_key()?)?;
+    let mut config = AppConfig::load()?;
+    config.use_keyring = true;
+    config.save()?;
+    println!("秘密鍵をキーチェーンに保存しました（{}）", keys.public_key().to_bech32()?);
+    Ok(())
+}
+
+// キーチェーンから秘密鍵を削除し、パスワードの入力に戻す関数（keys.json はそのまま残る）
+pub fn keyring_remove(_matches: &ArgMatches) -> Result<(), Box<dyn std::error::Error>> {
+    let removed = delete_secret_key()?;
+    let mut config = AppConfig::load()?;
+    config.use_keyring = false;
+    config.save()?;
+    if removed {
+        println!("キーチェーンから秘密鍵を削除しました");
+    } else {
+        println!("キーチェーンに秘密鍵は保存されていません");
+    }
+    Ok(())
+}
+
+// 署名の記録を表示する関数（署名したイベントごとに、受理したリレーをまとめて新しい順に表示する）
+pub fn log_show(matches: &ArgMatches) -> Result<(), Box<dyn std::error::Error>> {
+    let limit = *matches.get_one::<usize>("limit").unwrap_or(&50);
+    let kind = matches.get_one::<u64>("kind").copied();
+
+    let mut signed: Vec<SigningEntry> = Vec::new();
+    let mut positions: std::collections::HashMap<String, usize> = std::collections::HashMap::new();
+    for entry in read_entries()? {
+        match entry.action {
+            SigningAction::Signed if kind.map_or(true, |k| k == entry.kind) => {
+                positions.insert(entry.id.clone(), signed.len());
+                signed.push(entry);
+            }
+            SigningAction::Signed => {}
+            SigningAction::Sent => {
+                let Some(&position) = positions.get(&entry.id) else {
+                    continue;
+                };
+                for relay in entry.relays {
+                    if !signed[position].relays.contains(&relay) {
+                        signed[position].relays.push(relay);
+                    }
+                }
+            }
+        }
+    }
+    if signed.is_empty() {
+        println!("署名の記録はまだありません");
+        return Ok(());
+    }
+
+    if matches.get_flag("json") {
+        for entry in signed.iter().rev().take(limit) {
+            println!("{}", serde_json::to_string(entry)?);
+        }
+        return Ok(());
+    }
+    eprintln!("署名したイベント {}件のうち新しい{}件（{}）", signed.len(), limit.min(signed.len()), audit_log_path()?.display());
+    for entry in signed.iter().rev().take(limit) {
+        let id = EventId::from_hex(&entry.id).ok().and_then(|id| id.to_bech32().ok()).unwrap_or_else(|| entry.id.clone());
+        println!("{} kind {} ({}) {}", format_jst(Timestamp::from(entry.created_at)), entry.kind, kind_name(entry.kind), id);
+        if entry.relays.is_empty() {
+            println!("    送信先: なし（送信していないか、どのリレーにも受理されていません）");
+        } else {
+            println!("    送信先: {}", entry.relays.join(", "));
+        }
+    }
+    Ok(())
+}
+
+// 前回の TUI のセッションの統計を表示する関数
+pub fn session_stats(_matches: &ArgMatches) -> Result<(), Box<dyn std::error::Error>> {
+    let Some(stats) = SessionStats::load_last()? else {
+        println!("セッションの統計はまだありません（TUI を終了すると記録されます）");
+        return Ok(());
+    };
+    if let Some(ended_at) = stats.ended_at {
+        println!("{} 〜 {}", format_jst(Timestamp::from(stats.started_at)), format_jst(Timestamp::from(ended_at)));
+    }
+    for line in stats.report() {
+        println!("{}", line);
+    }
+    Ok(())
+}
+
+// 連続投稿の記録を表示する関数（--remind でリマインダーの時刻を過ぎていれば通知する。cron などで定期的に呼ぶ用）
+pub fn streak(matches: &ArgMatches) -> Result<(), Box<dyn std::error::Error>> {
+    let streak = PostingStreak::load()?;
+    let now = Timestamp::now();
+    println!("{}", streak.summary(now));
+
+    if matches.get_flag("remind") {
+        let reminder = AppConfig::load()?
+            .post_reminder
+            .ok_or("リマインダーの時刻が設定されていません（config set post_reminder '\"21:00\"'）")?;
+        if reminder_due(&streak, parse_reminder_time(&reminder)?, now) {
+            notify_reminder(&format!("今日はまだ投稿していません（{}日連続が途切れます）", streak.current(now)));
+        }
+    }
+    Ok(())
+}
+
+// NIP-05 のアドレスに使う公開鍵（--pubkey がなければ鍵ファイルに記録した公開鍵。記録がなければ鍵を読み込む）
+fn nip05_pubkey(matches: &ArgMatches) -> Result<XOnlyPublicKey, Box<dyn std::error::Error>> {
+    if let Some(input) = matches.get_one::<String>("pubkey") {
+        return resolve_pubkey(input);
+    }
+    match recorded_public_key() {
+        Some(public_key) => Ok(public_key),
+        None => Ok(read_keys()?.public_key()),
+    }
+}
+
+// プロフィールの項目の値を確かめる関数（空の値は項目を消すので確かめない）
+fn validate_profile_field(field: &str, value: &str) -> Result<(), Box<dyn std::error::Error>> {
+    if value.is_empty() {
+        return Ok(());
+    }
+    match field {
+        "picture" | "banner" => {
+            let url = Url::parse(value).map_err(|e| format!("--{}: {}: {}", field, value, e))?;
+            if url.scheme() != "https" && url.scheme() != "http" {
+                return Err(format!("--{} には https:// で始まる画像の URL を指定してください: {}", field, value).into());
+            }
+        }
+        "nip05" => match value.split_once('@') {
+            Some((name, domain)) => {
+                validate_name(name)?;
+                validate_domain(domain)?;
+            }
+            None => {
+                validate_domain(value)?;
+            }
+        },
+        "lud16" => {
+            let (name, domain) = value
+                .split_once('@')
+                .filter(|(name, _)| !name.is_empty())
+                .ok_or_else(|| format!("--lud16 はライトニングアドレス（名前@ドメイン）で指定してください: {}", value))?;
+            validate_domain(domain).map_err(|e| format!("--lud16 {}@: {}", name, e))?;
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+// プロフィール（kind 0）の項目を書き換えて送信する関数（指定しなかった項目は今のプロフィールのまま残し、空の値を渡すと消す）
+pub async fn profile_set(matches: &ArgMatches) -> Result<(), Box<dyn std::error::Error>> {
+    let changes: Vec<(&str, String)> = PROFILE_FIELDS
+        .iter()
+        .filter_map(|field| matches.get_one::<String>(field).map(|value| (*field, value.trim().to_string())))
+        .collect();
+    if changes.is_empty() {
+        return Err("書き換える項目を指定してください（--name / --about / --picture / --nip05 / --lud16 / --banner）".into());
+    }
+    for (field, value) in &changes {
+        validate_profile_field(field, value)?;
+    }
+
+    let keys = unlock_keys()?;
+    let client = connect_client(&keys).await?;
+
+    // 今のプロフィールに重ねて書き換える（見つからないまま送ると、指定しなかった項目が消えてしまう）
+    let current = fetch_metadata_event(&client, keys.public_key()).await?;
+    if current.is_none() && !confirm("今のプロフィールがリレーに見つかりません。指定した項目だけで新しく作成しますか？")? {
+        client.shutdown().await?;
+        return Ok(());
+    }
+    let content = merge_metadata(current.as_ref().map_or("{}", |event| event.content.as_str()), &changes);
+    let event = sign_event(EventBuilder::new(Kind::Metadata, content, []), &keys)?;
+    let result = publish(&client, event.clone()).await;
+    client.shutdown().await?;
+    let receipt = result?;
+
+    let mut profiles = ProfileCache::load()?;
+    profiles.record(&event);
+    profiles.save()?;
+
+    for (field, value) in &changes {
+        if value.is_empty() {
+            println!("{}: （削除しました）", field);
+        } else {
+            println!("{}: {}", field, value);
+        }
+    }
+    eprintln!("プロフィールを送信しました（{}）", receipt.summary());
+    Ok(())
+}
+
+// プロフィール（kind 0）を取得して表示する関数（省略すると自分）
+pub async fn profile_show(matches: &ArgMatches) -> Result<(), Box<dyn std::error::Error>> {
+    let pubkey = match matches.get_one::<String>("npub") {
+        Some(target) => resolve_pubkey(target)?,
+        None => read_keys()?.public_key(),
+    };
+
+    // 閲覧だけなので使い捨ての鍵で接続する
+    let client = connect_read_client(&Keys::generate()).await?;
+    let event = fetch_metadata_event(&client, pubkey).await?;
+    client.shutdown().await?;
+    let Some(event) = event else {
+        return Err(format!("{} のプロフィールが見つかりません", pubkey.to_bech32()?).into());
+    };
+
+    let mut profiles = ProfileCache::load()?;
+    profiles.record(&event);
+    if let Err(e) = profiles.save() {
+        eprintln!("プロフィールを保存できません: {}", e);
+    }
+
+    if matches.get_flag("json") {
+        println!("{}", event.as_json());
+        return Ok(());
+    }
+    let metadata: serde_json::Value = serde_json::from_str(&event.content).unwrap_or_default();
+    println!("{}", pubkey.to_bech32()?);
+    for field in ["display_name", "name", "about", "picture", "banner", "website", "nip05", "lud16", "lud06"] {
+        if let Some(value) = metadata[field].as_str().map(str::trim).filter(|value| !value.is_empty()) {
+            println!("{}: {}", field, value);
+        }
+    }
+    println!("更新: {}", format_jst(event.created_at));
+    Ok(())
+}
+
+// 自分のドメインに置く .well-known/nostr.json を出力する関数（JSON は標準出力、置き方の説明は標準エラー出力）
+pub async fn nip05_generate(matches: &ArgMatches) -> Result<(), Box<dyn std::error::Error>> {
+    let name = validate_name(matches.get_one::<String>("name").map(|s| s.as_str()).unwrap_or("_"))?;
+    let domain = validate_domain(matches.get_one::<String>("domain").ok_or("ドメインが指定されていません")?)?;
+    let pubkey = nip05_pubkey(matches)?;
+    let relays = if matches.get_flag("relays") { load_relays()?.relays } else { Vec::new() };
+
+    println!("{}", serde_json::to_string_pretty(&well_known_json(&name, &pubkey, &relays))?);
+    eprintln!("上の JSON を https://{}/.well-known/nostr.json として配信してください", domain);
+    eprintln!("  - ブラウザのクライアントから読めるよう、Access-Control-Allow-Origin: * のヘッダーを付けてください");
+    eprintln!("  - リダイレクトさせずに返してください（NIP-05 のクライアントはリダイレクトを追いません）");
+    eprintln!("  - すでに nostr.json があれば、names（と relays）の項目を足してください");
+    eprintln!("配信したら nip05 verify --name {} --domain {} で確かめ、プロフィールの nip05 に {} を設定してください", name, domain, address(&name, &domain));
+    Ok(())
+}
+
+// 配信した .well-known/nostr.json が自分の公開鍵を指しているかを確かめる関数
+pub async fn nip05_verify(matches: &ArgMatches) -> Result<(), Box<dyn std::error::Error>> {
+    if let Some(target) = matches.get_one::<String>("npub") {
+        return nip05_verify_profile(target).await;
+    }
+    let name = validate_name(matches.get_one::<String>("name").map(|s| s.as_str()).unwrap_or("_"))?;
+    let domain = validate_domain(matches.get_one::<String>("domain").ok_or("ドメインが指定されていません")?)?;
+    let pubkey = nip05_pubkey(matches)?;
+
+    println!("{} を確認しています...", well_known_url(&name, &domain));
+    let check = fetch_well_known(&name, &domain).await?;
+    for warning in &check.warnings {
+        println!("⚠ {}", warning);
+    }
+    match check.pubkey {
+        Some(found) if found == pubkey => {
+            println!("✓ {} は {} を指しています", address(&name, &domain), pubkey.to_bech32()?);
+            if !check.relays.is_empty() {
+                println!("  リレー: {}", check.relays.join(", "));
+            }
+            Ok(())
+        }
+        Some(found) => Err(format!("{} は別の公開鍵（{}）を指しています", address(&name, &domain), found.to_bech32()?).into()),
+        None => Err(format!("nostr.json の names に {} がないか、公開鍵（hex）を解釈できません", name).into()),
+    }
+}
+
+// ほかの人のプロフィール（kind 0）の NIP-05 が、そのドメインの nostr.json で本人を指しているか確かめる関数
+async fn nip05_verify_profile(target: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let pubkey = resolve_pubkey(target)?;
+    let keys = read_keys()?;
+    let client = connect_read_client(&keys).await?;
+    let profile = fetch_metadata_event(&client, pubkey).await;
+    client.shutdown().await?;
+    let profile = profile?.ok_or_else(|| format!("プロフィールが見つかりません: {}", pubkey.to_bech32().unwrap_or_default()))?;
+    let metadata = Metadata::from_json(&profile.content)?;
+    let nip05 = metadata.nip05.filter(|n| !n.trim().is_empty()).ok_or("プロフィールに NIP-05 のアドレスがありません")?;
+    let (name, domain) = parse_address(&nip05)?;
+
+    println!("{} を確認しています...", well_known_url(&name, &domain));
+    let check = fetch_well_known(&name, &domain).await?;
+    let verified = check.pubkey == Some(pubkey);
+    let mut profiles = ProfileCache::load()?;
+    profiles.record(&profile);
+    if let Some(cached) = profiles.profiles.get_mut(&pubkey.to_string()) {
+        cached.nip05_verified = verified;
+        cached.nip05_checked_at = Timestamp::now().as_u64();
+    }
+    profiles.save()?;
+
+    match check.pubkey {
+        Some(_) if verified => {
+            println!("✓ {} は {} を指しています", address(&name, &domain), pubkey.to_bech32()?);
+            Ok(())
+        }
+        Some(found) => Err(format!("{} は別の公開鍵（{}）を指しています。なりすましの可能性があります", nip05, found.to_bech32()?).into()),
+        None => Err(format!("{} の nostr.json に {} が見つかりません", domain, name).into()),
+    }
+}
+
+// NIP-05 のアドレスから公開鍵とリレーのヒントを調べる関数
+pub async fn nip05_resolve(matches: &ArgMatches) -> Result<(), Box<dyn std::error::Error>> {
+    let identifier = matches.get_one::<String>("address").ok_or("アドレスが指定されていません")?;
+    let (name, domain) = parse_address(identifier)?;
+    let check = fetch_well_known(&name, &domain).await?;
+    for warning in &check.warnings {
+        eprintln!("⚠ {}", warning);
+    }
+    let pubkey = check.pubkey.ok_or_else(|| format!("{} の nostr.json に {} が見つかりません", domain, name))?;
+    println!("{}", pubkey.to_bech32()?);
+    for relay in &check.relays {
+        println!("リレー: {}", relay);
+    }
+    Ok(())
+}
+
+// 下書きの一覧を表示する関数（新しく編集した順）
+pub fn drafts_list(_matches: &ArgMatches) -> Result<(), Box<dyn std::error::Error>> {
+    let store = DraftStore::load()?;
+    let drafts = store.active();
+    if drafts.is_empty() {
+        println!("下書きはありません");
+        return Ok(());
+    }
+    for draft in drafts {
+        println!("{}  {}  {}", draft.short_id(), format_jst(Timestamp::from(draft.updated_at)), draft.title());
+    }
+    Ok(())
+}
+
+// 下書きを表示する関数
+pub fn drafts_show(matches: &ArgMatches) -> Result<(), Box<dyn std::error::Error>> {
+    let id = matches.get_one::<String>("id").ok_or("下書きのIDが指定されていません")?;
+    let store = DraftStore::load()?;
+    let draft = store.find(id).ok_or_else(|| format!("下書きが見つかりません: {}", id))?;
+    if !draft.subject.is_empty() {
+        println!("件名: {}", draft.subject);
+    }
+    if !draft.hashtags.is_empty() {
+        println!("ハッシュタグ: {}", draft.hashtags);
+    }
+    println!("{}", draft.content);
+    Ok(())
+}
+
+// 下書きを保存する関数（sync_drafts が有効ならリレーにも保存する）
+pub async fn drafts_save(matches: &ArgMatches) -> Result<(), Box<dyn std::error::Error>> {
+    let content = matches.get_one::<String>("content").ok_or("本文が指定されていません")?;
+    let subject = matches.get_one::<String>("subject").cloned().unwrap_or_default();
+    let hashtags = matches
+        .get_many::<String>("hashtag")
+        .map(|values| values.cloned().collect::<Vec<_>>().join(" "))
+        .unwrap_or_default();
+    let mut store = DraftStore::load()?;
+    let draft = Draft::new(content, &subject, &hashtags);
+    println!("下書きを保存しました: {}", draft.short_id());
+    store.upsert(draft);
+    store.save()?;
+    sync_drafts_if_enabled(&mut store).await
+}
+
+// 下書きを削除する関数（ほかの端末にも削除を伝える）
+pub async fn drafts_delete(matches: &ArgMatches) -> Result<(), Box<dyn std::error::Error>> {
+    let id = matches.get_one::<String>("id").ok_or("下書きのIDが指定されていません")?;
+    let mut store = DraftStore::load()?;
+    let draft = store.find(id).cloned().ok_or_else(|| format!("下書きが見つかりません: {}", id))?;
+    store.delete(&draft.id);
+    store.save()?;
+    println!("下書きを削除しました: {}", draft.short_id());
+    sync_drafts_if_enabled(&mut store).await
+}
+
+// 手元とリレーの下書きを同期する関数（同じ下書きは最後に編集した方を残す）
+pub async fn drafts_sync(_matches: &ArgMatches) -> Result<(), Box<dyn std::error::Error>> {
+    let mut store = DraftStore::load()?;
+    run_drafts_sync(&mut store).await
+}
+
+// sync_drafts が有効なときだけ同期する
+async fn sync_drafts_if_enabled(store: &mut DraftStore) -> Result<(), Box<dyn std::error::Error>> {
+    if !AppConfig::load()?.sync_drafts {
+        return Ok(());
+    }
+    run_drafts_sync(store).await
+}
+
+async fn run_drafts_sync(store: &mut DraftStore) -> Result<(), Box<dyn std::error::Error>> {
+    let keys = unlock_keys()?;
+    let client = connect_client(&keys).await?;
+    let report = sync_drafts(&client, &keys, store).await?;
+    client.shutdown().await?;
+    println!("下書きを同期しました（受信 {}件 / 送信 {}件）", report.downloaded, report.uploaded);
+    Ok(())
+}
+
+// 鍵のエージェントを起動する関数（パスワードで復号した鍵を渡し、裏で動かしたまま戻る）
+pub fn agent_start(matches: &ArgMatches) -> Result<(), Box<dyn std::error::Error>> {
+    if let Ok(status) = agent_status() {
+        return Err(format!(
+            "エージェントはすでに動いています（アカウント {}、{} まで）。agent stop で止めてから起動してください",
+            status.account,
+            format_jst(Timestamp::from(status.expires_at))
+        )
+        .into());
+    }
+    let lifetime = parse_duration(matches.get_one::<String>("timeout").map(|s| s.as_str()).unwrap_or("1h"))?;
+    if external_signer() {
+        return Err("signer_command / bunker_uri を設定しているときは、秘密鍵を持たないのでエージェントは使えません".into());
+    }
+
+    let account = active_account()?;
+    let keys = unlock_keys()?;
+    let secret_key = keys.secret_key()?.display_secret().to_string();
+
+    // 自分自身を agent serve として起動し、鍵は引数ではなく標準入力で渡す（ps などから見えないように）
+    let mut child = std::process::Command::new(std::env::current_exe()?)
+        .args(["--account", &account, "agent", "serve", "--timeout", &lifetime.to_string()])
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        // 端末で Ctrl-C を押しても一緒に止まらないよう、別のプロセスグループにする
+        .process_group(0)
+        .spawn()?;
+    let mut stdin = child.stdin.take().ok_or("エージェントの標準入力を開けません")?;
+    stdin.write_all(format!("{}\n", secret_key).as_bytes())?;
+    drop(stdin);
+
+    // ソケットが開くまで少し待つ
+    for _ in 0..20 {
+        if let Ok(status) = agent_status() {
+            println!(
+                "エージェントを起動しました（アカウント {}、{} まで）。この間はパスワードを入力せずに使えます",
+                status.account,
+                format_jst(Timestamp::from(status.expires_at))
+            );
+            return Ok(());
+        }
+        std::thread::sleep(std::time::Duration::from_millis(100));
+    }
+    Err("エージェントを起動できませんでした".into())
+}
+
+// agent start から起動され、標準入力で受け取った鍵を持って常駐する関数
+pub async fn agent_serve(matches: &ArgMatches) -> Result<(), Box<dyn std::error::Error>> {
+    let lifetime = *matches.get_one::<u64>("timeout").ok_or("期限が指定されていません")?;
+    let mut line = String::new();
+    std::io::stdin().read_line(&mut line)?;
+    let keys = Keys::new(SecretKey::from_str(line.trim())?);
+    run_agent(active_account()?, keys, std::time::Duration::from_secs(lifetime)).await
+}
+
+// 動いているエージェントの状態を表示する関数
+pub fn agent_status_command(_matches: &ArgMatches) -> Result<(), Box<dyn std::error::Error>> {
+    match agent_status() {
+        Ok(status) => println!("エージェントは動いています（アカウント {}、{} まで）", status.account, format_jst(Timestamp::from(status.expires_at))),
+        Err(_) => println!("エージェントは動いていません（agent start で起動）"),
+    }
+    Ok(())
+}
+
+// エージェントを止め、持っていた鍵を消す関数
+pub fn agent_stop(_matches: &ArgMatches) -> Result<(), Box<dyn std::error::Error>> {
+    stop_agent().map_err(|e| format!("エージェントは動いていません: {}", e))?;
+    println!("エージェントを止めました");
+    Ok(())
+}
+
+// 鍵を保存しているアカウントの一覧を表示する関数（* は使用中のアカウント）
+pub fn account_list(_matches: &ArgMatches) -> Result<(), Box<dyn std::error::Error>> {
+    let active = active_account()?;
+    let names = list_accounts()?;
+    if names.is_empty() {
+        println!("鍵を保存しているアカウントはありません（generate-keys または import-key で作成してください）");
+        return Ok(());
+    }
+    for name in names {
+        let path = account_dir(&name)?.join("keys.json");
+        let key_file = read_key_file_at(&path).ok();
+        let read_only = key_file.as_ref().map_or(false, |key_file| !key_file.has_secret_key());
+        let public_key = key_file
+            .and_then(|key_file| key_file.public_key)
+            .unwrap_or_else(|| "（公開鍵は次に鍵を読み込んだときに記録されます）".to_string());
+        let mark = if name == active { "*" } else { " " };
+        println!("{} {:<16} {}{}", mark, name, public_key, if read_only { "（閲覧専用）" } else { "" });
+    }
+    Ok(())
+}
+
+// 既定で使うアカウントを切り替える関数
+pub fn account_switch(matches: &ArgMatches) -> Result<(), Box<dyn std::error::Error>> {
+    let name = matches.get_one::<String>("name").ok_or("アカウント名が指定されていません")?;
+    switch_account(name)?;
+    println!("アカウント {} に切り替えました", name);
+    Ok(())
+}
+
+// アカウントの鍵を削除する関数（キーチェーンに保存した秘密鍵も削除する）
+pub fn account_remove(matches: &ArgMatches) -> Result<(), Box<dyn std::error::Error>> {
+    let name = matches.get_one::<String>("name").ok_or("アカウント名が指定されていません")?;
+    if name == DEFAULT_ACCOUNT {
+        return Err("default アカウントは削除できません".into());
+    }
+    if !confirm(&format!("アカウント {} の鍵を削除しますか？（復元できません）", name))? {
+        return Ok(());
+    }
+    remove_account(name)?;
+    if let Err(e) = delete_account_secret_key(name) {
+        eprintln!("{}", e);
+    }
+    println!("アカウント {} を削除しました（使用中のアカウント: {}）", name, active_account()?);
+    Ok(())
+}
+
+// 投稿の共有用リンクを表示する関数（nevent と njump には、イベントを持っているリレーをヒントとして含める）
+pub async fn permalink(matches: &ArgMatches) -> Result<(), Box<dyn std::error::Error>> {
+    let input = matches.get_one::<String>("event-id").ok_or("イベントIDが指定されていません")?;
+    let event_id = parse_event_id(input)?;
+    let format = PermalinkFormat::parse(matches.get_one::<String>("format").map_or("nevent", |s| s.as_str()))?;
+
+    let relays = if format == PermalinkFormat::Hex {
+        Vec::new()
+    } else {
+        // 閲覧だけなので使い捨ての鍵で接続する
+        let client = connect_read_client(&Keys::generate()).await?;
+        let relays = find_event_relays(&client, event_id).await;
+        client.shutdown().await?;
+        if relays.is_empty() {
+            eprintln!("イベントを持っているリレーが見つからないため、リレーのヒントなしで作成します");
+        }
+        relays
+    };
+
+    let link = build_permalink(event_id, &relays, format)?;
+    println!("{}", link);
+    if matches.get_flag("copy") {
+        copy_to_clipboard(&link)?;
+        eprintln!("クリップボードにコピーしました（端末が OSC 52 に対応している場合）");
+    }
+    Ok(())
+}
+
+// キャッシュの検索で探す語（合成イベントの本文に含まれる）
+const BENCH_SEARCH_TERM: &str = "リレー";
+
+// リレーからの取得・キャッシュの検索・TUIの描画の時間を計測して表示する関数
+// 同じデータで比べられるよう、先に dev seed-cache でキャッシュを用意しておく
+pub async fn bench(matches: &ArgMatches) -> Result<(), Box<dyn std::error::Error>> {
+    let runs = (*matches.get_one::<usize>("runs").unwrap_or(&5)).max(1);
+    let frames = (*matches.get_one::<usize>("frames").unwrap_or(&200)).max(1);
+    let limit = *matches.get_one::<usize>("limit").unwrap_or(&500);
+    let mut results: Vec<BenchResult> = Vec::new();
+
+    // キャッシュ: 読み込みと、よく使う絞り込み・並べ替え
+    let events = load_cached_events()?;
+    if events.is_empty() {
+        return Err("キャッシュにイベントがありません。先に dev seed-cache を実行してください".into());
+    }
+    eprintln!("キャッシュの{}件のイベントで計測しています...", events.len());
+    results.push(summarize("キャッシュの読み込み", &time_runs(runs, load_cached_events), Some(events.len())));
+    let author = events[0].pubkey;
+    results.push(summarize(
+        "投稿者で絞り込み",
+        &time_runs(runs, || events.iter().filter(|e| e.pubkey == author).count()),
+        Some(events.len()),
+    ));
+    results.push(summarize(
+        "本文の検索",
+        &time_runs(runs, || events.iter().filter(|e| e.content.contains(BENCH_SEARCH_TERM)).count()),
+        Some(events.len()),
+    ));
+    let no_counts = std::collections::HashMap::new();
+    let no_scores = std::collections::HashMap::new();
+    for sort in [FeedSort::Newest, FeedSort::Oldest, FeedSort::Longest] {
+        let samples = time_runs(runs, || {
+            let mut sorted = events.clone();
+            sort_events(&mut sorted, sort, &no_counts, &no_scores);
+            sorted
+        });
+        results.push(summarize(&format!("並べ替え（{}）", sort.label()), &samples, Some(events.len())));
+    }
+
+    // TUI: 画面に出さずに描画する
+    eprintln!("描画を{}回計測しています...", frames);
+    let (list_frames, detail_frames) = crate::tui_app::bench_render(events.clone(), frames, 120, 40)?;
+    results.push(summarize("描画（一覧）", &list_frames, None));
+    results.push(summarize("描画（詳細・最大の本文）", &detail_frames, None));
+
+    // リレー: 読み込み用のリレーから取得する（閲覧だけなので使い捨ての鍵で接続する）
+    if !matches.get_flag("offline") {
+        eprintln!("リレーから{}件ずつ{}回取得しています...", limit, runs);
+        let client = connect_read_client(&Keys::generate()).await?;
+        let filter = Filter::new().kind(Kind::TextNote).limit(limit);
+        let mut samples = Vec::with_capacity(runs);
+        let mut fetched = 0;
+        for _ in 0..runs {
+            let started = std::time::Instant::now();
+            fetched += client.get_events_of(vec![filter.clone()], Some(std::time::Duration::from_secs(10))).await?.len();
+            samples.push(started.elapsed());
+        }
+        client.shutdown().await?;
+        results.push(summarize("リレーからの取得", &samples, Some(fetched / runs)));
+    }
+
+    if matches.get_flag("json") {
+        println!("{}", serde_json::to_string_pretty(&results)?);
+    } else {
+        print_report(&results);
+    }
     Ok(())
 }
 
 // 「うぃビームだころせ」効果音を再生する関数
 pub async fn play_uibeam(_matches: &ArgMatches) -> Result<(), Box<dyn std::error::Error>> {
-    println!("「うぃビームだころせ」を再生します...");
+    eprintln!("「うぃビームだころせ」を再生します...");
 
-    // 音声ファイルのURL
-    let url = "https://leiros.cloudfree.jp/usbtn/sound/uibeamdakorose.mp3";
+    // 音声ファイルのURL（メディアプロキシの設定に従う）
+    let url = resolve_media_url("https://leiros.cloudfree.jp/usbtn/sound/uibeamdakorose.mp3")?;
 
     // URLからのリクエストにUser-Agentを追加
-    println!("音声ファイルをダウンロード中...");
+    eprintln!("音声ファイルをダウンロード中...");
     let client = reqwest::Client::new();
-    let response = client.get(url)
+    let response = client.get(&url)
         .header("User-Agent", "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/91.0.4472.124 Safari/537.36")
         .header("Referer", "https://leiros.cloudfree.jp/usbtn/usbtn.html")
         .send()
@@ -352,7 +4444,7 @@ pub async fn play_uibeam(_matches: &ArgMatches) -> Result<(), Box<dyn std::error
 
     // 以下は元のコード
     let bytes = response.bytes().await?;
-    println!("ダウンロード完了: {}バイト", bytes.len());
+    eprintln!("ダウンロード完了: {}バイト", bytes.len());
 
     if bytes.len() < 100 {
         return Err("ダウンロードされたデータが小さすぎます".into());
@@ -362,28 +4454,28 @@ pub async fn play_uibeam(_matches: &ArgMatches) -> Result<(), Box<dyn std::error
     let cursor = Cursor::new(bytes);
 
     // 出力デバイスを取得
-    println!("オーディオデバイスを初期化中...");
+    eprintln!("オーディオデバイスを初期化中...");
     let (_stream, stream_handle) = OutputStream::try_default()?;
     let sink = Sink::try_new(&stream_handle)?;
 
     // データをデコードして再生
-    println!("音声データをデコード中...");
+    eprintln!("音声データをデコード中...");
     let source = match Decoder::new(cursor) {
         Ok(s) => s,
         Err(e) => {
-            println!("デコードエラー詳細: {:?}", e);
+            eprintln!("デコードエラー詳細: {:?}", e);
             return Err("音声データのデコードに失敗しました。MP3コーデッ���が利用可能か確認してください。".into());
         }
     };
 
     sink.append(source);
 
-    println!("再生中...");
+    eprintln!("再生中...");
 
     // 再生完了まで待機
     sink.sleep_until_end();
 
-    println!("再生完了！");
+    eprintln!("再生完了！");
     Ok(())
 }
 
@@ -0,0 +1,171 @@
+// 投稿の組み立て - 本文に件名（NIP-14）・ハッシュタグ・メンションのタグを付ける
+use nostr_sdk::prelude::*;
+use regex::{Captures, Regex};
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+// 送信する投稿の内容
+#[derive(Debug, Clone, Default)]
+pub struct NoteDraft {
+    pub content: String,
+    pub subject: Option<String>,
+    pub hashtags: Vec<String>,
+    // 本文中の #ハッシュタグ も t タグにするか
+    pub auto_hashtags: bool,
+    // 本文中でメンションした相手（apply_mentions で設定する）
+    pub mentions: Vec<XOnlyPublicKey>,
+}
+
+impl NoteDraft {
+    // 投稿に付けるタグを作成する
+    pub fn tags(&self) -> Result<Vec<Tag>, Box<dyn std::error::Error>> {
+        let mut tags = Vec::new();
+        if let Some(subject) = self.subject.as_deref().map(str::trim).filter(|s| !s.is_empty()) {
+            tags.push(Tag::parse(vec!["subject".to_string(), subject.to_string()])?);
+        }
+        for hashtag in self.all_hashtags() {
+            tags.push(Tag::parse(vec!["t".to_string(), hashtag])?);
+        }
+        // メンションした相手に通知が届くよう p タグを付ける
+        for pubkey in &self.mentions {
+            tags.push(Tag::parse(vec!["p".to_string(), pubkey.to_string()])?);
+        }
+        Ok(tags)
+    }
+
+    // 本文中のメンションを nostr: URI に揃え、メンションした相手を記録する
+    // 戻り値は別名が見つからなかった @名前 の一覧
+    pub fn apply_mentions(&mut self, aliases: &BTreeMap<String, String>) -> Vec<String> {
+        let mut unknown = Vec::new();
+
+        // @別名 と @npub1... を nostr: URI に置き換える（メールアドレスは対象外）
+        let at_re = Regex::new(r"(^|[^\w@])@([\w.-]*\w)").expect("メンションの正規表現が正しくありません");
+        let content = at_re
+            .replace_all(&self.content, |caps: &Captures| {
+                let name = &caps[2];
+                let target = if name.starts_with("npub1") || name.starts_with("nprofile1") {
+                    Some(name.to_string())
+                } else {
+                    aliases.get(name).cloned()
+                };
+                match target {
+                    Some(target) => format!("{}nostr:{}", &caps[1], target.trim_start_matches("nostr:")),
+                    None => {
+                        unknown.push(name.to_string());
+                        caps[0].to_string()
+                    }
+                }
+            })
+            .into_owned();
+
+        // nostr:npub1... / nostr:nprofile1... から公開鍵を取り出す
+        let uri_re = Regex::new(r"nostr:((?:npub1|nprofile1)[02-9ac-hj-np-z]+)").expect("メンションの正規表現が正しくありません");
+        for caps in uri_re.captures_iter(&content) {
+            let pubkey = mention_pubkey(&caps[1]);
+            if let Some(pubkey) = pubkey.filter(|pk| !self.mentions.contains(pk)) {
+                self.mentions.push(pubkey);
+            }
+        }
+
+        self.content = content;
+        unknown
+    }
+
+    // 指定されたハッシュタグと本文から抜き出したハッシュタグ（正規化・重複除去済み）
+    pub fn all_hashtags(&self) -> Vec<String> {
+        let extracted = if self.auto_hashtags { extract_hashtags(&self.content) } else { Vec::new() };
+        let mut hashtags: Vec<String> = Vec::new();
+        for tag in self.hashtags.iter().chain(extracted.iter()) {
+            let tag = normalize_hashtag(tag);
+            if !tag.is_empty() && !hashtags.contains(&tag) {
+                hashtags.push(tag);
+            }
+        }
+        hashtags
+    }
+}
+
+// npub / nprofile から公開鍵を取り出す
+fn mention_pubkey(bech32: &str) -> Option<XOnlyPublicKey> {
+    if bech32.starts_with("nprofile1") {
+        Nip19Profile::from_bech32(bech32).ok().map(|profile| profile.public_key)
+    } else {
+        XOnlyPublicKey::from_bech32(bech32).ok()
+    }
+}
+
+// ハッシュタグを正規化する（# を外して小文字にする）
+// 他のクライアントは小文字の t タグで検索するため、"#Nostr" も "nostr" として付ける
+pub fn normalize_hashtag(tag: &str) -> String {
+    tag.trim().trim_start_matches('#').to_lowercase()
+}
+
+// 本文から #ハッシュタグ を抜き出す
+// URLの "page#section" や "&#123;" を拾わないよう、直前が文字・記号でない位置だけを対象にし、
+// 数字だけのもの（"#1" など）は除く
+pub fn extract_hashtags(content: &str) -> Vec<String> {
+    let re = Regex::new(r"(?:^|[^\w#&/])#([\w]+)").expect("ハッシュタグの正規表現が正しくありません");
+    re.captures_iter(content)
+        .map(|caps| caps[1].to_string())
+        .filter(|tag| !tag.chars().all(|c| c.is_ascii_digit()))
+        .collect()
+}
+
+// "nostr, #rust 日記" のような入力をハッシュタグの一覧にする（# は省略可、重複は除く）
+pub fn parse_hashtag_list(input: &str) -> Vec<String> {
+    let mut hashtags: Vec<String> = Vec::new();
+    for word in input.split(|c: char| c.is_whitespace() || c == ',' || c == '、') {
+        let tag = word.trim().trim_start_matches('#');
+        if !tag.is_empty() && !hashtags.iter().any(|t| t == tag) {
+            hashtags.push(tag.to_string());
+        }
+    }
+    hashtags
+}
+
+// 返信に付ける e タグ（NIP-10 のマーカー付き）。返信先がスレッドの最初の投稿なら root だけ、
+// そうでなければ返信先のスレッドの root と、返信先そのものを reply として付ける
+pub fn reply_tags(parent: &Event, relay: &str) -> Result<Vec<Tag>, Box<dyn std::error::Error>> {
+    let e_tags: Vec<Vec<String>> = parent
+        .tags
+        .iter()
+        .map(|tag| tag.as_vec())
+        .filter(|values| values.first().map(|k| k.as_str()) == Some("e") && values.len() >= 2)
+        .collect();
+    // マーカーのある root、なければ古い書き方（位置で決まる）の先頭の e タグ
+    let root = e_tags
+        .iter()
+        .find(|values| values.get(3).map(|m| m.as_str()) == Some("root"))
+        .or_else(|| e_tags.iter().find(|values| values.get(3).map_or(true, |m| m.is_empty())))
+        .filter(|values| EventId::from_hex(&values[1]).is_ok());
+
+    let parent_id = parent.id.to_hex();
+    Ok(match root {
+        Some(root) => vec![
+            Tag::parse(vec![
+                "e".to_string(),
+                root[1].clone(),
+                root.get(2).cloned().unwrap_or_default(),
+                "root".to_string(),
+            ])?,
+            Tag::parse(vec!["e".to_string(), parent_id, relay.to_string(), "reply".to_string()])?,
+        ],
+        None => vec![Tag::parse(vec!["e".to_string(), parent_id, relay.to_string(), "root".to_string()])?],
+    })
+}
+
+// 返信で通知する相手（返信先の p タグの全員と、返信先の投稿者）
+pub fn thread_pubkeys(parent: &Event) -> Vec<XOnlyPublicKey> {
+    let mut pubkeys = vec![parent.pubkey];
+    for values in parent.tags.iter().map(|tag| tag.as_vec()) {
+        if values.first().map(|k| k.as_str()) != Some("p") {
+            continue;
+        }
+        if let Some(pubkey) = values.get(1).and_then(|hex| XOnlyPublicKey::from_str(hex).ok()) {
+            if !pubkeys.contains(&pubkey) {
+                pubkeys.push(pubkey);
+            }
+        }
+    }
+    pubkeys
+}
@@ -0,0 +1,151 @@
+// アプリ設定 - config.json の読み書き
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use crate::commands::app_dir;
+use crate::redact::{default_rules, RedactRule};
+use crate::retention::{default_rules as default_retention_rules, RetentionRule};
+use crate::storage::{read_json, write_json};
+
+// アプリ全体の設定（項目を増やすときは Default も更新する）
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct AppConfig {
+    // 自分の投稿への返信をスパムとみなす正規表現
+    pub spam_patterns: Vec<String>,
+    // true: 一致した返信を自動で通報・ミュート / false: 審査待ちキューに積むだけ
+    pub auto_moderation: bool,
+    // 翻訳版があるときに優先して表示する言語（ISO 639-1、未設定なら常に元の投稿）
+    pub preferred_language: Option<String>,
+    // メディア取得に使うプロキシ（例: "https://imgproxy.example/insecure/plain/"、{url} で埋め込み位置を指定）
+    pub media_proxy: Option<String>,
+    // プロキシを通さず直接取得するドメイン
+    pub media_allow_domains: Vec<String>,
+    // 取得しないドメイン（許可リストより優先）
+    pub media_deny_domains: Vec<String>,
+    // TUIのようこそ画面を閉じたことがあるか
+    pub tui_onboarded: bool,
+    // TUIの起動時に Happy Mac の起動画面（初期化の進み具合）を表示するか（キーを押せば飛ばせる）
+    pub boot_screen: bool,
+    // TUIの終了時に「It is now safe to turn off your client」の画面を表示するか
+    pub shutdown_screen: bool,
+    // 本文中の #ハッシュタグ を自動で t タグにするか
+    pub auto_hashtags: bool,
+    // 投稿で @別名 と書いたときに置き換える相手（別名 → npub）
+    pub aliases: BTreeMap<String, String>,
+    // send --location で値を省略したときに使う位置（ジオハッシュ）
+    pub home_geohash: Option<String>,
+    // 読み込みだけのコマンドで使うリレーの数（速い順。投稿は登録済みのすべてのリレーに送る）
+    pub read_relay_count: usize,
+    // 同時に接続するリレーの上限（多数のリレーが落ちているときに再接続が殺到しないようにする）
+    pub max_relay_connections: usize,
+    // TUIのイベント一覧で投稿者ごとにプロフィールカード（表示名・NIP-05・フォロワーか）を表示するか
+    pub profile_cards: bool,
+    // tui --public-terminal で伏せる公開鍵（npub または hex。使用中のアカウントの公開鍵は指定しなくても伏せる）
+    pub public_terminal_redact: Vec<String>,
+    // DMで入力中・既読を通知するか（相手とやりとりしていることが公開されるため既定は無効）
+    pub dm_receipts: bool,
+    // 入力中・既読の通知に対応している相手（hex。通知が届いた相手は自動で追加される）
+    pub dm_receipt_peers: Vec<String>,
+    // 並び順 plugin で使うコマンド（標準入力のイベントに「ID スコア」の行でスコアを付ける）
+    pub score_command: Option<String>,
+    // 公開したイベントを複製して送る個人用のアーカイブリレー（例: "wss://my-private-relay"）
+    pub backup_relay: Option<String>,
+    // 表示するイベントの ID と署名を手元で計算し直し、合わないものを捨てるか
+    pub strict_verification: bool,
+    // 秘密鍵を OS のキーチェーンから読み込み、パスワードの入力を省くか（keyring store で有効になる）
+    pub use_keyring: bool,
+    // フィードに含める kind（例: [1, 6, 30023] でリポストと長文記事も一覧に表示する）
+    pub feed_kinds: Vec<u64>,
+    // 既定で使うアカウント（accounts/<名前>/keys.json、None なら以前からの keys.json）
+    pub active_account: Option<String>,
+    // 投稿の下書きを暗号化してリレーにも保存し、ほかの端末と同期する（NIP-37）
+    pub sync_drafts: bool,
+    // ノートパッドを暗号化してリレーにも保存し、ほかの端末と同期する（NIP-78 のアプリデータ）
+    pub sync_notepad: bool,
+    // 署名を任せる外部のプログラム（標準入力に未署名のイベント、標準出力に署名済みのイベントの JSON）。設定すると秘密鍵を読み込まない
+    pub signer_command: Option<String>,
+    // 署名を任せる NIP-46 の署名サーバー（"bunker://<公開鍵>?relay=wss://...&secret=..."）。設定すると秘密鍵を読み込まない
+    pub bunker_uri: Option<String>,
+    // この時刻（日本時間の "HH:MM"）を過ぎても今日まだ投稿していなければ、デスクトップ通知とベルで知らせる
+    pub post_reminder: Option<String>,
+    // 秘密鍵を暗号化するときの鍵の導出方式（"scrypt" は NIP-49 の ncryptsec、"argon2id" は下のパラメータの Argon2id）
+    pub key_kdf: String,
+    // Argon2id で使うメモリ（KiB）と反復回数（変えると次に鍵を読み込んだときに暗号化し直す）
+    pub argon2_memory_kib: u32,
+    pub argon2_iterations: u32,
+    // 複数列のレイアウト（:columns）に並べる列（"home" / "mentions" / "#タグ" / "search:語" / "from:npub"）
+    pub columns: Vec<String>,
+    // パスワードをこの回数続けて間違えたら、password_lockout_minutes の間は鍵を読み込まない（0 でロックしない）
+    pub password_lockout_attempts: u32,
+    pub password_lockout_minutes: u64,
+    // zap で発行したインボイスを支払うコマンド（インボイスは $1 で渡す。例: "lncli payinvoice --force $1"）
+    pub zap_pay_command: Option<String>,
+    // TUI の一覧と詳細表示で伏せ字にする正規表現（{"name": "電話番号", "pattern": "..."}。u で投稿ごとに外せる）
+    pub redact_rules: Vec<RedactRule>,
+    // キャッシュの kind ごとの保存期間（{"kinds": "1", "keep": {"days": 90}}。keep は "forever" / "never" も使える。先に書いた規則を優先）
+    pub cache_retention: Vec<RetentionRule>,
+    // AUTH（NIP-42）のチャレンジに応えないリレー（認証すると公開鍵がリレーに知られる。relay auth off <URL> で追加）
+    pub auth_opt_out: Vec<String>,
+}
+
+impl Default for AppConfig {
+    fn default() -> Self {
+        Self {
+            spam_patterns: Vec::new(),
+            auto_moderation: false,
+            preferred_language: None,
+            media_proxy: None,
+            media_allow_domains: Vec::new(),
+            media_deny_domains: Vec::new(),
+            tui_onboarded: false,
+            boot_screen: true,
+            shutdown_screen: true,
+            auto_hashtags: true,
+            aliases: BTreeMap::new(),
+            home_geohash: None,
+            read_relay_count: 3,
+            max_relay_connections: 8,
+            profile_cards: false,
+            public_terminal_redact: Vec::new(),
+            dm_receipts: false,
+            dm_receipt_peers: Vec::new(),
+            score_command: None,
+            backup_relay: None,
+            strict_verification: false,
+            use_keyring: false,
+            feed_kinds: vec![1],
+            active_account: None,
+            sync_drafts: false,
+            sync_notepad: false,
+            signer_command: None,
+            bunker_uri: None,
+            post_reminder: None,
+            key_kdf: "scrypt".to_string(),
+            argon2_memory_kib: 64 * 1024,
+            argon2_iterations: 3,
+            columns: vec!["home".to_string(), "mentions".to_string()],
+            password_lockout_attempts: 0,
+            password_lockout_minutes: 60,
+            zap_pay_command: None,
+            redact_rules: default_rules(),
+            cache_retention: default_retention_rules(),
+            auth_opt_out: Vec::new(),
+        }
+    }
+}
+
+impl AppConfig {
+    // config.json を読み込む（未作成なら既定値）
+    pub fn load() -> Result<Self, Box<dyn std::error::Error>> {
+        let path = app_dir()?.join("config.json");
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        read_json(&path).map_err(|e| format!("設定ファイルを解釈できません: {}", e).into())
+    }
+
+    // config.json に保存する
+    pub fn save(&self) -> Result<(), Box<dyn std::error::Error>> {
+        write_json(&app_dir()?.join("config.json"), self)
+    }
+}
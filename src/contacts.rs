@@ -0,0 +1,90 @@
+// コンタクトリスト（kind 3）の解析と比較
+use nostr_sdk::prelude::*;
+use std::collections::BTreeSet;
+use std::str::FromStr;
+use std::time::Duration;
+
+// コンタクトリストの1項目（リレーのヒントとペットネームを保持する）
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContactEntry {
+    pub pubkey: String, // hex
+    pub relay: Option<String>,
+    pub petname: Option<String>,
+}
+
+// kind 3 イベントのpタグからコンタクトの一覧を取り出す
+pub fn parse_contacts(event: &Event) -> Vec<ContactEntry> {
+    event
+        .tags
+        .iter()
+        .map(|tag| tag.as_vec())
+        .filter(|values| values.first().map(|k| k.as_str()) == Some("p"))
+        .filter_map(|values| {
+            let pubkey = values.get(1)?.clone();
+            let non_empty = |v: Option<&String>| v.filter(|s| !s.is_empty()).cloned();
+            Some(ContactEntry {
+                pubkey,
+                relay: non_empty(values.get(2)),
+                petname: non_empty(values.get(3)),
+            })
+        })
+        .collect()
+}
+
+impl ContactEntry {
+    // p タグに戻す（リレーのヒントとペットネームは、あるところまで書く）
+    pub fn to_tag(&self) -> Result<Tag, Box<dyn std::error::Error>> {
+        let mut values = vec!["p".to_string(), self.pubkey.clone()];
+        match (&self.relay, &self.petname) {
+            (relay, Some(petname)) => values.extend([relay.clone().unwrap_or_default(), petname.clone()]),
+            (Some(relay), None) => values.push(relay.clone()),
+            (None, None) => {}
+        }
+        Ok(Tag::parse(values)?)
+    }
+}
+
+// 自分のコンタクトリストの最新版を取得する（リレーごとに版が違うことがあるので、最も新しいものを使う）
+pub async fn fetch_contact_list(client: &Client, me: XOnlyPublicKey) -> Result<Option<Event>, Box<dyn std::error::Error>> {
+    let filter = Filter::new().kind(Kind::ContactList).author(me).limit(1);
+    let events = client.get_events_of(vec![filter], Some(Duration::from_secs(10))).await?;
+    Ok(events.into_iter().max_by_key(|e| e.created_at))
+}
+
+// 書き換えたコンタクトの一覧から新しいコンタクトリストを作る
+// 元のリストの p 以外のタグと本文（古いクライアントが使うリレーの一覧）はそのまま引き継ぐ
+pub fn contact_list_builder(base: Option<&Event>, contacts: &[ContactEntry]) -> Result<EventBuilder, Box<dyn std::error::Error>> {
+    let mut tags: Vec<Tag> = base
+        .into_iter()
+        .flat_map(|event| event.tags.iter())
+        .filter(|tag| tag.as_vec().first().map(|k| k.as_str()) != Some("p"))
+        .cloned()
+        .collect();
+    for contact in contacts {
+        tags.push(contact.to_tag()?);
+    }
+    let content = base.map(|event| event.content.clone()).unwrap_or_default();
+    Ok(EventBuilder::new(Kind::ContactList, content, tags))
+}
+
+// フォロー中の公開鍵 (hex) の集合
+pub fn followed_set(event: &Event) -> BTreeSet<String> {
+    parse_contacts(event).into_iter().map(|c| c.pubkey).collect()
+}
+
+// 基準となるリストと比べて (足りないフォロー, 余分なフォロー) を返す
+pub fn diff_contacts(canonical: &Event, other: &Event) -> (Vec<String>, Vec<String>) {
+    let expected = followed_set(canonical);
+    let actual = followed_set(other);
+    let missing = expected.difference(&actual).cloned().collect();
+    let extra = actual.difference(&expected).cloned().collect();
+    (missing, extra)
+}
+
+// hexの公開鍵を表示用のnpubに変換する（変換できなければそのまま）
+pub fn display_pubkey(hex: &str) -> String {
+    XOnlyPublicKey::from_str(hex)
+        .ok()
+        .and_then(|pk| pk.to_bech32().ok())
+        .unwrap_or_else(|| hex.to_string())
+}
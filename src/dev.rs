@@ -0,0 +1,194 @@
+// 開発用のデータ - オフラインで UI や性能を確かめるための、毎回同じ内容になる合成イベント
+// 鍵・作成日時・本文・署名（補助乱数なし）まで決まった値から作るので、同じ引数なら同じ ID になる
+use nostr_sdk::hashes::{sha256, Hash};
+use nostr_sdk::prelude::*;
+use nostr_sdk::secp256k1::{KeyPair, Message, Secp256k1};
+use std::collections::BTreeMap;
+use crate::profiles::CachedProfile;
+use crate::verify::event_hash;
+
+// 最初のイベントの作成日時（2023-11-14）
+const SEED_BASE_TIME: u64 = 1_700_000_000;
+// 乱数の初期値
+const SEED_STATE: u64 = 0x6e6f_7374_7221;
+
+const JAPANESE_WORDS: [&str; 16] = [
+    "今日は", "いい天気", "ですね", "コーヒー", "を飲みながら", "リレー", "について", "考えています",
+    "ターミナル", "で", "読む", "タイムライン", "は", "静かで", "好き", "。",
+];
+const OTHER_CJK_WORDS: [&str; 6] = ["你好", "世界", "谢谢", "안녕하세요", "감사합니다", "좋은 하루"];
+const ENGLISH_WORDS: [&str; 10] = ["hello", "nostr", "relay", "zap", "note", "client", "rust", "terminal", "gm", "pura vida"];
+const EMOJI: [&str; 10] = ["⚡", "🌸", "🍣", "🐈", "🚀", "😂", "🙏", "🎉", "👨‍👩‍👧", "🏳️‍🌈"];
+const HASHTAGS: [&str; 5] = ["nostr", "日本語", "rust", "zap", "photography"];
+
+// 決まった順で値を返す乱数（xorshift）
+struct SeedRng(u64);
+
+impl SeedRng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    // 0 以上 n 未満
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n.max(1) as u64) as usize
+    }
+
+    fn pick<'a>(&mut self, items: &[&'a str]) -> &'a str {
+        items[self.below(items.len())]
+    }
+}
+
+// i 番目の投稿者の鍵（名前から作るので毎回同じ）
+fn seed_keys(index: usize) -> Result<Keys, Box<dyn std::error::Error>> {
+    let hash = sha256::Hash::hash(format!("nostr-cli-app seed author {}", index).as_bytes());
+    Ok(Keys::new(SecretKey::from_slice(hash.as_byte_array())?))
+}
+
+// 言語と絵文字を混ぜた本文（words 個の語）
+fn mixed_text(rng: &mut SeedRng, words: usize) -> String {
+    let mut text = String::new();
+    for i in 0..words {
+        let word = match rng.below(10) {
+            0..=4 => rng.pick(&JAPANESE_WORDS),
+            5 => rng.pick(&OTHER_CJK_WORDS),
+            6..=7 => rng.pick(&ENGLISH_WORDS),
+            _ => rng.pick(&EMOJI),
+        };
+        if i > 0 && word.is_ascii() {
+            text.push(' ');
+        }
+        text.push_str(word);
+        // ときどき改行を入れる
+        if rng.below(40) == 0 {
+            text.push('\n');
+        }
+    }
+    text
+}
+
+// 本文の長さ（語数）: 短文が多く、ときどき長文、まれに数百KBの巨大な本文
+fn content_words(rng: &mut SeedRng) -> usize {
+    match rng.below(100) {
+        0 => 40_000 + rng.below(20_000),
+        1..=12 => 300 + rng.below(1200),
+        13..=37 => 30 + rng.below(90),
+        _ => 5 + rng.below(15),
+    }
+}
+
+// 見出しとコードブロックを含む長文記事（NIP-23）の本文
+fn article_text(rng: &mut SeedRng) -> String {
+    let mut text = String::new();
+    for section in 1..=3 + rng.below(5) {
+        text.push_str(&format!("# 見出し {}\n\n{}\n\n", section, mixed_text(rng, 80 + rng.below(200))));
+        if rng.below(2) == 0 {
+            text.push_str(&format!("## 小見出し {}.1\n\n{}\n\n", section, mixed_text(rng, 40 + rng.below(80))));
+        }
+        if rng.below(3) == 0 {
+            text.push_str("```\n# コードブロック内の見出しではない行\ncargo run -- tui\n```\n\n");
+        }
+    }
+    text
+}
+
+// 作成日時を指定して署名したイベントを作る
+fn signed_event(
+    keys: &Keys,
+    created_at: u64,
+    kind: u64,
+    tags: Vec<Vec<String>>,
+    content: String,
+) -> Result<Event, Box<dyn std::error::Error>> {
+    let secp = Secp256k1::new();
+    let pubkey = keys.public_key();
+    let hash = event_hash(&pubkey, created_at, kind, &tags, &content);
+    let key_pair = KeyPair::from_secret_key(&secp, keys.secret_key()?);
+    let sig = secp.sign_schnorr_no_aux_rand(&Message::from_slice(hash.as_byte_array())?, &key_pair);
+    Ok(serde_json::from_value(serde_json::json!({
+        "id": hash.to_string(),
+        "pubkey": pubkey.to_string(),
+        "created_at": created_at,
+        "kind": kind,
+        "tags": tags,
+        "content": content,
+        "sig": sig.to_string(),
+    }))?)
+}
+
+// 合成したイベント（古い順）と投稿者のプロフィールを作る
+// kind 1 の短文・長文と返信、長文記事、リアクション、リポストを混ぜる
+pub fn seed_events(
+    count: usize,
+    authors: usize,
+) -> Result<(Vec<Event>, BTreeMap<String, CachedProfile>), Box<dyn std::error::Error>> {
+    let authors = authors.max(1);
+    let keys: Vec<Keys> = (0..authors).map(seed_keys).collect::<Result<_, _>>()?;
+    let mut rng = SeedRng(SEED_STATE);
+    let mut events: Vec<Event> = Vec::with_capacity(count);
+    let mut created_at = SEED_BASE_TIME;
+
+    for index in 0..count {
+        created_at += 10 + rng.below(600) as u64;
+        let author = &keys[rng.below(authors)];
+        // 返信などで参照する、既に作ったイベント
+        let target = (!events.is_empty()).then(|| events[rng.below(events.len())].clone());
+        let reference = |target: &Event| {
+            vec![
+                vec!["e".to_string(), target.id.to_hex()],
+                vec!["p".to_string(), target.pubkey.to_string()],
+            ]
+        };
+
+        let event = match (rng.below(20), &target) {
+            (0..=1, _) => {
+                let tags = vec![
+                    vec!["d".to_string(), format!("seed-article-{}", index)],
+                    vec!["title".to_string(), format!("合成記事 {} {}", index, rng.pick(&EMOJI))],
+                    vec!["published_at".to_string(), created_at.to_string()],
+                ];
+                signed_event(author, created_at, 30023, tags, article_text(&mut rng))?
+            }
+            (2..=3, Some(target)) => {
+                let reaction = if rng.below(2) == 0 { "+" } else { rng.pick(&EMOJI) };
+                signed_event(author, created_at, 7, reference(target), reaction.to_string())?
+            }
+            (4, Some(target)) => signed_event(author, created_at, 6, reference(target), String::new())?,
+            (5..=7, Some(target)) => {
+                let words = content_words(&mut rng);
+                signed_event(author, created_at, 1, reference(target), mixed_text(&mut rng, words))?
+            }
+            _ => {
+                let words = content_words(&mut rng);
+                let mut content = mixed_text(&mut rng, words);
+                let mut tags = Vec::new();
+                if rng.below(5) == 0 {
+                    let tag = rng.pick(&HASHTAGS);
+                    content.push_str(&format!(" #{}", tag));
+                    tags.push(vec!["t".to_string(), tag.to_lowercase()]);
+                }
+                signed_event(author, created_at, 1, tags, content)?
+            }
+        };
+        events.push(event);
+    }
+
+    let profiles = keys
+        .iter()
+        .enumerate()
+        .map(|(i, keys)| {
+            let profile = CachedProfile {
+                display_name: Some(format!("{} 合成ユーザー{}", EMOJI[i % EMOJI.len()], i)),
+                name: Some(format!("seed{}", i)),
+                nip05: None,
+                fetched_at: created_at,
+                ..Default::default()
+            };
+            (keys.public_key().to_string(), profile)
+        })
+        .collect();
+    Ok((events, profiles))
+}
@@ -0,0 +1,391 @@
+// ダイレクトメッセージ - NIP-17 のギフトラップ（NIP-44 + NIP-59）と旧方式の NIP-04
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::OsRng;
+use nostr_sdk::prelude::*;
+use serde::Deserialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::str::FromStr;
+use std::time::Duration;
+use crate::signer::sign_local;
+use crate::event_cache::{load_cached_dm_events, save_cached_dm_events, update_cached_dm_events};
+use crate::outbox::{publish, PublishReceipt, PUBLISH_TIMEOUT};
+
+// NIP-17 / NIP-59 のイベントの種類
+const KIND_PRIVATE_MESSAGE: u64 = 14;
+const KIND_SEAL: u64 = 13;
+const KIND_GIFT_WRAP: u64 = 1059;
+// 封印と外側の作成日時をさかのぼらせる幅（2日）
+const GIFT_WRAP_TIME_SPREAD_SECS: u64 = 2 * 24 * 60 * 60;
+// NIP-17 のDM受信用リレーの一覧（これを公開している相手はギフトラップを受け取れる）
+const KIND_DM_RELAYS: u64 = 10050;
+// 一度に取得するDMの上限
+const DM_FETCH_LIMIT: usize = 500;
+// 入力中・既読の通知（このアプリ独自の一時イベント。リレーには保存されない）
+const KIND_TYPING: u64 = 20_014;
+const KIND_READ_RECEIPT: u64 = 20_015;
+
+// 暗号化の方式
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DmScheme {
+    GiftWrap, // NIP-44 で暗号化し、NIP-59 で送信者と時刻も隠す
+    Nip04,    // 旧方式（本文は暗号化されるが、送信者・受信者・時刻が公開される）
+}
+
+impl DmScheme {
+    pub fn label(self) -> &'static str {
+        match self {
+            DmScheme::GiftWrap => "🔒 NIP-44 ギフトラップ",
+            DmScheme::Nip04 => "⚠ NIP-04（旧方式）",
+        }
+    }
+}
+
+// 復号したメッセージ1件
+#[derive(Debug, Clone)]
+pub struct DirectMessage {
+    pub id: EventId,
+    pub peer: XOnlyPublicKey,
+    pub outgoing: bool,
+    pub content: String,
+    pub created_at: Timestamp,
+    pub scheme: DmScheme,
+}
+
+// 相手ごとの会話
+#[derive(Debug, Clone)]
+pub struct Conversation {
+    pub peer: XOnlyPublicKey,
+    pub messages: Vec<DirectMessage>, // 古い順
+    // 次に送るときに使う方式
+    pub scheme: DmScheme,
+}
+
+impl Conversation {
+    pub fn last_at(&self) -> Timestamp {
+        self.messages.last().map(|m| m.created_at).unwrap_or_else(|| Timestamp::from(0))
+    }
+}
+
+// ギフトラップの中身（署名されていないイベント）
+#[derive(Deserialize)]
+struct Rumor {
+    pubkey: XOnlyPublicKey,
+    created_at: Timestamp,
+    kind: u64,
+    content: String,
+    tags: Vec<Vec<String>>,
+}
+
+// タグの一覧から最初の p タグの公開鍵を取り出す
+fn first_p_tag(tags: &[Vec<String>]) -> Option<XOnlyPublicKey> {
+    tags.iter()
+        .find(|values| values.first().map(|k| k.as_str()) == Some("p"))
+        .and_then(|values| values.get(1))
+        .and_then(|pk| XOnlyPublicKey::from_str(pk).ok())
+}
+
+// 自分宛て・自分が送ったDMを取得して復号する（復号できないものは読み飛ばす）
+// 2つ目の値は取得したイベントをキャッシュに保存できたか（ほかのインスタンスがロックを持っていれば false）
+pub async fn fetch_messages(client: &Client, keys: &Keys) -> Result<(Vec<DirectMessage>, bool), Box<dyn std::error::Error>> {
+    let me = keys.public_key();
+    let filters = vec![
+        Filter::new().kind(Kind::EncryptedDirectMessage).author(me).limit(DM_FETCH_LIMIT),
+        Filter::new().kind(Kind::EncryptedDirectMessage).pubkey(me).limit(DM_FETCH_LIMIT),
+        // 自分が送ったメッセージも、自分宛てのギフトラップとして保存している
+        Filter::new().kind(Kind::from(KIND_GIFT_WRAP)).pubkey(me).limit(DM_FETCH_LIMIT),
+    ];
+    let mut events = client.get_events_of(filters, Some(Duration::from_secs(10))).await?;
+    // NIP-17 のギフトラップは、自分が kind 10050 で公開した受信用リレーに届く
+    let inbox = fetch_dm_relays(client, me).await.unwrap_or_default();
+    let wraps = Filter::new().kind(Kind::from(KIND_GIFT_WRAP)).pubkey(me).limit(DM_FETCH_LIMIT);
+    events.extend(query_unregistered_relays(client, &inbox, vec![wraps]).await);
+
+    // 以前に取得したものと合わせて（暗号化されたまま）保存し、リレーから消えたメッセージも読めるようにする
+    events.extend(load_cached_dm_events()?);
+    let saved = save_cached_dm_events(&events)?;
+
+    let mut messages: Vec<DirectMessage> = events.iter().filter_map(|event| decrypt_event(keys, event)).collect();
+    messages.sort_by_key(|m| m.created_at);
+    messages.dedup_by_key(|m| m.id);
+    Ok((messages, saved))
+}
+
+// DMのイベント（kind 4 またはギフトラップ）を復号する（復号できなければ None）
+pub fn decrypt_event(keys: &Keys, event: &Event) -> Option<DirectMessage> {
+    match event.kind.as_u64() {
+        KIND_GIFT_WRAP => unwrap_gift_wrap(keys, event).ok(),
+        _ if event.kind == Kind::EncryptedDirectMessage => decrypt_nip04(keys, event).ok(),
+        _ => None,
+    }
+}
+
+// 指定した相手とのDMを保存済みのイベントから削除する（ギフトラップは復号して相手を確かめる）
+// 戻り値は削除したイベントの数
+pub fn purge_cached_conversation(keys: &Keys, peer: &XOnlyPublicKey) -> Result<usize, Box<dyn std::error::Error>> {
+    let mut removed = 0;
+    update_cached_dm_events(|events| {
+        let before = events.len();
+        events.retain(|event| decrypt_event(keys, event).map_or(true, |message| message.peer != *peer));
+        removed = before - events.len();
+        removed > 0
+    })?;
+    Ok(removed)
+}
+
+// NIP-04 のDMを復号する
+fn decrypt_nip04(keys: &Keys, event: &Event) -> Result<DirectMessage, Box<dyn std::error::Error>> {
+    let me = keys.public_key();
+    let outgoing = event.pubkey == me;
+    let peer = if outgoing {
+        let tags: Vec<Vec<String>> = event.tags.iter().map(|tag| tag.as_vec()).collect();
+        first_p_tag(&tags).ok_or("宛先がありません")?
+    } else {
+        event.pubkey
+    };
+    let content = nip04::decrypt(&keys.secret_key()?, &peer, &event.content)?;
+    Ok(DirectMessage { id: event.id, peer, outgoing, content, created_at: event.created_at, scheme: DmScheme::Nip04 })
+}
+
+// ギフトラップを開封する（ラップ → 封印 → 中身の順に復号し、封印の署名者と中身の送信者が一致するかを確かめる）
+fn unwrap_gift_wrap(keys: &Keys, wrap: &Event) -> Result<DirectMessage, Box<dyn std::error::Error>> {
+    let secret_key = keys.secret_key()?;
+    let seal: Event = serde_json::from_str(&nip44::decrypt(&secret_key, &wrap.pubkey, &wrap.content)?)?;
+    seal.verify()?;
+    if seal.kind.as_u64() != KIND_SEAL {
+        return Err("封印の種類が正しくありません".into());
+    }
+    let rumor: Rumor = serde_json::from_str(&nip44::decrypt(&secret_key, &seal.pubkey, &seal.content)?)?;
+    if rumor.pubkey != seal.pubkey {
+        return Err("送信者が封印の署名者と一致しません".into());
+    }
+    if rumor.kind != KIND_PRIVATE_MESSAGE {
+        return Err("DMではありません".into());
+    }
+
+    let me = keys.public_key();
+    let outgoing = rumor.pubkey == me;
+    let peer = if outgoing { first_p_tag(&rumor.tags).ok_or("宛先がありません")? } else { rumor.pubkey };
+    Ok(DirectMessage {
+        id: wrap.id,
+        peer,
+        outgoing,
+        content: rumor.content,
+        created_at: rumor.created_at,
+        scheme: DmScheme::GiftWrap,
+    })
+}
+
+// DM受信用リレー（kind 10050）を公開している相手、つまりギフトラップを受け取れる相手を調べる
+pub async fn fetch_gift_wrap_peers(
+    client: &Client,
+    peers: Vec<XOnlyPublicKey>,
+) -> Result<BTreeSet<XOnlyPublicKey>, Box<dyn std::error::Error>> {
+    if peers.is_empty() {
+        return Ok(BTreeSet::new());
+    }
+    let filter = Filter::new().kind(Kind::from(KIND_DM_RELAYS)).authors(peers);
+    let events = client.get_events_of(vec![filter], Some(Duration::from_secs(5))).await?;
+    Ok(events.into_iter().map(|e| e.pubkey).collect())
+}
+
+// 相手が kind 10050 で公開している DM 受信用リレー（公開していなければ空）
+pub async fn fetch_dm_relays(client: &Client, pubkey: XOnlyPublicKey) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let filter = Filter::new().kind(Kind::from(KIND_DM_RELAYS)).author(pubkey);
+    let events = client.get_events_of(vec![filter], Some(Duration::from_secs(5))).await?;
+    let Some(latest) = events.into_iter().max_by_key(|e| e.created_at) else {
+        return Ok(Vec::new());
+    };
+    Ok(latest
+        .tags
+        .iter()
+        .map(|tag| tag.as_vec())
+        .filter(|values| values.first().map(|k| k.as_str()) == Some("relay"))
+        .filter_map(|values| values.get(1).cloned())
+        .collect())
+}
+
+// 自分の DM 受信用リレーを公開するイベント（kind 10050。置き換え可能なので最新の1件だけが使われる）
+pub fn dm_relays_builder(relays: &[String]) -> Result<EventBuilder, Box<dyn std::error::Error>> {
+    let tags = relays
+        .iter()
+        .map(|url| Tag::parse(vec!["relay".to_string(), url.clone()]))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(EventBuilder::new(Kind::from(KIND_DM_RELAYS), "", tags))
+}
+
+// 登録していないリレーにも問い合わせる（一時的に追加し、取得後に外す。登録済みのリレーは問い合わせ済みなので飛ばす）
+async fn query_unregistered_relays(client: &Client, relays: &[String], filters: Vec<Filter>) -> Vec<Event> {
+    let registered: Vec<String> = client.relays().await.keys().map(|url| url.to_string()).collect();
+    let mut events = Vec::new();
+    for url in relays {
+        if registered.iter().any(|r| r.trim_end_matches('/') == url.trim_end_matches('/')) {
+            continue;
+        }
+        let fetched = async {
+            client.add_relay(url.as_str()).await?;
+            client.connect_relay(url.as_str()).await?;
+            let relay = client.relay(url.as_str()).await?;
+            let events = relay.get_events_of(filters.clone(), Duration::from_secs(10), FilterOptions::ExitOnEOSE).await?;
+            Ok::<Vec<Event>, Box<dyn std::error::Error>>(events)
+        }
+        .await;
+        let _ = client.remove_relay(url.as_str()).await;
+        // 取得できなかった受信用リレーは読み飛ばす（登録済みのリレーから取得した分は表示する）
+        if let Ok(fetched) = fetched {
+            events.extend(fetched);
+        }
+    }
+    events
+}
+
+// 指定したリレーにだけ送る（登録していないリレーは一時的に追加し、送信後に外す）
+async fn send_to_relays(client: &Client, event: &Event, relays: &[String]) -> PublishReceipt {
+    let registered: Vec<String> = client.relays().await.keys().map(|url| url.to_string()).collect();
+    let mut receipt = PublishReceipt::default();
+    for url in relays {
+        let known = registered.iter().any(|r| r.trim_end_matches('/') == url.trim_end_matches('/'));
+        let sent = async {
+            if !known {
+                client.add_relay(url.as_str()).await?;
+                client.connect_relay(url.as_str()).await?;
+            }
+            let opts = RelaySendOptions::new().timeout(Some(PUBLISH_TIMEOUT));
+            client.relay(url.as_str()).await?.send_event(event.clone(), opts).await?;
+            Ok::<(), Box<dyn std::error::Error>>(())
+        }
+        .await;
+        if !known {
+            let _ = client.remove_relay(url.as_str()).await;
+        }
+        match sent {
+            Ok(()) => receipt.accepted.push(url.clone()),
+            Err(e) => receipt.rejected.push((url.clone(), e.to_string())),
+        }
+    }
+    receipt
+}
+
+// DMを送る（NIP-17 に従い、ギフトラップは宛先が kind 10050 で公開した受信用リレーに送る）
+// 受信用リレーを公開していない、またはどれにも受理されなかったときは、kind 4 と同じく接続中のリレーに送る
+pub async fn send_message_events(
+    client: &Client,
+    events: Vec<(XOnlyPublicKey, Event)>,
+) -> Result<Vec<PublishReceipt>, Box<dyn std::error::Error>> {
+    let mut receipts = Vec::new();
+    for (recipient, event) in events {
+        if event.kind.as_u64() == KIND_GIFT_WRAP {
+            let inbox = fetch_dm_relays(client, recipient).await.unwrap_or_default();
+            if !inbox.is_empty() {
+                let receipt = send_to_relays(client, &event, &inbox).await;
+                if !receipt.accepted.is_empty() {
+                    receipts.push(receipt);
+                    continue;
+                }
+            }
+        }
+        receipts.push(publish(client, event).await?);
+    }
+    Ok(receipts)
+}
+
+// メッセージを相手ごとの会話にまとめる（新しい会話から順に並べる）
+// 相手がギフトラップを受け取れる、または相手からギフトラップが届いていればギフトラップで送る
+pub fn group_conversations(messages: Vec<DirectMessage>, gift_wrap_peers: &BTreeSet<XOnlyPublicKey>) -> Vec<Conversation> {
+    let mut by_peer: BTreeMap<XOnlyPublicKey, Vec<DirectMessage>> = BTreeMap::new();
+    for message in messages {
+        by_peer.entry(message.peer).or_default().push(message);
+    }
+    let mut conversations: Vec<Conversation> = by_peer
+        .into_iter()
+        .map(|(peer, messages)| {
+            let wraps = gift_wrap_peers.contains(&peer)
+                || messages.iter().any(|m| !m.outgoing && m.scheme == DmScheme::GiftWrap);
+            Conversation { peer, messages, scheme: if wraps { DmScheme::GiftWrap } else { DmScheme::Nip04 } }
+        })
+        .collect();
+    conversations.sort_by(|a, b| b.last_at().cmp(&a.last_at()));
+    conversations
+}
+
+// 封印と外側の作成日時（送った時刻を隠すため、NIP-59 に従って今から2日前までの間でばらつかせる）
+// ずらす量から送った時刻を推測されないよう、OS の暗号論的な乱数を使う
+fn tweaked_created_at() -> Timestamp {
+    let offset = OsRng.next_u64() % GIFT_WRAP_TIME_SPREAD_SECS;
+    Timestamp::from(Timestamp::now().as_u64().saturating_sub(offset))
+}
+
+// ギフトラップを1つ作る（封印は自分の鍵で署名し、外側は使い捨ての鍵で署名する。本当の時刻は中の rumor にだけ残す）
+fn gift_wrap(keys: &Keys, rumor_json: &str, recipient: &XOnlyPublicKey) -> Result<Event, Box<dyn std::error::Error>> {
+    let sealed = nip44::encrypt(&keys.secret_key()?, recipient, rumor_json, nip44::Version::V2)?;
+    let seal_builder = EventBuilder::new(Kind::from(KIND_SEAL), sealed, Vec::<Tag>::new()).custom_created_at(tweaked_created_at());
+    let seal = sign_local(seal_builder, keys)?;
+
+    let ephemeral = Keys::generate();
+    let wrapped = nip44::encrypt(&ephemeral.secret_key()?, recipient, seal.as_json(), nip44::Version::V2)?;
+    let p_tag = Tag::parse(vec!["p".to_string(), recipient.to_string()])?;
+    Ok(EventBuilder::new(Kind::from(KIND_GIFT_WRAP), wrapped, vec![p_tag])
+        .custom_created_at(tweaked_created_at())
+        .to_event(&ephemeral)?)
+}
+
+// 送信するイベントを宛先と組にして作る（ギフトラップは相手宛てと、自分の履歴に残すための自分宛ての2つ）
+pub fn build_message_events(
+    keys: &Keys,
+    peer: XOnlyPublicKey,
+    content: &str,
+    scheme: DmScheme,
+) -> Result<Vec<(XOnlyPublicKey, Event)>, Box<dyn std::error::Error>> {
+    match scheme {
+        DmScheme::GiftWrap => {
+            let p_tag = Tag::parse(vec!["p".to_string(), peer.to_string()])?;
+            let rumor = EventBuilder::new(Kind::from(KIND_PRIVATE_MESSAGE), content, vec![p_tag])
+                .to_unsigned_event(keys.public_key());
+            let rumor_json = serde_json::to_string(&rumor)?;
+            let me = keys.public_key();
+            Ok(vec![(peer, gift_wrap(keys, &rumor_json, &peer)?), (me, gift_wrap(keys, &rumor_json, &me)?)])
+        }
+        DmScheme::Nip04 => Ok(vec![(peer, sign_local(EventBuilder::new_encrypted_direct_msg(keys, peer, content, None)?, keys)?)]),
+    }
+}
+
+// 会話相手とやりとりする一時的な通知
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DmSignal {
+    Typing,          // 入力中
+    Read(Timestamp), // この時刻までのメッセージを読んだ
+}
+
+// 通知のイベントを作る（内容は相手宛てに NIP-44 で暗号化する）
+// 通知を送ったこと自体と宛先は公開されるため、設定で有効にしたときだけ使う
+pub fn signal_event(keys: &Keys, peer: XOnlyPublicKey, signal: DmSignal) -> Result<Event, Box<dyn std::error::Error>> {
+    let (kind, payload) = match signal {
+        DmSignal::Typing => (KIND_TYPING, "typing".to_string()),
+        DmSignal::Read(until) => (KIND_READ_RECEIPT, until.as_u64().to_string()),
+    };
+    let content = nip44::encrypt(&keys.secret_key()?, &peer, payload, nip44::Version::V2)?;
+    let p_tag = Tag::parse(vec!["p".to_string(), peer.to_string()])?;
+    sign_local(EventBuilder::new(Kind::from(kind), content, vec![p_tag]), keys)
+}
+
+// 受け取った通知を読み取る（自分宛てでないもの、復号できないものは None）
+pub fn parse_signal(keys: &Keys, event: &Event) -> Option<(XOnlyPublicKey, DmSignal)> {
+    let kind = event.kind.as_u64();
+    if kind != KIND_TYPING && kind != KIND_READ_RECEIPT {
+        return None;
+    }
+    let payload = nip44::decrypt(&keys.secret_key().ok()?, &event.pubkey, &event.content).ok()?;
+    let signal = match kind {
+        KIND_TYPING if payload == "typing" => DmSignal::Typing,
+        KIND_READ_RECEIPT => DmSignal::Read(Timestamp::from(payload.parse::<u64>().ok()?)),
+        _ => return None,
+    };
+    Some((event.pubkey, signal))
+}
+
+// 自分宛ての通知を購読するフィルター
+pub fn signal_filter(my_pubkey: XOnlyPublicKey) -> Filter {
+    Filter::new()
+        .kinds(vec![Kind::from(KIND_TYPING), Kind::from(KIND_READ_RECEIPT)])
+        .pubkey(my_pubkey)
+        .since(Timestamp::now())
+}
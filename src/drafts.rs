@@ -0,0 +1,227 @@
+// 投稿の下書き - アカウントごとの drafts.json と、リレーに置く暗号化した下書き（NIP-37 の kind 31234）
+// リレーの下書きは自分宛てに NIP-44 で暗号化するので、中身は自分の鍵でしか読めない
+use nostr_sdk::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::time::Duration;
+use crate::accounts::{account_dir, active_account};
+use crate::compose::parse_hashtag_list;
+use crate::signer::sign_local;
+use crate::storage::{read_json, write_json};
+
+const KIND_DRAFT: u64 = 31234;
+// 一度に取得する下書きの上限
+const DRAFT_FETCH_LIMIT: usize = 500;
+
+// 下書き1件
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct Draft {
+    pub id: String, // d タグ（端末をまたいで同じ下書きを指す）
+    pub content: String,
+    pub subject: String,
+    pub hashtags: String, // 空白・カンマ区切り（投稿作成画面の入力欄と同じ形式）
+    pub updated_at: u64,  // 最後に編集した日時（同期では新しい方を残す）
+    pub deleted: bool,    // 削除済み（他の端末にも削除を伝えるために残しておく）
+}
+
+impl Draft {
+    pub fn new(content: &str, subject: &str, hashtags: &str) -> Self {
+        Self {
+            // 使い捨ての鍵の公開鍵を、重なることのない乱数として使う
+            id: Keys::generate().public_key().to_string()[..16].to_string(),
+            content: content.to_string(),
+            subject: subject.to_string(),
+            hashtags: hashtags.to_string(),
+            updated_at: Timestamp::now().as_u64(),
+            deleted: false,
+        }
+    }
+
+    // 一覧に表示する短いID
+    pub fn short_id(&self) -> &str {
+        &self.id[..self.id.len().min(8)]
+    }
+
+    // 一覧に表示する1行（件名、なければ本文の1行目）
+    pub fn title(&self) -> String {
+        let title = if self.subject.trim().is_empty() { self.content.lines().next().unwrap_or("") } else { self.subject.trim() };
+        let mut title: String = title.chars().take(40).collect();
+        if title.is_empty() {
+            title = "（本文なし）".to_string();
+        }
+        title
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct DraftStore {
+    #[serde(default)]
+    pub drafts: Vec<Draft>,
+}
+
+impl DraftStore {
+    // 使用中のアカウントの drafts.json
+    fn path() -> Result<PathBuf, Box<dyn std::error::Error>> {
+        Ok(account_dir(&active_account()?)?.join("drafts.json"))
+    }
+
+    // drafts.json を読み込む（未作成なら空）
+    pub fn load() -> Result<Self, Box<dyn std::error::Error>> {
+        let path = Self::path()?;
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        read_json(&path)
+    }
+
+    pub fn save(&self) -> Result<(), Box<dyn std::error::Error>> {
+        write_json(&Self::path()?, self)
+    }
+
+    // 削除されていない下書き（新しく編集した順）
+    pub fn active(&self) -> Vec<&Draft> {
+        let mut drafts: Vec<&Draft> = self.drafts.iter().filter(|d| !d.deleted).collect();
+        drafts.sort_by(|a, b| b.updated_at.cmp(&a.updated_at));
+        drafts
+    }
+
+    // ID（先頭の数文字でもよい）で下書きを探す
+    pub fn find(&self, id: &str) -> Option<&Draft> {
+        self.drafts.iter().find(|d| !d.deleted && !id.is_empty() && d.id.starts_with(id))
+    }
+
+    // 下書きを保存する（同じ ID があれば置き換える）
+    pub fn upsert(&mut self, draft: Draft) {
+        match self.drafts.iter_mut().find(|d| d.id == draft.id) {
+            Some(existing) => *existing = draft,
+            None => self.drafts.push(draft),
+        }
+    }
+
+    // 下書きを削除済みにする（中身は消し、削除したことだけを同期する）
+    pub fn delete(&mut self, id: &str) -> bool {
+        match self.drafts.iter_mut().find(|d| !d.deleted && d.id == id) {
+            Some(draft) => {
+                *draft = Draft { id: draft.id.clone(), updated_at: Timestamp::now().as_u64(), deleted: true, ..Default::default() };
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+// 下書きをリレーに置くイベントにする
+// 中身は下書きの投稿（未署名の kind 1）を自分宛てに NIP-44 で暗号化したもの。削除済みは中身を空にする
+fn draft_event(keys: &Keys, draft: &Draft) -> Result<Event, Box<dyn std::error::Error>> {
+    let content = if draft.deleted {
+        String::new()
+    } else {
+        let mut tags: Vec<Vec<String>> = Vec::new();
+        if !draft.subject.trim().is_empty() {
+            tags.push(vec!["subject".to_string(), draft.subject.trim().to_string()]);
+        }
+        for hashtag in parse_hashtag_list(&draft.hashtags) {
+            tags.push(vec!["t".to_string(), hashtag]);
+        }
+        // 編集した日時を created_at に入れ、同期のときに新しい方を選べるようにする
+        let rumor = serde_json::json!({
+            "pubkey": keys.public_key().to_string(),
+            "created_at": draft.updated_at,
+            "kind": 1,
+            "tags": tags,
+            "content": draft.content,
+        });
+        nip44::encrypt(&keys.secret_key()?, &keys.public_key(), rumor.to_string(), nip44::Version::V2)?
+    };
+    let tags = vec![
+        Tag::parse(vec!["d".to_string(), draft.id.clone()])?,
+        Tag::parse(vec!["k".to_string(), "1".to_string()])?,
+    ];
+    sign_local(EventBuilder::new(Kind::from(KIND_DRAFT), content, tags), keys)
+}
+
+// リレーの下書きを読み取る（kind 1 以外の下書きや、復号できないものは None）
+fn parse_draft_event(keys: &Keys, event: &Event) -> Option<Draft> {
+    let tags: Vec<Vec<String>> = event.tags.iter().map(|tag| tag.as_vec()).collect();
+    let tag = |key: &str| tags.iter().find(|t| t.first().map(|k| k.as_str()) == Some(key)).and_then(|t| t.get(1)).cloned();
+    let id = tag("d")?;
+    if event.content.is_empty() {
+        // 削除済み（削除した日時は、削除を伝えたイベントの日時とみなす）
+        return Some(Draft { id, updated_at: event.created_at.as_u64(), deleted: true, ..Default::default() });
+    }
+    if tag("k").as_deref() != Some("1") {
+        return None;
+    }
+    let payload = nip44::decrypt(&keys.secret_key().ok()?, &keys.public_key(), &event.content).ok()?;
+    let rumor: serde_json::Value = serde_json::from_str(&payload).ok()?;
+    let rumor_tags: Vec<Vec<String>> = serde_json::from_value(rumor.get("tags")?.clone()).unwrap_or_default();
+    let values = |key: &str| {
+        rumor_tags
+            .iter()
+            .filter(|t| t.first().map(|k| k.as_str()) == Some(key))
+            .filter_map(|t| t.get(1).cloned())
+            .collect::<Vec<String>>()
+    };
+    Some(Draft {
+        id,
+        content: rumor.get("content")?.as_str()?.to_string(),
+        subject: values("subject").into_iter().next().unwrap_or_default(),
+        hashtags: values("t").join(" "),
+        updated_at: rumor.get("created_at").and_then(|v| v.as_u64()).unwrap_or(event.created_at.as_u64()),
+        deleted: false,
+    })
+}
+
+// 同期の結果
+#[derive(Debug, Default)]
+pub struct SyncReport {
+    pub downloaded: usize, // リレーの方が新しかった下書き
+    pub uploaded: usize,   // 手元の方が新しかった下書き
+}
+
+// 手元とリレーの下書きを同期する（同じ下書きは最後に編集した方を残す）
+pub async fn sync_drafts(client: &Client, keys: &Keys, store: &mut DraftStore) -> Result<SyncReport, Box<dyn std::error::Error>> {
+    let filter = Filter::new()
+        .kind(Kind::from(KIND_DRAFT))
+        .author(keys.public_key())
+        .limit(DRAFT_FETCH_LIMIT);
+    let events = client.get_events_of(vec![filter], Some(Duration::from_secs(10))).await?;
+
+    // 同じ下書きが複数のリレーから届いたら新しい方を使う
+    let mut remote: BTreeMap<String, Draft> = BTreeMap::new();
+    for draft in events.iter().filter_map(|event| parse_draft_event(keys, event)) {
+        if remote.get(&draft.id).map_or(true, |d| d.updated_at < draft.updated_at) {
+            remote.insert(draft.id.clone(), draft);
+        }
+    }
+
+    let mut report = SyncReport::default();
+    for draft in remote.values() {
+        match store.drafts.iter_mut().find(|d| d.id == draft.id) {
+            Some(local) if local.updated_at >= draft.updated_at => {}
+            Some(local) => {
+                *local = draft.clone();
+                report.downloaded += 1;
+            }
+            // 手元にない下書きが他の端末で削除されていても、手元に残すものはない
+            None if draft.deleted => {}
+            None => {
+                store.drafts.push(draft.clone());
+                report.downloaded += 1;
+            }
+        }
+    }
+
+    // 手元の方が新しい下書きと、まだリレーにない下書きを送る
+    for draft in &store.drafts {
+        let newer = remote.get(&draft.id).map_or(!draft.deleted, |d| draft.updated_at > d.updated_at);
+        if newer {
+            client.send_event(draft_event(keys, draft)?).await?;
+            report.uploaded += 1;
+        }
+    }
+    store.save()?;
+    Ok(report)
+}
@@ -0,0 +1,173 @@
+// 取得したイベントの保存（cache/*.ndjson） - TUIの起動時にリレーへ接続する前から一覧を表示する
+// 自分が送信したイベントも記録し、アーカイブリレーの検証に使う
+// DMは暗号化されたままのイベントを保存し、復号した本文は保存しない
+use nostr_sdk::prelude::*;
+use std::path::Path;
+use crate::commands::app_dir;
+use crate::feed_kinds::is_ephemeral;
+use crate::ndjson::NdjsonReader;
+use crate::retention::RetentionPolicy;
+use crate::storage::{write_atomic, FileLock};
+
+// 保存するイベントの上限（新しいものから残す）
+const CACHED_EVENT_LIMIT: usize = 500;
+const CACHED_DM_EVENT_LIMIT: usize = 2000;
+const SENT_EVENT_LIMIT: usize = 10_000;
+const MENTION_EVENT_LIMIT: usize = 5000;
+
+// フィードのイベントを保存するファイル
+const FEED_CACHE_FILE: &str = "events.ndjson";
+// DMのイベント（kind 4 とギフトラップ）を保存するファイル
+const DM_CACHE_FILE: &str = "dm_events.ndjson";
+// 自分が送信し、リレーに受理されたイベントを記録するファイル（backup verify で使う）
+const SENT_CACHE_FILE: &str = "sent_events.ndjson";
+// 自分宛てのメンション（mentions で過去の分も取得して貯めておく）
+const MENTION_CACHE_FILE: &str = "mentions.ndjson";
+
+// cache/<name> を読み込む（未作成なら空。壊れた行は読み飛ばす）
+fn load_ndjson(name: &str) -> Result<Vec<Event>, Box<dyn std::error::Error>> {
+    let path = app_dir()?.join("cache").join(name);
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    Ok(NdjsonReader::open(&path)?.filter_map(|(_, event)| event.ok()).collect())
+}
+
+// cache/<name> に書き込む（1行に1イベントのJSON、新しい順に上限まで。ロックは呼び出し側で取る）
+fn write_ndjson(path: &Path, events: &[Event], limit: usize) -> Result<(), Box<dyn std::error::Error>> {
+    let mut sorted: Vec<&Event> = events.iter().collect();
+    sorted.sort_by(|a, b| b.created_at.cmp(&a.created_at).then(a.id.cmp(&b.id)));
+    sorted.dedup_by(|a, b| a.id == b.id);
+
+    let mut contents = String::new();
+    for event in sorted.into_iter().take(limit) {
+        contents.push_str(&serde_json::to_string(event)?);
+        contents.push('\n');
+    }
+    write_atomic(path, contents.as_bytes())
+}
+
+// cache/<name> に保存する（ほかのインスタンスがロックを持っていれば待たずに諦め、false を返す）
+// フィードやメンションのキャッシュは取得のついでに書くだけなので、書けなくても読み取りは続けられる
+fn save_ndjson(name: &str, events: &[Event], limit: usize) -> Result<bool, Box<dyn std::error::Error>> {
+    let path = app_dir()?.join("cache").join(name);
+    let Some(_lock) = FileLock::try_acquire(&path)? else {
+        return Ok(false);
+    };
+    write_ndjson(&path, events, limit)?;
+    Ok(true)
+}
+
+// ロックを取って cache/<name> を読み込み、変更して保存する（同時に動くほかのインスタンスの変更を上書きしない）
+// change が false を返せば書き込まない
+fn update_ndjson(name: &str, limit: usize, change: impl FnOnce(&mut Vec<Event>) -> bool) -> Result<(), Box<dyn std::error::Error>> {
+    let path = app_dir()?.join("cache").join(name);
+    let _lock = FileLock::acquire(&path)?;
+    let mut events = load_ndjson(name)?;
+    if change(&mut events) {
+        write_ndjson(&path, &events, limit)?;
+    }
+    Ok(())
+}
+
+// 保存期間の掃除の対象（送信済みの記録は backup verify で照合するため対象にしない）
+const VACUUM_FILES: [(&str, usize); 3] = [
+    (FEED_CACHE_FILE, CACHED_EVENT_LIMIT),
+    (DM_CACHE_FILE, CACHED_DM_EVENT_LIMIT),
+    (MENTION_CACHE_FILE, MENTION_EVENT_LIMIT),
+];
+
+// 掃除の結果（ファイルごとに、残した件数と取り除いた件数）
+#[derive(Debug)]
+pub struct VacuumResult {
+    pub file: &'static str,
+    pub kept: usize,
+    pub removed: usize,
+}
+
+// 保存期間を過ぎたイベントを取り除く（dry_run なら数えるだけで書き換えない）
+pub fn vacuum_caches(policy: &RetentionPolicy, dry_run: bool) -> Result<Vec<VacuumResult>, Box<dyn std::error::Error>> {
+    let now = Timestamp::now();
+    let mut results = Vec::new();
+    for (file, limit) in VACUUM_FILES {
+        let mut result = VacuumResult { file, kept: 0, removed: 0 };
+        let mut prune = |events: &mut Vec<Event>| {
+            let before = events.len();
+            events.retain(|event| policy.retains(event, now));
+            result.kept = events.len();
+            result.removed = before - events.len();
+            result.removed > 0
+        };
+        if dry_run {
+            prune(&mut load_ndjson(file)?);
+        } else {
+            update_ndjson(file, limit, prune)?;
+        }
+        results.push(result);
+    }
+    Ok(results)
+}
+
+// 前回表示したフィードのイベント
+pub fn load_cached_events() -> Result<Vec<Event>, Box<dyn std::error::Error>> {
+    load_ndjson(FEED_CACHE_FILE)
+}
+
+// 保存期間（config の cache_retention）で残さないイベントを除く
+fn retained(events: &[Event]) -> Vec<Event> {
+    let policy = RetentionPolicy::load();
+    let now = Timestamp::now();
+    events.iter().filter(|e| policy.retains(e, now)).cloned().collect()
+}
+
+// 表示したフィードのイベントを保存する（一時的なイベントと、保存期間で残さないイベントは残さない）
+// 以下の save_cached_* は、ほかのインスタンスがロックを持っていて保存しなかったときに false を返す
+pub fn save_cached_events(events: &[Event]) -> Result<bool, Box<dyn std::error::Error>> {
+    let persistent: Vec<Event> = retained(events).into_iter().filter(|e| !is_ephemeral(e.kind)).collect();
+    save_ndjson(FEED_CACHE_FILE, &persistent, CACHED_EVENT_LIMIT)
+}
+
+// 開発用に合成したイベントをフィードのキャッシュとして保存する（件数の上限を設けない）
+pub fn save_seeded_events(events: &[Event]) -> Result<(), Box<dyn std::error::Error>> {
+    let path = app_dir()?.join("cache").join(FEED_CACHE_FILE);
+    let _lock = FileLock::acquire(&path)?;
+    write_ndjson(&path, events, events.len())
+}
+
+// 保存済みのDMのイベント（暗号化されたまま）
+pub fn load_cached_dm_events() -> Result<Vec<Event>, Box<dyn std::error::Error>> {
+    load_ndjson(DM_CACHE_FILE)
+}
+
+// DMのイベントを保存する（暗号化されたまま。保存期間で残さないイベントは除く）
+pub fn save_cached_dm_events(events: &[Event]) -> Result<bool, Box<dyn std::error::Error>> {
+    save_ndjson(DM_CACHE_FILE, &retained(events), CACHED_DM_EVENT_LIMIT)
+}
+
+// ロックを取って保存済みのDMのイベントを書き換える（change が false を返せば書き込まない）
+pub fn update_cached_dm_events(change: impl FnOnce(&mut Vec<Event>) -> bool) -> Result<(), Box<dyn std::error::Error>> {
+    update_ndjson(DM_CACHE_FILE, CACHED_DM_EVENT_LIMIT, change)
+}
+
+// 保存済みのメンション（ほかのアカウント宛てのものも含む）
+pub fn load_cached_mentions() -> Result<Vec<Event>, Box<dyn std::error::Error>> {
+    load_ndjson(MENTION_CACHE_FILE)
+}
+
+// メンションを保存する（保存期間で残さないイベントは除く）
+pub fn save_cached_mentions(events: &[Event]) -> Result<bool, Box<dyn std::error::Error>> {
+    save_ndjson(MENTION_CACHE_FILE, &retained(events), MENTION_EVENT_LIMIT)
+}
+
+// 送信済みのイベント（新しい順）
+pub fn load_sent_events() -> Result<Vec<Event>, Box<dyn std::error::Error>> {
+    load_ndjson(SENT_CACHE_FILE)
+}
+
+// 送信済みのイベントに追加する
+pub fn record_sent_event(event: &Event) -> Result<(), Box<dyn std::error::Error>> {
+    update_ndjson(SENT_CACHE_FILE, SENT_EVENT_LIMIT, |events| {
+        events.push(event.clone());
+        true
+    })
+}
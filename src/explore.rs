@@ -0,0 +1,97 @@
+// ネットワークの調査 - リレーから最近のイベントを集め、kind ごとの件数を数える
+use nostr_sdk::prelude::*;
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+// kind ごとに残す例のイベントの数
+pub const EXAMPLES_PER_KIND: usize = 3;
+
+// kind ごとの集計
+#[derive(Debug, Clone)]
+pub struct KindBucket {
+    pub kind: u64,
+    pub count: usize,
+    // 新しい順の例（EXAMPLES_PER_KIND 件まで）
+    pub examples: Vec<Event>,
+}
+
+// kind の名前（主な NIP の定義と、範囲で決まる種類）
+pub fn kind_name(kind: u64) -> &'static str {
+    match kind {
+        0 => "プロフィール (NIP-01)",
+        1 => "テキストノート (NIP-01)",
+        2 => "リレーの推薦 (NIP-01, 非推奨)",
+        3 => "コンタクトリスト (NIP-02)",
+        4 => "暗号化DM (NIP-04)",
+        5 => "削除要求 (NIP-09)",
+        6 => "リポスト (NIP-18)",
+        7 => "リアクション (NIP-25)",
+        8 => "バッジの授与 (NIP-58)",
+        13 => "シール (NIP-59)",
+        14 => "DM (NIP-17)",
+        16 => "汎用リポスト (NIP-18)",
+        40 => "チャンネルの作成 (NIP-28)",
+        41 => "チャンネルの設定 (NIP-28)",
+        42 => "チャンネルのメッセージ (NIP-28)",
+        1063 => "ファイルのメタデータ (NIP-94)",
+        1059 => "ギフトラップ (NIP-59)",
+        1311 => "ライブチャット (NIP-53)",
+        1984 => "通報 (NIP-56)",
+        1985 => "ラベル (NIP-32)",
+        9734 => "Zap リクエスト (NIP-57)",
+        9735 => "Zap レシート (NIP-57)",
+        9802 => "ハイライト (NIP-84)",
+        10000 => "ミュートリスト (NIP-51)",
+        10001 => "ピン留めリスト (NIP-51)",
+        10002 => "リレーリスト (NIP-65)",
+        10050 => "DM 受信用リレー (NIP-17)",
+        13194 => "ウォレットの情報 (NIP-47)",
+        20_014 => "入力中の通知 (このアプリ)",
+        20_015 => "既読の通知 (このアプリ)",
+        22242 => "リレーの認証 (NIP-42)",
+        23194 => "ウォレットへの依頼 (NIP-47)",
+        23195 => "ウォレットの応答 (NIP-47)",
+        24133 => "リモート署名 (NIP-46)",
+        27235 => "HTTP 認証 (NIP-98)",
+        30000 => "フォローセット (NIP-51)",
+        30008 => "プロフィールのバッジ (NIP-58)",
+        30009 => "バッジの定義 (NIP-58)",
+        30023 => "長文記事 (NIP-23)",
+        30024 => "長文記事の下書き (NIP-23)",
+        30078 => "アプリのデータ (NIP-78)",
+        30311 => "ライブイベント (NIP-53)",
+        31922 | 31923 => "カレンダーの予定 (NIP-52)",
+        31989 | 31990 => "アプリの推薦 (NIP-89)",
+        10_000..=19_999 => "置き換え可能なイベント",
+        20_000..=29_999 => "一時的なイベント",
+        30_000..=39_999 => "パラメータ付き置き換え可能なイベント",
+        _ => "不明",
+    }
+}
+
+// 最近のイベントを kind を問わず集める
+pub async fn sample_events(client: &Client, limit: usize) -> Result<Vec<Event>, Box<dyn std::error::Error>> {
+    let filter = Filter::new().limit(limit);
+    Ok(client.get_events_of(vec![filter], Some(Duration::from_secs(10))).await?)
+}
+
+// kind ごとの件数を数える（件数の多い順、同数なら kind の小さい順）
+pub fn histogram(events: &[Event]) -> Vec<KindBucket> {
+    let mut sorted: Vec<&Event> = events.iter().collect();
+    sorted.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+    sorted.dedup_by(|a, b| a.id == b.id);
+
+    let mut buckets: BTreeMap<u64, KindBucket> = BTreeMap::new();
+    for event in sorted {
+        let kind = event.kind.as_u64();
+        let bucket = buckets.entry(kind).or_insert_with(|| KindBucket { kind, count: 0, examples: Vec::new() });
+        bucket.count += 1;
+        if bucket.examples.len() < EXAMPLES_PER_KIND {
+            bucket.examples.push(event.clone());
+        }
+    }
+
+    let mut buckets: Vec<KindBucket> = buckets.into_values().collect();
+    buckets.sort_by(|a, b| b.count.cmp(&a.count).then(a.kind.cmp(&b.kind)));
+    buckets
+}
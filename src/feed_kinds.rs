@@ -0,0 +1,85 @@
+// フィードに含める kind（config の feed_kinds）と、テキストノート以外の kind の1行の表示
+use nostr_sdk::prelude::*;
+use crate::article::article_title;
+use crate::explore::kind_name;
+
+// 要約に使う本文の上限（文字数）
+const SUMMARY_CHARS: usize = 200;
+// 一時的なイベント（NIP-01）の kind の範囲。リレーは保存せず、購読中の相手に届けるだけ
+pub const EPHEMERAL_KINDS: std::ops::RangeInclusive<u64> = 20_000..=29_999;
+
+// フィードで取得する kind（未設定ならテキストノートだけ）
+pub fn feed_kinds(configured: &[u64]) -> Vec<Kind> {
+    if configured.is_empty() {
+        return vec![Kind::TextNote];
+    }
+    configured.iter().map(|kind| Kind::from(*kind)).collect()
+}
+
+// 一時的なイベントか（キャッシュに保存しない）
+pub fn is_ephemeral(kind: Kind) -> bool {
+    EPHEMERAL_KINDS.contains(&kind.as_u64())
+}
+
+// ライブで購読する一時的なイベントの kind（feed_kinds に含まれているもの）
+pub fn ephemeral_feed_kinds(configured: &[u64]) -> Vec<Kind> {
+    configured
+        .iter()
+        .filter(|kind| EPHEMERAL_KINDS.contains(kind))
+        .map(|kind| Kind::from(*kind))
+        .collect()
+}
+
+// 最初の値が name のタグの2番目の値
+fn tag_value(event: &Event, name: &str) -> Option<String> {
+    event
+        .tags
+        .iter()
+        .map(|tag| tag.as_vec())
+        .find(|values| values.first().map(|k| k.as_str()) == Some(name))
+        .and_then(|values| values.get(1).cloned())
+        .filter(|value| !value.trim().is_empty())
+}
+
+// 本文の最初の段落（見出しや空行は飛ばす）
+fn first_paragraph(content: &str) -> String {
+    content
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty() && !line.starts_with('#') && !line.starts_with("```"))
+        .unwrap_or("")
+        .chars()
+        .take(SUMMARY_CHARS)
+        .collect()
+}
+
+// テキストノート以外の kind を一覧に出すときの1行の表示（テキストノートは None で本文をそのまま使う）
+pub fn compact_content(event: &Event) -> Option<String> {
+    match event.kind.as_u64() {
+        1 => None,
+        // リポスト（NIP-18）: 本文に埋め込まれた元の投稿、なければ e タグの参照
+        6 | 16 => {
+            let reposted = serde_json::from_str::<Event>(&event.content).ok();
+            Some(match reposted {
+                Some(original) => {
+                    let author = original.pubkey.to_bech32().unwrap_or_default();
+                    let short = author.get(..16).unwrap_or(&author);
+                    format!("🔁 {}...: {}", short, first_paragraph(&original.content))
+                }
+                None => match tag_value(event, "e").and_then(|id| EventId::from_hex(id).ok()) {
+                    Some(id) => format!("🔁 {}", id.to_bech32().unwrap_or_default()),
+                    None => "🔁 リポスト".to_string(),
+                },
+            })
+        }
+        // 長文記事（NIP-23）: タイトルと要約（summary タグ、なければ最初の段落）
+        30023 => {
+            let title = article_title(event).unwrap_or_else(|| "無題の記事".to_string());
+            let summary = tag_value(event, "summary").unwrap_or_else(|| first_paragraph(&event.content));
+            Some(format!("📄 {} — {}", title, summary))
+        }
+        // 一時的なイベント: 届いたときだけ表示される短いメッセージ
+        kind if EPHEMERAL_KINDS.contains(&kind) => Some(format!("⚡ {}", first_paragraph(&event.content))),
+        kind => Some(format!("[{}] {}", kind_name(kind), first_paragraph(&event.content))),
+    }
+}
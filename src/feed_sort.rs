@@ -0,0 +1,128 @@
+// フィードの並び順 - 新しい順・古い順・長い順・Zap額順・返信数順・外部コマンドのスコア順
+use nostr_sdk::prelude::*;
+use std::collections::HashMap;
+use std::io::Write;
+use std::process::{Command, Stdio};
+use crate::analytics::FeedCounts;
+use crate::clock::sort_time;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FeedSort {
+    Newest,      // 新しい順
+    Oldest,      // 古い順
+    Longest,     // 長い順
+    MostZapped,  // Zap額の多い順
+    MostReplied, // 返信の多い順
+    Plugin,      // score_command のスコア順
+}
+
+impl FeedSort {
+    // メニューに並べる順
+    pub const ALL: [FeedSort; 6] = [
+        FeedSort::Newest,
+        FeedSort::Oldest,
+        FeedSort::Longest,
+        FeedSort::MostZapped,
+        FeedSort::MostReplied,
+        FeedSort::Plugin,
+    ];
+
+    // --sort で指定する名前から並び順を求める
+    pub fn parse(name: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        Self::ALL
+            .into_iter()
+            .find(|sort| sort.name() == name)
+            .ok_or_else(|| {
+                let names: Vec<&str> = Self::ALL.iter().map(|sort| sort.name()).collect();
+                format!("並び順は {} のいずれかを指定してください: {}", names.join(" / "), name).into()
+            })
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            FeedSort::Newest => "newest",
+            FeedSort::Oldest => "oldest",
+            FeedSort::Longest => "longest",
+            FeedSort::MostZapped => "most-zapped",
+            FeedSort::MostReplied => "most-replied",
+            FeedSort::Plugin => "plugin",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            FeedSort::Newest => "新しい順",
+            FeedSort::Oldest => "古い順",
+            FeedSort::Longest => "長い順",
+            FeedSort::MostZapped => "Zap額の多い順",
+            FeedSort::MostReplied => "返信の多い順",
+            FeedSort::Plugin => "スコア順",
+        }
+    }
+
+    // 並べ替えに返信数とZap額が必要か
+    pub fn needs_counts(self) -> bool {
+        matches!(self, FeedSort::MostZapped | FeedSort::MostReplied)
+    }
+}
+
+// 並び順に従ってイベントを並べ替える（同じ値なら新しい順、未来の日時は今として扱う）
+pub fn sort_events(
+    events: &mut [Event],
+    sort: FeedSort,
+    counts: &HashMap<EventId, FeedCounts>,
+    scores: &HashMap<EventId, f64>,
+) {
+    let count = |event: &Event| counts.get(&event.id).copied().unwrap_or_default();
+    let score = |event: &Event| scores.get(&event.id).copied().unwrap_or(0.0);
+    let now = Timestamp::now();
+    let time = |event: &Event| sort_time(event, now);
+    events.sort_by(|a, b| {
+        let order = match sort {
+            FeedSort::Newest => std::cmp::Ordering::Equal,
+            FeedSort::Oldest => time(a).cmp(&time(b)),
+            FeedSort::Longest => b.content.chars().count().cmp(&a.content.chars().count()),
+            FeedSort::MostZapped => count(b).zap_sats.cmp(&count(a).zap_sats).then(count(b).zaps.cmp(&count(a).zaps)),
+            FeedSort::MostReplied => count(b).replies.cmp(&count(a).replies),
+            FeedSort::Plugin => score(b).total_cmp(&score(a)),
+        };
+        order.then(time(b).cmp(&time(a)))
+    });
+}
+
+// 外部コマンドにイベントを渡してスコアを付けてもらう
+// 標準入力に1行1件のJSONを書き、標準出力から「イベントID(hex) スコア」の行を読む。
+// スコアのない行や解釈できない行は無視する（スコアのないイベントは 0 とみなす）
+pub fn plugin_scores(command: &str, events: &[Event]) -> Result<HashMap<EventId, f64>, Box<dyn std::error::Error>> {
+    let mut child = Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()?;
+
+    let mut input = String::new();
+    for event in events {
+        input.push_str(&event.as_json());
+        input.push('\n');
+    }
+    // 書き込みは別スレッドで行い、出力が多いコマンドとの間で詰まらないようにする
+    let mut stdin = child.stdin.take().ok_or("コマンドの標準入力を開けません")?;
+    let writer = std::thread::spawn(move || stdin.write_all(input.as_bytes()));
+    let output = child.wait_with_output()?;
+    let _ = writer.join();
+    if !output.status.success() {
+        return Err(format!("スコアを付けるコマンドが失敗しました ({})", output.status).into());
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let id = EventId::from_hex(fields.next()?).ok()?;
+            let score = fields.next()?.parse::<f64>().ok()?;
+            Some((id, score))
+        })
+        .collect())
+}
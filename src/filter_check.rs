@@ -0,0 +1,107 @@
+// リレーが返したイベントの再検査 - 問い合わせたフィルターに合わないイベント（リレーの不具合や関係のない投稿の混入）を手元で捨てる
+// 捨てた件数はリレーごとに cache/capabilities.json の filter_violations に数え、relay rank に表示する
+use nostr_sdk::prelude::*;
+use serde_json::Value;
+use std::collections::{BTreeMap, HashSet};
+use std::time::Duration;
+use crate::capabilities::CapabilityCache;
+
+// フィルターの値の一覧に含まれるか（ids と authors は NIP-01 の前方一致も受け入れる）
+fn contains_prefix(values: &Value, candidate: &str) -> bool {
+    values.as_array().map_or(true, |values| values.iter().filter_map(Value::as_str).any(|v| candidate.starts_with(v)))
+}
+
+// イベントが1つのフィルターの条件に合うか
+// 手元で確かめられない条件（limit と NIP-50 の search）は合うものとして扱う
+pub fn matches_filter(filter: &Filter, event: &Event) -> bool {
+    let Ok(Value::Object(conditions)) = serde_json::to_value(filter) else {
+        return true;
+    };
+    conditions.iter().all(|(key, values)| match key.as_str() {
+        "ids" => contains_prefix(values, &event.id.to_hex()),
+        "authors" => contains_prefix(values, &event.pubkey.to_string()),
+        "kinds" => values.as_array().map_or(true, |kinds| kinds.iter().any(|k| k.as_u64() == Some(event.kind.as_u64()))),
+        "since" => values.as_u64().map_or(true, |since| event.created_at.as_u64() >= since),
+        "until" => values.as_u64().map_or(true, |until| event.created_at.as_u64() <= until),
+        tag if tag.starts_with('#') => {
+            let Some(wanted) = values.as_array() else {
+                return true;
+            };
+            event.tags.iter().any(|t| {
+                let t = t.as_vec();
+                t.first().map(|name| name.as_str()) == tag.get(1..)
+                    && t.get(1).is_some_and(|value| wanted.iter().filter_map(Value::as_str).any(|w| w.eq_ignore_ascii_case(value)))
+            })
+        }
+        _ => true,
+    })
+}
+
+// いずれかのフィルターに合うか（1つの問い合わせの filters は OR で結ばれる）
+pub fn matches_any(filters: &[Filter], event: &Event) -> bool {
+    filters.is_empty() || filters.iter().any(|filter| matches_filter(filter, event))
+}
+
+// フィルターに合わないイベントを取り除き、取り除いた数を返す
+pub fn retain_matching(filters: &[Filter], events: &mut Vec<Event>) -> usize {
+    let before = events.len();
+    events.retain(|event| matches_any(filters, event));
+    before - events.len()
+}
+
+// 接続中のリレーそれぞれに問い合わせ、フィルターに合わないイベントを捨ててからまとめる
+// どのリレーが返したかが分かるよう、リレーごとに別々に問い合わせる（戻り値はイベントとリレーごとの捨てた件数）
+pub async fn query_checked(
+    client: &Client,
+    filters: Vec<Filter>,
+    timeout: Duration,
+) -> Result<(Vec<Event>, BTreeMap<String, usize>), Box<dyn std::error::Error>> {
+    let mut tasks = Vec::new();
+    for (url, relay) in client.relays().await {
+        if relay.status().await != RelayStatus::Connected {
+            continue;
+        }
+        let filters = filters.clone();
+        tasks.push((
+            url.to_string(),
+            tokio::spawn(async move { relay.get_events_of(filters, timeout, FilterOptions::ExitOnEOSE).await }),
+        ));
+    }
+
+    // まだどのリレーにもつながっていなければ、まとめて問い合わせる（どのリレーが返したかは分からない）
+    if tasks.is_empty() {
+        let mut events = client.get_events_of(filters.clone(), Some(timeout)).await?;
+        retain_matching(&filters, &mut events);
+        return Ok((events, BTreeMap::new()));
+    }
+
+    let mut events = Vec::new();
+    let mut violations = BTreeMap::new();
+    for (url, task) in tasks {
+        let Ok(Ok(mut fetched)) = task.await else {
+            continue;
+        };
+        let dropped = retain_matching(&filters, &mut fetched);
+        if dropped > 0 {
+            violations.insert(url, dropped);
+        }
+        events.extend(fetched);
+    }
+
+    // 複数のリレーから同じイベントが届くので1件にまとめる
+    let mut seen = HashSet::new();
+    events.retain(|event| seen.insert(event.id));
+    Ok((events, violations))
+}
+
+// リレーごとの捨てた件数を cache/capabilities.json に足す
+pub fn record_violations(violations: &BTreeMap<String, usize>) -> Result<(), Box<dyn std::error::Error>> {
+    if violations.is_empty() {
+        return Ok(());
+    }
+    let mut cache = CapabilityCache::load()?;
+    for (url, count) in violations {
+        cache.entry(url).filter_violations += *count as u64;
+    }
+    cache.save()
+}
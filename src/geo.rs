@@ -0,0 +1,117 @@
+// 位置情報 - ジオハッシュ（NIP-52 の g タグ）の作成と近所の投稿の絞り込み
+use nostr_sdk::prelude::*;
+
+// ジオハッシュで使う32文字
+const GEOHASH_BASE32: &[u8] = b"0123456789bcdefghjkmnpqrstuvwxyz";
+// 緯度経度から作るジオハッシュの桁数（5桁で約5km四方。自宅の位置を特定されにくい粗さ）
+pub const DEFAULT_PRECISION: usize = 5;
+// ジオハッシュの最大桁数
+const MAX_PRECISION: usize = 12;
+
+// ジオハッシュとして正しい文字列か
+pub fn is_valid_geohash(geohash: &str) -> bool {
+    !geohash.is_empty()
+        && geohash.len() <= MAX_PRECISION
+        && geohash.bytes().all(|b| GEOHASH_BASE32.contains(&b))
+}
+
+// 緯度経度をジオハッシュに変換する
+pub fn encode(lat: f64, lon: f64, precision: usize) -> String {
+    let (mut lat_range, mut lon_range) = ((-90.0, 90.0), (-180.0, 180.0));
+    let mut geohash = String::new();
+    let mut even_bit = true; // 経度から交互に二分する
+    let mut bits = 0;
+    let mut value = 0usize;
+
+    while geohash.len() < precision.clamp(1, MAX_PRECISION) {
+        let (range, coordinate): (&mut (f64, f64), f64) =
+            if even_bit { (&mut lon_range, lon) } else { (&mut lat_range, lat) };
+        let mid = (range.0 + range.1) / 2.0;
+        value <<= 1;
+        if coordinate >= mid {
+            value |= 1;
+            range.0 = mid;
+        } else {
+            range.1 = mid;
+        }
+        even_bit = !even_bit;
+
+        bits += 1;
+        if bits == 5 {
+            geohash.push(GEOHASH_BASE32[value] as char);
+            bits = 0;
+            value = 0;
+        }
+    }
+    geohash
+}
+
+// ジオハッシュまたは "緯度,経度" の入力をジオハッシュにする
+pub fn parse_location(input: &str) -> Result<String, Box<dyn std::error::Error>> {
+    let input = input.trim().to_lowercase();
+    if let Some((lat, lon)) = input.split_once(',') {
+        let lat: f64 = lat.trim().parse().map_err(|_| format!("緯度が正しくありません: {}", lat))?;
+        let lon: f64 = lon.trim().parse().map_err(|_| format!("経度が正しくありません: {}", lon))?;
+        if !(-90.0..=90.0).contains(&lat) || !(-180.0..=180.0).contains(&lon) {
+            return Err("緯度は-90〜90、経度は-180〜180の範囲で指定してください".into());
+        }
+        return Ok(encode(lat, lon, DEFAULT_PRECISION));
+    }
+    if !is_valid_geohash(&input) {
+        return Err(format!("ジオハッシュまたは「緯度,経度」で指定してください: {}", input).into());
+    }
+    Ok(input)
+}
+
+// 投稿に付ける g タグ
+// リレーは前方一致で検索できないため、短い桁から順にすべての接頭辞を付けて範囲検索に使えるようにする
+pub fn geohash_tags(geohash: &str) -> Result<Vec<Tag>, Box<dyn std::error::Error>> {
+    (1..=geohash.len())
+        .map(|len| -> Result<Tag, Box<dyn std::error::Error>> {
+            Ok(Tag::parse(vec!["g".to_string(), geohash[..len].to_string()])?)
+        })
+        .collect()
+}
+
+// 投稿の g タグ一覧
+pub fn event_geohashes(event: &Event) -> Vec<String> {
+    event
+        .tags
+        .iter()
+        .map(|tag| tag.as_vec())
+        .filter(|values| values.first().map(|k| k.as_str()) == Some("g"))
+        .filter_map(|values| values.get(1).map(|g| g.to_lowercase()))
+        .collect()
+}
+
+// 投稿が指定した範囲（ジオハッシュの接頭辞）に含まれるか
+pub fn is_near(event: &Event, prefix: &str) -> bool {
+    event_geohashes(event).iter().any(|g| g.starts_with(prefix))
+}
+
+// "5km" / "800m" / "20" (km) の形式の半径をキロメートルに変換する
+pub fn parse_radius_km(input: &str) -> Result<f64, Box<dyn std::error::Error>> {
+    let input = input.trim().to_lowercase();
+    let (number, scale) = if let Some(km) = input.strip_suffix("km") {
+        (km, 1.0)
+    } else if let Some(m) = input.strip_suffix('m') {
+        (m, 0.001)
+    } else {
+        (input.as_str(), 1.0)
+    };
+    let value: f64 = number.trim().parse().map_err(|_| format!("半径の形式が正しくありません: {}", input))?;
+    if value <= 0.0 {
+        return Err("半径は0より大きい値で指定してください".into());
+    }
+    Ok(value * scale)
+}
+
+// 半径を覆うのに必要なジオハッシュの桁数（各桁のおおよその誤差範囲から選ぶ）
+pub fn precision_for_radius_km(radius_km: f64) -> usize {
+    const CELL_ERROR_KM: [f64; 8] = [2500.0, 630.0, 78.0, 20.0, 2.4, 0.61, 0.076, 0.019];
+    CELL_ERROR_KM
+        .iter()
+        .rposition(|error| *error >= radius_km)
+        .map(|i| i + 1)
+        .unwrap_or(1)
+}
@@ -0,0 +1,113 @@
+// 記号の代替表示 - TUI の起動時に Mac 風の記号（⌘ 📁 ■□ など）を1つずつ書いてカーソルの移動量を測り、
+// 想定した幅で表示されない記号（フォントにない、曖昧な幅の文字が2桁になるなど）だけを ASCII に置き換える
+// 幅がずれると ratatui の計算と実際の表示が合わなくなり、枠や列が崩れるため
+use crossterm::{
+    cursor::{self, MoveTo},
+    execute,
+    style::Print,
+    terminal::{Clear, ClearType},
+};
+use std::io;
+use std::sync::OnceLock;
+use unicode_width::UnicodeWidthStr;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Glyph {
+    AppleLogo, // メニューバーの先頭（Commandキーのシンボル）
+    Folder,
+    Document,
+    Note,
+    Clip,
+    Checkmark,
+    Divider,
+    HappyMac, // ステータスバーの先頭（ハッピーマックに近いもの）
+    Pattern1, // 初代Mac風パターン
+    Pattern2,
+}
+
+impl Glyph {
+    pub const ALL: [Glyph; 10] = [
+        Glyph::AppleLogo,
+        Glyph::Folder,
+        Glyph::Document,
+        Glyph::Note,
+        Glyph::Clip,
+        Glyph::Checkmark,
+        Glyph::Divider,
+        Glyph::HappyMac,
+        Glyph::Pattern1,
+        Glyph::Pattern2,
+    ];
+
+    pub fn fancy(self) -> &'static str {
+        match self {
+            Glyph::AppleLogo => "⌘",
+            Glyph::Folder => "📁",
+            Glyph::Document => "📄",
+            Glyph::Note => "📝",
+            Glyph::Clip => "📎",
+            Glyph::Checkmark => "✓",
+            Glyph::Divider => "━━━━━━━━━━━━━━━━━━━━━━━━",
+            Glyph::HappyMac => "🙂",
+            Glyph::Pattern1 => "■ □ ■ □ ■ □ ■ □ ■ □ ■ □",
+            Glyph::Pattern2 => "□ ■ □ ■ □ ■ □ ■ □ ■ □ ■",
+        }
+    }
+
+    // 代わりに表示する文字（チェックマークは選んでいない行の " " と同じ1桁にする）
+    pub fn ascii(self) -> &'static str {
+        match self {
+            Glyph::AppleLogo => "@",
+            Glyph::Folder => "[+]",
+            Glyph::Document => "[=]",
+            Glyph::Note => "[/]",
+            Glyph::Clip => "[&]",
+            Glyph::Checkmark => "*",
+            Glyph::Divider => "------------------------",
+            Glyph::HappyMac => ":)",
+            Glyph::Pattern1 => "# . # . # . # . # . # .",
+            Glyph::Pattern2 => ". # . # . # . # . # . #",
+        }
+    }
+}
+
+// ASCII に置き換える記号（起動時に一度だけ決める。決める前は置き換えない）
+static FALLBACK: OnceLock<Vec<Glyph>> = OnceLock::new();
+
+// 表示する文字
+pub fn glyph(glyph: Glyph) -> &'static str {
+    if FALLBACK.get().is_some_and(|fallback| fallback.contains(&glyph)) {
+        glyph.ascii()
+    } else {
+        glyph.fancy()
+    }
+}
+
+// 画面の左上に書いて、カーソルが文字の幅だけ進んだか（端末が位置を返さなければ None）
+fn renders_at_width(stdout: &mut io::Stdout, text: &str) -> Option<bool> {
+    execute!(stdout, MoveTo(0, 0), Clear(ClearType::CurrentLine), Print(text)).ok()?;
+    let (column, _) = cursor::position().ok()?;
+    Some(column as usize == text.width())
+}
+
+// 置き換える記号を決める（force_ascii ならすべて。raw モードで代替画面に入ったあと、最初の描画の前に呼ぶ）
+// 戻り値は置き換えた記号。端末が位置を返さないときは測れないので、残りは置き換えない
+pub fn detect_fallback(force_ascii: bool) -> Vec<Glyph> {
+    let fallback: Vec<Glyph> = if force_ascii {
+        Glyph::ALL.to_vec()
+    } else {
+        let mut stdout = io::stdout();
+        let mut fallback = Vec::new();
+        for glyph in Glyph::ALL {
+            match renders_at_width(&mut stdout, glyph.fancy()) {
+                Some(true) => {}
+                Some(false) => fallback.push(glyph),
+                None => break,
+            }
+        }
+        let _ = execute!(stdout, MoveTo(0, 0), Clear(ClearType::CurrentLine));
+        fallback
+    };
+    let _ = FALLBACK.set(fallback.clone());
+    fallback
+}
@@ -0,0 +1,133 @@
+// TUIのコマンドラインと検索の履歴 - history.json に保存し、シェルのように呼び出す
+use serde::{Deserialize, Serialize};
+use std::fs;
+use crate::commands::app_dir;
+
+// 種類ごとに保存する履歴の上限
+const HISTORY_LIMIT: usize = 500;
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+pub struct History {
+    // : コマンドラインの履歴（古い順）
+    #[serde(default)]
+    pub commands: Vec<String>,
+    // 検索語の履歴（古い順）
+    #[serde(default)]
+    pub searches: Vec<String>,
+}
+
+impl History {
+    // history.json を読み込む（未作成なら空）
+    pub fn load() -> Result<Self, Box<dyn std::error::Error>> {
+        let path = app_dir()?.join("history.json");
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let contents = fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&contents)?)
+    }
+
+    // history.json に保存する
+    pub fn save(&self) -> Result<(), Box<dyn std::error::Error>> {
+        let dir = app_dir()?;
+        fs::create_dir_all(&dir)?;
+        fs::write(dir.join("history.json"), serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+}
+
+// 履歴に1件追加する（空行と直前と同じ内容は追加しない）
+pub fn push_entry(entries: &mut Vec<String>, line: &str) {
+    let line = line.trim();
+    if line.is_empty() || entries.last().map(|l| l.as_str()) == Some(line) {
+        return;
+    }
+    entries.push(line.to_string());
+    if entries.len() > HISTORY_LIMIT {
+        let excess = entries.len() - HISTORY_LIMIT;
+        entries.drain(..excess);
+    }
+}
+
+// 入力欄での履歴の呼び出し状態（↑/↓ での移動と Ctrl-R の逆方向検索）
+#[derive(Debug, Default, Clone)]
+pub struct Recall {
+    position: Option<usize>, // 表示中の履歴の位置（None なら入力中の行）
+    draft: String,           // 履歴を遡る前に入力していた内容
+    pub search: Option<String>, // 逆方向検索の検索語（検索中のみSome）
+}
+
+impl Recall {
+    // 入力が確定・取消されたら初期状態に戻す
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+
+    // ひとつ古い履歴を表示する
+    pub fn older(&mut self, entries: &[String], text: &mut String) {
+        let next = match self.position {
+            None if entries.is_empty() => return,
+            None => {
+                self.draft = text.clone();
+                entries.len() - 1
+            }
+            Some(0) => return,
+            Some(i) => i - 1,
+        };
+        self.position = Some(next);
+        *text = entries[next].clone();
+    }
+
+    // ひとつ新しい履歴を表示する（最新を過ぎたら入力中の内容に戻る）
+    pub fn newer(&mut self, entries: &[String], text: &mut String) {
+        match self.position {
+            None => {}
+            Some(i) if i + 1 < entries.len() => {
+                self.position = Some(i + 1);
+                *text = entries[i + 1].clone();
+            }
+            Some(_) => {
+                self.position = None;
+                *text = std::mem::take(&mut self.draft);
+            }
+        }
+    }
+
+    // 逆方向検索を始める
+    pub fn start_search(&mut self, text: &str) {
+        if self.position.is_none() {
+            self.draft = text.to_string();
+        }
+        self.search = Some(String::new());
+    }
+
+    // 検索語に一致する履歴を、from より前（古い方）から探して表示する
+    fn find_from(&mut self, entries: &[String], text: &mut String, from: usize) -> bool {
+        let Some(query) = self.search.as_deref() else { return false };
+        let found = entries[..from.min(entries.len())]
+            .iter()
+            .rposition(|entry| entry.contains(query));
+        if let Some(i) = found {
+            self.position = Some(i);
+            *text = entries[i].clone();
+        }
+        found.is_some()
+    }
+
+    // 検索語を変更したら最新の履歴から探し直す
+    pub fn update_search(&mut self, entries: &[String], text: &mut String) -> bool {
+        self.find_from(entries, text, entries.len())
+    }
+
+    // Ctrl-R を続けて押したら、さらに古い一致を探す
+    pub fn search_older(&mut self, entries: &[String], text: &mut String) -> bool {
+        let from = self.position.unwrap_or(entries.len());
+        self.find_from(entries, text, from)
+    }
+
+    // 検索を取り消して入力中の内容に戻す
+    pub fn cancel_search(&mut self, text: &mut String) {
+        *text = std::mem::take(&mut self.draft);
+        self.reset();
+    }
+}
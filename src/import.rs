@@ -0,0 +1,221 @@
+// ほかのクライアントからの引っ越し - Damus や Amethyst が書き出したイベントの JSON、または CSV からフォローとリレーを読み取り、
+// 今のコンタクトリストや relays.json と突き合わせて、増えるもの・書き換わるものを表示してから取り込む
+use nostr_sdk::prelude::*;
+use std::str::FromStr;
+use crate::contacts::{display_pubkey, parse_contacts, ContactEntry};
+
+// 読み込める形式
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ImportFormat {
+    Damus,    // イベントの JSON（1件・配列・1行に1件）
+    Amethyst, // バックアップの JSON（backupContactList / contactList にコンタクトリストのイベント）、またはイベントの JSON
+    Csv,      // 1行に「公開鍵,リレー,ペットネーム」（公開鍵は npub / hex、2列目以降は省略できる）
+}
+
+impl ImportFormat {
+    pub fn parse(name: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        match name {
+            "damus" => Ok(ImportFormat::Damus),
+            "amethyst" => Ok(ImportFormat::Amethyst),
+            "csv" => Ok(ImportFormat::Csv),
+            _ => Err(format!("読み込めない形式です（damus / amethyst / csv）: {}", name).into()),
+        }
+    }
+}
+
+// npub / hex の公開鍵を hex にする
+fn parse_pubkey(input: &str) -> Option<String> {
+    let input = input.trim().trim_matches('"').trim_start_matches("nostr:");
+    XOnlyPublicKey::from_bech32(input)
+        .ok()
+        .or_else(|| XOnlyPublicKey::from_str(input).ok())
+        .map(|pubkey| pubkey.to_string())
+}
+
+// ファイルの中のイベント（1件のオブジェクト、配列、または1行に1件）
+fn parse_events(text: &str) -> Vec<Event> {
+    let text = text.trim();
+    if let Ok(event) = Event::from_json(text) {
+        return vec![event];
+    }
+    if let Ok(values) = serde_json::from_str::<Vec<serde_json::Value>>(text) {
+        return values.into_iter().filter_map(|value| serde_json::from_value(value).ok()).collect();
+    }
+    text.lines().filter_map(|line| Event::from_json(line.trim()).ok()).collect()
+}
+
+// Amethyst のバックアップの中のイベント
+fn parse_amethyst_events(text: &str) -> Vec<Event> {
+    let Ok(backup) = serde_json::from_str::<serde_json::Value>(text) else {
+        return parse_events(text);
+    };
+    let events: Vec<Event> = ["backupContactList", "contactList", "backupNIP65RelayList", "relayList"]
+        .iter()
+        .filter_map(|key| match &backup[*key] {
+            serde_json::Value::String(json) => Event::from_json(json).ok(),
+            serde_json::Value::Object(_) => serde_json::from_value(backup[*key].clone()).ok(),
+            _ => None,
+        })
+        .collect();
+    if events.is_empty() {
+        parse_events(text)
+    } else {
+        events
+    }
+}
+
+// 署名を確かめたイベントのうち、指定した kind の最新のもの
+fn latest_of_kind(events: &[Event], kind: Kind) -> Option<&Event> {
+    events.iter().filter(|event| event.kind == kind && event.verify().is_ok()).max_by_key(|event| event.created_at)
+}
+
+// CSV の1行を分ける（"..." で囲んだ列の中のカンマはそのまま）
+fn split_csv_line(line: &str) -> Vec<String> {
+    let mut fields = vec![String::new()];
+    let mut quoted = false;
+    for c in line.chars() {
+        match c {
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(String::new()),
+            _ => fields.last_mut().expect("列がありません").push(c),
+        }
+    }
+    fields.into_iter().map(|field| field.trim().to_string()).collect()
+}
+
+// 書き出したファイルからフォローを読み取る（読み取れなかった行や公開鍵は数だけ返す）
+pub fn parse_follows(format: ImportFormat, text: &str) -> Result<(Vec<ContactEntry>, usize), Box<dyn std::error::Error>> {
+    if format == ImportFormat::Csv {
+        let mut contacts = Vec::new();
+        let mut skipped = 0;
+        for (i, line) in text.lines().enumerate() {
+            let fields = split_csv_line(line);
+            if fields.iter().all(String::is_empty) {
+                continue;
+            }
+            let Some(pubkey) = parse_pubkey(&fields[0]) else {
+                // 1行目の見出し（pubkey,relay,petname など）は数えない
+                if i > 0 {
+                    skipped += 1;
+                }
+                continue;
+            };
+            let field = |n: usize| fields.get(n).filter(|value| !value.is_empty()).cloned();
+            contacts.push(ContactEntry { pubkey, relay: field(1), petname: field(2) });
+        }
+        return Ok((contacts, skipped));
+    }
+
+    let events = match format {
+        ImportFormat::Amethyst => parse_amethyst_events(text),
+        _ => parse_events(text),
+    };
+    let contact_list = latest_of_kind(&events, Kind::ContactList).ok_or("コンタクトリスト（kind 3）のイベントが見つかりません")?;
+    let entries = parse_contacts(contact_list);
+    let total = entries.len();
+    let contacts: Vec<ContactEntry> = entries
+        .into_iter()
+        .filter_map(|entry| Some(ContactEntry { pubkey: parse_pubkey(&entry.pubkey)?, ..entry }))
+        .collect();
+    let skipped = total - contacts.len();
+    Ok((contacts, skipped))
+}
+
+// 書き出したファイルからリレーを読み取る（NIP-65 の kind 10002、コンタクトリストの本文のリレーの一覧、または1行に1つの URL）
+pub fn parse_relays(text: &str) -> Vec<String> {
+    let mut events = parse_amethyst_events(text);
+    events.retain(|event| event.verify().is_ok());
+    let mut relays: Vec<String> = Vec::new();
+    if let Some(relay_list) = latest_of_kind(&events, Kind::RelayList) {
+        relays.extend(
+            relay_list
+                .tags
+                .iter()
+                .map(|tag| tag.as_vec())
+                .filter(|values| values.first().map(|k| k.as_str()) == Some("r"))
+                .filter_map(|values| values.get(1).cloned()),
+        );
+    }
+    if let Some(contact_list) = latest_of_kind(&events, Kind::ContactList) {
+        if let Ok(serde_json::Value::Object(map)) = serde_json::from_str(&contact_list.content) {
+            relays.extend(map.keys().cloned());
+        }
+    }
+    if events.is_empty() {
+        relays.extend(
+            text.lines()
+                .flat_map(|line| line.split([',', ' ', '\t']))
+                .map(|field| field.trim().trim_matches('"'))
+                .filter(|field| field.starts_with("wss://") || field.starts_with("ws://"))
+                .map(str::to_string),
+        );
+    }
+    let mut unique = Vec::new();
+    for relay in relays {
+        let relay = relay.trim().to_string();
+        if !unique.contains(&relay) {
+            unique.push(relay);
+        }
+    }
+    unique
+}
+
+// 取り込みで変わるフォロー
+#[derive(Debug, Default)]
+pub struct FollowMerge {
+    pub added: Vec<ContactEntry>,   // 新しくフォローする
+    pub updated: Vec<ContactEntry>, // フォロー中で、リレーのヒントやペットネームが埋まる
+}
+
+impl FollowMerge {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.updated.is_empty()
+    }
+
+    // 変更の一覧を表示する（+ は新しいフォロー、~ はヒントの追加）
+    pub fn print_preview(&self) {
+        let describe = |contact: &ContactEntry| {
+            let mut line = display_pubkey(&contact.pubkey);
+            if let Some(petname) = &contact.petname {
+                line.push_str(&format!(" {}", petname));
+            }
+            if let Some(relay) = &contact.relay {
+                line.push_str(&format!(" ({})", relay));
+            }
+            line
+        };
+        for contact in &self.added {
+            println!("+ {}", describe(contact));
+        }
+        for contact in &self.updated {
+            println!("~ {}", describe(contact));
+        }
+    }
+}
+
+// 今のフォローに取り込む（フォローを外すことはせず、すでにあるリレーのヒントとペットネームは書き換えない）
+pub fn merge_follows(contacts: &mut Vec<ContactEntry>, imported: Vec<ContactEntry>) -> FollowMerge {
+    let mut merge = FollowMerge::default();
+    for entry in imported {
+        match contacts.iter_mut().find(|c| c.pubkey == entry.pubkey) {
+            Some(existing) => {
+                let relay = existing.relay.is_none() && entry.relay.is_some();
+                let petname = existing.petname.is_none() && entry.petname.is_some();
+                if relay {
+                    existing.relay = entry.relay;
+                }
+                if petname {
+                    existing.petname = entry.petname;
+                }
+                if relay || petname {
+                    merge.updated.push(existing.clone());
+                }
+            }
+            None => {
+                contacts.push(entry.clone());
+                merge.added.push(entry);
+            }
+        }
+    }
+    merge
+}
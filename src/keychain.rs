@@ -0,0 +1,86 @@
+// OS のキーチェーンへの秘密鍵の保存（macOS のキーチェーン・Windows の資格情報マネージャー・Linux の Secret Service）
+// config の use_keyring が true のとき、パスワードを入力せずに鍵を読み込む
+// Cargo の機能 keychain を外してビルドすると keyring に依存せず、保存と読み込みはエラー、削除は何もしない
+#[cfg(feature = "keychain")]
+use keyring::Entry;
+use nostr_sdk::prelude::*;
+#[cfg(feature = "keychain")]
+use crate::accounts::{active_account, DEFAULT_ACCOUNT};
+
+// キーチェーンに登録するときのサービス名とユーザー名（default 以外のアカウントは "secret-key:<名前>"）
+#[cfg(feature = "keychain")]
+const KEYRING_SERVICE: &str = "nostr-cli-app";
+#[cfg(feature = "keychain")]
+const KEYRING_USER: &str = "secret-key";
+
+#[cfg(not(feature = "keychain"))]
+const UNSUPPORTED: &str = "このビルドには OS のキーチェーン（機能 keychain）が含まれていません";
+
+// 使用中のアカウントのキーチェーンの項目
+#[cfg(feature = "keychain")]
+fn entry() -> Result<Entry, Box<dyn std::error::Error>> {
+    account_entry(&active_account()?)
+}
+
+#[cfg(feature = "keychain")]
+fn account_entry(account: &str) -> Result<Entry, Box<dyn std::error::Error>> {
+    let user = if account == DEFAULT_ACCOUNT {
+        KEYRING_USER.to_string()
+    } else {
+        format!("{}:{}", KEYRING_USER, account)
+    };
+    Ok(Entry::new(KEYRING_SERVICE, &user)?)
+}
+
+// 秘密鍵（nsec）をキーチェーンに保存する
+#[cfg(feature = "keychain")]
+pub fn store_secret_key(secret_key: &SecretKey) -> Result<(), Box<dyn std::error::Error>> {
+    entry()?.set_password(&secret_key.to_bech32()?)?;
+    Ok(())
+}
+
+#[cfg(not(feature = "keychain"))]
+pub fn store_secret_key(_secret_key: &SecretKey) -> Result<(), Box<dyn std::error::Error>> {
+    Err(UNSUPPORTED.into())
+}
+
+// キーチェーンから秘密鍵を読み込む（未登録なら None）
+#[cfg(feature = "keychain")]
+pub fn load_secret_key() -> Result<Option<SecretKey>, Box<dyn std::error::Error>> {
+    match entry()?.get_password() {
+        Ok(nsec) => Ok(Some(SecretKey::from_bech32(nsec.trim())?)),
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(e) => Err(format!("キーチェーンを読み込めません: {}", e).into()),
+    }
+}
+
+#[cfg(not(feature = "keychain"))]
+pub fn load_secret_key() -> Result<Option<SecretKey>, Box<dyn std::error::Error>> {
+    Err(UNSUPPORTED.into())
+}
+
+// 使用中のアカウントの秘密鍵をキーチェーンから削除する（削除したら true）
+#[cfg(feature = "keychain")]
+pub fn delete_secret_key() -> Result<bool, Box<dyn std::error::Error>> {
+    delete_account_secret_key(&active_account()?)
+}
+
+#[cfg(not(feature = "keychain"))]
+pub fn delete_secret_key() -> Result<bool, Box<dyn std::error::Error>> {
+    Ok(false)
+}
+
+// 指定したアカウントの秘密鍵をキーチェーンから削除する（削除したら true）
+#[cfg(feature = "keychain")]
+pub fn delete_account_secret_key(account: &str) -> Result<bool, Box<dyn std::error::Error>> {
+    match account_entry(account)?.delete_password() {
+        Ok(()) => Ok(true),
+        Err(keyring::Error::NoEntry) => Ok(false),
+        Err(e) => Err(format!("キーチェーンから削除できません: {}", e).into()),
+    }
+}
+
+#[cfg(not(feature = "keychain"))]
+pub fn delete_account_secret_key(_account: &str) -> Result<bool, Box<dyn std::error::Error>> {
+    Ok(false)
+}
@@ -0,0 +1,218 @@
+// 秘密鍵の暗号化（NIP-49 の ncryptsec）- パスワードから scrypt で鍵を作り、XChaCha20-Poly1305 で暗号化する
+// config の key_kdf を "argon2id" にすると、鍵の導出に Argon2id を使う（メモリ量と反復回数を設定でき、使ったパラメータは鍵ファイルに記録する）
+use bech32::{FromBase32, ToBase32, Variant};
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::{Aead, KeyInit, OsRng, Payload};
+use chacha20poly1305::{Key, XChaCha20Poly1305, XNonce};
+use nostr_sdk::prelude::*;
+use serde::{Deserialize, Serialize};
+use unicode_normalization::UnicodeNormalization;
+
+const NCRYPTSEC_HRP: &str = "ncryptsec";
+const NIP49_VERSION: u8 = 0x02;
+
+// 復号に失敗したときのエラー（パスワードの間違いとして数える）
+pub const WRONG_PASSWORD: &str = "パスワードが正しくありません";
+
+// scrypt のコスト（2^16 回、メモリ約64MiB）
+pub const DEFAULT_LOG_N: u8 = 16;
+
+// 鍵がこれまでに安全でない形で扱われたか（暗号文の関連データとして一緒に守られる）
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum KeySecurity {
+    Insecure = 0x00, // 平文で保存されたことがある
+    Secure = 0x01,   // 平文で扱われたことはない
+    Unknown = 0x02,  // 不明
+}
+
+impl KeySecurity {
+    fn from_byte(byte: u8) -> Result<Self, Box<dyn std::error::Error>> {
+        match byte {
+            0x00 => Ok(KeySecurity::Insecure),
+            0x01 => Ok(KeySecurity::Secure),
+            0x02 => Ok(KeySecurity::Unknown),
+            other => Err(format!("不明な鍵の安全性の値です: {}", other).into()),
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            KeySecurity::Insecure => "平文で保存されたことがあります",
+            KeySecurity::Secure => "平文で保存されたことはありません",
+            KeySecurity::Unknown => "不明",
+        }
+    }
+}
+
+// パスワード（NFKC で正規化）と salt から暗号化に使う鍵を作る
+fn derive_key(password: &str, salt: &[u8], log_n: u8) -> Result<[u8; 32], Box<dyn std::error::Error>> {
+    let normalized: String = password.nfkc().collect();
+    let params = scrypt::Params::new(log_n, 8, 1, 32).map_err(|e| format!("scrypt のパラメータが不正です: {}", e))?;
+    let mut key = [0u8; 32];
+    scrypt::scrypt(normalized.as_bytes(), salt, &params, &mut key)
+        .map_err(|e| format!("鍵の導出に失敗しました: {}", e))?;
+    Ok(key)
+}
+
+// Argon2id のパラメータ（鍵ファイルに記録し、あとで既定値や設定を変えても同じ値で復号する）
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct Argon2Params {
+    pub memory_kib: u32,  // 使うメモリ（KiB）
+    pub iterations: u32,  // 反復回数
+    pub parallelism: u32, // 並列度
+}
+
+// 鍵の導出の方式とパラメータ（鍵ファイルのヘッダー）
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct KdfHeader {
+    pub algorithm: String, // 今は "argon2id" だけ（新しい方式を足しても、古いファイルはこの値で見分ける）
+    #[serde(flatten)]
+    pub params: Argon2Params,
+    pub salt: String, // hex
+}
+
+// Argon2id で暗号化した秘密鍵
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct EncryptedKey {
+    pub kdf: KdfHeader,
+    pub nonce: String, // hex
+    pub security: u8,  // KeySecurity（暗号文の関連データとして一緒に守られる）
+    pub ciphertext: String, // hex（秘密鍵32バイト + 認証タグ16バイト）
+}
+
+impl EncryptedKey {
+    pub fn security(&self) -> Result<KeySecurity, Box<dyn std::error::Error>> {
+        KeySecurity::from_byte(self.security)
+    }
+}
+
+// パスワード（NFKC で正規化）と salt から Argon2id で暗号化に使う鍵を作る
+fn derive_key_argon2id(password: &str, salt: &[u8], params: Argon2Params) -> Result<[u8; 32], Box<dyn std::error::Error>> {
+    let normalized: String = password.nfkc().collect();
+    let argon2_params = argon2::Params::new(params.memory_kib, params.iterations, params.parallelism, Some(32))
+        .map_err(|e| format!("Argon2id のパラメータが不正です: {}", e))?;
+    let argon2 = argon2::Argon2::new(argon2::Algorithm::Argon2id, argon2::Version::V0x13, argon2_params);
+    let mut key = [0u8; 32];
+    argon2
+        .hash_password_into(normalized.as_bytes(), salt, &mut key)
+        .map_err(|e| format!("鍵の導出に失敗しました: {}", e))?;
+    Ok(key)
+}
+
+// 秘密鍵を Argon2id で導出した鍵で暗号化する
+pub fn encrypt_secret_key_argon2id(
+    secret_key: &SecretKey,
+    password: &str,
+    params: Argon2Params,
+    security: KeySecurity,
+) -> Result<EncryptedKey, Box<dyn std::error::Error>> {
+    let mut salt = [0u8; 16];
+    OsRng.fill_bytes(&mut salt);
+    let mut nonce = [0u8; 24];
+    OsRng.fill_bytes(&mut nonce);
+
+    let key = derive_key_argon2id(password, &salt, params)?;
+    let cipher = XChaCha20Poly1305::new(Key::from_slice(&key));
+    let security_byte = security as u8;
+    let ciphertext = cipher
+        .encrypt(
+            XNonce::from_slice(&nonce),
+            Payload { msg: &secret_key.secret_bytes(), aad: &[security_byte] },
+        )
+        .map_err(|_| "秘密鍵の暗号化に失敗しました")?;
+    Ok(EncryptedKey {
+        kdf: KdfHeader { algorithm: "argon2id".to_string(), params, salt: hex::encode(salt) },
+        nonce: hex::encode(nonce),
+        security: security_byte,
+        ciphertext: hex::encode(ciphertext),
+    })
+}
+
+// ヘッダーのパラメータで鍵を導出し、秘密鍵を復号する
+pub fn decrypt_encrypted_key(encrypted: &EncryptedKey, password: &str) -> Result<SecretKey, Box<dyn std::error::Error>> {
+    if encrypted.kdf.algorithm != "argon2id" {
+        return Err(format!("対応していない鍵の導出方式です: {}（アプリを更新してください）", encrypted.kdf.algorithm).into());
+    }
+    let key = derive_key_argon2id(password, &hex::decode(&encrypted.kdf.salt)?, encrypted.kdf.params)?;
+    let nonce = hex::decode(&encrypted.nonce)?;
+    if nonce.len() != 24 {
+        return Err("暗号化した秘密鍵の nonce の長さが不正です".into());
+    }
+    let cipher = XChaCha20Poly1305::new(Key::from_slice(&key));
+    let plain = cipher
+        .decrypt(
+            XNonce::from_slice(&nonce),
+            Payload { msg: &hex::decode(&encrypted.ciphertext)?, aad: &[encrypted.security] },
+        )
+        .map_err(|_| WRONG_PASSWORD)?;
+    Ok(SecretKey::from_slice(&plain)?)
+}
+
+// 秘密鍵を ncryptsec 文字列に暗号化する
+pub fn encrypt_secret_key(
+    secret_key: &SecretKey,
+    password: &str,
+    log_n: u8,
+    security: KeySecurity,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let mut salt = [0u8; 16];
+    OsRng.fill_bytes(&mut salt);
+    let mut nonce = [0u8; 24];
+    OsRng.fill_bytes(&mut nonce);
+
+    let key = derive_key(password, &salt, log_n)?;
+    let cipher = XChaCha20Poly1305::new(Key::from_slice(&key));
+    let security_byte = security as u8;
+    let ciphertext = cipher
+        .encrypt(
+            XNonce::from_slice(&nonce),
+            Payload { msg: &secret_key.secret_bytes(), aad: &[security_byte] },
+        )
+        .map_err(|_| "秘密鍵の暗号化に失敗しました")?;
+
+    // バージョン・log_n・salt・nonce・安全性・暗号文（秘密鍵32バイト + 認証タグ16バイト）
+    let mut data = vec![NIP49_VERSION, log_n];
+    data.extend_from_slice(&salt);
+    data.extend_from_slice(&nonce);
+    data.push(security_byte);
+    data.extend_from_slice(&ciphertext);
+    Ok(bech32::encode(NCRYPTSEC_HRP, data.to_base32(), Variant::Bech32)?)
+}
+
+// ncryptsec 文字列をバイト列に戻し、形式を確かめる
+fn decode_ncryptsec(ncryptsec: &str) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    let (hrp, data, _) = bech32::decode(ncryptsec.trim())?;
+    if hrp != NCRYPTSEC_HRP {
+        return Err(format!("ncryptsec ではありません: {}", hrp).into());
+    }
+    let data = Vec::<u8>::from_base32(&data)?;
+    if data.len() != 91 {
+        return Err(format!("ncryptsec の長さが不正です ({}バイト)", data.len()).into());
+    }
+    if data[0] != NIP49_VERSION {
+        return Err(format!("対応していない ncryptsec のバージョンです: {}", data[0]).into());
+    }
+    Ok(data)
+}
+
+// ncryptsec に記録された鍵の安全性（復号しなくても読める）
+pub fn key_security(ncryptsec: &str) -> Result<KeySecurity, Box<dyn std::error::Error>> {
+    KeySecurity::from_byte(decode_ncryptsec(ncryptsec)?[42])
+}
+
+// ncryptsec 文字列を復号して秘密鍵を返す
+pub fn decrypt_secret_key(ncryptsec: &str, password: &str) -> Result<SecretKey, Box<dyn std::error::Error>> {
+    let data = decode_ncryptsec(ncryptsec)?;
+    let log_n = data[1];
+    let salt = &data[2..18];
+    let nonce = &data[18..42];
+    let security_byte = data[42];
+    let ciphertext = &data[43..];
+
+    let key = derive_key(password, salt, log_n)?;
+    let cipher = XChaCha20Poly1305::new(Key::from_slice(&key));
+    let plain = cipher
+        .decrypt(XNonce::from_slice(nonce), Payload { msg: ciphertext, aad: &[security_byte] })
+        .map_err(|_| WRONG_PASSWORD)?;
+    Ok(SecretKey::from_slice(&plain)?)
+}
@@ -0,0 +1,232 @@
+// キオスク（展示）モード - 受信したノートを1件ずつ大きく表示する
+use crossterm::{
+    cursor::{Hide, Show},
+    event::{self, Event as CrosstermEvent, KeyCode},
+    execute,
+    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+};
+use nostr_sdk::prelude::*;
+use ratatui::{
+    backend::CrosstermBackend,
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, BorderType, Borders, Paragraph, Wrap},
+    Frame, Terminal,
+};
+use std::{
+    collections::VecDeque,
+    io,
+    time::{Duration, Instant},
+};
+use crate::commands::{format_jst, load_relays, DEFAULT_RELAY};
+use crate::media::{parse_imeta, with_alt_placeholders};
+use crate::transport::dial;
+
+// 未表示ノートを溜めておく上限（古いものから捨てる）
+const KIOSK_QUEUE_LIMIT: usize = 200;
+// 起動直後に表示する直近のノート数
+const KIOSK_BACKLOG: usize = 20;
+
+struct Kiosk {
+    hashtag: Option<String>,
+    queue: VecDeque<Event>,
+    current: Option<Event>,
+    shown_at: Instant,
+    interval: Duration,
+    received: usize,
+}
+
+impl Kiosk {
+    // 受信したノートをキューに追加
+    fn push(&mut self, event: Event) {
+        if self.queue.iter().any(|e| e.id == event.id)
+            || self.current.as_ref().map(|e| e.id) == Some(event.id)
+        {
+            return;
+        }
+        if self.queue.len() >= KIOSK_QUEUE_LIMIT {
+            self.queue.pop_front();
+        }
+        self.queue.push_back(event);
+        self.received += 1;
+    }
+
+    // 次のノートへ切り替え（キューが空なら現在のノートを表示し続ける）
+    fn advance(&mut self) {
+        if let Some(next) = self.queue.pop_front() {
+            self.current = Some(next);
+        }
+        self.shown_at = Instant::now();
+    }
+
+    fn due(&self) -> bool {
+        self.current.is_none() || self.shown_at.elapsed() >= self.interval
+    }
+}
+
+// 半角英数字を全角に変換して大きく見せる
+fn to_fullwidth(text: &str) -> String {
+    text.chars()
+        .map(|c| match c {
+            ' ' => '\u{3000}',
+            '!'..='~' => char::from_u32(c as u32 - 0x21 + 0xFF01).unwrap_or(c),
+            _ => c,
+        })
+        .collect()
+}
+
+pub async fn run_kiosk(hashtag: Option<String>, seconds: u64) -> io::Result<()> {
+    // 閲覧専用なので一時的な鍵で接続する（パスワード入力は不要）
+    let keys = Keys::generate();
+    let client = Client::new(&keys);
+
+    let relay_config = load_relays().map_err(|e| io::Error::new(io::ErrorKind::Other, e.to_string()))?;
+    let urls = if relay_config.relays.is_empty() {
+        vec![DEFAULT_RELAY.to_string()]
+    } else {
+        relay_config.relays
+    };
+    for url in urls {
+        let added = match dial(&url) {
+            Ok(local) => client.add_relay(local).await.map(|_| ()).map_err(|e| e.to_string()),
+            Err(e) => Err(e.to_string()),
+        };
+        if let Err(e) = added {
+            eprintln!("リレー接続エラー ({}): {}", url, e);
+        }
+    }
+    client.connect().await;
+
+    let mut filter = Filter::new().kind(Kind::TextNote).limit(KIOSK_BACKLOG);
+    if let Some(tag) = &hashtag {
+        filter = filter.hashtag(tag.trim_start_matches('#').to_lowercase());
+    }
+
+    let mut notifications = client.notifications();
+    client.subscribe(vec![filter]).await;
+
+    // 画面の初期化
+    enable_raw_mode()?;
+    let mut stdout = io::stdout();
+    execute!(stdout, EnterAlternateScreen, Hide)?;
+    let mut terminal = Terminal::new(CrosstermBackend::new(stdout))?;
+    terminal.clear()?;
+
+    let mut kiosk = Kiosk {
+        hashtag,
+        queue: VecDeque::new(),
+        current: None,
+        shown_at: Instant::now(),
+        interval: Duration::from_secs(seconds.max(1)),
+        received: 0,
+    };
+
+    loop {
+        // 届いているノートをすべて取り込む
+        while let Ok(notification) = notifications.try_recv() {
+            if let RelayPoolNotification::Event { event, .. } = notification {
+                if event.kind == Kind::TextNote {
+                    kiosk.push(event);
+                }
+            }
+        }
+
+        if kiosk.due() {
+            kiosk.advance();
+        }
+
+        terminal.draw(|f| render_kiosk(f, &kiosk))?;
+
+        if event::poll(Duration::from_millis(200))? {
+            if let CrosstermEvent::Key(key) = event::read()? {
+                match key.code {
+                    KeyCode::Char('q') | KeyCode::Esc => break,
+                    KeyCode::Right | KeyCode::Char(' ') => kiosk.advance(),
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    // 終了処理
+    disable_raw_mode()?;
+    execute!(terminal.backend_mut(), LeaveAlternateScreen, Show)?;
+    let _ = client.shutdown().await;
+
+    Ok(())
+}
+
+fn render_kiosk(f: &mut Frame, kiosk: &Kiosk) {
+    let area = f.size();
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .border_style(Style::default().fg(Color::Black))
+        .style(Style::default().bg(Color::White).fg(Color::Black));
+    f.render_widget(block.clone(), area);
+    let inner = block.inner(area);
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(1), // 投稿者と日時
+            Constraint::Min(1),    // 本文
+            Constraint::Length(1), // フッター
+        ])
+        .split(inner);
+
+    let Some(event) = &kiosk.current else {
+        let waiting = Paragraph::new("🙂 ノートを待っています...")
+            .style(Style::default().bg(Color::White).fg(Color::Black).add_modifier(Modifier::BOLD))
+            .alignment(Alignment::Center);
+        f.render_widget(waiting, centered_rows(chunks[1], 1));
+        return;
+    };
+
+    let author = event
+        .pubkey
+        .to_bech32()
+        .map(|pk| format!("npub...{}", &pk[pk.len().saturating_sub(8)..]))
+        .unwrap_or_else(|_| "unknown".to_string());
+    let header = Paragraph::new(Line::from(vec![
+        Span::styled(format!("📄 {}", author), Style::default().add_modifier(Modifier::BOLD)),
+        Span::raw(format!(" - {}", format_jst(event.created_at))),
+    ]))
+    .alignment(Alignment::Center);
+    f.render_widget(header, chunks[0]);
+
+    // 本文は全角化して大きく、上下中央に配置する
+    let content = to_fullwidth(with_alt_placeholders(&event.content, &parse_imeta(event)).trim());
+    let body_width = chunks[1].width.saturating_sub(8).max(1) as usize;
+    let estimated_rows = content
+        .split('\n')
+        .map(|line| unicode_width::UnicodeWidthStr::width(line) / body_width + 1)
+        .sum::<usize>() as u16;
+    let body_area = centered_rows(chunks[1], estimated_rows);
+    let body = Paragraph::new(content)
+        .style(Style::default().bg(Color::White).fg(Color::Black).add_modifier(Modifier::BOLD))
+        .alignment(Alignment::Center)
+        .wrap(Wrap { trim: true });
+    f.render_widget(body, Rect::new(body_area.x + 4, body_area.y, body_area.width.saturating_sub(8), body_area.height));
+
+    let tag_label = kiosk
+        .hashtag
+        .as_ref()
+        .map(|t| format!("#{} | ", t.trim_start_matches('#')))
+        .unwrap_or_default();
+    let footer = Paragraph::new(format!(
+        "{}受信 {}件 | 待機中 {}件 | Space: 次へ | q: 終了",
+        tag_label,
+        kiosk.received,
+        kiosk.queue.len()
+    ))
+    .alignment(Alignment::Center);
+    f.render_widget(footer, chunks[2]);
+}
+
+// 指定した行数の領域を上下中央に配置する
+fn centered_rows(area: Rect, rows: u16) -> Rect {
+    let rows = rows.min(area.height);
+    Rect::new(area.x, area.y + (area.height - rows) / 2, area.width, rows)
+}
@@ -0,0 +1,108 @@
+// 多言語投稿 - 言語ラベル（NIP-32）と翻訳版へのリンク
+use nostr_sdk::prelude::*;
+use std::collections::{HashMap, HashSet};
+
+// 言語ラベルの名前空間（NIP-32）
+pub const LANG_NAMESPACE: &str = "ISO-639-1";
+// 翻訳版へのリンクを表すタグ: ["translation", <イベントID>, <言語コード>]
+// eタグを使うと返信として扱うクライアントがあるため、独自のタグ名にしている
+pub const TRANSLATION_TAG: &str = "translation";
+
+// ISO 639-1 の言語コード（英小文字2文字）か
+pub fn is_valid_lang(code: &str) -> bool {
+    code.len() == 2 && code.chars().all(|c| c.is_ascii_lowercase())
+}
+
+// 言語コードを正規化して検査する
+pub fn normalize_lang(code: &str) -> Result<String, Box<dyn std::error::Error>> {
+    let code = code.trim().to_lowercase();
+    if !is_valid_lang(&code) {
+        return Err(format!("言語コードは ISO 639-1 の2文字で指定してください: {}", code).into());
+    }
+    Ok(code)
+}
+
+// 投稿の言語を示すラベルタグ
+pub fn language_tags(lang: &str) -> Result<Vec<Tag>, Box<dyn std::error::Error>> {
+    Ok(vec![
+        Tag::parse(vec!["L".to_string(), LANG_NAMESPACE.to_string()])?,
+        Tag::parse(vec!["l".to_string(), lang.to_string(), LANG_NAMESPACE.to_string()])?,
+    ])
+}
+
+// 翻訳版へのリンクタグ
+pub fn translation_tag(event_id: EventId, lang: &str) -> Result<Tag, Box<dyn std::error::Error>> {
+    Ok(Tag::parse(vec![TRANSLATION_TAG.to_string(), event_id.to_hex(), lang.to_string()])?)
+}
+
+// 投稿に付いている言語ラベル
+pub fn event_language(event: &Event) -> Option<String> {
+    event
+        .tags
+        .iter()
+        .map(|tag| tag.as_vec())
+        .find(|values| {
+            values.first().map(|k| k.as_str()) == Some("l")
+                && values.get(2).map(|ns| ns.as_str()) == Some(LANG_NAMESPACE)
+        })
+        .and_then(|values| values.get(1).cloned())
+}
+
+// 投稿が案内している翻訳版 (言語コード, イベントID) の一覧
+pub fn translations(event: &Event) -> Vec<(String, EventId)> {
+    event
+        .tags
+        .iter()
+        .map(|tag| tag.as_vec())
+        .filter(|values| values.first().map(|k| k.as_str()) == Some(TRANSLATION_TAG))
+        .filter_map(|values| {
+            let id = EventId::from_hex(values.get(1)?).ok()?;
+            Some((values.get(2)?.to_lowercase(), id))
+        })
+        .collect()
+}
+
+// 優先言語の翻訳版を持つ投稿について、元の投稿ID → 翻訳版イベントの対応を取得する
+// 元の投稿が既に優先言語で書かれている場合は取得しない
+pub async fn fetch_preferred_variants(
+    client: &Client,
+    events: &[Event],
+    preferred: &str,
+) -> Result<HashMap<EventId, Event>, Box<dyn std::error::Error>> {
+    let wanted: Vec<(EventId, EventId)> = events
+        .iter()
+        .filter(|e| event_language(e).as_deref() != Some(preferred))
+        .filter_map(|e| {
+            translations(e)
+                .into_iter()
+                .find(|(lang, _)| lang == preferred)
+                .map(|(_, variant)| (e.id, variant))
+        })
+        .collect();
+    if wanted.is_empty() {
+        return Ok(HashMap::new());
+    }
+
+    let filter = Filter::new().ids(wanted.iter().map(|(_, variant)| *variant));
+    let fetched = client
+        .get_events_of(vec![filter], Some(std::time::Duration::from_secs(10)))
+        .await?;
+
+    Ok(wanted
+        .into_iter()
+        .filter_map(|(original, variant)| {
+            // 他人の投稿を翻訳版として差し込めないよう、元の投稿者本人のものだけ採用する
+            let author = events.iter().find(|e| e.id == original)?.pubkey;
+            let event = fetched.iter().find(|e| e.id == variant && e.pubkey == author)?;
+            Some((original, event.clone()))
+        })
+        .collect())
+}
+
+// 一覧の中で他の投稿の翻訳版として案内されているイベントのID（重複表示を避けるため）
+pub fn variant_ids(events: &[Event]) -> HashSet<EventId> {
+    events
+        .iter()
+        .flat_map(|e| translations(e).into_iter().map(|(_, id)| id))
+        .collect()
+}
@@ -1,5 +1,64 @@
+mod accounts;
+mod agent;
+mod analytics;
+mod attempts;
+mod audit;
+mod animation;
+mod article;
+mod bench;
+mod bundle;
+mod capabilities;
+mod clock;
+mod columns;
 mod commands;
+mod compose;
+mod config;
+mod contacts;
+mod dev;
+mod dm;
+mod drafts;
+mod event_cache;
+mod explore;
+mod feed_kinds;
+mod filter_check;
+mod feed_sort;
+mod geo;
+mod glyphs;
+mod history;
+mod import;
+mod keychain;
+mod keystore;
+mod kiosk;
+mod lang;
+mod media;
+mod moderation;
+mod mutes;
+mod ndjson;
+mod nip05;
+mod nip65;
+mod notepad;
+mod outbox;
+mod palette;
+mod permalink;
+mod preflight;
+mod redact;
+mod retention;
+mod profiles;
+mod safety;
+mod schema;
+mod scrapbook;
+mod session;
+mod shamir;
+mod signer;
+mod snooze;
+mod storage;
+mod streak;
+mod template;
+mod trash;
+mod transport;
 mod tui_app;
+mod verify;
+mod zap;
 
 use clap::{Arg, ArgAction, Command};
 use std::error::Error;
@@ -10,6 +69,13 @@ async fn main() -> Result<(), Box<dyn Error>> {
     let matches = Command::new("Nostr CLI")
         .about("Nostr CLI アプリケーション")
         .subcommand_required(true)
+        .arg(
+            Arg::new("account")
+                .long("account")
+                .help("このコマンドで使うアカウント（account switch で選んだアカウントより優先）")
+                .global(true)
+                .action(ArgAction::Set),
+        )
         .subcommand(
             Command::new("generate-keys")
                 .about("新しい鍵ペアを生成")
@@ -19,9 +85,104 @@ async fn main() -> Result<(), Box<dyn Error>> {
                         .long("password")
                         .help("鍵の暗号化に使用するパスワード")
                         .action(ArgAction::Set),
+                )
+                .arg(
+                    Arg::new("mnemonic")
+                        .long("mnemonic")
+                        .help("BIP-39 のニーモニックから NIP-06 で鍵を導出し、ニーモニックを1度だけ表示する")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("words")
+                        .long("words")
+                        .help("ニーモニックの単語数（12 または 24）")
+                        .value_parser(clap::value_parser!(usize))
+                        .default_value("12")
+                        .requires("mnemonic"),
+                ),
+        )
+        .subcommand(
+            Command::new("recover-keys")
+                .about("ニーモニック（NIP-06）から鍵ペアを復元して暗号化して保存")
+                .arg(
+                    Arg::new("mnemonic")
+                        .help("ニーモニックの単語（- なら標準入力、省略すると画面に表示せずに入力を求める）")
+                        .num_args(1..),
+                )
+                .arg(
+                    Arg::new("passphrase")
+                        .long("passphrase")
+                        .help("BIP-39 のパスフレーズ（ニーモニックの作成時に設定した場合のみ）")
+                        .action(ArgAction::Set),
+                )
+                .arg(
+                    Arg::new("password")
+                        .short('p')
+                        .long("password")
+                        .help("鍵の暗号化に使用するパスワード")
+                        .action(ArgAction::Set),
+                ),
+        )
+        .subcommand(
+            Command::new("import-key")
+                .about("他のクライアントで使っている秘密鍵（nsec / hex）を取り込んで暗号化して保存")
+                .arg(
+                    Arg::new("key")
+                        .help("秘密鍵（nsec1... / hex、- なら標準入力、省略すると入力を求める）"),
+                )
+                .arg(
+                    Arg::new("shamir")
+                        .long("shamir")
+                        .help("export-key --shamir で分けた断片から戻す（- なら標準入力の1行に1つ、省略すると必要な数だけ入力を求める）")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("password")
+                        .short('p')
+                        .long("password")
+                        .help("鍵の暗号化に使用するパスワード")
+                        .action(ArgAction::Set),
+                ),
+        )
+        .subcommand(
+            Command::new("import-npub")
+                .about("公開鍵（npub）だけを登録して閲覧専用で使う（show-feed と TUI のイベントリストは使えるが、投稿などの署名はできない）")
+                .arg(Arg::new("npub").help("公開鍵（npub1... / hex / 別名）").required(true)),
+        )
+        .subcommand(
+            Command::new("export-key")
+                .about("パスワードを確かめてから秘密鍵を nsec で表示（モバイルのクライアントへの移行用）")
+                .arg(
+                    Arg::new("qr")
+                        .long("qr")
+                        .help("端末に QR コードでも表示する")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("ncryptsec")
+                        .long("ncryptsec")
+                        .help("平文の nsec の代わりに、パスワードで暗号化した ncryptsec（NIP-49）を書き出す")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("shamir")
+                        .long("shamir")
+                        .value_name("N-of-M")
+                        .conflicts_with("ncryptsec")
+                        .help("秘密鍵を M 個の断片に分け、N 個集めれば戻せるようにして書き出す（例: 2-of-3）"),
+                )
+                .arg(
+                    Arg::new("out-dir")
+                        .long("out-dir")
+                        .requires("shamir")
+                        .help("断片を1つずつ別のファイルに書き出すディレクトリ"),
                 ),
         )
         .subcommand(Command::new("show-keys").about("鍵情報を表示"))
+        .subcommand(
+            Command::new("delete-keys")
+                .about("使用中のアカウントの鍵ファイルを上書きして削除し、キーチェーンとエージェントの鍵も消す（端末を手放すとき用）"),
+        )
         .subcommand(
             Command::new("send")
                 .about("ノートを送信")
@@ -29,6 +190,55 @@ async fn main() -> Result<(), Box<dyn Error>> {
                     Arg::new("content")
                         .required(true)
                         .help("送信するメッセージの内容"),
+                )
+                .arg(
+                    Arg::new("lang")
+                        .long("lang")
+                        .help("本文の言語（ISO 639-1、例: ja）"),
+                )
+                .arg(
+                    Arg::new("alt")
+                        .long("alt")
+                        .num_args(2)
+                        .value_names(["LANG", "TEXT"])
+                        .action(ArgAction::Append)
+                        .requires("lang")
+                        .help("別の言語の版を同時に投稿（例: --alt en \"english version\"）"),
+                )
+                .arg(
+                    Arg::new("media")
+                        .long("media")
+                        .action(ArgAction::Append)
+                        .help("添付するメディアのURL（代替テキストとサイズを尋ねます）"),
+                )
+                .arg(
+                    Arg::new("subject")
+                        .long("subject")
+                        .help("件名（NIP-14）"),
+                )
+                .arg(
+                    Arg::new("hashtag")
+                        .short('t')
+                        .long("hashtag")
+                        .action(ArgAction::Append)
+                        .help("付けるハッシュタグ（複数指定可）"),
+                )
+                .arg(
+                    Arg::new("location")
+                        .long("location")
+                        .num_args(0..=1)
+                        .default_missing_value("")
+                        .help("位置情報を付ける（ジオハッシュまたは「緯度,経度」、省略時は home_geohash）"),
+                )
+                .arg(
+                    Arg::new("ephemeral")
+                        .long("ephemeral")
+                        .num_args(0..=1)
+                        .value_name("KIND")
+                        .default_missing_value("20000")
+                        .value_parser(clap::value_parser!(u64))
+                        .conflicts_with("alt")
+                        .help("リレーに保存されない一時的なイベント（kind 20000〜29999、省略時は 20000）として送る（ライブチャットでの在席の通知など）"),
                 ),
         )
         .subcommand(
@@ -53,6 +263,247 @@ async fn main() -> Result<(), Box<dyn Error>> {
                         .help("取得するイベントの最大数")
                         .value_parser(clap::value_parser!(usize))
                         .default_value("20"),
+                )
+                .arg(
+                    Arg::new("json")
+                        .long("json")
+                        .help("イベントを1行1件のJSONとして標準出力に出力")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("around")
+                        .long("around")
+                        .help("指定した日時（JST、例: \"2025-01-01 12:00\"）を中心にフィードを表示"),
+                )
+                .arg(
+                    Arg::new("near")
+                        .long("near")
+                        .help("指定した場所（ジオハッシュまたは「緯度,経度」）付近の投稿だけを表示"),
+                )
+                .arg(
+                    Arg::new("radius")
+                        .long("radius")
+                        .requires("near")
+                        .help("--near の範囲（例: 5km、800m。既定は20km）"),
+                )
+                .arg(
+                    Arg::new("relay")
+                        .long("relay")
+                        .help("指定した1つのリレー（例: wss://relay.example.com）の投稿だけを表示"),
+                )
+                .arg(
+                    Arg::new("sort")
+                        .long("sort")
+                        .value_parser(["newest", "oldest", "longest", "most-zapped", "most-replied", "plugin"])
+                        .default_value("newest")
+                        .help("並び順（plugin は config の score_command が付けたスコア順）"),
+                ),
+        )
+        .subcommand(
+            Command::new("react")
+                .about("投稿にリアクション（NIP-25 の kind 7）を送る")
+                .arg(Arg::new("event-id").required(true).help("イベントID (hex / note1 / nevent1)"))
+                .arg(Arg::new("emoji").help("絵文字、または + / -（省略すると +）")),
+        )
+        .subcommand(
+            Command::new("reply")
+                .about("投稿に返信する（NIP-10 のスレッドのタグを付ける）")
+                .arg(Arg::new("event-id").required(true).help("返信先のイベントID (hex / note1 / nevent1)"))
+                .arg(Arg::new("content").required(true).help("本文")),
+        )
+        .subcommand(
+            Command::new("repost")
+                .about("投稿をリポスト（NIP-18 の kind 6）する")
+                .arg(Arg::new("event-id").required(true).help("イベントID (hex / note1 / nevent1)"))
+                .arg(
+                    Arg::new("quote")
+                        .long("quote")
+                        .value_name("TEXT")
+                        .help("リポストの代わりに、コメント付きの引用投稿（kind 1 と q タグ）にする"),
+                ),
+        )
+        .subcommand(
+            Command::new("zap")
+                .about("ライトニングで Zap（NIP-57）を送る（相手のプロフィールの lud16 / lud06 からインボイスを発行する）")
+                .arg(Arg::new("target").required(true).help("相手の npub / hex / 別名、またはノートの note1 / nevent1"))
+                .arg(Arg::new("amount").required(true).value_parser(clap::value_parser!(u64)).help("金額（sats）"))
+                .arg(Arg::new("comment").help("Zap に添えるコメント")),
+        )
+        .subcommand(
+            Command::new("fingerprint")
+                .about("DMの相手との安全番号（両者の公開鍵から作る絵文字と数字）を表示する")
+                .arg(Arg::new("npub").required(true).help("相手の npub / hex / 別名"))
+                .arg(
+                    Arg::new("trust")
+                        .long("trust")
+                        .help("別名や NIP-05 が指す鍵が変わっていたとき、今の鍵を信頼して記録し直す")
+                        .action(ArgAction::SetTrue),
+                ),
+        )
+        .subcommand(
+            Command::new("clip")
+                .about("ノートを注釈付きでスクラップブックに保存")
+                .arg(
+                    Arg::new("event-id")
+                        .required(true)
+                        .help("保存するイベントのID (hex / note1 / nevent1)"),
+                )
+                .arg(
+                    Arg::new("note")
+                        .short('n')
+                        .long("note")
+                        .help("個人的なメモ"),
+                )
+                .arg(
+                    Arg::new("tag")
+                        .short('t')
+                        .long("tag")
+                        .help("分類用のタグ（複数指定可）")
+                        .action(ArgAction::Append),
+                ),
+        )
+        .subcommand(
+            Command::new("scrapbook")
+                .about("スクラップブックの一覧表示・全文検索")
+                .arg(
+                    Arg::new("query")
+                        .help("検索語（#tag でタグ検索）")
+                        .num_args(0..),
+                ),
+        )
+        .subcommand(
+            Command::new("thread")
+                .about("スレッドのミュート管理")
+                .subcommand_required(true)
+                .subcommand(
+                    Command::new("mute")
+                        .about("スレッドをミュート（メンション通知を抑止）")
+                        .arg(Arg::new("event-id").required(true).help("スレッド内のイベントID")),
+                )
+                .subcommand(
+                    Command::new("unmute")
+                        .about("スレッドのミュートを解除")
+                        .arg(Arg::new("event-id").required(true).help("スレッドのルートイベントID")),
+                )
+                .subcommand(Command::new("list").about("ミュート中のスレッドを一覧表示")),
+        )
+        .subcommand(
+            Command::new("note")
+                .about("投稿の情報")
+                .subcommand_required(true)
+                .subcommand(
+                    Command::new("stats")
+                        .about("返信・リポスト・リアクション・Zapを時間帯ごとに集計")
+                        .arg(Arg::new("event-id").required(true).help("対象のイベントID"))
+                        .arg(
+                            Arg::new("bucket")
+                                .long("bucket")
+                                .help("集計の時間単位 (例: 1h, 1d)。省略時は投稿からの経過時間で自動選択"),
+                        )
+                        .arg(
+                            Arg::new("json")
+                                .long("json")
+                                .help("JSONで出力")
+                                .action(ArgAction::SetTrue),
+                        ),
+                ),
+        )
+        .subcommand(
+            Command::new("follow")
+                .about("フォローする（コンタクトリストを取得して書き換え、送り直す。ほかのフォローのヒントはそのまま）")
+                .arg(Arg::new("npub").required(true).help("npub / nprofile / hex / 別名"))
+                .arg(Arg::new("relay").long("relay").help("その人の投稿が見つかるリレーのヒント"))
+                .arg(Arg::new("petname").long("petname").help("ペットネーム（自分だけの呼び名）")),
+        )
+        .subcommand(
+            Command::new("import-follows")
+                .about("ほかのクライアントが書き出したフォローを取り込む（増えるフォローを表示し、確かめてからコンタクトリストを送る）")
+                .arg(
+                    Arg::new("from")
+                        .long("from")
+                        .required(true)
+                        .value_parser(["damus", "amethyst", "csv"])
+                        .help("ファイルの形式（damus: イベントの JSON / amethyst: バックアップの JSON / csv: 公開鍵,リレー,ペットネーム）"),
+                )
+                .arg(Arg::new("file").required(true).help("読み込むファイル")),
+        )
+        .subcommand(
+            Command::new("import-relays")
+                .about("ほかのクライアントが書き出したリレー（kind 10002 / kind 3 の JSON、または1行に1つの URL）を relays.json に取り込む")
+                .arg(Arg::new("from").long("from").required(true).value_name("FILE").help("読み込むファイル")),
+        )
+        .subcommand(
+            Command::new("unfollow")
+                .about("フォローを外す")
+                .arg(Arg::new("npub").required(true).help("npub / nprofile / hex / 別名")),
+        )
+        .subcommand(
+            Command::new("following")
+                .about("フォロー（コンタクトリスト）の管理")
+                .subcommand_required(true)
+                .subcommand(
+                    Command::new("list")
+                        .about("フォロー中の一覧（ペットネーム・プロフィールの名前・リレーのヒント）を表示")
+                        .arg(
+                            Arg::new("json")
+                                .long("json")
+                                .action(ArgAction::SetTrue)
+                                .help("1行に1人の JSON で出力する"),
+                        ),
+                )
+                .subcommand(
+                    Command::new("audit")
+                        .about("リレーごとのコンタクトリストを比較し、古いリレーに最新版を再送")
+                        .arg(
+                            Arg::new("yes")
+                                .short('y')
+                                .long("yes")
+                                .help("確認せずに再送する")
+                                .action(ArgAction::SetTrue),
+                        ),
+                ),
+        )
+        .subcommand(
+            Command::new("moderation")
+                .about("自分の投稿へのスパム返信の自動モデレーション")
+                .subcommand_required(true)
+                .subcommand(
+                    Command::new("scan")
+                        .about("返信を検査し、スパムパターンに一致したものを通報・ミュート（または審査待ちに追加）")
+                        .arg(
+                            Arg::new("since")
+                                .long("since")
+                                .help("検査する期間 (例: 7d, 12h)")
+                                .default_value("7d"),
+                        ),
+                )
+                .subcommand(Command::new("queue").about("審査キューを表示"))
+                .subcommand(
+                    Command::new("approve")
+                        .about("審査待ちの項目を承認して通報・ミュート")
+                        .arg(Arg::new("id").required(true).value_parser(clap::value_parser!(u64)).help("審査キューの番号")),
+                )
+                .subcommand(
+                    Command::new("undo")
+                        .about("通報・ミュートを取り消す")
+                        .arg(Arg::new("id").required(true).value_parser(clap::value_parser!(u64)).help("審査キューの番号")),
+                ),
+        )
+        .subcommand(
+            Command::new("config")
+                .about("設定の表示・変更")
+                .subcommand_required(true)
+                .subcommand(Command::new("show").about("すべての設定を表示"))
+                .subcommand(
+                    Command::new("get")
+                        .about("設定値を表示")
+                        .arg(Arg::new("key").required(true).help("設定項目")),
+                )
+                .subcommand(
+                    Command::new("set")
+                        .about("設定値を変更（値はJSONとして解釈）")
+                        .arg(Arg::new("key").required(true).help("設定項目"))
+                        .arg(Arg::new("value").required(true).num_args(1..).allow_hyphen_values(true).help("設定値")),
                 ),
         )
         .subcommand(
@@ -61,11 +512,17 @@ async fn main() -> Result<(), Box<dyn Error>> {
                 .subcommand(Command::new("list").about("登録されているリレーを一覧表示"))
                 .subcommand(
                     Command::new("add")
-                        .about("リレーを追加")
+                        .about("リレーを追加（接続して書き込めるかを確かめてから保存）")
                         .arg(
                             Arg::new("url")
                                 .required(true)
                                 .help("追加するリレーのURL"),
+                        )
+                        .arg(
+                            Arg::new("force")
+                                .long("force")
+                                .action(ArgAction::SetTrue)
+                                .help("接続と書き込みを確かめずに追加"),
                         ),
                 )
                 .subcommand(
@@ -76,41 +533,956 @@ async fn main() -> Result<(), Box<dyn Error>> {
                                 .required(true)
                                 .help("削除するリレーのURL"),
                         ),
+                )
+                .subcommand(Command::new("rank").about("リレーの応答時間と成功率を計測し、読み込みに使う順位を表示"))
+                .subcommand(
+                    Command::new("auth")
+                        .about("リレーが AUTH（NIP-42）を求めたときに自動で認証するか（既定は on）")
+                        .arg(
+                            Arg::new("mode")
+                                .required(true)
+                                .value_parser(["on", "off"])
+                                .help("on: 認証する / off: 認証しない（公開鍵をリレーに知らせない）"),
+                        )
+                        .arg(Arg::new("url").required(true).help("リレーのURL")),
+                )
+                .subcommand(Command::new("publish").about("登録しているリレーを NIP-65 のリレーの一覧（kind 10002）として送信"))
+                .subcommand(
+                    Command::new("fetch")
+                        .about("ほかの人が NIP-65 で公開しているリレーの一覧を取得し、確かめてから取り込む")
+                        .arg(Arg::new("npub").required(true).help("npub / nprofile / hex / 別名")),
+                )
+                .subcommand(
+                    Command::new("probe-retention")
+                        .about("試験用イベントを送り、リレーが履歴をどこまで保持しているかを実測")
+                        .arg(
+                            Arg::new("url")
+                                .required(true)
+                                .help("調べるリレーのURL"),
+                        )
+                        .arg(
+                            Arg::new("wait")
+                                .long("wait")
+                                .help("送信後に待つ秒数")
+                                .value_parser(clap::value_parser!(u64))
+                                .default_value("10"),
+                        ),
+                ),
+        )
+        .subcommand(Command::new("health").about("鍵・設定・キャッシュ・リレー接続を検査し、問題があれば0以外で終了"))
+        .subcommand(
+            Command::new("tui")
+                .about("TUIモードで起動")
+                .arg(
+                    Arg::new("public-terminal")
+                        .long("public-terminal")
+                        .action(ArgAction::SetTrue)
+                        .help("鍵を読み込まず閲覧だけを行い、自分の公開鍵を伏せる（共有画面・プロジェクターでのデモ用）"),
+                )
+                .arg(
+                    Arg::new("ascii")
+                        .long("ascii")
+                        .action(ArgAction::SetTrue)
+                        .help("⌘ 📁 ■□ などの記号を使わず、すべて ASCII で表示する"),
+                ),
+        )
+        .subcommand(
+            Command::new("kiosk")
+                .about("受信したノートを1件ずつ大きく表示するキオスクモードで起動")
+                .arg(
+                    Arg::new("hashtag")
+                        .short('t')
+                        .long("hashtag")
+                        .help("表示するハッシュタグ"),
+                )
+                .arg(
+                    Arg::new("fullscreen-note-seconds")
+                        .long("fullscreen-note-seconds")
+                        .help("1件のノートを表示する秒数")
+                        .value_parser(clap::value_parser!(u64))
+                        .default_value("10"),
+                ),
+        )
+        .subcommand(
+            Command::new("event")
+                .about("イベントの作成と書き出し・取り込み")
+                .subcommand_required(true)
+                .subcommand(
+                    Command::new("build")
+                        .about("テンプレートからイベントを作成・署名してJSONで出力（bot や cron 向け）")
+                        .arg(
+                            Arg::new("kind")
+                                .long("kind")
+                                .value_parser(clap::value_parser!(u64))
+                                .default_value("1")
+                                .help("イベントの種類"),
+                        )
+                        .arg(
+                            Arg::new("content-template")
+                                .long("content-template")
+                                .conflicts_with("content")
+                                .help("本文のテンプレートファイル（- で標準入力、{{変数}} を置き換え）"),
+                        )
+                        .arg(Arg::new("content").long("content").help("本文のテンプレートを直接指定"))
+                        .arg(
+                            Arg::new("var")
+                                .long("var")
+                                .action(ArgAction::Append)
+                                .help("テンプレートの変数（名前=値、複数指定可）"),
+                        )
+                        .arg(
+                            Arg::new("tag")
+                                .long("tag")
+                                .action(ArgAction::Append)
+                                .help("付けるタグ（名前=値、値にも変数を使える）"),
+                        )
+                        .arg(
+                            Arg::new("publish")
+                                .long("publish")
+                                .action(ArgAction::SetTrue)
+                                .help("作成したイベントをリレーに送信"),
+                        ),
+                )
+                .subcommand(
+                    Command::new("export")
+                        .about("リレーのイベントを1行に1件の JSON（NDJSON）で書き出す（ページごとに書くので大量でも使うメモリは一定）")
+                        .arg(
+                            Arg::new("output")
+                                .short('o')
+                                .long("output")
+                                .required(true)
+                                .help("出力先（- で標準出力）"),
+                        )
+                        .arg(Arg::new("author").long("author").help("投稿者の npub / hex / 別名（省略すると自分）"))
+                        .arg(
+                            Arg::new("kind")
+                                .long("kind")
+                                .value_parser(clap::value_parser!(u64))
+                                .action(ArgAction::Append)
+                                .help("イベントの種類（複数指定可、省略するとすべて）"),
+                        )
+                        .arg(Arg::new("since").long("since").help("さかのぼる期間 (例: 365d、省略するとすべて)")),
+                )
+                .subcommand(
+                    Command::new("import")
+                        .about("NDJSON のアーカイブを1行ずつ読み、署名を確かめてからリレーに送る（壊れた行は行番号を報告して続ける）")
+                        .arg(Arg::new("input").required(true).help("読み込むファイル（- で標準入力）"))
+                        .arg(
+                            Arg::new("dry-run")
+                                .long("dry-run")
+                                .action(ArgAction::SetTrue)
+                                .help("送信せずに、解釈と署名の検証だけを行う"),
+                        ),
+                ),
+        )
+        .subcommand(
+            Command::new("watch")
+                .about("フィルターに一致するイベントを監視し、受信ごとにコマンドを実行")
+                .arg(
+                    Arg::new("filter")
+                        .long("filter")
+                        .required(true)
+                        .help("購読するフィルター（NIP-01 のJSON、例: '{\"kinds\":[1],\"#t\":[\"nostr\"]}'）"),
+                )
+                .arg(
+                    Arg::new("exec")
+                        .long("exec")
+                        .help("受信ごとに実行するコマンド（{} はイベントのJSONに置換、環境変数 NOSTR_EVENT にも設定）"),
+                )
+                .arg(
+                    Arg::new("max-concurrency")
+                        .long("max-concurrency")
+                        .value_parser(clap::value_parser!(usize))
+                        .default_value("4")
+                        .help("同時に実行するコマンドの上限"),
+                ),
+        )
+        .subcommand(
+            Command::new("outbox")
+                .about("どのリレーにも受理されなかったイベントの管理")
+                .subcommand_required(true)
+                .subcommand(Command::new("list").about("アウトボックスのイベントを一覧表示"))
+                .subcommand(Command::new("flush").about("アウトボックスのイベントを再送")),
+        )
+        .subcommand(
+            Command::new("backup")
+                .about("アーカイブリレー（config の backup_relay）の管理と、別の端末に移すための暗号化したバックアップ")
+                .subcommand_required(true)
+                .subcommand(
+                    Command::new("create")
+                        .about("鍵・リレー・設定・連絡先・キャッシュを1つのファイルにまとめ、パスフレーズで暗号化して書き出す")
+                        .arg(Arg::new("output").required(true).help("書き出すファイル")),
+                )
+                .subcommand(
+                    Command::new("restore")
+                        .about("backup create で書き出したファイルを復号して戻す（上書きするファイルは確認する）")
+                        .arg(Arg::new("input").required(true).help("バックアップのファイル")),
+                )
+                .subcommand(
+                    Command::new("verify")
+                        .about("送信済みのイベントがすべてアーカイブリレーにあるか確認")
+                        .arg(
+                            Arg::new("repair")
+                                .long("repair")
+                                .help("足りないイベントをアーカイブリレーに送る")
+                                .action(ArgAction::SetTrue),
+                        ),
+                ),
+        )
+        .subcommand(
+            Command::new("media")
+                .about("メディア取得の設定")
+                .subcommand_required(true)
+                .subcommand(
+                    Command::new("route")
+                        .about("メディアURLを実際にどこから取得するかを表示")
+                        .arg(Arg::new("url").required(true).help("メディアのURL")),
+                )
+                .subcommand(
+                    Command::new("verify")
+                        .about("メディアを取得して SHA-256 を照合（不一致なら保存せずエラー）")
+                        .arg(Arg::new("url").required(true).help("メディアのURL"))
+                        .arg(Arg::new("sha256").help("期待する SHA-256（Blossom のURLなら省略可）"))
+                        .arg(
+                            Arg::new("output")
+                                .short('o')
+                                .long("output")
+                                .help("一致したときに保存するファイル"),
+                        ),
+                ),
+        )
+        .subcommand(
+            Command::new("verify-feed")
+                .about("フィードのイベントの ID と署名を手元で検証し、リレーごとの失敗数を表示")
+                .arg(
+                    Arg::new("relay")
+                        .long("relay")
+                        .help("指定した1つのリレーだけを検証する"),
+                )
+                .arg(
+                    Arg::new("limit")
+                        .short('l')
+                        .long("limit")
+                        .help("各リレーから取得するイベントの数")
+                        .value_parser(clap::value_parser!(usize))
+                        .default_value("200"),
+                ),
+        )
+        .subcommand(
+            Command::new("explore")
+                .about("ネットワークの調査")
+                .subcommand_required(true)
+                .subcommand(
+                    Command::new("kinds")
+                        .about("最近のイベントを集め、kind ごとの件数と例を表示")
+                        .arg(
+                            Arg::new("relay")
+                                .long("relay")
+                                .help("指定した1つのリレーだけを調べる"),
+                        )
+                        .arg(
+                            Arg::new("limit")
+                                .short('l')
+                                .long("limit")
+                                .help("集めるイベントの数")
+                                .value_parser(clap::value_parser!(usize))
+                                .default_value("500"),
+                        )
+                        .arg(
+                            Arg::new("json")
+                                .long("json")
+                                .help("集計をJSONで出力")
+                                .action(ArgAction::SetTrue),
+                        ),
+                ),
+        )
+        .subcommand(
+            Command::new("dm")
+                .about("ダイレクトメッセージの管理")
+                .subcommand_required(true)
+                .subcommand(
+                    Command::new("send")
+                        .about("自分の鍵で暗号化したDM（NIP-04 の kind 4）を送る")
+                        .arg(Arg::new("npub").required(true).help("相手の npub / hex / 別名"))
+                        .arg(Arg::new("text").required(true).help("本文"))
+                        .arg(
+                            Arg::new("gift-wrap")
+                                .long("gift-wrap")
+                                .help("kind 4 の代わりにギフトラップ（NIP-17）で送る")
+                                .action(ArgAction::SetTrue),
+                        ),
+                )
+                .subcommand(
+                    Command::new("relays")
+                        .about("DM受信用リレー（kind 10050）を表示・公開する（URL を指定するとその一覧で置き換える）")
+                        .arg(Arg::new("url").num_args(1..).help("リレーの URL（省略すると公開中の一覧を表示）")),
+                )
+                .subcommand(
+                    Command::new("list")
+                        .about("DMを復号して表示（相手を省略すると会話の一覧）")
+                        .arg(Arg::new("npub").help("相手の npub / hex / 別名"))
+                        .arg(
+                            Arg::new("limit")
+                                .long("limit")
+                                .default_value("20")
+                                .value_parser(clap::value_parser!(usize))
+                                .help("表示する件数"),
+                        ),
+                )
+                .subcommand(
+                    Command::new("export")
+                        .about("会話を復号し、パスフレーズで暗号化したファイル（age 形式）に書き出す")
+                        .arg(Arg::new("npub").required(true).help("相手の npub / hex / 別名"))
+                        .arg(
+                            Arg::new("output")
+                                .short('o')
+                                .long("output")
+                                .required(true)
+                                .help("出力先（例: convo.json.age）"),
+                        ),
+                )
+                .subcommand(
+                    Command::new("purge")
+                        .about("会話を手元のキャッシュから削除（リレー上のイベントは残る）")
+                        .arg(Arg::new("npub").required(true).help("相手の npub / hex / 別名")),
+                ),
+        )
+        .subcommand(
+            Command::new("mentions")
+                .about("自分宛てのメンションを期間をさかのぼって取得し、保存済みの分とまとめてスレッドごとに表示")
+                .arg(
+                    Arg::new("since")
+                        .long("since")
+                        .help("さかのぼる期間 (例: 30d, 12h)")
+                        .default_value("30d"),
+                )
+                .arg(
+                    Arg::new("json")
+                        .long("json")
+                        .help("1行に1件のイベントのJSONで出力する")
+                        .action(ArgAction::SetTrue),
+                ),
+        )
+        .subcommand(
+            Command::new("permalink")
+                .about("投稿の共有用リンクを作成（nevent はイベントを持っているリレーをヒントに含める）")
+                .arg(Arg::new("event-id").required(true).help("イベントID (hex / note1 / nevent1)"))
+                .arg(
+                    Arg::new("format")
+                        .long("format")
+                        .help("形式（nevent / njump / hex）")
+                        .default_value("nevent"),
+                )
+                .arg(
+                    Arg::new("copy")
+                        .long("copy")
+                        .help("クリップボードにもコピーする（端末の OSC 52 を使う）")
+                        .action(ArgAction::SetTrue),
+                ),
+        )
+        .subcommand(
+            Command::new("account")
+                .about("複数の鍵ペア（アカウント）の管理")
+                .subcommand_required(true)
+                .subcommand(Command::new("list").about("鍵を保存しているアカウントの一覧（* は使用中）"))
+                .subcommand(
+                    Command::new("switch")
+                        .about("既定で使うアカウントを切り替え")
+                        .arg(Arg::new("name").help("アカウント名（default で以前からの鍵）").required(true)),
+                )
+                .subcommand(
+                    Command::new("remove")
+                        .about("アカウントの鍵を削除")
+                        .arg(Arg::new("name").help("アカウント名").required(true)),
+                ),
+        )
+        .subcommand(
+            Command::new("drafts")
+                .about("投稿の下書き（sync_drafts を有効にすると暗号化してリレーにも保存し、端末間で同期する）")
+                .subcommand_required(true)
+                .subcommand(Command::new("list").about("下書きの一覧（新しく編集した順）"))
+                .subcommand(
+                    Command::new("show")
+                        .about("下書きを表示")
+                        .arg(Arg::new("id").help("下書きのID（先頭の数文字でもよい）").required(true)),
+                )
+                .subcommand(
+                    Command::new("save")
+                        .about("下書きを保存")
+                        .arg(Arg::new("content").help("本文").required(true))
+                        .arg(Arg::new("subject").long("subject").help("件名"))
+                        .arg(
+                            Arg::new("hashtag")
+                                .short('t')
+                                .long("hashtag")
+                                .help("ハッシュタグ（複数指定可）")
+                                .action(ArgAction::Append),
+                        ),
+                )
+                .subcommand(
+                    Command::new("delete")
+                        .about("下書きを削除（同期していればほかの端末からも消える）")
+                        .arg(Arg::new("id").help("下書きのID（先頭の数文字でもよい）").required(true)),
+                )
+                .subcommand(Command::new("sync").about("手元とリレーの下書きを同期（同じ下書きは最後に編集した方を残す）")),
+        )
+        .subcommand(
+            Command::new("streak")
+                .about("毎日の連続投稿の記録（何日続けて投稿しているか）を表示")
+                .arg(
+                    Arg::new("remind")
+                        .long("remind")
+                        .action(ArgAction::SetTrue)
+                        .help("post_reminder の時刻を過ぎても今日まだ投稿していなければデスクトップ通知とベルで知らせる（cron などから呼ぶ用）"),
+                ),
+        )
+        .subcommand(
+            Command::new("profile")
+                .about("プロフィール（kind 0）の表示と書き換え")
+                .subcommand_required(true)
+                .subcommand(
+                    Command::new("set")
+                        .about("プロフィールの項目を書き換えて送信（指定しなかった項目はそのまま、空の値 \"\" を渡すと消す）")
+                        .arg(Arg::new("name").long("name").help("名前"))
+                        .arg(Arg::new("about").long("about").help("自己紹介"))
+                        .arg(Arg::new("picture").long("picture").help("アイコン画像の URL"))
+                        .arg(Arg::new("nip05").long("nip05").help("NIP-05 のアドレス（name@domain）"))
+                        .arg(Arg::new("lud16").long("lud16").help("ライトニングアドレス（Zap の受け取り先）"))
+                        .arg(Arg::new("banner").long("banner").help("バナー画像の URL")),
+                )
+                .subcommand(
+                    Command::new("show")
+                        .about("プロフィールを取得して表示")
+                        .arg(Arg::new("npub").help("npub / hex / 別名（省略すると自分）"))
+                        .arg(
+                            Arg::new("json")
+                                .long("json")
+                                .action(ArgAction::SetTrue)
+                                .help("kind 0 のイベントを JSON で出力する"),
+                        ),
+                ),
+        )
+        .subcommand(
+            Command::new("nip05")
+                .about("自分のドメインで NIP-05 のアドレス（name@domain）を配信するための補助")
+                .subcommand_required(true)
+                .subcommand(
+                    Command::new("generate")
+                        .about(".well-known/nostr.json に置く JSON を出力する")
+                        .arg(Arg::new("name").long("name").default_value("_").help("アドレスの @ の前（\"_\" でドメインそのもの）"))
+                        .arg(Arg::new("domain").long("domain").required(true).help("配信するドメイン（例: example.com）"))
+                        .arg(Arg::new("pubkey").long("pubkey").help("公開鍵（npub・hex・別名。省略で使用中のアカウント）"))
+                        .arg(
+                            Arg::new("relays")
+                                .long("relays")
+                                .action(ArgAction::SetTrue)
+                                .help("登録済みのリレーも relays に含める"),
+                        ),
+                )
+                .subcommand(
+                    Command::new("verify")
+                        .about("配信している nostr.json が自分の公開鍵を指しているか、または相手のプロフィールの NIP-05 が本人を指しているか確かめる")
+                        .arg(Arg::new("npub").conflicts_with_all(["domain", "pubkey"]).help("確かめる相手の npub / hex / 別名（プロフィールの nip05 を調べる）"))
+                        .arg(Arg::new("name").long("name").default_value("_").help("アドレスの @ の前"))
+                        .arg(Arg::new("domain").long("domain").required_unless_present("npub").help("配信しているドメイン"))
+                        .arg(Arg::new("pubkey").long("pubkey").help("確かめる公開鍵（省略で使用中のアカウント）")),
+                )
+                .subcommand(
+                    Command::new("resolve")
+                        .about("NIP-05 のアドレスから公開鍵（npub）とリレーのヒントを調べる")
+                        .arg(Arg::new("address").required(true).help("name@domain（ドメインだけなら _@domain）")),
+                ),
+        )
+        .subcommand(
+            Command::new("session")
+                .about("TUI のセッションの統計")
+                .subcommand_required(true)
+                .subcommand(Command::new("stats").about("前回の TUI のセッションの統計（受信・送信・転送量・リレー）を表示")),
+        )
+        .subcommand(
+            Command::new("log")
+                .about("このアプリが自分の鍵で署名したイベントの記録")
+                .subcommand_required(true)
+                .subcommand(
+                    Command::new("show")
+                        .about("署名したイベント（kind・ID・作成時刻）と受理したリレーを新しい順に表示")
+                        .arg(
+                            Arg::new("limit")
+                                .long("limit")
+                                .value_parser(clap::value_parser!(usize))
+                                .default_value("50")
+                                .help("表示する件数"),
+                        )
+                        .arg(
+                            Arg::new("kind")
+                                .long("kind")
+                                .value_parser(clap::value_parser!(u64))
+                                .help("この種類のイベントだけを表示"),
+                        )
+                        .arg(
+                            Arg::new("json")
+                                .long("json")
+                                .action(ArgAction::SetTrue)
+                                .help("1行に1件の JSON で出力する"),
+                        ),
+                ),
+        )
+        .subcommand(
+            Command::new("agent")
+                .about("復号した鍵をメモリに置いておくエージェント（ssh-agent のように、動いている間はパスワードの入力を省く）")
+                .subcommand_required(true)
+                .subcommand(
+                    Command::new("start")
+                        .about("パスワードで復号した鍵を持つエージェントを裏で起動")
+                        .arg(
+                            Arg::new("timeout")
+                                .long("timeout")
+                                .help("鍵を持っておく期間 (例: 30m, 8h)")
+                                .default_value("1h"),
+                        ),
+                )
+                .subcommand(Command::new("status").about("エージェントが動いているか、どのアカウントの鍵をいつまで持っているかを表示"))
+                .subcommand(Command::new("stop").about("エージェントを止めて鍵を消す"))
+                .subcommand(
+                    Command::new("serve")
+                        .hide(true)
+                        .arg(Arg::new("timeout").long("timeout").value_parser(clap::value_parser!(u64)).required(true)),
+                ),
+        )
+        .subcommand(
+            Command::new("keyring")
+                .about("OS のキーチェーンへの秘密鍵の保存（パスワードの入力を省く）")
+                .subcommand_required(true)
+                .subcommand(Command::new("store").about("パスワードで復号した秘密鍵をキーチェーンに保存"))
+                .subcommand(Command::new("remove").about("キーチェーンから秘密鍵を削除し、パスワードの入力に戻す")),
+        )
+        .subcommand(
+            Command::new("bench")
+                .about("リレーからの取得・キャッシュの検索・TUIの描画の時間を計測（dev seed-cache で用意したキャッシュを使う）")
+                .arg(
+                    Arg::new("runs")
+                        .long("runs")
+                        .help("キャッシュとリレーの計測を繰り返す回数")
+                        .value_parser(clap::value_parser!(usize))
+                        .default_value("5"),
+                )
+                .arg(
+                    Arg::new("frames")
+                        .long("frames")
+                        .help("描画を計測するフレーム数")
+                        .value_parser(clap::value_parser!(usize))
+                        .default_value("200"),
+                )
+                .arg(
+                    Arg::new("limit")
+                        .short('l')
+                        .long("limit")
+                        .help("リレーから1回に取得するイベントの数")
+                        .value_parser(clap::value_parser!(usize))
+                        .default_value("500"),
+                )
+                .arg(
+                    Arg::new("offline")
+                        .long("offline")
+                        .help("リレーからの取得を計測しない")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("json")
+                        .long("json")
+                        .help("結果をJSONで出力")
+                        .action(ArgAction::SetTrue),
+                ),
+        )
+        .subcommand(
+            Command::new("cache")
+                .about("キャッシュ（cache/*.ndjson）の管理")
+                .subcommand_required(true)
+                .subcommand(
+                    Command::new("policy")
+                        .about("kind ごとの保存期間（config の cache_retention）と、期限を過ぎて取り除かれるイベントの数を表示")
+                        .arg(
+                            Arg::new("vacuum")
+                                .long("vacuum")
+                                .help("期限を過ぎたイベントを今すぐ取り除く")
+                                .action(ArgAction::SetTrue),
+                        ),
+                ),
+        )
+        .subcommand(
+            Command::new("dev")
+                .about("開発用のコマンド")
+                .hide(true)
+                .subcommand_required(true)
+                .subcommand(
+                    Command::new("seed-cache")
+                        .about("毎回同じ内容の合成イベントでフィードのキャッシュを置き換える（オフラインでの UI・性能の確認用）")
+                        .arg(
+                            Arg::new("events")
+                                .long("events")
+                                .help("作るイベントの数")
+                                .value_parser(clap::value_parser!(usize))
+                                .default_value("1000"),
+                        )
+                        .arg(
+                            Arg::new("authors")
+                                .long("authors")
+                                .help("投稿者の数")
+                                .value_parser(clap::value_parser!(usize))
+                                .default_value("20"),
+                        ),
                 ),
         )
-        .subcommand(Command::new("tui").about("TUIモードで起動"))
         .subcommand(Command::new("uibeam").about("「うぃビームだころせ」効果音を再生"))
         .get_matches();
 
+    // --account が指定されていれば、このコマンドの間だけそのアカウントの鍵を使う
+    if let Some(name) = matches.get_one::<String>("account") {
+        accounts::validate_account_name(name)?;
+        accounts::set_account_override(Some(name.clone()));
+    }
+
     // サブコマンドに応じた処理
     match matches.subcommand() {
         Some(("generate-keys", sub_matches)) => {
             commands::generate_keys(sub_matches)?;
         }
+        Some(("recover-keys", sub_matches)) => {
+            commands::recover_keys(sub_matches)?;
+        }
+        Some(("import-key", sub_matches)) => {
+            commands::import_key(sub_matches)?;
+        }
+        Some(("import-npub", sub_matches)) => {
+            commands::import_npub(sub_matches)?;
+        }
+        Some(("export-key", sub_matches)) => {
+            commands::export_key(sub_matches)?;
+        }
         Some(("show-keys", sub_matches)) => {
             commands::show_keys(sub_matches)?;
         }
+        Some(("delete-keys", sub_matches)) => {
+            commands::delete_keys(sub_matches)?;
+        }
         Some(("send", sub_matches)) => {
             commands::send_note(sub_matches).await?;
         }
         Some(("show-feed", sub_matches)) => {
             commands::show_feed(sub_matches).await?;
         }
+        Some(("react", sub_matches)) => {
+            commands::react(sub_matches).await?;
+        }
+        Some(("reply", sub_matches)) => {
+            commands::reply(sub_matches).await?;
+        }
+        Some(("repost", sub_matches)) => {
+            commands::repost(sub_matches).await?;
+        }
+        Some(("zap", sub_matches)) => {
+            commands::zap(sub_matches).await?;
+        }
+        Some(("fingerprint", sub_matches)) => {
+            commands::fingerprint(sub_matches)?;
+        }
+        Some(("clip", sub_matches)) => {
+            commands::clip(sub_matches).await?;
+        }
+        Some(("scrapbook", sub_matches)) => {
+            commands::show_scrapbook(sub_matches)?;
+        }
+        Some(("thread", sub_matches)) => match sub_matches.subcommand() {
+            Some(("mute", mute_matches)) => {
+                commands::mute_thread(mute_matches).await?;
+            }
+            Some(("unmute", unmute_matches)) => {
+                commands::unmute_thread(unmute_matches)?;
+            }
+            Some(("list", list_matches)) => {
+                commands::list_muted_threads(list_matches)?;
+            }
+            _ => unreachable!(),
+        },
+        Some(("note", sub_matches)) => match sub_matches.subcommand() {
+            Some(("stats", stats_matches)) => {
+                commands::note_stats(stats_matches).await?;
+            }
+            _ => unreachable!(),
+        },
+        Some(("follow", sub_matches)) => {
+            commands::follow(sub_matches).await?;
+        }
+        Some(("import-follows", sub_matches)) => {
+            commands::import_follows(sub_matches).await?;
+        }
+        Some(("import-relays", sub_matches)) => {
+            commands::import_relays(sub_matches)?;
+        }
+        Some(("unfollow", sub_matches)) => {
+            commands::unfollow(sub_matches).await?;
+        }
+        Some(("following", sub_matches)) => match sub_matches.subcommand() {
+            Some(("list", list_matches)) => {
+                commands::following_list(list_matches).await?;
+            }
+            Some(("audit", audit_matches)) => {
+                commands::following_audit(audit_matches).await?;
+            }
+            _ => unreachable!(),
+        },
+        Some(("moderation", sub_matches)) => match sub_matches.subcommand() {
+            Some(("scan", scan_matches)) => {
+                commands::moderation_scan(scan_matches).await?;
+            }
+            Some(("queue", queue_matches)) => {
+                commands::moderation_queue(queue_matches)?;
+            }
+            Some(("approve", approve_matches)) => {
+                commands::moderation_approve(approve_matches).await?;
+            }
+            Some(("undo", undo_matches)) => {
+                commands::moderation_undo(undo_matches).await?;
+            }
+            _ => unreachable!(),
+        },
+        Some(("config", sub_matches)) => match sub_matches.subcommand() {
+            Some(("show", show_matches)) => {
+                commands::show_config(show_matches)?;
+            }
+            Some(("get", get_matches)) => {
+                commands::get_config(get_matches)?;
+            }
+            Some(("set", set_matches)) => {
+                commands::set_config(set_matches)?;
+            }
+            _ => unreachable!(),
+        },
         Some(("relay", sub_matches)) => match sub_matches.subcommand() {
             Some(("list", list_matches)) => {
                 commands::list_relays(list_matches)?;
             }
             Some(("add", add_matches)) => {
-                commands::add_relay(add_matches)?;
+                commands::add_relay(add_matches).await?;
             }
             Some(("remove", remove_matches)) => {
                 commands::remove_relay(remove_matches)?;
             }
+            Some(("auth", auth_matches)) => {
+                commands::relay_auth(auth_matches)?;
+            }
+            Some(("rank", rank_matches)) => {
+                commands::rank_relays(rank_matches).await?;
+            }
+            Some(("publish", publish_matches)) => {
+                commands::relay_publish(publish_matches).await?;
+            }
+            Some(("fetch", fetch_matches)) => {
+                commands::relay_fetch(fetch_matches).await?;
+            }
+            Some(("probe-retention", probe_matches)) => {
+                commands::probe_retention(probe_matches).await?;
+            }
+            _ => unreachable!(),
+        },
+        Some(("health", sub_matches)) => {
+            // 接続を閉じ終えてから、問題の分類に応じた終了コードで終了する
+            let code = commands::health(sub_matches).await?;
+            if code != 0 {
+                std::process::exit(code);
+            }
+        }
+        Some(("tui", sub_matches)) => {
+            tui_app::run_tui(sub_matches.get_flag("public-terminal"), sub_matches.get_flag("ascii")).await?;
+        }
+        Some(("kiosk", sub_matches)) => {
+            let hashtag = sub_matches.get_one::<String>("hashtag").cloned();
+            let seconds = *sub_matches.get_one::<u64>("fullscreen-note-seconds").unwrap_or(&10);
+            kiosk::run_kiosk(hashtag, seconds).await?;
+        }
+        Some(("event", sub_matches)) => match sub_matches.subcommand() {
+            Some(("build", build_matches)) => {
+                commands::event_build(build_matches).await?;
+            }
+            Some(("export", export_matches)) => {
+                commands::event_export(export_matches).await?;
+            }
+            Some(("import", import_matches)) => {
+                commands::event_import(import_matches).await?;
+            }
+            _ => unreachable!(),
+        },
+        Some(("watch", sub_matches)) => {
+            commands::watch(sub_matches).await?;
+        }
+        Some(("outbox", sub_matches)) => match sub_matches.subcommand() {
+            Some(("list", list_matches)) => {
+                commands::outbox_list(list_matches)?;
+            }
+            Some(("flush", flush_matches)) => {
+                commands::outbox_flush(flush_matches).await?;
+            }
+            _ => unreachable!(),
+        },
+        Some(("backup", sub_matches)) => match sub_matches.subcommand() {
+            Some(("create", create_matches)) => {
+                commands::backup_create(create_matches)?;
+            }
+            Some(("restore", restore_matches)) => {
+                commands::backup_restore(restore_matches)?;
+            }
+            Some(("verify", verify_matches)) => {
+                commands::backup_verify(verify_matches).await?;
+            }
+            _ => unreachable!(),
+        },
+        Some(("media", sub_matches)) => match sub_matches.subcommand() {
+            Some(("route", route_matches)) => {
+                commands::media_route(route_matches)?;
+            }
+            Some(("verify", verify_matches)) => {
+                commands::media_verify(verify_matches).await?;
+            }
+            _ => unreachable!(),
+        },
+        Some(("verify-feed", sub_matches)) => {
+            commands::verify_feed(sub_matches).await?;
+        }
+        Some(("explore", sub_matches)) => match sub_matches.subcommand() {
+            Some(("kinds", kinds_matches)) => {
+                commands::explore_kinds(kinds_matches).await?;
+            }
+            _ => unreachable!(),
+        },
+        Some(("mentions", sub_matches)) => {
+            commands::mentions(sub_matches).await?;
+        }
+        Some(("permalink", sub_matches)) => {
+            commands::permalink(sub_matches).await?;
+        }
+        Some(("account", sub_matches)) => match sub_matches.subcommand() {
+            Some(("list", list_matches)) => {
+                commands::account_list(list_matches)?;
+            }
+            Some(("switch", switch_matches)) => {
+                commands::account_switch(switch_matches)?;
+            }
+            Some(("remove", remove_matches)) => {
+                commands::account_remove(remove_matches)?;
+            }
+            _ => unreachable!(),
+        },
+        Some(("drafts", sub_matches)) => match sub_matches.subcommand() {
+            Some(("list", list_matches)) => {
+                commands::drafts_list(list_matches)?;
+            }
+            Some(("show", show_matches)) => {
+                commands::drafts_show(show_matches)?;
+            }
+            Some(("save", save_matches)) => {
+                commands::drafts_save(save_matches).await?;
+            }
+            Some(("delete", delete_matches)) => {
+                commands::drafts_delete(delete_matches).await?;
+            }
+            Some(("sync", sync_matches)) => {
+                commands::drafts_sync(sync_matches).await?;
+            }
             _ => unreachable!(),
         },
-        Some(("tui", _)) => {
-            tui_app::run_tui().await?;
+        Some(("streak", sub_matches)) => {
+            commands::streak(sub_matches)?;
         }
+        Some(("profile", sub_matches)) => match sub_matches.subcommand() {
+            Some(("set", set_matches)) => {
+                commands::profile_set(set_matches).await?;
+            }
+            Some(("show", show_matches)) => {
+                commands::profile_show(show_matches).await?;
+            }
+            _ => unreachable!(),
+        },
+        Some(("nip05", sub_matches)) => match sub_matches.subcommand() {
+            Some(("generate", generate_matches)) => {
+                commands::nip05_generate(generate_matches).await?;
+            }
+            Some(("verify", verify_matches)) => {
+                commands::nip05_verify(verify_matches).await?;
+            }
+            Some(("resolve", resolve_matches)) => {
+                commands::nip05_resolve(resolve_matches).await?;
+            }
+            _ => unreachable!(),
+        },
+        Some(("log", sub_matches)) => match sub_matches.subcommand() {
+            Some(("show", show_matches)) => {
+                commands::log_show(show_matches)?;
+            }
+            _ => unreachable!(),
+        },
+        Some(("session", sub_matches)) => match sub_matches.subcommand() {
+            Some(("stats", stats_matches)) => {
+                commands::session_stats(stats_matches)?;
+            }
+            _ => unreachable!(),
+        },
+        Some(("agent", sub_matches)) => match sub_matches.subcommand() {
+            Some(("start", start_matches)) => {
+                commands::agent_start(start_matches)?;
+            }
+            Some(("status", status_matches)) => {
+                commands::agent_status_command(status_matches)?;
+            }
+            Some(("stop", stop_matches)) => {
+                commands::agent_stop(stop_matches)?;
+            }
+            Some(("serve", serve_matches)) => {
+                commands::agent_serve(serve_matches).await?;
+            }
+            _ => unreachable!(),
+        },
+        Some(("keyring", sub_matches)) => match sub_matches.subcommand() {
+            Some(("store", store_matches)) => {
+                commands::keyring_store(store_matches)?;
+            }
+            Some(("remove", remove_matches)) => {
+                commands::keyring_remove(remove_matches)?;
+            }
+            _ => unreachable!(),
+        },
+        Some(("bench", sub_matches)) => {
+            commands::bench(sub_matches).await?;
+        }
+        Some(("cache", sub_matches)) => match sub_matches.subcommand() {
+            Some(("policy", policy_matches)) => {
+                commands::cache_policy(policy_matches)?;
+            }
+            _ => unreachable!(),
+        },
+        Some(("dev", sub_matches)) => match sub_matches.subcommand() {
+            Some(("seed-cache", seed_matches)) => {
+                commands::dev_seed_cache(seed_matches)?;
+            }
+            _ => unreachable!(),
+        },
+        Some(("dm", sub_matches)) => match sub_matches.subcommand() {
+            Some(("send", send_matches)) => {
+                commands::dm_send(send_matches).await?;
+            }
+            Some(("list", list_matches)) => {
+                commands::dm_list(list_matches).await?;
+            }
+            Some(("relays", relays_matches)) => {
+                commands::dm_relays(relays_matches).await?;
+            }
+            Some(("export", export_matches)) => {
+                commands::dm_export(export_matches).await?;
+            }
+            Some(("purge", purge_matches)) => {
+                commands::dm_purge(purge_matches)?;
+            }
+            _ => unreachable!(),
+        },
         Some(("uibeam", sub_matches)) => {
             commands::play_uibeam(sub_matches).await?;
         }
@@ -0,0 +1,199 @@
+// 添付メディアの情報（NIP-92 imeta タグ）と取得時のプロキシ設定・ハッシュの検証
+use nostr_sdk::hashes::{sha256, Hash};
+use nostr_sdk::prelude::*;
+use crate::config::AppConfig;
+
+// imeta タグ1つ分の情報
+#[derive(Debug, Clone, Default)]
+pub struct MediaInfo {
+    pub url: String,
+    pub mime: Option<String>,
+    pub alt: Option<String>,
+    pub dim: Option<String>, // "幅x高さ"
+    pub sha256: Option<String>, // 内容のハッシュ（x）
+}
+
+// URLの拡張子からMIMEタイプを推測する
+pub fn guess_mime(url: &str) -> Option<String> {
+    let path = url.split(['?', '#']).next().unwrap_or(url);
+    let ext = path.rsplit('.').next()?.to_lowercase();
+    let mime = match ext.as_str() {
+        "jpg" | "jpeg" => "image/jpeg",
+        "png" => "image/png",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "avif" => "image/avif",
+        "mp4" => "video/mp4",
+        "webm" => "video/webm",
+        "mp3" => "audio/mpeg",
+        _ => return None,
+    };
+    Some(mime.to_string())
+}
+
+// "幅x高さ" の形式か
+pub fn is_valid_dim(dim: &str) -> bool {
+    match dim.split_once('x') {
+        Some((w, h)) => w.parse::<u32>().is_ok() && h.parse::<u32>().is_ok(),
+        None => false,
+    }
+}
+
+// imeta タグを作成する
+pub fn imeta_tag(info: &MediaInfo) -> Result<Tag, Box<dyn std::error::Error>> {
+    let mut values = vec!["imeta".to_string(), format!("url {}", info.url)];
+    if let Some(mime) = &info.mime {
+        values.push(format!("m {}", mime));
+    }
+    if let Some(dim) = &info.dim {
+        values.push(format!("dim {}", dim));
+    }
+    if let Some(sha256) = &info.sha256 {
+        values.push(format!("x {}", sha256));
+    }
+    if let Some(alt) = &info.alt {
+        values.push(format!("alt {}", alt));
+    }
+    Ok(Tag::parse(values)?)
+}
+
+// イベントの imeta タグを読み取る
+pub fn parse_imeta(event: &Event) -> Vec<MediaInfo> {
+    event
+        .tags
+        .iter()
+        .map(|tag| tag.as_vec())
+        .filter(|values| values.first().map(|k| k.as_str()) == Some("imeta"))
+        .filter_map(|values| {
+            let mut info = MediaInfo::default();
+            // 各要素は "キー 値" の形式
+            for entry in values.iter().skip(1) {
+                let Some((key, value)) = entry.split_once(' ') else { continue };
+                match key {
+                    "url" => info.url = value.to_string(),
+                    "m" => info.mime = Some(value.to_string()),
+                    "alt" => info.alt = Some(value.to_string()),
+                    "dim" => info.dim = Some(value.to_string()),
+                    "x" => info.sha256 = Some(value.to_lowercase()),
+                    _ => {}
+                }
+            }
+            (!info.url.is_empty()).then_some(info)
+        })
+        .collect()
+}
+
+// 画像を表示できない環境向けに、本文中のメディアURLを代替テキストに置き換える
+pub fn with_alt_placeholders(content: &str, media: &[MediaInfo]) -> String {
+    let mut text = content.to_string();
+    for info in media {
+        if let Some(alt) = info.alt.as_deref().filter(|a| !a.is_empty()) {
+            text = text.replace(&info.url, &format!("[🖼 {}]", alt));
+        }
+    }
+    text
+}
+
+// メディアURLの取得方法
+#[derive(Debug, Clone, PartialEq)]
+pub enum MediaRoute {
+    Direct(String),  // 元のURLから直接取得する
+    Proxied(String), // プロキシ経由で取得する
+    Blocked,         // 取得しない
+}
+
+// URLのホスト名（小文字）を取り出す
+pub fn media_host(url: &str) -> Option<String> {
+    let rest = url.split_once("://").map(|(_, rest)| rest).unwrap_or(url);
+    let authority = rest.split(['/', '?', '#']).next()?;
+    let host = authority.rsplit('@').next()?.split(':').next()?;
+    (!host.is_empty()).then(|| host.to_lowercase())
+}
+
+// ホスト名がドメインの規則に一致するか（サブドメインも含む）
+fn domain_matches(host: &str, rule: &str) -> bool {
+    let rule = rule.trim().trim_start_matches("*.").to_lowercase();
+    host == rule || host.ends_with(&format!(".{}", rule))
+}
+
+// URLをクエリ文字列などに埋め込めるようにエンコードする
+fn percent_encode(input: &str) -> String {
+    input
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => (b as char).to_string(),
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+// 設定に従ってメディアURLの取得方法を決める
+// 拒否リストのドメインは取得せず、許可リストのドメインはプロキシを通さずに直接取得する。
+// それ以外はプロキシが設定されていればプロキシ経由にする。
+// プロキシのURLに {url} があればエンコードした元URLで置き換え、なければ末尾に元URLを付け足す
+// （imgproxy の /insecure/plain/ 形式など）。
+pub fn route_media_url(url: &str, config: &AppConfig) -> MediaRoute {
+    let host = media_host(url).unwrap_or_default();
+    if config.media_deny_domains.iter().any(|rule| domain_matches(&host, rule)) {
+        return MediaRoute::Blocked;
+    }
+    if config.media_allow_domains.iter().any(|rule| domain_matches(&host, rule)) {
+        return MediaRoute::Direct(url.to_string());
+    }
+    match config.media_proxy.as_deref().map(str::trim).filter(|p| !p.is_empty()) {
+        Some(proxy) if proxy.contains("{url}") => MediaRoute::Proxied(proxy.replace("{url}", &percent_encode(url))),
+        Some(proxy) => MediaRoute::Proxied(format!("{}{}", proxy, url)),
+        None => MediaRoute::Direct(url.to_string()),
+    }
+}
+
+// 設定に従って実際に取得するURLを返す（拒否されたドメインならエラー）
+pub fn resolve_media_url(url: &str) -> Result<String, Box<dyn std::error::Error>> {
+    match route_media_url(url, &AppConfig::load()?) {
+        MediaRoute::Direct(url) | MediaRoute::Proxied(url) => Ok(url),
+        MediaRoute::Blocked => Err(format!("取得が拒否されたドメインです: {}", url).into()),
+    }
+}
+
+// 64桁の16進数（SHA-256）か
+pub fn is_sha256_hex(value: &str) -> bool {
+    value.len() == 64 && value.bytes().all(|b| b.is_ascii_hexdigit())
+}
+
+// Blossom のURL（https://サーバー/<sha256>.拡張子）からハッシュを取り出す
+pub fn blossom_hash(url: &str) -> Option<String> {
+    let path = url.split(['?', '#']).next().unwrap_or(url);
+    let file = path.rsplit('/').next()?;
+    let stem = file.split('.').next().unwrap_or(file);
+    is_sha256_hex(stem).then(|| stem.to_lowercase())
+}
+
+// 内容の SHA-256 を16進数で返す
+pub fn sha256_hex(bytes: &[u8]) -> String {
+    sha256::Hash::hash(bytes).to_string()
+}
+
+// メディアを取得し、期待するハッシュ（x タグの値。なければ Blossom のURLから取り出したもの）と照合する
+// 一致しなければ内容を返さないので、改ざん・差し替えられたメディアを表示・保存しない
+pub async fn fetch_verified_media(url: &str, expected: Option<&str>) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    let expected = match expected {
+        Some(hash) if is_sha256_hex(hash) => Some(hash.to_lowercase()),
+        Some(hash) => return Err(format!("SHA-256 は64桁の16進数で指定してください: {}", hash).into()),
+        None => blossom_hash(url),
+    };
+
+    let response = reqwest::get(resolve_media_url(url)?).await?.error_for_status()?;
+    let bytes = response.bytes().await?.to_vec();
+
+    if let Some(expected) = expected {
+        let actual = sha256_hex(&bytes);
+        if actual != expected {
+            return Err(format!(
+                "⚠ ハッシュが一致しません。メディアが差し替えられている可能性があります（期待: {}、実際: {}）",
+                expected, actual
+            )
+            .into());
+        }
+    }
+    Ok(bytes)
+}
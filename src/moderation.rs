@@ -0,0 +1,108 @@
+// 自動モデレーション - 自分の投稿へのスパム返信を通報・ミュートし、その記録を審査キューに残す
+use nostr_sdk::prelude::*;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use crate::commands::app_dir;
+
+// NIP-56 の通報イベント
+pub const KIND_REPORT: u64 = 1984;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum ModerationStatus {
+    Pending, // 審査待ち（まだ通報もミュートもしていない）
+    Applied, // 通報・ミュート済み
+    Undone,  // 取り消し済み
+}
+
+// 審査キューの1項目
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ModerationAction {
+    pub id: u64,
+    pub reply: Event,
+    pub pattern: String,
+    pub status: ModerationStatus,
+    // 公開した通報イベントのID (hex)。取り消し時の削除要求に使う
+    #[serde(default)]
+    pub report_id: Option<String>,
+    pub created_at: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct ModerationQueue {
+    #[serde(default)]
+    pub next_id: u64,
+    #[serde(default)]
+    pub actions: Vec<ModerationAction>,
+}
+
+impl ModerationQueue {
+    // moderation.json を読み込む（未作成なら空）
+    pub fn load() -> Result<Self, Box<dyn std::error::Error>> {
+        let path = app_dir()?.join("moderation.json");
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let contents = fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&contents)?)
+    }
+
+    // moderation.json に保存する
+    pub fn save(&self) -> Result<(), Box<dyn std::error::Error>> {
+        let dir = app_dir()?;
+        fs::create_dir_all(&dir)?;
+        fs::write(dir.join("moderation.json"), serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    // 既にキューにある返信かどうか
+    pub fn contains(&self, event_id: EventId) -> bool {
+        self.actions.iter().any(|a| a.reply.id == event_id)
+    }
+
+    // 新しい項目を追加して番号を返す
+    pub fn push(&mut self, reply: Event, pattern: String, status: ModerationStatus) -> u64 {
+        self.next_id += 1;
+        self.actions.push(ModerationAction {
+            id: self.next_id,
+            reply,
+            pattern,
+            status,
+            report_id: None,
+            created_at: Timestamp::now().as_u64(),
+        });
+        self.next_id
+    }
+
+    pub fn get_mut(&mut self, id: u64) -> Option<&mut ModerationAction> {
+        self.actions.iter_mut().find(|a| a.id == id)
+    }
+}
+
+// 設定されたパターンを正規表現としてコンパイルする
+pub fn compile_patterns(patterns: &[String]) -> Result<Vec<Regex>, Box<dyn std::error::Error>> {
+    patterns
+        .iter()
+        .map(|p| Regex::new(p).map_err(|e| format!("スパムパターンが正しくありません ({}): {}", p, e).into()))
+        .collect()
+}
+
+// 本文に一致したパターンを返す
+pub fn match_spam<'a>(content: &str, patterns: &'a [Regex]) -> Option<&'a Regex> {
+    patterns.iter().find(|re| re.is_match(content))
+}
+
+// 返信をスパムとして通報するイベント（NIP-56）を作成する
+pub fn report_builder(reply: &Event) -> Result<EventBuilder, Box<dyn std::error::Error>> {
+    let tags = vec![
+        Tag::parse(vec!["p".to_string(), reply.pubkey.to_string(), "spam".to_string()])?,
+        Tag::parse(vec!["e".to_string(), reply.id.to_hex(), "spam".to_string()])?,
+    ];
+    Ok(EventBuilder::new(Kind::from(KIND_REPORT), "", tags))
+}
+
+// 通報を取り消すための削除要求（NIP-09）を作成する
+pub fn retract_builder(report_id: EventId) -> Result<EventBuilder, Box<dyn std::error::Error>> {
+    let tags = vec![Tag::parse(vec!["e".to_string(), report_id.to_hex()])?];
+    Ok(EventBuilder::new(Kind::EventDeletion, "モデレーションの取り消し", tags))
+}
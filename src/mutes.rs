@@ -0,0 +1,145 @@
+// ミュート設定 - ミュートしたスレッドと投稿者を mutes.json に保存する
+use nostr_sdk::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use crate::commands::app_dir;
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+pub struct MuteList {
+    // ミュートしたスレッドのルートイベントID (hex)
+    #[serde(default)]
+    pub threads: Vec<String>,
+    // ミュートした投稿者の公開鍵 (hex)
+    #[serde(default)]
+    pub authors: Vec<String>,
+}
+
+impl MuteList {
+    // mutes.json を読み込む（未作成なら空）
+    pub fn load() -> Result<Self, Box<dyn std::error::Error>> {
+        let path = app_dir()?.join("mutes.json");
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let contents = fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&contents)?)
+    }
+
+    // mutes.json に保存する
+    pub fn save(&self) -> Result<(), Box<dyn std::error::Error>> {
+        let dir = app_dir()?;
+        fs::create_dir_all(&dir)?;
+        fs::write(dir.join("mutes.json"), serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    // スレッドをミュートする（追加した場合は true）
+    pub fn mute_thread(&mut self, root: EventId) -> bool {
+        let hex = root.to_hex();
+        if self.threads.contains(&hex) {
+            return false;
+        }
+        self.threads.push(hex);
+        true
+    }
+
+    // スレッドのミュートを解除する（解除した場合は true）
+    pub fn unmute_thread(&mut self, root: EventId) -> bool {
+        let hex = root.to_hex();
+        let before = self.threads.len();
+        self.threads.retain(|t| t != &hex);
+        before != self.threads.len()
+    }
+
+    // 投稿者をミュートする（追加した場合は true）
+    pub fn mute_author(&mut self, pubkey: XOnlyPublicKey) -> bool {
+        let hex = pubkey.to_string();
+        if self.authors.contains(&hex) {
+            return false;
+        }
+        self.authors.push(hex);
+        true
+    }
+
+    // 投稿者のミュートを解除する（解除した場合は true）
+    pub fn unmute_author(&mut self, pubkey: XOnlyPublicKey) -> bool {
+        let hex = pubkey.to_string();
+        let before = self.authors.len();
+        self.authors.retain(|a| a != &hex);
+        before != self.authors.len()
+    }
+
+    // イベントの投稿者がミュートされているか
+    pub fn is_author_muted(&self, event: &Event) -> bool {
+        self.authors.contains(&event.pubkey.to_string())
+    }
+
+    // イベントがミュート中のスレッドに属しているか
+    pub fn is_thread_muted(&self, event: &Event) -> bool {
+        let root = thread_root(event).to_hex();
+        self.threads.contains(&root)
+            || event_tag_ids(event).iter().any(|id| self.threads.contains(&id.to_hex()))
+    }
+}
+
+// イベントのeタグに含まれるイベントID一覧
+pub fn event_tag_ids(event: &Event) -> Vec<EventId> {
+    event
+        .tags
+        .iter()
+        .map(|tag| tag.as_vec())
+        .filter(|values| values.first().map(|k| k.as_str()) == Some("e"))
+        .filter_map(|values| values.get(1).and_then(|id| EventId::from_hex(id).ok()))
+        .collect()
+}
+
+// NIP-10 に従ってスレッドのルートイベントIDを求める
+// root マーカー付きのeタグ、なければ最初のeタグ（旧形式）、どちらもなければ自身がルート
+pub fn thread_root(event: &Event) -> EventId {
+    let e_tags: Vec<Vec<String>> = event
+        .tags
+        .iter()
+        .map(|tag| tag.as_vec())
+        .filter(|values| values.first().map(|k| k.as_str()) == Some("e"))
+        .collect();
+
+    let marked_root = e_tags
+        .iter()
+        .find(|values| values.get(3).map(|m| m.as_str()) == Some("root"))
+        .and_then(|values| values.get(1))
+        .and_then(|id| EventId::from_hex(id).ok());
+
+    marked_root
+        .or_else(|| e_tags.first().and_then(|values| values.get(1)).and_then(|id| EventId::from_hex(id).ok()))
+        .unwrap_or(event.id)
+}
+
+// イベントの p タグに公開鍵が含まれているか（保存済みのメンションからアカウントごとに取り出す）
+pub fn mentions_me(event: &Event, my_pubkey: XOnlyPublicKey) -> bool {
+    let hex = my_pubkey.to_string();
+    event
+        .tags
+        .iter()
+        .map(|tag| tag.as_vec())
+        .any(|values| values.first().map(|k| k.as_str()) == Some("p") && values.get(1) == Some(&hex))
+}
+
+// フィルターにミュート中のスレッドを除く条件（"!#e"）を加える
+// nostr-sdk がこの条件をリレーに送れる形で保てなければ None（その場合は手元で捨てるだけにする）
+fn exclude_threads(filter: Filter, roots: &[String]) -> Option<Filter> {
+    let mut value = serde_json::to_value(&filter).ok()?;
+    value.as_object_mut()?.insert("!#e".to_string(), serde_json::json!(roots));
+    let excluded: Filter = serde_json::from_value(value).ok()?;
+    serde_json::to_value(&excluded).ok()?.get("!#e").is_some().then_some(excluded)
+}
+
+// 自分宛てのメンションを購読するフィルター（since より後のもの）
+// negative_filters: リレーが否定の条件に対応している（NIP-11 で公開している）なら、ミュート中のスレッドをリレー側で除く
+// 対応していないリレーから届いたイベントは、通知する前に is_thread_muted で捨てる
+pub fn mention_filters(my_pubkey: XOnlyPublicKey, mutes: &MuteList, negative_filters: bool, since: Timestamp) -> Vec<Filter> {
+    let filter = Filter::new().kind(Kind::TextNote).pubkey(my_pubkey).since(since);
+    if !negative_filters || mutes.threads.is_empty() {
+        return vec![filter];
+    }
+    vec![exclude_threads(filter.clone(), &mutes.threads).unwrap_or(filter)]
+}
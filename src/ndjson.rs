@@ -0,0 +1,108 @@
+// 1行に1イベントの JSON（NDJSON）の読み書き - ファイル全体を読み込まずに1行ずつ処理し、何GBもあるアーカイブでも使うメモリを一定に保つ
+// 解釈できない行は行番号を付けて返し、呼び出し側はそのまま次の行を読み続けられる
+use nostr_sdk::prelude::*;
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+// 1行の長さの上限（改行のない壊れたファイルを丸ごと読み込まないよう、超えた行は読み飛ばす）
+const MAX_LINE_BYTES: usize = 16 * 1024 * 1024;
+
+// NDJSON を1行ずつ読む（空行は飛ばす。戻り値は行番号と、イベントまたは解釈できなかった理由）
+pub struct NdjsonReader<R> {
+    reader: R,
+    line: usize,
+    buffer: Vec<u8>,
+    done: bool,
+}
+
+impl NdjsonReader<BufReader<File>> {
+    pub fn open(path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        let file = File::open(path).map_err(|e| format!("{} を開けません: {}", path.display(), e))?;
+        Ok(Self::new(BufReader::new(file)))
+    }
+}
+
+impl<R: BufRead> NdjsonReader<R> {
+    pub fn new(reader: R) -> Self {
+        Self { reader, line: 0, buffer: Vec::new(), done: false }
+    }
+
+    // 上限を超えた行の残り（次の改行まで）を捨てる
+    fn skip_rest_of_line(&mut self) -> std::io::Result<()> {
+        loop {
+            let available = self.reader.fill_buf()?;
+            if available.is_empty() {
+                return Ok(());
+            }
+            if let Some(position) = available.iter().position(|b| *b == b'\n') {
+                self.reader.consume(position + 1);
+                return Ok(());
+            }
+            let length = available.len();
+            self.reader.consume(length);
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for NdjsonReader<R> {
+    type Item = (usize, Result<Event, String>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.done {
+            self.buffer.clear();
+            self.line += 1;
+            let read = (&mut self.reader).take(MAX_LINE_BYTES as u64 + 1).read_until(b'\n', &mut self.buffer);
+            match read {
+                Ok(0) => self.done = true,
+                Ok(_) if self.buffer.len() > MAX_LINE_BYTES && !self.buffer.ends_with(b"\n") => {
+                    if let Err(e) = self.skip_rest_of_line() {
+                        self.done = true;
+                        return Some((self.line, Err(format!("読み込みに失敗しました: {}", e))));
+                    }
+                    return Some((self.line, Err(format!("1行が{}MBを超えています", MAX_LINE_BYTES / 1024 / 1024))));
+                }
+                Ok(_) => {
+                    let Ok(text) = std::str::from_utf8(&self.buffer) else {
+                        return Some((self.line, Err("UTF-8 ではありません".to_string())));
+                    };
+                    let text = text.trim();
+                    if text.is_empty() {
+                        continue;
+                    }
+                    return Some((self.line, Event::from_json(text).map_err(|e| e.to_string())));
+                }
+                Err(e) => {
+                    self.done = true;
+                    return Some((self.line, Err(format!("読み込みに失敗しました: {}", e))));
+                }
+            }
+        }
+        None
+    }
+}
+
+// NDJSON を1イベントずつ書き出す（バッファしてから書くので、件数が多くても書き込みの回数は少ない）
+pub struct NdjsonWriter<W: Write> {
+    writer: BufWriter<W>,
+    pub written: usize,
+}
+
+impl<W: Write> NdjsonWriter<W> {
+    pub fn new(writer: W) -> Self {
+        Self { writer: BufWriter::new(writer), written: 0 }
+    }
+
+    pub fn write(&mut self, event: &Event) -> Result<(), Box<dyn std::error::Error>> {
+        self.writer.write_all(event.as_json().as_bytes())?;
+        self.writer.write_all(b"\n")?;
+        self.written += 1;
+        Ok(())
+    }
+
+    // 残りを書き出して件数を返す
+    pub fn finish(mut self) -> Result<usize, Box<dyn std::error::Error>> {
+        self.writer.flush()?;
+        Ok(self.written)
+    }
+}
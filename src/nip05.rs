@@ -0,0 +1,125 @@
+// NIP-05（name@domain の Nostr アドレス）を自分のドメインで配信するための補助
+// .well-known/nostr.json に置く JSON を作り、配信したファイルが自分の公開鍵を指しているかを確かめる
+use nostr_sdk::prelude::*;
+use serde_json::json;
+use std::str::FromStr;
+use std::time::Duration;
+
+const FETCH_TIMEOUT: Duration = Duration::from_secs(10);
+
+// ローカル部（@ の前）に使える文字（NIP-05 では a-z0-9-_. のみ。"_" はドメインそのものを表す）
+pub fn validate_name(name: &str) -> Result<String, Box<dyn std::error::Error>> {
+    let name = name.trim().to_string();
+    if name.is_empty() || !name.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || matches!(c, '-' | '_' | '.')) {
+        return Err(format!("名前に使えるのは英小文字・数字・- _ . だけです: {}", name).into());
+    }
+    Ok(name)
+}
+
+// ドメイン（https:// や末尾の / は取り除く）
+pub fn validate_domain(domain: &str) -> Result<String, Box<dyn std::error::Error>> {
+    let domain = domain
+        .trim()
+        .trim_start_matches("https://")
+        .trim_start_matches("http://")
+        .trim_end_matches('/')
+        .to_lowercase();
+    if domain.is_empty() || domain.contains('/') || !domain.contains('.') {
+        return Err(format!("ドメインを指定してください（例: example.com）: {}", domain).into());
+    }
+    Ok(domain)
+}
+
+// 表示用のアドレス（"_" はドメインだけで表す）
+pub fn address(name: &str, domain: &str) -> String {
+    if name == "_" {
+        domain.to_string()
+    } else {
+        format!("{}@{}", name, domain)
+    }
+}
+
+// クライアントが問い合わせる URL
+pub fn well_known_url(name: &str, domain: &str) -> String {
+    format!("https://{}/.well-known/nostr.json?name={}", domain, name)
+}
+
+// .well-known/nostr.json の中身（公開鍵は hex。リレーを渡せば relays も加える）
+pub fn well_known_json(name: &str, pubkey: &XOnlyPublicKey, relays: &[String]) -> serde_json::Value {
+    let mut document = json!({ "names": { name: pubkey.to_string() } });
+    if !relays.is_empty() {
+        document["relays"] = json!({ pubkey.to_string(): relays });
+    }
+    document
+}
+
+// 配信しているファイルを確かめた結果
+#[derive(Debug, Default)]
+pub struct Nip05Check {
+    pub pubkey: Option<XOnlyPublicKey>, // 名前が指している公開鍵
+    pub relays: Vec<String>,            // 公開鍵に添えたリレー
+    pub warnings: Vec<String>,          // ブラウザのクライアントで失敗する原因など
+}
+
+// 配信しているファイルを取得して確かめる（NIP-05 ではリダイレクトを追ってはいけない）
+pub async fn fetch_well_known(name: &str, domain: &str) -> Result<Nip05Check, Box<dyn std::error::Error>> {
+    let url = well_known_url(name, domain);
+    let http = reqwest::Client::builder()
+        .timeout(FETCH_TIMEOUT)
+        .redirect(reqwest::redirect::Policy::none())
+        .build()?;
+    let response = http.get(&url).send().await.map_err(|e| format!("{} を取得できません: {}", url, e))?;
+    let status = response.status();
+    if status.is_redirection() {
+        let location = response.headers().get("location").and_then(|v| v.to_str().ok()).unwrap_or("");
+        return Err(format!("{} はリダイレクトしています（{}）。NIP-05 のクライアントはリダイレクトを追いません", url, location).into());
+    }
+    if !status.is_success() {
+        return Err(format!("{} の取得に失敗しました（HTTP {}）", url, status).into());
+    }
+
+    let mut check = Nip05Check::default();
+    let allow_origin = response.headers().get("access-control-allow-origin").and_then(|v| v.to_str().ok()).map(str::to_string);
+    if allow_origin.as_deref() != Some("*") {
+        check.warnings.push(
+            "Access-Control-Allow-Origin: * が返っていません（ブラウザのクライアントでは確認に失敗します）".to_string(),
+        );
+    }
+    let content_type = response.headers().get("content-type").and_then(|v| v.to_str().ok()).unwrap_or("").to_string();
+    if !content_type.contains("json") {
+        check.warnings.push(format!("Content-Type が JSON ではありません（{}）", content_type));
+    }
+
+    let document: serde_json::Value = serde_json::from_str(&response.text().await?)
+        .map_err(|e| format!("{} を JSON として解釈できません: {}", url, e))?;
+    let Some(hex) = document["names"][name].as_str() else {
+        return Ok(check);
+    };
+    if hex.starts_with("npub1") {
+        check.warnings.push("公開鍵が npub で書かれています（hex で書く必要があります）".to_string());
+    }
+    check.pubkey = XOnlyPublicKey::from_str(hex).ok();
+    if let Some(pubkey) = check.pubkey {
+        check.relays = document["relays"][pubkey.to_string()]
+            .as_array()
+            .map(|relays| relays.iter().filter_map(|r| r.as_str().map(str::to_string)).collect())
+            .unwrap_or_default();
+    }
+    Ok(check)
+}
+
+// NIP-05 のアドレス（name@domain、またはドメインだけ）を名前とドメインに分ける
+pub fn parse_address(identifier: &str) -> Result<(String, String), Box<dyn std::error::Error>> {
+    let identifier = identifier.trim();
+    let (name, domain) = identifier.split_once('@').unwrap_or(("_", identifier));
+    let name = if name.is_empty() { "_" } else { name };
+    Ok((validate_name(&name.to_lowercase())?, validate_domain(domain)?))
+}
+
+// NIP-05 のアドレスが指定した公開鍵を指しているか（取得できないときも false）
+pub async fn is_verified(identifier: &str, pubkey: &XOnlyPublicKey) -> bool {
+    let Ok((name, domain)) = parse_address(identifier) else {
+        return false;
+    };
+    fetch_well_known(&name, &domain).await.is_ok_and(|check| check.pubkey == Some(*pubkey))
+}
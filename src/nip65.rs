@@ -0,0 +1,61 @@
+// リレーの一覧（NIP-65 の kind 10002） - relays.json を「このリレーで読み書きしている」という公開の案内として送り、
+// ほかの人の案内を取得して取り込む。relays.json がまだない端末では、自分の案内からリレーを設定する
+use nostr_sdk::prelude::*;
+use std::time::Duration;
+
+// 一覧の1項目（marker は "read" か "write"、None なら読み書きの両方）
+#[derive(Debug, Clone, PartialEq)]
+pub struct RelayListEntry {
+    pub url: String,
+    pub marker: Option<String>,
+}
+
+impl RelayListEntry {
+    // 表示用の使い方
+    pub fn usage(&self) -> &'static str {
+        match self.marker.as_deref() {
+            Some("read") => "読み込み",
+            Some("write") => "書き込み",
+            _ => "読み書き",
+        }
+    }
+}
+
+// kind 10002 の r タグから一覧を取り出す
+pub fn parse_relay_list(event: &Event) -> Vec<RelayListEntry> {
+    let mut entries: Vec<RelayListEntry> = Vec::new();
+    for values in event.tags.iter().map(|tag| tag.as_vec()) {
+        if values.first().map(|k| k.as_str()) != Some("r") {
+            continue;
+        }
+        let Some(url) = values.get(1).map(|url| url.trim().to_string()).filter(|url| !url.is_empty()) else {
+            continue;
+        };
+        let marker = values.get(2).filter(|m| matches!(m.as_str(), "read" | "write")).cloned();
+        if !entries.iter().any(|entry| entry.url == url) {
+            entries.push(RelayListEntry { url, marker });
+        }
+    }
+    entries
+}
+
+// relays.json のリレーから kind 10002 を作る（前に送った一覧に同じリレーがあれば、読み書きの指定を引き継ぐ）
+pub fn relay_list_builder(relays: &[String], previous: Option<&Event>) -> Result<EventBuilder, Box<dyn std::error::Error>> {
+    let previous = previous.map(parse_relay_list).unwrap_or_default();
+    let mut tags = Vec::new();
+    for url in relays {
+        let mut values = vec!["r".to_string(), url.clone()];
+        if let Some(marker) = previous.iter().find(|entry| entry.url == *url).and_then(|entry| entry.marker.clone()) {
+            values.push(marker);
+        }
+        tags.push(Tag::parse(values)?);
+    }
+    Ok(EventBuilder::new(Kind::RelayList, "", tags))
+}
+
+// 公開鍵の最新の kind 10002 を取得する（見つからなければ None）
+pub async fn fetch_relay_list(client: &Client, pubkey: XOnlyPublicKey) -> Result<Option<Event>, Box<dyn std::error::Error>> {
+    let filter = Filter::new().kind(Kind::RelayList).author(pubkey).limit(1);
+    let events = client.get_events_of(vec![filter], Some(Duration::from_secs(10))).await?;
+    Ok(events.into_iter().max_by_key(|e| e.created_at))
+}
@@ -0,0 +1,109 @@
+// ノートパッド - 初代Macのデスクアクセサリーと同じ8ページのメモ帳。アカウントごとの notepad.json に保存する
+// sync_notepad が有効なら、全ページを自分宛てに NIP-44 で暗号化し、NIP-78 のアプリデータ（kind 30078）としてリレーにも置く
+use nostr_sdk::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::time::Duration;
+use crate::accounts::{account_dir, active_account};
+use crate::signer::sign_local;
+use crate::storage::{read_json, write_json};
+
+// ページ数
+pub const NOTEPAD_PAGES: usize = 8;
+// NIP-78 のアプリデータと、このアプリのノートパッドを指す d タグ
+const KIND_APP_DATA: u64 = 30078;
+const NOTEPAD_D_TAG: &str = "nostr-cli-app/notepad";
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct NotePad {
+    #[serde(default)]
+    pub pages: Vec<String>,
+    #[serde(default)]
+    pub updated_at: u64, // 最後に書き換えた日時（同期では新しい方を残す）
+}
+
+impl Default for NotePad {
+    fn default() -> Self {
+        Self { pages: vec![String::new(); NOTEPAD_PAGES], updated_at: 0 }
+    }
+}
+
+impl NotePad {
+    // 使用中のアカウントの notepad.json
+    fn path() -> Result<PathBuf, Box<dyn std::error::Error>> {
+        Ok(account_dir(&active_account()?)?.join("notepad.json"))
+    }
+
+    // notepad.json を読み込む（未作成なら白紙の8ページ）
+    pub fn load() -> Result<Self, Box<dyn std::error::Error>> {
+        let path = Self::path()?;
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let mut pad: Self = read_json(&path)?;
+        pad.pages.resize(NOTEPAD_PAGES, String::new());
+        Ok(pad)
+    }
+
+    pub fn save(&self) -> Result<(), Box<dyn std::error::Error>> {
+        write_json(&Self::path()?, self)
+    }
+
+    // ページを書き換える（書き換えた日時を進める）
+    pub fn page_mut(&mut self, page: usize) -> &mut String {
+        self.updated_at = Timestamp::now().as_u64();
+        &mut self.pages[page.min(NOTEPAD_PAGES - 1)]
+    }
+}
+
+// リレーに置くイベントにする（中身は全ページと書き換えた日時の JSON を自分宛てに暗号化したもの）
+fn notepad_event(keys: &Keys, pad: &NotePad) -> Result<Event, Box<dyn std::error::Error>> {
+    let content = nip44::encrypt(&keys.secret_key()?, &keys.public_key(), serde_json::to_string(pad)?, nip44::Version::V2)?;
+    let tags = vec![Tag::parse(vec!["d".to_string(), NOTEPAD_D_TAG.to_string()])?];
+    sign_local(EventBuilder::new(Kind::from(KIND_APP_DATA), content, tags), keys)
+}
+
+// リレーのノートパッドを読み取る（復号できなければ None）
+fn parse_notepad_event(keys: &Keys, event: &Event) -> Option<NotePad> {
+    let payload = nip44::decrypt(&keys.secret_key().ok()?, &keys.public_key(), &event.content).ok()?;
+    let mut pad: NotePad = serde_json::from_str(&payload).ok()?;
+    pad.pages.resize(NOTEPAD_PAGES, String::new());
+    Some(pad)
+}
+
+// 同期の結果
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NotePadSync {
+    UpToDate,   // 手元とリレーが同じ
+    Downloaded, // リレーの方が新しかった
+    Uploaded,   // 手元の方が新しかった、またはまだリレーになかった
+}
+
+// 手元とリレーのノートパッドを同期する（ページごとではなく、最後に書き換えた方を丸ごと残す）
+pub async fn sync_notepad(client: &Client, keys: &Keys, pad: &mut NotePad) -> Result<NotePadSync, Box<dyn std::error::Error>> {
+    let filter = Filter::new()
+        .kind(Kind::from(KIND_APP_DATA))
+        .author(keys.public_key())
+        .identifier(NOTEPAD_D_TAG);
+    let events = client.get_events_of(vec![filter], Some(Duration::from_secs(10))).await?;
+    let remote = events
+        .iter()
+        .filter_map(|event| parse_notepad_event(keys, event))
+        .max_by_key(|remote| remote.updated_at);
+
+    let result = match remote {
+        Some(remote) if remote.updated_at > pad.updated_at => {
+            *pad = remote;
+            NotePadSync::Downloaded
+        }
+        Some(remote) if remote.updated_at == pad.updated_at => NotePadSync::UpToDate,
+        // 白紙のまま一度も書いていなければ送らない
+        None if pad.updated_at == 0 => NotePadSync::UpToDate,
+        _ => {
+            client.send_event(notepad_event(keys, pad)?).await?;
+            NotePadSync::Uploaded
+        }
+    };
+    pad.save()?;
+    Ok(result)
+}
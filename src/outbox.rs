@@ -0,0 +1,208 @@
+// 投稿の送信確認とアウトボックス - どのリレーからも OK が返らなかったイベントを outbox.json に保存して再送する
+use nostr_sdk::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use crate::audit::record_sent;
+use crate::commands::app_dir;
+use crate::storage::{read_json, write_json, FileLock};
+use crate::config::AppConfig;
+use crate::event_cache::record_sent_event;
+use crate::preflight::authenticate;
+use crate::streak::record_post;
+
+// 各リレーからの OK を待つ時間
+pub const PUBLISH_TIMEOUT: Duration = Duration::from_secs(10);
+
+// 1件の送信結果（リレーごとの受理・拒否）
+#[derive(Debug, Default, Clone)]
+pub struct PublishReceipt {
+    pub accepted: Vec<String>,
+    pub rejected: Vec<(String, String)>, // (リレー, 理由)
+    pub backup_error: Option<String>,    // アーカイブリレーに送れなかった理由
+}
+
+impl PublishReceipt {
+    // "2/3件のリレーが受理" のような要約
+    pub fn summary(&self) -> String {
+        let summary = format!("{}/{}件のリレーが受理", self.accepted.len(), self.accepted.len() + self.rejected.len());
+        match &self.backup_error {
+            Some(e) => format!("{}、アーカイブリレーへの送信に失敗: {}", summary, e),
+            None => summary,
+        }
+    }
+
+    // 拒否・タイムアウトの理由をまとめたもの
+    pub fn failure_reason(&self) -> String {
+        if self.rejected.is_empty() {
+            return "接続中のリレーがありません".to_string();
+        }
+        self.rejected
+            .iter()
+            .map(|(url, reason)| format!("{}: {}", url, reason))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+// アウトボックスの1件
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct OutboxEntry {
+    pub event: Event,
+    pub attempts: u32,
+    pub last_error: String,
+    pub queued_at: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct Outbox {
+    #[serde(default)]
+    pub entries: Vec<OutboxEntry>,
+}
+
+impl Outbox {
+    // outbox.json を読み込む（未作成なら空）
+    pub fn load() -> Result<Self, Box<dyn std::error::Error>> {
+        let path = app_dir()?.join("outbox.json");
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        read_json(&path)
+    }
+
+    // outbox.json に保存する
+    pub fn save(&self) -> Result<(), Box<dyn std::error::Error>> {
+        write_json(&app_dir()?.join("outbox.json"), self)
+    }
+
+    // ロックを取って読み込み、変更して保存する（同時に動くほかのインスタンスの変更を上書きしない）
+    pub fn update<T>(change: impl FnOnce(&mut Self) -> T) -> Result<T, Box<dyn std::error::Error>> {
+        let _lock = FileLock::acquire(&app_dir()?.join("outbox.json"))?;
+        let mut outbox = Self::load()?;
+        let result = change(&mut outbox);
+        outbox.save()?;
+        Ok(result)
+    }
+
+    // 送信できなかったイベントを追加する（同じイベントは試行回数だけ更新する）
+    pub fn enqueue(&mut self, event: Event, error: String) {
+        match self.entries.iter_mut().find(|e| e.event.id == event.id) {
+            Some(entry) => {
+                entry.attempts += 1;
+                entry.last_error = error;
+            }
+            None => self.entries.push(OutboxEntry {
+                event,
+                attempts: 1,
+                last_error: error,
+                queued_at: Timestamp::now().as_u64(),
+            }),
+        }
+    }
+}
+
+// 接続中の各リレーに送信し、OK が返ったかどうかを集める
+pub async fn send_with_receipts(client: &Client, event: &Event) -> PublishReceipt {
+    let handles: Vec<_> = client
+        .relays()
+        .await
+        .into_iter()
+        .map(|(url, relay)| {
+            let event = event.clone();
+            tokio::spawn(async move {
+                let opts = RelaySendOptions::new().timeout(Some(PUBLISH_TIMEOUT));
+                (url.to_string(), relay.send_event(event, opts).await.map_err(|e| e.to_string()))
+            })
+        })
+        .collect();
+
+    let mut receipt = PublishReceipt::default();
+    for handle in handles {
+        match handle.await {
+            Ok((url, Ok(_))) => receipt.accepted.push(url),
+            Ok((url, Err(e))) => receipt.rejected.push((url, e)),
+            Err(e) => receipt.rejected.push(("?".to_string(), e.to_string())),
+        }
+    }
+    // 署名の記録に受理したリレーを残す（書き込めなくても送信の結果は変わらない）
+    let _ = record_sent(event, &receipt.accepted);
+    receipt
+}
+
+// 認証（NIP-42）を求めて拒否したリレーに、認証してから送り直す（受理されたリレーは receipt の拒否から受理に移す）
+async fn retry_after_auth(client: &Client, event: &Event, receipt: &mut PublishReceipt) {
+    let urls: Vec<String> = receipt
+        .rejected
+        .iter()
+        .filter(|(_, reason)| reason.contains("auth-required"))
+        .map(|(url, _)| url.clone())
+        .collect();
+    if urls.is_empty() {
+        return;
+    }
+    let mut retried = Vec::new();
+    for url in authenticate(client, &urls).await {
+        let Ok(relay) = client.relay(url.as_str()).await else {
+            continue;
+        };
+        let opts = RelaySendOptions::new().timeout(Some(PUBLISH_TIMEOUT));
+        if relay.send_event(event.clone(), opts).await.is_ok() {
+            receipt.rejected.retain(|(u, _)| *u != url);
+            receipt.accepted.push(url.clone());
+            retried.push(url);
+        }
+    }
+    let _ = record_sent(event, &retried);
+}
+
+// 個人用のアーカイブリレー（config の backup_relay）にもイベントを送る
+// 登録済みのリレーなら送信済みなので受理されたかだけを見る。それ以外は一時的に追加し、送信後に外す
+async fn mirror_to_backup(client: &Client, event: &Event, receipt: &PublishReceipt) -> Result<(), Box<dyn std::error::Error>> {
+    let Some(url) = AppConfig::load()?.backup_relay else {
+        return Ok(());
+    };
+    let same = |other: &str| other.trim_end_matches('/') == url.trim_end_matches('/');
+    if receipt.accepted.iter().any(|u| same(u)) {
+        return Ok(());
+    }
+    if let Some((_, reason)) = receipt.rejected.iter().find(|(u, _)| same(u)) {
+        return Err(reason.clone().into());
+    }
+
+    client.add_relay(url.as_str()).await?;
+    client.connect_relay(url.as_str()).await?;
+    let opts = RelaySendOptions::new().timeout(Some(PUBLISH_TIMEOUT));
+    let result = client.relay(url.as_str()).await?.send_event(event.clone(), opts).await;
+    client.remove_relay(url.as_str()).await?;
+    result?;
+    Ok(())
+}
+
+// 受理されたイベントを送信済みとして記録し、アーカイブリレーにも送る（失敗は receipt に残す）
+pub async fn archive_sent(client: &Client, event: &Event, receipt: &mut PublishReceipt) {
+    if let Err(e) = record_sent_event(event) {
+        receipt.backup_error = Some(format!("送信済みの記録に失敗: {}", e));
+        return;
+    }
+    // 連続投稿の記録は失敗しても送信の結果には影響させない
+    let _ = record_post(event);
+    if let Err(e) = mirror_to_backup(client, event, receipt).await {
+        receipt.backup_error = Some(format!("{}（backup verify --repair で再送できます）", e));
+    }
+}
+
+// イベントを公開する。少なくとも1つのリレーが受理しなければエラーにし、アウトボックスに保存する
+pub async fn publish(client: &Client, event: Event) -> Result<PublishReceipt, Box<dyn std::error::Error>> {
+    let mut receipt = send_with_receipts(client, &event).await;
+    retry_after_auth(client, &event, &mut receipt).await;
+    if receipt.accepted.is_empty() {
+        let reason = receipt.failure_reason();
+        Outbox::update(|outbox| outbox.enqueue(event, reason.clone()))?;
+        return Err(format!(
+            "どのリレーからも受理されませんでした（{}）。アウトボックスに保存したので outbox flush で再送できます",
+            reason
+        )
+        .into());
+    }
+    archive_sent(client, &event, &mut receipt).await;
+    Ok(receipt)
+}
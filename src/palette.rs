@@ -0,0 +1,105 @@
+// コマンドパレット（Ctrl-P） - 操作・タブ・連絡先・保存した検索を数文字の入力で絞り込んで実行する
+// 絞り込みは入力した文字を順に含むか（間が空いてもよい）で判定し、連続した一致や語頭の一致を上位にする
+
+// パレットの項目を選んだときの動作
+#[derive(Debug, Clone, PartialEq)]
+pub enum PaletteAction {
+    Tab(usize),      // タブを開く
+    Key(char),       // イベントリストでキーを押したのと同じ操作
+    Command(String), // : コマンドを実行する
+    Prefill(String), // 引数が必要な : コマンドを入力欄に途中まで入れる
+}
+
+#[derive(Debug, Clone)]
+pub struct PaletteItem {
+    pub label: String,  // 項目の名前（絞り込みの対象）
+    pub detail: String, // 右側に添える説明（キーやコマンド）
+    pub action: PaletteAction,
+}
+
+impl PaletteItem {
+    pub fn new(label: impl Into<String>, detail: impl Into<String>, action: PaletteAction) -> Self {
+        Self { label: label.into(), detail: detail.into(), action }
+    }
+}
+
+// 表示中のパレット
+#[derive(Debug, Default)]
+pub struct Palette {
+    pub query: String,
+    pub items: Vec<PaletteItem>,
+    pub matches: Vec<usize>, // 絞り込んだ項目の番号（よく一致する順）
+    pub selected: usize,     // matches の中で選択している位置
+}
+
+impl Palette {
+    pub fn new(items: Vec<PaletteItem>) -> Self {
+        let mut palette = Self { items, ..Default::default() };
+        palette.refilter();
+        palette
+    }
+
+    pub fn push(&mut self, c: char) {
+        self.query.push(c);
+        self.refilter();
+    }
+
+    pub fn pop(&mut self) {
+        self.query.pop();
+        self.refilter();
+    }
+
+    pub fn select_previous(&mut self) {
+        self.selected = self.selected.saturating_sub(1);
+    }
+
+    pub fn select_next(&mut self) {
+        self.selected = (self.selected + 1).min(self.matches.len().saturating_sub(1));
+    }
+
+    pub fn selected_item(&self) -> Option<&PaletteItem> {
+        self.matches.get(self.selected).and_then(|&i| self.items.get(i))
+    }
+
+    // 入力に一致する項目を、よく一致する順に並べ直す（同じ点数なら元の順）
+    fn refilter(&mut self) {
+        let mut scored: Vec<(i64, usize)> = self
+            .items
+            .iter()
+            .enumerate()
+            .filter_map(|(i, item)| {
+                let score = fuzzy_score(&self.query, &item.label)
+                    .into_iter()
+                    .chain(fuzzy_score(&self.query, &item.detail).map(|s| s / 2))
+                    .max()?;
+                Some((score, i))
+            })
+            .collect();
+        scored.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+        self.matches = scored.into_iter().map(|(_, i)| i).collect();
+        self.selected = 0;
+    }
+}
+
+// 入力した文字を順に含んでいれば点数を返す（大文字・小文字は区別しない。空の入力はすべてに一致）
+pub fn fuzzy_score(query: &str, text: &str) -> Option<i64> {
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+    let mut score = 0;
+    let mut position = 0;
+    let mut previous: Option<usize> = None;
+    for q in query.to_lowercase().chars().filter(|c| !c.is_whitespace()) {
+        let found = position + text[position..].iter().position(|&c| c == q)?;
+        score += 10;
+        // 続けて一致した文字と、語頭での一致を高く、飛ばした文字の分を低く評価する
+        if previous.is_some_and(|p| p + 1 == found) {
+            score += 15;
+        }
+        if found == 0 || text[found - 1] == ' ' || text[found - 1] == ':' {
+            score += 20;
+        }
+        score -= (found - position) as i64;
+        previous = Some(found);
+        position = found + 1;
+    }
+    Some(score)
+}
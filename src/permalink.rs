@@ -0,0 +1,113 @@
+// 投稿の共有用リンク - nevent（リレーのヒント付き）・njump.me の URL・hex と、端末経由のクリップボードへのコピー
+use nostr_sdk::prelude::*;
+use std::io::{self, Write};
+use std::time::{Duration, Instant};
+
+// nevent に含めるリレーのヒントの上限
+pub const MAX_RELAY_HINTS: usize = 3;
+// ブラウザで開ける共有用のゲートウェイ
+const NJUMP_URL: &str = "https://njump.me/";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PermalinkFormat {
+    Nevent, // nevent1...（リレーのヒント付き）
+    Njump,  // https://njump.me/nevent1...
+    Hex,    // イベントID（hex）
+}
+
+impl PermalinkFormat {
+    // 共有ダイアログに並べる順
+    pub const ALL: [PermalinkFormat; 3] = [PermalinkFormat::Nevent, PermalinkFormat::Njump, PermalinkFormat::Hex];
+
+    // --format で指定する名前から形式を求める
+    pub fn parse(name: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        Self::ALL
+            .into_iter()
+            .find(|format| format.name() == name)
+            .ok_or_else(|| format!("形式は nevent / njump / hex のいずれかを指定してください: {}", name).into())
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            PermalinkFormat::Nevent => "nevent",
+            PermalinkFormat::Njump => "njump",
+            PermalinkFormat::Hex => "hex",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            PermalinkFormat::Nevent => "nevent（リレーのヒント付き）",
+            PermalinkFormat::Njump => "njump.me の URL",
+            PermalinkFormat::Hex => "イベントID (hex)",
+        }
+    }
+}
+
+// 指定した形式の共有用リンクを作る（リレーのヒントは先頭から MAX_RELAY_HINTS 件まで）
+pub fn permalink(event_id: EventId, relays: &[String], format: PermalinkFormat) -> Result<String, Box<dyn std::error::Error>> {
+    let nevent = || Nip19Event::new(event_id, relays.iter().take(MAX_RELAY_HINTS).cloned()).to_bech32();
+    Ok(match format {
+        PermalinkFormat::Nevent => nevent()?,
+        PermalinkFormat::Njump => format!("{}{}", NJUMP_URL, nevent()?),
+        PermalinkFormat::Hex => event_id.to_hex(),
+    })
+}
+
+// 接続中のリレーのうち、イベントを持っているリレー（ヒントに使う）
+pub async fn find_event_relays(client: &Client, event_id: EventId) -> Vec<String> {
+    let relay_count = client.relays().await.len();
+    let mut notifications = client.notifications();
+    client.subscribe(vec![Filter::new().id(event_id)]).await;
+
+    let mut found = Vec::new();
+    let mut finished = std::collections::HashSet::new();
+    let started = Instant::now();
+    while finished.len() < relay_count && started.elapsed() < Duration::from_secs(5) {
+        let Ok(Ok(notification)) = tokio::time::timeout(Duration::from_secs(1), notifications.recv()).await else {
+            continue;
+        };
+        let RelayPoolNotification::Message { relay_url, message } = notification else {
+            continue;
+        };
+        match message {
+            RelayMessage::Event { event, .. } if event.id == event_id => {
+                let url = relay_url.to_string();
+                if !found.contains(&url) {
+                    found.push(url);
+                }
+            }
+            RelayMessage::EndOfStoredEvents(_) => {
+                finished.insert(relay_url.to_string());
+            }
+            _ => {}
+        }
+    }
+    let _ = client.unsubscribe().await;
+    found
+}
+
+// 端末のエスケープシーケンス（OSC 52）でクリップボードにコピーする
+// SSH 越しや tmux の中でも、対応している端末なら手元のクリップボードに届く
+pub fn copy_to_clipboard(text: &str) -> io::Result<()> {
+    let mut stdout = io::stdout();
+    write!(stdout, "\x1b]52;c;{}\x07", base64_encode(text.as_bytes()))?;
+    stdout.flush()
+}
+
+// OSC 52 に渡すための Base64 エンコード
+fn base64_encode(bytes: &[u8]) -> String {
+    const TABLE: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = chunk.iter().enumerate().fold(0u32, |n, (i, b)| n | ((*b as u32) << (16 - 8 * i)));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(TABLE[((n >> (18 - 6 * i)) & 0x3f) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
@@ -0,0 +1,233 @@
+// 送信前の確認 - リレーが NIP-11 で公開している書き込みの制限（本文の長さ・タグの数・PoW・認証・支払い）を
+// cache/capabilities.json に覚えておき、ノートを署名する前に照らし合わせる
+// 合わせられるもの（PoW の採掘、NIP-42 の認証）は合わせ、どうしても拒否するリレーは送る前に知らせる
+use nostr_sdk::prelude::*;
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+use tokio::sync::broadcast::error::RecvError;
+use crate::capabilities::{CapabilityCache, RelayLimits};
+use crate::config::AppConfig;
+use crate::signer::{sign_event, sign_event_pow};
+use crate::transport::dial;
+
+// NIP-11 の制限を取得し直すまでの時間
+const LIMITS_TTL_SECS: u64 = 24 * 60 * 60;
+// 手元で採掘する PoW の難易度の上限（1ビット増えるごとに時間が倍になるため、これより高いものは諦める）
+pub const MAX_POW_DIFFICULTY: u8 = 24;
+// 認証のチャレンジを待つ時間
+const AUTH_CHALLENGE_TIMEOUT: Duration = Duration::from_secs(5);
+
+// リレーの NIP-11 の情報から書き込みの制限を取得する（取得できなければ None）
+pub async fn fetch_relay_limits(url: &str) -> Option<RelayLimits> {
+    let http_url = url.replacen("wss://", "https://", 1).replacen("ws://", "http://", 1);
+    let http = reqwest::Client::builder().timeout(Duration::from_secs(5)).build().ok()?;
+    let response = http.get(&http_url).header("Accept", "application/nostr+json").send().await.ok()?;
+    let info: serde_json::Value = response.json().await.ok()?;
+    let limitation = &info["limitation"];
+    let flag = |key: &str| limitation[key].as_bool().unwrap_or(false);
+    Some(RelayLimits {
+        max_content_length: limitation["max_content_length"].as_u64(),
+        max_event_tags: limitation["max_event_tags"].as_u64(),
+        min_pow_difficulty: limitation["min_pow_difficulty"].as_u64().filter(|d| *d > 0).map(|d| d.min(u8::MAX as u64) as u8),
+        auth_required: flag("auth_required"),
+        payment_required: flag("payment_required"),
+        payments_url: info["payments_url"].as_str().map(str::to_string),
+        restricted_writes: flag("restricted_writes"),
+        negative_filters: flag("negative_filters"),
+    })
+}
+
+impl RelayLimits {
+    // 書き込む前に知っておくべき注意点（リレーを追加するときに表示する）
+    pub fn write_notes(&self) -> Vec<String> {
+        let mut notes = Vec::new();
+        if self.auth_required {
+            notes.push("書き込みに認証（NIP-42）が必要です".to_string());
+        }
+        if self.payment_required {
+            let payments_url = self.payments_url.as_ref().map(|u| format!("（{}）", u)).unwrap_or_default();
+            notes.push(format!("書き込みに支払いが必要です{}", payments_url));
+        }
+        if self.restricted_writes {
+            notes.push("書き込めるのは条件を満たしたユーザーだけです".to_string());
+        }
+        if let Some(difficulty) = self.min_pow_difficulty {
+            notes.push(format!("PoW（NIP-13）{}ビット以上のイベントだけを受け付けます", difficulty));
+        }
+        notes
+    }
+}
+
+// 取得してから時間の経った（またはまだ取得していない）リレーの制限を取得し直す（取得できなかったリレーは前の記録のまま）
+pub async fn refresh_limits(cache: &mut CapabilityCache, urls: &[String]) {
+    let now = Timestamp::now().as_u64();
+    let handles: Vec<_> = urls
+        .iter()
+        .filter(|url| {
+            let checked_at = cache.relays.get(url.as_str()).and_then(|cap| cap.limits_checked_at);
+            checked_at.map_or(true, |at| now.saturating_sub(at) >= LIMITS_TTL_SECS)
+        })
+        .map(|url| {
+            let url = url.clone();
+            tokio::spawn(async move {
+                let limits = fetch_relay_limits(&url).await;
+                (url, limits)
+            })
+        })
+        .collect();
+    for handle in handles {
+        if let Ok((url, Some(limits))) = handle.await {
+            let cap = cache.entry(&url);
+            cap.limits = Some(limits);
+            cap.limits_checked_at = Some(now);
+        }
+    }
+}
+
+// 送信前の確認の結果
+#[derive(Debug, Default, Clone)]
+pub struct Preflight {
+    pub relays: usize,                    // 確認したリレーの数
+    pub pow: u8,                          // 採掘する PoW の難易度（0 なら採掘しない）
+    pub pow_relays: Vec<String>,          // PoW を求めているリレー
+    pub refused: Vec<(String, String)>,   // 拒否する見込みのリレーと理由
+    pub warnings: Vec<(String, String)>,  // 拒否するかもしれないリレーと理由
+    pub auth: Vec<String>,                // 認証が必要なリレー
+}
+
+impl Preflight {
+    // すべてのリレーが拒否する見込みか
+    pub fn all_refused(&self) -> bool {
+        self.relays > 0 && self.refused.len() >= self.relays
+    }
+}
+
+// ノートの本文とタグの数を、リレーの制限と照らし合わせる（制限を取得できていないリレーは何も分からないので通す）
+pub fn check_note(cache: &CapabilityCache, urls: &[String], content: &str, tag_count: usize) -> Preflight {
+    let mut result = Preflight { relays: urls.len(), ..Default::default() };
+    let length = content.chars().count() as u64;
+    for url in urls {
+        let Some(limits) = cache.relays.get(url).and_then(|cap| cap.limits.as_ref()) else {
+            continue;
+        };
+        let mut refuse = |reason: String| result.refused.push((url.clone(), reason));
+        if let Some(max) = limits.max_content_length.filter(|max| length > *max) {
+            refuse(format!("本文は{}文字までです（{}文字）", max, length));
+        } else if let Some(max) = limits.max_event_tags.filter(|max| tag_count as u64 > *max) {
+            refuse(format!("タグは{}個までです（{}個）", max, tag_count));
+        } else if let Some(difficulty) = limits.min_pow_difficulty.filter(|d| *d > MAX_POW_DIFFICULTY) {
+            refuse(format!("PoW {}ビットを求めています（手元で採掘するのは{}ビットまで）", difficulty, MAX_POW_DIFFICULTY));
+        } else {
+            if let Some(difficulty) = limits.min_pow_difficulty {
+                result.pow = result.pow.max(difficulty);
+                result.pow_relays.push(url.clone());
+            }
+            if limits.auth_required {
+                result.auth.push(url.clone());
+            }
+            if limits.payment_required {
+                result.warnings.push((url.clone(), "支払いが済んでいなければ拒否されます".to_string()));
+            } else if limits.restricted_writes {
+                result.warnings.push((url.clone(), "書き込めるユーザーを制限しています".to_string()));
+            }
+        }
+    }
+    result
+}
+
+// 接続中のリレーの制限を取得し直してから、ノートを確認する
+pub async fn preflight_note(client: &Client, content: &str, tag_count: usize) -> Result<Preflight, Box<dyn std::error::Error>> {
+    let urls: Vec<String> = client.relays().await.into_keys().map(|url| url.to_string()).collect();
+    let mut cache = CapabilityCache::load()?;
+    refresh_limits(&mut cache, &urls).await;
+    cache.save()?;
+    Ok(check_note(&cache, &urls, content, tag_count))
+}
+
+// 確認の結果に合わせて署名する（PoW を求めるリレーがあれば、いちばん高い難易度で採掘してから署名する）
+pub fn sign_checked(builder: EventBuilder, keys: &Keys, preflight: &Preflight) -> Result<Event, Box<dyn std::error::Error>> {
+    if preflight.pow == 0 {
+        return sign_event(builder, keys);
+    }
+    sign_event_pow(builder, keys, preflight.pow)
+}
+
+fn same_relay(a: &str, b: &str) -> bool {
+    a.trim_end_matches('/') == b.trim_end_matches('/')
+}
+
+// 認証に応じないリレー（config の auth_opt_out。nostr-sdk に渡した URL で比べられるよう、unix:// なども中継の URL にする）
+fn auth_opt_out() -> Vec<String> {
+    let opt_out = AppConfig::load().map(|config| config.auth_opt_out).unwrap_or_default();
+    opt_out.iter().flat_map(|url| [url.clone(), dial(url).unwrap_or_default()]).filter(|url| !url.is_empty()).collect()
+}
+
+// チャレンジに署名した kind 22242 のイベントを送る（送れたら true）
+async fn send_auth(client: &Client, keys: &Keys, url: &str, challenge: String) -> bool {
+    let Ok(relay_url) = Url::parse(url) else {
+        return false;
+    };
+    let Ok(event) = sign_event(EventBuilder::auth(challenge, relay_url), keys) else {
+        return false;
+    };
+    let Ok(relay) = client.relay(url).await else {
+        return false;
+    };
+    relay.send_msg(ClientMessage::new_auth(event), RelaySendOptions::new()).await.is_ok()
+}
+
+// リレーから届いた AUTH のチャレンジに、接続している間ずっと自動で応える（auth_opt_out のリレーには応えない）
+// チャレンジは接続した直後に届くことが多いため、client.connect() より前に呼ぶ
+pub fn spawn_auto_auth(client: &Client) {
+    let opt_out = auth_opt_out();
+    let client = client.clone();
+    let mut notifications = client.notifications();
+    tokio::spawn(async move {
+        let keys = client.keys().await;
+        loop {
+            match notifications.recv().await {
+                Ok(RelayPoolNotification::Message { relay_url, message: RelayMessage::Auth { challenge } }) => {
+                    if !opt_out.iter().any(|url| same_relay(url, relay_url.as_str())) {
+                        send_auth(&client, &keys, relay_url.as_str(), challenge).await;
+                    }
+                }
+                Ok(RelayPoolNotification::Shutdown) | Err(RecvError::Closed) => break,
+                Ok(_) | Err(RecvError::Lagged(_)) => {}
+            }
+        }
+    });
+}
+
+// 指定したリレーに NIP-42 で認証する（チャレンジは接続した直後に届くことが多いため、つなぎ直して受け取る）
+// 戻り値は認証のイベントを送れたリレー（auth_opt_out のリレーは除く）
+pub async fn authenticate(client: &Client, urls: &[String]) -> Vec<String> {
+    let opt_out = auth_opt_out();
+    let urls: Vec<String> = urls.iter().filter(|url| !opt_out.iter().any(|o| same_relay(o, url))).cloned().collect();
+    let keys = client.keys().await;
+    let mut notifications = client.notifications();
+    for url in &urls {
+        let _ = client.disconnect_relay(url.as_str()).await;
+        let _ = client.connect_relay(url.as_str()).await;
+    }
+
+    let mut challenges: BTreeMap<String, String> = BTreeMap::new();
+    let started = Instant::now();
+    while challenges.len() < urls.len() && started.elapsed() < AUTH_CHALLENGE_TIMEOUT {
+        let Ok(Ok(notification)) = tokio::time::timeout(Duration::from_secs(1), notifications.recv()).await else {
+            continue;
+        };
+        if let RelayPoolNotification::Message { relay_url, message: RelayMessage::Auth { challenge } } = notification {
+            if let Some(url) = urls.iter().find(|url| same_relay(url, relay_url.as_str())) {
+                challenges.insert(url.clone(), challenge);
+            }
+        }
+    }
+
+    let mut authenticated = Vec::new();
+    for (url, challenge) in challenges {
+        if send_auth(client, &keys, &url, challenge).await {
+            authenticated.push(url);
+        }
+    }
+    authenticated
+}
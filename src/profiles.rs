@@ -0,0 +1,260 @@
+// 投稿者のプロフィールの記録（cache/profiles.json） - フィードのプロフィールカードに使う
+use nostr_sdk::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::time::Duration;
+use crate::commands::app_dir;
+use crate::storage::{read_json, write_json};
+use crate::contacts::followed_set;
+use crate::nip05::is_verified;
+
+// プロフィールとフォロワーを取得し直すまでの時間
+const PROFILE_TTL_SECS: u64 = 24 * 60 * 60;
+// 一度に取得するフォロワーのコンタクトリストの上限
+const FOLLOWER_FETCH_LIMIT: usize = 1000;
+
+// 投稿者1人分のプロフィール（kind 0 から表示に使う項目だけを保存する）
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+#[serde(default)]
+pub struct CachedProfile {
+    pub display_name: Option<String>,
+    pub name: Option<String>,
+    pub nip05: Option<String>,
+    // 取得した日時
+    pub fetched_at: u64,
+    // nip05 のドメインの nostr.json がこの公開鍵を指していたか、と確かめた日時
+    pub nip05_verified: bool,
+    pub nip05_checked_at: u64,
+}
+
+impl CachedProfile {
+    // kind 0 の内容から作る（見つからなかったときは空のまま記録する）
+    pub fn from_metadata(metadata: Option<Metadata>, fetched_at: u64) -> Self {
+        let metadata = metadata.unwrap_or_default();
+        Self { display_name: metadata.display_name, name: metadata.name, nip05: metadata.nip05, fetched_at, ..Default::default() }
+    }
+
+    // 確かめられた NIP-05 のアドレス
+    pub fn verified_nip05(&self) -> Option<&str> {
+        self.nip05.as_deref().filter(|_| self.nip05_verified)
+    }
+
+    // 表示名（display_name がなければ name）
+    pub fn label(&self) -> Option<&str> {
+        self.display_name
+            .as_deref()
+            .into_iter()
+            .chain(self.name.as_deref())
+            .map(str::trim)
+            .find(|s| !s.is_empty())
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct ProfileCache {
+    // 公開鍵 (hex) → プロフィール
+    #[serde(default)]
+    pub profiles: BTreeMap<String, CachedProfile>,
+    // 自分をフォローしている公開鍵 (hex)
+    #[serde(default)]
+    pub followers: BTreeSet<String>,
+    // フォロワーを調べた日時
+    #[serde(default)]
+    pub followers_checked_at: u64,
+    // 自分がフォローしている公開鍵 (hex)
+    #[serde(default)]
+    pub following: BTreeSet<String>,
+    // following を調べたアカウントの公開鍵 (hex) と日時（アカウントを切り替えたら調べ直す）
+    #[serde(default)]
+    pub following_owner: Option<String>,
+    #[serde(default)]
+    pub following_checked_at: u64,
+}
+
+impl ProfileCache {
+    // cache/profiles.json を読み込む（未作成なら空）
+    pub fn load() -> Result<Self, Box<dyn std::error::Error>> {
+        let path = app_dir()?.join("cache").join("profiles.json");
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        read_json(&path)
+    }
+
+    // cache/profiles.json に保存する
+    pub fn save(&self) -> Result<(), Box<dyn std::error::Error>> {
+        write_json(&app_dir()?.join("cache").join("profiles.json"), self)
+    }
+
+    pub fn get(&self, pubkey: &XOnlyPublicKey) -> Option<&CachedProfile> {
+        self.profiles.get(&pubkey.to_string())
+    }
+
+    // 自分をフォローしているか
+    pub fn is_follower(&self, pubkey: &XOnlyPublicKey) -> bool {
+        self.followers.contains(&pubkey.to_string())
+    }
+
+    // 表示中のイベントの投稿者のうち、未取得または古くなったプロフィールを取得し直す
+    // 自分のフォロワーも期限が切れていれば調べ直す。戻り値は更新したプロフィールの数
+    pub async fn refresh(
+        &mut self,
+        client: &Client,
+        events: &[Event],
+        my_pubkey: Option<XOnlyPublicKey>,
+    ) -> Result<usize, Box<dyn std::error::Error>> {
+        let now = Timestamp::now().as_u64();
+        let is_stale = |fetched_at: u64| now.saturating_sub(fetched_at) > PROFILE_TTL_SECS;
+
+        // 自分のプロフィールも取得する（投稿作成画面に名前を表示する）
+        let mut authors: Vec<XOnlyPublicKey> = Vec::new();
+        for pubkey in events.iter().map(|e| e.pubkey).chain(my_pubkey) {
+            let fresh = self.get(&pubkey).is_some_and(|p| !is_stale(p.fetched_at));
+            if !fresh && !authors.contains(&pubkey) {
+                authors.push(pubkey);
+            }
+        }
+
+        let mut filters = Vec::new();
+        if !authors.is_empty() {
+            filters.push(Filter::new().kind(Kind::Metadata).authors(authors.clone()));
+        }
+        // 自分を p タグに含むコンタクトリスト = 自分をフォローしている人
+        let check_followers = my_pubkey.filter(|_| is_stale(self.followers_checked_at));
+        if let Some(me) = check_followers {
+            filters.push(Filter::new().kind(Kind::ContactList).pubkey(me).limit(FOLLOWER_FETCH_LIMIT));
+        }
+        if filters.is_empty() {
+            return Ok(0);
+        }
+
+        let fetched = client.get_events_of(filters, Some(Duration::from_secs(5))).await?;
+
+        // 同じ投稿者の kind 0 が複数届いたら新しいものを使う
+        let mut latest: BTreeMap<XOnlyPublicKey, &Event> = BTreeMap::new();
+        for event in fetched.iter().filter(|e| e.kind == Kind::Metadata) {
+            if latest.get(&event.pubkey).map_or(true, |e| e.created_at < event.created_at) {
+                latest.insert(event.pubkey, event);
+            }
+        }
+        for pubkey in &authors {
+            let metadata = latest.get(pubkey).and_then(|e| serde_json::from_str::<Metadata>(&e.content).ok());
+            // プロフィールが見つからなかった投稿者も記録し、毎回問い合わせないようにする
+            self.profiles.insert(pubkey.to_string(), CachedProfile::from_metadata(metadata, now));
+        }
+
+        if let Some(me) = check_followers {
+            let me_hex = me.to_string();
+            self.followers = fetched
+                .iter()
+                .filter(|e| e.kind == Kind::ContactList && e.pubkey != me)
+                .filter(|e| {
+                    e.tags.iter().any(|tag| {
+                        let values = tag.as_vec();
+                        values.first().map(|k| k.as_str()) == Some("p") && values.get(1) == Some(&me_hex)
+                    })
+                })
+                .map(|e| e.pubkey.to_string())
+                .collect();
+            self.followers_checked_at = now;
+        }
+
+        self.save()?;
+        Ok(authors.len())
+    }
+
+    // NIP-05 のアドレスが未確認または古くなった投稿者について、ドメインの nostr.json が本人を指しているか確かめる
+    pub async fn verify_nip05s(&mut self, pubkeys: &[XOnlyPublicKey]) -> Result<(), Box<dyn std::error::Error>> {
+        let now = Timestamp::now().as_u64();
+        let mut handles = Vec::new();
+        for pubkey in pubkeys {
+            let Some(profile) = self.get(pubkey) else {
+                continue;
+            };
+            let Some(nip05) = profile.nip05.clone().filter(|n| !n.trim().is_empty()) else {
+                continue;
+            };
+            if now.saturating_sub(profile.nip05_checked_at) <= PROFILE_TTL_SECS
+                || handles.iter().any(|(p, _): &(XOnlyPublicKey, _)| p == pubkey)
+            {
+                continue;
+            }
+            let pubkey = *pubkey;
+            handles.push((pubkey, tokio::spawn(async move { is_verified(&nip05, &pubkey).await })));
+        }
+        if handles.is_empty() {
+            return Ok(());
+        }
+        for (pubkey, handle) in handles {
+            let verified = handle.await.unwrap_or(false);
+            if let Some(profile) = self.profiles.get_mut(&pubkey.to_string()) {
+                profile.nip05_verified = verified;
+                profile.nip05_checked_at = now;
+            }
+        }
+        self.save()
+    }
+
+    // 取得・送信した kind 0 で1人分を記録し直す
+    pub fn record(&mut self, event: &Event) {
+        let metadata = serde_json::from_str::<Metadata>(&event.content).ok();
+        self.profiles.insert(event.pubkey.to_string(), CachedProfile::from_metadata(metadata, Timestamp::now().as_u64()));
+    }
+
+    // 自分のコンタクトリスト（kind 3）が未取得・期限切れ・別のアカウントのものなら取得し直す
+    pub async fn refresh_following(&mut self, client: &Client, me: XOnlyPublicKey) -> Result<(), Box<dyn std::error::Error>> {
+        let now = Timestamp::now().as_u64();
+        let me_hex = me.to_string();
+        if self.following_owner.as_deref() == Some(me_hex.as_str())
+            && now.saturating_sub(self.following_checked_at) <= PROFILE_TTL_SECS
+        {
+            return Ok(());
+        }
+        if self.following_owner.as_deref() != Some(me_hex.as_str()) {
+            self.following.clear();
+        }
+
+        let filter = Filter::new().kind(Kind::ContactList).author(me).limit(1);
+        let fetched = client.get_events_of(vec![filter], Some(Duration::from_secs(5))).await?;
+        if let Some(latest) = fetched.iter().max_by_key(|e| e.created_at) {
+            self.following = followed_set(latest);
+        }
+        self.following_owner = Some(me_hex);
+        self.following_checked_at = now;
+        self.save()
+    }
+
+    // 送信したコンタクトリストでフォロー中の一覧を置き換える（follow / unfollow の後）
+    pub fn set_following(&mut self, contact_list: &Event) {
+        self.following = followed_set(contact_list);
+        self.following_owner = Some(contact_list.pubkey.to_string());
+        self.following_checked_at = Timestamp::now().as_u64();
+    }
+}
+
+// profile set で書き換えられる項目（kind 0 の JSON のキー）
+pub const PROFILE_FIELDS: [&str; 6] = ["name", "about", "picture", "nip05", "lud16", "banner"];
+
+// 最新の kind 0 を取得する（見つからなければ None）
+pub async fn fetch_metadata_event(client: &Client, pubkey: XOnlyPublicKey) -> Result<Option<Event>, Box<dyn std::error::Error>> {
+    let filter = Filter::new().kind(Kind::Metadata).author(pubkey).limit(1);
+    let events = client.get_events_of(vec![filter], Some(Duration::from_secs(10))).await?;
+    Ok(events.into_iter().max_by_key(|e| e.created_at))
+}
+
+// kind 0 の本文の項目を書き換える（空の値は項目を消す。ほかのクライアントが加えた項目はそのまま残す）
+pub fn merge_metadata(content: &str, changes: &[(&str, String)]) -> String {
+    let mut object = match serde_json::from_str::<serde_json::Value>(content) {
+        Ok(serde_json::Value::Object(object)) => object,
+        _ => serde_json::Map::new(),
+    };
+    for (key, value) in changes {
+        let value = value.trim();
+        if value.is_empty() {
+            object.remove(*key);
+        } else {
+            object.insert(key.to_string(), serde_json::Value::String(value.to_string()));
+        }
+    }
+    serde_json::Value::Object(object).to_string()
+}
@@ -0,0 +1,61 @@
+// 本文の伏せ字 - config の redact_rules の正規表現（電話番号、シードフレーズらしい行、nsec など）に一致した部分を、
+// TUI の一覧と詳細表示で「［伏せ字: 名前］」に置き換える。画面共有中に、ほかの人が投稿した個人情報をうっかり映さないため
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+
+// 伏せ字の規則（name は置き換えた部分に表示する名前）
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RedactRule {
+    pub name: String,
+    pub pattern: String,
+}
+
+// 既定の規則
+pub fn default_rules() -> Vec<RedactRule> {
+    let rule = |name: &str, pattern: &str| RedactRule { name: name.to_string(), pattern: pattern.to_string() };
+    vec![
+        // 0 から始まる国内の番号か、+ から始まる国際番号（区切りの - か空白が必要なので、日付や金額には一致しない）
+        rule("電話番号", r"(?:\+\d{1,3}[\- ]?\d{1,4}|\b0\d{1,4})[\- ]\d{1,4}[\- ]\d{3,4}\b"),
+        // 英小文字の単語だけが12〜24個並んだ行（BIP-39 のニーモニック）
+        rule("シードフレーズ", r"(?m)^[ \t]*(?:[a-z]{3,8}[ \t]+){11,23}[a-z]{3,8}[ \t]*$"),
+        rule("秘密鍵", r"\bnsec1[02-9ac-hj-np-z]{58}\b"),
+    ]
+}
+
+// 正規表現を用意した規則の一覧
+#[derive(Debug, Default)]
+pub struct Redactor {
+    rules: Vec<(String, Regex)>,
+}
+
+impl Redactor {
+    pub fn compile(rules: &[RedactRule]) -> Result<Self, Box<dyn std::error::Error>> {
+        let rules = rules
+            .iter()
+            .map(|rule| {
+                Regex::new(&rule.pattern)
+                    .map(|re| (rule.name.clone(), re))
+                    .map_err(|e| format!("伏せ字の規則「{}」の正規表現が正しくありません: {}", rule.name, e).into())
+            })
+            .collect::<Result<Vec<_>, Box<dyn std::error::Error>>>()?;
+        Ok(Self { rules })
+    }
+
+    // 伏せる部分があるか
+    pub fn matches(&self, content: &str) -> bool {
+        self.rules.iter().any(|(_, re)| re.is_match(content))
+    }
+
+    // 一致した部分を置き換える（一致しなければ借りたまま返す）
+    pub fn redact<'a>(&self, content: Cow<'a, str>) -> Cow<'a, str> {
+        let mut content = content;
+        for (name, re) in &self.rules {
+            if re.is_match(&content) {
+                let label = format!("［伏せ字: {}］", name);
+                content = Cow::Owned(re.replace_all(&content, regex::NoExpand(&label)).into_owned());
+            }
+        }
+        content
+    }
+}
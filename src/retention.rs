@@ -0,0 +1,100 @@
+// キャッシュの保存期間 - config の cache_retention で kind ごとに「ずっと残す」「N日で消す」「保存しない」を決め、
+// TUI の裏の掃除と cache policy --vacuum で cache/*.ndjson から期限を過ぎたイベントを取り除く
+// DM は残しておきたいが、古いテキストノートや一時的なイベントでキャッシュが膨らむのは避けたいため
+use nostr_sdk::prelude::*;
+use serde::{Deserialize, Serialize};
+use crate::config::AppConfig;
+
+// 残し方（JSON では "forever" / "never" / {"days": 90}）
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Keep {
+    Forever,
+    Never,
+    Days(u64),
+}
+
+// kind ごとの規則（kinds は "4"・"4,1059"・"20000-29999" のような kind と範囲の並び）
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RetentionRule {
+    pub kinds: String,
+    pub keep: Keep,
+}
+
+// 既定の規則（どの規則にも当たらない kind は件数の上限まで残す）
+pub fn default_rules() -> Vec<RetentionRule> {
+    let rule = |kinds: &str, keep: Keep| RetentionRule { kinds: kinds.to_string(), keep };
+    vec![
+        rule("4,1059", Keep::Forever), // DM（kind 4 とギフトラップ）
+        rule("1", Keep::Days(90)), // テキストノート
+        rule("20000-29999", Keep::Never), // 一時的なイベント
+    ]
+}
+
+// 規則の kind の並びを範囲にする
+fn parse_kinds(kinds: &str) -> Result<Vec<(u64, u64)>, Box<dyn std::error::Error>> {
+    kinds
+        .split(',')
+        .map(str::trim)
+        .filter(|part| !part.is_empty())
+        .map(|part| {
+            let (start, end) = part.split_once('-').unwrap_or((part, part));
+            match (start.trim().parse::<u64>(), end.trim().parse::<u64>()) {
+                (Ok(start), Ok(end)) if start <= end => Ok((start, end)),
+                _ => Err(format!("保存期間の kind が正しくありません: {}", part).into()),
+            }
+        })
+        .collect()
+}
+
+impl Keep {
+    // 表示用の説明
+    pub fn describe(&self) -> String {
+        match self {
+            Keep::Forever => "ずっと残す".to_string(),
+            Keep::Never => "保存しない".to_string(),
+            Keep::Days(days) => format!("{}日", days),
+        }
+    }
+}
+
+// kind の範囲を解釈した規則の一覧
+#[derive(Debug, Default)]
+pub struct RetentionPolicy {
+    rules: Vec<(Vec<(u64, u64)>, Keep)>,
+}
+
+impl RetentionPolicy {
+    // config の cache_retention から作る（読み込めなければ既定の規則）
+    pub fn load() -> Self {
+        let rules = AppConfig::load().map(|config| config.cache_retention).unwrap_or_else(|_| default_rules());
+        Self::compile(&rules).or_else(|_| Self::compile(&default_rules())).unwrap_or_default()
+    }
+
+    pub fn compile(rules: &[RetentionRule]) -> Result<Self, Box<dyn std::error::Error>> {
+        let rules = rules
+            .iter()
+            .map(|rule| parse_kinds(&rule.kinds).map(|ranges| (ranges, rule.keep)))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self { rules })
+    }
+
+    // kind に当てはまる残し方（先に書いた規則を優先し、どれにも当たらなければ None）
+    pub fn keep_for(&self, kind: Kind) -> Option<Keep> {
+        let kind = kind.as_u64();
+        self.rules
+            .iter()
+            .find(|(ranges, _)| ranges.iter().any(|(start, end)| (*start..=*end).contains(&kind)))
+            .map(|(_, keep)| *keep)
+    }
+
+    // now の時点でイベントを残すか
+    pub fn retains(&self, event: &Event, now: Timestamp) -> bool {
+        match self.keep_for(event.kind) {
+            None | Some(Keep::Forever) => true,
+            Some(Keep::Never) => false,
+            // 日数が大きすぎても桁あふれで消してしまわないよう、上限で止める
+            Some(Keep::Days(days)) => event.created_at.as_u64().saturating_add(days.saturating_mul(86_400)) >= now.as_u64(),
+        }
+    }
+}
@@ -0,0 +1,141 @@
+// DMの安全番号 - 自分と相手の公開鍵から、会って見比べられる短い指紋（絵文字8個と数字）を作る
+// 両者で同じ値になるので、食い違えば途中で鍵がすり替えられている。相手の別名や NIP-05 がどの鍵を指していたかを
+// 鍵ファイルの隣の dm_peers.json に覚えておき、別の鍵に変わったら警告する
+use nostr_sdk::hashes::{sha256, Hash};
+use nostr_sdk::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::str::FromStr;
+use crate::accounts::keys_path;
+use crate::profiles::ProfileCache;
+use crate::storage::{read_json, write_json};
+
+// 6ビットずつ割り当てる絵文字（どれも2桁で表示され、声に出して読み上げやすいもの）
+const EMOJI: [&str; 64] = [
+    "🐶", "🐱", "🐭", "🐹", "🐰", "🦊", "🐻", "🐼", "🐨", "🐯", "🦁", "🐮", "🐷", "🐸", "🐵", "🐔",
+    "🐧", "🐦", "🐤", "🦆", "🦉", "🐴", "🦄", "🐝", "🐛", "🦋", "🐌", "🐞", "🐢", "🐍", "🐙", "🦀",
+    "🐠", "🐬", "🐳", "🐘", "🦒", "🐪", "🌵", "🌲", "🌻", "🍄", "🌙", "⭐", "🔥", "🌈", "🍪", "🍎",
+    "🍋", "🍌", "🍉", "🍇", "🍓", "🍒", "🍑", "🍍", "🥕", "🌽", "🍞", "🧀", "🍕", "🍩", "🎈", "🔑",
+];
+// 絵文字の個数と、続けて表示する5桁の数字の組の数
+const EMOJI_COUNT: usize = 8;
+const DIGIT_GROUPS: usize = 4;
+
+// 安全番号
+#[derive(Debug, Clone, PartialEq)]
+pub struct SafetyNumber {
+    pub emoji: Vec<&'static str>,
+    pub digits: Vec<String>,
+}
+
+impl SafetyNumber {
+    pub fn emoji_line(&self) -> String {
+        self.emoji.join(" ")
+    }
+
+    pub fn digits_line(&self) -> String {
+        self.digits.join(" ")
+    }
+}
+
+// 2つの公開鍵から安全番号を作る（鍵を並べ替えてからハッシュするので、どちらの側で計算しても同じになる）
+pub fn safety_number(a: &XOnlyPublicKey, b: &XOnlyPublicKey) -> SafetyNumber {
+    let mut keys = [a.serialize(), b.serialize()];
+    keys.sort();
+    let hash = *sha256::Hash::hash(&keys.concat()).as_byte_array();
+
+    // 先頭の6バイト（48ビット）を6ビットずつ絵文字にする
+    let bits = hash[..6].iter().fold(0u64, |acc, b| (acc << 8) | *b as u64);
+    let emoji = (0..EMOJI_COUNT).map(|i| EMOJI[((bits >> (42 - 6 * i)) & 0x3f) as usize]).collect();
+    // 続く5バイトずつを10進5桁にする
+    let digits = (0..DIGIT_GROUPS)
+        .map(|i| {
+            let chunk = &hash[6 + 5 * i..11 + 5 * i];
+            let value = chunk.iter().fold(0u64, |acc, b| (acc << 8) | *b as u64);
+            format!("{:05}", value % 100_000)
+        })
+        .collect();
+    SafetyNumber { emoji, digits }
+}
+
+// 相手の呼び名（別名や NIP-05）
+pub fn alias_handle(name: &str) -> String {
+    format!("別名 {}", name)
+}
+
+pub fn nip05_handle(nip05: &str) -> String {
+    format!("NIP-05 {}", nip05.trim().to_lowercase())
+}
+
+// 相手の公開鍵を指している呼び名（config の別名と、取得済みプロフィールの NIP-05）
+pub fn peer_handles(peer: &XOnlyPublicKey, aliases: &BTreeMap<String, String>, profiles: &ProfileCache) -> Vec<String> {
+    let mut handles: Vec<String> = aliases
+        .iter()
+        .filter(|(_, target)| {
+            let target = target.trim_start_matches("nostr:");
+            XOnlyPublicKey::from_bech32(target).ok().or_else(|| XOnlyPublicKey::from_str(target).ok()) == Some(*peer)
+        })
+        .map(|(name, _)| alias_handle(name))
+        .collect();
+    if let Some(nip05) = profiles.get(peer).and_then(|p| p.nip05.as_deref()).filter(|n| !n.trim().is_empty()) {
+        handles.push(nip05_handle(nip05));
+    }
+    handles
+}
+
+// 呼び名が前と別の鍵を指していたときの記録
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeyChange {
+    pub handle: String,
+    pub previous: XOnlyPublicKey,
+}
+
+// 呼び名 → 最初に見た（または確認して信頼した）公開鍵 (hex)
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct KnownPeers {
+    #[serde(default)]
+    pub peers: BTreeMap<String, String>,
+}
+
+impl KnownPeers {
+    // 使用中のアカウントの鍵ファイルの隣
+    fn path() -> Result<PathBuf, Box<dyn std::error::Error>> {
+        Ok(keys_path()?.with_file_name("dm_peers.json"))
+    }
+
+    // dm_peers.json を読み込む（未作成なら空）
+    pub fn load() -> Result<Self, Box<dyn std::error::Error>> {
+        let path = Self::path()?;
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        read_json(&path)
+    }
+
+    pub fn save(&self) -> Result<(), Box<dyn std::error::Error>> {
+        write_json(&Self::path()?, self)
+    }
+
+    // 呼び名と公開鍵を照らし合わせる（初めて見た呼び名は覚える。変わったものは trust するまで前の鍵のまま残して返す）
+    pub fn observe(&mut self, handles: &[String], peer: &XOnlyPublicKey) -> Vec<KeyChange> {
+        let mut changes = Vec::new();
+        for handle in handles {
+            match self.peers.get(handle).map(|hex| XOnlyPublicKey::from_str(hex)) {
+                Some(Ok(previous)) if previous != *peer => changes.push(KeyChange { handle: handle.clone(), previous }),
+                Some(Ok(_)) => {}
+                _ => {
+                    self.peers.insert(handle.clone(), peer.to_string());
+                }
+            }
+        }
+        changes
+    }
+
+    // 今の鍵を信頼して記録し直す
+    pub fn trust(&mut self, handles: &[String], peer: &XOnlyPublicKey) {
+        for handle in handles {
+            self.peers.insert(handle.clone(), peer.to_string());
+        }
+    }
+}
@@ -0,0 +1,168 @@
+// イベントの形式の確認 - 手で組み立てたイベント（event build のテンプレートやタグ、event import の JSON）を、
+// kind ごとに決まっている必須のタグや本文の形式と照らし合わせ、どこが違うのかを「tags[2][1]」のような場所付きで返す
+// リレーやほかのクライアントに読めないイベントを、署名して送ってしまう前に止めるため
+use nostr_sdk::prelude::*;
+use std::fmt;
+
+// 形式の誤り（path は content / tags[i] / tags[i][j] / content.name のような場所）
+#[derive(Debug, Clone, PartialEq)]
+pub struct SchemaError {
+    pub path: String,
+    pub message: String,
+}
+
+impl fmt::Display for SchemaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.path, self.message)
+    }
+}
+
+// 本文の形式
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ContentFormat {
+    Any,
+    NonEmpty,
+    MetadataJson,  // プロフィールの JSON オブジェクト
+    EmptyOrObject, // 空、または JSON オブジェクト（コンタクトリストの古いリレーの一覧）
+    EmptyOrEvent,  // 空、またはリポストする元のイベントの JSON
+    Encrypted,     // NIP-04 の暗号文（base64?iv=base64）
+}
+
+// kind ごとの決まり（required はどれか1つあればよいタグ名の組の並び）
+struct KindSchema {
+    kind: u64,
+    name: &'static str,
+    required: &'static [&'static [&'static str]],
+    content: ContentFormat,
+}
+
+const SCHEMAS: [KindSchema; 11] = [
+    KindSchema { kind: 0, name: "プロフィール", required: &[], content: ContentFormat::MetadataJson },
+    KindSchema { kind: 1, name: "テキストノート", required: &[], content: ContentFormat::Any },
+    KindSchema { kind: 3, name: "コンタクトリスト", required: &[], content: ContentFormat::EmptyOrObject },
+    KindSchema { kind: 4, name: "暗号化DM", required: &[&["p"]], content: ContentFormat::Encrypted },
+    KindSchema { kind: 5, name: "削除の依頼", required: &[&["e", "a"]], content: ContentFormat::Any },
+    KindSchema { kind: 6, name: "リポスト", required: &[&["e"], &["p"]], content: ContentFormat::EmptyOrEvent },
+    KindSchema { kind: 7, name: "リアクション", required: &[&["e", "a"], &["p"]], content: ContentFormat::NonEmpty },
+    KindSchema { kind: 16, name: "リポスト", required: &[&["e", "a"], &["k"]], content: ContentFormat::EmptyOrEvent },
+    KindSchema { kind: 1984, name: "通報", required: &[&["p"]], content: ContentFormat::Any },
+    KindSchema { kind: 9734, name: "Zap リクエスト", required: &[&["p"], &["relays"]], content: ContentFormat::Any },
+    KindSchema { kind: 10002, name: "リレーの一覧", required: &[&["r"]], content: ContentFormat::Any },
+];
+
+// パラメータ付きの置き換え可能なイベント（d タグが必須）
+const PARAMETERIZED_KINDS: std::ops::Range<u64> = 30000..40000;
+
+fn is_hex_key(value: &str) -> bool {
+    value.len() == 64 && value.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+fn is_relay_url(value: &str) -> bool {
+    Url::parse(value).is_ok_and(|url| url.scheme() == "ws" || url.scheme() == "wss")
+}
+
+// タグ1つの値を確かめる（知っているタグ名だけ）
+fn check_tag(index: usize, tag: &[String], errors: &mut Vec<SchemaError>) {
+    let Some(name) = tag.first() else {
+        errors.push(SchemaError { path: format!("tags[{}]", index), message: "空のタグです".to_string() });
+        return;
+    };
+    let mut error = |position: usize, message: String| {
+        errors.push(SchemaError { path: format!("tags[{}][{}]", index, position), message });
+    };
+    if name.is_empty() {
+        error(0, "タグ名が空です".to_string());
+        return;
+    }
+    let value = tag.get(1).map(String::as_str);
+    match (name.as_str(), value) {
+        ("e" | "p" | "a" | "k" | "r", None) => error(1, format!("{} タグに値がありません", name)),
+        ("e", Some(id)) if !is_hex_key(id) => error(1, format!("イベントID（64桁の hex）ではありません: {}", id)),
+        ("p", Some(pubkey)) if !is_hex_key(pubkey) => error(1, format!("公開鍵（64桁の hex）ではありません: {}", pubkey)),
+        ("a", Some(address)) => {
+            let parts: Vec<&str> = address.splitn(3, ':').collect();
+            if parts.len() < 3 || parts[0].parse::<u64>().is_err() || !is_hex_key(parts[1]) {
+                error(1, format!("「kind:公開鍵:d タグ」の形ではありません: {}", address));
+            }
+        }
+        ("k", Some(kind)) if kind.parse::<u64>().is_err() => error(1, format!("kind（数値）ではありません: {}", kind)),
+        ("r", Some(url)) if !is_relay_url(url) && !url.starts_with("http") => error(1, format!("URL ではありません: {}", url)),
+        _ => {}
+    }
+    // e / p / a の3番目はリレーのヒント（空はよい）
+    if matches!(name.as_str(), "e" | "p" | "a") {
+        if let Some(relay) = tag.get(2).filter(|relay| !relay.is_empty() && !is_relay_url(relay)) {
+            error(2, format!("リレーの URL（ws:// / wss://）ではありません: {}", relay));
+        }
+    }
+}
+
+// 本文を確かめる
+fn check_content(format: ContentFormat, content: &str, errors: &mut Vec<SchemaError>) {
+    let mut error = |path: &str, message: String| errors.push(SchemaError { path: path.to_string(), message });
+    match format {
+        ContentFormat::Any => {}
+        ContentFormat::NonEmpty => {
+            if content.trim().is_empty() {
+                error("content", "本文が空です".to_string());
+            }
+        }
+        ContentFormat::MetadataJson => match serde_json::from_str::<serde_json::Value>(content) {
+            Ok(serde_json::Value::Object(fields)) => {
+                for (key, value) in &fields {
+                    if !value.is_string() && !value.is_null() && !value.is_boolean() {
+                        error(&format!("content.{}", key), "文字列ではありません".to_string());
+                    }
+                }
+            }
+            Ok(_) => error("content", "JSON のオブジェクトではありません".to_string()),
+            Err(e) => error("content", format!("JSON として解釈できません（{}行{}列）", e.line(), e.column())),
+        },
+        ContentFormat::EmptyOrObject => {
+            if !content.trim().is_empty() && !matches!(serde_json::from_str(content), Ok(serde_json::Value::Object(_))) {
+                error("content", "空か、JSON のオブジェクトである必要があります".to_string());
+            }
+        }
+        ContentFormat::EmptyOrEvent => {
+            if let Err(e) = (!content.trim().is_empty()).then(|| Event::from_json(content)).transpose() {
+                error("content", format!("空か、元のイベントの JSON である必要があります: {}", e));
+            }
+        }
+        ContentFormat::Encrypted => {
+            let valid = content.split_once("?iv=").is_some_and(|(data, iv)| !data.is_empty() && !iv.is_empty());
+            if !valid {
+                error("content", "NIP-04 の暗号文（…?iv=…）ではありません".to_string());
+            }
+        }
+    }
+}
+
+// イベントの形式を確かめる（誤りがなければ空）
+pub fn validate(kind: u64, content: &str, tags: &[Vec<String>]) -> Vec<SchemaError> {
+    let mut errors = Vec::new();
+    for (index, tag) in tags.iter().enumerate() {
+        check_tag(index, tag, &mut errors);
+    }
+
+    let has_tag = |name: &str| tags.iter().any(|tag| tag.first().map(String::as_str) == Some(name));
+    let schema = SCHEMAS.iter().find(|schema| schema.kind == kind);
+    if let Some(schema) = schema {
+        for names in schema.required {
+            if !names.iter().any(|name| has_tag(name)) {
+                let names = names.iter().map(|name| format!("{} タグ", name)).collect::<Vec<_>>().join(" か ");
+                errors.push(SchemaError { path: "tags".to_string(), message: format!("{}（kind {}）には {} が必要です", schema.name, kind, names) });
+            }
+        }
+        check_content(schema.content, content, &mut errors);
+    }
+    if PARAMETERIZED_KINDS.contains(&kind) && !has_tag("d") {
+        errors.push(SchemaError { path: "tags".to_string(), message: format!("kind {} には d タグ（識別子）が必要です", kind) });
+    }
+    errors
+}
+
+// 署名済みのイベントの形式を確かめる
+pub fn validate_event(event: &Event) -> Vec<SchemaError> {
+    let tags: Vec<Vec<String>> = event.tags.iter().map(|tag| tag.as_vec()).collect();
+    validate(event.kind.as_u64(), &event.content, &tags)
+}
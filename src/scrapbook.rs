@@ -0,0 +1,71 @@
+// スクラップブック - ノートを個人的な注釈・タグと一緒にローカル保存する
+use nostr_sdk::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use crate::commands::app_dir;
+
+// スクラップブックの1項目
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ScrapbookEntry {
+    pub event: Event,
+    #[serde(default)]
+    pub note: String,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    pub clipped_at: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct Scrapbook {
+    pub entries: Vec<ScrapbookEntry>,
+}
+
+impl Scrapbook {
+    // scrapbook.json を読み込む（未作成なら空）
+    pub fn load() -> Result<Self, Box<dyn std::error::Error>> {
+        let path = app_dir()?.join("scrapbook.json");
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let contents = fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&contents)?)
+    }
+
+    // scrapbook.json に保存する
+    pub fn save(&self) -> Result<(), Box<dyn std::error::Error>> {
+        let dir = app_dir()?;
+        fs::create_dir_all(&dir)?;
+        fs::write(dir.join("scrapbook.json"), serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    // 項目を追加する（同じイベントがあれば注釈とタグを更新する）
+    pub fn add(&mut self, entry: ScrapbookEntry) {
+        if let Some(existing) = self.entries.iter_mut().find(|e| e.event.id == entry.event.id) {
+            *existing = entry;
+        } else {
+            self.entries.push(entry);
+        }
+        self.entries.sort_by(|a, b| b.clipped_at.cmp(&a.clipped_at));
+    }
+
+    // 本文・注釈・タグを対象に全文検索する
+    // 空白区切りの語はすべて一致する必要があり、"#tag" はタグの完全一致として扱う
+    pub fn search(&self, query: &str) -> Vec<&ScrapbookEntry> {
+        let terms: Vec<String> = query.split_whitespace().map(|t| t.to_lowercase()).collect();
+
+        self.entries
+            .iter()
+            .filter(|entry| {
+                terms.iter().all(|term| {
+                    if let Some(tag) = term.strip_prefix('#') {
+                        return entry.tags.iter().any(|t| t.to_lowercase() == tag);
+                    }
+                    entry.event.content.to_lowercase().contains(term)
+                        || entry.note.to_lowercase().contains(term)
+                        || entry.tags.iter().any(|t| t.to_lowercase().contains(term))
+                })
+            })
+            .collect()
+    }
+}
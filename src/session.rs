@@ -0,0 +1,149 @@
+// セッションの統計 - TUI を起動してからの受信イベント数・送信した投稿数・転送量・接続中のリレー数
+// 終了時に cache/session_stats.json に保存し、session stats で前回の分を確認できる
+use nostr_sdk::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use crate::commands::app_dir;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct SessionStats {
+    pub started_at: u64,
+    pub ended_at: Option<u64>,
+    pub events_received: u64,
+    pub notes_sent: u64,
+    pub bytes_sent: u64,
+    pub bytes_received: u64,
+    pub relays_connected: usize,
+    pub relays_total: usize,
+    // リレーの統計はクライアントごとの累計なので、数え始めた時点の値を引き、切り替え前のクライアントの分を足す
+    #[serde(skip)]
+    carried: (u64, u64),
+    #[serde(skip)]
+    baseline: (u64, u64),
+    #[serde(skip)]
+    last_sample: (u64, u64),
+}
+
+impl SessionStats {
+    // 今から数え始める
+    pub fn new() -> Self {
+        Self { started_at: Timestamp::now().as_u64(), ..Default::default() }
+    }
+
+    // 接続中のクライアントのリレーの状態と転送量を反映する
+    pub fn record_relays(&mut self, connected: usize, total: usize, bytes_sent: u64, bytes_received: u64) {
+        self.relays_connected = connected;
+        self.relays_total = total;
+        self.last_sample = (bytes_sent, bytes_received);
+        self.bytes_sent = self.carried.0 + bytes_sent.saturating_sub(self.baseline.0);
+        self.bytes_received = self.carried.1 + bytes_received.saturating_sub(self.baseline.1);
+    }
+
+    // クライアントを作り直す前に、それまでの転送量を引き継ぐ
+    pub fn carry_over(&mut self) {
+        self.carried = (self.bytes_sent, self.bytes_received);
+        self.baseline = (0, 0);
+        self.last_sample = (0, 0);
+    }
+
+    // 今から数え直す（接続中のリレーの数はそのまま）
+    pub fn reset(&mut self) {
+        *self = Self {
+            relays_connected: self.relays_connected,
+            relays_total: self.relays_total,
+            baseline: self.last_sample,
+            last_sample: self.last_sample,
+            ..Self::new()
+        };
+    }
+
+    // 経過時間（秒）
+    fn elapsed_secs(&self) -> u64 {
+        self.ended_at.unwrap_or_else(|| Timestamp::now().as_u64()).saturating_sub(self.started_at)
+    }
+
+    // 拡張ステータスバーの1行
+    pub fn status_line(&self) -> String {
+        format!(
+            "📊 受信 {}件 | 送信 {}件 | ↑{} ↓{} | リレー {}/{} | {}",
+            self.events_received,
+            self.notes_sent,
+            format_bytes(self.bytes_sent),
+            format_bytes(self.bytes_received),
+            self.relays_connected,
+            self.relays_total,
+            format_duration(self.elapsed_secs())
+        )
+    }
+
+    // 終了時や session stats で表示する集計
+    pub fn report(&self) -> Vec<String> {
+        vec![
+            format!("セッションの時間: {}", format_duration(self.elapsed_secs())),
+            format!("受信したイベント: {}件", self.events_received),
+            format!("送信した投稿: {}件", self.notes_sent),
+            format!("転送量: 送信 {} / 受信 {}", format_bytes(self.bytes_sent), format_bytes(self.bytes_received)),
+            format!("接続中のリレー: {}/{}", self.relays_connected, self.relays_total),
+        ]
+    }
+
+    // 前回のセッションの統計を読み込む（未保存なら None）
+    pub fn load_last() -> Result<Option<Self>, Box<dyn std::error::Error>> {
+        let path = app_dir()?.join("cache").join("session_stats.json");
+        if !path.exists() {
+            return Ok(None);
+        }
+        Ok(Some(serde_json::from_str(&fs::read_to_string(path)?)?))
+    }
+
+    // 終了したセッションの統計として保存する
+    pub fn save_last(&self) -> Result<(), Box<dyn std::error::Error>> {
+        let dir = app_dir()?.join("cache");
+        fs::create_dir_all(&dir)?;
+        fs::write(dir.join("session_stats.json"), serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+}
+
+// クライアントのリレーの (接続中の数, 登録数, 送信バイト数, 受信バイト数)
+pub async fn sample_relays(client: &Client) -> (usize, usize, u64, u64) {
+    let relays = client.relays().await;
+    let mut connected = 0;
+    let mut bytes_sent = 0;
+    let mut bytes_received = 0;
+    for relay in relays.values() {
+        if relay.status().await == RelayStatus::Connected {
+            connected += 1;
+        }
+        let stats = relay.stats();
+        bytes_sent += stats.bytes_sent() as u64;
+        bytes_received += stats.bytes_received() as u64;
+    }
+    (connected, relays.len(), bytes_sent, bytes_received)
+}
+
+// バイト数を読みやすい単位にする（例: "1.2MB"）
+fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KB", "MB", "GB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{}B", bytes)
+    } else {
+        format!("{:.1}{}", value, UNITS[unit])
+    }
+}
+
+// 秒数を「1時間5分」「3分20秒」のように表す
+fn format_duration(secs: u64) -> String {
+    match secs {
+        0..=59 => format!("{}秒", secs),
+        60..=3599 => format!("{}分{}秒", secs / 60, secs % 60),
+        _ => format!("{}時間{}分", secs / 3600, secs % 3600 / 60),
+    }
+}
@@ -0,0 +1,170 @@
+// 秘密鍵の分散バックアップ（シャミアの秘密分散） - 秘密鍵を M 個の断片に分け、そのうち N 個を集めれば元に戻せるようにする
+// 断片は1バイトごとに GF(2^8) 上の N-1 次の多項式の値として作り、N-1 個までなら秘密鍵について何も分からない
+// 断片の形式: nshare-<N>of<M>-<番号>-<公開鍵の先頭8桁>-<64桁の hex>-<チェックサム8桁>
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::OsRng;
+use nostr_sdk::hashes::{sha256, Hash};
+use nostr_sdk::prelude::*;
+
+const SHARE_PREFIX: &str = "nshare";
+
+// GF(2^8) の掛け算（AES と同じ既約多項式 x^8 + x^4 + x^3 + x + 1）
+fn gf_mul(mut a: u8, mut b: u8) -> u8 {
+    let mut product = 0;
+    while b != 0 {
+        if b & 1 != 0 {
+            product ^= a;
+        }
+        let carry = a & 0x80;
+        a <<= 1;
+        if carry != 0 {
+            a ^= 0x1b;
+        }
+        b >>= 1;
+    }
+    product
+}
+
+// GF(2^8) の逆数（a^254）
+fn gf_inv(a: u8) -> u8 {
+    let mut result = 1;
+    for _ in 0..254 {
+        result = gf_mul(result, a);
+    }
+    result
+}
+
+// N-of-M の指定（"2-of-3" / "2of3" / "2/3"）
+pub fn parse_scheme(input: &str) -> Result<(u8, u8), Box<dyn std::error::Error>> {
+    let normalized = input.trim().to_lowercase().replace("-of-", "/").replace("of", "/");
+    let (threshold, total) = normalized.split_once('/').ok_or_else(|| format!("N-of-M の形式で指定してください（例: 2-of-3）: {}", input))?;
+    let threshold: u8 = threshold.trim().parse().map_err(|_| format!("N を数字で指定してください: {}", input))?;
+    let total: u8 = total.trim().parse().map_err(|_| format!("M を数字で指定してください: {}", input))?;
+    if threshold < 2 || threshold > total {
+        return Err("N は 2 以上、M 以下にしてください（N=1 なら分ける意味がありません）".into());
+    }
+    Ok((threshold, total))
+}
+
+// 断片1つ分
+#[derive(Debug, Clone, PartialEq)]
+pub struct Share {
+    pub threshold: u8,
+    pub total: u8,
+    pub index: u8,      // x 座標（1〜M）
+    pub key_id: String, // 公開鍵（hex）の先頭8桁（別の鍵の断片を混ぜていないか確かめる）
+    pub data: [u8; 32],
+}
+
+// 断片の本体のチェックサム（書き写し間違いを見つける）
+fn checksum(body: &str) -> String {
+    hex::encode(&sha256::Hash::hash(body.as_bytes()).as_byte_array()[..4])
+}
+
+impl Share {
+    fn body(&self) -> String {
+        format!("{}-{}of{}-{}-{}-{}", SHARE_PREFIX, self.threshold, self.total, self.index, self.key_id, hex::encode(self.data))
+    }
+
+    // 書き出す文字列
+    pub fn encode(&self) -> String {
+        let body = self.body();
+        let sum = checksum(&body);
+        format!("{}-{}", body, sum)
+    }
+
+    pub fn parse(input: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let input = input.trim();
+        let parts: Vec<&str> = input.split('-').collect();
+        let &[prefix, scheme, index, key_id, data, sum] = parts.as_slice() else {
+            return Err(format!("断片の形式が正しくありません: {}", input).into());
+        };
+        if prefix != SHARE_PREFIX {
+            return Err(format!("{} で始まる断片を指定してください", SHARE_PREFIX).into());
+        }
+        let (threshold, total) = parse_scheme(scheme)?;
+        let bytes = hex::decode(data)?;
+        let share = Self {
+            threshold,
+            total,
+            index: index.parse().map_err(|_| format!("断片の番号を解釈できません: {}", index))?,
+            key_id: key_id.to_string(),
+            data: bytes.try_into().map_err(|_| "断片の長さが正しくありません")?,
+        };
+        if share.index == 0 || share.index > share.total {
+            return Err(format!("断片の番号は 1〜{} です: {}", share.total, share.index).into());
+        }
+        if checksum(&share.body()) != sum {
+            return Err(format!("断片 {} のチェックサムが合いません（書き写し間違いの可能性があります）", share.index).into());
+        }
+        Ok(share)
+    }
+}
+
+// 公開鍵の先頭8桁
+fn key_id(secret_key: &SecretKey) -> String {
+    Keys::new(*secret_key).public_key().to_string()[..8].to_string()
+}
+
+// 秘密鍵を total 個の断片に分ける（threshold 個あれば戻せる）
+pub fn split(secret_key: &SecretKey, threshold: u8, total: u8) -> Vec<Share> {
+    let secret = secret_key.secret_bytes();
+    let id = key_id(secret_key);
+    let mut shares: Vec<Share> = (1..=total)
+        .map(|index| Share { threshold, total, index, key_id: id.clone(), data: [0; 32] })
+        .collect();
+
+    let mut coefficients = vec![0u8; threshold as usize - 1];
+    for (position, byte) in secret.iter().enumerate() {
+        OsRng.fill_bytes(&mut coefficients);
+        for share in shares.iter_mut() {
+            // ホーナー法で f(x) = secret + c1 x + c2 x^2 + ... を求める
+            let mut y = 0;
+            for coefficient in coefficients.iter().rev() {
+                y = gf_mul(y, share.index) ^ coefficient;
+            }
+            share.data[position] = gf_mul(y, share.index) ^ byte;
+        }
+    }
+    coefficients.fill(0);
+    shares
+}
+
+// 断片から秘密鍵を戻す（足りない・混ざっている・戻した鍵が合わないときはエラー）
+pub fn combine(shares: &[Share]) -> Result<SecretKey, Box<dyn std::error::Error>> {
+    let first = shares.first().ok_or("断片がありません")?;
+    if shares.iter().any(|s| s.threshold != first.threshold || s.total != first.total || s.key_id != first.key_id) {
+        return Err("別の鍵、または別の分け方の断片が混ざっています".into());
+    }
+    let mut indices: Vec<u8> = shares.iter().map(|s| s.index).collect();
+    indices.sort_unstable();
+    indices.dedup();
+    if indices.len() != shares.len() || indices.contains(&0) {
+        return Err("同じ番号の断片が含まれています".into());
+    }
+    if shares.len() < first.threshold as usize {
+        return Err(format!("断片が足りません（{}個必要です）", first.threshold).into());
+    }
+
+    // f(0) をラグランジュ補間で求める（GF(2^8) では足し算も引き算も XOR）
+    let used = &shares[..first.threshold as usize];
+    let mut secret = [0u8; 32];
+    for (j, share) in used.iter().enumerate() {
+        let mut basis = 1;
+        for (k, other) in used.iter().enumerate() {
+            if j != k {
+                basis = gf_mul(basis, gf_mul(other.index, gf_inv(other.index ^ share.index)));
+            }
+        }
+        for (position, byte) in share.data.iter().enumerate() {
+            secret[position] ^= gf_mul(*byte, basis);
+        }
+    }
+
+    let secret_key = SecretKey::from_slice(&secret).map_err(|_| "断片から秘密鍵を戻せませんでした")?;
+    secret.fill(0);
+    if key_id(&secret_key) != first.key_id {
+        return Err("断片から戻した鍵が断片に記録した公開鍵と合いません（断片が壊れている可能性があります）".into());
+    }
+    Ok(secret_key)
+}
@@ -0,0 +1,148 @@
+// リモートの署名サーバー（NIP-46 の bunker） - config の bunker_uri（bunker://<公開鍵>?relay=wss://...&secret=...）の署名サーバーに、
+// リレー越しに kind 24133 の暗号化したメッセージで署名を頼む。秘密鍵は署名サーバーにだけ置き、この端末には持たない
+use nostr_sdk::prelude::*;
+use std::time::Duration;
+use crate::commands::app_dir;
+use crate::storage::{read_json, write_json_no_backup};
+use crate::transport::dial;
+use super::{check_signed, Signer};
+
+// NIP-46 のメッセージの kind
+const KIND_NOSTR_CONNECT: u64 = 24133;
+// 署名サーバーの応答を待つ時間（サーバー側で承認を待つことがあるため長めにする）
+const RESPONSE_TIMEOUT: Duration = Duration::from_secs(60);
+
+// bunker:// の URI の中身
+struct BunkerUri {
+    remote: XOnlyPublicKey, // 署名サーバーの公開鍵
+    relays: Vec<String>,
+    secret: Option<String>,
+}
+
+fn parse_bunker_uri(uri: &str) -> Result<BunkerUri, Box<dyn std::error::Error>> {
+    let url = Url::parse(uri.trim()).map_err(|e| format!("bunker_uri を解釈できません: {}", e))?;
+    if url.scheme() != "bunker" {
+        return Err(format!("bunker_uri は bunker:// で始まる必要があります: {}", uri).into());
+    }
+    let remote = url.host_str().ok_or("bunker_uri に署名サーバーの公開鍵がありません")?;
+    let remote = XOnlyPublicKey::from_str(remote).map_err(|e| format!("bunker_uri の公開鍵が正しくありません: {}", e))?;
+    let relays: Vec<String> = url.query_pairs().filter(|(key, _)| key == "relay").map(|(_, value)| value.to_string()).collect();
+    if relays.is_empty() {
+        return Err("bunker_uri に relay= がありません".into());
+    }
+    let secret = url.query_pairs().find(|(key, _)| key == "secret").map(|(_, value)| value.to_string());
+    Ok(BunkerUri { remote, relays, secret })
+}
+
+// 署名サーバーに名乗るこの端末の鍵（cache/bunker_client.json。同じ鍵を使い続け、サーバー側の承認を覚えてもらう）
+fn client_keys() -> Result<Keys, Box<dyn std::error::Error>> {
+    let path = app_dir()?.join("cache").join("bunker_client.json");
+    if path.exists() {
+        let secret: String = read_json(&path)?;
+        return Ok(Keys::new(SecretKey::from_str(&secret)?));
+    }
+    let keys = Keys::generate();
+    write_json_no_backup(&path, &keys.secret_key()?.display_secret().to_string())?;
+    Ok(keys)
+}
+
+pub struct BunkerSigner {
+    uri: BunkerUri,
+    public_key: XOnlyPublicKey, // 鍵ファイルに記録した署名サーバーのユーザーの公開鍵
+    client_keys: Keys,
+}
+
+impl BunkerSigner {
+    pub fn new(uri: &str, public_key: XOnlyPublicKey) -> Result<Self, Box<dyn std::error::Error>> {
+        Ok(Self { uri: parse_bunker_uri(uri)?, public_key, client_keys: client_keys()? })
+    }
+
+    // 署名サーバーのリレーに接続し、(必要なら connect してから) 1つの要求を送って結果を受け取る
+    async fn request(&self, method: &str, params: Vec<String>) -> Result<String, Box<dyn std::error::Error>> {
+        let client = Client::new(&self.client_keys);
+        for relay in &self.uri.relays {
+            client.add_relay(dial(relay)?).await?;
+        }
+        client.connect().await;
+        let result = async {
+            if let Some(secret) = &self.uri.secret {
+                self.call(&client, "connect", vec![self.uri.remote.to_string(), secret.clone()]).await?;
+            }
+            self.call(&client, method, params).await
+        }
+        .await;
+        client.shutdown().await?;
+        result
+    }
+
+    // 要求を暗号化して送り、同じ ID の応答を待つ
+    async fn call(&self, client: &Client, method: &str, params: Vec<String>) -> Result<String, Box<dyn std::error::Error>> {
+        let secret_key = self.client_keys.secret_key()?;
+        let id = Keys::generate().public_key().to_string()[..16].to_string();
+        let message = serde_json::json!({ "id": id, "method": method, "params": params }).to_string();
+        let encrypted = nip04::encrypt(&secret_key, &self.uri.remote, message)?;
+        let p_tag = Tag::parse(vec!["p".to_string(), self.uri.remote.to_string()])?;
+        let event = EventBuilder::new(Kind::from(KIND_NOSTR_CONNECT), encrypted, vec![p_tag]).to_event(&self.client_keys)?;
+
+        let mut notifications = client.notifications();
+        let filter = Filter::new()
+            .kind(Kind::from(KIND_NOSTR_CONNECT))
+            .author(self.uri.remote)
+            .pubkey(self.client_keys.public_key())
+            .since(Timestamp::now());
+        client.subscribe(vec![filter]).await;
+        client.send_event(event).await?;
+
+        let deadline = tokio::time::Instant::now() + RESPONSE_TIMEOUT;
+        loop {
+            let notification = tokio::time::timeout_at(deadline, notifications.recv())
+                .await
+                .map_err(|_| format!("署名サーバーから{}秒以内に応答がありません（{}）", RESPONSE_TIMEOUT.as_secs(), method))?;
+            let Ok(RelayPoolNotification::Event { event, .. }) = notification else {
+                continue;
+            };
+            if event.pubkey != self.uri.remote {
+                continue;
+            }
+            let Ok(plain) = nip04::decrypt(&secret_key, &self.uri.remote, &event.content) else {
+                continue;
+            };
+            let Ok(response) = serde_json::from_str::<serde_json::Value>(&plain) else {
+                continue;
+            };
+            if response["id"].as_str() != Some(id.as_str()) {
+                continue;
+            }
+            let result = response["result"].as_str().unwrap_or_default();
+            // 承認のページを開くよう求められたら、表示して本当の応答を待ち続ける
+            if result == "auth_url" {
+                eprintln!("署名サーバーでの承認が必要です: {}", response["error"].as_str().unwrap_or_default());
+                continue;
+            }
+            if let Some(error) = response["error"].as_str().filter(|error| !error.is_empty()) {
+                return Err(format!("署名サーバーが断りました（{}）: {}", method, error).into());
+            }
+            return Ok(result.to_string());
+        }
+    }
+}
+
+impl Signer for BunkerSigner {
+    fn name(&self) -> &'static str {
+        "bunker"
+    }
+
+    fn public_key(&self) -> XOnlyPublicKey {
+        self.public_key
+    }
+
+    // 署名サーバーに未署名のイベントを渡し、返ってきたイベントを確かめる
+    // Signer は同期のトレイトなので、実行中の tokio のランタイムの上でリレーとのやりとりを待つ
+    fn sign(&self, unsigned: UnsignedEvent) -> Result<Event, Box<dyn std::error::Error>> {
+        let handle = tokio::runtime::Handle::try_current().map_err(|_| "リモートの署名は非同期の処理の中でしか使えません")?;
+        let params = vec![serde_json::to_string(&unsigned)?];
+        let signed = tokio::task::block_in_place(|| handle.block_on(self.request("sign_event", params)))?;
+        let event = Event::from_json(&signed).map_err(|e| format!("署名サーバーの応答をイベントとして解釈できません: {}", e))?;
+        check_signed(&unsigned, event, "署名サーバー")
+    }
+}
@@ -0,0 +1,53 @@
+// 外部の署名プログラム - config の signer_command を設定すると、秘密鍵を持たずに外部のプログラムに署名させる
+// 標準入力に未署名のイベントの JSON を書き、標準出力から署名済みのイベントの JSON を読む（ハードウェアやエアギャップでの署名向け）
+use nostr_sdk::prelude::*;
+use std::io::Write;
+use std::process::{Command, Stdio};
+use super::{check_signed, Signer};
+
+pub struct ExecSigner {
+    command: String,
+    public_key: XOnlyPublicKey, // 鍵ファイルに記録した署名プログラムの公開鍵
+}
+
+impl ExecSigner {
+    pub fn new(command: String, public_key: XOnlyPublicKey) -> Self {
+        Self { command, public_key }
+    }
+}
+
+impl Signer for ExecSigner {
+    fn name(&self) -> &'static str {
+        "exec"
+    }
+
+    fn public_key(&self) -> XOnlyPublicKey {
+        self.public_key
+    }
+
+    // 署名プログラムに未署名のイベントを渡し、返ってきたイベントを確かめる
+    fn sign(&self, unsigned: UnsignedEvent) -> Result<Event, Box<dyn std::error::Error>> {
+        let mut child = Command::new("sh")
+            .arg("-c")
+            .arg(&self.command)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| format!("署名プログラムを起動できません ({}): {}", self.command, e))?;
+
+        let input = serde_json::to_string(&unsigned)?;
+        let mut stdin = child.stdin.take().ok_or("署名プログラムの標準入力を開けません")?;
+        let writer = std::thread::spawn(move || stdin.write_all(input.as_bytes()));
+        let output = child.wait_with_output()?;
+        let _ = writer.join();
+        if !output.status.success() {
+            let message = String::from_utf8_lossy(&output.stderr).trim().to_string();
+            return Err(format!("署名プログラムが失敗しました ({}): {}", output.status, message).into());
+        }
+
+        let event = Event::from_json(String::from_utf8_lossy(&output.stdout).trim())
+            .map_err(|e| format!("署名プログラムの出力をイベントとして解釈できません: {}", e))?;
+        check_signed(&unsigned, event, "署名プログラム")
+    }
+}
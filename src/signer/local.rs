@@ -0,0 +1,30 @@
+// 手元の鍵での署名 - keys.json をパスワードで復号した鍵、OS のキーチェーン、エージェントから受け取った鍵で署名する
+use nostr_sdk::prelude::*;
+use super::Signer;
+
+pub struct LocalSigner {
+    keys: Keys,
+}
+
+impl LocalSigner {
+    pub fn new(keys: Keys) -> Self {
+        Self { keys }
+    }
+}
+
+impl Signer for LocalSigner {
+    fn name(&self) -> &'static str {
+        "local"
+    }
+
+    fn public_key(&self) -> XOnlyPublicKey {
+        self.keys.public_key()
+    }
+
+    fn sign(&self, unsigned: UnsignedEvent) -> Result<Event, Box<dyn std::error::Error>> {
+        if self.keys.secret_key().is_err() {
+            return Err("閲覧専用のアカウントでは署名できません（秘密鍵がありません）".into());
+        }
+        Ok(unsigned.sign(&self.keys)?)
+    }
+}
@@ -0,0 +1,108 @@
+// 署名の方法 - イベントへの署名を Signer トレイトにまとめ、設定に応じて使う方法（バックエンド）を選ぶ
+// 手元の鍵（local）のほか、外部の署名プログラム（exec、Cargo の機能 exec-signer）と
+// NIP-46 のリモートの署名サーバー（bunker、Cargo の機能 bunker-signer）を選べる
+// 方法を増やすときは、このディレクトリにモジュールを加えて active_signer で選ぶだけで、commands.rs は変えなくてよい
+use nostr_sdk::prelude::*;
+use crate::audit::record_signed;
+use crate::config::AppConfig;
+
+#[cfg(feature = "bunker-signer")]
+mod bunker;
+#[cfg(feature = "exec-signer")]
+mod exec;
+mod local;
+
+#[cfg(feature = "bunker-signer")]
+use bunker::BunkerSigner;
+#[cfg(feature = "exec-signer")]
+use exec::ExecSigner;
+use local::LocalSigner;
+
+// イベントに署名する方法
+pub trait Signer {
+    // 表示用の名前
+    fn name(&self) -> &'static str;
+    // 署名に使う公開鍵
+    fn public_key(&self) -> XOnlyPublicKey;
+    // 未署名のイベントに署名する
+    fn sign(&self, unsigned: UnsignedEvent) -> Result<Event, Box<dyn std::error::Error>>;
+}
+
+// 設定されている署名プログラム（なければ None）
+pub fn signer_command() -> Option<String> {
+    AppConfig::load().ok()?.signer_command.filter(|command| !command.trim().is_empty())
+}
+
+// 設定されている署名サーバー（なければ None）
+pub fn bunker_uri() -> Option<String> {
+    AppConfig::load().ok()?.bunker_uri.filter(|uri| !uri.trim().is_empty())
+}
+
+// 秘密鍵を持たず、外部（署名プログラムか署名サーバー）に署名を任せているか
+pub fn external_signer() -> bool {
+    signer_command().is_some() || bunker_uri().is_some()
+}
+
+// 設定に応じた署名の方法（bunker_uri があれば署名サーバー、signer_command があれば外部のプログラム、なければ手元の鍵）
+pub fn active_signer(keys: &Keys) -> Result<Box<dyn Signer>, Box<dyn std::error::Error>> {
+    match bunker_uri() {
+        #[cfg(feature = "bunker-signer")]
+        Some(uri) => return Ok(Box::new(BunkerSigner::new(&uri, keys.public_key())?)),
+        #[cfg(not(feature = "bunker-signer"))]
+        Some(_) => return Err("このビルドにはリモートの署名サーバー（機能 bunker-signer）が含まれていません。bunker_uri を外してください".into()),
+        None => {}
+    }
+    match signer_command() {
+        #[cfg(feature = "exec-signer")]
+        Some(command) => Ok(Box::new(ExecSigner::new(command, keys.public_key()))),
+        #[cfg(not(feature = "exec-signer"))]
+        Some(_) => Err("このビルドには外部の署名プログラム（機能 exec-signer）が含まれていません。signer_command を外してください".into()),
+        None => Ok(Box::new(LocalSigner::new(keys.clone()))),
+    }
+}
+
+// イベントに署名する（signer_command があればそのプログラムに、なければ手元の鍵で）
+pub fn sign_event(builder: EventBuilder, keys: &Keys) -> Result<Event, Box<dyn std::error::Error>> {
+    sign_with(active_signer(keys)?.as_ref(), builder)
+}
+
+// PoW（NIP-13）を採掘してから署名する（nonce タグを変えながら、ID の先頭の0のビットが difficulty 以上になるまで探す）
+pub fn sign_event_pow(builder: EventBuilder, keys: &Keys, difficulty: u8) -> Result<Event, Box<dyn std::error::Error>> {
+    let signer = active_signer(keys)?;
+    let event = signer
+        .sign(builder.to_unsigned_pow_event(signer.public_key(), difficulty))
+        .map_err(|e| format!("署名に失敗しました（{}）: {}", signer.name(), e))?;
+    record_signed(&event)?;
+    Ok(event)
+}
+
+// 手元の鍵で署名する（DM の暗号化など秘密鍵が手元に必要なもの。署名の記録には残す）
+pub fn sign_local(builder: EventBuilder, keys: &Keys) -> Result<Event, Box<dyn std::error::Error>> {
+    sign_with(&LocalSigner::new(keys.clone()), builder)
+}
+
+// 外部から返ってきたイベントが、頼んだ内容のまま頼んだ公開鍵で署名されているか確かめる（ID と署名も検証する）
+#[cfg(any(feature = "exec-signer", feature = "bunker-signer"))]
+fn check_signed(unsigned: &UnsignedEvent, event: Event, source: &str) -> Result<Event, Box<dyn std::error::Error>> {
+    if event.pubkey != unsigned.pubkey
+        || event.kind != unsigned.kind
+        || event.created_at != unsigned.created_at
+        || event.content != unsigned.content
+        || event.tags != unsigned.tags
+    {
+        return Err(format!("{}が返したイベントの内容が、署名を頼んだものと異なります", source).into());
+    }
+    event
+        .verify()
+        .map_err(|e| format!("{}が返したイベントの署名が正しくありません: {}", source, e))?;
+    Ok(event)
+}
+
+// 指定した方法で署名し、署名の記録に残す
+pub fn sign_with(signer: &dyn Signer, builder: EventBuilder) -> Result<Event, Box<dyn std::error::Error>> {
+    let event = signer
+        .sign(builder.to_unsigned_event(signer.public_key()))
+        .map_err(|e| format!("署名に失敗しました（{}）: {}", signer.name(), e))?;
+    record_signed(&event)?;
+    Ok(event)
+}
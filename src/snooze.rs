@@ -0,0 +1,110 @@
+// あとで読む（スヌーズ） - フィードのノートをいったん隠し、指定した時刻（1時間後・明日の朝・来週の月曜の朝）になったら
+// リマインダーに戻す。「あとで返信する」ための控えで、鍵ファイルの隣の snoozed.json に保存する（リレーには何も送らない）
+use chrono::{Datelike, Duration as ChronoDuration, FixedOffset, NaiveTime, TimeZone, Utc};
+use nostr_sdk::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use crate::accounts::keys_path;
+use crate::storage::{read_json, write_json};
+
+// 「明日」「来週」で戻す時刻（日本時間）
+const MORNING_HOUR: u32 = 9;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SnoozeDuration {
+    OneHour,
+    Tomorrow, // 明日の朝9時
+    NextWeek, // 来週の月曜の朝9時
+}
+
+impl SnoozeDuration {
+    pub const ALL: [SnoozeDuration; 3] = [SnoozeDuration::OneHour, SnoozeDuration::Tomorrow, SnoozeDuration::NextWeek];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            SnoozeDuration::OneHour => "1時間後",
+            SnoozeDuration::Tomorrow => "明日の朝（9:00）",
+            SnoozeDuration::NextWeek => "来週の月曜の朝（9:00）",
+        }
+    }
+
+    // リマインダーに戻す時刻
+    pub fn until(self, now: Timestamp) -> Timestamp {
+        let jst = FixedOffset::east_opt(9 * 3600).expect("JST のオフセットが正しくありません");
+        let now_jst = Utc
+            .timestamp_opt(now.as_u64() as i64, 0)
+            .single()
+            .unwrap_or_else(Utc::now)
+            .with_timezone(&jst);
+        let days = match self {
+            SnoozeDuration::OneHour => return Timestamp::from(now.as_u64() + 60 * 60),
+            SnoozeDuration::Tomorrow => 1,
+            SnoozeDuration::NextWeek => 7 - now_jst.weekday().num_days_from_monday() as i64,
+        };
+        let morning = NaiveTime::from_hms_opt(MORNING_HOUR, 0, 0).expect("時刻が正しくありません");
+        let date = now_jst.date_naive() + ChronoDuration::days(days);
+        let at = jst.from_local_datetime(&date.and_time(morning)).single().map_or(now.as_u64() as i64, |at| at.timestamp());
+        Timestamp::from(at.max(0) as u64)
+    }
+}
+
+// スヌーズ中の1件
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SnoozedNote {
+    pub event: Event,
+    pub until: u64, // リマインダーに戻す時刻（UNIX 秒）
+}
+
+impl SnoozedNote {
+    // 時刻が来てリマインダーに戻ったか
+    pub fn is_due(&self, now: Timestamp) -> bool {
+        now.as_u64() >= self.until
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct Snoozed {
+    #[serde(default)]
+    pub items: Vec<SnoozedNote>, // 戻す時刻の早い順
+}
+
+impl Snoozed {
+    // 使用中のアカウントの鍵ファイルの隣
+    fn path() -> Result<PathBuf, Box<dyn std::error::Error>> {
+        Ok(keys_path()?.with_file_name("snoozed.json"))
+    }
+
+    // snoozed.json を読み込む（未作成なら空）
+    pub fn load() -> Result<Self, Box<dyn std::error::Error>> {
+        let path = Self::path()?;
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        read_json(&path)
+    }
+
+    pub fn save(&self) -> Result<(), Box<dyn std::error::Error>> {
+        write_json(&Self::path()?, self)
+    }
+
+    pub fn contains(&self, id: &EventId) -> bool {
+        self.items.iter().any(|item| item.event.id == *id)
+    }
+
+    // スヌーズする（すでにスヌーズ中なら戻す時刻を変える）
+    pub fn snooze(&mut self, event: Event, until: Timestamp) {
+        self.items.retain(|item| item.event.id != event.id);
+        let index = self.items.partition_point(|item| item.until <= until.as_u64());
+        self.items.insert(index, SnoozedNote { event, until: until.as_u64() });
+    }
+
+    // リマインダーに戻った件数
+    pub fn due_count(&self, now: Timestamp) -> usize {
+        self.items.iter().filter(|item| item.is_due(now)).count()
+    }
+
+    // リマインダーから外す（フィードに戻す・返信する・片付けるとき）
+    pub fn take(&mut self, index: usize) -> Option<SnoozedNote> {
+        (index < self.items.len()).then(|| self.items.remove(index))
+    }
+}
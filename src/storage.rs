@@ -0,0 +1,150 @@
+// 設定ファイルの安全な読み書き - 一時ファイルに書いて fsync してから名前を変え、書き込みの途中で落ちても壊れたファイルを残さない
+// JSON のファイルは書き換える前の内容を1世代だけ <名前>.bak に残し、読み込めないとき（途中で切れた JSON など）はそこから戻す（鍵ファイルなど秘密を含むものは残さない）
+// TUI と CLI のコマンド（や2つの TUI）を同時に動かしても壊さないよう、読み込んで書き戻す間は <名前>.lock にアドバイザリロックを取る
+use fs2::FileExt;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+// ほかのプロセスがロックを持っているときに待つ時間と、取り直す間隔
+const LOCK_WAIT: Duration = Duration::from_secs(10);
+const LOCK_RETRY: Duration = Duration::from_millis(50);
+
+// 同じディレクトリに置く、拡張子を足した名前（keys.json → keys.json.bak）
+fn sibling(path: &Path, extension: &str) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".");
+    name.push(extension);
+    path.with_file_name(name)
+}
+
+// ファイルを置き換える（一時ファイルに書いて fsync し、名前を変えてからディレクトリも fsync する）
+pub fn write_atomic(path: &Path, contents: &[u8]) -> Result<(), Box<dyn std::error::Error>> {
+    let dir = path.parent().ok_or_else(|| format!("保存先のディレクトリが分かりません: {:?}", path))?;
+    fs::create_dir_all(dir)?;
+
+    let tmp = sibling(path, "tmp");
+    // 中身を書く前から本人だけが読める権限で作る（新しい鍵ファイルやキャッシュが umask で 0644 にならないように）
+    // mode は新しく作るときにしか効かないので、前回落ちて残った一時ファイルは先に消す
+    let _ = fs::remove_file(&tmp);
+    let mut file = OpenOptions::new().write(true).create(true).truncate(true).mode(0o600).open(&tmp)?;
+    // 鍵ファイルなどの権限を変えてしまわないよう、元のファイルの権限を引き継ぐ
+    if let Ok(metadata) = fs::metadata(path) {
+        fs::set_permissions(&tmp, metadata.permissions())?;
+    }
+    file.write_all(contents)?;
+    file.sync_all()?;
+    drop(file);
+
+    fs::rename(&tmp, path)?;
+    File::open(dir)?.sync_all()?;
+    Ok(())
+}
+
+// JSON のファイルを保存する（今の内容が読めるものなら、置き換える前に .bak に残す）
+pub fn write_json<T: Serialize>(path: &Path, value: &T) -> Result<(), Box<dyn std::error::Error>> {
+    if let Ok(current) = fs::read(path) {
+        if serde_json::from_slice::<serde_json::Value>(&current).is_ok() {
+            write_atomic(&sibling(path, "bak"), &current)?;
+        }
+    }
+    write_atomic(path, serde_json::to_string_pretty(value)?.as_bytes())
+}
+
+// 秘密を含む JSON のファイルを保存する（.bak を作らず、前に残った .bak もゼロで上書きして消す）
+// 鍵ファイルの .bak には平文の鍵や古いパスワードで暗号化した鍵が残り、read_json で生きた鍵ファイルに戻されてしまうため
+pub fn write_json_no_backup<T: Serialize>(path: &Path, value: &T) -> Result<(), Box<dyn std::error::Error>> {
+    write_atomic(path, serde_json::to_string_pretty(value)?.as_bytes())?;
+    shred_file(&sibling(path, "bak"))?;
+    Ok(())
+}
+
+// ファイルを同じ大きさのゼロで上書きして fsync してから削除する（なければ false）
+// SSD やコピーオンライトのファイルシステムでは元のブロックが残ることがあるので、ディスクの暗号化と合わせて使う
+pub fn shred_file(path: &Path) -> Result<bool, Box<dyn std::error::Error>> {
+    let Ok(metadata) = fs::symlink_metadata(path) else {
+        return Ok(false);
+    };
+    if metadata.is_file() {
+        let mut file = OpenOptions::new().write(true).open(path)?;
+        let zeros = vec![0u8; 4096];
+        let mut remaining = metadata.len();
+        while remaining > 0 {
+            let chunk = remaining.min(zeros.len() as u64) as usize;
+            file.write_all(&zeros[..chunk])?;
+            remaining -= chunk as u64;
+        }
+        file.sync_all()?;
+    }
+    fs::remove_file(path)?;
+    Ok(true)
+}
+
+// 読み取るだけのコマンドが、ほかのインスタンスがロックを持っていてキャッシュを書かなかったときに表示する案内
+pub const LOCK_HELD_NOTICE: &str = "別のインスタンスがキャッシュのロックを持っているため、書き込まずに読み取るだけにしました";
+
+// 取ったロック（手放すと解放する。プロセスが落ちても OS が解放する）
+pub struct FileLock {
+    _file: File,
+}
+
+impl FileLock {
+    // 待たずにロックを取る（ほかのプロセスが持っていれば None。読み取るだけの処理は書き込みを諦めて続ける）
+    pub fn try_acquire(path: &Path) -> Result<Option<Self>, Box<dyn std::error::Error>> {
+        let lock_path = sibling(path, "lock");
+        if let Some(dir) = lock_path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let file = OpenOptions::new().create(true).write(true).open(&lock_path)?;
+        match file.try_lock_exclusive() {
+            Ok(()) => Ok(Some(Self { _file: file })),
+            Err(e) if e.kind() == fs2::lock_contended_error().kind() => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    // ロックを取る（ほかのプロセスが持っていれば、しばらく待ってから諦める。書き込むことが目的の処理で使う）
+    pub fn acquire(path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        let started = Instant::now();
+        loop {
+            if let Some(lock) = Self::try_acquire(path)? {
+                return Ok(lock);
+            }
+            if started.elapsed() >= LOCK_WAIT {
+                return Err(format!(
+                    "別のインスタンスが {:?} のロックを持っているため書き込めません（そちらの処理が終わってからやり直してください）",
+                    path
+                )
+                .into());
+            }
+            std::thread::sleep(LOCK_RETRY);
+        }
+    }
+}
+
+// JSON のファイルを読み込む
+// 解釈できなければ .bak から戻し、壊れたファイルは <名前>.corrupt として調べられるように残す
+pub fn read_json<T: DeserializeOwned>(path: &Path) -> Result<T, Box<dyn std::error::Error>> {
+    let contents = fs::read_to_string(path)?;
+    let error = match serde_json::from_str(&contents) {
+        Ok(value) => return Ok(value),
+        Err(e) => e,
+    };
+
+    let backup = fs::read_to_string(sibling(path, "bak")).ok();
+    let Some((value, backup)) = backup.and_then(|backup| Some((serde_json::from_str::<T>(&backup).ok()?, backup))) else {
+        return Err(format!("{:?} を解釈できません: {}（前回の内容 .bak もありません）", path, error).into());
+    };
+    let corrupt = sibling(path, "corrupt");
+    fs::rename(path, &corrupt)?;
+    write_atomic(path, backup.as_bytes())?;
+    eprintln!(
+        "{:?} が壊れていた（{}）ため、前回保存した内容に戻しました（壊れたファイルは {:?}）",
+        path, error, corrupt
+    );
+    Ok(value)
+}
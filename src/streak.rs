@@ -0,0 +1,140 @@
+// 連続投稿（GM ストリーク） - 投稿した日（日本時間）をアカウントごとの streak.json に記録し、何日続けて投稿しているかを数える
+// config の post_reminder（"21:00" など）を過ぎても今日まだ投稿していなければ、デスクトップ通知（notify-send）と端末のベルで知らせる
+use chrono::{DateTime, FixedOffset, NaiveDate, NaiveTime, Utc};
+use nostr_sdk::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::io::Write;
+use std::path::PathBuf;
+use crate::accounts::{account_dir, active_account};
+use crate::storage::{read_json, write_json};
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct PostingStreak {
+    #[serde(default)]
+    pub days: BTreeSet<String>, // 投稿した日（日本時間の YYYY-MM-DD）
+}
+
+// 日本時間
+fn jst() -> FixedOffset {
+    FixedOffset::east_opt(9 * 3600).expect("タイムゾーンの初期化に失敗しました")
+}
+
+// 日本時間の日付
+fn jst_date(timestamp: Timestamp) -> NaiveDate {
+    DateTime::<Utc>::from_timestamp(timestamp.as_u64() as i64, 0)
+        .unwrap_or_default()
+        .with_timezone(&jst())
+        .date_naive()
+}
+
+// 日本時間の日付の文字列（YYYY-MM-DD、streak.json の記録と同じ形式）
+pub fn day_key(timestamp: Timestamp) -> String {
+    jst_date(timestamp).format("%Y-%m-%d").to_string()
+}
+
+impl PostingStreak {
+    // 使用中のアカウントの streak.json
+    fn path() -> Result<PathBuf, Box<dyn std::error::Error>> {
+        Ok(account_dir(&active_account()?)?.join("streak.json"))
+    }
+
+    // streak.json を読み込む（未作成なら空）
+    pub fn load() -> Result<Self, Box<dyn std::error::Error>> {
+        let path = Self::path()?;
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        read_json(&path)
+    }
+
+    pub fn save(&self) -> Result<(), Box<dyn std::error::Error>> {
+        write_json(&Self::path()?, self)
+    }
+
+    // 投稿した日として記録する（記録済みの日なら false）
+    pub fn record(&mut self, created_at: Timestamp) -> bool {
+        self.days.insert(day_key(created_at))
+    }
+
+    fn dates(&self) -> Vec<NaiveDate> {
+        self.days.iter().filter_map(|day| NaiveDate::parse_from_str(day, "%Y-%m-%d").ok()).collect()
+    }
+
+    // 今日投稿したか
+    pub fn posted_on(&self, now: Timestamp) -> bool {
+        self.days.contains(&day_key(now))
+    }
+
+    // 今日まで何日続けて投稿しているか（今日まだ投稿していなくても、昨日まで続いていれば途切れていない）
+    pub fn current(&self, now: Timestamp) -> usize {
+        let today = jst_date(now);
+        let mut day = if self.posted_on(now) { today } else { today.pred_opt().unwrap_or(today) };
+        let dates: BTreeSet<NaiveDate> = self.dates().into_iter().collect();
+        let mut count = 0;
+        while dates.contains(&day) {
+            count += 1;
+            match day.pred_opt() {
+                Some(previous) => day = previous,
+                None => break,
+            }
+        }
+        count
+    }
+
+    // これまでで最も長く続いた日数
+    pub fn longest(&self) -> usize {
+        let mut longest = 0;
+        let mut run = 0;
+        let mut previous: Option<NaiveDate> = None;
+        for date in self.dates() {
+            run = if previous.and_then(|p| p.succ_opt()) == Some(date) { run + 1 } else { 1 };
+            longest = longest.max(run);
+            previous = Some(date);
+        }
+        longest
+    }
+
+    // 拡張ステータスバーなどに表示する1行
+    pub fn summary(&self, now: Timestamp) -> String {
+        let today = if self.posted_on(now) { "今日は投稿済み" } else { "今日はまだ投稿していません" };
+        format!("🔥 {}日連続（最長 {}日、{}）", self.current(now), self.longest(), today)
+    }
+}
+
+// 送信した投稿を連続投稿の記録に加える（投稿以外の kind は数えない）
+pub fn record_post(event: &Event) -> Result<(), Box<dyn std::error::Error>> {
+    if event.kind != Kind::TextNote {
+        return Ok(());
+    }
+    let mut streak = PostingStreak::load()?;
+    if streak.record(event.created_at) {
+        streak.save()?;
+    }
+    Ok(())
+}
+
+// config の post_reminder（"HH:MM"、日本時間）を解釈する
+pub fn parse_reminder_time(input: &str) -> Result<NaiveTime, Box<dyn std::error::Error>> {
+    NaiveTime::parse_from_str(input.trim(), "%H:%M")
+        .map_err(|_| format!("リマインダーの時刻は HH:MM の形式で指定してください: {}", input).into())
+}
+
+// リマインダーの時刻を過ぎたのに今日まだ投稿していないか
+pub fn reminder_due(streak: &PostingStreak, reminder: NaiveTime, now: Timestamp) -> bool {
+    let local = DateTime::<Utc>::from_timestamp(now.as_u64() as i64, 0).unwrap_or_default().with_timezone(&jst());
+    local.time() >= reminder && !streak.posted_on(now)
+}
+
+// デスクトップ通知（通知デーモンに notify-send で送る）と端末のベルで知らせる
+// 通知デーモンがない環境では、ベルだけを鳴らす
+pub fn notify_reminder(message: &str) {
+    let _ = std::process::Command::new("notify-send")
+        .args(["--app-name=nostr-cli-app", "nostr-cli-app", message])
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .status();
+    let mut stdout = std::io::stdout();
+    let _ = stdout.write_all(b"\x07");
+    let _ = stdout.flush();
+}
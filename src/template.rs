@@ -0,0 +1,51 @@
+// イベントのテンプレート - {{変数}} を値に置き換えて本文やタグを作る
+use chrono::{FixedOffset, Utc};
+use std::collections::BTreeMap;
+
+// "name=値" の形式を (name, 値) に分ける
+pub fn parse_assignment(input: &str) -> Result<(String, String), Box<dyn std::error::Error>> {
+    let (name, value) = input
+        .split_once('=')
+        .ok_or_else(|| format!("「名前=値」の形式で指定してください: {}", input))?;
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(format!("名前が空です: {}", input).into());
+    }
+    Ok((name.to_string(), value.to_string()))
+}
+
+// 指定がなくても使える組み込みの変数（now: UNIX時刻、date / datetime: JST の日付・日時）
+pub fn builtin_vars() -> BTreeMap<String, String> {
+    let jst = FixedOffset::east_opt(9 * 3600).expect("タイムゾーンの初期化に失敗しました");
+    let now = Utc::now().with_timezone(&jst);
+    BTreeMap::from([
+        ("now".to_string(), now.timestamp().to_string()),
+        ("date".to_string(), now.format("%Y-%m-%d").to_string()),
+        ("datetime".to_string(), now.format("%Y-%m-%d %H:%M").to_string()),
+    ])
+}
+
+// テンプレートの {{変数}} を置き換える（未定義の変数はエラーにして、誤字のまま投稿しないようにする）
+// "{{{{" と書くと "{{" そのものを出力する
+pub fn render(template: &str, vars: &BTreeMap<String, String>) -> Result<String, Box<dyn std::error::Error>> {
+    let mut output = String::new();
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        output.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        if let Some(escaped) = after.strip_prefix("{{") {
+            output.push_str("{{");
+            rest = escaped;
+            continue;
+        }
+        let end = after.find("}}").ok_or("テンプレートの {{ が閉じられていません")?;
+        let name = after[..end].trim();
+        let value = vars
+            .get(name)
+            .ok_or_else(|| format!("テンプレートの変数が定義されていません: {}", name))?;
+        output.push_str(value);
+        rest = &after[end + 2..];
+    }
+    output.push_str(rest);
+    Ok(output)
+}
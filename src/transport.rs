@@ -0,0 +1,154 @@
+// リレーとの通信路 - nostr-sdk がつなげるのは ws:// と wss:// の WebSocket だけなので、それ以外の通信路
+// （unix:// の Unix ドメインソケットで待ち受ける手元のリレーなど）は、ループバックに待ち受けを開いてバイト列をそのまま中継し、
+// nostr-sdk には ws://127.0.0.1:ポート を渡す。新しい通信路は Transport を実装して TRANSPORTS に加えれば、コマンド側は変えずに使える
+// 中継の待ち受けは同じ端末のほかのユーザーからも接続できてしまうため、このプロセス自身が開いた接続でなければ切る
+// （確かめられるのは /proc のある Linux だけなので、ほかの OS では unix:// を使えない）
+use nostr_sdk::prelude::*;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Mutex, OnceLock};
+
+pub trait Transport: Sync {
+    // 受け持つ URL のスキーム
+    fn schemes(&self) -> &'static [&'static str];
+    // URL の形を確かめる
+    fn validate(&self, url: &Url) -> Result<(), Box<dyn std::error::Error>>;
+    // nostr-sdk に渡す ws:// / wss:// の URL を用意する（中継が必要ならここで始める）
+    fn dial(&self, url: &str) -> Result<String, Box<dyn std::error::Error>>;
+}
+
+// WebSocket（nostr-sdk がそのままつなぐ）
+struct WebSocketTransport;
+
+impl Transport for WebSocketTransport {
+    fn schemes(&self) -> &'static [&'static str] {
+        &["ws", "wss"]
+    }
+
+    fn validate(&self, _url: &Url) -> Result<(), Box<dyn std::error::Error>> {
+        Ok(())
+    }
+
+    fn dial(&self, url: &str) -> Result<String, Box<dyn std::error::Error>> {
+        Ok(url.to_string())
+    }
+}
+
+// Unix ドメインソケット（unix:///path/relay.sock。ソケットの先で WebSocket を話すリレーを想定する）
+struct UnixSocketTransport;
+
+// 中継中のソケット → (設定した unix:// の URL, nostr-sdk に渡した URL)（同じソケットには1つの中継を使い回す）
+static PROXIES: OnceLock<Mutex<HashMap<PathBuf, (String, String)>>> = OnceLock::new();
+
+fn socket_path(url: &Url) -> PathBuf {
+    PathBuf::from(url.path())
+}
+
+// 中継に接続してきた相手が、このプロセス自身の開いたソケットか
+// /proc/net/tcp から相手側（ローカルのポートが peer、接続先が中継の待ち受け）のソケットの inode を探し、
+// それがこのプロセスのファイルディスクリプタにあるかを見る
+#[cfg(target_os = "linux")]
+fn is_own_connection(peer: std::net::SocketAddr, listener: std::net::SocketAddr) -> bool {
+    let Ok(table) = std::fs::read_to_string("/proc/self/net/tcp") else {
+        return false;
+    };
+    let port = |address: &str| address.rsplit(':').next().and_then(|hex| u16::from_str_radix(hex, 16).ok());
+    let inode = table.lines().skip(1).find_map(|line| {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let matches = fields.len() > 9 && port(fields[1]) == Some(peer.port()) && port(fields[2]) == Some(listener.port());
+        matches.then(|| fields[9].to_string())
+    });
+    let Some(inode) = inode else {
+        return false;
+    };
+    let target = format!("socket:[{}]", inode);
+    let Ok(fds) = std::fs::read_dir("/proc/self/fd") else {
+        return false;
+    };
+    fds.filter_map(|fd| fd.ok())
+        .any(|fd| std::fs::read_link(fd.path()).is_ok_and(|link| link.to_string_lossy() == target))
+}
+
+#[cfg(not(target_os = "linux"))]
+fn is_own_connection(_peer: std::net::SocketAddr, _listener: std::net::SocketAddr) -> bool {
+    false
+}
+
+impl Transport for UnixSocketTransport {
+    fn schemes(&self) -> &'static [&'static str] {
+        &["unix"]
+    }
+
+    fn validate(&self, url: &Url) -> Result<(), Box<dyn std::error::Error>> {
+        if cfg!(not(target_os = "linux")) {
+            return Err(format!("{}: unix:// のリレーは Linux でだけ使えます（中継への接続元を確かめられないため）", url).into());
+        }
+        if url.path().is_empty() || url.path() == "/" {
+            return Err(format!("{}: ソケットのパスを指定してください（例: unix:///run/relay.sock）", url).into());
+        }
+        Ok(())
+    }
+
+    fn dial(&self, url: &str) -> Result<String, Box<dyn std::error::Error>> {
+        let path = socket_path(&Url::parse(url)?);
+        let mut proxies = PROXIES.get_or_init(Default::default).lock().map_err(|_| "中継の一覧を読めません")?;
+        if let Some((_, local)) = proxies.get(&path) {
+            return Ok(local.clone());
+        }
+
+        // ポートはプロセスごとに OS が選ぶ（決まったポートにすると、ほかのユーザーが先回りして待ち受けられる）
+        let listener = std::net::TcpListener::bind(("127.0.0.1", 0))?;
+        listener.set_nonblocking(true)?;
+        let listen_addr = listener.local_addr()?;
+        let local = format!("ws://{}", listen_addr);
+        let listener = tokio::net::TcpListener::from_std(listener)?;
+        let target = path.clone();
+        tokio::spawn(async move {
+            while let Ok((mut inbound, peer)) = listener.accept().await {
+                if !is_own_connection(peer, listen_addr) {
+                    continue;
+                }
+                let target = target.clone();
+                tokio::spawn(async move {
+                    if let Ok(mut outbound) = tokio::net::UnixStream::connect(&target).await {
+                        let _ = tokio::io::copy_bidirectional(&mut inbound, &mut outbound).await;
+                    }
+                });
+            }
+        });
+        proxies.insert(path, (url.to_string(), local.clone()));
+        Ok(local)
+    }
+}
+
+static TRANSPORTS: [&dyn Transport; 2] = [&WebSocketTransport, &UnixSocketTransport];
+
+fn transport_for(url: &Url) -> Result<&'static dyn Transport, Box<dyn std::error::Error>> {
+    TRANSPORTS
+        .iter()
+        .copied()
+        .find(|transport| transport.schemes().contains(&url.scheme()))
+        .ok_or_else(|| format!("{}: ws:// / wss:// / unix:// のいずれかで始まる必要があります", url).into())
+}
+
+// リレーの URL を確かめる
+pub fn validate(url: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let parsed = Url::parse(url).map_err(|e| format!("{}: {}", url, e))?;
+    transport_for(&parsed)?.validate(&parsed)
+}
+
+// nostr-sdk に渡した URL を、設定したリレーの URL に戻す（中継でなければそのまま。計測結果を設定した URL で記録するため）
+pub fn configured_url(dialed: &str) -> String {
+    if let Some(Ok(proxies)) = PROXIES.get().map(Mutex::lock) {
+        if let Some((configured, _)) = proxies.values().find(|(_, local)| local == dialed) {
+            return configured.clone();
+        }
+    }
+    dialed.to_string()
+}
+
+// リレーの URL を nostr-sdk に渡す URL にする（ws:// / wss:// はそのまま）
+pub fn dial(url: &str) -> Result<String, Box<dyn std::error::Error>> {
+    let parsed = Url::parse(url).map_err(|e| format!("{}: {}", url, e))?;
+    transport_for(&parsed)?.dial(url)
+}
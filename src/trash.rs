@@ -0,0 +1,109 @@
+// ゴミ箱 - フィードで削除・非表示にしたノートをすぐには消さず、鍵ファイルの隣の trash.json にしまっておく
+// 30日以内なら元に戻せる。自分のノートの削除要求（NIP-09 の kind 5）は、ゴミ箱を空にしたときに初めて送る
+use nostr_sdk::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use crate::accounts::keys_path;
+use crate::storage::{read_json, write_json};
+
+// 元に戻せる期間（日）
+pub const TRASH_RETENTION_DAYS: u64 = 30;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum TrashReason {
+    Deleted,   // 自分のノートを削除した（空にしたときに削除要求を送る）
+    Dismissed, // フィードから非表示にした（空にしても何も送らない）
+}
+
+impl TrashReason {
+    pub fn label(self) -> &'static str {
+        match self {
+            TrashReason::Deleted => "削除",
+            TrashReason::Dismissed => "非表示",
+        }
+    }
+}
+
+// ゴミ箱の1項目
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TrashItem {
+    pub event: Event,
+    pub reason: TrashReason,
+    pub trashed_at: u64, // ゴミ箱に入れた時刻（UNIX 秒）
+}
+
+impl TrashItem {
+    // まだ元に戻せるか
+    pub fn restorable(&self, now: Timestamp) -> bool {
+        now.as_u64().saturating_sub(self.trashed_at) < TRASH_RETENTION_DAYS * 24 * 60 * 60
+    }
+
+    // 元に戻せる残りの日数（切り上げ）
+    pub fn days_left(&self, now: Timestamp) -> u64 {
+        let elapsed = now.as_u64().saturating_sub(self.trashed_at);
+        (TRASH_RETENTION_DAYS * 24 * 60 * 60).saturating_sub(elapsed).div_ceil(24 * 60 * 60)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct Trash {
+    #[serde(default)]
+    pub items: Vec<TrashItem>, // 新しく入れた順
+}
+
+impl Trash {
+    // 使用中のアカウントの鍵ファイルの隣
+    fn path() -> Result<PathBuf, Box<dyn std::error::Error>> {
+        Ok(keys_path()?.with_file_name("trash.json"))
+    }
+
+    // trash.json を読み込む（未作成なら空）
+    pub fn load() -> Result<Self, Box<dyn std::error::Error>> {
+        let path = Self::path()?;
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        read_json(&path)
+    }
+
+    pub fn save(&self) -> Result<(), Box<dyn std::error::Error>> {
+        write_json(&Self::path()?, self)
+    }
+
+    pub fn contains(&self, id: &EventId) -> bool {
+        self.items.iter().any(|item| item.event.id == *id)
+    }
+
+    // ゴミ箱に入れる（すでに入っていれば false）
+    pub fn put(&mut self, event: Event, reason: TrashReason, now: Timestamp) -> bool {
+        if self.contains(&event.id) {
+            return false;
+        }
+        self.items.insert(0, TrashItem { event, reason, trashed_at: now.as_u64() });
+        true
+    }
+
+    // 元に戻す（30日を過ぎたものは戻せない）
+    pub fn restore(&mut self, index: usize, now: Timestamp) -> Result<TrashItem, Box<dyn std::error::Error>> {
+        let item = self.items.get(index).ok_or("項目がありません")?;
+        if !item.restorable(now) {
+            return Err(format!("ゴミ箱に入れてから{}日を過ぎたため元に戻せません", TRASH_RETENTION_DAYS).into());
+        }
+        Ok(self.items.remove(index))
+    }
+
+    // 空にしたときに送る削除要求の対象（自分のノートで削除したもの）
+    pub fn deleted_ids(&self) -> Vec<EventId> {
+        self.items.iter().filter(|item| item.reason == TrashReason::Deleted).map(|item| item.event.id).collect()
+    }
+}
+
+// ゴミ箱を空にするときに送る削除要求（NIP-09）を作成する
+pub fn deletion_builder(ids: &[EventId]) -> Result<EventBuilder, Box<dyn std::error::Error>> {
+    let tags = ids
+        .iter()
+        .map(|id| Tag::parse(vec!["e".to_string(), id.to_hex()]))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(EventBuilder::new(Kind::EventDeletion, "", tags))
+}
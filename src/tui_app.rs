@@ -2,41 +2,105 @@
 #![allow(dead_code)]
 
 use crossterm::{
-    event::{self, Event as CrosstermEvent, KeyCode, KeyEvent},
+    event::{self, Event as CrosstermEvent, KeyCode, KeyEvent, KeyModifiers},
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen, Clear, ClearType},
     cursor::{Hide, Show},
 };
 use nostr_sdk::prelude::*;
 use ratatui::{
-    backend::CrosstermBackend,
+    backend::{CrosstermBackend, TestBackend},
     layout::{Constraint, Direction, Layout, Rect, Alignment},
     style::{Color, Modifier, Style},
     text::{Line, Span},
-    widgets::{Block, Borders, BorderType, List, ListItem, ListState, Paragraph, Wrap},
+    widgets::{Block, Borders, BorderType, Gauge, List, ListItem, ListState, Paragraph, Sparkline, Wrap},
     Frame, Terminal,
 };
 use std::{
-    io::{self, Write},
+    borrow::Cow,
+    collections::{BTreeMap, HashMap, HashSet},
+    io,
+    str::FromStr,
+    sync::Arc,
     time::{Duration, Instant},
 };
-use crate::commands::{load_keys, load_relays};
-use chrono::{DateTime, Utc, FixedOffset};
+use crate::agent::agent_keys;
+use crate::accounts::{account_dir, active_account, list_accounts, set_account_override, DEFAULT_ACCOUNT};
+use crate::commands::{app_dir, check_relay_url, connect_measured, fetch_event_by_id, keychain_keys, format_jst, load_keys, load_relays, parse_jst_datetime, password_attempts_notice, read_only_public_key, recorded_public_key, save_relays, signer_keys, around_window, RelayConfig};
+use crate::nip65::{fetch_relay_list, parse_relay_list};
+use crate::transport::dial;
+use crate::scrapbook::Scrapbook;
+use crate::session::{sample_relays, SessionStats};
+use crate::signer::{external_signer, sign_event};
+use crate::storage::FileLock;
+use crate::streak::{day_key, notify_reminder, parse_reminder_time, reminder_due, PostingStreak};
+use crate::mutes::{mention_filters, thread_root, MuteList};
+use crate::glyphs::{detect_fallback, glyph, Glyph};
+use crate::notepad::{sync_notepad, NotePad, NotePadSync, NOTEPAD_PAGES};
+use crate::safety::{peer_handles, safety_number, KeyChange, KnownPeers};
+use crate::snooze::{SnoozeDuration, Snoozed};
+use crate::trash::{deletion_builder, Trash, TrashReason, TRASH_RETENTION_DAYS};
+use crate::animation::{watch_frame, zoom_rects, Activity, BootScreen, BootStep, HAPPY_MAC};
+use crate::article::{article_address, article_title, table_of_contents, Heading, ReadPositions};
+use crate::analytics::{aggregate, auto_bucket_secs, fetch_feed_counts, follow_interactions, interaction_filter, FeedCounts, FollowInteraction, NoteStats};
+use crate::compose::{parse_hashtag_list, reply_tags, thread_pubkeys, NoteDraft};
+use crate::capabilities::CapabilityCache;
+use crate::clock::{is_future, measure_clock_skew, skew_warning};
+use crate::columns::{Column, ColumnSource, COLUMN_FETCH_LIMIT, MIN_COLUMN_WIDTH};
+use crate::config::AppConfig;
+use crate::drafts::{sync_drafts, Draft, DraftStore};
+use crate::palette::{Palette, PaletteAction, PaletteItem};
+use crate::dm::{
+    build_message_events, fetch_gift_wrap_peers, fetch_messages, group_conversations, parse_signal, send_message_events,
+    signal_event, signal_filter, Conversation, DmScheme, DmSignal,
+};
+use crate::event_cache::{load_cached_events, save_cached_events, vacuum_caches};
+use crate::permalink::{copy_to_clipboard, permalink, PermalinkFormat};
+use crate::feed_kinds::{compact_content, ephemeral_feed_kinds, feed_kinds, is_ephemeral};
+use crate::explore::{histogram, kind_name, KindBucket};
+use crate::feed_sort::{plugin_scores, sort_events, FeedSort};
+use crate::filter_check::{query_checked, record_violations, retain_matching};
+use crate::verify::{retain_verified, verify_event};
+use crate::outbox::publish;
+use crate::preflight::{preflight_note, sign_checked, spawn_auto_auth};
+use crate::profiles::ProfileCache;
+use crate::history::{push_entry, History, Recall};
+use crate::lang::{event_language, fetch_preferred_variants, variant_ids};
+use crate::media::{parse_imeta, with_alt_placeholders};
+use crate::redact::Redactor;
+use crate::retention::RetentionPolicy;
+use tokio::sync::{broadcast, oneshot};
+use chrono::{DateTime, Utc, FixedOffset, NaiveTime};
 use unicode_width::UnicodeWidthStr;
 
 
-// Chicago風フォント表現用の定数
-const MAC_APPLE_LOGO: &str = "⌘"; // Commandキーのシンボル
-const MAC_FOLDER: &str = "📁";
-const MAC_DOCUMENT: &str = "📄";
-const MAC_NOTE: &str = "📝";
-const MAC_CHECKMARK: &str = "✓";
-const MAC_DIVIDER: &str = "━━━━━━━━━━━━━━━━━━━━━━━━";
-const MAC_HAPPY_MAC: &str = "🙂"; // ハッピーマック（実際のアイコンに近いもの）
-
-// 初代Mac風パターン（繰り返し使用可能）
-const MAC_PATTERN1: &str = "■ □ ■ □ ■ □ ■ □ ■ □ ■ □";
-const MAC_PATTERN2: &str = "□ ■ □ ■ □ ■ □ ■ □ ■ □ ■";
+// 入力中の通知を送る間隔と、受け取った通知を表示し続ける時間
+const TYPING_SEND_INTERVAL: Duration = Duration::from_secs(5);
+const TYPING_SHOW_DURATION: Duration = Duration::from_secs(8);
+// メンションなどのライブ購読の ID
+const LIVE_SUBSCRIPTION: &str = "tui-live";
+// キャッシュから保存期間を過ぎたイベントを取り除く間隔
+const CACHE_VACUUM_INTERVAL: Duration = Duration::from_secs(60 * 60);
+// リレーが未設定のときに使うリレー
+const TUI_DEFAULT_RELAYS: [&str; 2] = ["wss://relay-jp.nostr.wirednet.jp", "wss://yabu.me"];
+// 公開端末モードで伏せた公開鍵の代わりに表示する文字列
+const REDACTED_LABEL: &str = "（非表示）";
+// 一覧のプレビューで本文から読む上限（巨大な本文でも描画のたびに全体を処理しない）
+const PREVIEW_SOURCE_BYTES: usize = 4096;
+// 詳細表示で1行として扱う上限（改行のない巨大な本文も少しずつ表示する）
+const DETAIL_LINE_BYTES: usize = 2048;
+// 返信先の表示を PgUp/PgDn で動かす行数と、表示欄の高さの上限
+const REPLY_CONTEXT_SCROLL: u16 = 3;
+const REPLY_CONTEXT_MAX_HEIGHT: u16 = 10;
+// 処理中に右下に表示するズームする矩形の枠の大きさ
+const ZOOM_BOX_WIDTH: u16 = 12;
+const ZOOM_BOX_HEIGHT: u16 = 5;
+// セッションの統計のためにリレーの接続状態と転送量を調べる間隔
+const SESSION_SAMPLE_INTERVAL: Duration = Duration::from_secs(2);
+// 終了画面を表示しておく時間（キーを押せばすぐに閉じる）
+const SHUTDOWN_SCREEN_DURATION: Duration = Duration::from_millis(1500);
+// kind の分布を調べるときに集めるイベントの数
+const KIND_SAMPLE_LIMIT: usize = 500;
 
 // 電卓関連の定数
 const CALC_CLEAR: &str = "C";
@@ -47,6 +111,650 @@ const CALC_PLUS: &str = "+";
 const CALC_EQUAL: &str = "=";
 const CALC_DOT: &str = ".";
 
+// タブの数（イベントリスト / 投稿作成 / スクラップブック / DM）
+const TAB_COUNT: usize = 4;
+const TAB_SCRAPBOOK: usize = 2;
+const TAB_DM: usize = 3;
+// コマンドパレットに並べるタブの名前
+const TAB_NAMES: [&str; TAB_COUNT] = ["イベントリスト", "投稿作成", "スクラップブック", "DM"];
+
+// 長文とみなす文字数（これ以上の投稿には読了時間を表示）
+const LONG_CONTENT_CHARS: usize = 280;
+// 1分あたりに読める量の目安
+const READING_CJK_CHARS_PER_MIN: usize = 500;
+const READING_WORDS_PER_MIN: usize = 200;
+
+// 記事リストの並び順
+// 画面ごとのキーの割り当て（キーの処理も、? のヘルプと操作説明の行もこの表から作る）
+// keys が空の行は説明だけで、表の外（どの画面でも効くキーや文字入力）で処理する
+type KeyAction = fn(&mut App, KeyEvent);
+
+struct KeyBinding {
+    keys: &'static [KeyCode], // 押すキー
+    ctrl: bool,               // Ctrl と一緒に押すか
+    label: &'static str,      // ヘルプに出すキーの表記
+    description: &'static str,
+    action: KeyAction,
+}
+
+type Keymap = &'static [KeyBinding];
+
+const fn bind(keys: &'static [KeyCode], label: &'static str, description: &'static str, action: KeyAction) -> KeyBinding {
+    KeyBinding { keys, ctrl: false, label, description, action }
+}
+
+const fn bind_ctrl(keys: &'static [KeyCode], label: &'static str, description: &'static str, action: KeyAction) -> KeyBinding {
+    KeyBinding { keys, ctrl: true, label, description, action }
+}
+
+// 説明だけの行
+const fn note(label: &'static str, description: &'static str) -> KeyBinding {
+    KeyBinding { keys: &[], ctrl: false, label, description, action: |_, _| {} }
+}
+
+impl KeyBinding {
+    fn matches(&self, key: &KeyEvent) -> bool {
+        self.keys.contains(&key.code) && key.modifiers.contains(KeyModifiers::CONTROL) == self.ctrl
+    }
+}
+
+// 文字入力で書き込む文字（Ctrl と一緒に押したキーは除く）
+fn typed_char(key: KeyEvent) -> Option<char> {
+    match key.code {
+        KeyCode::Char(c) if !key.modifiers.contains(KeyModifiers::CONTROL) => Some(c),
+        _ => None,
+    }
+}
+
+// ↑/↓ でリストの選択を動かす（last は末尾の位置）
+fn step_selection(state: &mut ListState, key: KeyEvent, last: usize) {
+    let selected = state.selected().unwrap_or(0);
+    state.select(Some(if key.code == KeyCode::Up { selected.saturating_sub(1) } else { (selected + 1).min(last) }));
+}
+
+// イベントリスト・投稿作成・スクラップブック・DM のどのタブでも効くキー（各タブの表の後に探す）
+const TAB_KEYS: Keymap = &[
+    bind(&[KeyCode::Char('i')], "i", "投稿を作成", |app, _| app.toggle_input_mode()),
+    bind(&[KeyCode::Char('r')], "r", "更新", |app, _| app.refresh_events()),
+    bind(&[KeyCode::Char('o')], "o", "並び順の切り替え", |app, _| app.toggle_article_sort()),
+    bind(&[KeyCode::Char('v')], "v", "View メニュー（並び順を選択）", |app, _| app.open_view_menu()),
+    bind(&[KeyCode::Char('Z')], "Z", "リマインダー", |app, _| app.toggle_reminders()),
+    bind(&[KeyCode::Tab], "Tab", "タブの切り替え", |app, _| app.switch_tab((app.active_tab + 1) % TAB_COUNT)),
+    bind(&[KeyCode::Char('a')], "a", "About画面", |app, _| app.toggle_about()),
+    bind(&[KeyCode::Char('s')], "s", "電卓", |app, _| app.toggle_calculator()),
+    bind(&[KeyCode::Char('t')], "t", "ゴミ箱", |app, _| app.toggle_trash()),
+    bind(&[KeyCode::Char('n')], "n", "ノートパッド", |app, _| app.toggle_notepad()),
+    bind(&[KeyCode::Char(':')], ":", "コマンドライン", |app, _| app.command_input = Some(String::new())),
+    note("Ctrl-P", "コマンドパレット（操作・タブ・連絡先・検索を絞り込んで実行）"),
+    note("?", "ヘルプ"),
+    bind(&[KeyCode::Char('q')], "q", "終了", |app, _| app.quit_requested = true),
+];
+const FEED_KEYS: Keymap = &[
+    bind(&[KeyCode::Up, KeyCode::Down], "↑/↓", "イベントを選択", |app, key| {
+        if key.code == KeyCode::Up { app.previous() } else { app.next() }
+    }),
+    bind(&[KeyCode::PageUp, KeyCode::PageDown], "PgUp/PgDn", "ページ移動", |app, key| {
+        if key.code == KeyCode::PageUp { app.page_up() } else { app.page_down() }
+    }),
+    bind(&[KeyCode::Home, KeyCode::End], "Home/End", "先頭/末尾へ", |app, key| {
+        if key.code == KeyCode::Home { app.home() } else { app.end() }
+    }),
+    bind(&[KeyCode::Enter], "Enter", "詳細表示", |app, _| {
+        if !app.events.is_empty() {
+            app.toggle_detail_mode();
+        }
+    }),
+    bind(&[KeyCode::Char('g')], "g", "日時ジャンプ", |app, _| app.goto_input = Some(String::new())),
+    bind(&[KeyCode::Char('m')], "m", "スレッドをミュート", |app, _| app.mute_selected_thread()),
+    bind(&[KeyCode::Char('d')], "d", "自分の投稿を削除（ゴミ箱へ。空にしたときに削除要求を送る）", |app, _| app.trash_selected(TrashReason::Deleted)),
+    bind(&[KeyCode::Char('x')], "x", "投稿を非表示（ゴミ箱へ）", |app, _| app.trash_selected(TrashReason::Dismissed)),
+    bind(&[KeyCode::Char('z')], "z", "あとで読む（スヌーズ。時刻が来たらリマインダーに戻る）", |app, _| app.open_snooze_menu()),
+    bind(&[KeyCode::Char('p')], "p", "プロフィールカードの表示切り替え", |app, _| app.toggle_profile_cards()),
+    bind(&[KeyCode::Char('l')], "l", "表示するリレーを選択", |app, _| app.open_relay_picker()),
+    bind(&[KeyCode::Char('k')], "k", "kind の分布を調べる", |app, _| app.explore_kinds()),
+    bind(&[KeyCode::Char('y')], "y", "共有用リンクをコピー", |app, _| app.open_share_menu()),
+    bind(&[KeyCode::Char('u')], "u", "伏せ字を外す/戻す", |app, _| app.toggle_reveal()),
+    bind(&[KeyCode::Char('R')], "R", "選択中の投稿に返信", |app, _| app.start_reply()),
+];
+const DETAIL_KEYS: Keymap = &[
+    bind(&[KeyCode::Up, KeyCode::Down], "↑/↓", "スクロール", |app, key| {
+        if key.code == KeyCode::Up { app.detail_scroll_up() } else { app.detail_scroll_down() }
+    }),
+    bind(&[KeyCode::PageUp, KeyCode::PageDown], "PgUp/PgDn", "ページ移動", |app, key| {
+        if key.code == KeyCode::PageUp { app.detail_page_up() } else { app.detail_page_down() }
+    }),
+    // End は大きな値にしておく - 実際のスクロール最大値は表示時に制限される
+    bind(&[KeyCode::Home, KeyCode::End], "Home/End", "先頭/末尾へ", |app, key| {
+        app.detail_scroll = if key.code == KeyCode::Home { 0 } else { 1000 };
+    }),
+    bind(&[KeyCode::Char('t')], "t", "目次（長文記事）", |app, _| app.open_toc()),
+    bind(&[KeyCode::Char('['), KeyCode::Char(']')], "[/]", "前/次の見出しへ（長文記事）", |app, key| {
+        app.jump_section(key.code == KeyCode::Char(']'))
+    }),
+    bind(&[KeyCode::Char('y')], "y", "共有用リンクをコピー", |app, _| app.open_share_menu()),
+    bind(&[KeyCode::Char('u')], "u", "伏せ字を外す/戻す", |app, _| app.toggle_reveal()),
+    bind(&[KeyCode::Esc, KeyCode::Char('q')], "Esc/q", "戻る", |app, _| app.close_detail()),
+    note("?", "ヘルプ"),
+];
+const COMPOSE_KEYS: Keymap = &[
+    bind(&[KeyCode::Char('i')], "i", "入力を開始", |app, _| app.toggle_input_mode()),
+];
+const EDITING_KEYS: Keymap = &[
+    bind(&[KeyCode::Enter], "Enter", "送信", |app, _| app.send_message()),
+    bind(&[KeyCode::Tab], "Tab", "入力欄の切り替え（本文/件名/ハッシュタグ）", |app, _| app.compose_field = app.compose_field.next()),
+    bind(&[KeyCode::Backspace], "Backspace", "1文字削除", |app, _| {
+        app.compose_field_mut().pop();
+    }),
+    bind_ctrl(&[KeyCode::Char('s')], "Ctrl-S", "下書きを保存", |app, _| app.save_draft()),
+    bind(&[KeyCode::PageUp, KeyCode::PageDown], "PgUp/PgDn", "返信先をスクロール", |app, key| {
+        app.scroll_reply_context(key.code == KeyCode::PageDown)
+    }),
+    bind_ctrl(&[KeyCode::Char('x')], "Ctrl-X", "返信をやめる", |app, _| app.cancel_reply()),
+    bind(&[KeyCode::Esc], "Esc", "入力を終了", |app, _| app.toggle_input_mode()),
+    note("F1", "ヘルプ"),
+];
+const SCRAPBOOK_KEYS: Keymap = &[
+    bind(&[KeyCode::Char('/')], "/", "全文検索（#tag でタグ検索）", |app, _| app.scrap_searching = true),
+    bind(&[KeyCode::Up, KeyCode::Down], "↑/↓", "項目を選択", |app, key| {
+        if key.code == KeyCode::Up { app.scrap_previous() } else { app.scrap_next() }
+    }),
+];
+// 検索語の入力はすべて表の外（履歴つきの1行編集）で処理する
+const SCRAP_SEARCH_KEYS: Keymap = &[
+    note("Enter/Esc", "検索語の入力を終了"),
+    note("Backspace", "1文字削除"),
+    note("↑/↓", "履歴を呼び出す"),
+    note("Ctrl-R", "履歴を検索"),
+    note("F1", "ヘルプ"),
+];
+const DM_KEYS: Keymap = &[
+    bind(&[KeyCode::Up, KeyCode::Down], "↑/↓", "会話を選択", |app, key| {
+        let last = app.dm_conversations.len().saturating_sub(1);
+        step_selection(&mut app.dm_state, key, last);
+        app.queue_read_receipt();
+    }),
+    bind(&[KeyCode::Enter, KeyCode::Char('i')], "Enter/i", "メッセージを入力", |app, _| app.start_dm_compose()),
+    bind(&[KeyCode::Char('r')], "r", "更新", |app, _| app.refresh_dms()),
+    note(":dm <npub|別名>", "新しい会話"),
+    note(":receipts", "この相手との入力中・既読の通知を切り替え"),
+];
+const DM_EDITING_KEYS: Keymap = &[
+    bind(&[KeyCode::Enter], "Enter", "送信（旧方式のときは確認後にもう一度）", |app, _| app.submit_dm()),
+    bind(&[KeyCode::Backspace], "Backspace", "1文字削除", |app, _| {
+        app.dm_input.pop();
+    }),
+    bind(&[KeyCode::Esc], "Esc", "入力を終了", |app, _| {
+        app.dm_editing = false;
+        app.dm_fallback_warned = false;
+    }),
+    note("F1", "ヘルプ"),
+];
+const RELAY_PICKER_KEYS: Keymap = &[
+    bind(&[KeyCode::Up, KeyCode::Down], "↑/↓", "リレーを選択", |app, key| {
+        if let Some(state) = app.relay_picker.as_mut() {
+            step_selection(state, key, app.relay_choices.len());
+        }
+    }),
+    bind(&[KeyCode::Enter], "Enter", "このリレーのフィードを表示", |app, _| app.choose_feed_relay()),
+    bind(&[KeyCode::Esc, KeyCode::Char('q')], "Esc/q", "取消", |app, _| app.relay_picker = None),
+    note("?", "ヘルプ"),
+];
+const ACCOUNT_PICKER_KEYS: Keymap = &[
+    bind(&[KeyCode::Up, KeyCode::Down], "↑/↓", "アカウントを選択", |app, key| {
+        if let Some(state) = app.account_picker.as_mut() {
+            step_selection(state, key, app.account_choices.len().saturating_sub(1));
+        }
+    }),
+    bind(&[KeyCode::Enter], "Enter", "このアカウントに切り替え", |app, _| app.choose_account()),
+    bind(&[KeyCode::Esc, KeyCode::Char('q')], "Esc/q", "取消", |app, _| app.account_picker = None),
+    note("?", "ヘルプ"),
+];
+const DRAFT_PICKER_KEYS: Keymap = &[
+    bind(&[KeyCode::Up, KeyCode::Down], "↑/↓", "下書きを選択", |app, key| {
+        if let Some(state) = app.draft_picker.as_mut() {
+            step_selection(state, key, app.draft_choices.len().saturating_sub(1));
+        }
+    }),
+    bind(&[KeyCode::Enter], "Enter", "この下書きの続きを書く", |app, _| app.choose_draft()),
+    bind(&[KeyCode::Char('d')], "d", "下書きを削除", |app, _| app.delete_selected_draft()),
+    bind(&[KeyCode::Esc, KeyCode::Char('q')], "Esc/q", "閉じる", |app, _| app.draft_picker = None),
+    note("?", "ヘルプ"),
+];
+const PALETTE_KEYS: Keymap = &[
+    note("文字", "絞り込み（入力した文字を順に含む項目）"),
+    bind(&[KeyCode::Up, KeyCode::Down], "↑/↓", "項目を選択", |app, key| {
+        if let Some(palette) = app.palette.as_mut() {
+            if key.code == KeyCode::Up { palette.select_previous() } else { palette.select_next() }
+        }
+    }),
+    bind(&[KeyCode::Enter], "Enter", "実行", |app, _| app.run_selected_palette_item()),
+    bind(&[KeyCode::Backspace], "Backspace", "1文字削除", |app, _| {
+        if let Some(palette) = app.palette.as_mut() {
+            palette.pop();
+        }
+    }),
+    bind(&[KeyCode::Esc], "Esc", "閉じる", |app, _| app.palette = None),
+    note("F1", "ヘルプ"),
+];
+const KIND_EXPLORER_KEYS: Keymap = &[
+    bind(&[KeyCode::Up, KeyCode::Down], "↑/↓", "kind を選択", |app, key| {
+        if let Some(state) = app.kind_explorer.as_mut() {
+            step_selection(state, key, app.kind_buckets.len());
+        }
+    }),
+    bind(&[KeyCode::Enter], "Enter", "この kind のイベントをフィードに表示", |app, _| app.choose_feed_kind()),
+    bind(&[KeyCode::Esc, KeyCode::Char('q')], "Esc/q", "閉じる", |app, _| app.kind_explorer = None),
+    note("?", "ヘルプ"),
+];
+const VIEW_MENU_KEYS: Keymap = &[
+    bind(&[KeyCode::Up, KeyCode::Down], "↑/↓", "並び順を選択", |app, key| {
+        if let Some(state) = app.view_menu.as_mut() {
+            step_selection(state, key, FeedSort::ALL.len() - 1);
+        }
+    }),
+    bind(&[KeyCode::Enter], "Enter", "この順に並べ替える", |app, _| app.choose_article_sort()),
+    bind(&[KeyCode::Esc, KeyCode::Char('q'), KeyCode::Char('v')], "Esc/v", "閉じる", |app, _| app.view_menu = None),
+    note("?", "ヘルプ"),
+];
+const SHARE_KEYS: Keymap = &[
+    bind(&[KeyCode::Up, KeyCode::Down], "↑/↓", "形式を選択", |app, key| {
+        if let Some(state) = app.share_menu.as_mut() {
+            step_selection(state, key, app.share_links.len().saturating_sub(1));
+        }
+    }),
+    bind(&[KeyCode::Enter], "Enter", "クリップボードにコピー", |app, _| app.copy_share_link()),
+    bind(&[KeyCode::Esc, KeyCode::Char('q'), KeyCode::Char('y')], "Esc/y", "閉じる", |app, _| app.share_menu = None),
+    note("?", "ヘルプ"),
+];
+const TOC_KEYS: Keymap = &[
+    bind(&[KeyCode::Up, KeyCode::Down], "↑/↓", "見出しを選択", |app, key| {
+        let last = app.article_headings().len().saturating_sub(1);
+        if let Some(state) = app.toc_state.as_mut() {
+            step_selection(state, key, last);
+        }
+    }),
+    bind(&[KeyCode::Enter], "Enter", "この見出しへ移動", |app, _| app.choose_heading()),
+    bind(&[KeyCode::Esc, KeyCode::Char('q'), KeyCode::Char('t')], "Esc/t", "閉じる", |app, _| app.toc_state = None),
+    note("?", "ヘルプ"),
+];
+const GOTO_KEYS: Keymap = &[
+    bind(&[KeyCode::Enter], "Enter", "移動", |app, _| app.submit_goto()),
+    bind(&[KeyCode::Backspace], "Backspace", "1文字削除", |app, _| {
+        if let Some(input) = app.goto_input.as_mut() {
+            input.pop();
+        }
+    }),
+    bind(&[KeyCode::Esc], "Esc", "取消", |app, _| app.goto_input = None),
+    note("F1", "ヘルプ"),
+];
+const CALCULATOR_KEYS: Keymap = &[
+    bind(
+        &[
+            KeyCode::Char('0'), KeyCode::Char('1'), KeyCode::Char('2'), KeyCode::Char('3'), KeyCode::Char('4'),
+            KeyCode::Char('5'), KeyCode::Char('6'), KeyCode::Char('7'), KeyCode::Char('8'), KeyCode::Char('9'),
+            KeyCode::Char('.'),
+        ],
+        "0-9 .",
+        "数字の入力",
+        |app, key| match key.code {
+            KeyCode::Char('.') => app.calculator_input_dot(),
+            KeyCode::Char(digit) => app.calculator_input_digit(digit),
+            _ => {}
+        },
+    ),
+    bind(&[KeyCode::Char('+'), KeyCode::Char('-'), KeyCode::Char('*'), KeyCode::Char('/')], "+ - * /", "演算子", |app, key| {
+        if let KeyCode::Char(operator) = key.code {
+            app.calculator_operator(operator);
+        }
+    }),
+    bind(&[KeyCode::Char('='), KeyCode::Enter], "= / Enter", "計算", |app, _| app.calculator_equals()),
+    bind(&[KeyCode::Char('c')], "c", "クリア", |app, _| app.calculator_clear()),
+    bind(&[KeyCode::Esc, KeyCode::Char('q')], "Esc/q", "閉じる", |app, _| app.show_calculator = false),
+    note("?", "ヘルプ"),
+];
+const TRASH_KEYS: Keymap = &[
+    bind(&[KeyCode::Up, KeyCode::Down], "↑/↓", "項目を選択", |app, key| {
+        let last = app.trash.items.len().saturating_sub(1);
+        step_selection(&mut app.trash_state, key, last);
+    }),
+    bind(&[KeyCode::Char('r'), KeyCode::Enter], "r/Enter", "元に戻す（30日以内）", |app, _| app.restore_trash_item()),
+    bind(&[KeyCode::Char('E')], "E", "ゴミ箱を空にする…", |app, _| app.confirm_empty_trash()),
+    bind(&[KeyCode::Esc, KeyCode::Char('q'), KeyCode::Char('t')], "Esc/q/t", "閉じる", |app, _| app.show_trash = false),
+    note("?", "ヘルプ"),
+];
+const SNOOZE_KEYS: Keymap = &[
+    bind(&[KeyCode::Up, KeyCode::Down], "↑/↓", "戻す時刻を選択", |app, key| {
+        if let Some(state) = app.snooze_menu.as_mut() {
+            step_selection(state, key, SnoozeDuration::ALL.len() - 1);
+        }
+    }),
+    bind(&[KeyCode::Enter], "Enter", "スヌーズ", |app, _| app.choose_snooze()),
+    bind(&[KeyCode::Esc, KeyCode::Char('q'), KeyCode::Char('z')], "Esc/z", "閉じる", |app, _| app.snooze_menu = None),
+    note("?", "ヘルプ"),
+];
+const REMINDERS_KEYS: Keymap = &[
+    bind(&[KeyCode::Up, KeyCode::Down], "↑/↓", "項目を選択", |app, key| {
+        let last = app.snoozed.items.len().saturating_sub(1);
+        step_selection(&mut app.reminders_state, key, last);
+    }),
+    bind(&[KeyCode::Enter, KeyCode::Char('f')], "Enter/f", "フィードに戻す", |app, _| app.unsnooze_reminder()),
+    bind(&[KeyCode::Char('R')], "R", "返信する", |app, _| app.reply_to_reminder()),
+    bind(&[KeyCode::Char('x')], "x", "片付ける（フィードにも戻さない）", |app, _| app.dismiss_reminder()),
+    bind(&[KeyCode::Esc, KeyCode::Char('q'), KeyCode::Char('Z')], "Esc/q/Z", "閉じる", |app, _| app.show_reminders = false),
+    note("?", "ヘルプ"),
+];
+const NOTEPAD_KEYS: Keymap = &[
+    note("文字キー", "書き込む"),
+    bind(&[KeyCode::Enter], "Enter", "改行", |app, _| app.notepad.page_mut(app.notepad_page).push('\n')),
+    bind(&[KeyCode::Backspace], "Backspace", "1文字削除", |app, _| {
+        app.notepad.page_mut(app.notepad_page).pop();
+    }),
+    bind(&[KeyCode::PageUp, KeyCode::PageDown], "PgUp/PgDn", "前/次のページをめくる", |app, key| {
+        app.flip_notepad_page(key.code == KeyCode::PageDown)
+    }),
+    bind(&[KeyCode::Esc], "Esc", "保存して閉じる", |app, _| app.toggle_notepad()),
+    note("F1", "ヘルプ"),
+];
+const EMPTY_TRASH_KEYS: Keymap = &[
+    bind(&[KeyCode::Enter, KeyCode::Char('y')], "Enter/y", "OK（空にする）", |app, _| app.start_empty_trash()),
+    bind(&[KeyCode::Esc, KeyCode::Char('n'), KeyCode::Char('q')], "Esc/n", "キャンセル", |app, _| app.trash_confirm = false),
+];
+// コマンドラインの入力はすべて表の外（履歴つきの1行編集）で処理する
+const COMMAND_KEYS: Keymap = &[
+    note("Enter", "実行"),
+    note("↑/↓", "履歴を呼び出す"),
+    note("Ctrl-R", "履歴を検索（続けて押すとさらに古い一致）"),
+    note("Esc", "取消"),
+    note("F1", "ヘルプ"),
+    note(":r", "更新"),
+    note(":goto <日時>", "日時ジャンプ"),
+    note(":search <語>", "スクラップブックを検索"),
+    note(":sort [順]", "並び順の切り替え（newest / oldest / longest / most-zapped / most-replied / plugin）"),
+    note(":mute", "スレッドをミュート"),
+    note(":trash", "ゴミ箱を開く"),
+    note(":notepad", "ノートパッドを開く"),
+    note(":reminders", "リマインダーを開く"),
+    note(":cards", "プロフィールカードの表示切り替え"),
+    note(":dm <npub|別名>", "DMの会話を開く"),
+    note(":receipts", "選択中の相手との入力中・既読の通知を切り替え"),
+    note(":relay [URL]", "指定したリレーのフィードを表示（省略ですべてのリレー）"),
+    note(":kinds", "kind の分布を調べる"),
+    note(":account [名前]", "アカウントの切り替え（省略で一覧から選ぶ）"),
+    note(":stats", "セッションの統計を表示する拡張ステータスバーの切り替え"),
+    note(":session stats|reset", "セッションの統計を表示 / 数え直す"),
+    note(":drafts", "下書きの一覧（Enter で続きを書く）"),
+    note(":drafts sync", "手元とリレーの下書きを同期"),
+    note(":open <naddr>", "長文記事を開く"),
+    note(":columns [add <列>|remove]", "複数列のレイアウトの切り替え / 列の追加・削除"),
+    note(":help", "ヘルプ"),
+    note(":q", "終了"),
+];
+const COLUMN_KEYS: Keymap = &[
+    bind(&[KeyCode::Left, KeyCode::Char('h'), KeyCode::Right, KeyCode::Char('l')], "←/→ (h/l)", "列を選択", |app, key| {
+        app.column_focus = if matches!(key.code, KeyCode::Left | KeyCode::Char('h')) {
+            app.column_focus.saturating_sub(1)
+        } else {
+            (app.column_focus + 1).min(app.columns.len().saturating_sub(1))
+        };
+    }),
+    bind(&[KeyCode::Up, KeyCode::Char('k'), KeyCode::Down, KeyCode::Char('j')], "↑/↓ (k/j)", "選択中の列をスクロール", |app, key| {
+        if let Some(column) = app.columns.get_mut(app.column_focus) {
+            if matches!(key.code, KeyCode::Up | KeyCode::Char('k')) { column.select_previous() } else { column.select_next() }
+        }
+    }),
+    bind(&[KeyCode::Char('r')], "r", "すべての列を更新", |app, _| {
+        app.status = "列を更新中...".to_string();
+        app.columns_requested = true;
+    }),
+    note(":columns add <列>", "列を追加（home / mentions / #タグ / search:語 / from:npub）"),
+    note(":columns remove", "選択中の列を削除"),
+    bind(&[KeyCode::Esc], "Esc/:columns", "1列の表示に戻る", |app, _| app.set_column_mode(false)),
+];
+const ABOUT_KEYS: Keymap = &[
+    bind(&[KeyCode::Esc, KeyCode::Char('q')], "Esc/q", "閉じる", |app, _| app.show_about = false),
+    note("?", "ヘルプ"),
+];
+
+// キー操作の対象になっている画面（キーの処理とヘルプは App::key_mode で同じ画面を選ぶ）
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum KeyMode {
+    Palette,
+    Notepad,
+    Calculator,
+    EmptyTrash,
+    Trash,
+    Reminders,
+    Snooze,
+    Share,
+    RelayPicker,
+    AccountPicker,
+    DraftPicker,
+    Toc,
+    ViewMenu,
+    KindExplorer,
+    CommandLine,
+    Goto,
+    ScrapSearch,
+    DmEditing,
+    About,
+    Editing,
+    Detail,
+    Scrapbook,
+    Dm,
+    Columns,
+    Feed,
+    Compose,
+}
+
+impl KeyMode {
+    // ヘルプの見出しに出す画面の名前
+    fn title(self) -> &'static str {
+        match self {
+            KeyMode::Palette => "コマンドパレット",
+            KeyMode::Notepad => "ノートパッド",
+            KeyMode::Calculator => "電卓",
+            KeyMode::EmptyTrash => "ゴミ箱を空にする",
+            KeyMode::Trash => "ゴミ箱",
+            KeyMode::Reminders => "リマインダー",
+            KeyMode::Snooze => "スヌーズ",
+            KeyMode::Share => "共有",
+            KeyMode::RelayPicker => "リレーの選択",
+            KeyMode::AccountPicker => "アカウントの選択",
+            KeyMode::DraftPicker => "下書き",
+            KeyMode::Toc => "目次",
+            KeyMode::ViewMenu => "View メニュー",
+            KeyMode::KindExplorer => "kind の分布",
+            KeyMode::CommandLine => "コマンドライン",
+            KeyMode::Goto => "日時ジャンプ",
+            KeyMode::ScrapSearch => "スクラップブックの検索",
+            KeyMode::DmEditing => "DMの入力",
+            KeyMode::About => "About",
+            KeyMode::Editing => "投稿の入力",
+            KeyMode::Detail => "詳細表示",
+            KeyMode::Scrapbook => "スクラップブック",
+            KeyMode::Dm => "DM",
+            KeyMode::Columns => "複数列",
+            KeyMode::Feed => "イベントリスト",
+            KeyMode::Compose => "投稿作成",
+        }
+    }
+
+    // この画面でキーを探す表（先に並べた表の割り当てを優先する）
+    fn keymaps(self) -> &'static [Keymap] {
+        match self {
+            KeyMode::Palette => &[PALETTE_KEYS],
+            KeyMode::Notepad => &[NOTEPAD_KEYS],
+            KeyMode::Calculator => &[CALCULATOR_KEYS],
+            KeyMode::EmptyTrash => &[EMPTY_TRASH_KEYS],
+            KeyMode::Trash => &[TRASH_KEYS],
+            KeyMode::Reminders => &[REMINDERS_KEYS],
+            KeyMode::Snooze => &[SNOOZE_KEYS],
+            KeyMode::Share => &[SHARE_KEYS],
+            KeyMode::RelayPicker => &[RELAY_PICKER_KEYS],
+            KeyMode::AccountPicker => &[ACCOUNT_PICKER_KEYS],
+            KeyMode::DraftPicker => &[DRAFT_PICKER_KEYS],
+            KeyMode::Toc => &[TOC_KEYS],
+            KeyMode::ViewMenu => &[VIEW_MENU_KEYS],
+            KeyMode::KindExplorer => &[KIND_EXPLORER_KEYS],
+            KeyMode::CommandLine => &[COMMAND_KEYS],
+            KeyMode::Goto => &[GOTO_KEYS],
+            KeyMode::ScrapSearch => &[SCRAP_SEARCH_KEYS],
+            KeyMode::DmEditing => &[DM_EDITING_KEYS],
+            KeyMode::About => &[ABOUT_KEYS],
+            KeyMode::Editing => &[EDITING_KEYS],
+            KeyMode::Detail => &[DETAIL_KEYS],
+            KeyMode::Scrapbook => &[SCRAPBOOK_KEYS, TAB_KEYS],
+            KeyMode::Dm => &[DM_KEYS, TAB_KEYS],
+            KeyMode::Columns => &[COLUMN_KEYS, TAB_KEYS],
+            KeyMode::Feed => &[FEED_KEYS, TAB_KEYS],
+            KeyMode::Compose => &[COMPOSE_KEYS, TAB_KEYS],
+        }
+    }
+
+    // この画面で効く割り当て（先の表で同じキーに割り当て済みの行は押しても届かないので除く）
+    fn bindings(self) -> Vec<&'static KeyBinding> {
+        let mut bindings: Vec<&'static KeyBinding> = Vec::new();
+        for binding in self.keymaps().iter().flat_map(|keymap| keymap.iter()) {
+            let shadowed = !binding.keys.is_empty()
+                && binding
+                    .keys
+                    .iter()
+                    .all(|code| bindings.iter().any(|earlier| earlier.ctrl == binding.ctrl && earlier.keys.contains(code)));
+            if !shadowed {
+                bindings.push(binding);
+            }
+        }
+        bindings
+    }
+
+    // 表にないキーを文字入力として受け取る画面の処理（この画面では ? もヘルプではなく文字になる）
+    fn text_input(self) -> Option<KeyAction> {
+        match self {
+            KeyMode::Palette => Some(|app, key| {
+                if let (Some(c), Some(palette)) = (typed_char(key), app.palette.as_mut()) {
+                    palette.push(c);
+                }
+            }),
+            KeyMode::Notepad => Some(|app, key| {
+                if let Some(c) = typed_char(key) {
+                    app.notepad.page_mut(app.notepad_page).push(c);
+                }
+            }),
+            KeyMode::CommandLine => Some(App::edit_command_line),
+            KeyMode::Goto => Some(|app, key| {
+                if let (Some(c), Some(input)) = (typed_char(key), app.goto_input.as_mut()) {
+                    input.push(c);
+                }
+            }),
+            KeyMode::ScrapSearch => Some(App::edit_scrap_query),
+            KeyMode::DmEditing => Some(|app, key| {
+                if let Some(c) = typed_char(key) {
+                    app.dm_input.push(c);
+                    app.queue_typing();
+                }
+            }),
+            KeyMode::Editing => Some(|app, key| {
+                if let Some(c) = typed_char(key) {
+                    app.compose_field_mut().push(c);
+                }
+            }),
+            _ => None,
+        }
+    }
+}
+
+// 操作説明の1行を作る（例: "↑/↓: スクロール | Esc: 戻る"）
+fn keymap_hint(keys: Keymap) -> String {
+    keys.iter()
+        .map(|binding| format!("{}: {}", binding.label, binding.description))
+        .collect::<Vec<_>>()
+        .join(" | ")
+}
+
+// 本文の統計情報
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ContentStats {
+    pub words: usize,
+    pub chars: usize,
+    pub reading_minutes: usize,
+}
+
+// 詳細表示中の本文（開いたときに1度だけ作り、描画では見えている行だけを借りて使う）
+pub struct DetailContent {
+    pub event_id: EventId,
+    pub text: String,
+    // 各行の範囲（バイト位置）と、長い行を分けた続きの行か
+    pub lines: Vec<(usize, usize, bool)>,
+    pub stats: ContentStats,
+    pub headings: Vec<Heading>,
+}
+
+impl DetailContent {
+    // 改行で行に分け、改行のない長い行は DETAIL_LINE_BYTES ごとに分ける
+    fn new(event_id: EventId, text: String, is_article: bool) -> Self {
+        let mut lines = Vec::new();
+        let mut start = 0;
+        for line in text.split('\n') {
+            let end = start + line.len();
+            let mut piece = start;
+            while end - piece > DETAIL_LINE_BYTES {
+                let cut = piece + truncate_bytes(&text[piece..end], DETAIL_LINE_BYTES).len().max(1);
+                lines.push((piece, cut, piece != start));
+                piece = cut;
+            }
+            lines.push((piece, end, piece != start));
+            start = end + 1;
+        }
+
+        let headings = if is_article {
+            table_of_contents(
+                lines
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, (_, _, continued))| !continued)
+                    .map(|(i, (s, e, _))| (i, &text[*s..*e])),
+            )
+        } else {
+            Vec::new()
+        };
+        let stats = content_stats(&text);
+        Self { event_id, text, lines, stats, headings }
+    }
+
+    pub fn line_count(&self) -> usize {
+        self.lines.len()
+    }
+
+    pub fn line(&self, index: usize) -> &str {
+        self.lines.get(index).map_or("", |(start, end, _)| &self.text[*start..*end])
+    }
+}
+
+// 投稿作成画面の入力欄（編集中に Tab で切り替える）
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ComposeField {
+    Body,
+    Subject,
+    Hashtags,
+}
+
+impl ComposeField {
+    pub fn next(self) -> Self {
+        match self {
+            ComposeField::Body => ComposeField::Subject,
+            ComposeField::Subject => ComposeField::Hashtags,
+            ComposeField::Hashtags => ComposeField::Body,
+        }
+    }
+}
+
+// 返信先（返信する投稿と、それがスレッドの途中ならスレッドの最初の投稿）
+#[derive(Debug, Clone)]
+pub struct ReplyContext {
+    pub parent: Event,
+    pub root: Option<Event>,
+}
+
 // InputModeにPartialEqを追加
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum InputMode {
@@ -64,7 +772,16 @@ pub struct App {
     pub client: Option<Client>,
     pub my_public_key: Option<String>,
     pub keys: Option<Keys>,
-    pub message_to_send: Option<String>,
+    pub message_to_send: Option<NoteDraft>,
+    pub compose_field: ComposeField, // 入力中の投稿作成欄
+    pub compose_subject: String,     // 件名（NIP-14）
+    pub compose_hashtags: String,    // ハッシュタグ（空白・カンマ区切り）
+    pub reply_to: Option<ReplyContext>, // 返信先（投稿作成画面の入力欄の上に表示する）
+    pub reply_context_scroll: u16,   // 返信先の表示のスクロール位置（入力欄とは別に動かす）
+    pub reply_root_requested: Option<EventId>, // 取得するスレッドの最初の投稿（表示中のイベントになかったもの）
+    pub message_reply: Option<Event>, // 送信する投稿の返信先
+    pub auto_hashtags: bool,         // 本文中の #ハッシュタグ を t タグにするか
+    pub aliases: BTreeMap<String, String>, // メンション用の別名 → npub
     pub detail_mode: bool,
     pub detail_scroll: u16, // 詳細表示のスクロール位置
     pub show_about: bool,   // About画面表示フラグ
@@ -73,7 +790,121 @@ pub struct App {
     pub calculator_value: f64,       // 計算中の値
     pub calculator_op: Option<char>, // 演算子（+,-,*,/）
     pub calculator_new_input: bool,  // 新しい入力開始フラグ
-    
+    pub trash: Trash,                // ゴミ箱（削除・非表示にしたノート）
+    pub show_trash: bool,            // ゴミ箱表示フラグ
+    pub trash_state: ListState,      // ゴミ箱の選択位置
+    pub trash_confirm: bool,         // 「ゴミ箱を空にする…」の確認ダイアログ表示中か
+    pub empty_trash_requested: bool, // ゴミ箱を空にする要求（削除要求を送る）
+    pub snoozed: Snoozed,            // スヌーズ中・リマインダーに戻ったノート
+    pub snooze_menu: Option<ListState>, // スヌーズする時刻の選択（表示中のみSome）
+    pub show_reminders: bool,        // リマインダー表示フラグ
+    pub reminders_state: ListState,  // リマインダーの選択位置
+    pub reminders_announced: usize,  // 知らせたリマインダーの件数（増えたときだけ知らせる）
+    pub notepad: NotePad,            // ノートパッド（8ページ）
+    pub show_notepad: bool,          // ノートパッド表示フラグ
+    pub notepad_page: usize,         // 開いているページ（0〜7）
+    pub sync_notepad: bool,          // ノートパッドをリレーと同期するか
+    pub notepad_sync_requested: bool, // ノートパッドの同期の要求
+    pub relay_bootstrap_requested: bool, // relays.json がないときに、自分の NIP-65 のリレーの一覧から設定する要求
+    pub refresh_requested: bool,     // イベント更新要求フラグ
+    pub goto_input: Option<String>,  // 日時ジャンプの入力欄（表示中のみSome）
+    pub pending_goto: Option<Timestamp>, // ジャンプ先の日時
+    pub article_sort: FeedSort,      // リストの並び順
+    pub scrapbook: Scrapbook,        // スクラップブック
+    pub scrap_state: ListState,      // スクラップブックの選択位置
+    pub scrap_query: String,         // スクラップブックの検索語
+    pub scrap_searching: bool,       // 検索語の入力中フラグ
+    pub mutes: MuteList,             // ミュート中のスレッド
+    pub notifications: Option<broadcast::Receiver<RelayPoolNotification>>, // ライブ購読の受信口
+    pub resubscribe_requested: bool, // メンション購読の張り直し要求
+    pub session_started: Timestamp,  // 起動時刻（これ以降のメンションだけ通知する）
+    pub mentions_received: usize,    // 通知したメンション数
+    pub muted_dropped: usize,        // ミュートにより破棄したメンション数
+    pub pending_stats: Option<EventId>, // 反応の集計を取得するイベント
+    pub detail_stats: Option<NoteStats>, // 詳細表示中のイベントの反応集計
+    pub detail_follows: Vec<FollowInteraction>, // 詳細表示中のイベントに反応したフォロー中の人
+    pub preferred_language: Option<String>, // 翻訳版があれば優先して表示する言語
+    pub translations: HashMap<EventId, nostr_sdk::Event>, // 元の投稿ID → 優先言語の翻訳版
+    pub show_welcome: bool,          // 初回起動時のようこそ画面
+    pub show_help: bool,             // ヘルプ表示フラグ
+    pub quit_requested: bool,        // キー操作で終了を選んだ（handle_key_event が false を返す）
+    pub command_input: Option<String>, // : コマンドラインの入力（表示中のみSome）
+    pub history: History,            // コマンドラインと検索の履歴
+    pub command_recall: Recall,      // コマンドラインの履歴呼び出し状態
+    pub scrap_recall: Recall,        // 検索欄の履歴呼び出し状態
+    pub profile_cards: bool,         // 投稿者ごとのプロフィールカード表示
+    pub profiles: ProfileCache,      // 投稿者のプロフィールとフォロワーの記録
+    pub profiles_requested: bool,    // プロフィールの取得要求
+    pub public_terminal: bool,       // 公開端末モード（閲覧のみ、鍵を読み込まない）
+    pub redacted_pubkeys: Vec<XOnlyPublicKey>, // 公開端末モードで伏せる公開鍵
+    pub relays_ready: Option<oneshot::Receiver<()>>, // 裏で行うリレー接続の完了通知（接続中のみSome）
+    pub boot: Option<BootScreen>,    // 起動画面（表示中のみSome）
+    pub dm_conversations: Vec<Conversation>, // DMの会話（新しい順）
+    pub dm_state: ListState,         // 選択中の会話
+    pub dm_input: String,            // DMの入力欄
+    pub dm_editing: bool,            // DMの入力中フラグ
+    pub dm_fallback_warned: bool,    // 旧方式で送る警告を表示済みか（もう一度Enterで送信）
+    pub dm_refresh_requested: bool,  // DMの取得要求
+    pub dm_to_send: Option<(XOnlyPublicKey, String, DmScheme)>, // 送信するDM
+    pub dm_receipts: bool,           // 入力中・既読の通知を使うか（既定は無効）
+    pub dm_receipt_peers: Vec<XOnlyPublicKey>, // 通知に対応している相手
+    pub dm_typing: HashMap<XOnlyPublicKey, Instant>, // 相手から入力中の通知が届いた時刻
+    pub dm_read_until: HashMap<XOnlyPublicKey, Timestamp>, // 相手が既読にしたメッセージの時刻
+    pub dm_read_sent: HashMap<XOnlyPublicKey, Timestamp>,  // 相手に既読を通知したメッセージの時刻
+    pub dm_key_changes: HashMap<XOnlyPublicKey, Vec<KeyChange>>, // 別名や NIP-05 が前と別の鍵を指している相手
+    pub dm_typing_sent: Option<Instant>, // 最後に入力中を通知した時刻
+    pub dm_signals: Vec<(XOnlyPublicKey, DmSignal)>, // 送信待ちの通知
+    pub feed_relay: Option<String>,  // フィードを絞り込むリレー（None ならすべてのリレー）
+    pub relay_choices: Vec<String>,  // リレーの選択肢（登録済みのリレー）
+    pub relay_picker: Option<ListState>, // リレーの選択ダイアログ（表示中のみSome、0番は「すべて」）
+    pub share_menu: Option<ListState>,   // 共有用リンクのダイアログ（表示中のみSome）
+    pub share_links: Vec<(PermalinkFormat, String)>, // 共有ダイアログに並べるリンク
+    pub feed_kind: Option<u64>,      // kind の分布から選んだ kind（None なら feed_kinds）
+    pub feed_kinds: Vec<u64>,        // フィードに含める kind（config の feed_kinds）
+    pub kind_buckets: Vec<KindBucket>, // 集めたイベントの kind ごとの件数
+    pub kind_explorer: Option<ListState>, // kind の分布のダイアログ（表示中のみSome、0番はテキストノート）
+    pub kinds_requested: bool,       // kind の分布の取得要求
+    pub view_menu: Option<ListState>, // View メニュー（並び順の選択、表示中のみSome）
+    pub feed_counts: HashMap<EventId, FeedCounts>, // 表示中の投稿の返信数とZap額
+    pub plugin_scores: HashMap<EventId, f64>, // score_command が付けたスコア
+    pub sort_data_requested: bool,   // 並べ替えに使う情報の取得要求
+    pub read_positions: ReadPositions, // 長文記事ごとの読んだ位置
+    pub toc_state: Option<ListState>, // 長文記事の目次（表示中のみSome）
+    pub pending_article: Option<Coordinate>, // 開く長文記事（naddr）
+    pub strict_verification: bool,   // 表示するイベントの署名を手元で検証するか
+    pub invalid_dropped: usize,      // 検証に失敗して破棄したイベント数
+    pub detail_content: Option<DetailContent>, // 詳細表示中の本文（表示中のみSome）
+    pub content_stats: HashMap<EventId, ContentStats>, // 一覧に表示する長文の統計
+    pub compact_previews: HashMap<EventId, String>, // テキストノート以外の kind の一覧での1行の表示
+    pub redactor: Redactor,           // 本文の伏せ字の規則（config の redact_rules）
+    pub revealed: HashSet<EventId>,   // 伏せ字を外して表示しているイベント
+    pub activity: Option<Activity>, // 進行中の処理（読み込み中のアニメーションを表示する）
+    pub activity_started: Instant,  // 進行中の処理を始めた時刻（アニメーションのコマを決める）
+    pub account: String,             // 使用中のアカウント
+    pub account_choices: Vec<String>, // 切り替えられるアカウント（鍵を保存しているもの）
+    pub account_picker: Option<ListState>, // アカウントの選択ダイアログ（表示中のみSome）
+    pub pending_account: Option<String>, // 切り替え先のアカウント
+    pub session_stats: SessionStats, // 起動してからの受信・送信・転送量などの統計
+    pub show_session_stats: bool,    // 拡張ステータスバー（セッションの統計）を表示するか
+    pub secondary_instance: bool,    // 別の TUI がすでに動いているか（キャッシュを上書きせず読み取るだけにする）
+    pub streak: PostingStreak,       // 連続投稿の記録（拡張ステータスバーに表示）
+    pub post_reminder: Option<NaiveTime>, // この時刻を過ぎてもまだ投稿していなければ知らせる
+    pub reminded_on: Option<String>, // リマインダーで知らせた日（1日に1回だけ知らせる）
+    pub clock_skew: Option<i64>,     // リレーと比べた自分の時計のずれ（秒、測れていなければ None）
+    pub clock_skew_rx: Option<oneshot::Receiver<Option<i64>>>, // 裏で測っている時計のずれ（測定中のみSome）
+    pub verifying: Vec<(VerifyTarget, oneshot::Receiver<(Vec<nostr_sdk::Event>, usize)>)>, // 裏で署名を検証中の取得結果（検証後のイベントと破棄した数）
+    pub clock_warned: bool,          // 時計のずれの警告を表示済みか（もう一度Enterで送信）
+    pub current_draft: Option<String>, // 投稿作成画面で編集中の下書きのID（下書きから始めたときのみSome）
+    pub draft_choices: Vec<Draft>,   // 下書きの一覧（新しく編集した順）
+    pub draft_picker: Option<ListState>, // 下書きの選択ダイアログ（表示中のみSome）
+    pub sync_drafts: bool,           // 下書きをリレーと同期するか
+    pub drafts_sync_requested: bool, // 下書きの同期の要求
+    pub read_only: bool,             // 公開鍵だけを登録した閲覧専用のアカウントか（署名・復号が必要な操作はできない）
+    pub palette: Option<Palette>,    // コマンドパレット（表示中のみSome）
+    pub columns: Vec<Column>,        // 複数列のレイアウトの列（config の columns）
+    pub column_mode: bool,           // イベントリストを複数列で表示するか
+    pub column_focus: usize,         // 選択中の列
+    pub columns_requested: bool,     // 列のイベントの取得の要求
 }
 
 impl Default for App {
@@ -92,6 +923,15 @@ impl Default for App {
             my_public_key: None,
             keys: None,
             message_to_send: None,
+            compose_field: ComposeField::Body,
+            compose_subject: String::new(),
+            compose_hashtags: String::new(),
+            reply_to: None,
+            reply_context_scroll: 0,
+            reply_root_requested: None,
+            message_reply: None,
+            auto_hashtags: true,
+            aliases: BTreeMap::new(),
             detail_mode: false,
             detail_scroll: 0, // 初期値は0
             show_about: false,
@@ -100,6 +940,121 @@ impl Default for App {
             calculator_value: 0.0,
             calculator_op: None,
             calculator_new_input: true,
+            trash: Trash::default(),
+            show_trash: false,
+            trash_state: ListState::default(),
+            trash_confirm: false,
+            empty_trash_requested: false,
+            snoozed: Snoozed::default(),
+            snooze_menu: None,
+            show_reminders: false,
+            reminders_state: ListState::default(),
+            reminders_announced: 0,
+            notepad: NotePad::default(),
+            show_notepad: false,
+            notepad_page: 0,
+            sync_notepad: false,
+            notepad_sync_requested: false,
+            relay_bootstrap_requested: false,
+            refresh_requested: false,
+            goto_input: None,
+            pending_goto: None,
+            article_sort: FeedSort::Newest,
+            scrapbook: Scrapbook::default(),
+            scrap_state: ListState::default(),
+            scrap_query: String::new(),
+            scrap_searching: false,
+            mutes: MuteList::default(),
+            notifications: None,
+            resubscribe_requested: false,
+            session_started: Timestamp::now(),
+            mentions_received: 0,
+            muted_dropped: 0,
+            pending_stats: None,
+            detail_stats: None,
+            detail_follows: Vec::new(),
+            preferred_language: None,
+            translations: HashMap::new(),
+            show_welcome: false,
+            show_help: false,
+            quit_requested: false,
+            command_input: None,
+            history: History::default(),
+            command_recall: Recall::default(),
+            scrap_recall: Recall::default(),
+            profile_cards: false,
+            profiles: ProfileCache::default(),
+            profiles_requested: false,
+            public_terminal: false,
+            redacted_pubkeys: Vec::new(),
+            relays_ready: None,
+            boot: None,
+            dm_conversations: Vec::new(),
+            dm_state: ListState::default(),
+            dm_input: String::new(),
+            dm_editing: false,
+            dm_fallback_warned: false,
+            dm_refresh_requested: false,
+            dm_to_send: None,
+            dm_receipts: false,
+            dm_receipt_peers: Vec::new(),
+            dm_typing: HashMap::new(),
+            dm_read_until: HashMap::new(),
+            dm_read_sent: HashMap::new(),
+            dm_key_changes: HashMap::new(),
+            dm_typing_sent: None,
+            dm_signals: Vec::new(),
+            feed_relay: None,
+            relay_choices: Vec::new(),
+            relay_picker: None,
+            share_menu: None,
+            share_links: Vec::new(),
+            feed_kind: None,
+            feed_kinds: vec![Kind::TextNote.as_u64()],
+            kind_buckets: Vec::new(),
+            kind_explorer: None,
+            kinds_requested: false,
+            view_menu: None,
+            feed_counts: HashMap::new(),
+            plugin_scores: HashMap::new(),
+            sort_data_requested: false,
+            read_positions: ReadPositions::default(),
+            toc_state: None,
+            pending_article: None,
+            strict_verification: false,
+            invalid_dropped: 0,
+            detail_content: None,
+            content_stats: HashMap::new(),
+            compact_previews: HashMap::new(),
+            redactor: Redactor::default(),
+            revealed: HashSet::new(),
+            activity: None,
+            activity_started: Instant::now(),
+            account: DEFAULT_ACCOUNT.to_string(),
+            account_choices: Vec::new(),
+            account_picker: None,
+            pending_account: None,
+            session_stats: SessionStats::new(),
+            show_session_stats: false,
+            secondary_instance: false,
+            streak: PostingStreak::default(),
+            post_reminder: None,
+            reminded_on: None,
+            current_draft: None,
+            draft_choices: Vec::new(),
+            draft_picker: None,
+            sync_drafts: false,
+            drafts_sync_requested: false,
+            read_only: false,
+            palette: None,
+            columns: Vec::new(),
+            column_mode: false,
+            column_focus: 0,
+            columns_requested: false,
+            clock_skew: None,
+            clock_skew_rx: None,
+            verifying: Vec::new(),
+            clock_warned: false,
         }
     }
 }
@@ -126,6 +1081,267 @@ impl App {
         }
     }
 
+    // ゴミ箱の表示・非表示切り替え（開くたびに読み込み直す）
+    pub fn toggle_trash(&mut self) {
+        if self.public_terminal {
+            self.status = "公開端末モードではゴミ箱を使えません".to_string();
+            return;
+        }
+        self.show_trash = !self.show_trash;
+        self.trash_confirm = false;
+        if self.show_trash {
+            match Trash::load() {
+                Ok(trash) => self.trash = trash,
+                Err(e) => self.status = format!("ゴミ箱の読み込みに失敗: {}", e),
+            }
+            self.trash_state.select((!self.trash.items.is_empty()).then_some(0));
+        }
+    }
+
+    // ノートパッドの表示・非表示切り替え（開くときに読み込み、閉じるときに保存する）
+    pub fn toggle_notepad(&mut self) {
+        if self.public_terminal {
+            self.status = "公開端末モードではノートパッドを使えません".to_string();
+            return;
+        }
+        if self.show_notepad {
+            self.show_notepad = false;
+            self.status = match self.notepad.save() {
+                Ok(()) => "ノートパッドを保存しました".to_string(),
+                Err(e) => format!("ノートパッドの保存に失敗: {}", e),
+            };
+        } else {
+            match NotePad::load() {
+                Ok(pad) => self.notepad = pad,
+                Err(e) => {
+                    self.status = format!("ノートパッドの読み込みに失敗: {}", e);
+                    return;
+                }
+            }
+            self.show_notepad = true;
+        }
+        // 開くときはほかの端末で書いた分を取り込み、閉じるときは書いた分を送る
+        self.notepad_sync_requested = self.sync_notepad && !self.read_only;
+    }
+
+    // ノートパッドのページをめくる（最後のページの次は最初のページ）
+    fn flip_notepad_page(&mut self, forward: bool) {
+        self.notepad_page = if forward {
+            (self.notepad_page + 1) % NOTEPAD_PAGES
+        } else {
+            (self.notepad_page + NOTEPAD_PAGES - 1) % NOTEPAD_PAGES
+        };
+    }
+
+    // 選択中の投稿をゴミ箱に入れ、フィードから取り除く
+    // 削除は自分の投稿だけで、削除要求はゴミ箱を空にしたときに送る（それまではリレー上の投稿はそのまま）
+    // 選択中の投稿への返信を始める（投稿作成画面の入力欄の上に、返信先とスレッドの最初の投稿を表示する）
+    pub fn start_reply(&mut self) {
+        if self.public_terminal {
+            self.status = "公開端末モードでは投稿できません".to_string();
+            return;
+        }
+        if self.refuse_read_only("返信") {
+            return;
+        }
+        let Some(parent) = self.list_state.selected().and_then(|i| self.events.get(i)).cloned() else {
+            return;
+        };
+        self.start_reply_to(parent);
+    }
+
+    // 指定した投稿への返信を始める
+    fn start_reply_to(&mut self, parent: Event) {
+        let root_id = thread_root(&parent);
+        let root = if root_id == parent.id { None } else { self.events.iter().find(|e| e.id == root_id).cloned() };
+        // 表示中のイベントになければ、あとでリレーから取得する
+        self.reply_root_requested = (root_id != parent.id && root.is_none()).then_some(root_id);
+        self.status = format!(
+            "{} への返信を作成中（PgUp/PgDn で返信先をスクロール、Ctrl-X で返信をやめる）",
+            self.short_author(&parent.pubkey)
+        );
+        self.reply_to = Some(ReplyContext { parent, root });
+        self.reply_context_scroll = 0;
+        self.switch_tab(1);
+    }
+
+    // 返信をやめて、ふつうの投稿に戻す（入力した本文は残す）
+    pub fn cancel_reply(&mut self) {
+        if self.reply_to.take().is_some() {
+            self.reply_root_requested = None;
+            self.reply_context_scroll = 0;
+            self.status = "返信をやめました（本文はふつうの投稿として残っています）".to_string();
+        }
+    }
+
+    pub fn trash_selected(&mut self, reason: TrashReason) {
+        if self.public_terminal {
+            self.status = "公開端末モードではゴミ箱を使えません".to_string();
+            return;
+        }
+        let Some(index) = self.list_state.selected().filter(|i| *i < self.events.len()) else {
+            return;
+        };
+        if reason == TrashReason::Deleted {
+            if self.refuse_read_only("投稿を削除") {
+                return;
+            }
+            let mine = self.keys.as_ref().is_some_and(|keys| keys.public_key() == self.events[index].pubkey);
+            if !mine {
+                self.status = "削除できるのは自分の投稿だけです（x で非表示にできます）".to_string();
+                return;
+            }
+        }
+
+        let event = self.events[index].clone();
+        if !self.trash.put(event, reason, Timestamp::now()) {
+            self.status = "この投稿はすでにゴミ箱に入っています".to_string();
+            return;
+        }
+        if let Err(e) = self.trash.save() {
+            self.trash.items.remove(0);
+            self.status = format!("ゴミ箱の保存に失敗: {}", e);
+            return;
+        }
+        self.events.remove(index);
+        self.list_state.select(Some(index.min(self.events.len().saturating_sub(1))));
+        self.status = match reason {
+            TrashReason::Deleted => format!(
+                "🗑 ゴミ箱に入れました。{}日以内なら t で元に戻せます（削除要求はゴミ箱を空にしたときに送ります）",
+                TRASH_RETENTION_DAYS
+            ),
+            TrashReason::Dismissed => format!("🗑 ゴミ箱に入れました。{}日以内なら t で元に戻せます", TRASH_RETENTION_DAYS),
+        };
+    }
+
+    // 選択中の投稿をスヌーズする時刻を選ぶダイアログを開く
+    pub fn open_snooze_menu(&mut self) {
+        if self.public_terminal {
+            self.status = "公開端末モードではスヌーズを使えません".to_string();
+            return;
+        }
+        if self.list_state.selected().and_then(|i| self.events.get(i)).is_none() {
+            self.status = "スヌーズする投稿を選択してください".to_string();
+            return;
+        }
+        let mut state = ListState::default();
+        state.select(Some(0));
+        self.snooze_menu = Some(state);
+    }
+
+    // 選択中の投稿をフィードから隠し、指定した時刻にリマインダーに戻す
+    pub fn snooze_selected(&mut self, duration: SnoozeDuration) {
+        let Some(index) = self.list_state.selected().filter(|i| *i < self.events.len()) else {
+            return;
+        };
+        let until = duration.until(Timestamp::now());
+        self.snoozed.snooze(self.events[index].clone(), until);
+        if let Err(e) = self.snoozed.save() {
+            self.snoozed = Snoozed::load().unwrap_or_default();
+            self.status = format!("スヌーズの保存に失敗: {}", e);
+            return;
+        }
+        self.events.remove(index);
+        self.list_state.select(Some(index.min(self.events.len().saturating_sub(1))));
+        self.status = format!("⏰ {}（{}）にリマインダーに戻します。Z で一覧を開けます", duration.label(), format_jst(until));
+    }
+
+    // リマインダーの表示・非表示切り替え（開くたびに読み込み直す）
+    pub fn toggle_reminders(&mut self) {
+        if self.public_terminal {
+            self.status = "公開端末モードではリマインダーを使えません".to_string();
+            return;
+        }
+        self.show_reminders = !self.show_reminders;
+        if self.show_reminders {
+            match Snoozed::load() {
+                Ok(snoozed) => self.snoozed = snoozed,
+                Err(e) => self.status = format!("リマインダーの読み込みに失敗: {}", e),
+            }
+            self.reminders_state.select((!self.snoozed.items.is_empty()).then_some(0));
+        }
+    }
+
+    // リマインダーで選択中の項目を取り出す（保存できなければ元に戻す）
+    fn take_reminder(&mut self) -> Option<Event> {
+        let index = self.reminders_state.selected()?;
+        let item = self.snoozed.take(index)?;
+        if let Err(e) = self.snoozed.save() {
+            self.snoozed.items.insert(index, item);
+            self.status = format!("リマインダーの保存に失敗: {}", e);
+            return None;
+        }
+        let remaining = self.snoozed.items.len();
+        self.reminders_state.select((remaining > 0).then(|| index.min(remaining - 1)));
+        self.reminders_announced = self.snoozed.due_count(Timestamp::now());
+        Some(item.event)
+    }
+
+    // リマインダーで選択中の投稿をフィードに戻す
+    pub fn unsnooze_reminder(&mut self) {
+        let Some(event) = self.take_reminder() else {
+            return;
+        };
+        if !self.events.iter().any(|e| e.id == event.id) {
+            self.events.push(event);
+            self.apply_article_sort();
+        }
+        self.status = "フィードに戻しました".to_string();
+    }
+
+    // リマインダーで選択中の投稿に返信する（リマインダーから外して投稿作成画面を開く）
+    pub fn reply_to_reminder(&mut self) {
+        if self.refuse_read_only("返信") {
+            return;
+        }
+        if let Some(event) = self.take_reminder() {
+            self.show_reminders = false;
+            self.start_reply_to(event);
+        }
+    }
+
+    // リマインダーで選択中の投稿を片付ける（フィードにも戻さない）
+    pub fn dismiss_reminder(&mut self) {
+        if self.take_reminder().is_some() {
+            self.status = "リマインダーを片付けました".to_string();
+        }
+    }
+
+    // 時刻が来てリマインダーに戻った投稿が増えていれば知らせる
+    pub fn check_reminders(&mut self) {
+        let due = self.snoozed.due_count(Timestamp::now());
+        if due > self.reminders_announced {
+            self.status = format!("⏰ リマインダーが{}件あります（Z で開く）", due);
+        }
+        self.reminders_announced = due;
+    }
+
+    // ゴミ箱で選択中の項目を元に戻し、フィードに戻す
+    pub fn restore_trash_item(&mut self) {
+        let Some(index) = self.trash_state.selected() else {
+            return;
+        };
+        let item = match self.trash.restore(index, Timestamp::now()) {
+            Ok(item) => item,
+            Err(e) => {
+                self.status = e.to_string();
+                return;
+            }
+        };
+        if let Err(e) = self.trash.save() {
+            self.trash.items.insert(index, item);
+            self.status = format!("ゴミ箱の保存に失敗: {}", e);
+            return;
+        }
+        let remaining = self.trash.items.len();
+        self.trash_state.select((remaining > 0).then(|| index.min(remaining - 1)));
+        self.status = format!("{}した投稿を元に戻しました", item.reason.label());
+        if !self.events.iter().any(|e| e.id == item.event.id) {
+            self.events.push(item.event);
+            self.apply_article_sort();
+        }
+    }
+
     // 電卓の数字入力処理
     pub fn calculator_input_digit(&mut self, digit: char) {
         if self.calculator_new_input {
@@ -227,88 +1443,973 @@ impl App {
         }
     }
 
-    pub fn toggle_input_mode(&mut self) {
-        self.input_mode = match self.input_mode {
-            InputMode::Normal => InputMode::Editing,
-            InputMode::Editing => InputMode::Normal,
+    // 並び順の切り替え（新しい順 <-> 長い順）
+    // 表示する本文（優先言語の翻訳版があればそちら）
+    // 端末では画像を表示できないため、メディアのURLは代替テキストに置き換える
+    // 置き換えが必要なときだけ複製し、そうでなければ本文を借りたまま返す
+    pub fn display_content<'a>(&'a self, event: &'a nostr_sdk::Event) -> Cow<'a, str> {
+        let shown = self.translations.get(&event.id).unwrap_or(event);
+        self.display_text(event.id, shown, &shown.content)
+    }
+
+    // 一覧のプレビュー（本文の先頭だけを処理する）
+    pub fn preview_content(&self, event: &nostr_sdk::Event, limit: usize) -> String {
+        let shown = self.translations.get(&event.id).unwrap_or(event);
+        // リポストや長文記事などは、あらかじめ作った1行の表示を使う
+        let source = match self.compact_previews.get(&event.id) {
+            Some(compact) => compact.as_str(),
+            None => truncate_bytes(&shown.content, PREVIEW_SOURCE_BYTES),
         };
+        smart_truncate(&self.display_text(event.id, shown, source), limit)
     }
 
-    // 詳細表示モードの切り替え - スクロール位置もリセット
-    pub fn toggle_detail_mode(&mut self) {
-        self.detail_mode = !self.detail_mode;
-        if self.detail_mode {
-            self.detail_scroll = 0; // 詳細表示に入るたびスクロール位置をリセット
+    // 画像の代替テキストへの置き換えと、公開端末モード・伏せ字の規則での伏せ字を行う
+    fn display_text<'a>(&self, id: EventId, shown: &nostr_sdk::Event, content: &'a str) -> Cow<'a, str> {
+        let media = parse_imeta(shown);
+        let mut content = Cow::Borrowed(content);
+        if media.iter().any(|info| info.alt.as_deref().is_some_and(|alt| !alt.is_empty())) {
+            content = Cow::Owned(with_alt_placeholders(&content, &media));
         }
+        // 公開端末モードでは本文中のメンションからも伏せる
+        for npub in self.redacted_pubkeys.iter().filter(|_| self.public_terminal).filter_map(|pk| pk.to_bech32().ok()) {
+            if content.contains(&npub) {
+                content = Cow::Owned(content.replace(&npub, REDACTED_LABEL));
+            }
+        }
+        // u で伏せ字を外したイベントはそのまま（公開端末モードでは外せない）
+        if self.public_terminal || !self.revealed.contains(&id) {
+            content = self.redactor.redact(content);
+        }
+        content
     }
 
-    // 詳細表示時のスクロール - 上
-    pub fn detail_scroll_up(&mut self) {
-        if self.detail_scroll > 0 {
-            self.detail_scroll -= 1;
+    // 一覧に表示する長文の統計を、まだ数えていないイベントの分だけ数える
+    fn index_content_stats(&mut self) {
+        let ids: std::collections::HashSet<EventId> = self.events.iter().map(|e| e.id).collect();
+        self.content_stats.retain(|id, _| ids.contains(id));
+        let missing: Vec<(EventId, ContentStats)> = self
+            .events
+            .iter()
+            .filter(|e| is_long_form(e) && !self.content_stats.contains_key(&e.id))
+            .map(|e| (e.id, content_stats(&self.display_content(e))))
+            .collect();
+        self.content_stats.extend(missing);
+
+        self.compact_previews.retain(|id, _| ids.contains(id));
+        let missing: Vec<(EventId, String)> = self
+            .events
+            .iter()
+            .filter(|e| !self.compact_previews.contains_key(&e.id))
+            .filter_map(|e| Some((e.id, compact_content(self.translations.get(&e.id).unwrap_or(e))?)))
+            .collect();
+        self.compact_previews.extend(missing);
+    }
+
+    // 公開端末モードで伏せる公開鍵か
+    pub fn is_redacted(&self, pubkey: &XOnlyPublicKey) -> bool {
+        self.public_terminal && self.redacted_pubkeys.contains(pubkey)
+    }
+
+    // 一覧に表示する投稿者の短縮形（例: "npub...abcd1234"）
+    pub fn short_author(&self, pubkey: &XOnlyPublicKey) -> String {
+        if self.is_redacted(pubkey) {
+            return REDACTED_LABEL.to_string();
+        }
+        match pubkey.to_bech32() {
+            Ok(pk) => format!("npub...{}", &pk[pk.len()-8..]),
+            Err(_) => "unknown".to_string(),
         }
     }
 
-    // 詳細表示時のスクロール - 下
-    pub fn detail_scroll_down(&mut self) {
-        self.detail_scroll += 1;
+    // 次の並び順に切り替える
+    pub fn toggle_article_sort(&mut self) {
+        let index = FeedSort::ALL.iter().position(|s| *s == self.article_sort).unwrap_or(0);
+        self.set_article_sort(FeedSort::ALL[(index + 1) % FeedSort::ALL.len()]);
     }
 
-    // ページ単位のスクロール - 上
-    pub fn detail_page_up(&mut self) {
-        if self.detail_scroll >= 5 {
-            self.detail_scroll -= 5;
+    // 並び順を変更する（返信数・Zap額・スコアが必要なら取得してから並べ替える）
+    pub fn set_article_sort(&mut self, sort: FeedSort) {
+        self.article_sort = sort;
+        self.list_state.select(Some(0));
+        if sort.needs_counts() || sort == FeedSort::Plugin {
+            self.sort_data_requested = true;
+            self.status = format!("{}に並べ替えるための情報を取得中...", sort.label());
         } else {
-            self.detail_scroll = 0;
+            self.apply_article_sort();
+            self.status = format!("{}に並べ替えました", sort.label());
         }
     }
 
-    // ページ単位のスクロール - 下
-    pub fn detail_page_down(&mut self) {
-        self.detail_scroll += 5;
+    // 現在の並び順でイベントを並べ替える
+    pub fn apply_article_sort(&mut self) {
+        sort_events(&mut self.events, self.article_sort, &self.feed_counts, &self.plugin_scores);
+        // 一覧が変わったときは必ずここを通るので、長文の統計もここで用意する
+        self.index_content_stats();
     }
 
-    // 上にスクロール
-    pub fn previous(&mut self) {
-        let i = match self.list_state.selected() {
-            Some(i) => {
-                if i == 0 {
-                    0
-                } else {
-                    i - 1
-                }
-            }
-            None => 0,
-        };
-        self.list_state.select(Some(i));
+    // スクラップブックを読み直す（CLIで追加された項目を反映）
+    pub fn reload_scrapbook(&mut self) {
+        match Scrapbook::load() {
+            Ok(scrapbook) => self.scrapbook = scrapbook,
+            Err(e) => self.status = format!("スクラップブックの読み込みに失敗: {}", e),
+        }
+        self.scrap_state.select(Some(0));
     }
 
-    // 下にスクロール
-    pub fn next(&mut self) {
-        let i = match self.list_state.selected() {
-            Some(i) => {
-                if i >= self.events.len().saturating_sub(1) {
-                    self.events.len().saturating_sub(1)
-                } else {
-                    i + 1
-                }
-            }
-            None => 0,
-        };
-        self.list_state.select(Some(i));
+    // スクラップブック内の移動
+    pub fn scrap_previous(&mut self) {
+        let i = self.scrap_state.selected().unwrap_or(0).saturating_sub(1);
+        self.scrap_state.select(Some(i));
     }
 
-    // ページ上
-    pub fn page_up(&mut self) {
-        let i = match self.list_state.selected() {
-            Some(i) => {
-                if i < 5 {
-                    0
-                } else {
-                    i - 5
-                }
-            }
-            None => 0,
+    pub fn scrap_next(&mut self) {
+        let count = self.scrapbook.search(&self.scrap_query).len();
+        let i = (self.scrap_state.selected().unwrap_or(0) + 1).min(count.saturating_sub(1));
+        self.scrap_state.select(Some(i));
+    }
+
+    // 選択中のDMの会話
+    pub fn selected_conversation(&self) -> Option<&Conversation> {
+        self.dm_state.selected().and_then(|i| self.dm_conversations.get(i))
+    }
+
+    // この相手と入力中・既読の通知をやりとりするか（設定で有効にし、相手も対応している場合だけ）
+    fn receipts_enabled_for(&self, peer: &XOnlyPublicKey) -> bool {
+        self.dm_receipts && !self.public_terminal && !self.read_only && self.dm_receipt_peers.contains(peer)
+    }
+
+    // 表示中の会話で届いたメッセージを既読にしたことを通知する
+    fn queue_read_receipt(&mut self) {
+        if self.active_tab != TAB_DM {
+            return;
+        }
+        let Some(conversation) = self.selected_conversation() else { return };
+        let peer = conversation.peer;
+        let Some(latest) = conversation.messages.iter().filter(|m| !m.outgoing).map(|m| m.created_at).max() else {
+            return;
+        };
+        if !self.receipts_enabled_for(&peer) || self.dm_read_sent.get(&peer).is_some_and(|sent| *sent >= latest) {
+            return;
+        }
+        self.dm_read_sent.insert(peer, latest);
+        self.dm_signals.push((peer, DmSignal::Read(latest)));
+    }
+
+    // 入力中であることを通知する（一定の間隔を空ける）
+    fn queue_typing(&mut self) {
+        let Some(peer) = self.selected_conversation().map(|c| c.peer) else { return };
+        if !self.receipts_enabled_for(&peer) || self.dm_typing_sent.is_some_and(|sent| sent.elapsed() < TYPING_SEND_INTERVAL) {
+            return;
+        }
+        self.dm_typing_sent = Some(Instant::now());
+        self.dm_signals.push((peer, DmSignal::Typing));
+    }
+
+    // 選択中の相手との入力中・既読の通知を切り替える（相手のクライアントが対応しているときに使う）
+    pub fn toggle_receipt_peer(&mut self) {
+        let Some(peer) = self.selected_conversation().map(|c| c.peer) else {
+            self.status = "DMタブで会話を選択してください".to_string();
+            return;
+        };
+        let enabled = match self.dm_receipt_peers.iter().position(|p| *p == peer) {
+            Some(index) => {
+                self.dm_receipt_peers.remove(index);
+                false
+            }
+            None => {
+                self.dm_receipt_peers.push(peer);
+                true
+            }
+        };
+        self.save_receipt_peers();
+        self.status = match (enabled, self.dm_receipts) {
+            (true, true) => "この相手と入力中・既読を通知し合います".to_string(),
+            (true, false) => "この相手を通知の対象にしました（config set dm_receipts true で有効になります）".to_string(),
+            (false, _) => "この相手への入力中・既読の通知をやめました".to_string(),
+        };
+    }
+
+    fn save_receipt_peers(&mut self) {
+        let peers: Vec<String> = self.dm_receipt_peers.iter().map(|p| p.to_string()).collect();
+        let saved = AppConfig::load().and_then(|mut config| {
+            config.dm_receipt_peers = peers;
+            config.save()
+        });
+        if let Err(e) = saved {
+            self.status = format!("設定の保存に失敗: {}", e);
+        }
+    }
+
+    // 相手から届いた入力中・既読の通知を反映する（通知が届いた相手は対応しているとみなす）
+    fn handle_signal(&mut self, peer: XOnlyPublicKey, signal: DmSignal) {
+        if !self.dm_receipt_peers.contains(&peer) {
+            self.dm_receipt_peers.push(peer);
+            self.save_receipt_peers();
+        }
+        match signal {
+            DmSignal::Typing => {
+                self.dm_typing.insert(peer, Instant::now());
+            }
+            DmSignal::Read(until) => {
+                let read = self.dm_read_until.entry(peer).or_insert(until);
+                *read = (*read).max(until);
+            }
+        }
+    }
+
+    // 閲覧専用のアカウントなら、署名や復号が必要な操作を断る
+    fn refuse_read_only(&mut self, action: &str) -> bool {
+        if self.read_only {
+            self.status = format!("閲覧専用のアカウント（公開鍵のみ）では{}できません（import-key で秘密鍵を取り込んでください）", action);
+        }
+        self.read_only
+    }
+
+    // DMタブを開く（まだ取得していなければ取得する）
+    fn enter_dm_tab(&mut self) {
+        if self.public_terminal {
+            self.status = "公開端末モードではDMを表示しません".to_string();
+            return;
+        }
+        if self.refuse_read_only("DMを表示") {
+            return;
+        }
+        if self.dm_conversations.is_empty() {
+            self.dm_refresh_requested = true;
+            self.status = "DMを取得中...".to_string();
+        }
+    }
+
+    // 指定した相手（npub / hex / 別名）との会話を開く（なければ新しく作る）
+    pub fn open_conversation(&mut self, target: &str) {
+        if self.public_terminal {
+            self.status = "公開端末モードではDMを表示しません".to_string();
+            return;
+        }
+        if self.refuse_read_only("DMを表示") {
+            return;
+        }
+        let target = self.aliases.get(target).map(|npub| npub.as_str()).unwrap_or(target);
+        let peer = XOnlyPublicKey::from_bech32(target.trim_start_matches("nostr:"))
+            .ok()
+            .or_else(|| XOnlyPublicKey::from_str(target).ok());
+        let Some(peer) = peer else {
+            self.status = format!("公開鍵または別名を指定してください: {}", target);
+            return;
+        };
+
+        self.active_tab = TAB_DM;
+        self.input_mode = InputMode::Normal;
+        let index = match self.dm_conversations.iter().position(|c| c.peer == peer) {
+            Some(index) => index,
+            None => {
+                // 相手がギフトラップに対応しているかは次の取得で調べる
+                self.dm_conversations.insert(0, Conversation { peer, messages: Vec::new(), scheme: DmScheme::Nip04 });
+                self.dm_refresh_requested = true;
+                0
+            }
+        };
+        self.dm_state.select(Some(index));
+        self.start_dm_compose();
+    }
+
+    // 選択中の会話でメッセージの入力を始める
+    fn start_dm_compose(&mut self) {
+        if self.public_terminal || self.keys.is_none() {
+            self.status = "鍵が読み込まれていないためDMを送信できません".to_string();
+            return;
+        }
+        if self.selected_conversation().is_none() {
+            self.status = "会話がありません。:dm <npub|別名> で始められます".to_string();
+            return;
+        }
+        self.dm_editing = true;
+        self.dm_fallback_warned = false;
+    }
+
+    // 入力したDMを送信する
+    // 相手がギフトラップに対応していなければ、旧方式で送る前に一度警告して確認を求める
+    fn submit_dm(&mut self) {
+        let Some(conversation) = self.selected_conversation() else { return };
+        if self.dm_input.trim().is_empty() {
+            return;
+        }
+        let (peer, scheme) = (conversation.peer, conversation.scheme);
+        if scheme == DmScheme::Nip04 && !self.dm_fallback_warned {
+            self.dm_fallback_warned = true;
+            self.status = "⚠ 相手がNIP-17に対応していないため旧方式NIP-04で送信します。Enterで送信、Escで中止".to_string();
+            return;
+        }
+        self.dm_to_send = Some((peer, std::mem::take(&mut self.dm_input), scheme));
+        self.dm_editing = false;
+        self.dm_fallback_warned = false;
+        self.status = "DMを送信中...".to_string();
+    }
+
+    // 選択中の投稿の共有用リンクを作り、形式を選ぶダイアログを開く
+    // リレーのヒントは、絞り込んでいればそのリレー、なければ接続しているリレー
+    pub fn open_share_menu(&mut self) {
+        let target = match self.detail_content {
+            Some(_) => self.detail_event(),
+            None => self.list_state.selected().and_then(|i| self.events.get(i)),
+        };
+        let Some(event_id) = target.map(|e| e.id) else {
+            self.status = "共有する投稿を選択してください".to_string();
+            return;
+        };
+        let relays = match &self.feed_relay {
+            Some(url) => vec![url.clone()],
+            None => self.relay_choices.clone(),
+        };
+        self.share_links = PermalinkFormat::ALL
+            .into_iter()
+            .filter_map(|format| Some((format, permalink(event_id, &relays, format).ok()?)))
+            .collect();
+        let mut state = ListState::default();
+        state.select(Some(0));
+        self.share_menu = Some(state);
+    }
+
+    // アカウントの選択ダイアログを開く（使用中のアカウントを選択しておく）
+    pub fn open_account_picker(&mut self) {
+        if self.public_terminal {
+            self.status = "公開端末モードではアカウントを切り替えられません".to_string();
+            return;
+        }
+        self.account_choices = match list_accounts() {
+            Ok(names) if !names.is_empty() => names,
+            Ok(_) => {
+                self.status = "鍵を保存しているアカウントはありません".to_string();
+                return;
+            }
+            Err(e) => {
+                self.status = format!("アカウントの一覧を読み込めません: {}", e);
+                return;
+            }
+        };
+        let mut state = ListState::default();
+        state.select(Some(self.account_choices.iter().position(|name| *name == self.account).unwrap_or(0)));
+        self.account_picker = Some(state);
+    }
+
+    // アカウントの切り替えを要求する（鍵の読み込みとリレーへの接続し直しはメインループで行う）
+    pub fn request_account_switch(&mut self, name: &str) {
+        if self.public_terminal {
+            self.status = "公開端末モードではアカウントを切り替えられません".to_string();
+        } else if name == self.account {
+            self.status = format!("すでにアカウント {} を使用しています", name);
+        } else if let Err(e) = account_dir(name).map(|dir| dir.join("keys.json")).and_then(|path| {
+            if path.exists() {
+                Ok(())
+            } else {
+                Err(format!("アカウント {} の鍵が見つかりません", name).into())
+            }
+        }) {
+            self.status = e.to_string();
+        } else {
+            self.pending_account = Some(name.to_string());
+            self.status = format!("アカウント {} に切り替えています...", name);
+        }
+    }
+
+    // 投稿作成画面の入力を下書きとして保存する（下書きから始めたときは同じ下書きを更新する）
+    pub fn save_draft(&mut self) {
+        if self.input.trim().is_empty() && self.compose_subject.trim().is_empty() {
+            self.status = "保存する内容がありません".to_string();
+            return;
+        }
+        let mut draft = Draft::new(&self.input, &self.compose_subject, &self.compose_hashtags);
+        if let Some(id) = &self.current_draft {
+            draft.id = id.clone();
+        }
+        let saved = DraftStore::load().and_then(|mut store| {
+            store.upsert(draft.clone());
+            store.save()
+        });
+        match saved {
+            Ok(()) => {
+                self.current_draft = Some(draft.id.clone());
+                self.drafts_sync_requested = self.sync_drafts;
+                self.status = format!("下書きを保存しました: {}（:drafts で一覧）", draft.title());
+            }
+            Err(e) => self.status = format!("下書きを保存できません: {}", e),
+        }
+    }
+
+    // 下書きの選択ダイアログを開く
+    pub fn open_draft_picker(&mut self) {
+        self.draft_choices = match DraftStore::load() {
+            Ok(store) => store.active().into_iter().cloned().collect(),
+            Err(e) => {
+                self.status = format!("下書きを読み込めません: {}", e);
+                return;
+            }
+        };
+        if self.draft_choices.is_empty() {
+            self.status = "下書きはありません（投稿作成画面の Ctrl-S で保存）".to_string();
+            return;
+        }
+        let mut state = ListState::default();
+        state.select(Some(0));
+        self.draft_picker = Some(state);
+    }
+
+    // 下書きを投稿作成画面に読み込み、続きを書けるようにする
+    pub fn load_draft(&mut self, draft: &Draft) {
+        if self.public_terminal {
+            self.status = "公開端末モードでは投稿できません".to_string();
+            return;
+        }
+        if self.refuse_read_only("投稿") {
+            return;
+        }
+        self.input = draft.content.clone();
+        self.compose_subject = draft.subject.clone();
+        self.compose_hashtags = draft.hashtags.clone();
+        self.compose_field = ComposeField::Body;
+        self.current_draft = Some(draft.id.clone());
+        self.active_tab = 1;
+        self.input_mode = InputMode::Editing;
+        self.status = format!("下書き「{}」を読み込みました（Ctrl-S で上書き保存）", draft.title());
+    }
+
+    // 下書きを削除する（同期していればほかの端末にも削除を伝える）
+    pub fn delete_draft(&mut self, id: &str) {
+        let deleted = DraftStore::load().and_then(|mut store| {
+            let deleted = store.delete(id);
+            store.save()?;
+            Ok(deleted)
+        });
+        match deleted {
+            Ok(_) => {
+                self.draft_choices.retain(|draft| draft.id != id);
+                if self.current_draft.as_deref() == Some(id) {
+                    self.current_draft = None;
+                }
+                self.drafts_sync_requested = self.sync_drafts;
+            }
+            Err(e) => self.status = format!("下書きを削除できません: {}", e),
+        }
+    }
+
+    // リレーの選択ダイアログを開く（表示中のリレーを選択しておく）
+    pub fn open_relay_picker(&mut self) {
+        let mut state = ListState::default();
+        let current = self
+            .feed_relay
+            .as_ref()
+            .and_then(|url| self.relay_choices.iter().position(|c| c == url))
+            .map_or(0, |i| i + 1);
+        state.select(Some(current));
+        self.relay_picker = Some(state);
+    }
+
+    // フィードを指定したリレーだけに絞り込む（None ならすべてのリレーに戻す）
+    pub fn set_feed_relay(&mut self, relay: Option<String>) {
+        if let Some(url) = &relay {
+            if let Err(e) = check_relay_url(url) {
+                self.status = e.to_string();
+                return;
+            }
+        }
+        self.status = match &relay {
+            Some(url) => format!("{} のイベントを取得中...", url),
+            None => "すべてのリレーのイベントを取得中...".to_string(),
+        };
+        self.feed_relay = relay;
+        self.active_tab = 0;
+        self.close_detail();
+        self.list_state.select(Some(0));
+        self.refresh_requested = true;
+    }
+
+    // kind の分布を調べる（表示中のリレーの絞り込みに従う）
+    pub fn explore_kinds(&mut self) {
+        self.kinds_requested = true;
+        self.status = "イベントを集めています...".to_string();
+    }
+
+    // フィードで取得する kind（kind の分布から選んでいればその kind だけ）
+    pub fn feed_filter_kinds(&self) -> Vec<Kind> {
+        match self.feed_kind {
+            Some(kind) => vec![Kind::from(kind)],
+            None => feed_kinds(&self.feed_kinds),
+        }
+    }
+
+    // フィードに表示する kind を切り替える（None なら config の feed_kinds に戻す）
+    pub fn set_feed_kind(&mut self, kind: Option<u64>) {
+        self.feed_kind = kind;
+        self.status = match kind {
+            Some(kind) => format!("{} のイベントを取得中...", kind_name(kind)),
+            None => "イベントを取得中...".to_string(),
+        };
+        self.active_tab = 0;
+        self.close_detail();
+        self.list_state.select(Some(0));
+        self.refresh_requested = true;
+    }
+
+    // 選択中（詳細表示中ならそのイベント）の伏せ字を外す・戻す
+    pub fn toggle_reveal(&mut self) {
+        let target = match self.detail_content {
+            Some(_) => self.detail_event(),
+            None => self.list_state.selected().and_then(|i| self.events.get(i)),
+        };
+        let Some(event) = target.cloned() else {
+            return;
+        };
+        if self.public_terminal {
+            self.status = "公開端末モードでは伏せ字を外せません".to_string();
+            return;
+        }
+        let shown = self.translations.get(&event.id).unwrap_or(&event);
+        if !self.redactor.matches(&shown.content) {
+            self.status = "この投稿に伏せ字はありません".to_string();
+            return;
+        }
+        if self.revealed.remove(&event.id) {
+            self.status = "伏せ字に戻しました".to_string();
+        } else {
+            self.revealed.insert(event.id);
+            self.status = "伏せ字を外しました（画面共有中は注意。u でもう一度伏せます）".to_string();
+        }
+        // 詳細表示の本文は開いたときに作っているので作り直す
+        if self.detail_content.is_some() {
+            self.detail_content = Some(DetailContent::new(event.id, self.display_content(&event).into_owned(), event.kind == Kind::LongFormTextNote));
+        }
+    }
+
+    // プロフィールカード表示の切り替え（設定に保存し、次回起動時も引き継ぐ）
+    pub fn toggle_profile_cards(&mut self) {
+        self.profile_cards = !self.profile_cards;
+        self.profiles_requested = self.profile_cards;
+        self.status = if self.profile_cards {
+            "プロフィールカードを表示します".to_string()
+        } else {
+            "プロフィールカードを非表示にしました".to_string()
+        };
+        let mut config = AppConfig::load().unwrap_or_default();
+        config.profile_cards = self.profile_cards;
+        if let Err(e) = config.save() {
+            self.status = format!("設定の保存に失敗: {}", e);
+        }
+    }
+
+    // config の columns から列を作る（解釈できない指定は飛ばしてステータスに表示する）
+    pub fn load_columns(&mut self, specs: &[String]) {
+        self.columns.clear();
+        for spec in specs {
+            match ColumnSource::parse(spec) {
+                Ok(source) => self.columns.push(Column::new(source)),
+                Err(e) => self.status = e.to_string(),
+            }
+        }
+        self.column_focus = 0;
+    }
+
+    // 複数列のレイアウトの切り替え（切り替えたら列を取得し、購読し直す）
+    pub fn set_column_mode(&mut self, enabled: bool) {
+        if enabled && self.columns.is_empty() {
+            self.status = "列がありません。:columns add <列> で追加してください".to_string();
+            return;
+        }
+        self.column_mode = enabled;
+        self.active_tab = 0;
+        self.close_detail();
+        self.column_focus = self.column_focus.min(self.columns.len().saturating_sub(1));
+        if enabled {
+            self.columns_requested = true;
+            self.status = format!("{}列で表示します。列を取得中...", self.columns.len());
+        } else {
+            self.resubscribe_requested = true;
+            self.status = "1列の表示に戻しました".to_string();
+        }
+    }
+
+    // 列を追加して config に保存する
+    pub fn add_column(&mut self, spec: &str) {
+        let source = match ColumnSource::parse(spec) {
+            Ok(source) => source,
+            Err(e) => {
+                self.status = e.to_string();
+                return;
+            }
+        };
+        if self.columns.iter().any(|c| c.source == source) {
+            self.status = format!("{} の列は既にあります", source.title());
+            return;
+        }
+        self.status = format!("{} の列を追加しました", source.title());
+        self.columns.push(Column::new(source));
+        self.column_focus = self.columns.len() - 1;
+        self.save_columns();
+        if self.column_mode {
+            self.columns_requested = true;
+        } else {
+            self.set_column_mode(true);
+        }
+    }
+
+    // 選択中の列を削除して config に保存する
+    pub fn remove_column(&mut self) {
+        if self.column_focus >= self.columns.len() {
+            self.status = "削除する列がありません".to_string();
+            return;
+        }
+        let removed = self.columns.remove(self.column_focus);
+        self.column_focus = self.column_focus.min(self.columns.len().saturating_sub(1));
+        self.save_columns();
+        self.status = format!("{} の列を削除しました", removed.source.title());
+        if self.columns.is_empty() {
+            self.set_column_mode(false);
+        } else {
+            self.resubscribe_requested = true;
+        }
+    }
+
+    fn save_columns(&mut self) {
+        let mut config = AppConfig::load().unwrap_or_default();
+        config.columns = self.columns.iter().map(|c| c.source.spec()).collect();
+        if let Err(e) = config.save() {
+            self.status = format!("設定の保存に失敗: {}", e);
+        }
+    }
+
+    // ライブ購読で届いたイベントを、条件に合う列に加える
+    fn route_to_columns(&mut self, event: &nostr_sdk::Event) {
+        let me = self.keys.as_ref().map(|keys| keys.public_key());
+        let kinds = self.feed_filter_kinds();
+        for column in self.columns.iter_mut() {
+            if column.source.matches(event, me, &kinds) {
+                column.insert(event.clone());
+            }
+        }
+    }
+
+    // 選択中のイベントが属するスレッドをミュートする
+    pub fn mute_selected_thread(&mut self) {
+        let Some(event) = self.list_state.selected().and_then(|i| self.events.get(i)) else {
+            return;
+        };
+        let root = thread_root(event);
+        if self.mutes.mute_thread(root) {
+            match self.mutes.save() {
+                Ok(()) => {
+                    self.status = "スレッドをミュートしました。以後の通知は届きません".to_string();
+                    self.resubscribe_requested = true;
+                }
+                Err(e) => self.status = format!("ミュート設定の保存に失敗: {}", e),
+            }
+        } else {
+            self.status = "このスレッドは既にミュートされています".to_string();
+        }
+    }
+
+    // ライブ購読で届いたイベントを処理する
+    // ミュート中のスレッドへのメンションは、音や通知を出す前にここで捨てる
+    pub fn handle_incoming_event(&mut self, event: nostr_sdk::Event) {
+        if let Some((peer, signal)) = self.keys.as_ref().and_then(|keys| parse_signal(keys, &event)) {
+            if self.dm_receipts {
+                self.handle_signal(peer, signal);
+            }
+            return;
+        }
+
+        if is_ephemeral(event.kind) {
+            self.handle_ephemeral_event(event);
+            return;
+        }
+
+        // 複数列のレイアウトでは、メンション以外も条件に合う列に加える（メンションの通知は下で続けて処理する）
+        if self.column_mode
+            && event.created_at >= self.session_started
+            && !(self.strict_verification && verify_event(&event).is_err())
+            && !self.mutes.is_thread_muted(&event)
+            && !self.mutes.is_author_muted(&event)
+        {
+            self.route_to_columns(&event);
+        }
+
+        let my_hex = match &self.keys {
+            Some(keys) => keys.public_key().to_string(),
+            None => return,
+        };
+
+        let mentions_me = event.tags.iter().any(|tag| {
+            let values = tag.as_vec();
+            values.first().map(|k| k.as_str()) == Some("p") && values.get(1) == Some(&my_hex)
+        });
+        if !mentions_me || event.created_at < self.session_started || event.pubkey.to_string() == my_hex {
+            return;
+        }
+
+        if self.strict_verification && verify_event(&event).is_err() {
+            self.invalid_dropped += 1;
+            return;
+        }
+
+        // 否定の条件に対応していないリレーからは、ミュート中のスレッドも届くので、音や通知の前にここで捨てる
+        if self.mutes.is_thread_muted(&event) || self.mutes.is_author_muted(&event) {
+            self.muted_dropped += 1;
+            return;
+        }
+
+        if self.events.iter().any(|e| e.id == event.id) || self.trash.contains(&event.id) || self.snoozed.contains(&event.id) {
+            return;
+        }
+
+        self.mentions_received += 1;
+        self.status = format!("{} メンションを受信しました ({}件目)", glyph(Glyph::Note), self.mentions_received);
+        // 絞り込んだフィードには、条件に合わないメンションを混ぜない
+        if self.feed_relay.is_none() && self.feed_kind.is_none() {
+            self.events.insert(0, event);
+            self.apply_article_sort();
+        }
+
+        // 端末のベルで通知
+        let _ = io::Write::write_all(&mut io::stdout(), b"\x07");
+    }
+
+    // 一時的なイベントをフィードの先頭に加える（キャッシュには保存せず、次の更新で消える）
+    fn handle_ephemeral_event(&mut self, event: nostr_sdk::Event) {
+        if self.strict_verification && verify_event(&event).is_err() {
+            self.invalid_dropped += 1;
+            return;
+        }
+        if self.mutes.is_author_muted(&event) || self.events.iter().any(|e| e.id == event.id) {
+            return;
+        }
+        if self.feed_relay.is_some() || self.feed_kind.is_some_and(|kind| kind != event.kind.as_u64()) {
+            return;
+        }
+        self.status = format!("⚡ 一時的なイベント（kind {}）を受信しました", event.kind.as_u64());
+        self.events.insert(0, event);
+        self.apply_article_sort();
+    }
+
+    // 入力中の投稿作成欄
+    pub fn compose_field_mut(&mut self) -> &mut String {
+        match self.compose_field {
+            ComposeField::Body => &mut self.input,
+            ComposeField::Subject => &mut self.compose_subject,
+            ComposeField::Hashtags => &mut self.compose_hashtags,
+        }
+    }
+
+    pub fn toggle_input_mode(&mut self) {
+        if self.public_terminal && self.input_mode == InputMode::Normal {
+            self.status = "公開端末モードでは投稿できません".to_string();
+            return;
+        }
+        if self.input_mode == InputMode::Normal && self.refuse_read_only("投稿") {
+            return;
+        }
+        self.input_mode = match self.input_mode {
+            InputMode::Normal => InputMode::Editing,
+            InputMode::Editing => InputMode::Normal,
+        };
+    }
+
+    // 起動画面の段階を進める（起動画面を表示していなければ何もしない）
+    pub fn advance_boot(&mut self, step: BootStep) {
+        if let Some(boot) = self.boot.as_mut() {
+            boot.advance(step);
+        }
+    }
+
+    // 処理の開始（アニメーションは始めた時刻からの経過時間で進める）
+    pub fn start_activity(&mut self, activity: Activity) {
+        self.activity = Some(activity);
+        self.activity_started = Instant::now();
+    }
+
+    // 処理の終了（リレーへの接続がまだ続いていれば接続中の表示に戻す）
+    pub fn finish_activity(&mut self) {
+        self.activity = None;
+        if self.relays_ready.is_some() {
+            self.start_activity(Activity::Connecting);
+        }
+    }
+
+    // 詳細表示モードの切り替え - スクロール位置もリセット
+    pub fn toggle_detail_mode(&mut self) {
+        if self.detail_mode {
+            self.close_detail();
+            return;
+        }
+        self.detail_mode = true;
+        self.detail_scroll = 0; // 詳細表示に入るたびスクロール位置をリセット
+        // 本文は開いたときに1度だけ用意し、描画のたびに作り直さない
+        self.detail_content = self.list_state.selected().and_then(|i| self.events.get(i)).map(|e| {
+            DetailContent::new(e.id, self.display_content(e).into_owned(), e.kind == Kind::LongFormTextNote)
+        });
+        // 長文記事は前回読んだ位置から表示する
+        if let Some(line) = self.selected_article().and_then(|e| self.read_positions.get(&article_address(e))) {
+            self.detail_scroll = line;
+            self.status = format!("前回の続き（{}行目）から表示しています", line + 1);
+        }
+        // 反応の集計を取得する
+        self.detail_stats = None;
+        self.detail_follows.clear();
+        self.pending_stats = self.list_state.selected().and_then(|i| self.events.get(i)).map(|e| e.id);
+    }
+
+    // 詳細表示を閉じる（長文記事なら読んだ位置を記録する）
+    pub fn close_detail(&mut self) {
+        if self.detail_mode {
+            self.remember_read_position();
+        }
+        self.detail_mode = false;
+        self.detail_scroll = 0;
+        self.toc_state = None;
+        self.detail_content = None;
+    }
+
+    // 詳細表示中のイベント（一覧の並びが変わっても開いたときのイベント）
+    pub fn detail_event(&self) -> Option<&nostr_sdk::Event> {
+        let detail = self.detail_content.as_ref()?;
+        self.events.iter().find(|e| e.id == detail.event_id)
+    }
+
+    // 選択中（詳細表示中ならそのイベント）が長文記事ならそのイベント
+    fn selected_article(&self) -> Option<&nostr_sdk::Event> {
+        match self.detail_content {
+            Some(_) => self.detail_event(),
+            None => self.list_state.selected().and_then(|i| self.events.get(i)),
+        }
+        .filter(|e| e.kind == Kind::LongFormTextNote)
+    }
+
+    // 詳細表示中の長文記事の目次
+    pub fn article_headings(&self) -> &[Heading] {
+        self.detail_content.as_ref().map_or(&[], |detail| detail.headings.as_slice())
+    }
+
+    // 詳細表示中の長文記事の読んだ位置を記録する
+    fn remember_read_position(&mut self) {
+        let Some(event) = self.selected_article() else {
+            return;
+        };
+        let address = article_address(event);
+        let last_line = self.detail_content.as_ref().map_or(0, |d| d.line_count().saturating_sub(1));
+        let line = self.detail_scroll.min(last_line.min(u16::MAX as usize) as u16);
+        self.read_positions.set(&address, line);
+        if let Err(e) = self.read_positions.save() {
+            self.status = format!("読んだ位置の保存に失敗: {}", e);
+        }
+    }
+
+    // 前後の見出しへ移動する
+    pub fn jump_section(&mut self, forward: bool) {
+        let headings = self.article_headings().to_vec();
+        let current = self.detail_scroll as usize;
+        let target = if forward {
+            headings.iter().find(|h| h.line > current)
+        } else {
+            headings.iter().rev().find(|h| h.line < current)
+        };
+        match target {
+            Some(heading) => {
+                self.detail_scroll = heading.line.min(u16::MAX as usize) as u16;
+                self.status = format!("§ {}", heading.title);
+            }
+            None if headings.is_empty() => self.status = "見出しがありません".to_string(),
+            None => {}
+        }
+    }
+
+    // 目次を開く（今読んでいる見出しを選択しておく）
+    pub fn open_toc(&mut self) {
+        let headings = self.article_headings().to_vec();
+        if headings.is_empty() {
+            self.status = "見出しがありません".to_string();
+            return;
+        }
+        let current = headings.iter().rposition(|h| h.line <= self.detail_scroll as usize).unwrap_or(0);
+        let mut state = ListState::default();
+        state.select(Some(current));
+        self.toc_state = Some(state);
+    }
+
+    // naddr の長文記事を開く
+    pub fn open_article(&mut self, input: &str) {
+        match Coordinate::from_bech32(input.trim().trim_start_matches("nostr:")) {
+            Ok(coordinate) => {
+                self.pending_article = Some(coordinate);
+                self.status = "記事を取得中...".to_string();
+            }
+            Err(e) => self.status = format!("naddr を解釈できません: {}", e),
+        }
+    }
+
+    // 詳細表示時のスクロール - 上
+    pub fn detail_scroll_up(&mut self) {
+        if self.detail_scroll > 0 {
+            self.detail_scroll -= 1;
+        }
+    }
+
+    // 詳細表示時のスクロール - 下
+    pub fn detail_scroll_down(&mut self) {
+        self.detail_scroll += 1;
+    }
+
+    // ページ単位のスクロール - 上
+    pub fn detail_page_up(&mut self) {
+        if self.detail_scroll >= 5 {
+            self.detail_scroll -= 5;
+        } else {
+            self.detail_scroll = 0;
+        }
+    }
+
+    // ページ単位のスクロール - 下
+    pub fn detail_page_down(&mut self) {
+        self.detail_scroll += 5;
+    }
+
+    // 上にスクロール
+    pub fn previous(&mut self) {
+        let i = match self.list_state.selected() {
+            Some(i) => {
+                if i == 0 {
+                    0
+                } else {
+                    i - 1
+                }
+            }
+            None => 0,
+        };
+        self.list_state.select(Some(i));
+    }
+
+    // 下にスクロール
+    pub fn next(&mut self) {
+        let i = match self.list_state.selected() {
+            Some(i) => {
+                if i >= self.events.len().saturating_sub(1) {
+                    self.events.len().saturating_sub(1)
+                } else {
+                    i + 1
+                }
+            }
+            None => 0,
+        };
+        self.list_state.select(Some(i));
+    }
+
+    // ページ上
+    pub fn page_up(&mut self) {
+        let i = match self.list_state.selected() {
+            Some(i) => {
+                if i < 5 {
+                    0
+                } else {
+                    i - 5
+                }
+            }
+            None => 0,
         };
         self.list_state.select(Some(i));
     }
@@ -328,177 +2429,545 @@ impl App {
         self.list_state.select(Some(i));
     }
 
-    // 先頭へ
-    pub fn home(&mut self) {
-        self.list_state.select(Some(0));
+    // 先頭へ
+    pub fn home(&mut self) {
+        self.list_state.select(Some(0));
+    }
+
+    // 末尾へ
+    pub fn end(&mut self) {
+        if !self.events.is_empty() {
+            self.list_state.select(Some(self.events.len() - 1));
+        }
+    }
+
+    // キー操作の対象になっている画面（handle_key_event とヘルプで同じ優先順位を使う）
+    fn key_mode(&self) -> KeyMode {
+        if self.palette.is_some() {
+            KeyMode::Palette
+        } else if self.show_notepad {
+            KeyMode::Notepad
+        } else if self.show_calculator {
+            KeyMode::Calculator
+        } else if self.trash_confirm {
+            KeyMode::EmptyTrash
+        } else if self.show_trash {
+            KeyMode::Trash
+        } else if self.show_reminders {
+            KeyMode::Reminders
+        } else if self.snooze_menu.is_some() {
+            KeyMode::Snooze
+        } else if self.share_menu.is_some() {
+            KeyMode::Share
+        } else if self.relay_picker.is_some() {
+            KeyMode::RelayPicker
+        } else if self.account_picker.is_some() {
+            KeyMode::AccountPicker
+        } else if self.draft_picker.is_some() {
+            KeyMode::DraftPicker
+        } else if self.toc_state.is_some() {
+            KeyMode::Toc
+        } else if self.view_menu.is_some() {
+            KeyMode::ViewMenu
+        } else if self.kind_explorer.is_some() {
+            KeyMode::KindExplorer
+        } else if self.command_input.is_some() {
+            KeyMode::CommandLine
+        } else if self.goto_input.is_some() {
+            KeyMode::Goto
+        } else if self.scrap_searching {
+            KeyMode::ScrapSearch
+        } else if self.dm_editing {
+            KeyMode::DmEditing
+        } else if self.show_about {
+            KeyMode::About
+        } else if self.input_mode == InputMode::Editing {
+            KeyMode::Editing
+        } else if self.detail_mode {
+            KeyMode::Detail
+        } else if self.active_tab == TAB_SCRAPBOOK {
+            KeyMode::Scrapbook
+        } else if self.active_tab == TAB_DM {
+            KeyMode::Dm
+        } else if self.active_tab == 1 {
+            KeyMode::Compose
+        } else if self.column_mode {
+            KeyMode::Columns
+        } else {
+            KeyMode::Feed
+        }
+    }
+
+    // 現在の画面の名前とキー操作一覧（? のヘルプ用）
+    fn active_keymap(&self) -> (&'static str, Vec<&'static KeyBinding>) {
+        let mode = self.key_mode();
+        (mode.title(), mode.bindings())
+    }
+
+    // タブを切り替える
+    fn switch_tab(&mut self, tab: usize) {
+        self.active_tab = tab;
+        // 作成画面に切り替わったら自動で編集モードに
+        if self.active_tab == 1 && !self.public_terminal && !self.read_only {
+            self.input_mode = InputMode::Editing;
+        }
+        if self.active_tab == TAB_SCRAPBOOK {
+            self.reload_scrapbook();
+        }
+        if self.active_tab == TAB_DM {
+            self.enter_dm_tab();
+        }
+    }
+
+    // コマンドパレットを開く（操作・タブ・連絡先・保存した検索を並べる）
+    pub fn open_palette(&mut self) {
+        let mut items: Vec<PaletteItem> = TAB_NAMES
+            .iter()
+            .enumerate()
+            .map(|(tab, name)| PaletteItem::new(format!("タブ: {}", name), "Tab", PaletteAction::Tab(tab)))
+            .collect();
+        // イベントリストの1文字のキーの操作（: q は除く）
+        for binding in KeyMode::Feed.bindings() {
+            if let &[KeyCode::Char(c)] = binding.keys {
+                if !binding.ctrl && !matches!(c, ':' | 'q') {
+                    items.push(PaletteItem::new(binding.description, binding.label, PaletteAction::Key(c)));
+                }
+            }
+        }
+        // : コマンド（引数が必要なものは入力欄に途中まで入れる）
+        for binding in COMMAND_KEYS {
+            let (key, action) = (binding.label, binding.description);
+            let Some(line) = key.strip_prefix(':') else {
+                continue;
+            };
+            let mut words = line.split_whitespace();
+            let name = words.next().unwrap_or_default();
+            match words.next() {
+                Some(arg) if arg.starts_with('<') => {
+                    items.push(PaletteItem::new(action, key, PaletteAction::Prefill(format!("{} ", name))));
+                }
+                Some(arg) if arg.contains('|') => {
+                    for choice in arg.split('|') {
+                        let command = format!("{} {}", name, choice);
+                        items.push(PaletteItem::new(format!("{}: {}", action, choice), format!(":{}", command), PaletteAction::Command(command)));
+                    }
+                }
+                Some(arg) if !arg.starts_with('[') => {
+                    let command = format!("{} {}", name, arg);
+                    items.push(PaletteItem::new(action, format!(":{}", command), PaletteAction::Command(command)));
+                }
+                _ => items.push(PaletteItem::new(action, format!(":{}", name), PaletteAction::Command(name.to_string()))),
+            }
+        }
+        // 連絡先（別名）との DM
+        for name in self.aliases.keys() {
+            items.push(PaletteItem::new(format!("DM: {}", name), format!(":dm {}", name), PaletteAction::Command(format!("dm {}", name))));
+        }
+        // 保存した検索（新しい順）
+        for query in self.history.searches.iter().rev() {
+            items.push(PaletteItem::new(format!("検索: {}", query), ":search", PaletteAction::Command(format!("search {}", query))));
+        }
+        self.palette = Some(Palette::new(items));
+    }
+
+    // コマンドパレットで選んだ項目を実行する（false なら終了）
+    fn run_palette_action(&mut self, action: PaletteAction) -> bool {
+        match action {
+            PaletteAction::Tab(tab) => {
+                self.close_detail();
+                self.input_mode = InputMode::Normal;
+                self.switch_tab(tab);
+            }
+            PaletteAction::Key(c) => {
+                // イベントリストで押したのと同じように扱う
+                self.close_detail();
+                self.input_mode = InputMode::Normal;
+                self.active_tab = 0;
+                return self.handle_key_event(KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE));
+            }
+            PaletteAction::Command(line) => return self.execute_command(&line),
+            PaletteAction::Prefill(line) => self.command_input = Some(line),
+        }
+        true
+    }
+
+    // ようこそ画面を閉じ、次回から表示しないように記録する
+    pub fn dismiss_welcome(&mut self) {
+        self.show_welcome = false;
+        let saved = AppConfig::load().and_then(|mut config| {
+            config.tui_onboarded = true;
+            config.save()
+        });
+        if let Err(e) = saved {
+            self.status = format!("設定の保存に失敗: {}", e);
+        }
     }
 
-    // 末尾へ
-    pub fn end(&mut self) {
-        if !self.events.is_empty() {
-            self.list_state.select(Some(self.events.len() - 1));
+    // 履歴を保存する（失敗してもステータスに表示するだけ）
+    fn save_history(&mut self) {
+        if let Err(e) = self.history.save() {
+            self.status = format!("履歴の保存に失敗: {}", e);
         }
     }
 
-    pub fn handle_key_event(&mut self, key: KeyEvent) -> bool {
-        // 電卓表示中の処理
-        if self.show_calculator {
-            match key.code {
-                // 電卓を閉じる
-                KeyCode::Esc | KeyCode::Char('q') => {
-                    self.show_calculator = false;
-                    return true;
-                }
-                // 数字入力
-                KeyCode::Char('0') | KeyCode::Char('1') | KeyCode::Char('2') |
-                KeyCode::Char('3') | KeyCode::Char('4') | KeyCode::Char('5') |
-                KeyCode::Char('6') | KeyCode::Char('7') | KeyCode::Char('8') |
-                KeyCode::Char('9') => {
-                    if let KeyCode::Char(digit) = key.code {
-                        self.calculator_input_digit(digit);
-                    }
-                    return true;
-                }
-                // 小数点
-                KeyCode::Char('.') => {
-                    self.calculator_input_dot();
-                    return true;
-                }
-                // 演算子
-                KeyCode::Char('+') => {
-                    self.calculator_operator('+');
-                    return true;
-                }
-                KeyCode::Char('-') => {
-                    self.calculator_operator('-');
-                    return true;
+    // : コマンドラインの内容を実行する（false なら終了）
+    pub fn execute_command(&mut self, line: &str) -> bool {
+        let line = line.trim();
+        let (name, arg) = line.split_once(' ').unwrap_or((line, ""));
+        let arg = arg.trim();
+        match name {
+            "" => {}
+            "q" | "quit" => return false,
+            "r" | "refresh" => self.refresh_events(),
+            "goto" => match parse_jst_datetime(arg) {
+                Ok(timestamp) => {
+                    self.active_tab = 0;
+                    self.pending_goto = Some(timestamp);
+                    self.status = format!("{} 付近のイベントを取得中...", arg);
                 }
-                KeyCode::Char('*') => {
-                    self.calculator_operator('*');
-                    return true;
+                Err(e) => self.status = e.to_string(),
+            },
+            "search" => {
+                self.active_tab = TAB_SCRAPBOOK;
+                self.reload_scrapbook();
+                self.scrap_query = arg.to_string();
+                self.scrap_state.select(Some(0));
+                push_entry(&mut self.history.searches, arg);
+                self.save_history();
+            }
+            "sort" if arg.is_empty() => self.toggle_article_sort(),
+            "sort" => match FeedSort::parse(arg) {
+                Ok(sort) => self.set_article_sort(sort),
+                Err(e) => self.status = e.to_string(),
+            },
+            "mute" => self.mute_selected_thread(),
+            "trash" => {
+                if !self.show_trash {
+                    self.toggle_trash();
                 }
-                KeyCode::Char('/') => {
-                    self.calculator_operator('/');
-                    return true;
+            }
+            "reminders" => {
+                if !self.show_reminders {
+                    self.toggle_reminders();
                 }
-                // イコール
-                KeyCode::Char('=') | KeyCode::Enter => {
-                    self.calculator_equals();
-                    return true;
+            }
+            "notepad" => {
+                if !self.show_notepad {
+                    self.toggle_notepad();
                 }
-                // クリア
-                KeyCode::Char('c') => {
-                    self.calculator_clear();
-                    return true;
+            }
+            "cards" => self.toggle_profile_cards(),
+            "dm" => self.open_conversation(arg),
+            "receipts" => self.toggle_receipt_peer(),
+            "relay" => self.set_feed_relay((!arg.is_empty()).then(|| arg.to_string())),
+            "kinds" => self.explore_kinds(),
+            "stats" => {
+                self.show_session_stats = !self.show_session_stats;
+                self.status = if self.show_session_stats {
+                    "セッションの統計を表示します".to_string()
+                } else {
+                    "セッションの統計を隠しました".to_string()
+                };
+            }
+            "session" => match arg {
+                "" | "stats" => self.status = self.session_stats.report().join(" | "),
+                "reset" => {
+                    self.session_stats.reset();
+                    self.status = "セッションの統計を数え直します".to_string();
                 }
-                _ => return true, // 他のキーは無視
+                _ => self.status = format!("不明なサブコマンドです: session {}", arg),
+            },
+            "drafts" => match arg {
+                "" => self.open_draft_picker(),
+                "sync" if self.public_terminal => self.status = "公開端末モードでは下書きを同期できません".to_string(),
+                "sync" => self.drafts_sync_requested = !self.refuse_read_only("下書きを同期"),
+                _ => self.status = format!("不明なサブコマンドです: drafts {}", arg),
+            },
+            "account" if arg.is_empty() => self.open_account_picker(),
+            "account" => self.request_account_switch(arg),
+            "open" => self.open_article(arg),
+            "columns" => match arg.split_once(' ').unwrap_or((arg, "")) {
+                ("", _) => self.set_column_mode(!self.column_mode),
+                ("add", spec) => self.add_column(spec),
+                ("remove", _) => self.remove_column(),
+                _ => self.status = format!("不明なサブコマンドです: columns {}", arg),
+            },
+            "help" => self.show_help = true,
+            _ => self.status = format!("不明なコマンドです: {}", name),
+        }
+        true
+    }
+
+    pub fn handle_key_event(&mut self, key: KeyEvent) -> bool {
+        // ようこそ画面は閉じる操作だけを受け付ける
+        if self.show_welcome {
+            if matches!(key.code, KeyCode::Enter | KeyCode::Esc | KeyCode::Char(' ') | KeyCode::Char('q')) {
+                self.dismiss_welcome();
             }
+            return true;
         }
 
-        // About画面表示中の処理
-        if self.show_about {
-            match key.code {
-                KeyCode::Esc | KeyCode::Char('q') => {
-                    self.show_about = false;
-                    return true;
-                }
-                _ => return true,
-            }
-        }
-
-        match self.input_mode {
-            InputMode::Normal => {
-                if self.detail_mode {
-                    // 詳細表示モード中
-                    match key.code {
-                        KeyCode::Esc | KeyCode::Char('q') => {
-                            self.detail_mode = false; // 詳細表示を閉じる
-                            self.detail_scroll = 0;   // スクロール位置リセット
-                            return true;
-                        }
-                        KeyCode::Up => {
-                            self.detail_scroll_up();
-                            return true;
-                        }
-                        KeyCode::Down => {
-                            self.detail_scroll_down();
-                            return true;
-                        }
-                        KeyCode::PageUp => {
-                            self.detail_page_up();
-                            return true;
-                        }
-                        KeyCode::PageDown => {
-                            self.detail_page_down();
-                            return true;
-                        }
-                        KeyCode::Home => {
-                            self.detail_scroll = 0;
-                            return true;
-                        }
-                        KeyCode::End => {
-                            // 特に大きな値を設定 - 実際のスクロール最大値は表示時に制限される
-                            self.detail_scroll = 1000;
-                            return true;
-                        }
-                        _ => return true, // 他のキーは無視
-                    }
-                }
+        // ヘルプ表示中の処理
+        if self.show_help {
+            if matches!(key.code, KeyCode::Esc | KeyCode::Enter | KeyCode::Char('?') | KeyCode::Char('q') | KeyCode::F(1)) {
+                self.show_help = false;
+            }
+            return true;
+        }
 
-                // 通常モード
-                match key.code {
-                    KeyCode::Char('q') => return false,
-                    KeyCode::Char('i') => self.toggle_input_mode(),
-                    KeyCode::Char('r') => self.status = "イベントを更新中...".to_string(),
-                    KeyCode::Char('a') => self.toggle_about(), // About画面表示
-                    KeyCode::Char('s') => self.toggle_calculator(), // cからsキーに変更
-                    KeyCode::Tab => {
-                        self.active_tab = (self.active_tab + 1) % 2;
-                        // 作成画面に切り替わったら自動で編集モードに
-                        if self.active_tab == 1 {
-                            self.input_mode = InputMode::Editing;
-                        }
-                    }
-                    KeyCode::Enter => {
-                        // Enterで詳細表示モードに
-                        if !self.events.is_empty() && self.active_tab == 0 {
-                            self.toggle_detail_mode();
-                        }
-                    }
-                    KeyCode::Up => self.previous(),
-                    KeyCode::Down => self.next(),
-                    KeyCode::Home => self.home(),
-                    KeyCode::End => self.end(),
-                    KeyCode::PageUp => self.page_up(),
-                    KeyCode::PageDown => self.page_down(),
-                    _ => {}
+        // F1 はどの画面でも、? は文字入力中以外でヘルプを開く
+        let mode = self.key_mode();
+        if key.code == KeyCode::F(1) || (key.code == KeyCode::Char('?') && mode.text_input().is_none()) {
+            self.show_help = true;
+            return true;
+        }
+
+        // Ctrl-P はどの画面からでもコマンドパレットを開く
+        if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('p') {
+            self.open_palette();
+            return true;
+        }
+
+        // 画面の表からキーを探し、なければ文字入力として扱う（どちらでもなければ無視する）
+        match mode.bindings().into_iter().find(|binding| binding.matches(&key)) {
+            Some(binding) => (binding.action)(self, key),
+            None => {
+                if let Some(text_input) = mode.text_input() {
+                    text_input(self, key);
                 }
             }
-            InputMode::Editing => match key.code {
-                KeyCode::Enter => {
-                    self.send_message();
-                }
-                KeyCode::Char(c) => {
-                    self.input.push(c);
-                }
-                KeyCode::Backspace => {
-                    self.input.pop();
-                }
-                KeyCode::Esc => {
-                    self.toggle_input_mode();
-                }
-                _ => {}
-            },
         }
-        true
+        !std::mem::take(&mut self.quit_requested)
+    }
+
+    // イベントを取得し直す
+    fn refresh_events(&mut self) {
+        self.status = "イベントを更新中...".to_string();
+        self.refresh_requested = true;
+    }
+
+    // DMを取得し直す（DMを扱えないモードではイベントの更新にする）
+    fn refresh_dms(&mut self) {
+        if self.public_terminal || self.read_only {
+            self.refresh_events();
+            return;
+        }
+        self.dm_refresh_requested = true;
+        self.status = "DMを取得中...".to_string();
+    }
+
+    // View メニューを開く（今の並び順を選択しておく）
+    fn open_view_menu(&mut self) {
+        let mut state = ListState::default();
+        state.select(FeedSort::ALL.iter().position(|s| *s == self.article_sort));
+        self.view_menu = Some(state);
+    }
+
+    // 返信先の投稿をスクロールする
+    fn scroll_reply_context(&mut self, down: bool) {
+        if !down {
+            self.reply_context_scroll = self.reply_context_scroll.saturating_sub(REPLY_CONTEXT_SCROLL);
+        } else if let Some(reply) = &self.reply_to {
+            let limit = reply.parent.content.lines().count() as u16 + 4;
+            self.reply_context_scroll = (self.reply_context_scroll + REPLY_CONTEXT_SCROLL).min(limit);
+        }
+    }
+
+    // コマンドパレットで選択中の項目を実行する
+    fn run_selected_palette_item(&mut self) {
+        let action = self.palette.as_ref().and_then(|palette| palette.selected_item()).map(|item| item.action.clone());
+        self.palette = None;
+        if let Some(action) = action {
+            self.quit_requested = !self.run_palette_action(action);
+        }
+    }
+
+    // : コマンドラインの入力（Enter で実行する）
+    fn edit_command_line(&mut self, key: KeyEvent) {
+        let Some(mut line) = self.command_input.take() else {
+            return;
+        };
+        match edit_line(&mut line, &mut self.command_recall, &self.history.commands, key) {
+            LineAction::Submit => {
+                self.command_recall.reset();
+                push_entry(&mut self.history.commands, &line);
+                self.save_history();
+                self.quit_requested = !self.execute_command(&line);
+            }
+            LineAction::Cancel => self.command_recall.reset(),
+            LineAction::Editing => self.command_input = Some(line),
+        }
+    }
+
+    // スクラップブックの検索語の入力
+    fn edit_scrap_query(&mut self, key: KeyEvent) {
+        match edit_line(&mut self.scrap_query, &mut self.scrap_recall, &self.history.searches, key) {
+            LineAction::Submit | LineAction::Cancel => {
+                self.scrap_searching = false;
+                self.scrap_recall.reset();
+                push_entry(&mut self.history.searches, &self.scrap_query);
+                self.save_history();
+            }
+            LineAction::Editing => {}
+        }
+        self.scrap_state.select(Some(0));
+    }
+
+    // 日時ジャンプの入力欄の日時へ移動する
+    fn submit_goto(&mut self) {
+        let Some(input) = self.goto_input.as_ref() else {
+            return;
+        };
+        match parse_jst_datetime(input) {
+            Ok(timestamp) => {
+                self.pending_goto = Some(timestamp);
+                self.status = format!("{} 付近のイベントを取得中...", input.trim());
+                self.goto_input = None;
+            }
+            Err(e) => self.status = e.to_string(),
+        }
+    }
+
+    // 「ゴミ箱を空にする…」の確認ダイアログを開く
+    fn confirm_empty_trash(&mut self) {
+        if self.trash.items.is_empty() {
+            self.status = "ゴミ箱は空です".to_string();
+        } else {
+            self.trash_confirm = true;
+        }
+    }
+
+    // 確認ダイアログで OK を選んだらゴミ箱を空にする
+    fn start_empty_trash(&mut self) {
+        self.trash_confirm = false;
+        self.empty_trash_requested = true;
+        self.status = "ゴミ箱を空にしています...".to_string();
+    }
+
+    // スヌーズする時刻の選択ダイアログで選んだ時刻までスヌーズする
+    fn choose_snooze(&mut self) {
+        let Some(state) = self.snooze_menu.take() else {
+            return;
+        };
+        self.snooze_selected(SnoozeDuration::ALL[state.selected().unwrap_or(0)]);
+    }
+
+    // 共有ダイアログで選んだ形式のリンクをコピーする
+    fn copy_share_link(&mut self) {
+        let Some(state) = self.share_menu.take() else {
+            return;
+        };
+        if let Some((format, link)) = self.share_links.get(state.selected().unwrap_or(0)) {
+            self.status = match copy_to_clipboard(link) {
+                Ok(()) => format!("{}をコピーしました: {}", format.label(), link),
+                Err(e) => format!("コピーに失敗: {}", e),
+            };
+        }
+    }
+
+    // リレーの選択ダイアログで選んだリレーのフィードを表示する（先頭はすべてのリレー）
+    fn choose_feed_relay(&mut self) {
+        let Some(state) = self.relay_picker.take() else {
+            return;
+        };
+        let relay = state.selected().unwrap_or(0).checked_sub(1).and_then(|i| self.relay_choices.get(i)).cloned();
+        self.set_feed_relay(relay);
+    }
+
+    // アカウントの選択ダイアログで選んだアカウントに切り替える
+    fn choose_account(&mut self) {
+        let Some(state) = self.account_picker.take() else {
+            return;
+        };
+        if let Some(name) = self.account_choices.get(state.selected().unwrap_or(0)).cloned() {
+            self.request_account_switch(&name);
+        }
+    }
+
+    // 下書きの選択ダイアログで選んだ下書きの続きを書く
+    fn choose_draft(&mut self) {
+        let Some(state) = self.draft_picker.take() else {
+            return;
+        };
+        if let Some(draft) = self.draft_choices.get(state.selected().unwrap_or(0)).cloned() {
+            self.load_draft(&draft);
+        }
+    }
+
+    // 下書きの選択ダイアログで選択中の下書きを削除する
+    fn delete_selected_draft(&mut self) {
+        let selected = self.draft_picker.as_ref().and_then(ListState::selected).unwrap_or(0);
+        let Some(draft) = self.draft_choices.get(selected).cloned() else {
+            return;
+        };
+        self.delete_draft(&draft.id);
+        self.status = format!("下書き「{}」を削除しました", draft.title());
+        if self.draft_choices.is_empty() {
+            self.draft_picker = None;
+        } else if let Some(state) = self.draft_picker.as_mut() {
+            state.select(Some(selected.min(self.draft_choices.len() - 1)));
+        }
+    }
+
+    // 目次で選んだ見出しへ移動する
+    fn choose_heading(&mut self) {
+        let Some(state) = self.toc_state.take() else {
+            return;
+        };
+        if let Some(heading) = self.article_headings().get(state.selected().unwrap_or(0)).cloned() {
+            self.detail_scroll = heading.line.min(u16::MAX as usize) as u16;
+            self.status = format!("§ {}", heading.title);
+        }
+    }
+
+    // View メニューで選んだ順にイベントリストを並べ替える
+    fn choose_article_sort(&mut self) {
+        let Some(state) = self.view_menu.take() else {
+            return;
+        };
+        self.active_tab = 0;
+        self.set_article_sort(FeedSort::ALL[state.selected().unwrap_or(0)]);
+    }
+
+    // kind の分布のダイアログで選んだ kind のイベントをフィードに表示する（先頭はすべての kind）
+    fn choose_feed_kind(&mut self) {
+        let Some(state) = self.kind_explorer.take() else {
+            return;
+        };
+        let kind = state.selected().unwrap_or(0).checked_sub(1).and_then(|i| self.kind_buckets.get(i)).map(|b| b.kind);
+        self.set_feed_kind(kind);
     }
 
     pub fn send_message(&mut self) {
-        if self.input.is_empty() {
+        if self.input.is_empty() || self.public_terminal || self.read_only {
             return;
         }
 
-        self.message_to_send = Some(self.input.clone());
+        let mut draft = NoteDraft {
+            content: self.input.clone(),
+            subject: Some(self.compose_subject.clone()),
+            hashtags: parse_hashtag_list(&self.compose_hashtags),
+            auto_hashtags: self.auto_hashtags,
+            mentions: Vec::new(),
+        };
+        // 登録されていない別名があれば送信せず、入力を続けられるようにする
+        let unknown = draft.apply_mentions(&self.aliases);
+        if !unknown.is_empty() {
+            self.status = format!("別名が登録されていません: @{}", unknown.join(", @"));
+            return;
+        }
+        // 時計が大きくずれていれば1度だけ警告し、もう一度 Enter で送信する
+        if let Some(warning) = self.clock_skew.and_then(skew_warning) {
+            if !self.clock_warned {
+                self.clock_warned = true;
+                self.status = format!("{}（もう一度 Enter で送信）", warning);
+                return;
+            }
+        }
+        self.message_to_send = Some(draft);
+        self.message_reply = self.reply_to.take().map(|reply| reply.parent);
+        self.reply_root_requested = None;
+        self.reply_context_scroll = 0;
         self.status = "メッセージを送信中...".to_string();
 
         // 既存の送信処理...
@@ -507,6 +2976,9 @@ impl App {
         // 送信成功処理
         self.status = "メッセージを送信し、イベントを取得しました".to_string();
         self.input.clear();
+        self.compose_subject.clear();
+        self.compose_hashtags.clear();
+        self.compose_field = ComposeField::Body;
         self.input_mode = InputMode::Normal;
 
         // 投稿作成画面からイベントリスト画面に自動で戻る
@@ -532,61 +3004,581 @@ impl App {
 }
 
 
-
-// 電卓の結果を初代Mac風に整形する関数
-fn format_calculator_result(value: f64) -> String {
-    if value.is_infinite() || value.is_nan() {
-        return "Error".to_string();
+
+// 1行入力のキー処理の結果
+enum LineAction {
+    Submit,  // Enter で確定
+    Cancel,  // Esc で取消
+    Editing, // 入力を続ける
+}
+
+// 履歴付きの1行入力のキー処理（: コマンドラインと検索欄で共通）
+// ↑/↓ で履歴を移動し、Ctrl-R で逆方向検索（続けて押すとさらに古い一致）
+fn edit_line(text: &mut String, recall: &mut Recall, entries: &[String], key: KeyEvent) -> LineAction {
+    if key.modifiers.contains(KeyModifiers::CONTROL) {
+        if key.code == KeyCode::Char('r') {
+            if recall.search.is_none() {
+                recall.start_search(text);
+            } else {
+                recall.search_older(entries, text);
+            }
+        }
+        return LineAction::Editing;
+    }
+
+    // 逆方向検索中は検索語を編集する
+    if recall.search.is_some() {
+        match key.code {
+            KeyCode::Enter => {
+                recall.search = None;
+                return LineAction::Submit;
+            }
+            KeyCode::Esc => recall.cancel_search(text),
+            KeyCode::Backspace => {
+                if let Some(query) = recall.search.as_mut() {
+                    query.pop();
+                }
+                recall.update_search(entries, text);
+            }
+            KeyCode::Char(c) => {
+                if let Some(query) = recall.search.as_mut() {
+                    query.push(c);
+                }
+                recall.update_search(entries, text);
+            }
+            // 矢印キーなどは見つかった内容のまま検索を終える
+            _ => recall.search = None,
+        }
+        return LineAction::Editing;
+    }
+
+    match key.code {
+        KeyCode::Enter => return LineAction::Submit,
+        KeyCode::Esc => return LineAction::Cancel,
+        KeyCode::Up => recall.older(entries, text),
+        KeyCode::Down => recall.newer(entries, text),
+        KeyCode::Backspace => {
+            text.pop();
+        }
+        KeyCode::Char(c) => text.push(c),
+        _ => {}
+    }
+    LineAction::Editing
+}
+
+// 逆方向検索中の入力欄の見出し
+fn recall_label(recall: &Recall) -> Option<String> {
+    recall.search.as_ref().map(|query| format!("(reverse-i-search)`{}': ", query))
+}
+
+// 電卓の結果を初代Mac風に整形する関数
+fn format_calculator_result(value: f64) -> String {
+    if value.is_infinite() || value.is_nan() {
+        return "Error".to_string();
+    }
+
+    // 整数部か小数部か判断して適切にフォーマット
+    if value == (value as i64) as f64 {
+        // 整数値の場合
+        format!("{}", value as i64)
+    } else {
+        // 小数値の場合（初代Mac電卓は小数点以下最大9桁）
+        let formatted = format!("{:.9}", value);
+        // 末尾の0を削除
+        formatted.trim_end_matches('0').trim_end_matches('.').to_string()
+    }
+}
+
+// パスワード入力処理（プロンプトは端末に直接表示し、標準出力には書き込まない）
+fn read_password() -> io::Result<String> {
+    rpassword::prompt_password("鍵を復号化するためのパスワードを入力してください: ")
+}
+
+// 取得したイベントの行き先（厳格な検証モードでは、署名の検証が終わってから反映する）
+#[derive(Debug, Clone, Copy)]
+pub enum VerifyTarget {
+    Cached,            // 起動時に読み込んだキャッシュ
+    Feed,              // フィードの更新
+    Around(Timestamp), // 日時ジャンプ
+    Article,           // naddr で開いた長文記事
+    Column(usize),     // 複数列のレイアウトの列
+}
+
+// 取得したイベントを行き先に反映する
+// 厳格な検証モードでは署名の検証を spawn_blocking で別スレッドに回し、終わったら receive_verified で反映する
+// 大量のイベントを検証する間も、画面の描画とキー操作を止めないため
+async fn deliver_events(app: &mut App, target: VerifyTarget, mut events: Vec<Event>) {
+    if !app.strict_verification {
+        show_events(app, target, events).await;
+        return;
+    }
+    app.status = format!("{}件のイベントの署名を検証中...", events.len());
+    let (tx, rx) = oneshot::channel();
+    tokio::task::spawn_blocking(move || {
+        let invalid = retain_verified(&mut events);
+        let _ = tx.send((events, invalid));
+    });
+    app.verifying.push((target, rx));
+}
+
+// 署名の検証が終わったイベントを受け取り、行き先に反映する
+async fn receive_verified(app: &mut App) {
+    let mut index = 0;
+    while index < app.verifying.len() {
+        match app.verifying[index].1.try_recv() {
+            Ok((events, invalid)) => {
+                let (target, _) = app.verifying.remove(index);
+                app.invalid_dropped += invalid;
+                show_events(app, target, events).await;
+            }
+            Err(oneshot::error::TryRecvError::Empty) => index += 1,
+            Err(oneshot::error::TryRecvError::Closed) => {
+                app.verifying.remove(index);
+            }
+        }
+    }
+}
+
+// イベントを行き先ごとに表示する
+async fn show_events(app: &mut App, target: VerifyTarget, events: Vec<Event>) {
+    match target {
+        VerifyTarget::Cached => show_cached_events(app, events),
+        VerifyTarget::Feed => show_feed_events(app, events).await,
+        VerifyTarget::Around(center) => show_events_around(app, center, events).await,
+        VerifyTarget::Article => show_article(app, events),
+        VerifyTarget::Column(column) => show_column_events(app, column, events),
+    }
+}
+
+// 前回保存したイベントを表示する（検証の間にフィードを取得し終えていれば、古いキャッシュで上書きしない）
+fn show_cached_events(app: &mut App, events: Vec<Event>) {
+    if !app.events.is_empty() {
+        return;
+    }
+    app.events = events
+        .into_iter()
+        .filter(|e| !app.mutes.is_author_muted(e) && !app.trash.contains(&e.id) && !app.snoozed.contains(&e.id))
+        .collect();
+    app.apply_article_sort();
+}
+
+// イベントの取得 - nostr-sdk APIの更新に対応
+async fn fetch_events(app: &mut App) -> Result<(), Box<dyn std::error::Error>> {
+    if let Some(client) = &app.client {
+        let filter = Filter::new()
+            .limit(100)
+            .kinds(app.feed_filter_kinds());
+
+        let events = query_feed(client, app.feed_relay.as_deref(), vec![filter]).await?;
+        deliver_events(app, VerifyTarget::Feed, events).await;
+    }
+    Ok(())
+}
+
+// 取得したフィードのイベントを表示する
+async fn show_feed_events(app: &mut App, events: Vec<Event>) {
+    // ミュートした投稿者のイベントと、他の投稿の翻訳版は表示しない
+    let variants = variant_ids(&events);
+    app.events = events
+        .into_iter()
+        .filter(|e| {
+            !app.mutes.is_author_muted(e) && !variants.contains(&e.id) && !app.trash.contains(&e.id) && !app.snoozed.contains(&e.id)
+        })
+        .collect();
+    app.apply_article_sort();
+    app.status = match &app.feed_relay {
+        Some(url) => format!("{} から{}件のイベントを取得しました", url, app.events.len()),
+        None => format!("{}件のイベントを取得しました", app.events.len()),
+    };
+    let future = app.events.iter().filter(|e| is_future(e, Timestamp::now())).count();
+    if future > 0 {
+        app.status.push_str(&format!("（作成日時が未来の{}件は今の日時として並べています）", future));
+    }
+
+    // 次回の起動時にすぐ表示できるよう保存する（リレーや kind を絞り込んだフィードは保存しない）
+    if app.feed_relay.is_none() && app.feed_kind.is_none() && !app.secondary_instance {
+        match save_cached_events(&app.events) {
+            Ok(true) => {}
+            Ok(false) => app.status.push_str("（別のインスタンスが使用中のため、キャッシュは保存していません）"),
+            Err(e) => app.status = format!("イベントの保存に失敗: {}", e),
+        }
+    }
+
+    fetch_translations(app).await;
+    app.profiles_requested = app.profile_cards;
+    app.sort_data_requested = app.article_sort.needs_counts() || app.article_sort == FeedSort::Plugin;
+}
+
+// 指定日時付近のイベントを取得し、その時刻に最も近いイベントを選択する
+async fn fetch_events_around(app: &mut App, center: Timestamp) -> Result<(), Box<dyn std::error::Error>> {
+    if let Some(client) = &app.client {
+        let (since, until) = around_window(center);
+        let kinds = app.feed_filter_kinds();
+
+        // 指定時刻の前後を別々のフィルターで取得し、片側だけに偏らないようにする
+        let filters = vec![
+            Filter::new().kinds(kinds.clone()).since(since).until(center).limit(50),
+            Filter::new().kinds(kinds).since(center).until(until).limit(50),
+        ];
+
+        let events = query_feed(client, app.feed_relay.as_deref(), filters).await?;
+        deliver_events(app, VerifyTarget::Around(center), events).await;
+    }
+    Ok(())
+}
+
+// 指定日時付近で取得したイベントを表示し、その時刻に最も近いイベントを選択する
+async fn show_events_around(app: &mut App, center: Timestamp, mut events: Vec<Event>) {
+    // 前後2つの範囲の両方に入ったイベントも隣り合うよう、同じ日時なら ID 順に並べてから重複を除く
+    events.sort_by(|a, b| b.created_at.cmp(&a.created_at).then(a.id.cmp(&b.id)));
+    events.dedup_by_key(|e| e.id);
+
+    // 指定時刻に最も近いイベントを中心として選択
+    let nearest = events
+        .iter()
+        .enumerate()
+        .min_by_key(|(_, e)| e.created_at.as_u64().abs_diff(center.as_u64()))
+        .map(|(i, _)| i)
+        .unwrap_or(0);
+
+    app.events = events;
+    app.list_state.select(Some(nearest));
+    app.status = format!("指定日時付近の{}件のイベントを取得しました", app.events.len());
+
+    fetch_translations(app).await;
+    app.profiles_requested = app.profile_cards;
+}
+
+// フィードのイベントを取得する（リレーの指定があればそのリレーだけに問い合わせる）
+// 登録していないリレーは、その場で追加して接続する
+// フィルターに合わないイベントは捨て、返したリレーの不一致の件数に数える（relay rank に表示）
+async fn query_feed(client: &Client, relay: Option<&str>, filters: Vec<Filter>) -> Result<Vec<Event>, Box<dyn std::error::Error>> {
+    let (events, violations) = match relay {
+        None => query_checked(client, filters, Duration::from_secs(10)).await?,
+        Some(url) => {
+            let url = dial(url)?;
+            let url = url.as_str();
+            client.add_relay(url).await?;
+            client.connect_relay(url).await?;
+            let relay = client.relay(url).await?;
+            let mut events = relay.get_events_of(filters.clone(), Duration::from_secs(10), FilterOptions::ExitOnEOSE).await?;
+            let dropped = retain_matching(&filters, &mut events);
+            (events, [(url.to_string(), dropped)].into_iter().filter(|(_, dropped)| *dropped > 0).collect())
+        }
+    };
+    let _ = record_violations(&violations);
+    Ok(events)
+}
+
+// naddr の長文記事を取得し、一覧の先頭に加えて詳細表示で開く
+async fn fetch_article(app: &mut App, coordinate: Coordinate) -> Result<(), Box<dyn std::error::Error>> {
+    let Some(client) = &app.client else {
+        return Ok(());
+    };
+    // naddr に含まれるリレーにも問い合わせる
+    for url in &coordinate.relays {
+        if client.add_relay(url.as_str()).await.is_ok() {
+            let _ = client.connect_relay(url.as_str()).await;
+        }
+    }
+    let filter = Filter::new()
+        .kind(coordinate.kind)
+        .authors(vec![coordinate.pubkey])
+        .identifier(coordinate.identifier.clone());
+    let events = client.get_events_of(vec![filter], Some(Duration::from_secs(10))).await?;
+    deliver_events(app, VerifyTarget::Article, events).await;
+    Ok(())
+}
+
+// naddr で取得した長文記事を開く
+fn show_article(app: &mut App, events: Vec<Event>) {
+    // 置き換え可能なイベントなので最も新しい版を使う
+    let Some(article) = events.into_iter().max_by_key(|e| e.created_at) else {
+        app.status = "記事の取得に失敗: 記事が見つかりません".to_string();
+        return;
+    };
+
+    app.close_detail();
+    app.events.retain(|e| article_address(e) != article_address(&article));
+    app.events.insert(0, article);
+    app.index_content_stats();
+    app.active_tab = 0;
+    app.list_state.select(Some(0));
+    app.toggle_detail_mode();
+}
+
+// 並べ替えに使う情報（返信数とZap額、または score_command のスコア）を取得して並べ替える
+async fn fetch_sort_data(app: &mut App) -> Result<(), Box<dyn std::error::Error>> {
+    let sort = app.article_sort;
+    if sort.needs_counts() {
+        if let Some(client) = &app.client {
+            app.feed_counts = fetch_feed_counts(client, &app.events).await?;
+        }
+    }
+    if sort == FeedSort::Plugin {
+        let command = AppConfig::load()?
+            .score_command
+            .ok_or("score_command が設定されていません（config set score_command '\"<コマンド>\"'）")?;
+        app.plugin_scores = plugin_scores(&command, &app.events)?;
+    }
+    app.apply_article_sort();
+    app.list_state.select(Some(0));
+    app.status = format!("{}に並べ替えました", sort.label());
+    Ok(())
+}
+
+// 最近のイベントを集めて kind ごとに数え、分布のダイアログを開く
+async fn fetch_kind_histogram(app: &mut App) -> Result<(), Box<dyn std::error::Error>> {
+    if let Some(client) = &app.client {
+        let filter = Filter::new().limit(KIND_SAMPLE_LIMIT);
+        let events = query_feed(client, app.feed_relay.as_deref(), vec![filter]).await?;
+        app.kind_buckets = histogram(&events);
+        app.status = format!("{}件のイベントを{}種類の kind に分類しました", events.len(), app.kind_buckets.len());
+        let mut state = ListState::default();
+        state.select(Some(0));
+        app.kind_explorer = Some(state);
+    }
+    Ok(())
+}
+
+// 表示中のイベントについて、優先言語の翻訳版を取得する
+async fn fetch_translations(app: &mut App) {
+    app.translations.clear();
+    if let (Some(client), Some(lang)) = (&app.client, &app.preferred_language) {
+        app.translations = fetch_preferred_variants(client, &app.events, lang).await.unwrap_or_default();
+    }
+    // 表示する本文が変わるので長文の統計を数え直す
+    app.content_stats.clear();
+    app.compact_previews.clear();
+    app.index_content_stats();
+}
+
+// 表示中のイベントの投稿者のプロフィールと、自分のフォロワーを取得する
+async fn fetch_profiles(app: &mut App) -> Result<(), Box<dyn std::error::Error>> {
+    if let Some(client) = &app.client {
+        let my_pubkey = app.keys.as_ref().map(|keys| keys.public_key());
+        app.profiles.refresh(client, &app.events, my_pubkey).await?;
+    }
+    Ok(())
+}
+
+// DMを取得して相手ごとの会話にまとめる（選択中の相手と、まだメッセージのない会話は残す）
+async fn fetch_direct_messages(app: &mut App) -> Result<(), Box<dyn std::error::Error>> {
+    let (Some(client), Some(keys)) = (&app.client, &app.keys) else {
+        return Ok(());
+    };
+    // ほかのインスタンスがキャッシュのロックを持っていれば保存しないだけで、表示は続ける
+    let (messages, _) = fetch_messages(client, keys).await?;
+
+    let mut peers: Vec<XOnlyPublicKey> = messages.iter().map(|m| m.peer).collect();
+    peers.extend(app.dm_conversations.iter().map(|c| c.peer));
+    peers.sort();
+    peers.dedup();
+    let gift_wrap_peers = fetch_gift_wrap_peers(client, peers).await?;
+
+    let selected = app.selected_conversation().map(|c| c.peer);
+    let mut conversations = group_conversations(messages, &gift_wrap_peers);
+    for empty in app.dm_conversations.iter().filter(|c| !conversations.iter().any(|n| n.peer == c.peer)) {
+        let scheme = if gift_wrap_peers.contains(&empty.peer) { DmScheme::GiftWrap } else { DmScheme::Nip04 };
+        conversations.insert(0, Conversation { peer: empty.peer, messages: Vec::new(), scheme });
     }
+    app.dm_conversations = conversations;
+    let index = selected.and_then(|peer| app.dm_conversations.iter().position(|c| c.peer == peer)).unwrap_or(0);
+    app.dm_state.select((!app.dm_conversations.is_empty()).then_some(index));
+    app.status = format!("{}件の会話を取得しました", app.dm_conversations.len());
+    check_peer_keys(app);
+    app.queue_read_receipt();
+    Ok(())
+}
 
-    // 整数部か小数部か判断して適切にフォーマット
-    if value == (value as i64) as f64 {
-        // 整数値の場合
-        format!("{}", value as i64)
-    } else {
-        // 小数値の場合（初代Mac電卓は小数点以下最大9桁）
-        let formatted = format!("{:.9}", value);
-        // 末尾の0を削除
-        formatted.trim_end_matches('0').trim_end_matches('.').to_string()
+// 会話の相手の別名や NIP-05 が前と別の鍵を指していないか調べる（変わっていればステータスで目立つように警告する）
+fn check_peer_keys(app: &mut App) {
+    let Ok(mut known) = KnownPeers::load() else {
+        return;
+    };
+    app.dm_key_changes.clear();
+    for conversation in &app.dm_conversations {
+        let handles = peer_handles(&conversation.peer, &app.aliases, &app.profiles);
+        let changes = known.observe(&handles, &conversation.peer);
+        if !changes.is_empty() {
+            app.dm_key_changes.insert(conversation.peer, changes);
+        }
+    }
+    let _ = known.save();
+    if let Some(changes) = app.dm_key_changes.values().next() {
+        let handles: Vec<&str> = changes.iter().map(|c| c.handle.as_str()).collect();
+        app.status = format!(
+            "⚠⚠⚠ 相手の鍵が変わりました（{}）。安全番号を確かめ、本人なら fingerprint --trust で記録し直してください",
+            handles.join("、")
+        );
     }
 }
 
-// パスワード入力処理
-fn read_password() -> io::Result<String> {
-    print!("鍵を復号化するためのパスワードを入力してください: ");
-    io::stdout().flush()?;
+// DMを送信する（どのリレーにも受理されなければアウトボックスに保存される）
+async fn send_direct_message(
+    app: &mut App,
+    peer: XOnlyPublicKey,
+    content: &str,
+    scheme: DmScheme,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let (Some(client), Some(keys)) = (&app.client, &app.keys) else {
+        return Err("クライアントまたは鍵が初期化されていません".into());
+    };
+    send_message_events(client, build_message_events(keys, peer, content, scheme)?).await?;
+    app.status = format!("DMを送信しました（{}）", scheme.label());
+    Ok(())
+}
 
-    match rpassword::read_password() {
-        Ok(pw) => Ok(pw),
-        Err(e) => Err(io::Error::new(io::ErrorKind::Other, e.to_string()))
+// 入力中・既読の通知を送る（一時的な通知なので、届かなくても再送しない）
+async fn send_dm_signals(app: &mut App) {
+    let signals = std::mem::take(&mut app.dm_signals);
+    if let (Some(client), Some(keys)) = (&app.client, &app.keys) {
+        for (peer, signal) in signals {
+            if let Ok(event) = signal_event(keys, peer, signal) {
+                let _ = client.send_event(event).await;
+            }
+        }
     }
 }
 
-// イベントの取得 - nostr-sdk APIの更新に対応
-async fn fetch_events(app: &mut App) -> Result<(), Box<dyn std::error::Error>> {
+// 詳細表示中のイベントへの反応を取得して集計する
+async fn fetch_note_stats(app: &mut App, event_id: EventId) -> Result<(), Box<dyn std::error::Error>> {
+    let Some(posted_at) = app.events.iter().find(|e| e.id == event_id).map(|e| e.created_at) else {
+        return Ok(());
+    };
+    // まずキャッシュ済みのイベントだけで、フォロー中の人の反応を表示しておく
+    app.detail_follows = cached_follow_interactions(app, event_id, &[]);
     if let Some(client) = &app.client {
-        let filter = Filter::new()
-            .limit(100)
-            .kinds(vec![Kind::TextNote]);
-
-        let events = client.get_events_of(vec![filter], None).await?;
+        if let Some(me) = app.keys.as_ref().map(|keys| keys.public_key()) {
+            if let Err(e) = app.profiles.refresh_following(client, me).await {
+                app.status = format!("フォローの取得に失敗: {}", e);
+            }
+        }
+        let events = client
+            .get_events_of(vec![interaction_filter(event_id)], Some(Duration::from_secs(5)))
+            .await?;
+        let bucket_secs = auto_bucket_secs(posted_at, Timestamp::now());
+        app.detail_stats = Some(aggregate(event_id, posted_at, &events, bucket_secs));
+        app.detail_follows = cached_follow_interactions(app, event_id, &events);
+    }
+    Ok(())
+}
 
-        // 時間順（降順）に並び替え
-        let mut sorted_events = events;
-        sorted_events.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+// キャッシュ済みのイベントと取得した反応から、投稿に反応したフォロー中の人を集める
+// フォローは使用中のアカウントのコンタクトリストのものだけを使う
+fn cached_follow_interactions(app: &App, event_id: EventId, fetched: &[nostr_sdk::Event]) -> Vec<FollowInteraction> {
+    let me = app.keys.as_ref().map(|keys| keys.public_key().to_string());
+    if me.is_none() || app.profiles.following_owner != me {
+        return Vec::new();
+    }
+    follow_interactions(event_id, app.events.iter().chain(fetched), &app.profiles.following)
+}
 
-        app.events = sorted_events;
-        app.status = format!("{}件のイベントを取得しました", app.events.len());
+// 自分宛てメンションのライブ購読を（張り直して）開始する
+// リレーごとに LIVE_SUBSCRIPTION の ID で上書きするので、ほかの購読はそのまま残り、起動時刻より後のメンションを受け取り続ける
+async fn subscribe_mentions(app: &mut App) {
+    if let (Some(client), Some(keys)) = (&app.client, &app.keys) {
+        let mut filters = Vec::new();
+        // feed_kinds に含めた一時的なイベントは、届いたときだけフィードに表示する
+        let ephemeral = ephemeral_feed_kinds(&app.feed_kinds);
+        if !ephemeral.is_empty() {
+            filters.push(Filter::new().kinds(ephemeral).since(Timestamp::now()));
+        }
+        if app.dm_receipts {
+            filters.push(signal_filter(keys.public_key()));
+        }
+        // 複数列のレイアウトでは、各列の新しいイベントも購読する
+        if app.column_mode {
+            let kinds = app.feed_filter_kinds();
+            filters.extend(
+                app.columns
+                    .iter()
+                    .filter_map(|c| c.source.filter(Some(keys.public_key()), kinds.clone()))
+                    .map(|f| f.since(Timestamp::now())),
+            );
+        }
+        // 否定の条件に対応したリレーには、ミュート中のスレッドを除いたメンションのフィルターを送る
+        let capabilities = CapabilityCache::load().unwrap_or_default();
+        for (url, relay) in client.relays().await {
+            let negative = capabilities.supports_negative_filters(url.as_str());
+            let mut relay_filters = mention_filters(keys.public_key(), &app.mutes, negative, app.session_started);
+            relay_filters.extend(filters.iter().cloned());
+            let id = InternalSubscriptionId::Custom(LIVE_SUBSCRIPTION.to_string());
+            let _ = relay.subscribe_with_internal_id(id, relay_filters, RelaySendOptions::new()).await;
+        }
     }
+}
 
+// 複数列のレイアウトの各列のイベントを取得し、各列の購読を張り直す
+async fn fetch_columns(app: &mut App) -> Result<(), Box<dyn std::error::Error>> {
+    if let Some(client) = app.client.clone() {
+        let me = app.keys.as_ref().map(|keys| keys.public_key());
+        let kinds = app.feed_filter_kinds();
+        for index in 0..app.columns.len() {
+            let Some(filter) = app.columns[index].source.filter(me, kinds.clone()) else {
+                continue;
+            };
+            let events = query_feed(&client, app.feed_relay.as_deref(), vec![filter.limit(COLUMN_FETCH_LIMIT)]).await?;
+            deliver_events(app, VerifyTarget::Column(index), events).await;
+        }
+    }
+    subscribe_mentions(app).await;
     Ok(())
 }
 
+// 複数列のレイアウトの列に取得したイベントを表示する（検証の間に列を削除していれば捨てる）
+fn show_column_events(app: &mut App, index: usize, mut events: Vec<Event>) {
+    events.retain(|e| !app.mutes.is_author_muted(e) && !app.mutes.is_thread_muted(e));
+    let Some(column) = app.columns.get_mut(index) else {
+        return;
+    };
+    column.set_events(events);
+    let total: usize = app.columns.iter().map(|c| c.events.len()).sum();
+    app.status = format!("{}列・{}件のイベントを取得しました", app.columns.len(), total);
+}
+
+// ライブ購読の受信口に溜まったイベントを処理する
+fn drain_notifications(app: &mut App) {
+    let mut incoming = Vec::new();
+    if let Some(receiver) = app.notifications.as_mut() {
+        while let Ok(notification) = receiver.try_recv() {
+            if let RelayPoolNotification::Event { event, .. } = notification {
+                app.session_stats.events_received += 1;
+                incoming.push(event);
+            }
+        }
+    }
+    for event in incoming {
+        app.handle_incoming_event(event);
+    }
+}
+
 // メッセージ送信 - nostr-sdk APIの更新に対応
-async fn send_message(app: &mut App, message: &str) -> Result<(), Box<dyn std::error::Error>> {
-    if let (Some(client), Some(_keys)) = (&app.client, &app.keys) {
-        let event_id = client.publish_text_note(message.to_string(), Vec::<Tag>::new()).await?;
-        app.status = format!("メッセージを送信しました: {}", event_id);
+async fn send_message(app: &mut App, draft: &NoteDraft) -> Result<(), Box<dyn std::error::Error>> {
+    let reply = app.message_reply.take();
+    if let (Some(client), Some(keys)) = (&app.client, &app.keys) {
+        // 返信なら NIP-10 のスレッドのタグを付け、スレッドの参加者にも通知する
+        let mut draft = draft.clone();
+        let mut tags = Vec::new();
+        if let Some(parent) = &reply {
+            for pubkey in thread_pubkeys(parent) {
+                if pubkey != keys.public_key() && !draft.mentions.contains(&pubkey) {
+                    draft.mentions.push(pubkey);
+                }
+            }
+            tags = reply_tags(parent, "")?;
+        }
+        tags.extend(draft.tags()?);
+        // リレーの制限と照らし合わせ、PoW を求めるリレーがあれば採掘してから署名する
+        let preflight = preflight_note(client, &draft.content, tags.len()).await?;
+        // どのリレーからも受理されなければエラー（イベントはアウトボックスに保存される）
+        let event = sign_checked(EventBuilder::new_text_note(&draft.content, tags), keys, &preflight)?;
+        let event_id = event.id;
+        let receipt = publish(client, event).await?;
+        app.status = format!("メッセージを送信しました: {}（{}）", event_id, receipt.summary());
+        if let Some((url, reason)) = preflight.refused.first() {
+            app.status.push_str(&format!(" ⚠ {}件のリレーは制限により拒否します（{}: {}）", preflight.refused.len(), url, reason));
+        }
     } else {
         app.status = "クライアントまたは鍵が初期化されていません".to_string();
     }
@@ -594,13 +3586,115 @@ async fn send_message(app: &mut App, message: &str) -> Result<(), Box<dyn std::e
     Ok(())
 }
 
+// ゴミ箱を空にする（自分の投稿を削除したものがあれば、削除要求を送って受理されてから消す）
+async fn empty_trash(app: &mut App) -> Result<(), Box<dyn std::error::Error>> {
+    let ids = app.trash.deleted_ids();
+    let mut summary = None;
+    if !ids.is_empty() {
+        let (Some(client), Some(keys)) = (&app.client, &app.keys) else {
+            return Err("クライアントまたは鍵が初期化されていません".into());
+        };
+        // どのリレーからも受理されなければエラー（ゴミ箱はそのまま残す）
+        let event = sign_event(deletion_builder(&ids)?, keys)?;
+        summary = Some(publish(client, event).await?.summary());
+    }
+    let count = app.trash.items.len();
+    app.trash.items.clear();
+    app.trash.save()?;
+    app.trash_state.select(None);
+    app.status = match summary {
+        Some(summary) => format!("ゴミ箱を空にしました（{}項目、削除要求 {}件: {}）", count, ids.len(), summary),
+        None => format!("ゴミ箱を空にしました（{}項目）", count),
+    };
+    Ok(())
+}
+
+// 手元とリレーのノートパッドを同期する（最後に書き換えた方を残す）
+async fn sync_tui_notepad(app: &mut App) {
+    let (Some(client), Some(keys)) = (&app.client, &app.keys) else {
+        return;
+    };
+    let synced = sync_notepad(client, keys, &mut app.notepad).await;
+    match synced {
+        Ok(NotePadSync::Downloaded) => app.status = "ほかの端末で書いたノートパッドを取り込みました".to_string(),
+        Ok(NotePadSync::Uploaded) => app.status = "ノートパッドをリレーに保存しました（暗号化済み）".to_string(),
+        Ok(NotePadSync::UpToDate) => {}
+        Err(e) => app.status = format!("ノートパッドを同期できません: {}", e),
+    }
+}
+
+// 自分の NIP-65 のリレーの一覧（kind 10002）からリレーを設定し、relays.json にも保存する
+async fn bootstrap_tui_relays(app: &mut App) {
+    let (Some(client), Some(keys)) = (&app.client, &app.keys) else {
+        return;
+    };
+    let relay_list = match fetch_relay_list(client, keys.public_key()).await {
+        Ok(Some(relay_list)) => relay_list,
+        Ok(None) => return,
+        Err(e) => {
+            app.status = format!("リレーの一覧を取得できません: {}", e);
+            return;
+        }
+    };
+    let urls: Vec<String> = parse_relay_list(&relay_list)
+        .into_iter()
+        .map(|entry| entry.url)
+        .filter(|url| check_relay_url(url).is_ok())
+        .collect();
+    if urls.is_empty() {
+        return;
+    }
+    for url in &urls {
+        if client.add_relay(url.as_str()).await.is_ok() {
+            let _ = client.connect_relay(url.as_str()).await;
+        }
+    }
+    app.relay_choices = urls.clone();
+    let count = urls.len();
+    app.status = match save_relays(&RelayConfig { relays: urls }) {
+        Ok(()) => format!("NIP-65 のリレーの一覧から {} 個のリレーを設定しました", count),
+        Err(e) => format!("NIP-65 のリレーの一覧から {} 個のリレーに接続しました（保存に失敗: {}）", count, e),
+    };
+    app.refresh_requested = true;
+}
+
+// 手元とリレーの下書きを同期する（同じ下書きは最後に編集した方を残す）
+async fn sync_tui_drafts(app: &mut App) {
+    let (Some(client), Some(keys)) = (&app.client, &app.keys) else {
+        return;
+    };
+    let synced = match DraftStore::load() {
+        Ok(mut store) => sync_drafts(client, keys, &mut store).await,
+        Err(e) => Err(e),
+    };
+    app.status = match synced {
+        Ok(report) if report.downloaded + report.uploaded == 0 => "下書きは同期済みです".to_string(),
+        Ok(report) => format!("下書きを同期しました（受信 {}件 / 送信 {}件）", report.downloaded, report.uploaded),
+        Err(e) => format!("下書きを同期できません: {}", e),
+    };
+}
+
 fn render_compose_mac_style(f: &mut Frame, app: &App, area: Rect) {
-    let title = format!("{} 投稿作成", MAC_NOTE);
+    let title = format!("{} 投稿作成", glyph(Glyph::Note));
     let window = mac_window_block(&title);
 
     f.render_widget(window.clone(), area);
     let inner_area = window.inner(area);
 
+    // 返信中なら、返信先を入力欄の上に表示する
+    let inner_area = match &app.reply_to {
+        Some(reply) => {
+            let height = (inner_area.height / 3).clamp(3, REPLY_CONTEXT_MAX_HEIGHT);
+            let parts = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Length(height), Constraint::Min(0)])
+                .split(inner_area);
+            render_reply_context(f, app, reply, parts[0]);
+            parts[1]
+        }
+        None => inner_area,
+    };
+
     // 投稿作成エリアを描画するテキスト要素を準備
     let mut text = Vec::new();
 
@@ -610,10 +3704,19 @@ fn render_compose_mac_style(f: &mut Frame, app: &App, area: Rect) {
                   Style::default().fg(Color::Black))
     ]));
 
-    // 公開鍵表示
-    let pubkey_display = match &app.my_public_key {
-        Some(pk) => pk.clone(),
-        None => "公開鍵が読み込まれていません".to_string(),
+    // 公開鍵表示（プロフィールの名前が分かれば名前も添える）
+    let my_name = app
+        .keys
+        .as_ref()
+        .and_then(|keys| app.profiles.get(&keys.public_key()))
+        .and_then(|profile| profile.label())
+        .map(str::to_string);
+    let pubkey_display = match (&app.my_public_key, my_name) {
+        _ if app.public_terminal => "公開端末モードのため投稿できません".to_string(),
+        (Some(pk), _) if app.read_only => format!("{}（閲覧専用のため投稿できません）", pk),
+        (Some(pk), Some(name)) => format!("{}（{}）", name, pk),
+        (Some(pk), None) => pk.clone(),
+        (None, _) => "公開鍵が読み込まれていません".to_string(),
     };
 
     text.push(Line::from(vec![
@@ -627,10 +3730,24 @@ fn render_compose_mac_style(f: &mut Frame, app: &App, area: Rect) {
     text.push(Line::from(""));  // 空行
     text.push(Line::from(divider));
 
+    // 件名とハッシュタグの欄（入力中の欄には ▶ を付ける）
+    let editing = app.input_mode == InputMode::Editing;
+    let marker = |field: ComposeField| if editing && app.compose_field == field { "▶ " } else { "  " };
+    let label_style = Style::default().fg(Color::Black).add_modifier(Modifier::BOLD);
+    let subject_label = format!("{}件名：", marker(ComposeField::Subject));
+    let hashtags_label = format!("{}ハッシュタグ：", marker(ComposeField::Hashtags));
+    text.push(Line::from(vec![
+        Span::styled(subject_label.clone(), label_style),
+        Span::raw(app.compose_subject.clone()),
+    ]));
+    text.push(Line::from(vec![
+        Span::styled(hashtags_label.clone(), label_style),
+        Span::raw(app.compose_hashtags.clone()),
+    ]));
+
     // 入力欄のタイトル
     text.push(Line::from(vec![
-        Span::styled("メッセージ内容：", 
-                  Style::default().fg(Color::Black).add_modifier(Modifier::BOLD))
+        Span::styled(format!("{}メッセージ内容：", marker(ComposeField::Body)), label_style)
     ]));
 
     // 入力内容を表示
@@ -662,25 +3779,57 @@ fn render_compose_mac_style(f: &mut Frame, app: &App, area: Rect) {
 
     // 編集モードの場合はカーソルを表示
     if let InputMode::Editing = app.input_mode {
-        // カーソル位置の計算を修正（数値を調整）
-        let base_lines = 5; // 公開鍵表示 + 空行 + 区切り線 + タイトル行
-
-        // 入力済み行数とカーソル位置を計算
-        let input_lines: Vec<&str> = app.input.split('\n').collect();
-        let cursor_y_offset = input_lines.len().saturating_sub(1);
-        let last_line = input_lines.last().unwrap_or(&"");
+        // 案内4行（公開鍵表示 + 空行 + 区切り線）の後に件名・ハッシュタグ・タイトル行が続く
+        let (cursor_x, cursor_y) = match app.compose_field {
+            ComposeField::Subject => (subject_label.width() + app.compose_subject.width(), 4),
+            ComposeField::Hashtags => (hashtags_label.width() + app.compose_hashtags.width(), 5),
+            ComposeField::Body => {
+                // 入力済み行数とカーソル位置を計算
+                let input_lines: Vec<&str> = app.input.split('\n').collect();
+                let last_line = input_lines.last().unwrap_or(&"");
+                (last_line.width(), 7 + input_lines.len().saturating_sub(1))
+            }
+        };
 
-        // カーソル位置を設定（Y位置を調整）
-        f.set_cursor(
-            inner_area.x + last_line.width() as u16,
-            inner_area.y + base_lines as u16 + cursor_y_offset as u16
-        );
+        f.set_cursor(inner_area.x + cursor_x as u16, inner_area.y + cursor_y as u16);
     }
 }
 
 
 
 
+// 返信先を表示する（スレッドの最初の投稿は畳んで1行、返信先は本文全体。PgUp/PgDn で入力欄とは別にスクロールする）
+fn render_reply_context(f: &mut Frame, app: &App, reply: &ReplyContext, area: Rect) {
+    let bold = Style::default().fg(Color::Black).add_modifier(Modifier::BOLD);
+    let mut lines = Vec::new();
+    match &reply.root {
+        Some(root) => {
+            lines.push(Line::from(Span::styled(
+                format!("┌ スレッドの最初: {} - {}", peer_label(app, &root.pubkey), format_jst(root.created_at)),
+                bold,
+            )));
+            let first = root.content.lines().find(|line| !line.trim().is_empty()).unwrap_or_default();
+            lines.push(Line::from(format!("│ {}", smart_truncate(first, 40))));
+        }
+        None if thread_root(&reply.parent) != reply.parent.id => lines.push(Line::from("┌ スレッドの最初の投稿を取得中...")),
+        None => {}
+    }
+    lines.push(Line::from(Span::styled(
+        format!("└ 返信先: {} - {}", peer_label(app, &reply.parent.pubkey), format_jst(reply.parent.created_at)),
+        bold,
+    )));
+    for line in reply.parent.content.lines() {
+        lines.push(Line::from(format!("  {}", line)));
+    }
+
+    let paragraph = Paragraph::new(lines)
+        .block(Block::default().borders(Borders::BOTTOM))
+        .style(Style::default().bg(Color::White).fg(Color::Black))
+        .wrap(Wrap { trim: false })
+        .scroll((app.reply_context_scroll, 0));
+    f.render_widget(paragraph, area);
+}
+
 // About画面を描画 - シンプル版
 fn render_about_screen(f: &mut Frame, _app: &App) {
     let area = f.size();
@@ -748,9 +3897,9 @@ fn render_about_screen(f: &mut Frame, _app: &App) {
         )),
         Line::from(""),
         // チェッカーボードパターン（3行）
-        Line::from("■ □ ■ □ ■ □ ■ □ ■ □"),
-        Line::from("□ ■ □ ■ □ ■ □ ■ □ ■"),
-        Line::from("■ □ ■ □ ■ □ ■ □ ■ □"),
+        Line::from(glyph(Glyph::Pattern1)),
+        Line::from(glyph(Glyph::Pattern2)),
+        Line::from(glyph(Glyph::Pattern1)),
         Line::from(""),
         Line::from(Span::styled(
             "ESC または q キーで閉じる",
@@ -765,13 +3914,91 @@ fn render_about_screen(f: &mut Frame, _app: &App) {
     f.render_widget(about_paragraph, inner_area);
 }
 
+// 起動画面（Happy Mac と「Welcome to Nostr」、初期化の進み具合）
+fn render_boot_screen(f: &mut Frame, app: &App, boot: &BootScreen) {
+    let area = f.size();
+    let width = 44.min(area.width);
+    let height = (HAPPY_MAC.len() as u16 + 8).min(area.height);
+    let boot_area = Rect::new(
+        area.x + area.width.saturating_sub(width) / 2,
+        area.y + area.height.saturating_sub(height) / 2,
+        width,
+        height,
+    );
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(HAPPY_MAC.len() as u16), // Happy Mac
+            Constraint::Length(1),                       // 空行
+            Constraint::Length(1),                       // Welcome to Nostr
+            Constraint::Length(1),                       // 空行
+            Constraint::Length(1),                       // 進捗バー
+            Constraint::Length(1),                       // 初期化中の段階
+            Constraint::Min(0),                          // 状態（パスワードの案内など）
+        ])
+        .split(boot_area);
+
+    let mac: Vec<Line> = HAPPY_MAC.iter().map(|line| Line::from(*line)).collect();
+    f.render_widget(Paragraph::new(mac).alignment(Alignment::Center), chunks[0]);
+    f.render_widget(
+        Paragraph::new(Span::styled("Welcome to Nostr", Style::default().add_modifier(Modifier::BOLD)))
+            .alignment(Alignment::Center),
+        chunks[2],
+    );
+    f.render_widget(
+        Gauge::default()
+            .gauge_style(Style::default().fg(Color::Black).bg(Color::White))
+            .ratio(boot.progress().clamp(0.0, 1.0))
+            .label(""),
+        chunks[4],
+    );
+    f.render_widget(Paragraph::new(boot.step.label()).alignment(Alignment::Center), chunks[5]);
+    f.render_widget(
+        Paragraph::new(app.status.as_str())
+            .style(Style::default().fg(Color::DarkGray))
+            .alignment(Alignment::Center)
+            .wrap(Wrap { trim: true }),
+        chunks[6],
+    );
+}
+
+// 終了画面（キーを押すか少し待つと閉じる）
+fn show_shutdown_screen(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>) -> io::Result<()> {
+    terminal.draw(|f| {
+        let area = f.size();
+        f.render_widget(Block::default().style(Style::default().bg(Color::Black)), area);
+        let width = 44.min(area.width);
+        let height = 5.min(area.height);
+        let dialog = Rect::new(
+            area.x + area.width.saturating_sub(width) / 2,
+            area.y + area.height.saturating_sub(height) / 2,
+            width,
+            height,
+        );
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .border_type(BorderType::Double)
+            .style(Style::default().bg(Color::White).fg(Color::Black));
+        let text = vec![
+            Line::from(Span::styled("It is now safe to turn off", Style::default().add_modifier(Modifier::BOLD))),
+            Line::from(Span::styled("your client.", Style::default().add_modifier(Modifier::BOLD))),
+            Line::from("クライアントを終了しました"),
+        ];
+        f.render_widget(Paragraph::new(text).block(block).alignment(Alignment::Center), dialog);
+    })?;
+    if event::poll(SHUTDOWN_SCREEN_DURATION)? {
+        let _ = event::read()?;
+    }
+    Ok(())
+}
+
 
 // 詳細表示
 fn render_event_detail_mac_style(f: &mut Frame, app: &App, area: Rect) {
-    if let Some(selected) = app.list_state.selected() {
-        if selected < app.events.len() {
-            let event = &app.events[selected];
-
+    // 本文は開いたときに用意したもの（描画のたびに複製しない）
+    if let Some(detail) = app.detail_content.as_ref() {
+        if let Some(event) = app.detail_event() {
             // Mac風ダイアログウィンドウ
             let dialog_width = area.width.saturating_sub(10).min(80).max(60);
             let dialog_height = area.height.saturating_sub(8).min(30).max(20);
@@ -800,7 +4027,11 @@ fn render_event_detail_mac_style(f: &mut Frame, app: &App, area: Rect) {
             f.render_widget(shadow, shadow_area);
 
             // ダイアログ本体
-            let dialog_title = format!("{} Event Detail" ,MAC_DOCUMENT ); 
+            // 長文記事はタイトルを表示する
+            let dialog_title = match article_title(event).filter(|_| event.kind == Kind::LongFormTextNote) {
+                Some(title) => format!("{} {}", glyph(Glyph::Document), smart_truncate(&title, dialog_width.saturating_sub(8) as usize)),
+                None => format!("{} Event Detail", glyph(Glyph::Document)),
+            };
             let dialog_block = Block::default()
                 .title(Span::styled(
                     format!(" {} ", dialog_title),
@@ -818,8 +4049,10 @@ fn render_event_detail_mac_style(f: &mut Frame, app: &App, area: Rect) {
             let chunks = Layout::default()
                 .direction(Direction::Vertical)
                 .constraints([
-                    Constraint::Length(inner_area.height.min(4)), // メタデータ用
+                    Constraint::Length(inner_area.height.min(6)), // メタデータ用
                     Constraint::Min(1),    // 内容表示用
+                    Constraint::Length(3), // 反応の集計用
+                    Constraint::Length(1), // フォロー中の人の反応用
                     Constraint::Length(1), // 操作説明用
                 ])
                 .split(inner_area);
@@ -832,6 +4065,7 @@ fn render_event_detail_mac_style(f: &mut Frame, app: &App, area: Rect) {
 
             // 公開鍵
             let pubkey_str = match event.pubkey.to_bech32() {
+                _ if app.is_redacted(&event.pubkey) => REDACTED_LABEL.to_string(),
                 Ok(pk) => {
                     if pk.len() > 20 {
                         let start = pk.get(0..10).unwrap_or("").to_string();
@@ -864,6 +4098,11 @@ let jst_date = utc_date.with_timezone(&jst_offset);
 
 // 日本時間でフォーマット
 let date = jst_date.format("%Y-%m-%d %H:%M:%S (JST)").to_string();
+            let date = if is_future(event, Timestamp::now()) {
+                format!("{} ⏰ 未来の日時です（投稿者の時計がずれている可能性があります）", date)
+            } else {
+                date
+            };
 
 metadata_text.push(Line::from(vec![
     Span::styled("日時: ", Style::default().fg(Color::Black).add_modifier(Modifier::BOLD)),
@@ -909,6 +4148,30 @@ metadata_text.push(Line::from(vec![
                 Span::raw(short_sig),
             ]));
 
+            // 本文の統計
+            let stats = detail.stats;
+            metadata_text.push(Line::from(vec![
+                Span::styled("統計: ", Style::default().fg(Color::Black).add_modifier(Modifier::BOLD)),
+                Span::raw(format_content_stats(&stats)),
+            ]));
+
+            // 言語ラベルと翻訳版の表示状況
+            let language = match (app.translations.get(&event.id), event_language(event)) {
+                (Some(variant), original) => format!(
+                    "{}（{}の翻訳版を表示中）",
+                    event_language(variant).unwrap_or_default(),
+                    original.unwrap_or_else(|| "元の投稿".to_string())
+                ),
+                (None, Some(lang)) => lang,
+                (None, None) => String::new(),
+            };
+            if !language.is_empty() {
+                metadata_text.push(Line::from(vec![
+                    Span::styled("言語: ", Style::default().fg(Color::Black).add_modifier(Modifier::BOLD)),
+                    Span::raw(language),
+                ]));
+            }
+
             let metadata_paragraph = Paragraph::new(metadata_text)
                 .style(Style::default().bg(Color::White).fg(Color::Black));
 
@@ -918,19 +4181,19 @@ metadata_text.push(Line::from(vec![
             let content_area = chunks[1];
 
             // 区切り線を動的に生成 - ウィンドウ幅に合わせる
-            let divider_char = '─'; // または MAC_DIVIDER に含まれる文字
+            let divider_char = '─'; // または Glyph::Divider に含まれる文字
             let divider_count = content_area.width as usize;
             let divider_str: String = std::iter::repeat(divider_char).take(divider_count).collect();
             let divider = Line::from(divider_str);
 
-            // 改行で分割した内容
-            let content_lines: Vec<&str> = event.content.split('\n').collect();
+            // 改行で分割した内容（行の範囲は開いたときに計算済み）
+            let line_count = detail.line_count();
 
             // スクロールに対応して表示範囲を制限 - 型の修正
             let max_visible_lines = content_area.height.saturating_sub(2) as usize; // ヘッダー分を引く
 
             // 型の不一致を修正
-            let max_scroll = content_lines.len().saturating_sub(1);
+            let max_scroll = line_count.saturating_sub(1);
             let max_scroll_u16 = if max_scroll > u16::MAX as usize {
                 u16::MAX
             } else {
@@ -944,24 +4207,30 @@ metadata_text.push(Line::from(vec![
                 divider.clone(),
             ];
 
-            for line in content_lines.iter().skip(start_line).take(max_visible_lines) {
-                text.push(Line::from(Span::raw(line.to_string())));
+            // 見えている行だけを借りて渡す
+            let visible_end = start_line.saturating_add(max_visible_lines).min(line_count);
+            for index in start_line..visible_end {
+                text.push(Line::from(Span::raw(detail.line(index))));
             }
 
             // スクロール情報 - 安全に計算
-            if content_lines.len() > max_visible_lines {
-                let scroll_percent = if content_lines.len() > 0 {
-                    (start_line as f64 / content_lines.len().saturating_sub(1).max(1) as f64 * 100.0).min(100.0) as u32
+            if line_count > max_visible_lines {
+                let scroll_percent = if line_count > 0 {
+                    (start_line as f64 / line_count.saturating_sub(1).max(1) as f64 * 100.0).min(100.0) as u32
                 } else {
                     0
                 };
 
-                let scroll_info = format!(
+                let mut scroll_info = format!(
                     "[{}/{}行目 ({}%) 表示中]",
-                    start_line.saturating_add(1).min(content_lines.len()),
-                    content_lines.len(),
+                    start_line.saturating_add(1).min(line_count),
+                    line_count,
                     scroll_percent
                 );
+                // 長文記事は今読んでいる見出しも表示する
+                if let Some(heading) = app.article_headings().iter().rev().find(|h| h.line <= start_line) {
+                    scroll_info.push_str(&format!(" § {}", heading.title));
+                }
 
                 text.push(Line::from(Span::styled(
                     scroll_info,
@@ -975,13 +4244,19 @@ metadata_text.push(Line::from(vec![
 
             f.render_widget(paragraph, content_area);
 
+            // 反応の集計（合計とスパークライン）
+            render_note_stats(f, app.detail_stats.as_ref(), chunks[2]);
+
+            // フォロー中の人の反応（「フォロー中の○○さんがいいねしました」に相当）
+            render_follow_interactions(f, app, chunks[3]);
+
             // 操作説明
             f.render_widget(
                 Paragraph::new(Line::from(Span::styled(
-                    "↑↓: スクロール | Esc: 戻る",
+                    keymap_hint(DETAIL_KEYS),
                     Style::default().fg(Color::Black).add_modifier(Modifier::BOLD)
                 ))),
-                chunks[2]
+                chunks[4]
             );
         }
     }
@@ -991,96 +4266,514 @@ metadata_text.push(Line::from(vec![
 
 
 
-// イベントリスト表示
-fn render_events_mac_style(f: &mut Frame, app: &App, area: Rect) {
-    if app.detail_mode {
-        // 詳細表示モード - Mac風ダイアログとして表示
-        render_event_detail_mac_style(f, app, area);
-        return;
+// 投稿に反応したフォロー中の人を1行で描画（例: "👥 フォロー中: alice ❤🔁, bob 💬"）
+fn render_follow_interactions(f: &mut Frame, app: &App, area: Rect) {
+    if app.detail_follows.is_empty() {
+        return;
+    }
+    let people: Vec<String> = app
+        .detail_follows
+        .iter()
+        .map(|interaction| {
+            let mut marks: String = interaction
+                .reactions
+                .iter()
+                .map(|r| if r == "+" { "❤" } else { r.as_str() })
+                .collect();
+            if interaction.reposted {
+                marks.push('🔁');
+            }
+            if interaction.replied {
+                marks.push('💬');
+            }
+            format!("{} {}", peer_label(app, &interaction.pubkey), marks)
+        })
+        .collect();
+    let text = format!("👥 フォロー中の{}人: {}", people.len(), people.join(", "));
+    let text = smart_truncate(&text, area.width as usize);
+    f.render_widget(
+        Paragraph::new(text).style(Style::default().bg(Color::White).fg(Color::Black)),
+        area,
+    );
+}
+
+// 反応の集計をスパークライン付きで描画
+fn render_note_stats(f: &mut Frame, stats: Option<&NoteStats>, area: Rect) {
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(1), Constraint::Min(1)])
+        .split(area);
+
+    let Some(stats) = stats else {
+        f.render_widget(
+            Paragraph::new("反応を集計中...")
+                .style(Style::default().bg(Color::White).fg(Color::Black).add_modifier(Modifier::ITALIC)),
+            rows[0],
+        );
+        return;
+    };
+
+    let summary = format!(
+        "💬 {}  🔁 {}  ❤ {}  ⚡ {} ({} sats)  👥 {}",
+        stats.replies, stats.reposts, stats.reactions, stats.zaps, stats.zap_sats, stats.unique_pubkeys
+    );
+    f.render_widget(
+        Paragraph::new(summary)
+            .style(Style::default().bg(Color::White).fg(Color::Black).add_modifier(Modifier::BOLD)),
+        rows[0],
+    );
+
+    // 表示幅に収まる直近の時間帯だけを描く
+    let data: Vec<u64> = stats.buckets.iter().map(|b| b.total()).collect();
+    let visible = &data[data.len().saturating_sub(rows[1].width as usize)..];
+    let sparkline = Sparkline::default()
+        .data(visible)
+        .style(Style::default().bg(Color::White).fg(Color::Black));
+    f.render_widget(sparkline, rows[1]);
+}
+
+// イベントリスト表示
+fn render_events_mac_style(f: &mut Frame, app: &App, area: Rect) {
+    if app.detail_mode {
+        // 詳細表示モード - Mac風ダイアログとして表示
+        render_event_detail_mac_style(f, app, area);
+        return;
+    }
+
+    if app.column_mode {
+        render_columns(f, app, area);
+        return;
+    }
+
+    // 通常表示モード
+    // 修正後（イベント数を表示しない場合）
+let title = match app.article_sort {
+    FeedSort::Newest => format!("{} Events", glyph(Glyph::Folder)),
+    sort => format!("{} Events ({})", glyph(Glyph::Folder), sort.label()),
+};
+    // kind やリレーを絞り込んでいるときはタイトルに表示する
+    let title = match app.feed_kind {
+        Some(kind) => format!("{} [kind {}: {}]", title, kind, kind_name(kind)),
+        None => title,
+    };
+    let title = match &app.feed_relay {
+        Some(url) => format!("{} @ {}", title, url),
+        None => title,
+    };
+
+    let window = mac_window_block(&title);
+
+    // 白背景に設定
+    f.render_widget(window.clone(), area);
+    let inner_area = window.inner(area);
+
+    if app.events.is_empty() {
+        let message = format!("{} No events. Press R to refresh.", glyph(Glyph::HappyMac));
+        let paragraph = Paragraph::new(message)
+            .style(Style::default()
+                .bg(Color::White)
+                .fg(Color::Black)
+                .add_modifier(Modifier::BOLD)) // Chicago風
+            .alignment(Alignment::Center);
+        f.render_widget(paragraph, inner_area);
+        return;
+    }
+
+    // イベントリスト項目を作成
+    let mut list_items = Vec::new();
+    let mut previous_author = None;
+    for event in &app.events {
+        // 公開鍵表示（短縮形）
+        let pubkey = app.short_author(&event.pubkey);
+
+        // 日時表示 - Macスタイルの短い形式 (JSTに変換)
+let utc_date = DateTime::<Utc>::from_timestamp(event.created_at.as_u64() as i64, 0)
+    .unwrap_or_default();
+let jst_offset = FixedOffset::east_opt(9 * 3600).unwrap();
+let jst_date = utc_date.with_timezone(&jst_offset);
+let date = jst_date.format("%m/%d/%y %H:%M").to_string();
+        // 未来の日時のイベントは投稿者の時計がずれているので印を付ける
+        let date = if is_future(event, Timestamp::now()) { format!("⏰{}", date) } else { date };
+
+
+        // コンテンツのプレビュー - スマート切り捨て処理
+let content_preview = app.preview_content(event, 137);
+
+
+        // Mac風のリストアイテム (Chicago風アイコン使用)
+        let mut lines = if app.profile_cards {
+            // 同じ投稿者が続く間は最初の投稿にだけプロフィールカードを付ける
+            let mut lines = Vec::new();
+            if previous_author != Some(event.pubkey) {
+                lines.extend(profile_card_lines(app, &event.pubkey, &pubkey));
+            }
+            lines.push(Line::from(Span::styled(
+                format!("  {} {}", glyph(Glyph::Document), date),
+                Style::default().fg(Color::Black),
+            )));
+            lines.push(Line::from(Span::styled(
+                format!("  {}", content_preview),
+                Style::default().fg(Color::Black),
+            )));
+            lines
+        } else {
+            vec![
+                Line::from(vec![
+                    Span::styled(format!("{} {} - ",glyph(Glyph::Document),  pubkey), 
+                                Style::default().fg(Color::Black).add_modifier(Modifier::BOLD)), // Chicago風
+                    Span::styled(date, Style::default().fg(Color::Black)),
+                ]),
+                Line::from(Span::styled(content_preview, 
+                        Style::default().fg(Color::Black))),
+            ]
+        };
+        previous_author = Some(event.pubkey);
+
+        // 長文には読了時間などの統計を添える
+        if let Some(stats) = app.content_stats.get(&event.id) {
+            lines.push(Line::from(Span::styled(
+                format_content_stats(stats),
+                Style::default().fg(Color::Black).add_modifier(Modifier::ITALIC),
+            )));
+        }
+
+        lines.push(Line::from(""));  // 項目間の空白行
+        let item = ListItem::new(lines);
+
+        list_items.push(item);
+    }
+
+    // ハイライト用の文字列を変数に格納し、ライフタイムを延長
+    let highlight_prefix = format!("{} ", glyph(Glyph::Checkmark));
+
+    let events_list = List::new(list_items)
+        .style(Style::default().bg(Color::White).fg(Color::Black))
+        .highlight_style(
+            Style::default()
+                .bg(Color::Black)
+                .fg(Color::White)
+                .add_modifier(Modifier::BOLD)) // 選択項目はChicago風に強調
+        .highlight_symbol(&highlight_prefix);
+
+    f.render_stateful_widget(events_list, inner_area, &mut app.list_state.clone());
+}
+
+// 複数列のレイアウト（幅に収まる列数だけ、選択中の列が見える範囲を並べる）
+fn render_columns(f: &mut Frame, app: &App, area: Rect) {
+    let visible = ((area.width / MIN_COLUMN_WIDTH).max(1) as usize).min(app.columns.len());
+    if visible == 0 {
+        return;
+    }
+    let start = (app.column_focus + 1).saturating_sub(visible);
+    let chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints(vec![Constraint::Ratio(1, visible as u32); visible])
+        .split(area);
+
+    for (offset, chunk) in chunks.iter().enumerate() {
+        let index = start + offset;
+        let column = &app.columns[index];
+        let focused = index == app.column_focus;
+        let title = format!("{} ({}/{})", column.source.title(), index + 1, app.columns.len());
+        // 選択中の列は太い枠で示す
+        let window = mac_window_block(&title).border_type(if focused { BorderType::Thick } else { BorderType::Rounded });
+        f.render_widget(window.clone(), *chunk);
+        let inner_area = window.inner(*chunk);
+
+        if column.events.is_empty() {
+            let paragraph = Paragraph::new(format!("{} No events.", glyph(Glyph::HappyMac)))
+                .style(Style::default().bg(Color::White).fg(Color::Black))
+                .alignment(Alignment::Center);
+            f.render_widget(paragraph, inner_area);
+            continue;
+        }
+
+        let preview_limit = (inner_area.width as usize).saturating_mul(2).max(20);
+        let list_items: Vec<ListItem> = column
+            .events
+            .iter()
+            .map(|event| {
+                ListItem::new(vec![
+                    Line::from(vec![
+                        Span::styled(
+                            format!("{} - ", app.short_author(&event.pubkey)),
+                            Style::default().fg(Color::Black).add_modifier(Modifier::BOLD),
+                        ),
+                        Span::styled(format_jst(event.created_at), Style::default().fg(Color::Black)),
+                    ]),
+                    Line::from(Span::styled(app.preview_content(event, preview_limit), Style::default().fg(Color::Black))),
+                    Line::from(""),
+                ])
+            })
+            .collect();
+
+        // 選択の強調は選択中の列だけに表示する
+        let highlight_style = if focused {
+            Style::default().bg(Color::Black).fg(Color::White).add_modifier(Modifier::BOLD)
+        } else {
+            Style::default().add_modifier(Modifier::BOLD)
+        };
+        let list = List::new(list_items)
+            .style(Style::default().bg(Color::White).fg(Color::Black))
+            .highlight_style(highlight_style);
+        f.render_stateful_widget(list, inner_area, &mut column.state.clone());
     }
+}
 
-    // 通常表示モード
-    // 修正後（イベント数を表示しない場合）
-let title = format!("{} Events", MAC_FOLDER);
+// 投稿者のプロフィールカード（1行目: 表示名とフォロワー表示、2行目: NIP-05 または npub）
+fn profile_card_lines(app: &App, author: &XOnlyPublicKey, short_npub: &str) -> Vec<Line<'static>> {
+    // 伏せる公開鍵は表示名や NIP-05 からも特定できないようにする
+    let profile = app.profiles.get(author).filter(|_| !app.is_redacted(author));
+    let name = profile.and_then(|p| p.label()).unwrap_or(short_npub).to_string();
+    let handle = profile
+        .and_then(|p| p.nip05.as_deref())
+        .filter(|nip05| !nip05.is_empty())
+        .map(|nip05| nip05.trim_start_matches("_@").to_string())
+        .unwrap_or_else(|| short_npub.to_string());
+
+    let mut header = vec![Span::styled(
+        format!("{} {}", glyph(Glyph::HappyMac), name),
+        Style::default().fg(Color::Black).add_modifier(Modifier::BOLD),
+    )];
+    if app.profiles.is_follower(author) && !app.public_terminal {
+        header.push(Span::styled(" [フォロワー]", Style::default().fg(Color::Black)));
+    }
+    vec![
+        Line::from(header),
+        Line::from(Span::styled(
+            format!("   {}", handle),
+            Style::default().fg(Color::Black).add_modifier(Modifier::ITALIC),
+        )),
+    ]
+}
 
+// スクラップブック表示
+fn render_scrapbook_mac_style(f: &mut Frame, app: &App, area: Rect) {
+    let title = format!("{} Scrapbook", glyph(Glyph::Clip));
     let window = mac_window_block(&title);
 
-    // 白背景に設定
     f.render_widget(window.clone(), area);
     let inner_area = window.inner(area);
 
-    if app.events.is_empty() {
-        let message = format!("{} No events. Press R to refresh.", MAC_HAPPY_MAC);
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(2), // 検索欄
+            Constraint::Min(1),    // 項目リスト
+        ])
+        .split(inner_area);
+
+    // 検索欄
+    let search_label = match recall_label(&app.scrap_recall) {
+        Some(label) => label,
+        None if app.scrap_searching => "検索 (Enterで確定): ".to_string(),
+        None => "検索 (/): ".to_string(),
+    };
+    let search_line = Paragraph::new(vec![
+        Line::from(vec![
+            Span::styled(search_label.clone(), Style::default().fg(Color::Black).add_modifier(Modifier::BOLD)),
+            Span::raw(app.scrap_query.clone()),
+        ]),
+        Line::from("─".repeat(chunks[0].width as usize)),
+    ])
+    .style(Style::default().bg(Color::White).fg(Color::Black));
+    f.render_widget(search_line, chunks[0]);
+
+    if app.scrap_searching {
+        f.set_cursor(
+            chunks[0].x + search_label.width() as u16 + app.scrap_query.width() as u16,
+            chunks[0].y,
+        );
+    }
+
+    let entries = app.scrapbook.search(&app.scrap_query);
+    if entries.is_empty() {
+        let message = if app.scrapbook.entries.is_empty() {
+            format!("{} スクラップブックは空です。clip コマンドで保存できます。", glyph(Glyph::HappyMac))
+        } else {
+            format!("{} 一致する項目がありません", glyph(Glyph::HappyMac))
+        };
         let paragraph = Paragraph::new(message)
-            .style(Style::default()
-                .bg(Color::White)
-                .fg(Color::Black)
-                .add_modifier(Modifier::BOLD)) // Chicago風
+            .style(Style::default().bg(Color::White).fg(Color::Black).add_modifier(Modifier::BOLD))
             .alignment(Alignment::Center);
-        f.render_widget(paragraph, inner_area);
+        f.render_widget(paragraph, chunks[1]);
         return;
     }
 
-    // イベントリスト項目を作成
     let mut list_items = Vec::new();
-    for event in &app.events {
-        // 公開鍵表示（短縮形）
-        let pubkey = match event.pubkey.to_bech32() {
-            Ok(pk) => format!("npub...{}", &pk[pk.len()-8..]),
-            Err(_) => "unknown".to_string(),
-        };
-
-        // 日時表示 - Macスタイルの短い形式 (JSTに変換)
-let utc_date = DateTime::<Utc>::from_timestamp(event.created_at.as_u64() as i64, 0)
-    .unwrap_or_default();
-let jst_offset = FixedOffset::east_opt(9 * 3600).unwrap();
-let jst_date = utc_date.with_timezone(&jst_offset);
-let date = jst_date.format("%m/%d/%y %H:%M").to_string();
-
+    for entry in entries {
+        let pubkey = app.short_author(&entry.event.pubkey);
+        let tags = entry.tags.iter().map(|t| format!("#{}", t)).collect::<Vec<_>>().join(" ");
 
-        // コンテンツのプレビュー - スマート切り捨て処理
-let content_preview = smart_truncate(&event.content, 137);
-
-
-        // Mac風のリストアイテム (Chicago風アイコン使用)
-        let item = ListItem::new(vec![
+        let mut lines = vec![
             Line::from(vec![
-                Span::styled(format!("{} {} - ",MAC_DOCUMENT,  pubkey), 
-                            Style::default().fg(Color::Black).add_modifier(Modifier::BOLD)), // Chicago風
-                Span::styled(date, Style::default().fg(Color::Black)),
+                Span::styled(format!("{} {} ", glyph(Glyph::Document), pubkey),
+                            Style::default().fg(Color::Black).add_modifier(Modifier::BOLD)),
+                Span::styled(tags, Style::default().fg(Color::Black)),
             ]),
-            Line::from(Span::styled(content_preview, 
+            Line::from(Span::styled(smart_truncate(&entry.event.content, 137),
                     Style::default().fg(Color::Black))),
-            Line::from(""),  // 項目間の空白行
-        ]);
-
-        list_items.push(item);
+        ];
+        if !entry.note.is_empty() {
+            lines.push(Line::from(Span::styled(
+                format!("✎ {}", entry.note),
+                Style::default().fg(Color::Black).add_modifier(Modifier::ITALIC),
+            )));
+        }
+        lines.push(Line::from(""));
+        list_items.push(ListItem::new(lines));
     }
 
-    // ハイライト用の文字列を変数に格納し、ライフタイムを延長
-    let highlight_prefix = format!("{} ", MAC_CHECKMARK);
-
-    let events_list = List::new(list_items)
+    let highlight_prefix = format!("{} ", glyph(Glyph::Checkmark));
+    let list = List::new(list_items)
         .style(Style::default().bg(Color::White).fg(Color::Black))
         .highlight_style(
             Style::default()
                 .bg(Color::Black)
                 .fg(Color::White)
-                .add_modifier(Modifier::BOLD)) // 選択項目はChicago風に強調
+                .add_modifier(Modifier::BOLD))
         .highlight_symbol(&highlight_prefix);
 
-    f.render_stateful_widget(events_list, inner_area, &mut app.list_state.clone());
+    f.render_stateful_widget(list, chunks[1], &mut app.scrap_state.clone());
 }
 
-// スマートな切り捨て処理 - 飽和演算使用
-fn smart_truncate(text: &str, limit: usize) -> String {
-    if text.chars().count() <= limit {
-        return text.to_string();
+// DMの相手の表示名（プロフィールがなければ短縮した npub）
+fn peer_label(app: &App, peer: &XOnlyPublicKey) -> String {
+    match app.profiles.get(peer).and_then(|p| p.label()) {
+        Some(name) if !app.is_redacted(peer) => name.to_string(),
+        _ => app.short_author(peer),
+    }
+}
+
+// DM表示（左: 会話の一覧、右: メッセージ履歴と入力欄）
+fn render_dm_mac_style(f: &mut Frame, app: &App, area: Rect) {
+    let title = format!("{} DM", glyph(Glyph::Note));
+    let window = mac_window_block(&title);
+    f.render_widget(window.clone(), area);
+    let inner_area = window.inner(area);
+
+    if app.public_terminal || app.read_only {
+        let message = if app.public_terminal { "公開端末モードではDMを表示しません" } else { "閲覧専用のアカウントではDMを表示できません" };
+        let paragraph = Paragraph::new(format!("{} {}", glyph(Glyph::HappyMac), message))
+            .style(Style::default().bg(Color::White).fg(Color::Black).add_modifier(Modifier::BOLD))
+            .alignment(Alignment::Center);
+        f.render_widget(paragraph, inner_area);
+        return;
+    }
+
+    let columns = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(30), Constraint::Percentage(70)])
+        .split(inner_area);
+
+    // 会話の一覧
+    let items: Vec<ListItem> = app
+        .dm_conversations
+        .iter()
+        .map(|conversation| {
+            let last = conversation
+                .messages
+                .last()
+                .map(|m| smart_truncate(&m.content, 20))
+                .unwrap_or_else(|| "（新しい会話）".to_string());
+            let changed = if app.dm_key_changes.contains_key(&conversation.peer) { "⚠ " } else { "" };
+            ListItem::new(vec![
+                Line::from(Span::styled(
+                    format!("{}{}", changed, peer_label(app, &conversation.peer)),
+                    Style::default().fg(Color::Black).add_modifier(Modifier::BOLD),
+                )),
+                Line::from(Span::styled(last, Style::default().fg(Color::Black))),
+            ])
+        })
+        .collect();
+    let highlight_prefix = format!("{} ", glyph(Glyph::Checkmark));
+    let list = List::new(items)
+        .block(Block::default().borders(Borders::RIGHT))
+        .style(Style::default().bg(Color::White).fg(Color::Black))
+        .highlight_style(Style::default().bg(Color::Black).fg(Color::White).add_modifier(Modifier::BOLD))
+        .highlight_symbol(&highlight_prefix);
+    f.render_stateful_widget(list, columns[0], &mut app.dm_state.clone());
+
+    let Some(conversation) = app.selected_conversation() else {
+        let paragraph = Paragraph::new("会話がありません。:dm <npub|別名> で始められます")
+            .style(Style::default().bg(Color::White).fg(Color::Black))
+            .alignment(Alignment::Center);
+        f.render_widget(paragraph, columns[1]);
+        return;
+    };
+
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(1), Constraint::Min(3), Constraint::Length(4)])
+        .split(columns[1]);
+
+    // 安全番号（相手の画面と見比べる。鍵が変わった相手は反転して警告する）
+    let header = match (app.dm_key_changes.get(&conversation.peer), &app.keys) {
+        (Some(changes), _) => Paragraph::new(format!("⚠ 鍵が変わりました: {} は以前別の鍵を指していました", changes[0].handle))
+            .style(Style::default().bg(Color::Black).fg(Color::White).add_modifier(Modifier::BOLD)),
+        (None, Some(keys)) => Paragraph::new(format!("安全番号 {}", safety_number(&keys.public_key(), &conversation.peer).emoji_line()))
+            .style(Style::default().bg(Color::White).fg(Color::Black)),
+        (None, None) => Paragraph::new(""),
+    };
+    f.render_widget(header, rows[0]);
+
+    // メッセージ履歴（新しいものが下に来るよう、末尾から表示できる分だけ）
+    let mut lines = Vec::new();
+    let read_until = app.dm_read_until.get(&conversation.peer);
+    for message in &conversation.messages {
+        let who = if message.outgoing { "自分".to_string() } else { peer_label(app, &message.peer) };
+        let mark = if message.scheme == DmScheme::GiftWrap { "🔒" } else { "⚠" };
+        let read = if message.outgoing && read_until.is_some_and(|until| message.created_at <= *until) { " ✓既読" } else { "" };
+        lines.push(Line::from(Span::styled(
+            format!("{} {} - {}{}", mark, who, format_jst(message.created_at), read),
+            Style::default().fg(Color::Black).add_modifier(Modifier::BOLD),
+        )));
+        lines.push(Line::from(Span::styled(message.content.clone(), Style::default().fg(Color::Black))));
+        lines.push(Line::from(""));
+    }
+    let visible = rows[1].height as usize;
+    let skip = lines.len().saturating_sub(visible);
+    let history = Paragraph::new(lines.into_iter().skip(skip).collect::<Vec<_>>())
+        .style(Style::default().bg(Color::White).fg(Color::Black))
+        .wrap(Wrap { trim: false });
+    f.render_widget(history, rows[1]);
+
+    // 入力欄（使う暗号化方式を常に表示し、旧方式なら警告する）
+    let scheme_line = if app.dm_fallback_warned {
+        "⚠ 旧方式 NIP-04 で送信します（送信者・受信者・時刻が公開されます）。Enterで送信、Escで中止".to_string()
+    } else {
+        let typing = app
+            .dm_typing
+            .get(&conversation.peer)
+            .filter(|at| at.elapsed() < TYPING_SHOW_DURATION)
+            .map_or("", |_| "  ✎ 相手が入力中...");
+        format!("暗号化: {}{}", conversation.scheme.label(), typing)
+    };
+    let prompt = "> ";
+    let composer = Paragraph::new(vec![
+        Line::from(Span::styled(scheme_line, Style::default().fg(Color::Black).add_modifier(Modifier::BOLD))),
+        Line::from(format!("{}{}", prompt, app.dm_input)),
+    ])
+    .block(Block::default().borders(Borders::TOP))
+    .style(Style::default().bg(Color::White).fg(Color::Black))
+    .wrap(Wrap { trim: false });
+    f.render_widget(composer, rows[2]);
+
+    if app.dm_editing {
+        f.set_cursor(
+            rows[2].x + prompt.width() as u16 + app.dm_input.width() as u16,
+            rows[2].y + 2,
+        );
     }
+}
 
-    // 指定文字数まで切り取る
-    let chars: Vec<char> = text.chars().collect();
-    let mut truncated: String = chars.iter().take(limit).collect();
+// スマートな切り捨て処理 - 飽和演算使用
+fn smart_truncate(text: &str, limit: usize) -> String {
+    // 指定文字数まで切り取る（全体を文字の配列にせず、切る位置だけを探す）
+    let mut truncated = match text.char_indices().nth(limit) {
+        Some((end, _)) => text[..end].to_string(),
+        None => return text.to_string(),
+    };
 
     // 最後の単語を完全に含めるか切る判断 - 飽和演算使用
     if let Some(last_space) = truncated.rfind(' ') {
@@ -1093,27 +4786,102 @@ fn smart_truncate(text: &str, limit: usize) -> String {
     format!("{}...", truncated)
 }
 
+// 先頭から最大 max バイトを、文字の途中で切らずに取り出す
+fn truncate_bytes(text: &str, max: usize) -> &str {
+    if text.len() <= max {
+        return text;
+    }
+    let mut end = max;
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    &text[..end]
+}
 
 
-pub async fn run_tui() -> io::Result<()> {
-    // 初期化
-    enable_raw_mode()?;
-    let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen, Hide)?;
 
-    let backend = CrosstermBackend::new(stdout);
-    let mut terminal = Terminal::new(backend)?;
-    terminal.clear()?;
+// CJK文字かどうか（単語区切りがないため文字数で読了時間を見積もる）
+fn is_cjk(c: char) -> bool {
+    matches!(c,
+        '\u{3040}'..='\u{30FF}' |   // ひらがな・カタカナ
+        '\u{3400}'..='\u{4DBF}' |   // CJK統合漢字拡張A
+        '\u{4E00}'..='\u{9FFF}' |   // CJK統合漢字
+        '\u{AC00}'..='\u{D7AF}' |   // ハングル
+        '\u{F900}'..='\u{FAFF}' |   // CJK互換漢字
+        '\u{FF66}'..='\u{FF9F}')    // 半角カタカナ
+}
 
-    let mut app = App::new();
-    app.status = "アプリケーションを起動しました。パスワードを入力してください...".to_string();
+// 本文の単語数・文字数・読了時間を計算する
+pub fn content_stats(content: &str) -> ContentStats {
+    let chars = content.chars().count();
+    let cjk_chars = content.chars().filter(|c| is_cjk(*c)).count();
 
-    terminal.draw(|f| ui(f, &app))?;
+    // CJK以外の文字だけで単語を数える
+    let words = content
+        .split(|c: char| c.is_whitespace() || is_cjk(c))
+        .filter(|w| w.chars().any(|c| c.is_alphanumeric()))
+        .count();
+
+    let minutes = cjk_chars as f64 / READING_CJK_CHARS_PER_MIN as f64
+        + words as f64 / READING_WORDS_PER_MIN as f64;
+
+    ContentStats {
+        words: words + cjk_chars,
+        chars,
+        reading_minutes: minutes.ceil().max(1.0) as usize,
+    }
+}
+
+// 統計情報を表示用の文字列に整形する
+fn format_content_stats(stats: &ContentStats) -> String {
+    format!("📖 約{}分 | {}文字 | {}語", stats.reading_minutes, stats.chars, stats.words)
+}
+
+// 読了時間を表示すべき投稿かどうか（長文ノートまたはNIP-23記事）
+fn is_long_form(event: &nostr_sdk::Event) -> bool {
+    event.kind == Kind::LongFormTextNote || event.content.chars().count() >= LONG_CONTENT_CHARS
+}
+
+// パスワードを入力して鍵を復号化し、App に設定する
+fn unlock_tui_keys(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>, app: &mut App) -> io::Result<Keys> {
+    // 署名を外部のプログラムに任せるときは、パスワードを入力せずに公開鍵だけで起動する
+    if external_signer() {
+        return match signer_keys() {
+            Ok(keys) => set_tui_keys(terminal, app, keys),
+            Err(e) => {
+                disable_raw_mode()?;
+                execute!(terminal.backend_mut(), LeaveAlternateScreen, Show)?;
+                Err(io::Error::new(io::ErrorKind::Other, e.to_string()))
+            }
+        };
+    }
+    // 公開鍵だけを登録したアカウントは、パスワードを入力せずに閲覧専用で起動する
+    match read_only_public_key() {
+        Ok(Some(public_key)) => return set_tui_keys(terminal, app, Keys::from_public_key(public_key)),
+        Ok(None) => {}
+        Err(e) => app.status = format!("鍵ファイルを読み込めません: {}", e),
+    }
+    // 鍵のエージェントが動いているか、キーチェーンに保存してあればパスワードを入力しない
+    if let Some(keys) = active_account().ok().and_then(|account| agent_keys(&account)) {
+        return set_tui_keys(terminal, app, keys);
+    }
+    match keychain_keys() {
+        Ok(Some(keys)) => return set_tui_keys(terminal, app, keys),
+        Ok(None) => app.status = "アプリケーションを起動しました。パスワードを入力してください...".to_string(),
+        Err(e) => app.status = format!("{}。パスワードを入力してください...", e),
+    }
+
+    terminal.draw(|f| ui(f, app))?;
 
     // パスワード入力のために一時的にraw modeを無効化し、通常画面に戻る
     execute!(terminal.backend_mut(), LeaveAlternateScreen, Show)?;
     disable_raw_mode()?;
 
+    // パスワードを続けて間違えていれば知らせる（ロック中は入力させずに終了する）
+    if let Err(e) = password_attempts_notice() {
+        return Err(io::Error::new(io::ErrorKind::Other, e.to_string()));
+    }
+
     // パスワード入力
     let password = match read_password() {
         Ok(pw) => pw,
@@ -1133,13 +4901,13 @@ pub async fn run_tui() -> io::Result<()> {
     terminal.clear()?; // 再度クリア
 
     app.status = "パスワードを受け付けました。鍵を復号化しています...".to_string();
-    terminal.draw(|f| ui(f, &app))?;
+    terminal.draw(|f| ui(f, app))?;
 
     let keys = match load_keys(&password) {
         Ok(k) => k,
         Err(e) => {
             app.status = format!("鍵の読み込みに失敗: {}", e);
-            terminal.draw(|f| ui(f, &app))?;
+            terminal.draw(|f| ui(f, app))?;
             std::thread::sleep(std::time::Duration::from_secs(3));
 
             disable_raw_mode()?;
@@ -1148,109 +4916,507 @@ pub async fn run_tui() -> io::Result<()> {
         }
     };
 
+    set_tui_keys(terminal, app, keys)
+}
+
+// 読み込んだ鍵を App に設定する
+fn set_tui_keys(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>, app: &mut App, keys: Keys) -> io::Result<Keys> {
     app.my_public_key = match keys.public_key().to_bech32() {
         Ok(pk) => Some(pk),
         Err(e) => {
             app.status = format!("公開鍵の変換に失敗: {}", e);
-            terminal.draw(|f| ui(f, &app))?;
+            terminal.draw(|f| ui(f, app))?;
             std::thread::sleep(std::time::Duration::from_secs(3));
             return Err(io::Error::new(io::ErrorKind::Other, e.to_string()));
         }
     };
 
+    // 公開鍵だけの鍵なら閲覧専用にする（投稿・DMなどは断る。署名を外部のプログラムに任せるときは投稿できる）
+    app.read_only = keys.secret_key().is_err() && !external_signer();
+    app.streak = PostingStreak::load().unwrap_or_default();
     app.keys = Some(keys.clone());
+    Ok(keys)
+}
 
-    let client = Client::new(&keys);
-    app.status = "クライアントを作成しました。リレーに接続しています...".to_string();
-    terminal.draw(|f| ui(f, &app))?;
-
-    let relay_config = match load_relays() {
-        Ok(c) => c,
+// リレーを登録してクライアントを App に設定し、接続は裏で始める（完了は relays_ready で通知される）
+async fn start_tui_client(app: &mut App, client: Client, max_relay_connections: usize) {
+    // リレーの登録だけを先に済ませる（接続はまだ始まらないので待たされない）
+    let relay_urls = match load_relays() {
+        Ok(config) if !config.relays.is_empty() => config.relays,
+        Ok(_) => {
+            // 既定のリレーに接続したあと、自分の NIP-65 のリレーの一覧があればそちらに切り替える
+            app.relay_bootstrap_requested = app.keys.is_some() && !app.public_terminal;
+            TUI_DEFAULT_RELAYS.iter().map(|url| url.to_string()).collect()
+        }
         Err(e) => {
             app.status = format!("リレー設定の読み込みに失敗: {}、デフォルトを使用します", e);
-            terminal.draw(|f| ui(f, &app))?;
-            let mut config = crate::commands::RelayConfig::default();
-            config.relays = vec![];
-            config
+            TUI_DEFAULT_RELAYS.iter().map(|url| url.to_string()).collect()
+        }
+    };
+    app.relay_choices = relay_urls.clone();
+    // 接続の失敗が続いているリレーは間隔を空け、同時接続数も上限までに抑える
+    let mut capabilities = CapabilityCache::load().unwrap_or_default();
+    let (relay_urls, _) = capabilities.select_for_connect(&relay_urls, max_relay_connections);
+    // unix:// などは中継の URL にする
+    let mut dialed = Vec::new();
+    for url in &relay_urls {
+        let added = match dial(url) {
+            Ok(local) => client.add_relay(local.clone()).await.map(|_| local).map_err(|e| e.to_string()),
+            Err(e) => Err(e.to_string()),
+        };
+        match added {
+            Ok(local) => dialed.push(local),
+            Err(e) => app.status = format!("リレー接続エラー ({}): {}", url, e),
+        }
+    }
+    let relay_urls = dialed;
+
+    // リレーへの接続は裏で行い、その間もキャッシュのイベントを操作できるようにする
+    let (ready_tx, ready_rx) = oneshot::channel();
+    let background = client.clone();
+    let save_capabilities = !app.secondary_instance;
+    // AUTH（NIP-42）を求めるリレーには自動で認証する（公開端末モードでは鍵がないので応えない）
+    if !app.public_terminal {
+        spawn_auto_auth(&client);
+    }
+    tokio::spawn(async move {
+        connect_measured(&background, &relay_urls, &mut capabilities).await;
+        if save_capabilities {
+            let _ = capabilities.save();
+        }
+        let _ = ready_tx.send(());
+    });
+    app.relays_ready = Some(ready_rx);
+    app.start_activity(Activity::Connecting);
+    app.notifications = Some(client.notifications());
+    app.client = Some(client);
+}
+
+// アカウントを切り替える: 鍵を読み込み直し、新しい鍵のクライアントでリレーに接続し直す
+// 切り替えはこの TUI の間だけで、既定のアカウント（account switch）は変えない
+async fn switch_tui_account(
+    terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+    app: &mut App,
+    name: String,
+) -> io::Result<()> {
+    // 開いているノートパッドは、切り替える前のアカウントに保存して閉じる
+    if app.show_notepad {
+        app.toggle_notepad();
+    }
+    // 前のアカウントのノートパッドを新しいアカウントと同期しないよう、要求は取り消して中身も捨てる
+    app.notepad_sync_requested = false;
+    app.notepad = NotePad::default();
+    let previous = app.account.clone();
+    set_account_override(Some(name.clone()));
+    let keys = match unlock_tui_keys(terminal, app) {
+        Ok(keys) => keys,
+        Err(e) => {
+            // 読み込めなければ元のアカウントのまま TUI に戻る
+            set_account_override(Some(previous));
+            enable_raw_mode()?;
+            execute!(terminal.backend_mut(), EnterAlternateScreen, Hide)?;
+            terminal.clear()?;
+            app.status = format!("アカウント {} に切り替えられません: {}", name, e);
+            return Ok(());
+        }
+    };
+
+    if let Some(old) = app.client.take() {
+        let (connected, total, sent, received) = sample_relays(&old).await;
+        app.session_stats.record_relays(connected, total, sent, received);
+        app.session_stats.carry_over();
+        let _ = old.shutdown().await;
+    }
+    // 前のアカウントの DM とメンションの通知は持ち越さない
+    app.dm_conversations.clear();
+    app.dm_state = ListState::default();
+    app.dm_typing.clear();
+    app.dm_read_until.clear();
+    app.dm_read_sent.clear();
+    app.dm_key_changes.clear();
+    app.dm_signals.clear();
+    app.mentions_received = 0;
+    app.current_draft = None;
+    app.reply_to = None;
+    app.reply_root_requested = None;
+    app.trash = Trash::load().unwrap_or_default();
+    app.show_trash = false;
+    app.snoozed = Snoozed::load().unwrap_or_default();
+    app.show_reminders = false;
+    app.snooze_menu = None;
+    app.reminders_announced = 0;
+    app.account = name;
+
+    let max_relay_connections = AppConfig::load().unwrap_or_default().max_relay_connections;
+    start_tui_client(app, Client::new(&keys), max_relay_connections).await;
+    app.status = format!("アカウント {} に切り替えました。リレーに接続中...", app.account);
+    Ok(())
+}
+
+pub async fn run_tui(public_terminal: bool, force_ascii: bool) -> io::Result<()> {
+    // 初期化
+    enable_raw_mode()?;
+    let mut stdout = io::stdout();
+    execute!(stdout, EnterAlternateScreen, Hide)?;
+    // 最初の描画の前に、端末で幅が崩れる記号を調べて ASCII に置き換える
+    let fallback_glyphs = detect_fallback(force_ascii);
+
+    let backend = CrosstermBackend::new(stdout);
+    let mut terminal = Terminal::new(backend)?;
+    terminal.clear()?;
+
+    let mut app = App::new();
+    app.public_terminal = public_terminal;
+    // 別の TUI がすでに動いていれば、キャッシュなどを上書きしないよう読み取るだけにする（ロックは終了まで持つ）
+    let instance_lock = app_dir().and_then(|dir| FileLock::try_acquire(&dir.join("tui")));
+    app.secondary_instance = matches!(instance_lock, Ok(None));
+    app.reload_scrapbook();
+    app.mutes = MuteList::load().unwrap_or_default();
+    if !app.public_terminal {
+        app.trash = Trash::load().unwrap_or_default();
+        app.snoozed = Snoozed::load().unwrap_or_default();
+    }
+    let config = AppConfig::load().unwrap_or_default();
+    app.preferred_language = config.preferred_language;
+    app.auto_hashtags = config.auto_hashtags;
+    app.sync_drafts = config.sync_drafts;
+    app.sync_notepad = config.sync_notepad;
+    app.aliases = config.aliases;
+    app.show_welcome = !config.tui_onboarded && !app.public_terminal;
+    app.history = History::load().unwrap_or_default();
+    app.profile_cards = config.profile_cards;
+    app.strict_verification = config.strict_verification;
+    app.feed_kinds = config.feed_kinds;
+    app.load_columns(&config.columns);
+    app.post_reminder = match config.post_reminder.as_deref().map(parse_reminder_time) {
+        Some(Ok(time)) => Some(time),
+        Some(Err(e)) => {
+            app.status = e.to_string();
+            None
+        }
+        None => None,
+    };
+    app.redactor = match Redactor::compile(&config.redact_rules) {
+        Ok(redactor) => redactor,
+        Err(e) => {
+            app.status = e.to_string();
+            Redactor::default()
+        }
+    };
+    // キャッシュの掃除は裏で定期的に行う（別の TUI が動いているときは、そちらに任せる）
+    if !app.secondary_instance {
+        match RetentionPolicy::compile(&config.cache_retention) {
+            Ok(policy) => {
+                // ファイルのロックを待つ間はスレッドを止めるので、非同期の実行スレッドとは別のスレッドで掃除する
+                let policy = Arc::new(policy);
+                tokio::spawn(async move {
+                    let mut interval = tokio::time::interval(CACHE_VACUUM_INTERVAL);
+                    loop {
+                        interval.tick().await;
+                        let policy = Arc::clone(&policy);
+                        let _ = tokio::task::spawn_blocking(move || {
+                            let _ = vacuum_caches(&policy, false);
+                        })
+                        .await;
+                    }
+                });
+            }
+            Err(e) => app.status = e.to_string(),
+        }
+    }
+    app.dm_receipts = config.dm_receipts;
+    app.dm_receipt_peers = config
+        .dm_receipt_peers
+        .iter()
+        .filter_map(|pk| XOnlyPublicKey::from_str(pk).ok())
+        .collect();
+    app.profiles = ProfileCache::load().unwrap_or_default();
+    app.read_positions = ReadPositions::load().unwrap_or_default();
+    app.boot = config.boot_screen.then(BootScreen::new);
+    let shutdown_screen = config.shutdown_screen;
+    let max_relay_connections = config.max_relay_connections;
+    app.redacted_pubkeys = config
+        .public_terminal_redact
+        .iter()
+        .filter_map(|pk| XOnlyPublicKey::from_bech32(pk).ok().or_else(|| XOnlyPublicKey::from_str(pk).ok()))
+        .collect();
+    // 自分の公開鍵は設定に書き込まず、使用中のアカウントの鍵ファイルからその都度読んで伏せる
+    if public_terminal {
+        if let Some(me) = recorded_public_key().filter(|me| !app.redacted_pubkeys.contains(me)) {
+            app.redacted_pubkeys.push(me);
         }
+    }
+
+    // パスワード入力やリレー接続を待たずに、前回保存したイベントをすぐに表示する
+    deliver_events(&mut app, VerifyTarget::Cached, load_cached_events().unwrap_or_default()).await;
+    app.status = format!("前回取得した{}件のイベントを表示しています", app.events.len());
+    if app.secondary_instance {
+        app.status.push_str("（別の TUI が動いているため、キャッシュは読み取るだけで保存しません）");
+    }
+    if !force_ascii && !fallback_glyphs.is_empty() {
+        app.status.push_str(&format!("（この端末で幅が崩れる記号{}個を ASCII で表示します。--ascii ですべて ASCII）", fallback_glyphs.len()));
+    }
+    terminal.draw(|f| ui(f, &app))?;
+    app.advance_boot(BootStep::UnlockKeys);
+
+    let client = if public_terminal {
+        // 公開端末モードでは鍵を一切読み込まず、署名できないクライアントで閲覧だけを行う
+        Client::default()
+    } else {
+        app.account = active_account().unwrap_or_else(|_| DEFAULT_ACCOUNT.to_string());
+        let keys = unlock_tui_keys(&mut terminal, &mut app)?;
+        Client::new(&keys)
     };
+    start_tui_client(&mut app, client, max_relay_connections).await;
+    app.advance_boot(BootStep::ConnectRelays);
+    // 投稿する前に警告できるよう、リレーと自分の時計のずれを裏で測っておく
+    if !public_terminal {
+        let (skew_tx, skew_rx) = oneshot::channel();
+        let relay_urls = app.relay_choices.clone();
+        tokio::spawn(async move {
+            let _ = skew_tx.send(measure_clock_skew(&relay_urls).await);
+        });
+        app.clock_skew_rx = Some(skew_rx);
+    }
+    app.status = format!("前回取得した{}件のイベントを表示しています。リレーに接続中...", app.events.len());
+    terminal.draw(|f| ui(f, &app))?;
 
-    if relay_config.relays.is_empty() {
-        app.status = "デフォルトリレーに接続しています...".to_string();
+    let tick_rate = Duration::from_millis(100);
+    let mut last_tick = Instant::now();
+    let mut last_session_sample = Instant::now();
+
+    loop {
+        if app.boot.as_ref().is_some_and(BootScreen::finished) {
+            app.boot = None;
+        }
         terminal.draw(|f| ui(f, &app))?;
 
-        match client.add_relay("wss://relay-jp.nostr.wirednet.jp").await {
-            Ok(_) => {
-                app.status = "デフォルトリレーに接続しました".to_string();
-                terminal.draw(|f| ui(f, &app))?;
-            },
-            Err(e) => {
-                app.status = format!("デフォルトリレー接続エラー: {}", e);
-                terminal.draw(|f| ui(f, &app))?;
+        let timeout = tick_rate
+            .checked_sub(last_tick.elapsed())
+            .unwrap_or_else(|| Duration::from_secs(0));
+
+        if event::poll(timeout)? {
+            if let CrosstermEvent::Key(key) = event::read()? {
+                // 起動画面はキーを押せば閉じる（押したキーは操作に使わない）
+                if app.boot.is_some() {
+                    app.boot = None;
+                } else if !app.handle_key_event(key) {
+                    break;
+                }
+            }
+        }
+
+        // リレーに接続できたら購読を始め、最新のイベントに更新する
+        if let Some(ready) = app.relays_ready.as_mut() {
+            match ready.try_recv() {
+                Ok(()) => {
+                    app.relays_ready = None;
+                    app.finish_activity();
+                    app.advance_boot(BootStep::Done);
+                    subscribe_mentions(&mut app).await;
+                    app.refresh_requested = true;
+                    app.drafts_sync_requested = app.sync_drafts && !app.public_terminal && !app.read_only;
+                    app.status = if app.public_terminal {
+                        "公開端末モード（閲覧のみ）で接続しました。?キーでキー操作を表示します。".to_string()
+                    } else if app.read_only {
+                        "閲覧専用のアカウント（公開鍵のみ）で接続しました。投稿やDMはできません。".to_string()
+                    } else {
+                        "接続完了。?キーでこの画面のキー操作を表示します。".to_string()
+                    };
+                }
+                Err(oneshot::error::TryRecvError::Empty) => {}
+                Err(oneshot::error::TryRecvError::Closed) => {
+                    app.relays_ready = None;
+                    app.finish_activity();
+                    app.advance_boot(BootStep::Done);
+                }
+            }
+        }
+
+        if let Some(skew_rx) = app.clock_skew_rx.as_mut() {
+            match skew_rx.try_recv() {
+                Ok(skew) => {
+                    app.clock_skew_rx = None;
+                    app.clock_skew = skew;
+                    if let Some(warning) = skew.and_then(skew_warning) {
+                        app.status = warning;
+                    }
+                }
+                Err(oneshot::error::TryRecvError::Empty) => {}
+                Err(oneshot::error::TryRecvError::Closed) => app.clock_skew_rx = None,
+            }
+        }
+
+        // 裏で署名を検証していたイベントが届いたら表示に反映する
+        receive_verified(&mut app).await;
+
+        // 投稿のリマインダー（閲覧専用では投稿できないので知らせない）
+        let now = Timestamp::now();
+        if let Some(reminder) = app.post_reminder {
+            let today = day_key(now);
+            if !app.read_only && app.reminded_on.as_deref() != Some(today.as_str()) && reminder_due(&app.streak, reminder, now) {
+                app.reminded_on = Some(today);
+                app.status = format!("今日はまだ投稿していません（{}）", app.streak.summary(now));
+                notify_reminder(&app.status);
+            }
+        }
+
+        if let Some(name) = app.pending_account.take() {
+            switch_tui_account(&mut terminal, &mut app, name).await?;
+        }
+
+        if app.relay_bootstrap_requested && app.relays_ready.is_none() {
+            app.relay_bootstrap_requested = false;
+            show_activity(&mut terminal, &mut app, Activity::Fetching)?;
+            bootstrap_tui_relays(&mut app).await;
+            app.finish_activity();
+        }
+
+        if app.notepad_sync_requested && app.relays_ready.is_none() {
+            app.notepad_sync_requested = false;
+            show_activity(&mut terminal, &mut app, Activity::Fetching)?;
+            sync_tui_notepad(&mut app).await;
+            app.finish_activity();
+        }
+
+        if app.drafts_sync_requested && app.relays_ready.is_none() {
+            app.drafts_sync_requested = false;
+            show_activity(&mut terminal, &mut app, Activity::Fetching)?;
+            sync_tui_drafts(&mut app).await;
+            app.finish_activity();
+        }
+
+        if app.resubscribe_requested {
+            app.resubscribe_requested = false;
+            subscribe_mentions(&mut app).await;
+        }
+
+        drain_notifications(&mut app);
+
+        if app.columns_requested {
+            app.columns_requested = false;
+            show_activity(&mut terminal, &mut app, Activity::Fetching)?;
+            if let Err(e) = fetch_columns(&mut app).await {
+                app.status = format!("列の取得エラー: {}", e);
+            }
+            app.finish_activity();
+        }
+
+        if app.refresh_requested {
+            app.refresh_requested = false;
+            show_activity(&mut terminal, &mut app, Activity::Fetching)?;
+            if let Err(e) = fetch_events(&mut app).await {
+                app.status = format!("イベント取得エラー: {}", e);
+            }
+            app.finish_activity();
+        }
+
+        if let Some(coordinate) = app.pending_article.take() {
+            show_activity(&mut terminal, &mut app, Activity::Fetching)?;
+            if let Err(e) = fetch_article(&mut app, coordinate).await {
+                app.status = format!("記事の取得に失敗: {}", e);
+            }
+            app.finish_activity();
+        }
+
+        if app.sort_data_requested {
+            app.sort_data_requested = false;
+            show_activity(&mut terminal, &mut app, Activity::Fetching)?;
+            if let Err(e) = fetch_sort_data(&mut app).await {
+                app.status = format!("並べ替えに失敗: {}", e);
+            }
+            app.finish_activity();
+        }
+
+        if app.kinds_requested {
+            app.kinds_requested = false;
+            show_activity(&mut terminal, &mut app, Activity::Fetching)?;
+            if let Err(e) = fetch_kind_histogram(&mut app).await {
+                app.status = format!("イベントの取得に失敗: {}", e);
+            }
+            app.finish_activity();
+        }
+
+        if app.profiles_requested {
+            app.profiles_requested = false;
+            if let Err(e) = fetch_profiles(&mut app).await {
+                app.status = format!("プロフィールの取得に失敗: {}", e);
+            }
+        }
+
+        if app.dm_refresh_requested {
+            app.dm_refresh_requested = false;
+            show_activity(&mut terminal, &mut app, Activity::Fetching)?;
+            if let Err(e) = fetch_direct_messages(&mut app).await {
+                app.status = format!("DMの取得に失敗: {}", e);
             }
+            app.finish_activity();
         }
 
-        // デフォルトリレーを変更（wss://yabu.me）
-        match client.add_relay("wss://yabu.me").await {
-            Ok(_) => {
-                app.status = format!("追加リレーに接続しました: wss://yabu.me");
-                terminal.draw(|f| ui(f, &app))?;
-            },
-            Err(e) => {
-                app.status = format!("リレー接続エラー (wss://yabu.me): {}", e);
-                terminal.draw(|f| ui(f, &app))?;
-            }
+        if !app.dm_signals.is_empty() {
+            send_dm_signals(&mut app).await;
         }
-    } else {
-        for url in &relay_config.relays {
-            app.status = format!("リレーに接続中: {}", url);
-            terminal.draw(|f| ui(f, &app))?;
-
-            match client.add_relay(url.clone()).await {
-                Ok(_) => {
-                    app.status = format!("リレーに接続: {}", url);
-                    terminal.draw(|f| ui(f, &app))?;
-                },
+
+        if let Some((peer, content, scheme)) = app.dm_to_send.take() {
+            show_activity(&mut terminal, &mut app, Activity::Publishing)?;
+            let sent = send_direct_message(&mut app, peer, &content, scheme).await;
+            app.finish_activity();
+            match sent {
+                Ok(()) => app.dm_refresh_requested = true,
                 Err(e) => {
-                    app.status = format!("リレー接続エラー ({}): {}", url, e);
-                    terminal.draw(|f| ui(f, &app))?;
+                    // 入力した内容は失わないように入力欄に戻す
+                    app.dm_input = content;
+                    app.status = format!("DMの送信エラー: {}", e);
                 }
             }
         }
-    }
 
-    client.connect().await;
-    app.client = Some(client);
-    app.status = "接続完了。rキーで更新、aキーでAbout画面、sキーで電卓を表示します。".to_string(); // cキーをsキーに変更
-    terminal.draw(|f| ui(f, &app))?;
-
-    let tick_rate = Duration::from_millis(100);
-    let mut last_tick = Instant::now();
-
-    loop {
-        terminal.draw(|f| ui(f, &app))?;
+        if let Some(event_id) = app.pending_stats.take() {
+            if let Err(e) = fetch_note_stats(&mut app, event_id).await {
+                app.status = format!("反応の集計に失敗: {}", e);
+            }
+        }
 
-        let timeout = tick_rate
-            .checked_sub(last_tick.elapsed())
-            .unwrap_or_else(|| Duration::from_secs(0));
+        if let Some(center) = app.pending_goto.take() {
+            show_activity(&mut terminal, &mut app, Activity::Fetching)?;
+            if let Err(e) = fetch_events_around(&mut app, center).await {
+                app.status = format!("イベント取得エラー: {}", e);
+            }
+            app.finish_activity();
+        }
 
-        if event::poll(timeout)? {
-            if let CrosstermEvent::Key(key) = event::read()? {
-                if !app.handle_key_event(key) {
-                    break;
-                }
+        if app.empty_trash_requested {
+            app.empty_trash_requested = false;
+            show_activity(&mut terminal, &mut app, Activity::Publishing)?;
+            if let Err(e) = empty_trash(&mut app).await {
+                app.status = format!("ゴミ箱を空にできません: {}", e);
+            }
+            app.finish_activity();
+        }
 
-                if key.code == KeyCode::Char('r') && app.input_mode == InputMode::Normal {
-                    if let Err(e) = fetch_events(&mut app).await {
-                        app.status = format!("イベント取得エラー: {}", e);
-                    }
-                }
+        if let Some(root_id) = app.reply_root_requested.take() {
+            let root = match &app.client {
+                Some(client) => fetch_event_by_id(client, root_id).await.ok(),
+                None => None,
+            };
+            if let Some(reply) = app.reply_to.as_mut().filter(|reply| thread_root(&reply.parent) == root_id) {
+                reply.root = root;
             }
         }
 
-        if let Some(message) = app.message_to_send.take() {
-            match send_message(&mut app, &message).await {
+        if let Some(draft) = app.message_to_send.take() {
+            let message = draft.content.clone();
+            show_activity(&mut terminal, &mut app, Activity::Publishing)?;
+            let sent = send_message(&mut app, &draft).await;
+            app.finish_activity();
+            match sent {
                 Ok(()) => {
+                    app.session_stats.notes_sent += 1;
+                    app.streak.record(Timestamp::now());
+                    // 下書きから書いた投稿を送ったら、その下書きは消す
+                    if let Some(id) = app.current_draft.take() {
+                        app.delete_draft(&id);
+                    }
                     // 修正：マルチバイト文字にも対応するプレビュー生成
                     let preview = if message.chars().count() > 20 {
                         let truncated: String = message.chars().take(17).collect();
@@ -1269,20 +5435,100 @@ pub async fn run_tui() -> io::Result<()> {
                     }
                 }
                 Err(e) => {
+                    app.current_draft = None;
                     app.status = format!("送信エラー: {}", e);
                 }
             }
         }
 
+        // リレーの接続状態と転送量はときどき調べる
+        if last_session_sample.elapsed() >= SESSION_SAMPLE_INTERVAL {
+            last_session_sample = Instant::now();
+            if let Some(client) = &app.client {
+                let (connected, total, sent, received) = sample_relays(client).await;
+                app.session_stats.record_relays(connected, total, sent, received);
+            }
+        }
+
         if last_tick.elapsed() >= tick_rate {
             last_tick = Instant::now();
+            app.check_reminders();
         }
     }
 
-    // 終了処理
+    // 終了処理（長文記事を読んでいる途中ならその位置を記録する）
+    app.close_detail();
+    if shutdown_screen {
+        show_shutdown_screen(&mut terminal)?;
+    }
     disable_raw_mode()?;
     execute!(terminal.backend_mut(), LeaveAlternateScreen, Show)?;
 
+    // セッションの統計を表示し、session stats で見返せるよう保存する
+    if let Some(client) = &app.client {
+        let (connected, total, sent, received) = sample_relays(client).await;
+        app.session_stats.record_relays(connected, total, sent, received);
+    }
+    app.session_stats.ended_at = Some(Timestamp::now().as_u64());
+    for line in app.session_stats.report() {
+        println!("{}", line);
+    }
+    if app.secondary_instance {
+        eprintln!("別の TUI が動いていたため、セッションの統計は保存しませんでした");
+    } else if let Err(e) = app.session_stats.save_last() {
+        eprintln!("セッションの統計の保存に失敗: {}", e);
+    }
+
+    Ok(())
+}
+
+// 性能計測用: 画面に出さずに一覧と詳細表示（最も大きな本文）を frames 回ずつ描画し、1回ごとの時間を返す
+pub fn bench_render(
+    events: Vec<nostr_sdk::Event>,
+    frames: usize,
+    width: u16,
+    height: u16,
+) -> io::Result<(Vec<Duration>, Vec<Duration>)> {
+    let mut terminal = Terminal::new(TestBackend::new(width, height))?;
+    let mut app = App::new();
+    app.events = events;
+    app.apply_article_sort();
+    let count = app.events.len().max(1);
+
+    // 一覧: 選択を1件ずつ動かしてスクロールも含める
+    let mut list = Vec::with_capacity(frames);
+    for frame in 0..frames {
+        app.list_state.select(Some(frame % count));
+        let started = Instant::now();
+        terminal.draw(|f| ui(f, &app))?;
+        list.push(started.elapsed());
+    }
+
+    // 詳細表示: 開く時間も含め、1行ずつスクロールしながら描画する
+    let largest = app.events.iter().enumerate().max_by_key(|(_, e)| e.content.len()).map(|(i, _)| i);
+    app.list_state.select(largest);
+    let mut detail = Vec::with_capacity(frames);
+    let started = Instant::now();
+    app.toggle_detail_mode();
+    terminal.draw(|f| ui(f, &app))?;
+    detail.push(started.elapsed());
+    for frame in 1..frames {
+        app.detail_scroll = frame.min(u16::MAX as usize) as u16;
+        let started = Instant::now();
+        terminal.draw(|f| ui(f, &app))?;
+        detail.push(started.elapsed());
+    }
+    Ok((list, detail))
+}
+
+// 処理を始めたことを、待ち時間に入る前に1度描画して知らせる
+fn show_activity(
+    terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+    app: &mut App,
+    activity: Activity,
+) -> io::Result<()> {
+    app.start_activity(activity);
+    terminal.draw(|f| ui(f, app))?;
     Ok(())
 }
 
@@ -1315,14 +5561,42 @@ fn ui(f: &mut Frame, app: &App) {
     let bg_block = mac_background_block();
     f.render_widget(bg_block, f.size());
 
+    if let Some(boot) = &app.boot {
+        render_boot_screen(f, app, boot);
+        return;
+    }
+
     // 電卓表示の場合とAbout画面表示の場合は変更なし
     if app.show_calculator {
         render_calculator(f, app);
+        render_overlays(f, app);
+        return;
+    }
+
+    if app.show_notepad {
+        render_notepad(f, app);
+        render_overlays(f, app);
+        return;
+    }
+
+    if app.show_trash {
+        render_trash(f, app);
+        if app.trash_confirm {
+            render_empty_trash_dialog(f, app);
+        }
+        render_overlays(f, app);
+        return;
+    }
+
+    if app.show_reminders {
+        render_reminders(f, app);
+        render_overlays(f, app);
         return;
     }
 
     if app.show_about {
         render_about_screen(f, app);
+        render_overlays(f, app);
         return;
     }
 
@@ -1334,12 +5608,13 @@ fn ui(f: &mut Frame, app: &App) {
             Constraint::Length(1),  // メニューバー
             Constraint::Min(0),     // メインコンテンツ（拡大）
             Constraint::Length(1),  // ステータスバー
+            Constraint::Length(u16::from(app.show_session_stats)), // 拡張ステータスバー（セッションの統計）
         ])
         .split(f.size());
 
     // Mac風メニューバー (変更なし)
     let menu_items = vec![
-        format!(" {} File ", MAC_APPLE_LOGO), 
+        format!(" {} File ", glyph(Glyph::AppleLogo)), 
         " Edit ".to_string(), 
         " View ".to_string(), 
         " Special ".to_string(), 
@@ -1361,15 +5636,95 @@ fn ui(f: &mut Frame, app: &App) {
 
     f.render_widget(menu_bar, chunks[0]);
 
+    // メニューバーの右端に使用中のアカウントを表示する（公開端末モードでは鍵を使わないので出さない）
+    if !app.public_terminal {
+        let label = if app.read_only { format!("👤 {}（閲覧専用） ", app.account) } else { format!("👤 {} ", app.account) };
+        let width = (label.width() as u16).min(chunks[0].width);
+        let area = Rect::new(chunks[0].right() - width, chunks[0].y, width, 1);
+        f.render_widget(Paragraph::new(label).style(Style::default().bg(Color::White).fg(Color::Black)), area);
+    }
+
+    // View メニューを開いているときは、メニューバーの View の下に並び順の一覧を表示する
+    if let Some(state) = &app.view_menu {
+        let x = menu_items[..2].iter().map(|item| item.width() as u16).sum::<u16>();
+        render_view_menu(f, app, state, x, chunks[0].y + 1);
+    }
+
     // タブに応じたコンテンツ表示 (変更なし)
     match app.active_tab {
         0 => render_events_mac_style(f, app, chunks[1]),
         1 => render_compose_mac_style(f, app, chunks[1]),
+        TAB_SCRAPBOOK => render_scrapbook_mac_style(f, app, chunks[1]),
+        TAB_DM => render_dm_mac_style(f, app, chunks[1]),
         _ => {}
     }
 
-    // ステータスバー (常に表示)
-    let status_text = format!("{} {}", MAC_HAPPY_MAC, app.status);
+    // 日時ジャンプの入力ダイアログ
+    if let Some(input) = &app.goto_input {
+        render_goto_dialog(f, input);
+    }
+
+    // 長文記事の目次
+    if let Some(state) = &app.toc_state {
+        render_toc_dialog(f, app, state);
+    }
+
+    // リレーの選択ダイアログ
+    if let Some(state) = &app.relay_picker {
+        render_relay_picker(f, app, state);
+    }
+
+    // アカウントの選択ダイアログ
+    if let Some(state) = &app.account_picker {
+        render_account_picker(f, app, state);
+    }
+
+    // 下書きの選択ダイアログ
+    if let Some(state) = &app.draft_picker {
+        render_draft_picker(f, app, state);
+    }
+
+    // コマンドパレット
+    if let Some(palette) = &app.palette {
+        render_palette(f, palette);
+    }
+
+    // 共有用リンクのダイアログ
+    if let Some(state) = &app.share_menu {
+        render_share_menu(f, app, state);
+    }
+
+    // スヌーズする時刻の選択ダイアログ
+    if let Some(state) = &app.snooze_menu {
+        render_snooze_menu(f, state);
+    }
+
+    // kind の分布のダイアログ
+    if let Some(state) = &app.kind_explorer {
+        render_kind_explorer(f, app, state);
+    }
+
+    // ステータスバー (常に表示、コマンドライン入力中はその入力欄になる)
+    if let Some(line) = &app.command_input {
+        let prompt = recall_label(&app.command_recall).unwrap_or_else(|| ":".to_string());
+        f.render_widget(
+            Paragraph::new(format!("{}{}", prompt, line))
+                .style(Style::default().bg(Color::White).fg(Color::Black).add_modifier(Modifier::BOLD)),
+            chunks[2],
+        );
+        f.set_cursor(chunks[2].x + prompt.width() as u16 + line.width() as u16, chunks[2].y);
+        render_overlays(f, app);
+        return;
+    }
+
+    // 処理中は Happy Mac の代わりに腕時計カーソルを回す
+    let mut status_text = match app.activity {
+        Some(activity) => format!("{} [{}] {}", watch_frame(app.activity_started.elapsed()), activity.label(), app.status),
+        None => format!("{} {}", glyph(Glyph::HappyMac), app.status),
+    };
+    if app.invalid_dropped > 0 {
+        status_text.push_str(&format!("  [署名の検証に失敗して破棄: {}件]", app.invalid_dropped));
+    }
     let status_style = Style::default()
         .bg(Color::White)
         .fg(Color::Black)
@@ -1379,9 +5734,552 @@ fn ui(f: &mut Frame, app: &App) {
         .style(status_style);
 
     f.render_widget(status, chunks[2]);
+
+    if app.show_session_stats {
+        let line = format!("{} | 🔥 {}日連続", app.session_stats.status_line(), app.streak.current(Timestamp::now()));
+        f.render_widget(Paragraph::new(line).style(status_style), chunks[3]);
+    }
+
+    // 処理中はメイン領域の右下でズームする矩形を表示する
+    if app.activity.is_some() {
+        render_zoom_rects(f, app, chunks[1]);
+    }
+
+    render_overlays(f, app);
+}
+
+// 初代Macのウィンドウを開くときのような、中心から広がる矩形を小さな枠の中に描く
+fn render_zoom_rects(f: &mut Frame, app: &App, area: Rect) {
+    let width = ZOOM_BOX_WIDTH.min(area.width);
+    let height = ZOOM_BOX_HEIGHT.min(area.height);
+    if width < 2 || height < 2 {
+        return;
+    }
+    let zoom_area = Rect::new(area.right() - width, area.bottom() - height, width, height);
+    f.render_widget(ratatui::widgets::Clear, zoom_area);
+    f.render_widget(mac_background_block(), zoom_area);
+    for rect in zoom_rects(zoom_area, app.activity_started.elapsed()) {
+        f.render_widget(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::Black).bg(Color::White)),
+            rect,
+        );
+    }
+}
+
+// ようこそ画面とヘルプを最前面に描画
+fn render_overlays(f: &mut Frame, app: &App) {
+    if app.show_welcome {
+        render_welcome_dialog(f);
+    } else if app.show_help {
+        render_help_dialog(f, app);
+    }
+}
+
+// 影付きのダイアログ枠を画面中央に描画し、内側の領域を返す
+fn render_dialog_frame(f: &mut Frame, title: &str, width: u16, height: u16) -> Rect {
+    let area = f.size();
+    let width = width.min(area.width);
+    let height = height.min(area.height);
+
+    let dialog_area = Rect::new(
+        area.x + area.width.saturating_sub(width) / 2,
+        area.y + area.height.saturating_sub(height) / 2,
+        width,
+        height,
+    );
+
+    // 影を描画
+    let shadow_area = Rect::new(
+        dialog_area.x.saturating_add(1),
+        dialog_area.y.saturating_add(1),
+        width.min(area.width.saturating_sub(dialog_area.x + 1)),
+        height.min(area.height.saturating_sub(dialog_area.y + 1)),
+    );
+    f.render_widget(Block::default().style(Style::default().bg(Color::DarkGray)), shadow_area);
+
+    // 下の画面の文字が残らないように消してから枠を描く
+    f.render_widget(ratatui::widgets::Clear, dialog_area);
+    let dialog_block = mac_window_block(title);
+    f.render_widget(dialog_block.clone(), dialog_area);
+    dialog_block.inner(dialog_area)
+}
+
+// 初回起動時のようこそ画面を描画
+fn render_welcome_dialog(f: &mut Frame) {
+    let bold = Style::default().fg(Color::Black).add_modifier(Modifier::BOLD);
+    let text = vec![
+        Line::from(Span::styled(format!("{} nostr CLI へようこそ", glyph(Glyph::HappyMac)), bold)),
+        Line::from(""),
+        Line::from(Span::styled("タブ（Tabキーで切り替え）", bold)),
+        Line::from(" イベントリスト  : タイムラインの閲覧、Enterで詳細"),
+        Line::from(" 投稿作成        : iで入力、Enterで送信"),
+        Line::from(" スクラップブック: clip で保存したノートの検索"),
+        Line::from(""),
+        Line::from(Span::styled("よく使うキー", bold)),
+        Line::from(" r: 更新   g: 日時ジャンプ   a: About   s: 電卓"),
+        Line::from(" ?: その画面のキー操作を表示   q: 終了"),
+        Line::from(""),
+        Line::from(Span::styled("Enter / Esc で閉じる（次回からは表示しません）", Style::default().fg(Color::Black))),
+    ];
+
+    let inner_area = render_dialog_frame(f, "Welcome", 56, text.len() as u16 + 2);
+    f.render_widget(
+        Paragraph::new(text).style(Style::default().bg(Color::White).fg(Color::Black)),
+        inner_area,
+    );
+}
+
+// 現在の画面のキー操作一覧を描画
+fn render_help_dialog(f: &mut Frame, app: &App) {
+    let (screen, keys) = app.active_keymap();
+    let key_width = keys.iter().map(|binding| binding.label.width()).max().unwrap_or(0);
+
+    let mut text = vec![
+        Line::from(Span::styled(
+            format!("{} のキー操作", screen),
+            Style::default().fg(Color::Black).add_modifier(Modifier::BOLD),
+        )),
+        Line::from(""),
+    ];
+    for binding in keys {
+        text.push(Line::from(vec![
+            Span::styled(
+                format!(" {}{}  ", binding.label, " ".repeat(key_width - binding.label.width())),
+                Style::default().fg(Color::Black).add_modifier(Modifier::BOLD),
+            ),
+            Span::raw(binding.description),
+        ]));
+    }
+    text.push(Line::from(""));
+    text.push(Line::from(Span::styled("Esc / ? で閉じる", Style::default().fg(Color::Black))));
+
+    let inner_area = render_dialog_frame(f, "Help", 48, text.len() as u16 + 2);
+    f.render_widget(
+        Paragraph::new(text).style(Style::default().bg(Color::White).fg(Color::Black)),
+        inner_area,
+    );
+}
+
+
+
+// フィードを表示するリレーの選択ダイアログを描画
+fn render_relay_picker(f: &mut Frame, app: &App, state: &ListState) {
+    let mut items = vec![ListItem::new(" すべてのリレー")];
+    items.extend(app.relay_choices.iter().map(|url| {
+        let mark = if app.feed_relay.as_deref() == Some(url.as_str()) { glyph(Glyph::Checkmark) } else { " " };
+        ListItem::new(format!("{}{}", mark, url))
+    }));
+
+    let width = app.relay_choices.iter().map(|url| url.width()).max().unwrap_or(0).max(20) as u16 + 6;
+    let inner_area = render_dialog_frame(f, "Relays", width, items.len() as u16 + 2);
+    let list = List::new(items)
+        .style(Style::default().bg(Color::White).fg(Color::Black))
+        .highlight_style(Style::default().bg(Color::Black).fg(Color::White));
+    let mut state = state.clone();
+    f.render_stateful_widget(list, inner_area, &mut state);
+}
+
+// 切り替えるアカウントの選択ダイアログを描画
+fn render_account_picker(f: &mut Frame, app: &App, state: &ListState) {
+    let items: Vec<ListItem> = app
+        .account_choices
+        .iter()
+        .map(|name| {
+            let mark = if *name == app.account { glyph(Glyph::Checkmark) } else { " " };
+            ListItem::new(format!("{}{}", mark, name))
+        })
+        .collect();
+    let width = app.account_choices.iter().map(|name| name.width()).max().unwrap_or(0).max(20) as u16 + 6;
+    let inner_area = render_dialog_frame(f, "Accounts", width, items.len() as u16 + 2);
+    let list = List::new(items)
+        .style(Style::default().bg(Color::White).fg(Color::Black))
+        .highlight_style(Style::default().bg(Color::Black).fg(Color::White));
+    let mut state = state.clone();
+    f.render_stateful_widget(list, inner_area, &mut state);
+}
+
+// コマンドパレットを描画（1行目が入力欄、その下に絞り込んだ項目と説明）
+fn render_palette(f: &mut Frame, palette: &Palette) {
+    let inner_area = render_dialog_frame(f, "Command Palette", 70, 16);
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(1), Constraint::Min(0)])
+        .split(inner_area);
+    f.render_widget(
+        Paragraph::new(format!("> {}█", palette.query)).style(Style::default().bg(Color::White).fg(Color::Black)),
+        rows[0],
+    );
+
+    let label_width = rows[1].width.saturating_sub(24) as usize;
+    let items: Vec<ListItem> = palette
+        .matches
+        .iter()
+        .filter_map(|&i| palette.items.get(i))
+        .map(|item| {
+            let padding = label_width.saturating_sub(item.label.width());
+            ListItem::new(format!(" {}{} {}", item.label, " ".repeat(padding), item.detail))
+        })
+        .collect();
+    if items.is_empty() {
+        f.render_widget(
+            Paragraph::new(" 一致する項目がありません").style(Style::default().bg(Color::White).fg(Color::DarkGray)),
+            rows[1],
+        );
+        return;
+    }
+    let list = List::new(items)
+        .style(Style::default().bg(Color::White).fg(Color::Black))
+        .highlight_style(Style::default().bg(Color::Black).fg(Color::White));
+    let mut state = ListState::default();
+    state.select(Some(palette.selected));
+    f.render_stateful_widget(list, rows[1], &mut state);
+}
+
+// 下書きの選択ダイアログを描画（編集した日時と件名または本文の1行目）
+fn render_draft_picker(f: &mut Frame, app: &App, state: &ListState) {
+    let items: Vec<ListItem> = app
+        .draft_choices
+        .iter()
+        .map(|draft| ListItem::new(format!(" {}  {}", format_jst(Timestamp::from(draft.updated_at)), draft.title())))
+        .collect();
+    let width = items.iter().map(|item| item.width()).max().unwrap_or(0).clamp(30, 80) as u16 + 4;
+    let inner_area = render_dialog_frame(f, "Drafts", width, items.len().min(15) as u16 + 2);
+    let list = List::new(items)
+        .style(Style::default().bg(Color::White).fg(Color::Black))
+        .highlight_style(Style::default().bg(Color::Black).fg(Color::White));
+    let mut state = state.clone();
+    f.render_stateful_widget(list, inner_area, &mut state);
+}
+
+// ノートパッドを描画（右下にページ番号と、初代Macと同じく折り返したページの角を描く）
+fn render_notepad(f: &mut Frame, app: &App) {
+    let title = format!("{} Note Pad", glyph(Glyph::Note));
+    let inner_area = render_dialog_frame(f, &title, 52, 18);
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(0), Constraint::Length(1)])
+        .split(inner_area);
+
+    let text = app.notepad.pages.get(app.notepad_page).map(|page| page.as_str()).unwrap_or_default();
+    f.render_widget(
+        Paragraph::new(format!("{}█", text))
+            .wrap(Wrap { trim: false })
+            .style(Style::default().bg(Color::White).fg(Color::Black)),
+        rows[0],
+    );
+    let footer = format!("◤ PgUp/PgDn でめくる  Esc で閉じる{:>width$}", format!("{} / {}", app.notepad_page + 1, NOTEPAD_PAGES), width = 14);
+    f.render_widget(
+        Paragraph::new(footer).style(Style::default().bg(Color::White).fg(Color::DarkGray)),
+        rows[1],
+    );
+}
+
+// ゴミ箱を描画（入れた日時・理由・元に戻せる残り日数と本文の1行目）
+fn render_trash(f: &mut Frame, app: &App) {
+    let now = Timestamp::now();
+    let title = format!("🗑 Trash — {}項目", app.trash.items.len());
+    let inner_area = render_dialog_frame(f, &title, 78, 20);
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(0), Constraint::Length(1), Constraint::Length(1)])
+        .split(inner_area);
+
+    if app.trash.items.is_empty() {
+        f.render_widget(
+            Paragraph::new(" ゴミ箱は空です（d で削除、x で非表示にした投稿がここに入ります）")
+                .style(Style::default().bg(Color::White).fg(Color::DarkGray)),
+            rows[0],
+        );
+    } else {
+        let items: Vec<ListItem> = app
+            .trash
+            .items
+            .iter()
+            .map(|item| {
+                let remaining = if item.restorable(now) {
+                    format!("あと{}日", item.days_left(now))
+                } else {
+                    "期限切れ".to_string()
+                };
+                let preview: String = item.event.content.lines().next().unwrap_or_default().chars().take(40).collect();
+                let line = format!(
+                    " {}  {}  {}  {}",
+                    format_jst(Timestamp::from(item.trashed_at)),
+                    item.reason.label(),
+                    remaining,
+                    preview
+                );
+                let style = if item.restorable(now) { Style::default() } else { Style::default().fg(Color::DarkGray) };
+                ListItem::new(line).style(style)
+            })
+            .collect();
+        let list = List::new(items)
+            .style(Style::default().bg(Color::White).fg(Color::Black))
+            .highlight_style(Style::default().bg(Color::Black).fg(Color::White));
+        let mut state = app.trash_state.clone();
+        f.render_stateful_widget(list, rows[0], &mut state);
+    }
+
+    // ゴミ箱の表示中はステータスバーが隠れるので、元に戻せなかった理由などはここに出す
+    f.render_widget(
+        Paragraph::new(format!(" {}", app.status)).style(Style::default().bg(Color::White).fg(Color::Black)),
+        rows[1],
+    );
+    f.render_widget(
+        Paragraph::new(keymap_hint(TRASH_KEYS)).style(Style::default().bg(Color::White).fg(Color::DarkGray)),
+        rows[2],
+    );
+}
+
+// 「ゴミ箱を空にする…」の確認ダイアログを描画（初代Macと同じく、既定のボタンの OK を太枠で示す）
+fn render_empty_trash_dialog(f: &mut Frame, app: &App) {
+    let bold = Style::default().fg(Color::Black).add_modifier(Modifier::BOLD);
+    let deletions = app.trash.deleted_ids().len();
+    let mut text = vec![
+        Line::from(Span::styled(
+            format!(" ⚠  ゴミ箱には{}項目あります。", app.trash.items.len()),
+            bold,
+        )),
+        Line::from("    これらの項目を完全に削除してもよろしいですか？"),
+    ];
+    if deletions > 0 {
+        text.push(Line::from(format!("    自分の投稿{}件の削除要求（kind 5）をリレーに送ります。", deletions)));
+    }
+    text.push(Line::from(""));
+    text.push(Line::from(vec![
+        Span::raw("                 [ キャンセル ]   "),
+        Span::styled("╔═ OK ═╗", bold),
+    ]));
+
+    let inner_area = render_dialog_frame(f, "Empty Trash…", 60, text.len() as u16 + 2);
+    f.render_widget(
+        Paragraph::new(text).style(Style::default().bg(Color::White).fg(Color::Black)),
+        inner_area,
+    );
+}
+
+// スヌーズする時刻の選択肢を描画
+fn render_snooze_menu(f: &mut Frame, state: &ListState) {
+    let now = Timestamp::now();
+    let items: Vec<ListItem> = SnoozeDuration::ALL
+        .iter()
+        .map(|duration| ListItem::new(format!(" {}  {}", duration.label(), format_jst(duration.until(now)))))
+        .collect();
+    let inner_area = render_dialog_frame(f, "⏰ Snooze", 50, SnoozeDuration::ALL.len() as u16 + 2);
+    let list = List::new(items)
+        .style(Style::default().bg(Color::White).fg(Color::Black))
+        .highlight_style(Style::default().bg(Color::Black).fg(Color::White));
+    let mut state = state.clone();
+    f.render_stateful_widget(list, inner_area, &mut state);
+}
+
+// リマインダーを描画（時刻が来たものは太字、まだのものは戻る時刻を薄く表示する）
+fn render_reminders(f: &mut Frame, app: &App) {
+    let now = Timestamp::now();
+    let title = format!("⏰ Reminders — {}件（スヌーズ中 {}件）", app.snoozed.due_count(now), app.snoozed.items.len());
+    let inner_area = render_dialog_frame(f, &title, 78, 20);
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(0), Constraint::Length(1), Constraint::Length(1)])
+        .split(inner_area);
+
+    if app.snoozed.items.is_empty() {
+        f.render_widget(
+            Paragraph::new(" リマインダーはありません（フィードで z を押すと、投稿をあとで読むために隠せます）")
+                .style(Style::default().bg(Color::White).fg(Color::DarkGray)),
+            rows[0],
+        );
+    } else {
+        let items: Vec<ListItem> = app
+            .snoozed
+            .items
+            .iter()
+            .map(|item| {
+                let preview: String = item.event.content.lines().next().unwrap_or_default().chars().take(40).collect();
+                let (when, style) = if item.is_due(now) {
+                    ("⏰".to_string(), Style::default().add_modifier(Modifier::BOLD))
+                } else {
+                    (format!("{}まで", format_jst(Timestamp::from(item.until))), Style::default().fg(Color::DarkGray))
+                };
+                ListItem::new(format!(" {}  {}  {}", when, app.short_author(&item.event.pubkey), preview)).style(style)
+            })
+            .collect();
+        let list = List::new(items)
+            .style(Style::default().bg(Color::White).fg(Color::Black))
+            .highlight_style(Style::default().bg(Color::Black).fg(Color::White));
+        let mut state = app.reminders_state.clone();
+        f.render_stateful_widget(list, rows[0], &mut state);
+    }
+
+    f.render_widget(
+        Paragraph::new(format!(" {}", app.status)).style(Style::default().bg(Color::White).fg(Color::Black)),
+        rows[1],
+    );
+    f.render_widget(
+        Paragraph::new(keymap_hint(REMINDERS_KEYS)).style(Style::default().bg(Color::White).fg(Color::DarkGray)),
+        rows[2],
+    );
+}
+
+// 共有用リンクの形式と内容を並べて描画
+fn render_share_menu(f: &mut Frame, app: &App, state: &ListState) {
+    let items: Vec<ListItem> = app
+        .share_links
+        .iter()
+        .map(|(format, link)| ListItem::new(vec![Line::from(format!(" {}", format.label())), Line::from(format!("   {}", link))]))
+        .collect();
+    let width = app.share_links.iter().map(|(_, link)| link.width() + 4).max().unwrap_or(0).clamp(30, 100) as u16;
+    let inner_area = render_dialog_frame(f, "Share", width, app.share_links.len() as u16 * 2 + 2);
+    let list = List::new(items)
+        .style(Style::default().bg(Color::White).fg(Color::Black))
+        .highlight_style(Style::default().bg(Color::Black).fg(Color::White));
+    let mut state = state.clone();
+    f.render_stateful_widget(list, inner_area, &mut state);
+}
+
+// 長文記事の目次を描画（見出しの深さに応じて字下げする）
+fn render_toc_dialog(f: &mut Frame, app: &App, state: &ListState) {
+    let headings = app.article_headings();
+    let items: Vec<ListItem> = headings
+        .iter()
+        .map(|h| ListItem::new(format!("{}{}", "  ".repeat(h.level - 1), h.title)))
+        .collect();
+    let width = headings
+        .iter()
+        .map(|h| (h.level - 1) * 2 + h.title.width())
+        .max()
+        .unwrap_or(0)
+        .clamp(20, 70) as u16
+        + 4;
+
+    let inner_area = render_dialog_frame(f, "Contents", width, items.len() as u16 + 2);
+    let list = List::new(items)
+        .style(Style::default().bg(Color::White).fg(Color::Black))
+        .highlight_style(Style::default().bg(Color::Black).fg(Color::White));
+    let mut state = state.clone();
+    f.render_stateful_widget(list, inner_area, &mut state);
+}
+
+// View メニュー（並び順の一覧）を描画
+fn render_view_menu(f: &mut Frame, app: &App, state: &ListState, x: u16, y: u16) {
+    let items: Vec<ListItem> = FeedSort::ALL
+        .iter()
+        .map(|sort| {
+            let mark = if *sort == app.article_sort { glyph(Glyph::Checkmark) } else { " " };
+            ListItem::new(format!("{} {}", mark, sort.label()))
+        })
+        .collect();
+
+    let area = f.size();
+    let menu_area = Rect::new(
+        x.min(area.width.saturating_sub(1)),
+        y,
+        20.min(area.width.saturating_sub(x)),
+        (items.len() as u16 + 2).min(area.height.saturating_sub(y)),
+    );
+    f.render_widget(ratatui::widgets::Clear, menu_area);
+    let list = List::new(items)
+        .block(Block::default().borders(Borders::ALL).style(Style::default().bg(Color::White).fg(Color::Black)))
+        .highlight_style(Style::default().bg(Color::Black).fg(Color::White));
+    let mut state = state.clone();
+    f.render_stateful_widget(list, menu_area, &mut state);
+}
+
+// kind の分布のダイアログを描画（選択中の kind の例を下に表示する）
+fn render_kind_explorer(f: &mut Frame, app: &App, state: &ListState) {
+    let max = app.kind_buckets.first().map_or(1, |b| b.count).max(1);
+    let mut items = vec![ListItem::new(" テキストノート（通常のフィード）")];
+    items.extend(app.kind_buckets.iter().map(|bucket| {
+        let bar = "█".repeat((bucket.count * 16).div_ceil(max));
+        ListItem::new(format!(" {:>6} {:<16} {:>4} {}", bucket.kind, bar, bucket.count, kind_name(bucket.kind)))
+    }));
+
+    let selected = state.selected().unwrap_or(0);
+    let examples: Vec<Line> = selected
+        .checked_sub(1)
+        .and_then(|i| app.kind_buckets.get(i))
+        .map(|bucket| {
+            bucket
+                .examples
+                .iter()
+                .map(|e| Line::from(format!(" {} {}", format_jst(e.created_at), smart_truncate(&e.content.replace('\n', " "), 40))))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let height = (items.len() + examples.len() + 3) as u16;
+    let inner_area = render_dialog_frame(f, "Kinds", 72, height);
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(1), Constraint::Length(examples.len() as u16 + 1)])
+        .split(inner_area);
+
+    let list = List::new(items)
+        .style(Style::default().bg(Color::White).fg(Color::Black))
+        .highlight_style(Style::default().bg(Color::Black).fg(Color::White));
+    let mut state = state.clone();
+    f.render_stateful_widget(list, chunks[0], &mut state);
+
+    let mut lines = vec![Line::from(Span::styled(
+        glyph(Glyph::Divider),
+        Style::default().fg(Color::Black),
+    ))];
+    lines.extend(examples);
+    f.render_widget(
+        Paragraph::new(lines).style(Style::default().bg(Color::White).fg(Color::Black)),
+        chunks[1],
+    );
 }
 
+// 日時ジャンプの入力ダイアログを描画
+fn render_goto_dialog(f: &mut Frame, input: &str) {
+    let area = f.size();
+
+    let dialog_width = 44.min(area.width);
+    let dialog_height = 6.min(area.height);
+
+    let dialog_area = Rect::new(
+        area.x + area.width.saturating_sub(dialog_width) / 2,
+        area.y + area.height.saturating_sub(dialog_height) / 2,
+        dialog_width,
+        dialog_height,
+    );
+
+    // 影を描画
+    let shadow_area = Rect::new(
+        dialog_area.x.saturating_add(1),
+        dialog_area.y.saturating_add(1),
+        dialog_width,
+        dialog_height,
+    );
+    f.render_widget(Block::default().style(Style::default().bg(Color::DarkGray)), shadow_area);
+
+    let dialog_block = mac_window_block("Go to Date");
+    f.render_widget(dialog_block.clone(), dialog_area);
+    let inner_area = dialog_block.inner(dialog_area);
+
+    let text = vec![
+        Line::from(Span::styled(
+            "移動先の日時 (JST):",
+            Style::default().fg(Color::Black).add_modifier(Modifier::BOLD),
+        )),
+        Line::from(Span::raw(input.to_string())),
+        Line::from(""),
+        Line::from(Span::styled(
+            "例: 2025-01-01 12:00 | Enter: 移動 | Esc: 取消",
+            Style::default().fg(Color::Black),
+        )),
+    ];
+
+    f.render_widget(
+        Paragraph::new(text).style(Style::default().bg(Color::White).fg(Color::Black)),
+        inner_area,
+    );
 
+    f.set_cursor(inner_area.x + input.width() as u16, inner_area.y + 1);
+}
 
 // 電卓画面描画関数 - 最終版
 fn render_calculator(f: &mut Frame, app: &App) {
@@ -0,0 +1,66 @@
+// イベントの署名の検証 - SDK やリレーの検証に頼らず、ID と署名を手元で計算し直す（config の strict_verification）
+// キャッシュの読み込みやさかのぼった取得で数千件をまとめて検証するときは、CPU のコアごとのスレッドに分ける（rayon）
+use nostr_sdk::hashes::{sha256, Hash};
+use nostr_sdk::prelude::*;
+use nostr_sdk::secp256k1::{Message, Secp256k1, VerifyOnly};
+use rayon::prelude::*;
+
+// これより少なければスレッドに分けずに検証する（分ける手間の方が大きい）
+const PARALLEL_THRESHOLD: usize = 64;
+
+thread_local! {
+    // 検証用のコンテキストはスレッドごとに1度だけ作って使い回す
+    static SECP: Secp256k1<VerifyOnly> = Secp256k1::verification_only();
+}
+
+// 検証に失敗した理由
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum VerifyError {
+    IdMismatch,   // 内容から計算した ID と一致しない
+    BadSignature, // 署名が公開鍵と ID に合わない
+}
+
+impl VerifyError {
+    pub fn label(self) -> &'static str {
+        match self {
+            VerifyError::IdMismatch => "IDの不一致",
+            VerifyError::BadSignature => "署名の不一致",
+        }
+    }
+}
+
+// NIP-01 のシリアライズ [0, 公開鍵, 作成日時, kind, タグ, 本文] の SHA-256（イベントの ID になる）
+pub fn event_hash(pubkey: &XOnlyPublicKey, created_at: u64, kind: u64, tags: &[Vec<String>], content: &str) -> sha256::Hash {
+    let serialized = serde_json::json!([0, pubkey.to_string(), created_at, kind, tags, content]).to_string();
+    sha256::Hash::hash(serialized.as_bytes())
+}
+
+// 内容から計算し直した ID と一致するかを確かめ、署名を検証する
+pub fn verify_event(event: &Event) -> Result<(), VerifyError> {
+    let tags: Vec<Vec<String>> = event.tags.iter().map(|tag| tag.as_vec()).collect();
+    let hash = event_hash(&event.pubkey, event.created_at.as_u64(), event.kind.as_u64(), &tags, &event.content);
+    if event.id.as_bytes() != hash.as_byte_array() {
+        return Err(VerifyError::IdMismatch);
+    }
+
+    let message = Message::from_slice(hash.as_byte_array()).map_err(|_| VerifyError::IdMismatch)?;
+    SECP.with(|secp| secp.verify_schnorr(&event.sig, &message, &event.pubkey))
+        .map_err(|_| VerifyError::BadSignature)
+}
+
+// まとめて検証する（件数が多ければスレッドに分ける。結果は events と同じ順）
+pub fn verify_batch(events: &[Event]) -> Vec<Result<(), VerifyError>> {
+    if events.len() < PARALLEL_THRESHOLD {
+        events.iter().map(verify_event).collect()
+    } else {
+        events.par_iter().map(verify_event).collect()
+    }
+}
+
+// 検証に失敗したイベントを取り除き、取り除いた数を返す
+pub fn retain_verified(events: &mut Vec<Event>) -> usize {
+    let before = events.len();
+    let mut results = verify_batch(events).into_iter();
+    events.retain(|_| results.next().is_some_and(|result| result.is_ok()));
+    before - events.len()
+}
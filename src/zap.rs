@@ -0,0 +1,116 @@
+// Zap（NIP-57） - 相手のプロフィールのライトニングアドレス（lud16）または LNURL（lud06）から支払い先を調べ、
+// Zap リクエスト（kind 9734）を添えてインボイスを発行してもらう。リクエストは署名するだけでリレーには送らず、
+// 支払いを受けた相手のウォレットが Zap レシート（kind 9735）を公開する
+use bech32::{FromBase32, ToBase32, Variant};
+use nostr_sdk::prelude::*;
+use std::str::FromStr;
+use std::time::Duration;
+
+pub const KIND_ZAP_REQUEST: u64 = 9734;
+const LNURL_HRP: &str = "lnurl";
+const FETCH_TIMEOUT: Duration = Duration::from_secs(10);
+
+// プロフィールの lud16 / lud06 から LNURL-pay の URL を求める（lud16 を優先する）
+pub fn lnurl_pay_url(metadata: &Metadata) -> Result<String, Box<dyn std::error::Error>> {
+    if let Some(address) = metadata.lud16.as_deref().map(str::trim).filter(|a| !a.is_empty()) {
+        let (name, domain) = address
+            .split_once('@')
+            .filter(|(name, domain)| !name.is_empty() && !domain.is_empty())
+            .ok_or_else(|| format!("ライトニングアドレスを解釈できません: {}", address))?;
+        return Ok(format!("https://{}/.well-known/lnurlp/{}", domain.to_lowercase(), name.to_lowercase()));
+    }
+    if let Some(lnurl) = metadata.lud06.as_deref().map(str::trim).filter(|l| !l.is_empty()) {
+        let (hrp, data, _) = bech32::decode(&lnurl.to_lowercase())?;
+        if hrp != LNURL_HRP {
+            return Err(format!("LNURL ではありません: {}", hrp).into());
+        }
+        return Ok(String::from_utf8(Vec::<u8>::from_base32(&data)?)?);
+    }
+    Err("相手のプロフィールにライトニングアドレス（lud16 / lud06）がありません".into())
+}
+
+// URL を LNURL（bech32）にする（Zap リクエストの lnurl タグとコールバックに添える）
+pub fn encode_lnurl(url: &str) -> Result<String, Box<dyn std::error::Error>> {
+    Ok(bech32::encode(LNURL_HRP, url.as_bytes().to_base32(), Variant::Bech32)?)
+}
+
+// LNURL-pay の支払い先の情報
+#[derive(Debug, Clone)]
+pub struct PayEndpoint {
+    pub callback: String,
+    pub min_sendable: u64, // ミリサトシ
+    pub max_sendable: u64,
+    pub allows_nostr: bool,                    // Zap リクエストを受け付けるか
+    pub nostr_pubkey: Option<XOnlyPublicKey>,  // Zap レシートに署名する鍵
+}
+
+// LNURL-pay の支払い先の情報を取得する
+pub async fn fetch_pay_endpoint(url: &str) -> Result<PayEndpoint, Box<dyn std::error::Error>> {
+    let http = reqwest::Client::builder().timeout(FETCH_TIMEOUT).build()?;
+    let response = http.get(url).send().await.map_err(|e| format!("{} を取得できません: {}", url, e))?;
+    if !response.status().is_success() {
+        return Err(format!("{} の取得に失敗しました（HTTP {}）", url, response.status()).into());
+    }
+    let info: serde_json::Value = response.json().await.map_err(|e| format!("{} を JSON として解釈できません: {}", url, e))?;
+    if info["status"].as_str() == Some("ERROR") {
+        return Err(format!("支払い先がエラーを返しました: {}", info["reason"].as_str().unwrap_or("理由不明")).into());
+    }
+    if info["tag"].as_str() != Some("payRequest") {
+        return Err(format!("LNURL-pay の支払い先ではありません: {}", url).into());
+    }
+    Ok(PayEndpoint {
+        callback: info["callback"].as_str().ok_or("支払い先に callback がありません")?.to_string(),
+        min_sendable: info["minSendable"].as_u64().unwrap_or(1000),
+        max_sendable: info["maxSendable"].as_u64().unwrap_or(u64::MAX),
+        allows_nostr: info["allowsNostr"].as_bool().unwrap_or(false),
+        nostr_pubkey: info["nostrPubkey"].as_str().and_then(|hex| XOnlyPublicKey::from_str(hex).ok()),
+    })
+}
+
+// Zap リクエスト（kind 9734）を作成する（ノートへの Zap なら e タグも付ける。本文はコメント）
+pub fn zap_request_builder(
+    recipient: &XOnlyPublicKey,
+    target: Option<&Event>,
+    amount_msats: u64,
+    lnurl: &str,
+    relays: &[String],
+    comment: &str,
+) -> Result<EventBuilder, Box<dyn std::error::Error>> {
+    let mut tags = vec![
+        Tag::parse([vec!["relays".to_string()], relays.to_vec()].concat())?,
+        Tag::parse(vec!["amount".to_string(), amount_msats.to_string()])?,
+        Tag::parse(vec!["lnurl".to_string(), lnurl.to_string()])?,
+        Tag::parse(vec!["p".to_string(), recipient.to_string()])?,
+    ];
+    if let Some(target) = target {
+        tags.push(Tag::parse(vec!["e".to_string(), target.id.to_hex()])?);
+        tags.push(Tag::parse(vec!["k".to_string(), target.kind.as_u64().to_string()])?);
+    }
+    Ok(EventBuilder::new(Kind::from(KIND_ZAP_REQUEST), comment, tags))
+}
+
+// コールバックにインボイスを発行してもらう（zap_request が None なら Zap ではない普通の支払い）
+pub async fn fetch_invoice(
+    endpoint: &PayEndpoint,
+    amount_msats: u64,
+    zap_request: Option<&Event>,
+    lnurl: &str,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let mut query = vec![("amount", amount_msats.to_string())];
+    if let Some(zap_request) = zap_request {
+        query.push(("nostr", zap_request.as_json()));
+        query.push(("lnurl", lnurl.to_string()));
+    }
+    let http = reqwest::Client::builder().timeout(FETCH_TIMEOUT).build()?;
+    let response = http
+        .get(&endpoint.callback)
+        .query(&query)
+        .send()
+        .await
+        .map_err(|e| format!("インボイスを取得できません: {}", e))?;
+    let body: serde_json::Value = response.json().await.map_err(|e| format!("インボイスの応答を解釈できません: {}", e))?;
+    if body["status"].as_str() == Some("ERROR") {
+        return Err(format!("インボイスを発行できませんでした: {}", body["reason"].as_str().unwrap_or("理由不明")).into());
+    }
+    Ok(body["pr"].as_str().ok_or("応答にインボイス（pr）がありません")?.to_string())
+}